@@ -122,5 +122,6 @@ pub fn valid_schedule(
 #[pre(low <= high)]
 #[post(low <= ret && ret <= high)]
 fn midpoint(low: Height, high: Height) -> Height {
-    (low.value() + (high.value() + 1 - low.value()) / 2).into()
+    let half_distance = (high.value() - low.value() + 1) / 2;
+    low.checked_add(half_distance).expect("height overflow")
 }
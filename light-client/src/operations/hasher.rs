@@ -2,7 +2,7 @@
 
 use crate::types::{Header, ValidatorSet};
 
-use tendermint::{merkle, Hash};
+use tendermint::Hash;
 
 /// Hashing for headers and validator sets
 pub trait Hasher: Send {
@@ -24,12 +24,6 @@ impl Hasher for ProdHasher {
 
     /// Compute the Merkle root of the validator set
     fn hash_validator_set(&self, validator_set: &ValidatorSet) -> Hash {
-        let validator_bytes: Vec<Vec<u8>> = validator_set
-            .validators()
-            .iter()
-            .map(|validator| validator.hash_bytes())
-            .collect();
-
-        Hash::Sha256(merkle::simple_hash_from_byte_vectors(validator_bytes))
+        validator_set.hash()
     }
 }
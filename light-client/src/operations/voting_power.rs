@@ -3,16 +3,15 @@
 use crate::{
     bail,
     predicates::errors::VerificationError,
-    types::{Commit, SignedHeader, TrustThreshold, ValidatorSet},
+    types::{SignedHeader, TrustThreshold, ValidatorSet},
 };
 
 use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
 use std::fmt;
 
-use tendermint::block::CommitSig;
 use tendermint::trust_threshold::TrustThreshold as _;
-use tendermint::vote::{SignedVote, Vote};
+use tendermint::vote::SignedVote;
 
 /// Tally for the voting power computed by the `VotingPowerCalculator`
 #[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
@@ -114,13 +113,10 @@ impl VotingPowerCalculator for ProdVotingPowerCalculator {
         let mut seen_validators = HashSet::new();
 
         // Get non-absent votes from the signatures
-        let non_absent_votes = signatures.iter().enumerate().flat_map(|(idx, signature)| {
-            if let Some(vote) = non_absent_vote(signature, idx as u64, &signed_header.commit) {
-                Some((signature, vote))
-            } else {
-                None
-            }
-        });
+        let non_absent_votes = signatures
+            .iter()
+            .enumerate()
+            .flat_map(|(idx, signature)| Some((signature, signed_header.commit.vote(idx)?)));
 
         for (signature, vote) in non_absent_votes {
             // Ensure we only count a validator's power once
@@ -179,38 +175,6 @@ impl VotingPowerCalculator for ProdVotingPowerCalculator {
     }
 }
 
-fn non_absent_vote(commit_sig: &CommitSig, validator_index: u64, commit: &Commit) -> Option<Vote> {
-    let (validator_address, timestamp, signature, block_id) = match commit_sig {
-        CommitSig::BlockIDFlagAbsent { .. } => return None,
-        CommitSig::BlockIDFlagCommit {
-            validator_address,
-            timestamp,
-            signature,
-        } => (
-            *validator_address,
-            *timestamp,
-            signature,
-            Some(commit.block_id.clone()),
-        ),
-        CommitSig::BlockIDFlagNil {
-            validator_address,
-            timestamp,
-            signature,
-        } => (*validator_address, *timestamp, signature, None),
-    };
-
-    Some(Vote {
-        vote_type: tendermint::vote::Type::Precommit,
-        height: commit.height,
-        round: commit.round,
-        block_id,
-        timestamp,
-        validator_address,
-        validator_index,
-        signature: *signature,
-    })
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -10,10 +10,16 @@ fn main() {
         let url = "https://github.com/tendermint/tendermint";
         Repository::clone(url, &tendermint_dir).unwrap();
     }
-    let proto_paths = [format!("{}/proto", tendermint_dir)];
+    let ics23_dir = var("ICS23_DIR").unwrap_or_else(|_| "target/ics23".to_string());
+    if !Path::new(&ics23_dir).exists() {
+        let url = "https://github.com/confio/ics23";
+        Repository::clone(url, &ics23_dir).unwrap();
+    }
+    let proto_paths = [format!("{}/proto", tendermint_dir), format!("{}/proofs.proto", ics23_dir)];
     let proto_includes_paths = [
         format!("{}/proto", tendermint_dir),
         format!("{}/third_party/proto", tendermint_dir),
+        ics23_dir,
     ];
 
     // List available proto files
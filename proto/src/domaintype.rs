@@ -0,0 +1,131 @@
+//! DomainType trait
+//!
+//! The DomainType trait allows separation of the data sent on the wire (currently encoded using
+//! protobuf) from the structures used in Rust. The structures used to encode/decode from/to the
+//! wire are called "Raw" types (they mirror the definitions in the specifications) and the Rust
+//! types we use internally are called the "Domain" types. These Domain types can implement
+//! additional checks and conversions to consume the incoming data easier for a Rust developer.
+//!
+//! The benefits include decoding the wire into a struct that is inherently valid as well as hiding
+//! the encoding and decoding details from the developer. This latter is important if/when we decide
+//! to exchange the underlying Prost library with something else. (Another protobuf implementation
+//! or a completely different encoding.) Encoding is not the core product of Tendermint it's a
+//! necessary dependency.
+//!
+//! Decode: bytestream -> Raw -> Domain
+//! The `decode` function takes two steps to decode from a bytestream to a DomainType:
+//!
+//! 1. Decode the bytestream into a Raw type using the Prost library,
+//! 2. Transform that Raw type into a Domain type using the TryFrom trait of the DomainType.
+//!
+//! Encode: Domain -> Raw -> bytestream
+//! The `encode` function takes two steps to encode a DomainType into a bytestream:
+//!
+//! 1. Transform the Domain type into a Raw type using the TryFrom trait of the Raw type (which
+//!    `From` implementations provide for conversions which can't fail),
+//! 2. Encode the Raw type into a bytestream using the Prost library.
+
+use crate::{Error, Kind};
+use anomaly::BoxError;
+use bytes::{Buf, BufMut};
+use prost::{encoding::encoded_len_varint, Message};
+use std::convert::{TryFrom, TryInto};
+
+/// DomainType trait allows protobuf encoding and decoding for domain types
+pub trait DomainType<T: Message + TryFrom<Self> + Default>
+where
+    Self: Sized + Clone + TryFrom<T>,
+    <Self as TryFrom<T>>::Error: Into<BoxError>,
+    <T as TryFrom<Self>>::Error: Into<BoxError>,
+{
+    /// Encodes the DomainType into a buffer.
+    ///
+    /// This function replaces the Prost::Message encode() function for DomainTypes.
+    fn encode<B: BufMut>(&self, buf: &mut B) -> Result<(), Error> {
+        self.to_raw()?
+            .encode(buf)
+            .map_err(|e| Kind::EncodeMessage.context(e).into())
+    }
+
+    /// Encodes the DomainType with a length-delimiter to a buffer.
+    ///
+    /// An error will be returned if the buffer does not have sufficient capacity.
+    ///
+    /// This function replaces the Prost::Message encode_length_delimited() function for
+    /// DomainTypes.
+    fn encode_length_delimited<B: BufMut>(&self, buf: &mut B) -> Result<(), Error> {
+        self.to_raw()?
+            .encode_length_delimited(buf)
+            .map_err(|e| Kind::EncodeMessage.context(e).into())
+    }
+
+    /// Decodes an instance of the message from a buffer and then converts it into DomainType.
+    ///
+    /// The entire buffer will be consumed.
+    ///
+    /// This function replaces the Prost::Message decode() function for DomainTypes.
+    fn decode<B: Buf>(buf: B) -> Result<Self, Error> {
+        T::decode(buf).map_or_else(
+            |e| Err(Kind::DecodeMessage.context(e).into()),
+            |t| Self::try_from(t).map_err(|e| Kind::TryFromProtobuf.context(e).into()),
+        )
+    }
+
+    /// Decodes a length-delimited instance of the message from the buffer.
+    ///
+    /// The entire buffer will be consumed.
+    ///
+    /// This function replaces the Prost::Message decode_length_delimited() function for
+    /// DomainTypes.
+    fn decode_length_delimited<B: Buf>(buf: B) -> Result<Self, Error> {
+        T::decode_length_delimited(buf).map_or_else(
+            |e| Err(Kind::DecodeMessage.context(e).into()),
+            |t| Self::try_from(t).map_err(|e| Kind::TryFromProtobuf.context(e).into()),
+        )
+    }
+
+    /// Returns the encoded length of the message without a length delimiter.
+    ///
+    /// This function replaces the Prost::Message encoded_len() function for DomainTypes.
+    fn encoded_len(&self) -> Result<usize, Error> {
+        self.to_raw().map(|raw| raw.encoded_len())
+    }
+
+    /// Transforms the DomainType into its Raw type, failing if the Raw type can't represent it
+    /// (e.g. an integer out of its range).
+    fn to_raw(&self) -> Result<T, Error> {
+        T::try_from(self.clone()).map_err(|e| Kind::EncodeMessage.context(e).into())
+    }
+
+    /// Encodes the DomainType into a protobuf-encoded Vec<u8>
+    fn encode_vec(&self) -> Result<Vec<u8>, Error> {
+        let raw = self.to_raw()?;
+        let mut wire = Vec::with_capacity(raw.encoded_len());
+        raw.encode(&mut wire)
+            .map(|_| wire)
+            .map_err(|e| Kind::EncodeMessage.context(e).into())
+    }
+
+    /// Decodes a protobuf-encoded instance of the message from a Vec<u8> and then converts it into
+    /// DomainType.
+    fn decode_vec(v: &[u8]) -> Result<Self, Error> {
+        Self::decode(v)
+    }
+
+    /// Encodes the DomainType with a length-delimiter to a Vec<u8> protobuf-encoded message.
+    fn encode_length_delimited_vec(&self) -> Result<Vec<u8>, Error> {
+        let raw = self.to_raw()?;
+        let len = raw.encoded_len();
+        let lenu64 = len.try_into().map_err(|e| Kind::EncodeMessage.context(e))?;
+        let mut wire = Vec::with_capacity(len + encoded_len_varint(lenu64));
+        raw.encode_length_delimited(&mut wire)
+            .map(|_| wire)
+            .map_err(|e| Kind::EncodeMessage.context(e).into())
+    }
+
+    /// Decodes a protobuf-encoded instance of the message with a length-delimiter from a Vec<u8>
+    /// and then converts it into DomainType.
+    fn decode_length_delimited_vec(v: &[u8]) -> Result<Self, Error> {
+        Self::decode_length_delimited(v)
+    }
+}
@@ -86,3 +86,15 @@ mod tendermint {
 }
 
 pub use tendermint::*;
+
+mod domaintype;
+pub use domaintype::DomainType;
+
+mod error;
+pub use error::{Error, Kind};
+
+/// ICS-23 commitment proofs (<https://github.com/confio/ics23>)
+pub mod ics23 {
+    #![allow(missing_docs)]
+    include!("prost/ics23.rs");
+}
@@ -303,6 +303,14 @@ pub struct ResponseCheckTx {
     pub events: ::std::vec::Vec<Event>,
     #[prost(string, tag="8")]
     pub codespace: std::string::String,
+    #[prost(string, tag="9")]
+    pub sender: std::string::String,
+    #[prost(int64, tag="10")]
+    pub priority: i64,
+    /// mempool_error is set by Tendermint.
+    /// ABCI applications creating a ResponseCheckTX should not set mempool_error.
+    #[prost(string, tag="11")]
+    pub mempool_error: std::string::String,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct ResponseDeliverTx {
@@ -2,6 +2,8 @@
 
 use bytes::buf::ext::BufExt;
 use hyper::header;
+use serde::{de::DeserializeOwned, Serialize};
+use std::fmt;
 
 use tendermint::abci::{self, Transaction};
 use tendermint::block::Height;
@@ -138,9 +140,13 @@ impl Client {
         Ok(())
     }
 
-    /// `/genesis`: get genesis file.
-    pub async fn genesis(&self) -> Result<Genesis, Error> {
-        Ok(self.perform(genesis::Request).await?.genesis)
+    /// `/genesis`: get genesis file, deserializing its `app_state` as
+    /// `AppState` (e.g. `serde_json::Value`).
+    pub async fn genesis<AppState>(&self) -> Result<Genesis<AppState>, Error>
+    where
+        AppState: fmt::Debug + Default + Serialize + DeserializeOwned,
+    {
+        Ok(self.perform(genesis::Request::default()).await?.genesis)
     }
 
     /// `/net_info`: obtain information about P2P and other network connections.
@@ -154,6 +160,16 @@ impl Client {
         self.perform(status::Request).await
     }
 
+    /// `/tx`: find a transaction by its hash, optionally with a proof of its
+    /// inclusion in the block containing it.
+    pub async fn tx(
+        &self,
+        hash: abci::transaction::Hash,
+        prove: bool,
+    ) -> Result<tx::Response, Error> {
+        self.perform(tx::Request::new(hash, prove)).await
+    }
+
     /// `/broadcast_evidence`: broadcast an evidence.
     pub async fn broadcast_evidence(&self, e: Evidence) -> Result<evidence::Response, Error> {
         self.perform(evidence::Request::new(e)).await
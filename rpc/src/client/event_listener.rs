@@ -10,6 +10,7 @@ use std::collections::HashMap;
 use std::error::Error as stdError;
 use tokio::net::TcpStream;
 
+use tendermint::abci::{self, Gas};
 use tendermint::block;
 use tendermint::net;
 
@@ -173,26 +174,12 @@ pub struct TxResult {
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct TxResultResult {
     pub log: String,
-    pub gas_wanted: String,
-    pub gas_used: String,
-    pub events: Vec<TmEvent>,
+    pub gas_wanted: Gas,
+    pub gas_used: Gas,
+    pub events: Vec<abci::Event>,
 }
 impl response::Response for TxResultResult {}
 
-/// Tendermint ABCI Events
-#[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct TmEvent {
-    #[serde(rename = "type")]
-    pub event_type: String,
-    pub attributes: Vec<Attribute>,
-}
-/// Event Attributes
-#[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct Attribute {
-    pub key: String,
-    pub value: String,
-}
-
 ///Block Value
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct EventDataNewBlock {
@@ -207,7 +194,7 @@ pub struct EventDataNewBlock {
 /// Begin Block Events
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ResultBeginBlock {
-    pub events: Option<Vec<TmEvent>>,
+    pub events: Option<Vec<abci::Event>>,
 }
 ///End Block Events
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -4,7 +4,7 @@
 use serde::{Deserialize, Serialize};
 
 use tendermint::{
-    abci::{transaction, Code, Data, Log, Transaction},
+    abci::{transaction, Code, Codespace, Data, Log, QualifiedCode, Transaction},
     block,
 };
 
@@ -63,4 +63,15 @@ pub struct TxResult {
 
     /// Log
     pub log: Log,
+
+    /// Codespace
+    #[serde(default)]
+    pub codespace: Codespace,
+}
+
+impl TxResult {
+    /// Get the response code qualified by its codespace
+    pub fn qualified_code(&self) -> QualifiedCode {
+        self.code.in_codespace(self.codespace.clone())
+    }
 }
@@ -2,7 +2,7 @@
 
 use serde::{Deserialize, Serialize};
 
-use tendermint::abci::{transaction, Code, Data, Log, Transaction};
+use tendermint::abci::{transaction, Code, Codespace, Data, Log, QualifiedCode, Transaction};
 
 /// `/broadcast_tx_sync`: returns with the response from `CheckTx`.
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
@@ -38,8 +38,19 @@ pub struct Response {
     /// Log
     pub log: Log,
 
+    /// Codespace
+    #[serde(default)]
+    pub codespace: Codespace,
+
     /// Transaction hash
     pub hash: transaction::Hash,
 }
 
+impl Response {
+    /// Get the response code qualified by its codespace
+    pub fn qualified_code(&self) -> QualifiedCode {
+        self.code.in_codespace(self.codespace.clone())
+    }
+}
+
 impl crate::Response for Response {}
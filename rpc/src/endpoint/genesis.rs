@@ -1,26 +1,41 @@
 //! `/genesis` endpoint JSONRPC wrapper
 
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{fmt, marker::PhantomData};
 
 use tendermint::Genesis;
 
-/// Get the genesis state for the current chain
+/// Get the genesis state for the current chain, deserializing its
+/// `app_state` as `AppState`
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
-pub struct Request;
+pub struct Request<AppState = serde_json::Value>(#[serde(skip)] PhantomData<AppState>);
 
-impl crate::Request for Request {
-    type Response = Response;
+impl<AppState> Default for Request<AppState> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<AppState> crate::Request for Request<AppState>
+where
+    AppState: fmt::Debug + Default + Serialize + DeserializeOwned,
+{
+    type Response = Response<AppState>;
 
     fn method(&self) -> crate::Method {
         crate::Method::Genesis
     }
 }
 
-/// Block responses
+/// Genesis responses
 #[derive(Clone, Debug, Deserialize, Serialize)]
-pub struct Response {
+#[serde(bound(deserialize = "Genesis<AppState>: Deserialize<'de>"))]
+pub struct Response<AppState = serde_json::Value> {
     /// Genesis data
-    pub genesis: Genesis,
+    pub genesis: Genesis<AppState>,
 }
 
-impl crate::Response for Response {}
+impl<AppState> crate::Response for Response<AppState> where
+    AppState: Default + Serialize + DeserializeOwned
+{
+}
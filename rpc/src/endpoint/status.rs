@@ -2,7 +2,7 @@
 
 use serde::{Deserialize, Serialize};
 
-use tendermint::{block, node, serializers, validator, Hash, Time};
+use tendermint::{block, hash::AppHash, node, serializers, validator, Hash, Time};
 
 /// Node status request
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
@@ -39,8 +39,7 @@ pub struct SyncInfo {
     pub latest_block_hash: Option<Hash>,
 
     /// Latest app hash
-    #[serde(deserialize_with = "serializers::parse_non_empty_hash")]
-    pub latest_app_hash: Option<Hash>,
+    pub latest_app_hash: AppHash,
 
     /// Latest block height
     pub latest_block_height: block::Height,
@@ -48,6 +47,26 @@ pub struct SyncInfo {
     /// Latest block time
     pub latest_block_time: Time,
 
+    /// Earliest block hash (Tendermint 0.34)
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "serializers::parse_non_empty_hash"
+    )]
+    pub earliest_block_hash: Option<Hash>,
+
+    /// Earliest app hash (Tendermint 0.34)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub earliest_app_hash: Option<AppHash>,
+
+    /// Earliest block height (Tendermint 0.34)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub earliest_block_height: Option<block::Height>,
+
+    /// Earliest block time (Tendermint 0.34)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub earliest_block_time: Option<Time>,
+
     /// Are we catching up?
     pub catching_up: bool,
 }
@@ -0,0 +1,82 @@
+//! `/tx` endpoint JSONRPC wrapper
+
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize};
+
+use tendermint::abci::{transaction, DeliverTx, Transaction};
+use tendermint::block;
+use tendermint::serializers;
+
+/// Find a transaction by its hash, optionally with a proof of its inclusion
+/// in the block containing it
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Request {
+    /// Hash of the transaction
+    #[serde(with = "serializers::bytes::base64string")]
+    hash: Vec<u8>,
+
+    /// Include proof in response
+    prove: bool,
+}
+
+impl Request {
+    /// Create a new request for the transaction with the given hash
+    pub fn new(hash: transaction::Hash, prove: bool) -> Self {
+        Self {
+            hash: hash.as_bytes().to_vec(),
+            prove,
+        }
+    }
+}
+
+impl crate::Request for Request {
+    type Response = Response;
+
+    fn method(&self) -> crate::Method {
+        crate::Method::Tx
+    }
+}
+
+/// Transaction responses
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Response {
+    /// Transaction hash
+    pub hash: transaction::Hash,
+
+    /// Height of the block containing the transaction
+    pub height: block::Height,
+
+    /// Index of the transaction in the block
+    pub index: u32,
+
+    /// Result of executing the transaction
+    pub tx_result: DeliverTx,
+
+    /// The transaction
+    pub tx: Transaction,
+
+    /// Proof of the inclusion of the transaction in the block (if requested)
+    #[serde(default, deserialize_with = "parse_non_empty_proof")]
+    pub proof: Option<transaction::Proof>,
+}
+
+impl crate::Response for Response {}
+
+/// Tendermint returns an empty proof (with an empty root hash) if no proof was
+/// requested
+fn parse_non_empty_proof<'de, D>(deserializer: D) -> Result<Option<transaction::Proof>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let proof = match Option::<serde_json::Value>::deserialize(deserializer)? {
+        Some(proof) => proof,
+        None => return Ok(None),
+    };
+
+    if proof["root_hash"].as_str().map_or(true, str::is_empty) {
+        return Ok(None);
+    }
+
+    serde_json::from_value(proof)
+        .map(Some)
+        .map_err(|e| D::Error::custom(format!("{}", e)))
+}
@@ -0,0 +1,74 @@
+//! Fuzz target for parsing JSONRPC responses, which come from nodes a client
+//! doesn't necessarily trust, e.g. public RPC endpoints or light client
+//! witnesses.
+//!
+//! The cargo-fuzz harness in `rpc/fuzz` calls [`response`], which only
+//! panics on a bug: a response type panicking on malformed JSON, or parsing
+//! differently depending on the [`ParseMode`].
+
+use crate::{endpoint::*, ParseMode, Response};
+
+/// Parse arbitrary bytes as a response of every endpoint, in each mode
+pub fn response(data: &[u8]) {
+    parse::<abci_info::Response>(data);
+    parse::<abci_query::Response>(data);
+    parse::<block::Response>(data);
+    parse::<block_results::Response>(data);
+    parse::<blockchain::Response>(data);
+    parse::<broadcast::tx_async::Response>(data);
+    parse::<broadcast::tx_commit::Response>(data);
+    parse::<broadcast::tx_sync::Response>(data);
+    parse::<commit::Response>(data);
+    parse::<evidence::Response>(data);
+    parse::<genesis::Response>(data);
+    parse::<health::Response>(data);
+    parse::<net_info::Response>(data);
+    parse::<status::Response>(data);
+    parse::<tx::Response>(data);
+    parse::<validators::Response>(data);
+}
+
+/// Parse a response, checking responses parsed strictly are also parsed
+/// leniently
+fn parse<R: Response>(data: &[u8]) {
+    let lenient = R::from_string(data);
+    let strict = R::from_string_with(data, ParseMode::Strict);
+    let _ = R::from_reader(data);
+
+    if strict.is_ok() {
+        assert!(
+            lenient.is_ok(),
+            "response parsed strictly but not leniently"
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ignores_malformed_responses() {
+        let inputs: &[&[u8]] = &[
+            b"",
+            b"\xff\xfe",
+            b"{",
+            br#"{"jsonrpc":"2.0","id":"","result":null}"#,
+            br#"{"jsonrpc":"2.0","id":"","result":{"block_id":[],"block":1e400}}"#,
+            br#"{"jsonrpc":"2.0","id":"","error":{"code":-32603,"message":""}}"#,
+        ];
+
+        for data in inputs {
+            response(data);
+        }
+
+        // Deeply nested JSON, e.g. in a genesis app state, hits serde_json's
+        // recursion limit rather than overflowing the stack
+        let nested = format!(
+            r#"{{"jsonrpc":"2.0","id":"","result":{{"genesis":{{"app_state":{}{}}}}}}}"#,
+            "[".repeat(100_000),
+            "]".repeat(100_000)
+        );
+        response(nested.as_bytes());
+    }
+}
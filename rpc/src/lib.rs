@@ -7,6 +7,8 @@ pub use client::{event_listener, Client};
 
 pub mod endpoint;
 pub mod error;
+#[cfg(feature = "fuzzing")]
+pub mod fuzz;
 mod id;
 mod method;
 pub mod request;
@@ -14,5 +16,10 @@ pub mod response;
 mod version;
 
 pub use self::{
-    error::Error, id::Id, method::Method, request::Request, response::Response, version::Version,
+    error::Error,
+    id::Id,
+    method::Method,
+    request::Request,
+    response::{ParseMode, Response},
+    version::Version,
 };
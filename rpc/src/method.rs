@@ -51,6 +51,9 @@ pub enum Method {
     /// Get node status
     Status,
 
+    /// Get a transaction by its hash
+    Tx,
+
     /// Get validator info for a block
     Validators,
 
@@ -78,6 +81,7 @@ impl Method {
             Method::Health => "health",
             Method::NetInfo => "net_info",
             Method::Status => "status",
+            Method::Tx => "tx",
             Method::Validators => "validators",
             Method::Subscribe => "subscribe",
             Method::BroadcastEvidence => "broadcast_evidence",
@@ -103,6 +107,7 @@ impl FromStr for Method {
             "health" => Method::Health,
             "net_info" => Method::NetInfo,
             "status" => Method::Status,
+            "tx" => Method::Tx,
             "validators" => Method::Validators,
             "subscribe" => Method::Subscribe,
             "broadcast_evidence" => Method::BroadcastEvidence,
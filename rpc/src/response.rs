@@ -2,8 +2,28 @@
 
 use super::{Error, Id, Version};
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
 use std::io::Read;
 
+/// How strictly responses are checked against the JSON produced by Tendermint
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ParseMode {
+    /// Ignore fields which aren't part of the response type (the default)
+    Lenient,
+
+    /// Reject fields which aren't part of the response type, e.g. because
+    /// their name or casing differs from the JSON produced by Go, and
+    /// integers which Go encodes as strings but which are numbers in the
+    /// response (or vice versa)
+    Strict,
+}
+
+impl Default for ParseMode {
+    fn default() -> ParseMode {
+        ParseMode::Lenient
+    }
+}
+
 /// JSONRPC responses
 pub trait Response: Serialize + DeserializeOwned + Sized {
     /// Parse a JSONRPC response from a JSON string
@@ -13,6 +33,42 @@ pub trait Response: Serialize + DeserializeOwned + Sized {
         wrapper.into_result()
     }
 
+    /// Parse a JSONRPC response from a JSON string using the given mode
+    fn from_string_with(response: impl AsRef<[u8]>, mode: ParseMode) -> Result<Self, Error> {
+        if mode == ParseMode::Lenient {
+            return Self::from_string(response);
+        }
+
+        let mut deserializer = serde_json::Deserializer::from_slice(response.as_ref());
+        let mut deviations = vec![];
+
+        let wrapper: Wrapper<Self> = serde_ignored::deserialize(&mut deserializer, |path| {
+            deviations.push(format!("{}: unknown field", path))
+        })
+        .map_err(Error::parse_error)?;
+
+        deserializer.end().map_err(Error::parse_error)?;
+
+        // Lenient deserializers accept other encodings than Go's (e.g. both
+        // numbers and strings for integers), which re-encoding the result
+        // reveals, as it follows Go's
+        if let Some(result) = &wrapper.result {
+            let json: Value =
+                serde_json::from_slice(response.as_ref()).map_err(Error::parse_error)?;
+            let encoded = serde_json::to_value(result).map_err(Error::parse_error)?;
+            compare_encodings("result", &json["result"], &encoded, &mut deviations);
+        }
+
+        if !deviations.is_empty() {
+            return Err(Error::parse_error(format!(
+                "response deviates from Tendermint's JSON: {}",
+                deviations.join(", ")
+            )));
+        }
+
+        wrapper.into_result()
+    }
+
     /// Parse a JSONRPC response from an `io::Reader`
     fn from_reader(reader: impl Read) -> Result<Self, Error> {
         let wrapper: Wrapper<Self> = serde_json::from_reader(reader).map_err(Error::parse_error)?;
@@ -20,6 +76,43 @@ pub trait Response: Serialize + DeserializeOwned + Sized {
     }
 }
 
+/// Compare a response's JSON with its re-encoding, collecting the values
+/// which are encoded differently: fields whose name only differs in casing,
+/// and integers encoded as a number instead of a string or vice versa.
+///
+/// Other differences (e.g. omitted defaults or `null`s) are left alone, as
+/// they don't change how Go reads the value.
+fn compare_encodings(path: &str, json: &Value, encoded: &Value, deviations: &mut Vec<String>) {
+    match (json, encoded) {
+        (Value::Object(json), Value::Object(encoded)) => {
+            for (name, value) in json {
+                let path = format!("{}.{}", path, name);
+
+                if let Some(encoded) = encoded.get(name) {
+                    compare_encodings(&path, value, encoded, deviations);
+                } else if let Some(expected) = encoded
+                    .keys()
+                    .find(|expected| expected.eq_ignore_ascii_case(name))
+                {
+                    deviations.push(format!("{}: field is spelled `{}`", path, expected));
+                }
+            }
+        }
+        (Value::Array(json), Value::Array(encoded)) => {
+            for (index, (value, encoded)) in json.iter().zip(encoded).enumerate() {
+                compare_encodings(&format!("{}.{}", path, index), value, encoded, deviations);
+            }
+        }
+        (Value::Number(number), Value::String(string)) if number.to_string() == *string => {
+            deviations.push(format!("{}: integer must be encoded as a string", path));
+        }
+        (Value::String(string), Value::Number(number)) if number.to_string() == *string => {
+            deviations.push(format!("{}: integer must not be encoded as a string", path));
+        }
+        _ => (),
+    }
+}
+
 /// JSONRPC response wrapper (i.e. message envelope)
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct Wrapper<R> {
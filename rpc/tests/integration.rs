@@ -2,7 +2,10 @@
 
 mod endpoints {
     use std::{fs, path::PathBuf};
-    use tendermint::abci::Code;
+    use tendermint::{
+        abci::{Code, CodeTable},
+        block, channel,
+    };
 
     use tendermint_rpc::{self as rpc, endpoint, Response};
 
@@ -38,6 +41,17 @@ mod endpoints {
     fn block() {
         let response = endpoint::block::Response::from_string(&read_json_fixture("block")).unwrap();
 
+        assert_eq!(response.block.header.hash(), response.block_id.hash);
+        response.block.validate_basic().unwrap();
+
+        // An empty block fits within the space reserved for the header and
+        // the last commit of its single validator
+        let block_size = block::Size::default();
+        assert!(
+            response.block.encoded_size().unwrap()
+                <= block_size.max_bytes - block_size.max_data_bytes_no_evidence(1).unwrap()
+        );
+
         let tendermint::Block {
             header,
             data,
@@ -45,7 +59,7 @@ mod endpoints {
             last_commit,
         } = response.block;
 
-        assert_eq!(header.version.block, 10);
+        assert_eq!(header.version.block, block::Version::TENDERMINT_V0_33);
         assert_eq!(header.chain_id.as_str(), EXAMPLE_CHAIN);
         assert_eq!(header.height.value(), 10);
         assert_eq!(data.iter().len(), 0);
@@ -97,7 +111,7 @@ mod endpoints {
             last_commit,
         } = response.block;
 
-        assert_eq!(header.version.block, 10);
+        assert_eq!(header.version.block, block::Version::TENDERMINT_V0_33);
         assert_eq!(header.chain_id.as_str(), EXAMPLE_CHAIN);
         assert_eq!(header.height.value(), 1);
         assert!(header.last_block_id.is_none());
@@ -124,6 +138,14 @@ mod endpoints {
         assert_eq!(deliver_tx[0].gas_wanted.value(), 200_000);
         assert_eq!(deliver_tx[0].gas_used.value(), 105_662);
 
+        let event = deliver_tx[0].events_of_type("someevent1").next().unwrap();
+        assert_eq!(event.first_value_of("action"), Some("delegate"));
+        assert_eq!(
+            event.first_value_of("delegator"),
+            Some("cosmos1nwyeyqudzru5l64e83dnmq79q4stqz7fwl5v5a")
+        );
+        assert_eq!(event.first_value_of("recipient"), None);
+
         assert_eq!(validator_updates[0].power.value(), 1_233_243);
     }
 
@@ -182,6 +204,23 @@ mod endpoints {
         );
     }
 
+    #[test]
+    fn broadcast_tx_sync_error() {
+        let response = endpoint::broadcast::tx_sync::Response::from_string(&read_json_fixture(
+            "broadcast_tx_sync_error",
+        ))
+        .unwrap();
+
+        assert_eq!(response.code, Code::Err(5));
+        assert_eq!(response.codespace.as_str(), "sdk");
+
+        let codes = CodeTable::new().with("sdk", 5, "insufficient funds");
+        assert_eq!(
+            response.qualified_code().to_error(&codes),
+            Some("insufficient funds")
+        );
+    }
+
     #[test]
     fn broadcast_tx_commit() {
         let response = endpoint::broadcast::tx_commit::Response::from_string(&read_json_fixture(
@@ -235,8 +274,10 @@ mod endpoints {
 
     #[test]
     fn genesis() {
-        let response =
-            endpoint::genesis::Response::from_string(&read_json_fixture("genesis")).unwrap();
+        let response = endpoint::genesis::Response::<serde_json::Value>::from_string(
+            &read_json_fixture("genesis"),
+        )
+        .unwrap();
 
         let tendermint::Genesis {
             chain_id,
@@ -248,6 +289,38 @@ mod endpoints {
         assert_eq!(consensus_params.block.max_bytes, 200_000);
     }
 
+    #[test]
+    fn genesis_typed_app_state() {
+        #[derive(Debug, Default, serde::Deserialize, serde::Serialize)]
+        struct AppState {
+            accounts: Vec<Account>,
+        }
+
+        #[derive(Debug, serde::Deserialize, serde::Serialize)]
+        struct Account {
+            address: String,
+            coins: Vec<Coin>,
+        }
+
+        #[derive(Debug, serde::Deserialize, serde::Serialize)]
+        struct Coin {
+            denom: String,
+            amount: String,
+        }
+
+        let response =
+            endpoint::genesis::Response::<AppState>::from_string(&read_json_fixture("genesis"))
+                .unwrap();
+        let accounts = response.genesis.app_state.accounts;
+
+        assert_eq!(
+            accounts[0].address,
+            "cosmos1000ya26q2cmh399q4c5aaacd9lmmdqp92z6l7q"
+        );
+        assert_eq!(accounts[0].coins[0].denom, "uatom");
+        assert_eq!(accounts[0].coins[0].amount, "5000000000");
+    }
+
     #[test]
     fn health() {
         endpoint::health::Response::from_string(&read_json_fixture("health")).unwrap();
@@ -260,6 +333,18 @@ mod endpoints {
 
         assert_eq!(response.n_peers, 2);
         assert_eq!(response.peers[0].node_info.network.as_str(), EXAMPLE_CHAIN);
+
+        let peer_address = response.peers[0].node_info.address().unwrap();
+        assert_eq!(peer_address.id, response.peers[0].node_info.id);
+        assert_eq!(peer_address.host, "11.22.33.44");
+        assert_eq!(peer_address.port, 26656);
+
+        for peer in &response.peers {
+            peer.node_info.validate().unwrap();
+            assert!(response.peers[0]
+                .node_info
+                .is_compatible_with(&peer.node_info));
+        }
     }
 
     #[test]
@@ -268,10 +353,48 @@ mod endpoints {
             endpoint::status::Response::from_string(&read_json_fixture("status")).unwrap();
 
         assert_eq!(response.node_info.network.as_str(), EXAMPLE_CHAIN);
+        assert_eq!(
+            response.node_info.protocol_version.block,
+            block::Version::TENDERMINT_V0_33
+        );
+        assert!(response.node_info.protocol_version.block < block::Version::TENDERMINT_V0_34);
+        assert!(response.node_info.channels.contains(channel::Id(0x20)));
+        response.node_info.validate().unwrap();
         assert_eq!(response.sync_info.latest_block_height.value(), 410_744);
+        assert_eq!(
+            response.sync_info.latest_app_hash.to_string(),
+            "38FE3F06E3EB936C2EE14DA6BEA15F97FEF8814824F022EE06635D7B2C39A0BA"
+        );
         assert_eq!(response.validator_info.voting_power.value(), 0);
     }
 
+    #[test]
+    fn tx() {
+        let response = endpoint::tx::Response::from_string(&read_json_fixture("tx")).unwrap();
+
+        assert_eq!(response.height.value(), 1001);
+        assert_eq!(response.tx.hash().to_string(), response.hash.to_string());
+        assert_eq!(
+            response.tx_result.events[0].first_value_of("name"),
+            Some("tendermint")
+        );
+
+        let proof = response.proof.unwrap();
+        assert_eq!(proof.data, response.tx);
+        assert!(proof.verify(&proof.root_hash).is_ok());
+        assert!(proof.verify(&tendermint::Hash::Sha256([0; 32])).is_err());
+
+        // Tendermint returns an empty proof if none was requested
+        let mut json: serde_json::Value = serde_json::from_str(&read_json_fixture("tx")).unwrap();
+        json["result"]["proof"] = serde_json::json!({
+            "root_hash": "",
+            "data": null,
+            "proof": {"total": "0", "index": "0", "leaf_hash": null, "aunts": null}
+        });
+        let response = endpoint::tx::Response::from_string(json.to_string()).unwrap();
+        assert!(response.proof.is_none());
+    }
+
     #[test]
     fn validators() {
         let response =
@@ -1,29 +1,47 @@
 //! Application BlockChain Interface (ABCI)
 //!
 //! NOTE: This module contains types for ABCI responses as consumed from RPC
-//! endpoints. It does not contain an ABCI protocol implementation.
-//!
-//! For that, see:
-//!
-//! <https://github.com/tendermint/rust-abci>
+//! endpoints, domain types for the requests and responses applications
+//! handle, the [`Application`] trait ABCI applications implement, and a
+//! [`Server`] serving them to Tendermint.
 
+pub mod application;
 mod code;
+pub mod codec;
+mod codespace;
+pub mod conformance;
 mod data;
 mod gas;
+#[cfg(feature = "grpc")]
+#[cfg_attr(docsrs, doc(cfg(feature = "grpc")))]
+pub mod grpc;
 mod info;
+pub mod kvstore;
+pub mod listener;
 mod log;
+pub mod middleware;
 mod path;
+#[cfg(feature = "abci-plus-plus")]
+pub mod plus_plus;
+pub mod request;
 pub mod responses;
+pub mod server;
+pub mod snapshot;
 pub mod tag;
 pub mod transaction;
 
 pub use self::{
-    code::Code,
+    application::{Application, SyncApplication},
+    code::{Code, CodeTable, MapCode, QualifiedCode},
+    codespace::Codespace,
     data::Data,
     gas::Gas,
     info::Info,
     log::Log,
     path::Path,
     responses::{DeliverTx, Event, Responses},
+    snapshot::Snapshot,
     transaction::Transaction,
 };
+
+pub use self::server::Server;
@@ -0,0 +1,666 @@
+//! ABCI applications: the state machines Tendermint replicates, which it
+//! drives through ABCI requests.
+//!
+//! Applications implement [`Application`], whose methods are asynchronous so
+//! that applications doing I/O (e.g. reading state from disk in `check_tx`
+//! or `query`) don't block the task serving their connection. Simple
+//! applications can implement the synchronous [`SyncApplication`] instead,
+//! and be served through [`Blocking`].
+//!
+//! Like Go's `BaseApplication`, every method has a default answering with an
+//! empty response, so applications only implement the methods they need.
+//! [`handle_request`] dispatches Tendermint's requests to an application.
+//!
+//! <https://github.com/tendermint/spec/blob/master/spec/abci/abci.md>
+
+#[cfg(feature = "abci-plus-plus")]
+use super::plus_plus::{
+    self, RequestExtendVote, RequestPrepareProposal, RequestProcessProposal,
+    RequestVerifyVoteExtension, ResponseExtendVote, ResponsePrepareProposal,
+    ResponseProcessProposal, ResponseVerifyVoteExtension,
+};
+use async_trait::async_trait;
+use std::sync::Mutex;
+use tendermint_proto::abci::{
+    request, response, Request, RequestApplySnapshotChunk, RequestBeginBlock, RequestCheckTx,
+    RequestCommit, RequestDeliverTx, RequestEndBlock, RequestInfo, RequestInitChain,
+    RequestListSnapshots, RequestLoadSnapshotChunk, RequestOfferSnapshot, RequestQuery,
+    RequestSetOption, Response, ResponseApplySnapshotChunk, ResponseBeginBlock, ResponseCheckTx,
+    ResponseCommit, ResponseDeliverTx, ResponseEcho, ResponseEndBlock, ResponseException,
+    ResponseFlush, ResponseInfo, ResponseInitChain, ResponseListSnapshots,
+    ResponseLoadSnapshotChunk, ResponseOfferSnapshot, ResponseQuery, ResponseSetOption,
+};
+
+/// ABCI application, answering Tendermint's requests asynchronously.
+///
+/// Methods take `&self`: Tendermint sends requests over several connections
+/// at once, so applications guard their state themselves, e.g. with a
+/// mutex.
+#[async_trait]
+pub trait Application: Send + Sync {
+    /// Get information about the application, e.g. the height and app hash
+    /// of its last committed block, which Tendermint replays blocks from
+    async fn info(&self, _request: RequestInfo) -> ResponseInfo {
+        ResponseInfo::default()
+    }
+
+    /// Set a non-consensus option, e.g. a minimum fee
+    async fn set_option(&self, _request: RequestSetOption) -> ResponseSetOption {
+        ResponseSetOption::default()
+    }
+
+    /// Initialize the application's state from the genesis file, once, at
+    /// genesis
+    async fn init_chain(&self, _request: RequestInitChain) -> ResponseInitChain {
+        ResponseInitChain::default()
+    }
+
+    /// Query the application's state
+    async fn query(&self, _request: RequestQuery) -> ResponseQuery {
+        ResponseQuery::default()
+    }
+
+    /// Check whether a transaction should enter the mempool, or stay in it
+    /// when rechecked after a commit (see [`CheckTxKind`]), and with which
+    /// priority
+    ///
+    /// [`CheckTxKind`]: super::request::CheckTxKind
+    async fn check_tx(&self, _request: RequestCheckTx) -> ResponseCheckTx {
+        ResponseCheckTx::default()
+    }
+
+    /// Start executing a block
+    async fn begin_block(&self, _request: RequestBeginBlock) -> ResponseBeginBlock {
+        ResponseBeginBlock::default()
+    }
+
+    /// Execute a transaction of the current block
+    async fn deliver_tx(&self, _request: RequestDeliverTx) -> ResponseDeliverTx {
+        ResponseDeliverTx::default()
+    }
+
+    /// Finish executing the current block, e.g. updating the validator set
+    async fn end_block(&self, _request: RequestEndBlock) -> ResponseEndBlock {
+        ResponseEndBlock::default()
+    }
+
+    /// Persist the state reached by the current block, returning its app
+    /// hash
+    async fn commit(&self, _request: RequestCommit) -> ResponseCommit {
+        ResponseCommit::default()
+    }
+
+    /// List the snapshots of the application's state available to peers
+    /// restoring it through state sync, e.g. as
+    /// [`Snapshot`](super::snapshot::Snapshot)s
+    async fn list_snapshots(&self, _request: RequestListSnapshots) -> ResponseListSnapshots {
+        ResponseListSnapshots::default()
+    }
+
+    /// Decide whether to restore a snapshot offered by a peer, while state
+    /// syncing: by default, every snapshot is refused
+    async fn offer_snapshot(&self, _request: RequestOfferSnapshot) -> ResponseOfferSnapshot {
+        ResponseOfferSnapshot::default()
+    }
+
+    /// Load a chunk of one of the listed snapshots, for a peer restoring it
+    async fn load_snapshot_chunk(
+        &self,
+        _request: RequestLoadSnapshotChunk,
+    ) -> ResponseLoadSnapshotChunk {
+        ResponseLoadSnapshotChunk::default()
+    }
+
+    /// Apply a chunk of the snapshot being restored: once every chunk is
+    /// applied, Tendermint checks the app hash the application reports
+    async fn apply_snapshot_chunk(
+        &self,
+        _request: RequestApplySnapshotChunk,
+    ) -> ResponseApplySnapshotChunk {
+        ResponseApplySnapshotChunk::default()
+    }
+
+    /// Handle a request from Tendermint, as the [`Server`] does: by default,
+    /// it's dispatched to the method answering it with [`handle_request`].
+    ///
+    /// [Middleware](super::middleware) overrides this to act on every
+    /// request.
+    ///
+    /// [`Server`]: super::Server
+    async fn handle(&self, request: Request) -> Response {
+        handle_request(self, request).await
+    }
+
+    /// Prepare the proposal of the next block, e.g. reordering or dropping
+    /// transactions: by default, the transactions are proposed in order up
+    /// to the maximum size (ABCI++)
+    #[cfg(feature = "abci-plus-plus")]
+    async fn prepare_proposal(&self, request: RequestPrepareProposal) -> ResponsePrepareProposal {
+        plus_plus::default_prepare_proposal(request)
+    }
+
+    /// Check a proposed block before voting on it: by default, every
+    /// proposal is accepted (ABCI++)
+    #[cfg(feature = "abci-plus-plus")]
+    async fn process_proposal(&self, _request: RequestProcessProposal) -> ResponseProcessProposal {
+        ResponseProcessProposal {
+            status: plus_plus::ProposalStatus::Accept as i32,
+        }
+    }
+
+    /// Extend a precommit for a block: by default, the extension is empty
+    /// (ABCI++)
+    #[cfg(feature = "abci-plus-plus")]
+    async fn extend_vote(&self, _request: RequestExtendVote) -> ResponseExtendVote {
+        ResponseExtendVote::default()
+    }
+
+    /// Verify the extension of another validator's precommit: by default,
+    /// every extension is accepted (ABCI++)
+    #[cfg(feature = "abci-plus-plus")]
+    async fn verify_vote_extension(
+        &self,
+        _request: RequestVerifyVoteExtension,
+    ) -> ResponseVerifyVoteExtension {
+        ResponseVerifyVoteExtension {
+            status: plus_plus::VerifyStatus::Accept as i32,
+        }
+    }
+}
+
+/// ABCI application answering Tendermint's requests synchronously, which
+/// [`Blocking`] serves as an [`Application`]
+pub trait SyncApplication: Send {
+    /// See [`Application::info`]
+    fn info(&mut self, _request: RequestInfo) -> ResponseInfo {
+        ResponseInfo::default()
+    }
+
+    /// See [`Application::set_option`]
+    fn set_option(&mut self, _request: RequestSetOption) -> ResponseSetOption {
+        ResponseSetOption::default()
+    }
+
+    /// See [`Application::init_chain`]
+    fn init_chain(&mut self, _request: RequestInitChain) -> ResponseInitChain {
+        ResponseInitChain::default()
+    }
+
+    /// See [`Application::query`]
+    fn query(&mut self, _request: RequestQuery) -> ResponseQuery {
+        ResponseQuery::default()
+    }
+
+    /// See [`Application::check_tx`]
+    fn check_tx(&mut self, _request: RequestCheckTx) -> ResponseCheckTx {
+        ResponseCheckTx::default()
+    }
+
+    /// See [`Application::begin_block`]
+    fn begin_block(&mut self, _request: RequestBeginBlock) -> ResponseBeginBlock {
+        ResponseBeginBlock::default()
+    }
+
+    /// See [`Application::deliver_tx`]
+    fn deliver_tx(&mut self, _request: RequestDeliverTx) -> ResponseDeliverTx {
+        ResponseDeliverTx::default()
+    }
+
+    /// See [`Application::end_block`]
+    fn end_block(&mut self, _request: RequestEndBlock) -> ResponseEndBlock {
+        ResponseEndBlock::default()
+    }
+
+    /// See [`Application::commit`]
+    fn commit(&mut self, _request: RequestCommit) -> ResponseCommit {
+        ResponseCommit::default()
+    }
+
+    /// See [`Application::list_snapshots`]
+    fn list_snapshots(&mut self, _request: RequestListSnapshots) -> ResponseListSnapshots {
+        ResponseListSnapshots::default()
+    }
+
+    /// See [`Application::offer_snapshot`]
+    fn offer_snapshot(&mut self, _request: RequestOfferSnapshot) -> ResponseOfferSnapshot {
+        ResponseOfferSnapshot::default()
+    }
+
+    /// See [`Application::load_snapshot_chunk`]
+    fn load_snapshot_chunk(
+        &mut self,
+        _request: RequestLoadSnapshotChunk,
+    ) -> ResponseLoadSnapshotChunk {
+        ResponseLoadSnapshotChunk::default()
+    }
+
+    /// See [`Application::apply_snapshot_chunk`]
+    fn apply_snapshot_chunk(
+        &mut self,
+        _request: RequestApplySnapshotChunk,
+    ) -> ResponseApplySnapshotChunk {
+        ResponseApplySnapshotChunk::default()
+    }
+
+    /// See [`Application::prepare_proposal`]
+    #[cfg(feature = "abci-plus-plus")]
+    fn prepare_proposal(&mut self, request: RequestPrepareProposal) -> ResponsePrepareProposal {
+        plus_plus::default_prepare_proposal(request)
+    }
+
+    /// See [`Application::process_proposal`]
+    #[cfg(feature = "abci-plus-plus")]
+    fn process_proposal(&mut self, _request: RequestProcessProposal) -> ResponseProcessProposal {
+        ResponseProcessProposal {
+            status: plus_plus::ProposalStatus::Accept as i32,
+        }
+    }
+
+    /// See [`Application::extend_vote`]
+    #[cfg(feature = "abci-plus-plus")]
+    fn extend_vote(&mut self, _request: RequestExtendVote) -> ResponseExtendVote {
+        ResponseExtendVote::default()
+    }
+
+    /// See [`Application::verify_vote_extension`]
+    #[cfg(feature = "abci-plus-plus")]
+    fn verify_vote_extension(
+        &mut self,
+        _request: RequestVerifyVoteExtension,
+    ) -> ResponseVerifyVoteExtension {
+        ResponseVerifyVoteExtension {
+            status: plus_plus::VerifyStatus::Accept as i32,
+        }
+    }
+}
+
+/// Adapter serving a [`SyncApplication`] as an [`Application`].
+///
+/// Like Go's local client, requests are handled one at a time under a lock,
+/// blocking the task for as long as the application takes to answer: this
+/// suits applications which keep their state in memory.
+#[derive(Debug)]
+pub struct Blocking<A>(Mutex<A>);
+
+impl<A> Blocking<A> {
+    /// Serve the given application
+    pub fn new(app: A) -> Self {
+        Blocking(Mutex::new(app))
+    }
+
+    /// Get the application back
+    pub fn into_inner(self) -> A {
+        self.0.into_inner().expect("poisoned lock")
+    }
+
+    fn with<T>(&self, f: impl FnOnce(&mut A) -> T) -> T {
+        let mut app = self.0.lock().expect("poisoned lock");
+        f(&mut *app)
+    }
+}
+
+#[async_trait]
+impl<A: SyncApplication> Application for Blocking<A> {
+    async fn info(&self, request: RequestInfo) -> ResponseInfo {
+        self.with(|app| app.info(request))
+    }
+
+    async fn set_option(&self, request: RequestSetOption) -> ResponseSetOption {
+        self.with(|app| app.set_option(request))
+    }
+
+    async fn init_chain(&self, request: RequestInitChain) -> ResponseInitChain {
+        self.with(|app| app.init_chain(request))
+    }
+
+    async fn query(&self, request: RequestQuery) -> ResponseQuery {
+        self.with(|app| app.query(request))
+    }
+
+    async fn check_tx(&self, request: RequestCheckTx) -> ResponseCheckTx {
+        self.with(|app| app.check_tx(request))
+    }
+
+    async fn begin_block(&self, request: RequestBeginBlock) -> ResponseBeginBlock {
+        self.with(|app| app.begin_block(request))
+    }
+
+    async fn deliver_tx(&self, request: RequestDeliverTx) -> ResponseDeliverTx {
+        self.with(|app| app.deliver_tx(request))
+    }
+
+    async fn end_block(&self, request: RequestEndBlock) -> ResponseEndBlock {
+        self.with(|app| app.end_block(request))
+    }
+
+    async fn commit(&self, request: RequestCommit) -> ResponseCommit {
+        self.with(|app| app.commit(request))
+    }
+
+    async fn list_snapshots(&self, request: RequestListSnapshots) -> ResponseListSnapshots {
+        self.with(|app| app.list_snapshots(request))
+    }
+
+    async fn offer_snapshot(&self, request: RequestOfferSnapshot) -> ResponseOfferSnapshot {
+        self.with(|app| app.offer_snapshot(request))
+    }
+
+    async fn load_snapshot_chunk(
+        &self,
+        request: RequestLoadSnapshotChunk,
+    ) -> ResponseLoadSnapshotChunk {
+        self.with(|app| app.load_snapshot_chunk(request))
+    }
+
+    async fn apply_snapshot_chunk(
+        &self,
+        request: RequestApplySnapshotChunk,
+    ) -> ResponseApplySnapshotChunk {
+        self.with(|app| app.apply_snapshot_chunk(request))
+    }
+
+    #[cfg(feature = "abci-plus-plus")]
+    async fn prepare_proposal(&self, request: RequestPrepareProposal) -> ResponsePrepareProposal {
+        self.with(|app| app.prepare_proposal(request))
+    }
+
+    #[cfg(feature = "abci-plus-plus")]
+    async fn process_proposal(&self, request: RequestProcessProposal) -> ResponseProcessProposal {
+        self.with(|app| app.process_proposal(request))
+    }
+
+    #[cfg(feature = "abci-plus-plus")]
+    async fn extend_vote(&self, request: RequestExtendVote) -> ResponseExtendVote {
+        self.with(|app| app.extend_vote(request))
+    }
+
+    #[cfg(feature = "abci-plus-plus")]
+    async fn verify_vote_extension(
+        &self,
+        request: RequestVerifyVoteExtension,
+    ) -> ResponseVerifyVoteExtension {
+        self.with(|app| app.verify_vote_extension(request))
+    }
+}
+
+/// Handle a request from Tendermint with the given application, returning
+/// the response.
+///
+/// Echo and flush requests are answered without the application, and
+/// requests without a value with an exception.
+pub async fn handle_request<A>(app: &A, request: Request) -> Response
+where
+    A: Application + ?Sized,
+{
+    use request::Value as Req;
+    use response::Value as Res;
+
+    let value = match request.value {
+        Some(Req::Echo(request)) => Res::Echo(ResponseEcho {
+            message: request.message,
+        }),
+        Some(Req::Flush(_)) => Res::Flush(ResponseFlush {}),
+        Some(Req::Info(request)) => Res::Info(app.info(request).await),
+        Some(Req::SetOption(request)) => Res::SetOption(app.set_option(request).await),
+        Some(Req::InitChain(request)) => Res::InitChain(app.init_chain(request).await),
+        Some(Req::Query(request)) => Res::Query(app.query(request).await),
+        Some(Req::BeginBlock(request)) => Res::BeginBlock(app.begin_block(request).await),
+        Some(Req::CheckTx(request)) => Res::CheckTx(app.check_tx(request).await),
+        Some(Req::DeliverTx(request)) => Res::DeliverTx(app.deliver_tx(request).await),
+        Some(Req::EndBlock(request)) => Res::EndBlock(app.end_block(request).await),
+        Some(Req::Commit(request)) => Res::Commit(app.commit(request).await),
+        Some(Req::ListSnapshots(request)) => Res::ListSnapshots(app.list_snapshots(request).await),
+        Some(Req::OfferSnapshot(request)) => Res::OfferSnapshot(app.offer_snapshot(request).await),
+        Some(Req::LoadSnapshotChunk(request)) => {
+            Res::LoadSnapshotChunk(app.load_snapshot_chunk(request).await)
+        }
+        Some(Req::ApplySnapshotChunk(request)) => {
+            Res::ApplySnapshotChunk(app.apply_snapshot_chunk(request).await)
+        }
+        None => Res::Exception(ResponseException {
+            error: "empty request".to_owned(),
+        }),
+    };
+
+    Response { value: Some(value) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abci::snapshot::Snapshot;
+    use futures::executor::block_on;
+    use std::convert::TryFrom;
+    use tendermint_proto::abci::{
+        response_apply_snapshot_chunk::Result as ApplySnapshotChunkResult,
+        response_offer_snapshot::Result as OfferSnapshotResult, RequestEcho, RequestFlush,
+    };
+
+    /// Counts the transactions it delivers
+    #[derive(Default)]
+    struct Counter {
+        txs: u64,
+        height: i64,
+    }
+
+    impl SyncApplication for Counter {
+        fn info(&mut self, _request: RequestInfo) -> ResponseInfo {
+            ResponseInfo {
+                last_block_height: self.height,
+                ..ResponseInfo::default()
+            }
+        }
+
+        fn deliver_tx(&mut self, _request: RequestDeliverTx) -> ResponseDeliverTx {
+            self.txs += 1;
+            ResponseDeliverTx::default()
+        }
+
+        fn commit(&mut self, _request: RequestCommit) -> ResponseCommit {
+            self.height += 1;
+            ResponseCommit {
+                data: self.txs.to_be_bytes().to_vec(),
+                ..ResponseCommit::default()
+            }
+        }
+    }
+
+    fn request(value: request::Value) -> Request {
+        Request { value: Some(value) }
+    }
+
+    #[test]
+    fn dispatches_requests() {
+        let app = Blocking::new(Counter::default());
+
+        let echo = request::Value::Echo(RequestEcho {
+            message: "hello".to_owned(),
+        });
+        assert_eq!(
+            block_on(handle_request(&app, request(echo))).value,
+            Some(response::Value::Echo(ResponseEcho {
+                message: "hello".to_owned()
+            }))
+        );
+
+        let flush = request::Value::Flush(RequestFlush {});
+        assert_eq!(
+            block_on(handle_request(&app, request(flush))).value,
+            Some(response::Value::Flush(ResponseFlush {}))
+        );
+
+        for _ in 0..2 {
+            let deliver_tx = request::Value::DeliverTx(RequestDeliverTx::default());
+            block_on(handle_request(&app, request(deliver_tx)));
+        }
+
+        let commit = request::Value::Commit(RequestCommit {});
+        match block_on(handle_request(&app, request(commit))).value {
+            Some(response::Value::Commit(response)) => {
+                assert_eq!(response.data, [0, 0, 0, 0, 0, 0, 0, 2])
+            }
+            other => panic!("unexpected response: {:?}", other),
+        }
+
+        let info = request::Value::Info(RequestInfo::default());
+        match block_on(handle_request(&app, request(info))).value {
+            Some(response::Value::Info(response)) => assert_eq!(response.last_block_height, 1),
+            other => panic!("unexpected response: {:?}", other),
+        }
+
+        assert!(matches!(
+            block_on(handle_request(&app, Request { value: None })).value,
+            Some(response::Value::Exception(_))
+        ));
+        assert_eq!(app.into_inner().txs, 2);
+    }
+
+    /// Applications which don't override a method answer with empty
+    /// responses
+    #[test]
+    fn answers_with_defaults() {
+        struct Empty;
+        impl Application for Empty {}
+
+        let check_tx = request::Value::CheckTx(RequestCheckTx::default());
+        assert_eq!(
+            block_on(handle_request(&Empty, request(check_tx))).value,
+            Some(response::Value::CheckTx(ResponseCheckTx::default()))
+        );
+    }
+
+    /// Serves a snapshot of a single chunk, and restores snapshots of its
+    /// format
+    struct Snapshots {
+        snapshot: Snapshot,
+        restored: Vec<u8>,
+    }
+
+    impl SyncApplication for Snapshots {
+        fn list_snapshots(&mut self, _request: RequestListSnapshots) -> ResponseListSnapshots {
+            ResponseListSnapshots {
+                snapshots: vec![self.snapshot.clone().into()],
+            }
+        }
+
+        fn offer_snapshot(&mut self, request: RequestOfferSnapshot) -> ResponseOfferSnapshot {
+            let result = match request.snapshot.map(Snapshot::try_from) {
+                Some(Ok(snapshot)) if snapshot.format == self.snapshot.format => {
+                    OfferSnapshotResult::Accept
+                }
+                Some(Ok(_)) => OfferSnapshotResult::RejectFormat,
+                _ => OfferSnapshotResult::Reject,
+            };
+
+            ResponseOfferSnapshot {
+                result: result as i32,
+            }
+        }
+
+        fn load_snapshot_chunk(
+            &mut self,
+            _request: RequestLoadSnapshotChunk,
+        ) -> ResponseLoadSnapshotChunk {
+            ResponseLoadSnapshotChunk {
+                chunk: b"state".to_vec(),
+            }
+        }
+
+        fn apply_snapshot_chunk(
+            &mut self,
+            request: RequestApplySnapshotChunk,
+        ) -> ResponseApplySnapshotChunk {
+            self.restored = request.chunk;
+            ResponseApplySnapshotChunk {
+                result: ApplySnapshotChunkResult::Accept as i32,
+                ..ResponseApplySnapshotChunk::default()
+            }
+        }
+    }
+
+    #[test]
+    fn dispatches_state_sync_requests() {
+        let snapshot = Snapshot {
+            height: 10u64.into(),
+            format: 1,
+            chunks: 1,
+            hash: vec![1; 32],
+            metadata: vec![],
+        };
+        let app = Blocking::new(Snapshots {
+            snapshot: snapshot.clone(),
+            restored: vec![],
+        });
+        let handle = |value| block_on(handle_request(&app, request(value))).value;
+
+        let list = request::Value::ListSnapshots(RequestListSnapshots {});
+        match handle(list) {
+            Some(response::Value::ListSnapshots(response)) => {
+                let listed = response.snapshots.into_iter().map(Snapshot::try_from);
+                assert_eq!(
+                    listed.collect::<Result<Vec<_>, _>>().unwrap(),
+                    [snapshot.clone()]
+                );
+            }
+            other => panic!("unexpected response: {:?}", other),
+        }
+
+        for (format, result) in &[
+            (1, OfferSnapshotResult::Accept),
+            (2, OfferSnapshotResult::RejectFormat),
+        ] {
+            let offer = request::Value::OfferSnapshot(RequestOfferSnapshot {
+                snapshot: Some(
+                    Snapshot {
+                        format: *format,
+                        ..snapshot.clone()
+                    }
+                    .into(),
+                ),
+                app_hash: vec![],
+            });
+            assert_eq!(
+                handle(offer),
+                Some(response::Value::OfferSnapshot(ResponseOfferSnapshot {
+                    result: *result as i32
+                }))
+            );
+        }
+
+        let load = request::Value::LoadSnapshotChunk(RequestLoadSnapshotChunk {
+            height: 10,
+            format: 1,
+            chunk: 0,
+        });
+        let chunk = match handle(load) {
+            Some(response::Value::LoadSnapshotChunk(response)) => response.chunk,
+            other => panic!("unexpected response: {:?}", other),
+        };
+
+        let apply = request::Value::ApplySnapshotChunk(RequestApplySnapshotChunk {
+            index: 0,
+            chunk,
+            sender: "peer".to_owned(),
+        });
+        assert!(matches!(
+            handle(apply),
+            Some(response::Value::ApplySnapshotChunk(_))
+        ));
+        assert_eq!(app.into_inner().restored, b"state");
+    }
+
+    /// Applications which don't support state sync refuse every snapshot
+    #[test]
+    fn refuses_snapshots_by_default() {
+        struct Empty;
+        impl Application for Empty {}
+
+        let offer = request::Value::OfferSnapshot(RequestOfferSnapshot::default());
+        match block_on(handle_request(&Empty, request(offer))).value {
+            Some(response::Value::OfferSnapshot(response)) => {
+                assert_eq!(response.result, OfferSnapshotResult::Unknown as i32)
+            }
+            other => panic!("unexpected response: {:?}", other),
+        }
+    }
+}
@@ -1,6 +1,7 @@
+use super::codespace::Codespace;
 use serde::de::{Deserialize, Deserializer, Visitor};
 use serde::{Serialize, Serializer};
-use std::fmt;
+use std::{collections::HashMap, fmt, mem::Discriminant};
 
 /// ABCI application response codes.
 ///
@@ -42,6 +43,14 @@ impl Code {
     pub fn value(self) -> u32 {
         u32::from(self)
     }
+
+    /// Qualify this code with the codespace which defines it
+    pub fn in_codespace(self, codespace: impl Into<Codespace>) -> QualifiedCode {
+        QualifiedCode {
+            codespace: codespace.into(),
+            code: self,
+        }
+    }
 }
 
 impl From<u32> for Code {
@@ -62,6 +71,160 @@ impl From<Code> for u32 {
     }
 }
 
+/// Response code qualified by its codespace.
+///
+/// Non-zero codes are only meaningful within a codespace: applications (e.g.
+/// Cosmos SDK chains) reuse the same numeric codes across modules.
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct QualifiedCode {
+    /// Codespace which defines the code
+    pub codespace: Codespace,
+
+    /// Response code
+    pub code: Code,
+}
+
+impl QualifiedCode {
+    /// Was the response OK?
+    pub fn is_ok(&self) -> bool {
+        self.code.is_ok()
+    }
+
+    /// Was the response an error?
+    pub fn is_err(&self) -> bool {
+        self.code.is_err()
+    }
+
+    /// Translate this code into an application-defined error using the given
+    /// mapping. Returns `None` if the code is OK or isn't known to the mapping.
+    pub fn to_error<M>(&self, mapping: &M) -> Option<M::Error>
+    where
+        M: MapCode + ?Sized,
+    {
+        match self.code {
+            Code::Ok => None,
+            Code::Err(code) => mapping.map_code(&self.codespace, code),
+        }
+    }
+}
+
+impl fmt::Display for QualifiedCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.codespace.is_empty() {
+            write!(f, "{}", self.code.value())
+        } else {
+            write!(f, "{}:{}", self.codespace, self.code.value())
+        }
+    }
+}
+
+/// Translate (codespace, code) pairs into application-defined errors.
+pub trait MapCode {
+    /// Application-defined error type
+    type Error;
+
+    /// Map the given non-zero code in the given codespace to an error, if
+    /// it is known
+    fn map_code(&self, codespace: &Codespace, code: u32) -> Option<Self::Error>;
+}
+
+impl<F, E> MapCode for F
+where
+    F: Fn(&Codespace, u32) -> Option<E>,
+{
+    type Error = E;
+
+    fn map_code(&self, codespace: &Codespace, code: u32) -> Option<E> {
+        self(codespace, code)
+    }
+}
+
+/// Table mapping (codespace, code) pairs to application-defined errors, and
+/// back.
+///
+/// Errors are mapped back to codes by variant, so that an application's
+/// error enum can register each of its variants once, with any data, and
+/// report its errors in responses (see e.g. [`DeliverTx::from_error`]).
+///
+/// [`DeliverTx::from_error`]: super::DeliverTx::from_error
+#[derive(Clone, Debug)]
+pub struct CodeTable<E> {
+    entries: HashMap<(Codespace, u32), E>,
+    codes: HashMap<Discriminant<E>, QualifiedCode>,
+}
+
+impl<E> CodeTable<E> {
+    /// Code of the errors missing from a table, in no codespace
+    pub const UNKNOWN_ERROR: u32 = 1;
+
+    /// Create a new, empty table
+    pub fn new() -> Self {
+        CodeTable {
+            entries: HashMap::new(),
+            codes: HashMap::new(),
+        }
+    }
+
+    /// Add the error for the given code in the given codespace
+    pub fn with(mut self, codespace: impl Into<Codespace>, code: u32, error: E) -> Self {
+        self.insert(codespace, code, error);
+        self
+    }
+
+    /// Add the error for the given code in the given codespace, returning
+    /// the previous error for it (if any)
+    pub fn insert(&mut self, codespace: impl Into<Codespace>, code: u32, error: E) -> Option<E> {
+        let qualified = Code::from(code).in_codespace(codespace);
+        let discriminant = std::mem::discriminant(&error);
+        self.codes.insert(discriminant, qualified.clone());
+
+        let previous = self
+            .entries
+            .insert((qualified.codespace.clone(), code), error)?;
+
+        // Errors of the replaced variant no longer have this code
+        let replaced = std::mem::discriminant(&previous);
+        if replaced != discriminant && self.codes.get(&replaced) == Some(&qualified) {
+            self.codes.remove(&replaced);
+        }
+
+        Some(previous)
+    }
+
+    /// Get the error for the given code in the given codespace
+    pub fn get(&self, codespace: &Codespace, code: u32) -> Option<&E> {
+        self.entries.get(&(codespace.clone(), code))
+    }
+
+    /// Get the code of the given error, i.e. of the errors of its variant
+    pub fn code_of(&self, error: &E) -> Option<&QualifiedCode> {
+        self.codes.get(&std::mem::discriminant(error))
+    }
+
+    /// Get the code reporting the given error in a response: its own, or
+    /// [`Self::UNKNOWN_ERROR`] in no codespace if it's missing from the
+    /// table
+    pub fn report(&self, error: &E) -> QualifiedCode {
+        self.code_of(error)
+            .cloned()
+            .unwrap_or_else(|| Code::Err(Self::UNKNOWN_ERROR).in_codespace(Codespace::default()))
+    }
+}
+
+impl<E> Default for CodeTable<E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<E: Clone> MapCode for CodeTable<E> {
+    type Error = E;
+
+    fn map_code(&self, codespace: &Codespace, code: u32) -> Option<E> {
+        self.get(codespace, code).cloned()
+    }
+}
+
 impl Serialize for Code {
     fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
         self.value().serialize(serializer)
@@ -103,3 +266,81 @@ impl<'de> Deserialize<'de> for Code {
         deserializer.deserialize_any(CodeVisitor)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, Debug, Eq, PartialEq)]
+    enum SdkError {
+        InsufficientFunds,
+        OutOfGas,
+    }
+
+    fn sdk_codes() -> CodeTable<SdkError> {
+        CodeTable::new()
+            .with("sdk", 5, SdkError::InsufficientFunds)
+            .with("sdk", 11, SdkError::OutOfGas)
+    }
+
+    #[test]
+    fn maps_codes_within_codespaces() {
+        let table = sdk_codes();
+
+        assert_eq!(
+            Code::from(5).in_codespace("sdk").to_error(&table),
+            Some(SdkError::InsufficientFunds)
+        );
+        assert_eq!(Code::from(5).in_codespace("bank").to_error(&table), None);
+        assert_eq!(Code::from(12).in_codespace("sdk").to_error(&table), None);
+        assert_eq!(Code::Ok.in_codespace("sdk").to_error(&table), None);
+    }
+
+    #[test]
+    fn maps_codes_with_closures() {
+        let mapping = |codespace: &Codespace, code: u32| match (codespace.as_str(), code) {
+            ("sdk", 11) => Some(SdkError::OutOfGas),
+            _ => None,
+        };
+
+        assert_eq!(
+            Code::from(11).in_codespace("sdk").to_error(&mapping),
+            Some(SdkError::OutOfGas)
+        );
+    }
+
+    #[test]
+    fn maps_errors_to_codes() {
+        #[derive(Clone, Debug)]
+        enum BankError {
+            InsufficientFunds { needed: u64 },
+            UnknownDenom,
+            Frozen,
+        }
+
+        let table = CodeTable::new()
+            .with("bank", 2, BankError::InsufficientFunds { needed: 0 })
+            .with("bank", 3, BankError::UnknownDenom);
+
+        let error = BankError::InsufficientFunds { needed: 100 };
+        assert!(matches!(
+            error,
+            BankError::InsufficientFunds { needed: 100 }
+        ));
+        assert_eq!(table.code_of(&error).unwrap().to_string(), "bank:2");
+        assert_eq!(table.report(&BankError::UnknownDenom).to_string(), "bank:3");
+        assert!(table.code_of(&BankError::Frozen).is_none());
+        assert_eq!(table.report(&BankError::Frozen).to_string(), "1");
+
+        // Replaced errors lose their code
+        let table = table.with("bank", 3, BankError::Frozen);
+        assert!(table.code_of(&BankError::UnknownDenom).is_none());
+        assert_eq!(table.report(&BankError::Frozen).to_string(), "bank:3");
+    }
+
+    #[test]
+    fn displays_qualified_codes() {
+        assert_eq!(Code::from(5).in_codespace("sdk").to_string(), "sdk:5");
+        assert_eq!(Code::from(5).in_codespace("").to_string(), "5");
+    }
+}
@@ -0,0 +1,169 @@
+//! Framing of ABCI messages on a connection: like Tendermint's socket client
+//! and server, each message is prefixed with its length as an unsigned
+//! varint.
+//!
+//! Writing a message doesn't flush the writer, so that responses can be
+//! buffered until Tendermint flushes its requests.
+
+use crate::{error::Kind, Error};
+use anomaly::fail;
+use futures::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use prost::Message;
+
+/// Maximum size of an encoded message, as enforced by Tendermint
+pub const MAX_MESSAGE_SIZE: usize = 100 * 1024 * 1024;
+
+/// Read a length-prefixed message, returning `None` if the connection was
+/// closed between messages
+pub async fn read_message_async<M, R>(reader: &mut R) -> Result<Option<M>, Error>
+where
+    M: Message + Default,
+    R: AsyncRead + Unpin,
+{
+    match read_frame_async(reader, MAX_MESSAGE_SIZE).await? {
+        Some(bytes) => M::decode(bytes.as_slice())
+            .map(Some)
+            .map_err(|e| Kind::Parse.context(e).into()),
+        None => Ok(None),
+    }
+}
+
+/// Write a length-prefixed message, without flushing the writer
+pub async fn write_message_async<M, W>(writer: &mut W, message: &M) -> Result<(), Error>
+where
+    M: Message,
+    W: AsyncWrite + Unpin,
+{
+    let mut bytes = Vec::with_capacity(message.encoded_len());
+    message.encode(&mut bytes).expect("buffer is large enough");
+    write_frame_async(writer, &bytes).await
+}
+
+/// Read the contents of a length-prefixed frame of at most `max_size`
+/// bytes, returning `None` if the connection was closed between frames.
+///
+/// Lengths above the maximum are refused as soon as their prefix exceeds it,
+/// before anything is allocated.
+pub async fn read_frame_async<R>(reader: &mut R, max_size: usize) -> Result<Option<Vec<u8>>, Error>
+where
+    R: AsyncRead + Unpin,
+{
+    let mut len = 0u64;
+    let mut byte = [0u8];
+
+    for i in 0.. {
+        if let Err(e) = reader.read_exact(&mut byte).await {
+            if i == 0 && e.kind() == std::io::ErrorKind::UnexpectedEof {
+                return Ok(None);
+            }
+
+            return Err(Kind::Io.context(e).into());
+        }
+
+        if i == 10 || (i == 9 && byte[0] > 1) {
+            fail!(Kind::Parse, "malformed message length prefix");
+        }
+
+        len |= u64::from(byte[0] & 0x7f) << (7 * i);
+        if len > max_size as u64 {
+            fail!(
+                Kind::Length,
+                "message of at least {} bytes exceeds maximum of {}",
+                len,
+                max_size
+            );
+        }
+
+        if byte[0] & 0x80 == 0 {
+            break;
+        }
+    }
+
+    let mut bytes = Vec::new();
+    reader
+        .take(len)
+        .read_to_end(&mut bytes)
+        .await
+        .map_err(|e| Kind::Io.context(e))?;
+
+    if (bytes.len() as u64) < len {
+        fail!(
+            Kind::Parse,
+            "truncated frame: got {} of {} bytes",
+            bytes.len(),
+            len
+        );
+    }
+
+    Ok(Some(bytes))
+}
+
+/// Write the given frame contents with their length prefix, without flushing
+/// the writer
+pub async fn write_frame_async<W>(writer: &mut W, bytes: &[u8]) -> Result<(), Error>
+where
+    W: AsyncWrite + Unpin,
+{
+    let mut frame = Vec::with_capacity(bytes.len() + 10);
+    prost::encoding::encode_varint(bytes.len() as u64, &mut frame);
+    frame.extend_from_slice(bytes);
+
+    writer
+        .write_all(&frame)
+        .await
+        .map_err(|e| Kind::Io.context(e).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::{executor::block_on, io::Cursor};
+    use tendermint_proto::abci::{request, Request, RequestEcho};
+
+    #[test]
+    fn round_trip() {
+        let echo = Request {
+            value: Some(request::Value::Echo(RequestEcho {
+                message: "hello".to_owned(),
+            })),
+        };
+
+        let mut wire = Cursor::new(vec![]);
+        block_on(async {
+            write_message_async(&mut wire, &echo).await.unwrap();
+            write_message_async(&mut wire, &Request::default())
+                .await
+                .unwrap();
+        });
+
+        let wire = wire.into_inner();
+        let mut reader = wire.as_slice();
+        block_on(async {
+            assert_eq!(read_message_async(&mut reader).await.unwrap(), Some(echo));
+            assert_eq!(
+                read_message_async(&mut reader).await.unwrap(),
+                Some(Request::default())
+            );
+            assert_eq!(
+                read_message_async::<Request, _>(&mut reader).await.unwrap(),
+                None
+            );
+        });
+    }
+
+    #[test]
+    fn rejects_truncated_and_oversized_frames() {
+        block_on(async {
+            let truncated = [5u8, 1, 2];
+            assert!(read_frame_async(&mut &truncated[..], 10).await.is_err());
+
+            let oversized = [0x80u8, 0x01];
+            assert!(read_frame_async(&mut &oversized[..], 100).await.is_err());
+
+            let truncated_prefix = [0x80u8];
+            assert!(read_frame_async(&mut &truncated_prefix[..], 100)
+                .await
+                .is_err());
+        });
+    }
+}
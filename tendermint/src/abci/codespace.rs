@@ -0,0 +1,51 @@
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Display};
+
+/// ABCI codespace: the namespace (e.g. an application module) which defines
+/// the meaning of a non-zero [`Code`](super::Code).
+///
+/// The empty codespace is used by applications which don't namespace their
+/// codes.
+#[derive(Clone, Debug, Default, Deserialize, Eq, Hash, PartialEq, PartialOrd, Ord, Serialize)]
+pub struct Codespace(String);
+
+impl Codespace {
+    /// Create a new codespace
+    pub fn new(name: impl Into<String>) -> Self {
+        Codespace(name.into())
+    }
+
+    /// Borrow the codespace as a string slice
+    pub fn as_str(&self) -> &str {
+        self.0.as_str()
+    }
+
+    /// Is this the empty codespace?
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl From<&str> for Codespace {
+    fn from(name: &str) -> Self {
+        Codespace(name.to_owned())
+    }
+}
+
+impl From<String> for Codespace {
+    fn from(name: String) -> Self {
+        Codespace(name)
+    }
+}
+
+impl AsRef<str> for Codespace {
+    fn as_ref(&self) -> &str {
+        self.0.as_ref()
+    }
+}
+
+impl Display for Codespace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
@@ -0,0 +1,327 @@
+//! Conformance checks for ABCI applications, driving fresh instances of an
+//! application through the requests Tendermint sends a new chain and checking
+//! the invariants Tendermint relies on, e.g. from an application's tests:
+//!
+//! ```
+//! use tendermint::abci::{application::Blocking, conformance::Harness, kvstore::KeyValueStore};
+//!
+//! Harness::new(|| Blocking::new(KeyValueStore::new()))
+//!     .with_block(vec![b"name=satoshi".to_vec()])
+//!     .run()
+//!     .unwrap();
+//! ```
+//!
+//! The checked invariants are:
+//!
+//! - echo requests are echoed back
+//! - a fresh application reports height 0, and after each commit reports its
+//!   height and the app hash it committed
+//! - app hashes, and the results of delivered transactions (their code,
+//!   data and gas, as hashed into blocks), are the same across instances:
+//!   applications which aren't deterministic halt the chain
+//! - checking transactions, as Tendermint's mempool does before proposing
+//!   them, doesn't affect app hashes or results
+
+use super::{application::handle_request, Application};
+use crate::{
+    block,
+    error::{Error, Kind},
+};
+use anomaly::{fail, format_err};
+use futures::executor::block_on;
+use std::convert::TryFrom;
+use tendermint_proto::{
+    abci::{
+        request, response, Request, RequestBeginBlock, RequestCheckTx, RequestCommit,
+        RequestDeliverTx, RequestEcho, RequestEndBlock, RequestInfo, RequestInitChain,
+    },
+    types::Header,
+};
+
+/// Chain ID of the chains the harness runs, by default
+pub const DEFAULT_CHAIN_ID: &str = "conformance";
+
+/// Conformance test of an ABCI application, running the same blocks on
+/// fresh instances of the application
+#[derive(Clone, Debug)]
+pub struct Harness<F> {
+    new_app: F,
+    chain_id: String,
+    blocks: Vec<Vec<Vec<u8>>>,
+}
+
+/// Results of a block, which every instance of an application must agree on
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct BlockResults {
+    /// Code, data, and gas wanted and used, of each delivered transaction
+    txs: Vec<(u32, Vec<u8>, i64, i64)>,
+
+    /// App hash committed at the end of the block
+    app_hash: Vec<u8>,
+}
+
+impl<A, F> Harness<F>
+where
+    A: Application,
+    F: Fn() -> A,
+{
+    /// Test the applications created by `new_app`, each with a fresh state.
+    ///
+    /// Unless blocks are added with [`Harness::with_block`], the harness
+    /// runs an empty block, then blocks of `key=value` transactions.
+    pub fn new(new_app: F) -> Self {
+        Harness {
+            new_app,
+            chain_id: DEFAULT_CHAIN_ID.to_owned(),
+            blocks: vec![],
+        }
+    }
+
+    /// Set the chain ID sent to the application
+    pub fn with_chain_id(mut self, chain_id: impl Into<String>) -> Self {
+        self.chain_id = chain_id.into();
+        self
+    }
+
+    /// Add a block with the given transactions, e.g. ones the application
+    /// accepts
+    pub fn with_block(mut self, txs: Vec<Vec<u8>>) -> Self {
+        self.blocks.push(txs);
+        self
+    }
+
+    /// Run the blocks, returning the first violated invariant
+    pub fn run(&self) -> Result<(), Error> {
+        block_on(self.run_async())
+    }
+
+    /// Run the blocks asynchronously, e.g. for applications which need the
+    /// runtime of a test
+    pub async fn run_async(&self) -> Result<(), Error> {
+        let default_blocks;
+        let blocks = if self.blocks.is_empty() {
+            default_blocks = vec![
+                vec![],
+                vec![b"name=satoshi".to_vec()],
+                vec![b"name=nakamoto".to_vec(), b"year=2008".to_vec()],
+            ];
+            &default_blocks
+        } else {
+            &self.blocks
+        };
+
+        let checked = self.run_chain(blocks, true).await?;
+        let unchecked = self.run_chain(blocks, false).await?;
+
+        for (i, (checked, unchecked)) in checked.iter().zip(&unchecked).enumerate() {
+            if checked != unchecked {
+                fail!(
+                    Kind::Protocol,
+                    "results of block {} differ across instances: {:?} != {:?}",
+                    i + 1,
+                    checked,
+                    unchecked
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Run the blocks on a fresh application, checking their transactions
+    /// first if `check_txs` is set
+    async fn run_chain(
+        &self,
+        blocks: &[Vec<Vec<u8>>],
+        check_txs: bool,
+    ) -> Result<Vec<BlockResults>, Error> {
+        let app = (self.new_app)();
+        echo(&app).await?;
+
+        let info = app.info(RequestInfo::default()).await;
+        check_height(info.last_block_height, block::Height::default())?;
+
+        app.init_chain(RequestInitChain {
+            chain_id: self.chain_id.clone(),
+            initial_height: 1,
+            ..RequestInitChain::default()
+        })
+        .await;
+
+        let mut results = Vec::with_capacity(blocks.len());
+        for (i, txs) in blocks.iter().enumerate() {
+            let height = block::Height::from(i as u64 + 1);
+
+            if check_txs {
+                for tx in txs {
+                    app.check_tx(RequestCheckTx {
+                        tx: tx.clone(),
+                        ..RequestCheckTx::default()
+                    })
+                    .await;
+                }
+            }
+
+            app.begin_block(RequestBeginBlock {
+                header: Some(Header {
+                    chain_id: self.chain_id.clone(),
+                    height: height.into(),
+                    ..Header::default()
+                }),
+                ..RequestBeginBlock::default()
+            })
+            .await;
+
+            let mut tx_results = Vec::with_capacity(txs.len());
+            for tx in txs {
+                let response = app.deliver_tx(RequestDeliverTx { tx: tx.clone() }).await;
+                tx_results.push((
+                    response.code,
+                    response.data,
+                    response.gas_wanted,
+                    response.gas_used,
+                ));
+            }
+
+            app.end_block(RequestEndBlock {
+                height: height.into(),
+            })
+            .await;
+            let app_hash = app.commit(RequestCommit {}).await.data;
+
+            let info = app.info(RequestInfo::default()).await;
+            check_height(info.last_block_height, height)?;
+            if info.last_block_app_hash != app_hash {
+                fail!(
+                    Kind::Protocol,
+                    "info reports app hash {:?} at height {}, but {:?} was committed",
+                    info.last_block_app_hash,
+                    height,
+                    app_hash
+                );
+            }
+
+            results.push(BlockResults {
+                txs: tx_results,
+                app_hash,
+            });
+        }
+
+        Ok(results)
+    }
+}
+
+/// Check echo requests are echoed back, as the server dispatches them
+async fn echo<A: Application>(app: &A) -> Result<(), Error> {
+    let message = "conformance".to_owned();
+    let request = Request {
+        value: Some(request::Value::Echo(RequestEcho {
+            message: message.clone(),
+        })),
+    };
+
+    match handle_request(app, request).await.value {
+        Some(response::Value::Echo(response)) if response.message == message => Ok(()),
+        other => fail!(Kind::Protocol, "unexpected echo response: {:?}", other),
+    }
+}
+
+/// Check the application reports the expected height
+fn check_height(actual: i64, expected: block::Height) -> Result<(), Error> {
+    let actual = block::Height::try_from(actual)
+        .map_err(|_| format_err!(Kind::OutOfRange, "info reports height {}", actual))?;
+
+    if actual != expected {
+        return Err(Kind::HeightMismatch { expected, actual }.into());
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        abci::{application::Blocking, kvstore::KeyValueStore},
+        error::kind,
+    };
+    use std::sync::atomic::{AtomicI64, AtomicU8, Ordering};
+    use tendermint_proto::abci::{ResponseCommit, ResponseInfo};
+
+    #[test]
+    fn kvstore_conforms() {
+        Harness::new(|| Blocking::new(KeyValueStore::new()))
+            .run()
+            .unwrap();
+
+        Harness::new(|| Blocking::new(KeyValueStore::new()))
+            .with_chain_id("test-chain")
+            .with_block(vec![b"a=1".to_vec(), b"b".to_vec()])
+            .with_block(vec![b"a=2".to_vec()])
+            .run()
+            .unwrap();
+    }
+
+    #[test]
+    fn detects_nondeterminism() {
+        static INSTANCES: AtomicU8 = AtomicU8::new(0);
+
+        // Commits its instance number as app hash
+        struct Random {
+            instance: u8,
+            height: AtomicI64,
+        }
+
+        #[async_trait::async_trait]
+        impl Application for Random {
+            async fn info(&self, _request: RequestInfo) -> ResponseInfo {
+                let height = self.height.load(Ordering::SeqCst);
+                ResponseInfo {
+                    last_block_height: height,
+                    last_block_app_hash: if height > 0 {
+                        vec![self.instance]
+                    } else {
+                        vec![]
+                    },
+                    ..ResponseInfo::default()
+                }
+            }
+
+            async fn commit(&self, _request: RequestCommit) -> ResponseCommit {
+                self.height.fetch_add(1, Ordering::SeqCst);
+                ResponseCommit {
+                    data: vec![self.instance],
+                    ..ResponseCommit::default()
+                }
+            }
+        }
+
+        let new_app = || Random {
+            instance: INSTANCES.fetch_add(1, Ordering::SeqCst),
+            height: AtomicI64::new(0),
+        };
+        let error = Harness::new(new_app).run().unwrap_err();
+        assert_eq!(kind(error.as_ref()), Some(&Kind::Protocol));
+    }
+
+    #[test]
+    fn detects_stale_info() {
+        struct Stale;
+
+        #[async_trait::async_trait]
+        impl Application for Stale {
+            async fn info(&self, _request: RequestInfo) -> ResponseInfo {
+                ResponseInfo::default()
+            }
+        }
+
+        let error = Harness::new(|| Stale).run().unwrap_err();
+        assert_eq!(
+            kind(error.as_ref()),
+            Some(&Kind::HeightMismatch {
+                expected: block::Height::from(1u64),
+                actual: block::Height::default(),
+            })
+        );
+    }
+}
@@ -5,9 +5,11 @@
 //!
 //! <https://tendermint.com/docs/spec/abci/apps.html#gas>
 
-use crate::{Error, Kind};
+use crate::{block, Error, Kind};
+use anomaly::{fail, format_err};
 use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
 use std::{
+    convert::TryFrom,
     fmt::{self, Display},
     str::FromStr,
 };
@@ -17,10 +19,58 @@ use std::{
 pub struct Gas(u64);
 
 impl Gas {
+    /// Largest amount of gas representable in ABCI (which uses `int64`)
+    pub const MAX: Gas = Gas(i64::MAX as u64);
+
     /// Get the inner integer value
     pub fn value(self) -> u64 {
         self.0
     }
+
+    /// Add gas, returning `None` if the result exceeds [`Gas::MAX`]
+    pub fn checked_add(self, other: Gas) -> Option<Gas> {
+        self.0
+            .checked_add(other.0)
+            .filter(|&sum| sum <= Self::MAX.0)
+            .map(Gas)
+    }
+
+    /// Subtract gas, returning `None` if the result would be negative
+    pub fn checked_sub(self, other: Gas) -> Option<Gas> {
+        self.0.checked_sub(other.0).map(Gas)
+    }
+
+    /// Multiply gas by a count (e.g. a per-message cost by a number of
+    /// messages), returning `None` if the result exceeds [`Gas::MAX`]
+    pub fn checked_mul(self, count: u64) -> Option<Gas> {
+        self.0
+            .checked_mul(count)
+            .filter(|&product| product <= Self::MAX.0)
+            .map(Gas)
+    }
+
+    /// Add gas, saturating at [`Gas::MAX`]
+    pub fn saturating_add(self, other: Gas) -> Gas {
+        self.checked_add(other).unwrap_or(Self::MAX)
+    }
+
+    /// Subtract gas, saturating at zero
+    pub fn saturating_sub(self, other: Gas) -> Gas {
+        Gas(self.0.saturating_sub(other.0))
+    }
+
+    /// Does this amount of gas fit within the given block gas limit?
+    ///
+    /// A negative limit (Tendermint uses `-1`) means there is no limit.
+    pub fn fits_within(self, max_gas: i64) -> bool {
+        max_gas < 0 || self.0 <= max_gas as u64
+    }
+
+    /// Does this amount of gas fit within the gas limit of blocks of the
+    /// given size?
+    pub fn fits_in_block(self, block_size: &block::Size) -> bool {
+        self.fits_within(block_size.max_gas)
+    }
 }
 
 impl From<u64> for Gas {
@@ -41,18 +91,57 @@ impl Display for Gas {
     }
 }
 
+impl TryFrom<i64> for Gas {
+    type Error = Error;
+
+    fn try_from(amount: i64) -> Result<Gas, Error> {
+        if amount < 0 {
+            fail!(Kind::OutOfRange, "negative gas: {}", amount);
+        }
+
+        Ok(Gas(amount as u64))
+    }
+}
+
+impl TryFrom<Gas> for i64 {
+    type Error = Error;
+
+    fn try_from(gas: Gas) -> Result<i64, Error> {
+        if gas > Gas::MAX {
+            fail!(Kind::OutOfRange, "gas exceeds int64: {}", gas);
+        }
+
+        Ok(gas.0 as i64)
+    }
+}
+
 impl FromStr for Gas {
     type Err = Error;
 
     fn from_str(s: &str) -> Result<Self, Error> {
-        Ok(Self::from(s.parse::<u64>().map_err(|_| Kind::Parse)?))
+        let amount = s
+            .parse::<i64>()
+            .map_err(|e| format_err!(Kind::Parse, "invalid gas {:?}: {}", s, e))?;
+
+        Self::try_from(amount)
     }
 }
 
 impl<'de> Deserialize<'de> for Gas {
     fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
-        Ok(Self::from_str(&String::deserialize(deserializer)?)
-            .map_err(|e| D::Error::custom(format!("{}", e)))?)
+        /// ABCI encodes gas as a string, but accept plain integers as well
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            String(String),
+            Integer(i64),
+        }
+
+        match Repr::deserialize(deserializer)? {
+            Repr::String(s) => Self::from_str(&s),
+            Repr::Integer(amount) => Self::try_from(amount),
+        }
+        .map_err(|e| D::Error::custom(format!("{}", e)))
     }
 }
 
@@ -61,3 +150,52 @@ impl Serialize for Gas {
         self.to_string().serialize(serializer)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checked_arithmetic() {
+        let gas = Gas::from(100);
+
+        assert_eq!(gas.checked_add(Gas::from(50)), Some(Gas::from(150)));
+        assert_eq!(gas.checked_sub(Gas::from(150)), None);
+        assert_eq!(gas.checked_mul(3), Some(Gas::from(300)));
+        assert_eq!(Gas::MAX.checked_add(Gas::from(1)), None);
+        assert_eq!(Gas::MAX.checked_mul(2), None);
+
+        assert_eq!(Gas::MAX.saturating_add(gas), Gas::MAX);
+        assert_eq!(gas.saturating_sub(Gas::from(150)), Gas::from(0));
+    }
+
+    #[test]
+    fn block_gas_limits() {
+        let gas = Gas::from(100);
+
+        assert!(gas.fits_within(-1));
+        assert!(gas.fits_within(100));
+        assert!(!gas.fits_within(99));
+        assert!(gas.fits_in_block(&block::Size::default()));
+    }
+
+    #[test]
+    fn serde_as_int64_strings() {
+        assert_eq!(
+            serde_json::from_str::<Gas>(r#""200000""#).unwrap(),
+            Gas::from(200_000)
+        );
+        assert_eq!(
+            serde_json::from_str::<Gas>("200000").unwrap(),
+            Gas::from(200_000)
+        );
+        assert_eq!(
+            serde_json::to_string(&Gas::from(200_000)).unwrap(),
+            r#""200000""#
+        );
+
+        for invalid in &[r#""-1""#, "-1", r#""9223372036854775808""#, r#""abc""#] {
+            assert!(serde_json::from_str::<Gas>(invalid).is_err(), "{}", invalid);
+        }
+    }
+}
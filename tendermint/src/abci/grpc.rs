@@ -0,0 +1,342 @@
+//! gRPC ABCI server, serving an [`Application`] through Tendermint's
+//! `ABCIApplication` gRPC service, for nodes configured with
+//! `abci = "grpc"`.
+//!
+//! Each method wraps its request in the [`Request`] envelope of the socket
+//! protocol, which the [`Server`] handles like a request read from a
+//! connection of its type (see [`Server::handle_request`]), so both backends
+//! share their request and response conversions and their [`Config`]:
+//! exclusive requests share the server's lock, the requests of a sequential
+//! connection type are handled one at a time, and parallel ones at once.
+//! ABCI++ requests aren't part of the service.
+//!
+//! [`GrpcServer`] is a `tower` service to add to a `tonic` server:
+//!
+//! ```no_run
+//! use tendermint::abci::{application::Blocking, grpc::GrpcServer, kvstore::KeyValueStore, Server};
+//!
+//! async fn serve() -> Result<(), tonic::transport::Error> {
+//!     let server = GrpcServer::new(Server::new(Blocking::new(KeyValueStore::new())));
+//!
+//!     tonic::transport::Server::builder()
+//!         .add_service(server)
+//!         .serve("127.0.0.1:26658".parse().unwrap())
+//!         .await
+//! }
+//! ```
+//!
+//! [`Config`]: super::server::Config
+
+use super::{
+    server::{Concurrency, ConnectionType},
+    Application, Server,
+};
+use futures::lock::Mutex;
+use std::{
+    sync::Arc,
+    task::{Context, Poll},
+};
+use tendermint_proto::abci::{request, response, Request};
+use tonic::{
+    body::BoxBody,
+    codec::ProstCodec,
+    codegen::{http, BoxFuture, HttpBody, Never, Service, StdError},
+    server::{Grpc, UnaryService},
+    transport::NamedService,
+    Status,
+};
+
+/// Name of Tendermint's ABCI gRPC service
+pub const SERVICE_NAME: &str = "tendermint.abci.ABCIApplication";
+
+/// gRPC ABCI server, serving an application with a [`Server`]
+pub struct GrpcServer<A> {
+    inner: Arc<Inner<A>>,
+}
+
+impl<A: Application> GrpcServer<A> {
+    /// Serve the application of the given server
+    pub fn new(server: Server<A>) -> Self {
+        GrpcServer {
+            inner: Arc::new(Inner {
+                server,
+                sequential: Default::default(),
+            }),
+        }
+    }
+
+    /// Borrow the server, e.g. to shut it down: serve with `tonic`'s
+    /// `serve_with_shutdown`, passing it [`Server::shutdown_requested`]
+    pub fn server(&self) -> &Server<A> {
+        &self.inner.server
+    }
+}
+
+impl<A> Clone for GrpcServer<A> {
+    fn clone(&self) -> Self {
+        GrpcServer {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<A> NamedService for GrpcServer<A> {
+    const NAME: &'static str = SERVICE_NAME;
+}
+
+impl<A, B> Service<http::Request<B>> for GrpcServer<A>
+where
+    A: Application + 'static,
+    B: HttpBody + Send + Sync + 'static,
+    B::Error: Into<StdError> + Send + 'static,
+{
+    type Response = http::Response<BoxBody>;
+    type Error = Never;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: http::Request<B>) -> Self::Future {
+        use request::Value as Req;
+        use response::Value as Res;
+
+        let method = request
+            .uri()
+            .path()
+            .strip_prefix("/tendermint.abci.ABCIApplication/")
+            .map(str::to_owned);
+        let inner = self.inner.clone();
+
+        macro_rules! unary {
+            ($method:ident) => {
+                unary(inner, request, Req::$method, |value| match value {
+                    Res::$method(response) => Some(response),
+                    _ => None,
+                })
+            };
+        }
+
+        match method.as_deref() {
+            Some("Echo") => unary!(Echo),
+            Some("Flush") => unary!(Flush),
+            Some("Info") => unary!(Info),
+            Some("SetOption") => unary!(SetOption),
+            Some("DeliverTx") => unary!(DeliverTx),
+            Some("CheckTx") => unary!(CheckTx),
+            Some("Query") => unary!(Query),
+            Some("Commit") => unary!(Commit),
+            Some("InitChain") => unary!(InitChain),
+            Some("BeginBlock") => unary!(BeginBlock),
+            Some("EndBlock") => unary!(EndBlock),
+            Some("ListSnapshots") => unary!(ListSnapshots),
+            Some("OfferSnapshot") => unary!(OfferSnapshot),
+            Some("LoadSnapshotChunk") => unary!(LoadSnapshotChunk),
+            Some("ApplySnapshotChunk") => unary!(ApplySnapshotChunk),
+            _ => Box::pin(async {
+                Ok(http::Response::builder()
+                    .status(200)
+                    .header("grpc-status", "12")
+                    .body(BoxBody::empty())
+                    .expect("valid unimplemented response"))
+            }),
+        }
+    }
+}
+
+/// State shared by the clones of a [`GrpcServer`]
+struct Inner<A> {
+    server: Server<A>,
+    /// Locks of the sequential connection types, in the order of
+    /// [`Inner::sequential_lock`]
+    sequential: [Mutex<()>; 4],
+}
+
+impl<A: Application> Inner<A> {
+    /// Handle a request as a request of its connection type
+    async fn handle(&self, value: request::Value) -> Option<response::Value> {
+        let _guard = match ConnectionType::of(&value) {
+            Some(connection)
+                if self.server.config().concurrency(connection) == Concurrency::Sequential =>
+            {
+                Some(self.sequential_lock(connection).lock().await)
+            }
+            _ => None,
+        };
+
+        self.server
+            .handle_request(Request { value: Some(value) })
+            .await
+            .value
+    }
+
+    fn sequential_lock(&self, connection: ConnectionType) -> &Mutex<()> {
+        match connection {
+            ConnectionType::Consensus => &self.sequential[0],
+            ConnectionType::Mempool => &self.sequential[1],
+            ConnectionType::Info => &self.sequential[2],
+            ConnectionType::Snapshot => &self.sequential[3],
+        }
+    }
+}
+
+/// Serve a unary method, whose requests are wrapped in and whose responses
+/// are unwrapped from the socket protocol's envelopes with the given
+/// functions
+fn unary<A, B, Req, Res>(
+    inner: Arc<Inner<A>>,
+    request: http::Request<B>,
+    wrap: fn(Req) -> request::Value,
+    unwrap: fn(response::Value) -> Option<Res>,
+) -> BoxFuture<http::Response<BoxBody>, Never>
+where
+    A: Application + 'static,
+    B: HttpBody + Send + Sync + 'static,
+    B::Error: Into<StdError> + Send + 'static,
+    Req: prost::Message + Default + Send + Sync + 'static,
+    Res: prost::Message + Send + Sync + 'static,
+{
+    let method = Method {
+        inner,
+        wrap,
+        unwrap,
+    };
+
+    Box::pin(async move {
+        let mut grpc = Grpc::new(ProstCodec::default());
+        Ok(grpc.unary(method, request).await)
+    })
+}
+
+/// Unary method of the service
+struct Method<A, Req, Res> {
+    inner: Arc<Inner<A>>,
+    wrap: fn(Req) -> request::Value,
+    unwrap: fn(response::Value) -> Option<Res>,
+}
+
+impl<A, Req, Res> UnaryService<Req> for Method<A, Req, Res>
+where
+    A: Application + 'static,
+    Res: Send + 'static,
+{
+    type Response = Res;
+    type Future = BoxFuture<tonic::Response<Res>, Status>;
+
+    fn call(&mut self, request: tonic::Request<Req>) -> Self::Future {
+        let inner = self.inner.clone();
+        let value = (self.wrap)(request.into_inner());
+        let unwrap = self.unwrap;
+
+        Box::pin(async move {
+            match inner.handle(value).await {
+                Some(response::Value::Exception(exception)) => {
+                    Err(Status::unknown(exception.error))
+                }
+                Some(value) => unwrap(value)
+                    .map(tonic::Response::new)
+                    .ok_or_else(|| Status::internal("unexpected response type")),
+                None => Err(Status::internal("empty response")),
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abci::{application::Blocking, kvstore::KeyValueStore, server::Config};
+    use futures::executor::block_on;
+    use tendermint_proto::abci::{
+        RequestCommit, RequestDeliverTx, RequestEcho, RequestQuery, ResponseCommit,
+        ResponseDeliverTx, ResponseEcho, ResponseQuery,
+    };
+
+    fn method<Req, Res>(
+        server: &GrpcServer<Blocking<KeyValueStore>>,
+        wrap: fn(Req) -> request::Value,
+        unwrap: fn(response::Value) -> Option<Res>,
+    ) -> Method<Blocking<KeyValueStore>, Req, Res> {
+        Method {
+            inner: server.inner.clone(),
+            wrap,
+            unwrap,
+        }
+    }
+
+    fn call<Req, Res: Send + 'static>(
+        method: &mut Method<Blocking<KeyValueStore>, Req, Res>,
+        request: Req,
+    ) -> Result<Res, Status> {
+        block_on(method.call(tonic::Request::new(request))).map(tonic::Response::into_inner)
+    }
+
+    #[test]
+    fn serves_the_application() {
+        let config = Config {
+            consensus: Concurrency::Sequential,
+            mempool: Concurrency::Parallel,
+            ..Config::default()
+        };
+        let server = GrpcServer::new(Server::with_config(
+            Blocking::new(KeyValueStore::new()),
+            config,
+        ));
+
+        let mut echo = method(&server, request::Value::Echo, |value| match value {
+            response::Value::Echo(response) => Some(response),
+            _ => None,
+        });
+        let echoed: ResponseEcho = call(
+            &mut echo,
+            RequestEcho {
+                message: "hello".to_owned(),
+            },
+        )
+        .unwrap();
+        assert_eq!(echoed.message, "hello");
+
+        let mut deliver_tx = method(&server, request::Value::DeliverTx, |value| match value {
+            response::Value::DeliverTx(response) => Some(response),
+            _ => None,
+        });
+        let delivered: ResponseDeliverTx = call(
+            &mut deliver_tx,
+            RequestDeliverTx {
+                tx: b"name=satoshi".to_vec(),
+            },
+        )
+        .unwrap();
+        assert_eq!(delivered.code, 0);
+
+        let mut commit = method(&server, request::Value::Commit, |value| match value {
+            response::Value::Commit(response) => Some(response),
+            _ => None,
+        });
+        let _: ResponseCommit = call(&mut commit, RequestCommit {}).unwrap();
+
+        let mut query = method(&server, request::Value::Query, |value| match value {
+            response::Value::Query(response) => Some(response),
+            _ => None,
+        });
+        let queried: ResponseQuery = call(
+            &mut query,
+            RequestQuery {
+                data: b"name".to_vec(),
+                ..RequestQuery::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(queried.value, b"satoshi".to_vec());
+    }
+
+    #[test]
+    fn rejects_mismatched_responses() {
+        let server = GrpcServer::new(Server::new(Blocking::new(KeyValueStore::new())));
+
+        let mut echo = method(&server, request::Value::Echo, |_| None::<ResponseEcho>);
+        let status = call(&mut echo, RequestEcho::default()).unwrap_err();
+        assert_eq!(status.code(), tonic::Code::Internal);
+    }
+}
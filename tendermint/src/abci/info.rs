@@ -2,9 +2,27 @@ use serde::{Deserialize, Serialize};
 use std::fmt::{self, Display};
 
 /// ABCI info
-#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
 pub struct Info(String);
 
+impl From<&str> for Info {
+    fn from(s: &str) -> Self {
+        Info(s.to_owned())
+    }
+}
+
+impl From<String> for Info {
+    fn from(s: String) -> Self {
+        Info(s)
+    }
+}
+
+impl From<Info> for String {
+    fn from(info: Info) -> Self {
+        info.0
+    }
+}
+
 impl AsRef<str> for Info {
     fn as_ref(&self) -> &str {
         self.0.as_ref()
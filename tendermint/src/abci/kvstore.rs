@@ -0,0 +1,345 @@
+//! Example key-value store application, after Go's `kvstore` example
+//! application, e.g. to test ABCI servers and the applications built on them.
+//!
+//! Transactions of the form `key=value` store `value` under `key`; any other
+//! transaction is stored under itself. Queries look up the value stored under
+//! the query data in the last committed state.
+//!
+//! Unlike Go's `kvstore`, whose app hash is the number of stored keys, the
+//! app hash is the root of a simple Merkle tree over the stored pairs, as in
+//! Tendermint's `SimpleProofsFromMap`, so that queries can prove their
+//! results: proven queries return a `simple:v` proof op, which
+//! [`verify_value`] checks against an app hash.
+
+use super::application::SyncApplication;
+use crate::{
+    error::{Error, Kind},
+    merkle::{self, proof::SimpleProof},
+};
+use anomaly::{fail, format_err};
+use prost::Message as _;
+use sha2::{Digest, Sha256};
+use std::{collections::BTreeMap, convert::TryFrom};
+use tendermint_proto::{
+    abci::{
+        Event, EventAttribute, RequestCheckTx, RequestCommit, RequestDeliverTx, RequestInfo,
+        RequestQuery, ResponseCheckTx, ResponseCommit, ResponseDeliverTx, ResponseInfo,
+        ResponseQuery,
+    },
+    crypto::{ProofOp, ProofOps, ValueOp},
+};
+
+/// Type of the proof ops of the values stored in a simple Merkle tree
+pub const PROOF_OP_VALUE: &str = "simple:v";
+
+/// ABCI version reported by [`KeyValueStore`]'s info
+const ABCI_VERSION: &str = "0.17.0";
+
+/// Code of the queries which can't be answered
+const CODE_UNAVAILABLE: u32 = 1;
+
+/// Example application storing key-value pairs, which is served as an
+/// [`Application`] through [`Blocking`].
+///
+/// [`Application`]: super::Application
+/// [`Blocking`]: super::application::Blocking
+#[derive(Clone, Debug, Default)]
+pub struct KeyValueStore {
+    /// Pairs stored by the transactions delivered so far
+    pending: BTreeMap<Vec<u8>, Vec<u8>>,
+
+    /// Pairs stored as of the last commit
+    committed: BTreeMap<Vec<u8>, Vec<u8>>,
+
+    /// Height of the last commit
+    height: i64,
+
+    /// App hash of the last commit
+    app_hash: Vec<u8>,
+}
+
+impl KeyValueStore {
+    /// Create an empty store
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the value stored under the given key as of the last commit
+    pub fn get(&self, key: &[u8]) -> Option<&[u8]> {
+        self.committed.get(key).map(Vec::as_slice)
+    }
+
+    /// Get the height of the last commit
+    pub fn height(&self) -> i64 {
+        self.height
+    }
+
+    /// Get the app hash of the last commit
+    pub fn app_hash(&self) -> &[u8] {
+        &self.app_hash
+    }
+
+    /// Leaves of the Merkle tree of the committed pairs, in key order, along
+    /// with its root and the proof of each leaf
+    fn proofs(&self) -> (merkle::Hash, Vec<SimpleProof>) {
+        let leaves = self
+            .committed
+            .iter()
+            .map(|(key, value)| leaf(key, value))
+            .collect::<Vec<_>>();
+
+        merkle::simple_proofs_from_byte_vectors(&leaves)
+    }
+}
+
+impl SyncApplication for KeyValueStore {
+    fn info(&mut self, _request: RequestInfo) -> ResponseInfo {
+        ResponseInfo {
+            data: format!("{{\"size\":{}}}", self.committed.len()),
+            version: ABCI_VERSION.to_owned(),
+            app_version: 1,
+            last_block_height: self.height,
+            last_block_app_hash: self.app_hash.clone(),
+        }
+    }
+
+    fn query(&mut self, request: RequestQuery) -> ResponseQuery {
+        if request.height != 0 && request.height != self.height {
+            return ResponseQuery {
+                code: CODE_UNAVAILABLE,
+                log: format!("only the last height ({}) can be queried", self.height),
+                height: self.height,
+                ..ResponseQuery::default()
+            };
+        }
+
+        let key = request.data;
+        let value = self.committed.get(&key).cloned();
+        let log = if value.is_some() {
+            "exists"
+        } else {
+            "does not exist"
+        };
+
+        // Only stored values can be proven
+        let proof_ops = match &value {
+            Some(_) if request.prove => {
+                let index = self.committed.range(..key.clone()).count();
+                let proof = self.proofs().1.swap_remove(index);
+                Some(value_proof_ops(&key, proof))
+            }
+            _ => None,
+        };
+
+        ResponseQuery {
+            log: log.to_owned(),
+            index: -1,
+            key,
+            value: value.unwrap_or_default(),
+            proof_ops,
+            height: self.height,
+            ..ResponseQuery::default()
+        }
+    }
+
+    fn check_tx(&mut self, _request: RequestCheckTx) -> ResponseCheckTx {
+        ResponseCheckTx {
+            gas_wanted: 1,
+            ..ResponseCheckTx::default()
+        }
+    }
+
+    fn deliver_tx(&mut self, request: RequestDeliverTx) -> ResponseDeliverTx {
+        let (key, value) = match request.tx.iter().position(|&byte| byte == b'=') {
+            Some(i) if !request.tx[i + 1..].contains(&b'=') => {
+                (request.tx[..i].to_vec(), request.tx[i + 1..].to_vec())
+            }
+            _ => (request.tx.clone(), request.tx),
+        };
+
+        let attribute = |key: &str, value: &[u8], index| EventAttribute {
+            key: key.as_bytes().to_vec(),
+            value: value.to_vec(),
+            index,
+        };
+
+        // The events of Go's `kvstore`
+        let event = Event {
+            r#type: "app".to_owned(),
+            attributes: vec![
+                attribute("creator", b"Cosmoshi Netowoko", true),
+                attribute("key", &key, true),
+                attribute("index_key", b"index is working", true),
+                attribute("noindex_key", b"index is working", false),
+            ],
+        };
+
+        self.pending.insert(key, value);
+
+        ResponseDeliverTx {
+            events: vec![event],
+            ..ResponseDeliverTx::default()
+        }
+    }
+
+    fn commit(&mut self, _request: RequestCommit) -> ResponseCommit {
+        self.committed = self.pending.clone();
+        self.height += 1;
+        self.app_hash = self.proofs().0.to_vec();
+
+        ResponseCommit {
+            data: self.app_hash.clone(),
+            ..ResponseCommit::default()
+        }
+    }
+}
+
+/// Verify that the given proof ops, returned by a proven query, prove that
+/// `value` is stored under `key` in the state with the given app hash
+pub fn verify_value(
+    app_hash: &[u8],
+    key: &[u8],
+    value: &[u8],
+    proof_ops: &ProofOps,
+) -> Result<(), Error> {
+    let op = match proof_ops.ops.as_slice() {
+        [op] if op.r#type == PROOF_OP_VALUE => op,
+        _ => fail!(
+            Kind::InvalidProof,
+            "expected a single {} proof op",
+            PROOF_OP_VALUE
+        ),
+    };
+
+    let value_op =
+        ValueOp::decode(op.data.as_slice()).map_err(|e| Kind::InvalidProof.context(e))?;
+    if op.key != key || value_op.key != key {
+        fail!(Kind::InvalidProof, "proof is for another key");
+    }
+
+    let proof = value_op
+        .proof
+        .ok_or_else(|| format_err!(Kind::InvalidProof, "missing proof"))?;
+    let proof = SimpleProof::try_from(proof)?;
+
+    let root = merkle::Hash::try_from(app_hash)
+        .map_err(|_| format_err!(Kind::InvalidProof, "invalid app hash"))?;
+
+    proof.verify(&root, &leaf(key, value))
+}
+
+/// Leaf of a pair in the Merkle tree: the key and the hash of the value,
+/// each prefixed with its length, as Tendermint's `KVPair` encodes them
+fn leaf(key: &[u8], value: &[u8]) -> Vec<u8> {
+    let value_hash = Sha256::digest(value);
+
+    let mut leaf = Vec::with_capacity(key.len() + value_hash.len() + 4);
+    prost::encoding::encode_varint(key.len() as u64, &mut leaf);
+    leaf.extend_from_slice(key);
+    prost::encoding::encode_varint(value_hash.len() as u64, &mut leaf);
+    leaf.extend_from_slice(&value_hash);
+    leaf
+}
+
+fn value_proof_ops(key: &[u8], proof: SimpleProof) -> ProofOps {
+    let value_op = ValueOp {
+        key: key.to_vec(),
+        proof: Some(proof.into()),
+    };
+
+    let mut data = Vec::with_capacity(value_op.encoded_len());
+    value_op.encode(&mut data).expect("buffer is large enough");
+
+    ProofOps {
+        ops: vec![ProofOp {
+            r#type: PROOF_OP_VALUE.to_owned(),
+            key: key.to_vec(),
+            data,
+        }],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abci::application::{handle_request, Blocking};
+    use futures::executor::block_on;
+    use tendermint_proto::abci::{request, response, Request};
+
+    fn deliver(app: &mut KeyValueStore, tx: &[u8]) -> ResponseDeliverTx {
+        app.deliver_tx(RequestDeliverTx { tx: tx.to_vec() })
+    }
+
+    fn query(app: &mut KeyValueStore, key: &[u8]) -> ResponseQuery {
+        app.query(RequestQuery {
+            data: key.to_vec(),
+            prove: true,
+            ..RequestQuery::default()
+        })
+    }
+
+    #[test]
+    fn stores_and_proves_pairs() {
+        let mut app = KeyValueStore::new();
+        for tx in &[
+            &b"name=satoshi"[..],
+            &b"abc"[..],
+            &b"a=b=c"[..],
+            &b"zed=1"[..],
+        ] {
+            let response = deliver(&mut app, tx);
+            assert_eq!(response.code, 0);
+            assert_eq!(response.events[0].r#type, "app");
+        }
+
+        // Uncommitted pairs can't be queried
+        assert_eq!(query(&mut app, b"name").log, "does not exist");
+
+        let commit = app.commit(RequestCommit {});
+        assert_eq!(commit.data, app.app_hash());
+        assert_eq!(app.height(), 1);
+        assert_eq!(app.get(b"a=b=c"), Some(&b"a=b=c"[..]));
+
+        for (key, value) in &[
+            (&b"name"[..], &b"satoshi"[..]),
+            (&b"abc"[..], &b"abc"[..]),
+            (&b"a=b=c"[..], &b"a=b=c"[..]),
+            (&b"zed"[..], &b"1"[..]),
+        ] {
+            let response = query(&mut app, key);
+            assert_eq!(response.value, *value);
+            assert_eq!(response.height, 1);
+
+            let proof_ops = response.proof_ops.unwrap();
+            verify_value(&commit.data, key, value, &proof_ops).unwrap();
+            assert!(verify_value(&commit.data, key, b"forged", &proof_ops).is_err());
+        }
+
+        let missing = query(&mut app, b"missing");
+        assert_eq!(missing.log, "does not exist");
+        assert!(missing.proof_ops.is_none());
+
+        let info = app.info(RequestInfo::default());
+        assert_eq!(info.data, r#"{"size":4}"#);
+        assert_eq!(info.last_block_app_hash, commit.data);
+    }
+
+    #[test]
+    fn serves_requests() {
+        let app = Blocking::new(KeyValueStore::new());
+        let handle = |value| block_on(handle_request(&app, Request { value: Some(value) })).value;
+
+        handle(request::Value::DeliverTx(RequestDeliverTx {
+            tx: b"name=satoshi".to_vec(),
+        }));
+        handle(request::Value::Commit(RequestCommit {}));
+
+        match handle(request::Value::Query(RequestQuery {
+            data: b"name".to_vec(),
+            ..RequestQuery::default()
+        })) {
+            Some(response::Value::Query(response)) => assert_eq!(response.value, b"satoshi"),
+            other => panic!("unexpected response: {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,425 @@
+//! Listener accepting Tendermint's connections to an ABCI [`Server`], on a
+//! TCP or UNIX domain socket, e.g. at a node's `proxy_app` address.
+//!
+//! Accepting connections, and I/O on them, block the current thread: each
+//! connection is served on a thread of its own, with
+//! `futures::executor::block_on`. Applications running in an async runtime
+//! can instead accept connections with it and pass them to
+//! [`Server::serve_connection`].
+
+use super::{server::Server, Application};
+use crate::{error::Kind, net, Error};
+use anomaly::{fail, format_err};
+use futures::{executor::block_on, io::AllowStdIo};
+#[cfg(unix)]
+use std::{
+    fs,
+    os::unix::{
+        fs::PermissionsExt,
+        net::{UnixListener, UnixStream},
+    },
+    path::PathBuf,
+};
+use std::{
+    io::{self, Read, Write},
+    net::{Shutdown, TcpListener, TcpStream},
+    sync::Arc,
+    thread,
+    time::Duration,
+};
+
+/// Interval at which the listener checks whether its server is shutting down
+/// while no connection arrives
+const SHUTDOWN_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Options of the socket files of UNIX domain socket listeners
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct UnixSocketOptions {
+    /// Replace the socket file left behind at the address, e.g. by a crashed
+    /// application, instead of failing to bind it
+    pub replace_stale: bool,
+
+    /// Remove the socket file when the listener is dropped
+    pub remove_on_drop: bool,
+
+    /// Permissions of the socket file (e.g. `0o660`, for Tendermint to
+    /// connect from another user of the same group), instead of those
+    /// following from the process' umask
+    pub mode: Option<u32>,
+}
+
+impl Default for UnixSocketOptions {
+    fn default() -> Self {
+        UnixSocketOptions {
+            replace_stale: true,
+            remove_on_drop: true,
+            mode: None,
+        }
+    }
+}
+
+/// Listener accepting Tendermint's connections
+#[derive(Debug)]
+pub struct Listener {
+    socket: Socket,
+}
+
+#[derive(Debug)]
+enum Socket {
+    Tcp(TcpListener),
+    #[cfg(unix)]
+    Unix {
+        listener: UnixListener,
+        path: PathBuf,
+        remove_on_drop: bool,
+    },
+}
+
+impl Listener {
+    /// Listen on the given `tcp://` or `unix://` address, with the default
+    /// options for UNIX domain sockets
+    pub fn bind(address: &net::Address) -> Result<Self, Error> {
+        Self::bind_with(address, UnixSocketOptions::default())
+    }
+
+    /// Listen on the given `tcp://` or `unix://` address, with the given
+    /// options for UNIX domain sockets
+    pub fn bind_with(address: &net::Address, options: UnixSocketOptions) -> Result<Self, Error> {
+        let socket = match address {
+            net::Address::Tcp { host, port, .. } => {
+                let listener = TcpListener::bind((host.as_str(), *port))
+                    .map_err(|e| format_err!(Kind::Io, "couldn't bind {}: {}", address, e))?;
+                Socket::Tcp(listener)
+            }
+            #[cfg(unix)]
+            net::Address::Unix { path } => bind_unix(path, options)?,
+            #[cfg(not(unix))]
+            net::Address::Unix { .. } => {
+                let _ = options;
+                fail!(Kind::Io, "UNIX domain sockets are unsupported: {}", address)
+            }
+        };
+
+        Ok(Listener { socket })
+    }
+
+    /// Get the local address of a TCP listener, e.g. to find the port
+    /// picked when binding port 0
+    pub fn local_tcp_addr(&self) -> Option<std::net::SocketAddr> {
+        match &self.socket {
+            Socket::Tcp(listener) => listener.local_addr().ok(),
+            #[cfg(unix)]
+            Socket::Unix { .. } => None,
+        }
+    }
+
+    /// Wait for Tendermint to open a connection
+    pub fn accept(&self) -> Result<AllowStdIo<Connection>, Error> {
+        self.accept_connection()
+            .map(AllowStdIo::new)
+            .map_err(|e| Kind::Io.context(e).into())
+    }
+
+    /// Serve the connections accepted from now on with the given server,
+    /// each on a thread of its own, until the server shuts down.
+    ///
+    /// Once it does, the listener stops accepting connections, and the
+    /// served connections stop reading requests: this returns once they've
+    /// written their outstanding responses and closed.
+    pub fn serve<A>(&self, server: Arc<Server<A>>) -> Result<(), Error>
+    where
+        A: Application + 'static,
+    {
+        self.set_nonblocking(true)?;
+
+        let mut connections = vec![];
+        while !server.is_shutting_down() {
+            let connection = match self.accept_connection() {
+                Ok(connection) => connection,
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                    thread::sleep(SHUTDOWN_POLL_INTERVAL);
+                    continue;
+                }
+                Err(e) => return Err(Kind::Io.context(e).into()),
+            };
+
+            connection.set_nonblocking(false)?;
+            let reader = connection.try_clone()?;
+            let server = server.clone();
+            let handle = thread::spawn(move || {
+                block_on(server.serve_connection(AllowStdIo::new(connection)))
+            });
+
+            connections.push((reader, handle));
+        }
+
+        // Blocked reads only return once the connection is shut down
+        for (reader, _) in &connections {
+            let _ = reader.shutdown_read();
+        }
+
+        for (_, handle) in connections {
+            handle
+                .join()
+                .map_err(|_| format_err!(Kind::Io, "connection thread panicked"))??;
+        }
+
+        Ok(())
+    }
+
+    fn accept_connection(&self) -> io::Result<Connection> {
+        match &self.socket {
+            Socket::Tcp(listener) => {
+                let (stream, _) = listener.accept()?;
+                stream.set_nodelay(true)?;
+                Ok(Connection::Tcp(stream))
+            }
+            #[cfg(unix)]
+            Socket::Unix { listener, .. } => {
+                let (stream, _) = listener.accept()?;
+                Ok(Connection::Unix(stream))
+            }
+        }
+    }
+
+    fn set_nonblocking(&self, nonblocking: bool) -> Result<(), Error> {
+        match &self.socket {
+            Socket::Tcp(listener) => listener.set_nonblocking(nonblocking),
+            #[cfg(unix)]
+            Socket::Unix { listener, .. } => listener.set_nonblocking(nonblocking),
+        }
+        .map_err(|e| Kind::Io.context(e).into())
+    }
+}
+
+#[cfg(unix)]
+impl Drop for Listener {
+    fn drop(&mut self) {
+        if let Socket::Unix {
+            path,
+            remove_on_drop: true,
+            ..
+        } = &self.socket
+        {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+/// Connection accepted from Tendermint
+#[derive(Debug)]
+pub enum Connection {
+    /// TCP connection
+    Tcp(TcpStream),
+
+    /// UNIX domain socket connection
+    #[cfg(unix)]
+    Unix(UnixStream),
+}
+
+impl Connection {
+    fn try_clone(&self) -> Result<Self, Error> {
+        match self {
+            Connection::Tcp(stream) => stream.try_clone().map(Connection::Tcp),
+            #[cfg(unix)]
+            Connection::Unix(stream) => stream.try_clone().map(Connection::Unix),
+        }
+        .map_err(|e| Kind::Io.context(e).into())
+    }
+
+    fn set_nonblocking(&self, nonblocking: bool) -> Result<(), Error> {
+        match self {
+            Connection::Tcp(stream) => stream.set_nonblocking(nonblocking),
+            #[cfg(unix)]
+            Connection::Unix(stream) => stream.set_nonblocking(nonblocking),
+        }
+        .map_err(|e| Kind::Io.context(e).into())
+    }
+
+    fn shutdown_read(&self) -> io::Result<()> {
+        match self {
+            Connection::Tcp(stream) => stream.shutdown(Shutdown::Read),
+            #[cfg(unix)]
+            Connection::Unix(stream) => stream.shutdown(Shutdown::Read),
+        }
+    }
+}
+
+impl Read for Connection {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Connection::Tcp(stream) => stream.read(buf),
+            #[cfg(unix)]
+            Connection::Unix(stream) => stream.read(buf),
+        }
+    }
+}
+
+impl Write for Connection {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Connection::Tcp(stream) => stream.write(buf),
+            #[cfg(unix)]
+            Connection::Unix(stream) => stream.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Connection::Tcp(stream) => stream.flush(),
+            #[cfg(unix)]
+            Connection::Unix(stream) => stream.flush(),
+        }
+    }
+}
+
+#[cfg(unix)]
+fn bind_unix(path: &PathBuf, options: UnixSocketOptions) -> Result<Socket, Error> {
+    if path.exists() {
+        if !options.replace_stale {
+            fail!(Kind::Io, "{} already exists", path.display());
+        }
+
+        fs::remove_file(path)
+            .map_err(|e| format_err!(Kind::Io, "couldn't remove {}: {}", path.display(), e))?;
+    }
+
+    let listener = UnixListener::bind(path)
+        .map_err(|e| format_err!(Kind::Io, "couldn't bind {}: {}", path.display(), e))?;
+
+    if let Some(mode) = options.mode {
+        fs::set_permissions(path, fs::Permissions::from_mode(mode)).map_err(|e| {
+            format_err!(
+                Kind::Io,
+                "couldn't set permissions of {}: {}",
+                path.display(),
+                e
+            )
+        })?;
+    }
+
+    Ok(Socket::Unix {
+        listener,
+        path: path.clone(),
+        remove_on_drop: options.remove_on_drop,
+    })
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::abci::{application::Blocking, codec, kvstore::KeyValueStore};
+    use std::{env, process};
+    use tendermint_proto::abci::{
+        request, response, Request, RequestCommit, RequestDeliverTx, RequestFlush, RequestQuery,
+        Response,
+    };
+
+    fn socket_path(name: &str) -> PathBuf {
+        env::temp_dir().join(format!("tendermint-abci-{}-{}.sock", name, process::id()))
+    }
+
+    fn request(stream: &mut UnixStream, value: request::Value) -> response::Value {
+        let mut connection = AllowStdIo::new(stream);
+        block_on(async {
+            let request = Request { value: Some(value) };
+            let flush = Request {
+                value: Some(request::Value::Flush(RequestFlush {})),
+            };
+            codec::write_message_async(&mut connection, &request)
+                .await
+                .unwrap();
+            codec::write_message_async(&mut connection, &flush)
+                .await
+                .unwrap();
+
+            let response = codec::read_message_async::<Response, _>(&mut connection)
+                .await
+                .unwrap()
+                .unwrap();
+            codec::read_message_async::<Response, _>(&mut connection)
+                .await
+                .unwrap()
+                .unwrap();
+            response.value.unwrap()
+        })
+    }
+
+    #[test]
+    fn serves_unix_socket() {
+        let path = socket_path("serve");
+        let address = net::Address::Unix { path: path.clone() };
+        let listener = Listener::bind_with(
+            &address,
+            UnixSocketOptions {
+                mode: Some(0o660),
+                ..UnixSocketOptions::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            fs::metadata(&path).unwrap().permissions().mode() & 0o777,
+            0o660
+        );
+
+        let server = Arc::new(Server::new(Blocking::new(KeyValueStore::new())));
+        let serving = {
+            let server = server.clone();
+            thread::spawn(move || listener.serve(server))
+        };
+
+        let mut consensus = UnixStream::connect(&path).unwrap();
+        let mut info = UnixStream::connect(&path).unwrap();
+
+        request(
+            &mut consensus,
+            request::Value::DeliverTx(RequestDeliverTx {
+                tx: b"name=satoshi".to_vec(),
+            }),
+        );
+        request(&mut consensus, request::Value::Commit(RequestCommit {}));
+
+        let query = request::Value::Query(RequestQuery {
+            data: b"name".to_vec(),
+            ..RequestQuery::default()
+        });
+        match request(&mut info, query) {
+            response::Value::Query(response) => assert_eq!(response.value, b"satoshi"),
+            other => panic!("unexpected response: {:?}", other),
+        }
+
+        // Shutting down closes the connections, and the listener
+        server.shutdown();
+        serving.join().unwrap().unwrap();
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn keeps_existing_sockets() {
+        let path = socket_path("existing");
+        let address = net::Address::Unix { path: path.clone() };
+        let first = Listener::bind(&address).unwrap();
+
+        let options = UnixSocketOptions {
+            replace_stale: false,
+            ..UnixSocketOptions::default()
+        };
+        assert!(Listener::bind_with(&address, options).is_err());
+
+        drop(first);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn binds_tcp() {
+        let address = "tcp://127.0.0.1:0".parse::<net::Address>().unwrap();
+        let listener = Listener::bind(&address).unwrap();
+        let port = listener.local_tcp_addr().unwrap().port();
+
+        let _client = TcpStream::connect(("127.0.0.1", port)).unwrap();
+        assert!(matches!(
+            listener.accept().unwrap().into_inner(),
+            Connection::Tcp(_)
+        ));
+    }
+}
@@ -9,7 +9,7 @@ pub struct Log(String);
 impl Log {
     /// Parse the log data as JSON, returning a `serde_json::Value`
     pub fn parse_json(&self) -> Result<serde_json::Value, Error> {
-        serde_json::from_str(&self.0).map_err(|_| Kind::Parse.into())
+        serde_json::from_str(&self.0).map_err(|e| Kind::Parse.context(e).into())
     }
 }
 
@@ -19,6 +19,18 @@ impl From<&str> for Log {
     }
 }
 
+impl From<String> for Log {
+    fn from(s: String) -> Self {
+        Log(s)
+    }
+}
+
+impl From<Log> for String {
+    fn from(log: Log) -> Self {
+        log.0
+    }
+}
+
 impl AsRef<str> for Log {
     fn as_ref(&self) -> &str {
         self.0.as_ref()
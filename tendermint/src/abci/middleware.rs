@@ -0,0 +1,361 @@
+//! Middleware acting on every ABCI request an application handles, e.g. to
+//! log requests, record metrics or catch panics, without changing the
+//! application.
+//!
+//! Middleware implements [`Middleware`], whose [`call`](Middleware::call)
+//! passes requests on to the [`Next`] layer, and is stacked around an
+//! application with [`Layered`]: the last layer added sees requests first.
+//!
+//! ```
+//! use std::time::Duration;
+//! use tendermint::abci::{
+//!     application::Blocking,
+//!     kvstore::KeyValueStore,
+//!     middleware::{CatchPanic, Layered, Observe},
+//!     Server,
+//! };
+//!
+//! let app = Layered::new(Blocking::new(KeyValueStore::new()), CatchPanic)
+//!     .layer(Observe::new(|method: &str, _response: &_, elapsed: Duration| {
+//!         println!("{} took {:?}", method, elapsed)
+//!     }));
+//! let server = Server::new(app);
+//! ```
+//!
+//! Layers see the requests the [`Server`](super::Server) dispatches through
+//! [`Application::handle`]. ABCI++ requests, which have no `Request`
+//! variant, are passed straight to the application.
+
+#[cfg(feature = "abci-plus-plus")]
+use super::plus_plus::{
+    RequestExtendVote, RequestPrepareProposal, RequestProcessProposal, RequestVerifyVoteExtension,
+    ResponseExtendVote, ResponsePrepareProposal, ResponseProcessProposal,
+    ResponseVerifyVoteExtension,
+};
+use super::Application;
+use async_trait::async_trait;
+use futures::FutureExt;
+use std::{
+    any::Any,
+    panic::AssertUnwindSafe,
+    time::{Duration, Instant},
+};
+use tendermint_proto::abci::{
+    request, response, Request, RequestApplySnapshotChunk, RequestBeginBlock, RequestCheckTx,
+    RequestCommit, RequestDeliverTx, RequestEndBlock, RequestInfo, RequestInitChain,
+    RequestListSnapshots, RequestLoadSnapshotChunk, RequestOfferSnapshot, RequestQuery,
+    RequestSetOption, Response, ResponseApplySnapshotChunk, ResponseBeginBlock, ResponseCheckTx,
+    ResponseCommit, ResponseDeliverTx, ResponseEndBlock, ResponseException, ResponseInfo,
+    ResponseInitChain, ResponseListSnapshots, ResponseLoadSnapshotChunk, ResponseOfferSnapshot,
+    ResponseQuery, ResponseSetOption,
+};
+
+/// Middleware acting on the requests an application handles
+#[async_trait]
+pub trait Middleware: Send + Sync {
+    /// Handle a request, usually by passing it on to the next layer with
+    /// [`Next::run`]
+    async fn call(&self, request: Request, next: Next<'_>) -> Response;
+}
+
+/// Next layer of a stack of middleware, down to the application
+#[derive(Clone, Copy)]
+pub struct Next<'a> {
+    app: &'a dyn Application,
+}
+
+impl<'a> Next<'a> {
+    /// Pass the request on to the next layer, returning its response
+    pub async fn run(self, request: Request) -> Response {
+        self.app.handle(request).await
+    }
+}
+
+/// Application wrapped in a layer of middleware
+#[derive(Debug)]
+pub struct Layered<A, M> {
+    app: A,
+    middleware: M,
+}
+
+impl<A, M> Layered<A, M>
+where
+    A: Application,
+    M: Middleware,
+{
+    /// Wrap the given application in a layer of middleware
+    pub fn new(app: A, middleware: M) -> Self {
+        Layered { app, middleware }
+    }
+
+    /// Wrap this stack in another layer of middleware, which sees requests
+    /// before this one
+    pub fn layer<N: Middleware>(self, middleware: N) -> Layered<Self, N> {
+        Layered::new(self, middleware)
+    }
+
+    /// Get the wrapped application
+    pub fn app(&self) -> &A {
+        &self.app
+    }
+
+    /// Get the wrapped application back
+    pub fn into_inner(self) -> A {
+        self.app
+    }
+}
+
+/// Methods called directly, rather than through [`Application::handle`],
+/// skip this layer
+#[async_trait]
+impl<A, M> Application for Layered<A, M>
+where
+    A: Application,
+    M: Middleware,
+{
+    async fn info(&self, request: RequestInfo) -> ResponseInfo {
+        self.app.info(request).await
+    }
+
+    async fn set_option(&self, request: RequestSetOption) -> ResponseSetOption {
+        self.app.set_option(request).await
+    }
+
+    async fn init_chain(&self, request: RequestInitChain) -> ResponseInitChain {
+        self.app.init_chain(request).await
+    }
+
+    async fn query(&self, request: RequestQuery) -> ResponseQuery {
+        self.app.query(request).await
+    }
+
+    async fn check_tx(&self, request: RequestCheckTx) -> ResponseCheckTx {
+        self.app.check_tx(request).await
+    }
+
+    async fn begin_block(&self, request: RequestBeginBlock) -> ResponseBeginBlock {
+        self.app.begin_block(request).await
+    }
+
+    async fn deliver_tx(&self, request: RequestDeliverTx) -> ResponseDeliverTx {
+        self.app.deliver_tx(request).await
+    }
+
+    async fn end_block(&self, request: RequestEndBlock) -> ResponseEndBlock {
+        self.app.end_block(request).await
+    }
+
+    async fn commit(&self, request: RequestCommit) -> ResponseCommit {
+        self.app.commit(request).await
+    }
+
+    async fn list_snapshots(&self, request: RequestListSnapshots) -> ResponseListSnapshots {
+        self.app.list_snapshots(request).await
+    }
+
+    async fn offer_snapshot(&self, request: RequestOfferSnapshot) -> ResponseOfferSnapshot {
+        self.app.offer_snapshot(request).await
+    }
+
+    async fn load_snapshot_chunk(
+        &self,
+        request: RequestLoadSnapshotChunk,
+    ) -> ResponseLoadSnapshotChunk {
+        self.app.load_snapshot_chunk(request).await
+    }
+
+    async fn apply_snapshot_chunk(
+        &self,
+        request: RequestApplySnapshotChunk,
+    ) -> ResponseApplySnapshotChunk {
+        self.app.apply_snapshot_chunk(request).await
+    }
+
+    async fn handle(&self, request: Request) -> Response {
+        self.middleware.call(request, Next { app: &self.app }).await
+    }
+
+    #[cfg(feature = "abci-plus-plus")]
+    async fn prepare_proposal(&self, request: RequestPrepareProposal) -> ResponsePrepareProposal {
+        self.app.prepare_proposal(request).await
+    }
+
+    #[cfg(feature = "abci-plus-plus")]
+    async fn process_proposal(&self, request: RequestProcessProposal) -> ResponseProcessProposal {
+        self.app.process_proposal(request).await
+    }
+
+    #[cfg(feature = "abci-plus-plus")]
+    async fn extend_vote(&self, request: RequestExtendVote) -> ResponseExtendVote {
+        self.app.extend_vote(request).await
+    }
+
+    #[cfg(feature = "abci-plus-plus")]
+    async fn verify_vote_extension(
+        &self,
+        request: RequestVerifyVoteExtension,
+    ) -> ResponseVerifyVoteExtension {
+        self.app.verify_vote_extension(request).await
+    }
+}
+
+/// Middleware answering requests whose handling panics with an exception,
+/// with the panic's message, instead of unwinding through the server.
+///
+/// Tendermint closes the connection of an exception, halting the node
+/// rather than the whole process.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct CatchPanic;
+
+#[async_trait]
+impl Middleware for CatchPanic {
+    async fn call(&self, request: Request, next: Next<'_>) -> Response {
+        match AssertUnwindSafe(next.run(request)).catch_unwind().await {
+            Ok(response) => response,
+            Err(panic) => Response {
+                value: Some(response::Value::Exception(ResponseException {
+                    error: format!("application panicked: {}", panic_message(&*panic)),
+                })),
+            },
+        }
+    }
+}
+
+/// Middleware reporting each request's method, response and handling time
+/// to a callback, e.g. to log requests or record metrics
+#[derive(Clone, Debug)]
+pub struct Observe<F> {
+    observer: F,
+}
+
+impl<F> Observe<F>
+where
+    F: Fn(&str, &Response, Duration) + Send + Sync,
+{
+    /// Report requests to the given callback
+    pub fn new(observer: F) -> Self {
+        Observe { observer }
+    }
+}
+
+#[async_trait]
+impl<F> Middleware for Observe<F>
+where
+    F: Fn(&str, &Response, Duration) + Send + Sync,
+{
+    async fn call(&self, request: Request, next: Next<'_>) -> Response {
+        let method = request.value.as_ref().map_or("unknown", method);
+        let start = Instant::now();
+        let response = next.run(request).await;
+
+        (self.observer)(method, &response, start.elapsed());
+        response
+    }
+}
+
+/// Name of the method of the given request, as in Tendermint's metrics
+pub fn method(request: &request::Value) -> &'static str {
+    use request::Value::*;
+
+    match request {
+        Echo(_) => "echo",
+        Flush(_) => "flush",
+        Info(_) => "info",
+        SetOption(_) => "set_option",
+        InitChain(_) => "init_chain",
+        Query(_) => "query",
+        BeginBlock(_) => "begin_block",
+        CheckTx(_) => "check_tx",
+        DeliverTx(_) => "deliver_tx",
+        EndBlock(_) => "end_block",
+        Commit(_) => "commit",
+        ListSnapshots(_) => "list_snapshots",
+        OfferSnapshot(_) => "offer_snapshot",
+        LoadSnapshotChunk(_) => "load_snapshot_chunk",
+        ApplySnapshotChunk(_) => "apply_snapshot_chunk",
+    }
+}
+
+fn panic_message(panic: &(dyn Any + Send)) -> &str {
+    if let Some(message) = panic.downcast_ref::<&str>() {
+        message
+    } else if let Some(message) = panic.downcast_ref::<String>() {
+        message
+    } else {
+        "unknown panic"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abci::{application::Blocking, kvstore::KeyValueStore};
+    use futures::executor::block_on;
+    use std::sync::Mutex;
+
+    struct Panicking;
+
+    #[async_trait]
+    impl Application for Panicking {
+        async fn commit(&self, _request: RequestCommit) -> ResponseCommit {
+            panic!("out of disk space")
+        }
+    }
+
+    fn request(value: request::Value) -> Request {
+        Request { value: Some(value) }
+    }
+
+    #[test]
+    fn catches_panics() {
+        let app = Layered::new(Panicking, CatchPanic);
+
+        let response = block_on(app.handle(request(request::Value::Commit(RequestCommit {}))));
+        match response.value {
+            Some(response::Value::Exception(exception)) => {
+                assert_eq!(exception.error, "application panicked: out of disk space")
+            }
+            other => panic!("unexpected response: {:?}", other),
+        }
+
+        let response = block_on(app.handle(request(request::Value::Info(RequestInfo::default()))));
+        assert!(matches!(response.value, Some(response::Value::Info(_))));
+    }
+
+    #[test]
+    fn layers_see_requests_outermost_first() {
+        let observed = Mutex::new(vec![]);
+        let app = Layered::new(Panicking, CatchPanic).layer(Observe::new(
+            |method: &str, response: &Response, _elapsed: Duration| {
+                let exception = matches!(response.value, Some(response::Value::Exception(_)));
+                observed
+                    .lock()
+                    .unwrap()
+                    .push((method.to_owned(), exception));
+            },
+        ));
+
+        block_on(app.handle(request(request::Value::Commit(RequestCommit {}))));
+        block_on(app.handle(request(request::Value::Query(RequestQuery::default()))));
+
+        // The observer sees the exception the inner layer answered with
+        assert_eq!(
+            *observed.lock().unwrap(),
+            vec![("commit".to_owned(), true), ("query".to_owned(), false)]
+        );
+    }
+
+    #[test]
+    fn calls_skip_layers() {
+        let app = Layered::new(Blocking::new(KeyValueStore::new()), CatchPanic);
+        block_on(app.deliver_tx(RequestDeliverTx {
+            tx: b"name=satoshi".to_vec(),
+        }));
+        block_on(app.commit(RequestCommit {}));
+
+        assert_eq!(
+            app.into_inner().into_inner().get(b"name"),
+            Some(&b"satoshi"[..])
+        );
+    }
+}
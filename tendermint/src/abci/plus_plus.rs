@@ -0,0 +1,519 @@
+//! ABCI++ requests, with which applications of chains running Tendermint
+//! 0.38 (or CometBFT) take part in building and voting on blocks: preparing
+//! and processing proposals, and extending precommits and verifying their
+//! extensions.
+//!
+//! The Tendermint 0.34 protobuf definitions this crate is built from lack
+//! these requests, so they're encoded here with the field numbers of
+//! Tendermint 0.38's `Request` and `Response`, which no ABCI request or
+//! response uses:
+//!
+//! <https://github.com/cometbft/cometbft/blob/v0.38.0/proto/tendermint/abci/types.proto>
+//!
+//! Messages carrying an ABCI++ request are decoded with [`decode_request`],
+//! which leaves any other request to be decoded as an ABCI one.
+
+use super::Application;
+use crate::error::{Error, Kind};
+use prost::Message as _;
+use prost_types::Timestamp;
+use tendermint_proto::abci::Validator;
+
+/// Request to prepare the proposal of the next block, e.g. to reorder,
+/// drop or add transactions
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct RequestPrepareProposal {
+    /// Maximum total size of the proposed transactions
+    #[prost(int64, tag = "1")]
+    pub max_tx_bytes: i64,
+
+    /// Transactions from the mempool, which may exceed `max_tx_bytes`
+    #[prost(bytes, repeated, tag = "2")]
+    pub txs: Vec<Vec<u8>>,
+
+    /// Commit of the previous block, with the vote extensions of its
+    /// precommits
+    #[prost(message, optional, tag = "3")]
+    pub local_last_commit: Option<ExtendedCommitInfo>,
+
+    /// Misbehavior of validators to punish
+    #[prost(message, repeated, tag = "4")]
+    pub misbehavior: Vec<Misbehavior>,
+
+    /// Height of the proposed block
+    #[prost(int64, tag = "5")]
+    pub height: i64,
+
+    /// Time of the proposed block
+    #[prost(message, optional, tag = "6")]
+    pub time: Option<Timestamp>,
+
+    /// Hash of the validator set of the next block
+    #[prost(bytes, tag = "7")]
+    pub next_validators_hash: Vec<u8>,
+
+    /// Address of the proposer
+    #[prost(bytes, tag = "8")]
+    pub proposer_address: Vec<u8>,
+}
+
+/// Transactions of the prepared proposal
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ResponsePrepareProposal {
+    /// Proposed transactions, within `max_tx_bytes`
+    #[prost(bytes, repeated, tag = "1")]
+    pub txs: Vec<Vec<u8>>,
+}
+
+/// Request to check a proposed block, before voting on it
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct RequestProcessProposal {
+    /// Proposed transactions
+    #[prost(bytes, repeated, tag = "1")]
+    pub txs: Vec<Vec<u8>>,
+
+    /// Commit of the previous block
+    #[prost(message, optional, tag = "2")]
+    pub proposed_last_commit: Option<CommitInfo>,
+
+    /// Misbehavior of validators to punish
+    #[prost(message, repeated, tag = "3")]
+    pub misbehavior: Vec<Misbehavior>,
+
+    /// Hash of the proposed block
+    #[prost(bytes, tag = "4")]
+    pub hash: Vec<u8>,
+
+    /// Height of the proposed block
+    #[prost(int64, tag = "5")]
+    pub height: i64,
+
+    /// Time of the proposed block
+    #[prost(message, optional, tag = "6")]
+    pub time: Option<Timestamp>,
+
+    /// Hash of the validator set of the next block
+    #[prost(bytes, tag = "7")]
+    pub next_validators_hash: Vec<u8>,
+
+    /// Address of the proposer
+    #[prost(bytes, tag = "8")]
+    pub proposer_address: Vec<u8>,
+}
+
+/// Whether the proposed block is valid
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ResponseProcessProposal {
+    /// See [`ProposalStatus`]
+    #[prost(enumeration = "ProposalStatus", tag = "1")]
+    pub status: i32,
+}
+
+/// Status of a processed proposal
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+#[repr(i32)]
+pub enum ProposalStatus {
+    /// Unknown status: Tendermint panics
+    Unknown = 0,
+
+    /// The proposal is valid: prevote for it
+    Accept = 1,
+
+    /// The proposal is invalid: prevote nil
+    Reject = 2,
+}
+
+/// Request for the extension of a precommit for a block
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct RequestExtendVote {
+    /// Hash of the block
+    #[prost(bytes, tag = "1")]
+    pub hash: Vec<u8>,
+
+    /// Height of the block
+    #[prost(int64, tag = "2")]
+    pub height: i64,
+
+    /// Time of the block
+    #[prost(message, optional, tag = "3")]
+    pub time: Option<Timestamp>,
+
+    /// Transactions of the block
+    #[prost(bytes, repeated, tag = "4")]
+    pub txs: Vec<Vec<u8>>,
+
+    /// Commit of the previous block
+    #[prost(message, optional, tag = "5")]
+    pub proposed_last_commit: Option<CommitInfo>,
+
+    /// Misbehavior of validators to punish
+    #[prost(message, repeated, tag = "6")]
+    pub misbehavior: Vec<Misbehavior>,
+
+    /// Hash of the validator set of the next block
+    #[prost(bytes, tag = "7")]
+    pub next_validators_hash: Vec<u8>,
+
+    /// Address of the proposer
+    #[prost(bytes, tag = "8")]
+    pub proposer_address: Vec<u8>,
+}
+
+/// Extension of a precommit, which the validator signs along with it
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ResponseExtendVote {
+    /// Application-specific extension, possibly empty
+    #[prost(bytes, tag = "1")]
+    pub vote_extension: Vec<u8>,
+}
+
+/// Request to verify the extension of another validator's precommit
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct RequestVerifyVoteExtension {
+    /// Hash of the block
+    #[prost(bytes, tag = "1")]
+    pub hash: Vec<u8>,
+
+    /// Address of the validator
+    #[prost(bytes, tag = "2")]
+    pub validator_address: Vec<u8>,
+
+    /// Height of the block
+    #[prost(int64, tag = "3")]
+    pub height: i64,
+
+    /// Extension to verify
+    #[prost(bytes, tag = "4")]
+    pub vote_extension: Vec<u8>,
+}
+
+/// Whether a vote extension is valid
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ResponseVerifyVoteExtension {
+    /// See [`VerifyStatus`]
+    #[prost(enumeration = "VerifyStatus", tag = "1")]
+    pub status: i32,
+}
+
+/// Status of a verified vote extension
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+#[repr(i32)]
+pub enum VerifyStatus {
+    /// Unknown status: Tendermint panics
+    Unknown = 0,
+
+    /// The extension is valid
+    Accept = 1,
+
+    /// The extension is invalid: the precommit is rejected
+    Reject = 2,
+}
+
+/// Votes on the previous block
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct CommitInfo {
+    /// Round of the commit
+    #[prost(int32, tag = "1")]
+    pub round: i32,
+
+    /// Votes of the validators
+    #[prost(message, repeated, tag = "2")]
+    pub votes: Vec<VoteInfo>,
+}
+
+/// Vote of a validator on the previous block
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct VoteInfo {
+    /// Validator
+    #[prost(message, optional, tag = "1")]
+    pub validator: Option<Validator>,
+
+    /// See [`BlockIdFlag`]
+    #[prost(enumeration = "BlockIdFlag", tag = "3")]
+    pub block_id_flag: i32,
+}
+
+/// Votes on the previous block, with the extensions of their precommits
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ExtendedCommitInfo {
+    /// Round of the commit
+    #[prost(int32, tag = "1")]
+    pub round: i32,
+
+    /// Votes of the validators
+    #[prost(message, repeated, tag = "2")]
+    pub votes: Vec<ExtendedVoteInfo>,
+}
+
+/// Vote of a validator on the previous block, with its extension
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ExtendedVoteInfo {
+    /// Validator
+    #[prost(message, optional, tag = "1")]
+    pub validator: Option<Validator>,
+
+    /// Extension of the precommit
+    #[prost(bytes, tag = "3")]
+    pub vote_extension: Vec<u8>,
+
+    /// Signature of the extension
+    #[prost(bytes, tag = "4")]
+    pub extension_signature: Vec<u8>,
+
+    /// See [`BlockIdFlag`]
+    #[prost(enumeration = "BlockIdFlag", tag = "5")]
+    pub block_id_flag: i32,
+}
+
+/// Whether and for what a validator voted
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+#[repr(i32)]
+pub enum BlockIdFlag {
+    /// Unknown
+    Unknown = 0,
+
+    /// The validator didn't vote
+    Absent = 1,
+
+    /// The validator voted for the block
+    Commit = 2,
+
+    /// The validator voted nil
+    Nil = 3,
+}
+
+/// Misbehavior of a validator
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Misbehavior {
+    /// See [`MisbehaviorType`]
+    #[prost(enumeration = "MisbehaviorType", tag = "1")]
+    pub r#type: i32,
+
+    /// Misbehaving validator
+    #[prost(message, optional, tag = "2")]
+    pub validator: Option<Validator>,
+
+    /// Height of the misbehavior
+    #[prost(int64, tag = "3")]
+    pub height: i64,
+
+    /// Time of the block at the height of the misbehavior
+    #[prost(message, optional, tag = "4")]
+    pub time: Option<Timestamp>,
+
+    /// Total voting power of the validator set at the height of the
+    /// misbehavior
+    #[prost(int64, tag = "5")]
+    pub total_voting_power: i64,
+}
+
+/// Type of misbehavior
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+#[repr(i32)]
+pub enum MisbehaviorType {
+    /// Unknown
+    Unknown = 0,
+
+    /// Signing conflicting votes
+    DuplicateVote = 1,
+
+    /// Attacking light clients
+    LightClientAttack = 2,
+}
+
+/// ABCI++ request, as a field of Tendermint 0.38's `Request` oneof
+#[derive(Clone, PartialEq, prost::Oneof)]
+pub enum Request {
+    /// Prepare a proposal
+    #[prost(message, tag = "16")]
+    PrepareProposal(RequestPrepareProposal),
+
+    /// Process a proposal
+    #[prost(message, tag = "17")]
+    ProcessProposal(RequestProcessProposal),
+
+    /// Extend a precommit
+    #[prost(message, tag = "18")]
+    ExtendVote(RequestExtendVote),
+
+    /// Verify a precommit's extension
+    #[prost(message, tag = "19")]
+    VerifyVoteExtension(RequestVerifyVoteExtension),
+}
+
+/// ABCI++ response, as a field of Tendermint 0.38's `Response` oneof
+#[derive(Clone, PartialEq, prost::Oneof)]
+pub enum Response {
+    /// Prepared proposal
+    #[prost(message, tag = "17")]
+    PrepareProposal(ResponsePrepareProposal),
+
+    /// Processed proposal
+    #[prost(message, tag = "18")]
+    ProcessProposal(ResponseProcessProposal),
+
+    /// Precommit extension
+    #[prost(message, tag = "19")]
+    ExtendVote(ResponseExtendVote),
+
+    /// Verified precommit extension
+    #[prost(message, tag = "20")]
+    VerifyVoteExtension(ResponseVerifyVoteExtension),
+}
+
+/// The ABCI++ fields of a `Request`
+#[derive(Clone, PartialEq, prost::Message)]
+struct RawRequest {
+    #[prost(oneof = "Request", tags = "16, 17, 18, 19")]
+    value: Option<Request>,
+}
+
+/// The ABCI++ fields of a `Response`
+#[derive(Clone, PartialEq, prost::Message)]
+struct RawResponse {
+    #[prost(oneof = "Response", tags = "17, 18, 19, 20")]
+    value: Option<Response>,
+}
+
+/// Decode an encoded `Request`, returning `None` if it isn't an ABCI++
+/// request, so that it can be decoded as an ABCI one
+pub fn decode_request(bytes: &[u8]) -> Result<Option<Request>, Error> {
+    RawRequest::decode(bytes)
+        .map(|raw| raw.value)
+        .map_err(|e| Kind::Parse.context(e).into())
+}
+
+/// Encode an ABCI++ request as a `Request`
+pub fn encode_request(request: Request) -> Vec<u8> {
+    encode(RawRequest {
+        value: Some(request),
+    })
+}
+
+/// Decode an encoded `Response`, returning `None` if it isn't an ABCI++
+/// response, so that it can be decoded as an ABCI one
+pub fn decode_response(bytes: &[u8]) -> Result<Option<Response>, Error> {
+    RawResponse::decode(bytes)
+        .map(|raw| raw.value)
+        .map_err(|e| Kind::Parse.context(e).into())
+}
+
+/// Encode an ABCI++ response as a `Response`
+pub fn encode_response(response: Response) -> Vec<u8> {
+    encode(RawResponse {
+        value: Some(response),
+    })
+}
+
+fn encode(message: impl prost::Message) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(message.encoded_len());
+    message.encode(&mut bytes).expect("buffer is large enough");
+    bytes
+}
+
+/// Handle an ABCI++ request from Tendermint with the given application,
+/// returning the response
+pub async fn handle_request<A>(app: &A, request: Request) -> Response
+where
+    A: Application + ?Sized,
+{
+    match request {
+        Request::PrepareProposal(request) => {
+            Response::PrepareProposal(app.prepare_proposal(request).await)
+        }
+        Request::ProcessProposal(request) => {
+            Response::ProcessProposal(app.process_proposal(request).await)
+        }
+        Request::ExtendVote(request) => Response::ExtendVote(app.extend_vote(request).await),
+        Request::VerifyVoteExtension(request) => {
+            Response::VerifyVoteExtension(app.verify_vote_extension(request).await)
+        }
+    }
+}
+
+/// Propose the given transactions in order, up to the maximum total size,
+/// as Go's `BaseApplication` does
+pub(super) fn default_prepare_proposal(request: RequestPrepareProposal) -> ResponsePrepareProposal {
+    let max_tx_bytes = request.max_tx_bytes;
+    let mut total_bytes = 0i64;
+    let txs = request
+        .txs
+        .into_iter()
+        .take_while(|tx| {
+            total_bytes = total_bytes.saturating_add(tx.len() as i64);
+            total_bytes <= max_tx_bytes
+        })
+        .collect();
+
+    ResponsePrepareProposal { txs }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tendermint_proto::abci::{request, Request as AbciRequest, RequestEcho};
+
+    #[test]
+    fn round_trips_requests() {
+        let request = Request::VerifyVoteExtension(RequestVerifyVoteExtension {
+            hash: vec![1; 32],
+            validator_address: vec![2; 20],
+            height: 7,
+            vote_extension: b"price=42".to_vec(),
+        });
+
+        let bytes = encode_request(request.clone());
+        assert_eq!(decode_request(&bytes).unwrap(), Some(request));
+
+        // Tendermint 0.34 peers see an empty request
+        assert_eq!(AbciRequest::decode(bytes.as_slice()).unwrap().value, None);
+    }
+
+    #[test]
+    fn leaves_abci_requests() {
+        let echo = AbciRequest {
+            value: Some(request::Value::Echo(RequestEcho {
+                message: "hello".to_owned(),
+            })),
+        };
+
+        assert_eq!(decode_request(&encode(echo)).unwrap(), None);
+    }
+
+    #[test]
+    fn handles_requests_by_default() {
+        use futures::executor::block_on;
+
+        struct Empty;
+        impl Application for Empty {}
+
+        let prepare = Request::PrepareProposal(RequestPrepareProposal {
+            max_tx_bytes: 5,
+            txs: vec![b"abc".to_vec(), b"de".to_vec(), b"f".to_vec()],
+            ..RequestPrepareProposal::default()
+        });
+        assert_eq!(
+            block_on(handle_request(&Empty, prepare)),
+            Response::PrepareProposal(ResponsePrepareProposal {
+                txs: vec![b"abc".to_vec(), b"de".to_vec()]
+            })
+        );
+
+        let process = Request::ProcessProposal(RequestProcessProposal::default());
+        assert_eq!(
+            block_on(handle_request(&Empty, process)),
+            Response::ProcessProposal(ResponseProcessProposal {
+                status: ProposalStatus::Accept as i32
+            })
+        );
+
+        let response = encode_response(block_on(handle_request(
+            &Empty,
+            Request::ExtendVote(RequestExtendVote::default()),
+        )));
+        assert_eq!(
+            decode_response(&response).unwrap(),
+            Some(Response::ExtendVote(ResponseExtendVote::default()))
+        );
+    }
+}
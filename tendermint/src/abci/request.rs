@@ -0,0 +1,219 @@
+//! ABCI requests Tendermint sends to applications, as domain types converting
+//! from and to their protobuf counterparts.
+
+use super::{path::Path, transaction::Transaction};
+use crate::{
+    block,
+    error::{Error, Kind},
+};
+use anomaly::fail;
+use std::convert::{TryFrom, TryInto};
+use tendermint_proto::{
+    abci::{
+        CheckTxType as RawCheckTxType, RequestCheckTx as RawRequestCheckTx,
+        RequestDeliverTx as RawRequestDeliverTx, RequestQuery as RawRequestQuery,
+    },
+    DomainType,
+};
+
+/// Check TX request, asking whether a transaction may enter the mempool.
+///
+/// This type corresponds to the `RequestCheckTx` proto.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CheckTx {
+    /// Transaction to check
+    pub tx: Transaction,
+
+    /// Whether the transaction is new, or rechecked after a block was
+    /// committed
+    pub kind: CheckTxKind,
+}
+
+impl DomainType<RawRequestCheckTx> for CheckTx {}
+
+impl TryFrom<RawRequestCheckTx> for CheckTx {
+    type Error = Error;
+
+    fn try_from(value: RawRequestCheckTx) -> Result<Self, Self::Error> {
+        Ok(CheckTx {
+            tx: Transaction::new(value.tx),
+            kind: CheckTxKind::try_from(value.r#type)?,
+        })
+    }
+}
+
+impl From<CheckTx> for RawRequestCheckTx {
+    fn from(value: CheckTx) -> Self {
+        RawRequestCheckTx {
+            tx: value.tx.into_vec(),
+            r#type: value.kind.into(),
+        }
+    }
+}
+
+/// Why a transaction is checked.
+///
+/// This type corresponds to the `CheckTxType` proto enum.
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub enum CheckTxKind {
+    /// The transaction is new to the mempool: it's checked in full
+    New,
+
+    /// The transaction stayed in the mempool while a block was committed: it
+    /// is checked against the new state, e.g. only its nonce and balance
+    Recheck,
+}
+
+impl Default for CheckTxKind {
+    fn default() -> Self {
+        CheckTxKind::New
+    }
+}
+
+impl TryFrom<i32> for CheckTxKind {
+    type Error = Error;
+
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
+        match RawCheckTxType::from_i32(value) {
+            Some(RawCheckTxType::New) => Ok(CheckTxKind::New),
+            Some(RawCheckTxType::Recheck) => Ok(CheckTxKind::Recheck),
+            None => fail!(Kind::Parse, "unknown check tx type: {}", value),
+        }
+    }
+}
+
+impl From<CheckTxKind> for i32 {
+    fn from(value: CheckTxKind) -> Self {
+        let raw = match value {
+            CheckTxKind::New => RawCheckTxType::New,
+            CheckTxKind::Recheck => RawCheckTxType::Recheck,
+        };
+
+        raw as i32
+    }
+}
+
+/// Deliver TX request, executing a transaction of the current block.
+///
+/// This type corresponds to the `RequestDeliverTx` proto.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DeliverTx {
+    /// Transaction to execute
+    pub tx: Transaction,
+}
+
+impl DomainType<RawRequestDeliverTx> for DeliverTx {}
+
+impl TryFrom<RawRequestDeliverTx> for DeliverTx {
+    type Error = Error;
+
+    fn try_from(value: RawRequestDeliverTx) -> Result<Self, Self::Error> {
+        Ok(DeliverTx {
+            tx: Transaction::new(value.tx),
+        })
+    }
+}
+
+impl From<DeliverTx> for RawRequestDeliverTx {
+    fn from(value: DeliverTx) -> Self {
+        RawRequestDeliverTx {
+            tx: value.tx.into_vec(),
+        }
+    }
+}
+
+/// Query request, reading the application's state.
+///
+/// This type corresponds to the `RequestQuery` proto.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Query {
+    /// Query data, e.g. the key to look up
+    pub data: Vec<u8>,
+
+    /// Path of the queried data
+    pub path: Path,
+
+    /// Height of the queried state, or `None` for the latest one
+    pub height: Option<block::Height>,
+
+    /// Whether to return a proof of the result
+    pub prove: bool,
+}
+
+impl DomainType<RawRequestQuery> for Query {}
+
+impl TryFrom<RawRequestQuery> for Query {
+    type Error = Error;
+
+    fn try_from(value: RawRequestQuery) -> Result<Self, Self::Error> {
+        // Height 0 queries the latest state
+        let height = match value.height {
+            0 => None,
+            height => Some(height.try_into()?),
+        };
+
+        Ok(Query {
+            data: value.data,
+            path: value.path.parse()?,
+            height,
+            prove: value.prove,
+        })
+    }
+}
+
+impl From<Query> for RawRequestQuery {
+    fn from(value: Query) -> Self {
+        RawRequestQuery {
+            data: value.data,
+            path: value.path.to_string(),
+            height: value.height.map(Into::into).unwrap_or_default(),
+            prove: value.prove,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn query_round_trip() {
+        let raw = RawRequestQuery {
+            data: b"name".to_vec(),
+            path: "/store/key".to_owned(),
+            height: 0,
+            prove: true,
+        };
+
+        let query = Query::try_from(raw.clone()).unwrap();
+        assert_eq!(query.path.to_string(), "/store/key");
+        assert_eq!(query.height, None);
+        assert_eq!(RawRequestQuery::from(query), raw);
+
+        let at_height = Query::try_from(RawRequestQuery {
+            height: 7,
+            ..raw.clone()
+        })
+        .unwrap();
+        assert_eq!(at_height.height, Some(block::Height::from(7u64)));
+
+        assert!(Query::try_from(RawRequestQuery { height: -1, ..raw }).is_err());
+    }
+
+    #[test]
+    fn encodes_check_tx() {
+        let check_tx = CheckTx {
+            tx: Transaction::new(b"name=satoshi".to_vec()),
+            kind: CheckTxKind::Recheck,
+        };
+
+        let bytes = check_tx.encode_vec().unwrap();
+        assert_eq!(CheckTx::decode_vec(&bytes).unwrap(), check_tx);
+
+        let unknown_type = RawRequestCheckTx {
+            r#type: 2,
+            ..RawRequestCheckTx::from(check_tx)
+        };
+        assert!(CheckTx::try_from(unknown_type).is_err());
+    }
+}
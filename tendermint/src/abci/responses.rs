@@ -1,9 +1,31 @@
 //! ABCI response types used by the `/block_results` RPC endpoint.
+//!
+//! The responses applications return to `CheckTx` and `DeliverTx` requests
+//! convert from and to their protobuf counterparts, checking their gas
+//! amounts and the UTF-8 encoding of their event attributes.
 
-use super::{code::Code, data::Data, gas::Gas, info::Info, log::Log, tag::Tag};
-use crate::{consensus, serializers, validator};
+pub use super::codespace::Codespace;
+use super::{
+    code::{Code, CodeTable, QualifiedCode},
+    data::Data,
+    gas::Gas,
+    info::Info,
+    log::Log,
+    tag::Tag,
+};
+use crate::{consensus, serializers, validator, Error};
 use serde::{Deserialize, Deserializer, Serialize};
-use std::fmt::{self, Display};
+use std::{
+    convert::{TryFrom, TryInto},
+    fmt::Display,
+};
+use tendermint_proto::{
+    abci::{
+        Event as RawEvent, ResponseCheckTx as RawResponseCheckTx,
+        ResponseDeliverTx as RawResponseDeliverTx,
+    },
+    DomainType,
+};
 
 /// Responses for ABCI calls which occur during block processing.
 ///
@@ -44,7 +66,7 @@ where
 ///
 /// <https://github.com/tendermint/tendermint/blob/master/abci/types/types.proto>
 // TODO(tarcieri): generate this automatically from the proto
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
 pub struct DeliverTx {
     /// ABCI application response code
     pub code: Code,
@@ -74,8 +96,194 @@ pub struct DeliverTx {
     pub codespace: Codespace,
 }
 
-/// Event
-#[derive(Clone, Debug, Serialize, Deserialize)]
+impl DeliverTx {
+    /// Response to a transaction which failed with the given error, reported
+    /// with its code in the given table and its message as the log
+    pub fn from_error<E: Display>(codes: &CodeTable<E>, error: &E) -> Self {
+        let code = codes.report(error);
+
+        DeliverTx {
+            code: code.code,
+            codespace: code.codespace,
+            log: error.to_string().into(),
+            ..DeliverTx::default()
+        }
+    }
+
+    /// Get the response code qualified by its codespace
+    pub fn qualified_code(&self) -> QualifiedCode {
+        self.code.in_codespace(self.codespace.clone())
+    }
+
+    /// Iterate over the events of the given type
+    pub fn events_of_type<'a>(&'a self, type_str: &'a str) -> impl Iterator<Item = &'a Event> {
+        self.events
+            .iter()
+            .filter(move |event| event.type_str == type_str)
+    }
+}
+
+impl DomainType<RawResponseDeliverTx> for DeliverTx {}
+
+impl TryFrom<RawResponseDeliverTx> for DeliverTx {
+    type Error = Error;
+
+    fn try_from(value: RawResponseDeliverTx) -> Result<Self, Self::Error> {
+        Ok(DeliverTx {
+            code: value.code.into(),
+            data: value.data.into(),
+            log: value.log.into(),
+            info: value.info.into(),
+            gas_wanted: value.gas_wanted.try_into()?,
+            gas_used: value.gas_used.try_into()?,
+            events: try_from_raw_events(value.events)?,
+            codespace: value.codespace.into(),
+        })
+    }
+}
+
+impl From<DeliverTx> for RawResponseDeliverTx {
+    fn from(value: DeliverTx) -> Self {
+        RawResponseDeliverTx {
+            code: value.code.into(),
+            data: value.data.into(),
+            log: value.log.into(),
+            info: value.info.into(),
+            gas_wanted: raw_gas(value.gas_wanted),
+            gas_used: raw_gas(value.gas_used),
+            events: value.events.into_iter().map(Into::into).collect(),
+            codespace: value.codespace.as_str().to_owned(),
+        }
+    }
+}
+
+/// Check TX response, which tells whether a transaction may enter the
+/// mempool.
+///
+/// This type corresponds to the `ResponseCheckTx` proto.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct CheckTx {
+    /// ABCI application response code: transactions are only added to the
+    /// mempool if it's OK
+    pub code: Code,
+
+    /// ABCI application data
+    pub data: Data,
+
+    /// ABCI log data (nondeterministic)
+    pub log: Log,
+
+    /// ABCI info (nondeterministic)
+    pub info: Info,
+
+    /// Amount of gas wanted
+    pub gas_wanted: Gas,
+
+    /// Amount of gas used
+    pub gas_used: Gas,
+
+    /// Events
+    pub events: Vec<Event>,
+
+    /// Codespace
+    pub codespace: Codespace,
+
+    /// Application-defined sender of the transaction, e.g. to limit the
+    /// transactions each sender has in the mempool
+    pub sender: String,
+
+    /// Priority of the transaction in prioritized mempools, e.g. its fee:
+    /// higher priority transactions are proposed first
+    pub priority: i64,
+
+    /// Why the mempool refused the transaction, set by Tendermint rather
+    /// than the application
+    pub mempool_error: String,
+}
+
+impl CheckTx {
+    /// Response rejecting a transaction with the given error, reported with
+    /// its code in the given table and its message as the log
+    pub fn from_error<E: Display>(codes: &CodeTable<E>, error: &E) -> Self {
+        let code = codes.report(error);
+
+        CheckTx {
+            code: code.code,
+            codespace: code.codespace,
+            log: error.to_string().into(),
+            ..CheckTx::default()
+        }
+    }
+
+    /// Get the response code qualified by its codespace
+    pub fn qualified_code(&self) -> QualifiedCode {
+        self.code.in_codespace(self.codespace.clone())
+    }
+}
+
+impl DomainType<RawResponseCheckTx> for CheckTx {}
+
+impl TryFrom<RawResponseCheckTx> for CheckTx {
+    type Error = Error;
+
+    fn try_from(value: RawResponseCheckTx) -> Result<Self, Self::Error> {
+        Ok(CheckTx {
+            code: value.code.into(),
+            data: value.data.into(),
+            log: value.log.into(),
+            info: value.info.into(),
+            gas_wanted: value.gas_wanted.try_into()?,
+            gas_used: value.gas_used.try_into()?,
+            events: try_from_raw_events(value.events)?,
+            codespace: value.codespace.into(),
+            sender: value.sender,
+            priority: value.priority,
+            mempool_error: value.mempool_error,
+        })
+    }
+}
+
+impl From<CheckTx> for RawResponseCheckTx {
+    fn from(value: CheckTx) -> Self {
+        RawResponseCheckTx {
+            code: value.code.into(),
+            data: value.data.into(),
+            log: value.log.into(),
+            info: value.info.into(),
+            gas_wanted: raw_gas(value.gas_wanted),
+            gas_used: raw_gas(value.gas_used),
+            events: value.events.into_iter().map(Into::into).collect(),
+            codespace: value.codespace.as_str().to_owned(),
+            sender: value.sender,
+            priority: value.priority,
+            mempool_error: value.mempool_error,
+        }
+    }
+}
+
+fn try_from_raw_events(events: Vec<RawEvent>) -> Result<Vec<Event>, Error> {
+    events.into_iter().map(TryInto::try_into).collect()
+}
+
+/// Gas amounts are `int64` in protobuf: amounts beyond its range saturate
+fn raw_gas(gas: Gas) -> i64 {
+    i64::try_from(gas).unwrap_or(i64::MAX)
+}
+
+/// Event, built by applications with [`Event::new`] and [`Event::attr`]:
+///
+/// ```
+/// use tendermint::abci::Event;
+///
+/// let event = Event::new("transfer")
+///     .attr("sender", "cosmos1sender")
+///     .index(true)
+///     .attr("amount", "100stake");
+///
+/// assert_eq!(event.first_value_of("sender"), Some("cosmos1sender"));
+/// assert!(event.attributes[0].index);
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub struct Event {
     /// Event type
     #[serde(rename = "type")]
@@ -85,6 +293,70 @@ pub struct Event {
     pub attributes: Vec<Tag>,
 }
 
+impl Event {
+    /// Create an event of the given type, without attributes
+    pub fn new(type_str: impl Into<String>) -> Self {
+        Event {
+            type_str: type_str.into(),
+            attributes: vec![],
+        }
+    }
+
+    /// Add an unindexed attribute
+    pub fn attr(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.attributes.push(Tag::new(key, value));
+        self
+    }
+
+    /// Set whether the attribute added last is indexed by the node, i.e.
+    /// whether transactions can be searched by it.
+    ///
+    /// Leaves an event without attributes unchanged.
+    pub fn index(mut self, index: bool) -> Self {
+        if let Some(tag) = self.attributes.last_mut() {
+            tag.index = index;
+        }
+        self
+    }
+
+    /// Iterate over the values of the attributes with the given key
+    pub fn values_of<'a>(&'a self, key: &'a str) -> impl Iterator<Item = &'a str> {
+        self.attributes
+            .iter()
+            .filter(move |tag| tag.key.as_ref() == key)
+            .map(|tag| tag.value.as_ref())
+    }
+
+    /// Get the value of the first attribute with the given key
+    pub fn first_value_of(&self, key: &str) -> Option<&str> {
+        self.values_of(key).next()
+    }
+}
+
+impl TryFrom<RawEvent> for Event {
+    type Error = Error;
+
+    fn try_from(value: RawEvent) -> Result<Self, Self::Error> {
+        Ok(Event {
+            type_str: value.r#type,
+            attributes: value
+                .attributes
+                .into_iter()
+                .map(TryInto::try_into)
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+impl From<Event> for RawEvent {
+    fn from(value: Event) -> Self {
+        RawEvent {
+            r#type: value.type_str,
+            attributes: value.attributes.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
 /// Begin block response.
 ///
 /// This type corresponds to the `ResponseBeginBlock` proto from:
@@ -128,18 +400,139 @@ where
     Ok(Option::deserialize(deserializer)?.unwrap_or_default())
 }
 
-/// Codespace
-#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
-pub struct Codespace(String);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tendermint_proto::abci::EventAttribute as RawEventAttribute;
 
-impl AsRef<str> for Codespace {
-    fn as_ref(&self) -> &str {
-        self.0.as_ref()
+    fn raw_deliver_tx() -> RawResponseDeliverTx {
+        RawResponseDeliverTx {
+            code: 2,
+            data: b"data".to_vec(),
+            log: "log".to_owned(),
+            info: "info".to_owned(),
+            gas_wanted: 10,
+            gas_used: 5,
+            events: vec![RawEvent {
+                r#type: "transfer".to_owned(),
+                attributes: vec![RawEventAttribute {
+                    key: b"sender".to_vec(),
+                    value: b"alice".to_vec(),
+                    index: true,
+                }],
+            }],
+            codespace: "bank".to_owned(),
+        }
     }
-}
 
-impl Display for Codespace {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.0)
+    #[test]
+    fn deliver_tx_round_trip() {
+        let deliver_tx = DeliverTx::try_from(raw_deliver_tx()).unwrap();
+        assert_eq!(deliver_tx.gas_used.value(), 5);
+        assert_eq!(deliver_tx.events[0].type_str, "transfer");
+        assert_eq!(deliver_tx.events[0].attributes[0].value.as_ref(), "alice");
+        assert_eq!(deliver_tx.qualified_code().to_string(), "bank:2");
+        assert_eq!(RawResponseDeliverTx::from(deliver_tx), raw_deliver_tx());
+    }
+
+    #[test]
+    fn check_tx_round_trip() {
+        let raw = RawResponseCheckTx {
+            gas_wanted: 10,
+            sender: "alice".to_owned(),
+            priority: 42,
+            ..RawResponseCheckTx::default()
+        };
+
+        let check_tx = CheckTx::try_from(raw.clone()).unwrap();
+        assert_eq!((check_tx.sender.as_str(), check_tx.priority), ("alice", 42));
+        assert_eq!(RawResponseCheckTx::from(check_tx), raw);
+    }
+
+    #[test]
+    fn builds_events() {
+        let event = Event::new("transfer")
+            .attr("sender", "alice")
+            .index(true)
+            .attr("amount", String::from("100stake"));
+
+        let deliver_tx = RawResponseDeliverTx {
+            events: vec![event.clone().into()],
+            ..raw_deliver_tx()
+        };
+        assert_eq!(deliver_tx.events[0].r#type, "transfer");
+        assert_eq!(
+            deliver_tx.events[0].attributes,
+            [
+                RawEventAttribute {
+                    key: b"sender".to_vec(),
+                    value: b"alice".to_vec(),
+                    index: true,
+                },
+                RawEventAttribute {
+                    key: b"amount".to_vec(),
+                    value: b"100stake".to_vec(),
+                    index: false,
+                }
+            ]
+        );
+
+        // Attributes are Base64-encoded in JSON
+        assert_eq!(
+            serde_json::to_value(&event).unwrap()["attributes"][0],
+            serde_json::json!({"key": "c2VuZGVy", "value": "YWxpY2U=", "index": true})
+        );
+    }
+
+    #[test]
+    fn indexing_without_attributes_is_a_no_op() {
+        assert_eq!(Event::new("transfer").index(true), Event::new("transfer"));
+    }
+
+    #[test]
+    fn reports_errors() {
+        #[derive(Clone, Debug)]
+        enum BankError {
+            InsufficientFunds { needed: u64 },
+        }
+
+        impl Display for BankError {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                match self {
+                    BankError::InsufficientFunds { needed } => {
+                        write!(f, "insufficient funds: {} needed", needed)
+                    }
+                }
+            }
+        }
+
+        let codes = CodeTable::new().with("bank", 2, BankError::InsufficientFunds { needed: 0 });
+        let error = BankError::InsufficientFunds { needed: 100 };
+
+        let deliver_tx = DeliverTx::from_error(&codes, &error);
+        assert_eq!(deliver_tx.qualified_code().to_string(), "bank:2");
+        assert_eq!(deliver_tx.log.as_ref(), "insufficient funds: 100 needed");
+
+        let check_tx = RawResponseCheckTx::from(CheckTx::from_error(&codes, &error));
+        assert_eq!((check_tx.code, check_tx.codespace.as_str()), (2, "bank"));
+
+        // Responses map back to the errors of their codes
+        assert!(matches!(
+            deliver_tx.qualified_code().to_error(&codes),
+            Some(BankError::InsufficientFunds { .. })
+        ));
+    }
+
+    #[test]
+    fn rejects_invalid_responses() {
+        let negative_gas = RawResponseDeliverTx {
+            gas_wanted: -1,
+            ..raw_deliver_tx()
+        };
+        assert!(DeliverTx::try_from(negative_gas).is_err());
+
+        let mut invalid_utf8 = raw_deliver_tx();
+        invalid_utf8.events[0].attributes[0].value = vec![0xff];
+        assert!(DeliverTx::try_from(invalid_utf8).is_err());
     }
 }
@@ -0,0 +1,779 @@
+//! ABCI server, serving an [`Application`] to Tendermint over its socket
+//! connections.
+//!
+//! Tendermint opens four connections to its application, each carrying one
+//! type of requests (see [`ConnectionType`]). Like Go's socket server, the
+//! [`Server`] handles the requests of each connection one at a time, under a
+//! lock shared by every connection, unless configured otherwise: each
+//! connection type can be given its own [`Concurrency`], e.g. to check
+//! transactions in parallel while blocks are still executed one request at a
+//! time.
+//!
+//! Connections on a TCP or UNIX domain socket can be accepted and served
+//! with a [`Listener`](super::listener::Listener), each on a thread of its
+//! own. Otherwise accepting connections is left to the caller's runtime: the
+//! server serves each accepted connection with [`Server::serve_connection`],
+//! whose futures can run concurrently, e.g. in tasks sharing the server
+//! through an `Arc`.
+//!
+//! Connections only read requests as fast as they answer them: parallel
+//! connections handle at most [`Config::pipelining_depth`] requests at once,
+//! and stop reading while their responses can't be written. A server
+//! [shut down](Server::shutdown) stops reading requests, and closes its
+//! connections once their outstanding responses are written.
+
+#[cfg(feature = "abci-plus-plus")]
+use super::plus_plus;
+use super::{codec, Application};
+use crate::{error::Kind, Error};
+use futures::{
+    future::{self, BoxFuture},
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufWriter},
+    lock::{Mutex, MutexGuard},
+    stream::{self, FuturesOrdered, StreamExt},
+};
+use prost::Message;
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex as StdMutex,
+    },
+    task::{Context, Poll, Waker},
+};
+use tendermint_proto::abci::{request, response, Request, Response};
+
+/// Type of the connections Tendermint opens to its application, after the
+/// requests they carry
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub enum ConnectionType {
+    /// Executes blocks: `InitChain`, `BeginBlock`, `DeliverTx`, `EndBlock`
+    /// and `Commit` (and the ABCI++ requests)
+    Consensus,
+
+    /// Checks transactions before they enter the mempool: `CheckTx`
+    Mempool,
+
+    /// Queries the application: `Info`, `SetOption` and `Query`
+    Info,
+
+    /// Serves and restores snapshots: the state sync requests
+    Snapshot,
+}
+
+impl ConnectionType {
+    /// Get the type of the connection carrying the given request, if it's
+    /// specific to one: echo and flush requests are sent on every connection
+    pub fn of(request: &request::Value) -> Option<Self> {
+        use request::Value as Req;
+
+        match request {
+            Req::Echo(_) | Req::Flush(_) => None,
+            Req::Info(_) | Req::SetOption(_) | Req::Query(_) => Some(ConnectionType::Info),
+            Req::CheckTx(_) => Some(ConnectionType::Mempool),
+            Req::InitChain(_)
+            | Req::BeginBlock(_)
+            | Req::DeliverTx(_)
+            | Req::EndBlock(_)
+            | Req::Commit(_) => Some(ConnectionType::Consensus),
+            Req::ListSnapshots(_)
+            | Req::OfferSnapshot(_)
+            | Req::LoadSnapshotChunk(_)
+            | Req::ApplySnapshotChunk(_) => Some(ConnectionType::Snapshot),
+        }
+    }
+}
+
+/// How the requests of a connection are handled
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Concurrency {
+    /// One request at a time, under a lock shared by every exclusive
+    /// connection, as Go's socket server handles all requests
+    Exclusive,
+
+    /// One request at a time, concurrently with the requests of other
+    /// connections
+    Sequential,
+
+    /// Several requests at a time, concurrently with the requests of other
+    /// connections. Responses are still sent in the order of the requests.
+    Parallel,
+}
+
+/// Concurrency of each connection type, and limits of the requests and
+/// responses of a connection
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Config {
+    /// Concurrency of the consensus connection
+    pub consensus: Concurrency,
+
+    /// Concurrency of the mempool connection
+    pub mempool: Concurrency,
+
+    /// Concurrency of the info connection
+    pub info: Concurrency,
+
+    /// Concurrency of the snapshot connection
+    pub snapshot: Concurrency,
+
+    /// Maximum number of requests of a parallel connection handled at once:
+    /// reading stops at this depth until the first of them is answered
+    pub pipelining_depth: usize,
+
+    /// Size in bytes of the buffer of the responses of a connection, which
+    /// are written out once it's full, even before Tendermint flushes them
+    pub response_buffer_size: usize,
+}
+
+impl Config {
+    /// Get the concurrency of the given connection type
+    pub fn concurrency(&self, connection: ConnectionType) -> Concurrency {
+        match connection {
+            ConnectionType::Consensus => self.consensus,
+            ConnectionType::Mempool => self.mempool,
+            ConnectionType::Info => self.info,
+            ConnectionType::Snapshot => self.snapshot,
+        }
+    }
+}
+
+/// Every connection is exclusive, as in Go's socket server
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            consensus: Concurrency::Exclusive,
+            mempool: Concurrency::Exclusive,
+            info: Concurrency::Exclusive,
+            snapshot: Concurrency::Exclusive,
+            pipelining_depth: 64,
+            response_buffer_size: 64 * 1024,
+        }
+    }
+}
+
+/// ABCI server, serving an application to Tendermint's connections
+#[derive(Debug)]
+pub struct Server<A> {
+    app: A,
+    config: Config,
+    /// Lock of the exclusive connections
+    exclusive: Mutex<()>,
+    shutdown: Shutdown,
+}
+
+impl<A: Application> Server<A> {
+    /// Serve the given application with the default configuration
+    pub fn new(app: A) -> Self {
+        Self::with_config(app, Config::default())
+    }
+
+    /// Serve the given application with the given configuration
+    pub fn with_config(app: A, config: Config) -> Self {
+        Server {
+            app,
+            config,
+            exclusive: Mutex::new(()),
+            shutdown: Shutdown::default(),
+        }
+    }
+
+    /// Borrow the application
+    pub fn app(&self) -> &A {
+        &self.app
+    }
+
+    /// Get the configuration
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Shut the server down gracefully: its connections stop reading
+    /// requests, answer the ones they already read, and close once their
+    /// responses are written
+    pub fn shutdown(&self) {
+        self.shutdown.request();
+    }
+
+    /// Whether the server is shutting down, e.g. to stop accepting
+    /// connections
+    pub fn is_shutting_down(&self) -> bool {
+        self.shutdown.is_requested()
+    }
+
+    /// Wait for the server to shut down, e.g. to stop accepting connections
+    pub async fn shutdown_requested(&self) {
+        future::poll_fn(|cx| {
+            if self.shutdown.poll_requested(cx) {
+                Poll::Ready(())
+            } else {
+                Poll::Pending
+            }
+        })
+        .await
+    }
+
+    /// Serve an accepted connection until Tendermint closes it, or the
+    /// server shuts down.
+    ///
+    /// Responses are buffered until Tendermint flushes its requests, no
+    /// request is being handled, or the buffer is full. Malformed requests
+    /// close the connection with an error, as in Go's socket server.
+    pub async fn serve_connection<S>(&self, stream: S) -> Result<(), Error>
+    where
+        S: AsyncRead + AsyncWrite + Unpin + Send,
+    {
+        let (reader, writer) = stream.split();
+        let mut writer = BufWriter::with_capacity(self.config.response_buffer_size, writer);
+        let mut requests = Box::pin(stream::unfold(reader, |mut reader| async move {
+            match codec::read_frame_async(&mut reader, codec::MAX_MESSAGE_SIZE).await {
+                Ok(Some(bytes)) => Some((Ok(bytes), reader)),
+                Ok(None) => None,
+                Err(e) => Some((Err(e), reader)),
+            }
+        }));
+
+        let mut pending = FuturesOrdered::new();
+        // Request which can't run until the pending ones complete
+        let mut held = None;
+        let mut closed = false;
+
+        loop {
+            if pending.is_empty() {
+                match held.take() {
+                    Some(request) => pending.push(self.handle(request)),
+                    None if closed => break,
+                    None => (),
+                }
+            }
+
+            let next = future::poll_fn(|cx| {
+                if let Poll::Ready(Some(response)) = pending.poll_next_unpin(cx) {
+                    return Poll::Ready(Next::Response(response));
+                }
+
+                if !closed && held.is_none() && pending.len() < self.pipelining_depth() {
+                    if self.shutdown.poll_requested(cx) {
+                        return Poll::Ready(Next::Request(None));
+                    }
+
+                    if let Poll::Ready(bytes) = requests.poll_next_unpin(cx) {
+                        return Poll::Ready(Next::Request(bytes));
+                    }
+                }
+
+                Poll::Pending
+            })
+            .await;
+
+            match next {
+                Next::Response((bytes, flush)) => {
+                    codec::write_frame_async(&mut writer, &bytes).await?;
+
+                    if flush || pending.is_empty() {
+                        writer.flush().await.map_err(|e| Kind::Io.context(e))?;
+                    }
+                }
+                Next::Request(None) => closed = true,
+                Next::Request(Some(bytes)) => {
+                    let request = Incoming::decode(&bytes?)?;
+
+                    if pending.is_empty() || self.is_parallel(&request) {
+                        pending.push(self.handle(request));
+                    } else {
+                        held = Some(request);
+                    }
+                }
+            }
+        }
+
+        writer.close().await.map_err(|e| Kind::Io.context(e).into())
+    }
+
+    /// Handle a single request, under the lock of the exclusive connections
+    /// if its connection type is exclusive, for backends other than the
+    /// socket protocol such as the gRPC backend of the `grpc` feature.
+    ///
+    /// Connections order the requests of sequential connection types
+    /// themselves, and so must the callers of this method.
+    pub async fn handle_request(&self, request: Request) -> Response {
+        let connection = request.value.as_ref().and_then(ConnectionType::of);
+        let _guard = self.lock_exclusive(connection).await;
+
+        self.app.handle(request).await
+    }
+
+    /// Take the lock of the exclusive connections if the given connection
+    /// type is exclusive
+    async fn lock_exclusive(
+        &self,
+        connection: Option<ConnectionType>,
+    ) -> Option<MutexGuard<'_, ()>> {
+        match connection.map(|connection| self.config.concurrency(connection)) {
+            Some(Concurrency::Exclusive) => Some(self.exclusive.lock().await),
+            _ => None,
+        }
+    }
+
+    /// Pipelining depth of parallel connections, which handle at least one
+    /// request at a time
+    fn pipelining_depth(&self) -> usize {
+        self.config.pipelining_depth.max(1)
+    }
+
+    /// Whether the given request may run while the previous requests of its
+    /// connection are still being handled
+    fn is_parallel(&self, request: &Incoming) -> bool {
+        match request.connection_type() {
+            Some(connection) => self.config.concurrency(connection) == Concurrency::Parallel,
+            None => true,
+        }
+    }
+
+    /// Handle a request, returning its encoded response and whether it's a
+    /// flush response
+    fn handle(&self, request: Incoming) -> BoxFuture<'_, (Vec<u8>, bool)> {
+        let connection = request.connection_type();
+
+        Box::pin(async move {
+            let _guard = self.lock_exclusive(connection).await;
+
+            match request {
+                Incoming::Abci(request) => {
+                    let response = self.app.handle(request).await;
+                    let flush = matches!(response.value, Some(response::Value::Flush(_)));
+
+                    let mut bytes = Vec::with_capacity(response.encoded_len());
+                    response.encode(&mut bytes).expect("buffer is large enough");
+                    (bytes, flush)
+                }
+                #[cfg(feature = "abci-plus-plus")]
+                Incoming::PlusPlus(request) => {
+                    let response = plus_plus::handle_request(&self.app, request).await;
+                    (plus_plus::encode_response(response), false)
+                }
+            }
+        })
+    }
+}
+
+/// Request read from a connection
+enum Incoming {
+    Abci(Request),
+    #[cfg(feature = "abci-plus-plus")]
+    PlusPlus(plus_plus::Request),
+}
+
+impl Incoming {
+    fn decode(bytes: &[u8]) -> Result<Self, Error> {
+        #[cfg(feature = "abci-plus-plus")]
+        {
+            if let Some(request) = plus_plus::decode_request(bytes)? {
+                return Ok(Incoming::PlusPlus(request));
+            }
+        }
+
+        Request::decode(bytes)
+            .map(Incoming::Abci)
+            .map_err(|e| Kind::Parse.context(e).into())
+    }
+
+    fn connection_type(&self) -> Option<ConnectionType> {
+        match self {
+            Incoming::Abci(request) => request.value.as_ref().and_then(ConnectionType::of),
+            #[cfg(feature = "abci-plus-plus")]
+            Incoming::PlusPlus(_) => Some(ConnectionType::Consensus),
+        }
+    }
+}
+
+/// Next event of a connection
+enum Next {
+    /// Encoded response to send, and whether it's a flush response
+    Response((Vec<u8>, bool)),
+
+    /// Request read, unless the connection was closed or the server is
+    /// shutting down
+    Request(Option<Result<Vec<u8>, Error>>),
+}
+
+/// Shutdown signal, waking the connections waiting for it
+#[derive(Debug, Default)]
+struct Shutdown {
+    requested: AtomicBool,
+    wakers: StdMutex<Vec<Waker>>,
+}
+
+impl Shutdown {
+    fn request(&self) {
+        self.requested.store(true, Ordering::SeqCst);
+
+        for waker in self.wakers.lock().expect("poisoned lock").drain(..) {
+            waker.wake();
+        }
+    }
+
+    fn is_requested(&self) -> bool {
+        self.requested.load(Ordering::SeqCst)
+    }
+
+    /// Check whether shutdown was requested, waking the current task once
+    /// it is otherwise
+    fn poll_requested(&self, cx: &mut Context<'_>) -> bool {
+        if self.is_requested() {
+            return true;
+        }
+
+        let mut wakers = self.wakers.lock().expect("poisoned lock");
+        if !wakers.iter().any(|waker| waker.will_wake(cx.waker())) {
+            wakers.push(cx.waker().clone());
+        }
+        drop(wakers);
+
+        // Shutdown may have been requested before the waker was registered
+        self.is_requested()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abci::{application::Blocking, kvstore::KeyValueStore};
+    use async_trait::async_trait;
+    use futures::{
+        executor::block_on,
+        io::{Cursor, Error as IoError},
+    };
+    use std::{
+        pin::Pin,
+        sync::atomic::{AtomicUsize, Ordering},
+        task::Context,
+    };
+    use tendermint_proto::abci::{
+        RequestCheckTx, RequestCommit, RequestDeliverTx, RequestFlush, RequestQuery, Response,
+        ResponseCheckTx,
+    };
+
+    /// Connection whose requests are all sent upfront
+    struct Duplex {
+        input: Cursor<Vec<u8>>,
+        output: Vec<u8>,
+        /// Whether reading blocks once the requests are read, instead of
+        /// closing the connection
+        hang: bool,
+    }
+
+    impl Duplex {
+        fn new(requests: impl IntoIterator<Item = request::Value>) -> Self {
+            let mut input = Cursor::new(vec![]);
+            for value in requests {
+                let request = Request { value: Some(value) };
+                block_on(codec::write_message_async(&mut input, &request)).unwrap();
+            }
+            input.set_position(0);
+
+            Duplex {
+                input,
+                output: vec![],
+                hang: false,
+            }
+        }
+
+        fn hanging(self) -> Self {
+            Duplex { hang: true, ..self }
+        }
+
+        fn responses(&self) -> Vec<response::Value> {
+            let mut reader = self.output.as_slice();
+            let mut responses = vec![];
+            while let Some(response) =
+                block_on(codec::read_message_async::<Response, _>(&mut reader)).unwrap()
+            {
+                responses.push(response.value.unwrap());
+            }
+            responses
+        }
+    }
+
+    impl AsyncRead for Duplex {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut [u8],
+        ) -> Poll<Result<usize, IoError>> {
+            if self.hang && self.input.position() == self.input.get_ref().len() as u64 {
+                return Poll::Pending;
+            }
+
+            Pin::new(&mut self.input).poll_read(cx, buf)
+        }
+    }
+
+    impl AsyncWrite for Duplex {
+        fn poll_write(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<Result<usize, IoError>> {
+            Pin::new(&mut self.output).poll_write(cx, buf)
+        }
+
+        fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), IoError>> {
+            Pin::new(&mut self.output).poll_flush(cx)
+        }
+
+        fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), IoError>> {
+            Pin::new(&mut self.output).poll_close(cx)
+        }
+    }
+
+    /// Checks transactions once the given number of checks started
+    #[derive(Default)]
+    struct Checker {
+        wait_for: usize,
+        started: AtomicUsize,
+        in_flight: AtomicUsize,
+        max_in_flight: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl Application for Checker {
+        async fn check_tx(&self, request: RequestCheckTx) -> ResponseCheckTx {
+            self.started.fetch_add(1, Ordering::SeqCst);
+            let in_flight = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_in_flight.fetch_max(in_flight, Ordering::SeqCst);
+
+            // Wait at least once for the other checks to start
+            let mut waited = false;
+            future::poll_fn(|cx: &mut Context<'_>| {
+                if waited && self.started.load(Ordering::SeqCst) >= self.wait_for {
+                    Poll::Ready(())
+                } else {
+                    waited = true;
+                    cx.waker().wake_by_ref();
+                    Poll::Pending
+                }
+            })
+            .await;
+
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+            ResponseCheckTx {
+                data: request.tx,
+                ..ResponseCheckTx::default()
+            }
+        }
+    }
+
+    fn check_txs(count: u8) -> Vec<request::Value> {
+        let mut requests = (0..count)
+            .map(|i| {
+                request::Value::CheckTx(RequestCheckTx {
+                    tx: vec![i],
+                    r#type: 0,
+                })
+            })
+            .collect::<Vec<_>>();
+        requests.push(request::Value::Flush(RequestFlush {}));
+        requests
+    }
+
+    fn assert_checked_in_order(responses: Vec<response::Value>, count: u8) {
+        assert_eq!(responses.len(), usize::from(count) + 1);
+        for (i, response) in responses.iter().take(count.into()).enumerate() {
+            match response {
+                response::Value::CheckTx(response) => assert_eq!(response.data, [i as u8]),
+                other => panic!("unexpected response: {:?}", other),
+            }
+        }
+        assert!(matches!(responses.last(), Some(response::Value::Flush(_))));
+    }
+
+    #[test]
+    fn checks_transactions_in_parallel() {
+        let server = Server::with_config(
+            Checker {
+                wait_for: 3,
+                ..Checker::default()
+            },
+            Config {
+                mempool: Concurrency::Parallel,
+                ..Config::default()
+            },
+        );
+
+        let mut connection = Duplex::new(check_txs(3));
+        block_on(server.serve_connection(&mut connection)).unwrap();
+
+        assert_checked_in_order(connection.responses(), 3);
+        assert_eq!(server.app().max_in_flight.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn serializes_exclusive_connections() {
+        let server = Server::new(Checker::default());
+
+        let mut connection = Duplex::new(check_txs(3));
+        block_on(server.serve_connection(&mut connection)).unwrap();
+
+        assert_checked_in_order(connection.responses(), 3);
+        assert_eq!(server.app().max_in_flight.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn limits_pipelining_depth() {
+        let server = Server::with_config(
+            Checker::default(),
+            Config {
+                mempool: Concurrency::Parallel,
+                pipelining_depth: 1,
+                ..Config::default()
+            },
+        );
+
+        let mut connection = Duplex::new(check_txs(3));
+        block_on(server.serve_connection(&mut connection)).unwrap();
+
+        assert_checked_in_order(connection.responses(), 3);
+        assert_eq!(server.app().max_in_flight.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn shuts_down_gracefully() {
+        let server = Server::with_config(
+            Checker::default(),
+            Config {
+                mempool: Concurrency::Parallel,
+                ..Config::default()
+            },
+        );
+
+        // The checks are read, but not answered, before the shutdown
+        let mut connection = Duplex::new(check_txs(3)).hanging();
+        let (served, _) = block_on(future::join(
+            server.serve_connection(&mut connection),
+            async {
+                assert!(!server.is_shutting_down());
+                server.shutdown();
+            },
+        ));
+        served.unwrap();
+
+        assert!(server.is_shutting_down());
+        block_on(server.shutdown_requested());
+        assert_checked_in_order(connection.responses(), 3);
+    }
+
+    #[test]
+    fn serves_applications() {
+        let server = Server::new(Blocking::new(KeyValueStore::new()));
+
+        let mut consensus = Duplex::new(vec![
+            request::Value::DeliverTx(RequestDeliverTx {
+                tx: b"name=satoshi".to_vec(),
+            }),
+            request::Value::Commit(RequestCommit {}),
+        ]);
+        let mut info = Duplex::new(vec![request::Value::Query(RequestQuery {
+            data: b"name".to_vec(),
+            ..RequestQuery::default()
+        })]);
+
+        block_on(server.serve_connection(&mut consensus)).unwrap();
+        block_on(server.serve_connection(&mut info)).unwrap();
+
+        match info.responses().as_slice() {
+            [response::Value::Query(response)] => assert_eq!(response.value, b"satoshi"),
+            other => panic!("unexpected responses: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn dispatches_rechecks() {
+        use crate::abci::{
+            request::{CheckTx, CheckTxKind},
+            responses,
+        };
+        use std::convert::TryFrom;
+
+        /// Prioritizes new transactions over rechecked ones
+        struct Prioritizer;
+
+        #[async_trait]
+        impl Application for Prioritizer {
+            async fn check_tx(&self, request: RequestCheckTx) -> ResponseCheckTx {
+                let request = CheckTx::try_from(request).unwrap();
+                let priority = match request.kind {
+                    CheckTxKind::New => 2,
+                    CheckTxKind::Recheck => 1,
+                };
+
+                responses::CheckTx {
+                    sender: "alice".to_owned(),
+                    priority,
+                    ..responses::CheckTx::default()
+                }
+                .into()
+            }
+        }
+
+        let server = Server::new(Prioritizer);
+        let mut mempool = Duplex::new(vec![
+            request::Value::CheckTx(RequestCheckTx::default()),
+            request::Value::CheckTx(RequestCheckTx {
+                r#type: CheckTxKind::Recheck.into(),
+                ..RequestCheckTx::default()
+            }),
+        ]);
+        block_on(server.serve_connection(&mut mempool)).unwrap();
+
+        match mempool.responses().as_slice() {
+            [response::Value::CheckTx(new), response::Value::CheckTx(recheck)] => {
+                assert_eq!((new.sender.as_str(), new.priority), ("alice", 2));
+                assert_eq!(recheck.priority, 1);
+            }
+            other => panic!("unexpected responses: {:?}", other),
+        }
+    }
+
+    #[cfg(feature = "abci-plus-plus")]
+    #[test]
+    fn serves_abci_plus_plus_requests() {
+        use crate::abci::plus_plus::{self, RequestProcessProposal};
+
+        struct Empty;
+        impl Application for Empty {}
+
+        let request = plus_plus::encode_request(plus_plus::Request::ProcessProposal(
+            RequestProcessProposal::default(),
+        ));
+        let mut input = Cursor::new(vec![]);
+        block_on(codec::write_frame_async(&mut input, &request)).unwrap();
+        input.set_position(0);
+
+        let server = Server::new(Empty);
+        let mut consensus = Duplex {
+            input,
+            output: vec![],
+            hang: false,
+        };
+        block_on(server.serve_connection(&mut consensus)).unwrap();
+
+        let response = block_on(codec::read_frame_async(
+            &mut consensus.output.as_slice(),
+            usize::MAX,
+        ))
+        .unwrap()
+        .unwrap();
+        assert!(matches!(
+            plus_plus::decode_response(&response).unwrap(),
+            Some(plus_plus::Response::ProcessProposal(_))
+        ));
+    }
+
+    #[test]
+    fn types_connections() {
+        let check_tx = request::Value::CheckTx(RequestCheckTx::default());
+        assert_eq!(ConnectionType::of(&check_tx), Some(ConnectionType::Mempool));
+
+        let flush = request::Value::Flush(RequestFlush {});
+        assert_eq!(ConnectionType::of(&flush), None);
+    }
+}
@@ -0,0 +1,118 @@
+//! Snapshots of application state, which nodes joining the network restore
+//! through state sync instead of replaying every block.
+//!
+//! <https://github.com/tendermint/spec/blob/master/spec/abci/apps.md#state-sync>
+
+use crate::{
+    block,
+    error::{Error, Kind},
+};
+use anomaly::fail;
+use std::convert::TryFrom;
+use tendermint_proto::{abci::Snapshot as RawSnapshot, DomainType};
+
+/// Metadata of a snapshot, as listed by applications and offered to the
+/// applications of nodes restoring it.
+///
+/// This type corresponds to the `Snapshot` proto.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Snapshot {
+    /// Height the snapshot was taken at
+    pub height: block::Height,
+
+    /// Application-specific format of the snapshot, e.g. to reject snapshots
+    /// in formats the application doesn't support
+    pub format: u32,
+
+    /// Number of chunks the snapshot is split into
+    pub chunks: u32,
+
+    /// Hash of the snapshot, identifying it across peers
+    pub hash: Vec<u8>,
+
+    /// Application-specific metadata, e.g. the hashes of the chunks
+    pub metadata: Vec<u8>,
+}
+
+impl DomainType<RawSnapshot> for Snapshot {}
+
+/// Snapshots are only restorable at a height, with some chunks and a hash
+impl TryFrom<RawSnapshot> for Snapshot {
+    type Error = Error;
+
+    fn try_from(value: RawSnapshot) -> Result<Self, Self::Error> {
+        if value.height == 0 {
+            fail!(Kind::OutOfRange, "snapshot at height 0");
+        }
+
+        if value.chunks == 0 {
+            fail!(Kind::OutOfRange, "snapshot without chunks");
+        }
+
+        if value.hash.is_empty() {
+            fail!(Kind::Length, "snapshot without hash");
+        }
+
+        Ok(Snapshot {
+            height: value.height.into(),
+            format: value.format,
+            chunks: value.chunks,
+            hash: value.hash,
+            metadata: value.metadata,
+        })
+    }
+}
+
+impl From<Snapshot> for RawSnapshot {
+    fn from(value: Snapshot) -> Self {
+        RawSnapshot {
+            height: value.height.value(),
+            format: value.format,
+            chunks: value.chunks,
+            hash: value.hash,
+            metadata: value.metadata,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn raw_snapshot() -> RawSnapshot {
+        RawSnapshot {
+            height: 100,
+            format: 1,
+            chunks: 3,
+            hash: vec![0xab; 32],
+            metadata: b"chunk hashes".to_vec(),
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let snapshot = Snapshot::try_from(raw_snapshot()).unwrap();
+        assert_eq!(snapshot.height.value(), 100);
+        assert_eq!(RawSnapshot::from(snapshot), raw_snapshot());
+    }
+
+    #[test]
+    fn rejects_unrestorable_snapshots() {
+        for raw in vec![
+            RawSnapshot {
+                height: 0,
+                ..raw_snapshot()
+            },
+            RawSnapshot {
+                chunks: 0,
+                ..raw_snapshot()
+            },
+            RawSnapshot {
+                hash: vec![],
+                ..raw_snapshot()
+            },
+        ] {
+            assert!(Snapshot::try_from(raw).is_err());
+        }
+    }
+}
@@ -1,17 +1,65 @@
 //! Tags
 
-use crate::error::Error;
+use crate::error::{Error, Kind};
 use serde::{Deserialize, Serialize};
-use std::{fmt, str::FromStr};
+use std::{convert::TryFrom, fmt, str::FromStr};
+use tendermint_proto::abci::EventAttribute as RawEventAttribute;
 
-/// Tags
-#[derive(Clone, Debug, Serialize, Deserialize)]
+/// Tags (a.k.a. event attributes).
+///
+/// ABCI keys and values are bytes, which are Base64-encoded in JSON: they are
+/// decoded (as UTF-8) when deserializing, and encoded again when serializing.
+/// Keys and values which aren't UTF-8 are decoded lossily, with invalid
+/// sequences replaced by U+FFFD, so that they don't fail the whole response.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub struct Tag {
     /// Key
+    #[serde(with = "base64_string")]
     pub key: Key,
 
     /// Value
+    #[serde(with = "base64_string")]
     pub value: Value,
+
+    /// Whether the tag is indexed by the node
+    #[serde(default)]
+    pub index: bool,
+}
+
+impl Tag {
+    /// Create an unindexed tag
+    pub fn new(key: impl Into<String>, value: impl Into<String>) -> Self {
+        Tag {
+            key: Key(key.into()),
+            value: Value(value.into()),
+            index: false,
+        }
+    }
+}
+
+/// Event attributes of ABCI responses carry raw bytes, which must be UTF-8
+impl TryFrom<RawEventAttribute> for Tag {
+    type Error = Error;
+
+    fn try_from(value: RawEventAttribute) -> Result<Self, Self::Error> {
+        let utf8 = |bytes| String::from_utf8(bytes).map_err(|e| Kind::Parse.context(e));
+
+        Ok(Tag {
+            key: Key(utf8(value.key)?),
+            value: Value(utf8(value.value)?),
+            index: value.index,
+        })
+    }
+}
+
+impl From<Tag> for RawEventAttribute {
+    fn from(value: Tag) -> Self {
+        RawEventAttribute {
+            key: value.key.0.into_bytes(),
+            value: value.value.0.into_bytes(),
+            index: value.index,
+        }
+    }
 }
 
 /// Tag keys
@@ -61,3 +109,75 @@ impl fmt::Display for Value {
         write!(f, "{}", &self.0)
     }
 }
+
+/// Serialize strings as Base64 of their UTF-8 bytes
+mod base64_string {
+    use crate::error::Error;
+    use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+    use std::str::FromStr;
+    use subtle_encoding::base64;
+
+    pub fn serialize<S, T>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+        T: AsRef<str>,
+    {
+        String::from_utf8(base64::encode(value.as_ref()))
+            .unwrap()
+            .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+    where
+        D: Deserializer<'de>,
+        T: FromStr<Err = Error>,
+    {
+        let encoded = Option::<String>::deserialize(deserializer)?.unwrap_or_default();
+        let bytes = base64::decode(&encoded).map_err(|e| D::Error::custom(format!("{}", e)))?;
+        let decoded = String::from_utf8(bytes)
+            .unwrap_or_else(|e| String::from_utf8_lossy(e.as_bytes()).into_owned());
+
+        decoded
+            .parse()
+            .map_err(|e| D::Error::custom(format!("{}", e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_base64_attributes() {
+        let tag: Tag = serde_json::from_str(
+            r#"{"key": "cmVjaXBpZW50", "value": "Y29zbW9zMXJlY2lwaWVudA==", "index": true}"#,
+        )
+        .unwrap();
+
+        assert_eq!(tag.key.as_ref(), "recipient");
+        assert_eq!(tag.value.as_ref(), "cosmos1recipient");
+        assert!(tag.index);
+
+        assert_eq!(
+            serde_json::to_string(&tag).unwrap(),
+            r#"{"key":"cmVjaXBpZW50","value":"Y29zbW9zMXJlY2lwaWVudA==","index":true}"#
+        );
+    }
+
+    #[test]
+    fn decodes_binary_values_lossily() {
+        let tag: Tag = serde_json::from_str(r#"{"key": "a2V5", "value": "/wBh"}"#).unwrap();
+
+        assert_eq!(tag.key.as_ref(), "key");
+        assert_eq!(tag.value.as_ref(), "\u{fffd}\u{0}a");
+    }
+
+    #[test]
+    fn decodes_null_values() {
+        let tag: Tag = serde_json::from_str(r#"{"key": "a2V5", "value": null}"#).unwrap();
+
+        assert_eq!(tag.key.as_ref(), "key");
+        assert_eq!(tag.value.as_ref(), "");
+        assert!(!tag.index);
+    }
+}
@@ -1,8 +1,11 @@
 //! Transactions
 
 mod hash;
+mod proof;
 
-pub use self::hash::Hash;
+pub use self::{hash::Hash, proof::Proof};
+use crate::hash::{DefaultHasher, Hasher};
+use crate::merkle::simple_hash_from_byte_vectors_with;
 use std::slice;
 use {
     serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer},
@@ -32,6 +35,17 @@ impl Transaction {
     pub fn as_bytes(&self) -> &[u8] {
         self.0.as_slice()
     }
+
+    /// Compute the hash of this transaction (i.e. the SHA-256 digest of its
+    /// contents), as used to look up transactions
+    pub fn hash(&self) -> Hash {
+        self.hash_with(&DefaultHasher)
+    }
+
+    /// Compute the hash of this transaction using the given [`Hasher`]
+    pub fn hash_with<H: Hasher + ?Sized>(&self, hasher: &H) -> Hash {
+        Hash::new(hasher.digest(self.as_bytes()))
+    }
 }
 
 impl AsRef<[u8]> for Transaction {
@@ -86,6 +100,31 @@ impl Data {
     pub fn iter(&self) -> slice::Iter<'_, Transaction> {
         self.as_ref().iter()
     }
+
+    /// Compute the Merkle root of the hashes of these transactions, i.e. the
+    /// `data_hash` of the header of the block containing them.
+    ///
+    /// Returns `None` if there are no transactions.
+    pub fn root_hash(&self) -> Option<crate::Hash> {
+        self.root_hash_with(&DefaultHasher)
+    }
+
+    /// Compute the Merkle root of the hashes of these transactions using the
+    /// given [`Hasher`]
+    pub fn root_hash_with<H: Hasher + ?Sized>(&self, hasher: &H) -> Option<crate::Hash> {
+        if self.as_ref().is_empty() {
+            return None;
+        }
+
+        let tx_hashes = self
+            .iter()
+            .map(|tx| tx.hash_with(hasher).as_bytes().to_vec())
+            .collect::<Vec<_>>();
+
+        Some(crate::Hash::Sha256(simple_hash_from_byte_vectors_with(
+            &tx_hashes, hasher,
+        )))
+    }
 }
 
 impl AsRef<[Transaction]> for Data {
@@ -93,3 +132,38 @@ impl AsRef<[Transaction]> for Data {
         self.txs.as_deref().unwrap_or_else(|| &[])
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{Data, Transaction};
+    use crate::hash::{Algorithm, Hash};
+
+    #[test]
+    fn transaction_hash() {
+        let tx = Transaction::new(b"tx1=1".to_vec());
+        assert_eq!(
+            tx.hash().to_string(),
+            "4F3A57A0BFFDC8CC6B359CC36A02957F006D786A33AB353648AFCEE3D464D32F"
+        );
+    }
+
+    #[test]
+    fn data_root_hash() {
+        assert_eq!(Data::default().root_hash(), None);
+
+        let data = Data::new(vec![
+            Transaction::new(b"tx1=1".to_vec()),
+            Transaction::new(b"tx2=2".to_vec()),
+        ]);
+        assert_eq!(
+            data.root_hash(),
+            Some(
+                Hash::from_hex_upper(
+                    Algorithm::Sha256,
+                    "194901EA83E9132C9D6BEE4F91C03FAF382AE58952D21435662409304622AF3F"
+                )
+                .unwrap()
+            )
+        );
+    }
+}
@@ -0,0 +1,120 @@
+//! Transaction inclusion proofs
+
+use super::Transaction;
+use crate::{
+    block,
+    error::Kind,
+    merkle::{proof::SimpleProof, simple_proofs_from_byte_vectors},
+    Error, Hash,
+};
+use anomaly::fail;
+use serde::{Deserialize, Serialize};
+
+/// Proof of the inclusion of a transaction in a block, as returned by the
+/// `/tx` RPC endpoint when called with `prove=true`.
+///
+/// The leaves of the Merkle tree are the hashes of the block's transactions,
+/// and its root is the `data_hash` of the block's header.
+///
+/// <https://github.com/tendermint/tendermint/blob/v0.34.0/types/tx.go>
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Proof {
+    /// Merkle root of the block's transactions
+    pub root_hash: Hash,
+
+    /// The proven transaction
+    pub data: Transaction,
+
+    /// Merkle proof of the inclusion of the transaction's hash
+    pub proof: SimpleProof,
+}
+
+impl Proof {
+    /// Create proofs of the inclusion of each of the given transactions in
+    /// the block containing them (in the same order).
+    pub fn for_transactions(txs: &[Transaction]) -> Vec<Proof> {
+        let tx_hashes = txs
+            .iter()
+            .map(|tx| tx.hash().as_bytes().to_vec())
+            .collect::<Vec<_>>();
+        let (root_hash, proofs) = simple_proofs_from_byte_vectors(&tx_hashes);
+
+        txs.iter()
+            .zip(proofs)
+            .map(|(tx, proof)| Proof {
+                root_hash: Hash::Sha256(root_hash),
+                data: tx.clone(),
+                proof,
+            })
+            .collect()
+    }
+
+    /// Verify that this proof proves the inclusion of its transaction in the
+    /// block with the given `data_hash`
+    pub fn verify(&self, data_hash: &Hash) -> Result<(), Error> {
+        if &self.root_hash != data_hash {
+            return Err(Kind::InvalidProof
+                .context(Kind::HashMismatch {
+                    expected: *data_hash,
+                    actual: self.root_hash,
+                })
+                .into());
+        }
+
+        if self.proof.total == 0 {
+            fail!(Kind::InvalidProof, "proof has no leaves");
+        }
+
+        match &self.root_hash {
+            Hash::Sha256(root_hash) => self.proof.verify(root_hash, self.data.hash().as_bytes()),
+        }
+    }
+
+    /// Verify that this proof proves the inclusion of its transaction in the
+    /// block with the given header
+    pub fn verify_header(&self, header: &block::Header) -> Result<(), Error> {
+        match &header.data_hash {
+            Some(data_hash) => self.verify(data_hash),
+            None => fail!(Kind::InvalidProof, "block has no transactions"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn example_txs() -> Vec<Transaction> {
+        (0..5)
+            .map(|i| Transaction::new(format!("tx{}={}", i, i).into_bytes()))
+            .collect()
+    }
+
+    #[test]
+    fn verifies_transaction_proofs() {
+        let txs = example_txs();
+        let data_hash = super::super::Data::new(txs.clone()).root_hash().unwrap();
+        let proofs = Proof::for_transactions(&txs);
+
+        for (tx, proof) in txs.iter().zip(&proofs) {
+            assert_eq!(&proof.data, tx);
+            assert!(proof.verify(&data_hash).is_ok());
+        }
+
+        let mut wrong_tx = proofs[1].clone();
+        wrong_tx.data = txs[2].clone();
+        assert!(wrong_tx.verify(&data_hash).is_err());
+
+        assert!(proofs[0].verify(&Hash::Sha256([0; 32])).is_err());
+    }
+
+    #[test]
+    fn serde_roundtrip() {
+        let proof = Proof::for_transactions(&example_txs()).remove(3);
+        let json = serde_json::to_value(&proof).unwrap();
+
+        assert_eq!(json["proof"]["total"], "5");
+        assert_eq!(json["proof"]["index"], "3");
+        assert_eq!(serde_json::from_value::<Proof>(json).unwrap(), proof);
+    }
+}
@@ -5,18 +5,21 @@ use crate::{
     public_key::Ed25519,
 };
 
+use anomaly::{fail, format_err};
 use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 use sha2::{Digest, Sha256};
 use std::{
-    convert::TryInto,
+    convert::{TryFrom, TryInto},
     fmt::{self, Debug, Display},
     str::FromStr,
 };
 use subtle::{self, ConstantTimeEq};
-use subtle_encoding::hex;
+use subtle_encoding::{bech32, hex};
 
 #[cfg(feature = "secp256k1")]
 use crate::public_key::Secp256k1;
+#[cfg(feature = "sr25519")]
+use crate::public_key::Sr25519;
 #[cfg(feature = "secp256k1")]
 use ripemd160::Ripemd160;
 
@@ -37,6 +40,39 @@ impl Id {
     pub fn as_bytes(&self) -> &[u8] {
         &self.0[..]
     }
+
+    /// Encode this account ID as Bech32 with the given human readable prefix
+    /// (e.g. `cosmos` or `cosmosvaloper`)
+    pub fn to_bech32(&self, hrp: &str) -> String {
+        bech32::encode(hrp, self.as_bytes())
+    }
+
+    /// Decode an account ID from Bech32, checking it has the expected human
+    /// readable prefix
+    pub fn from_bech32(encoded: &str, hrp: &str) -> Result<Id, Error> {
+        let (actual_hrp, bytes) = bech32::decode(encoded)
+            .map_err(|e| format_err!(Kind::Parse, "invalid bech32: {}", e))?;
+
+        if actual_hrp != hrp {
+            fail!(
+                Kind::Parse,
+                "expected bech32 prefix {:?}, got {:?}",
+                hrp,
+                actual_hrp
+            );
+        }
+
+        let bytes: [u8; LENGTH] = bytes.as_slice().try_into().map_err(|_| {
+            format_err!(
+                Kind::Length,
+                "expected {}-byte account ID, got {} bytes",
+                LENGTH,
+                bytes.len()
+            )
+        })?;
+
+        Ok(Id(bytes))
+    }
 }
 
 impl AsRef<[u8]> for Id {
@@ -45,6 +81,29 @@ impl AsRef<[u8]> for Id {
     }
 }
 
+impl TryFrom<Vec<u8>> for Id {
+    type Error = Error;
+
+    fn try_from(value: Vec<u8>) -> Result<Self, Self::Error> {
+        let bytes: [u8; LENGTH] = value.as_slice().try_into().map_err(|_| {
+            format_err!(
+                Kind::Length,
+                "expected {}-byte account ID, got {} bytes",
+                LENGTH,
+                value.len()
+            )
+        })?;
+
+        Ok(Id(bytes))
+    }
+}
+
+impl From<Id> for Vec<u8> {
+    fn from(id: Id) -> Vec<u8> {
+        id.as_bytes().to_vec()
+    }
+}
+
 impl ConstantTimeEq for Id {
     #[inline]
     fn ct_eq(&self, other: &Id) -> subtle::Choice {
@@ -67,7 +126,7 @@ impl Debug for Id {
     }
 }
 
-// RIPEMD160(SHA256(pk))
+/// Derive an account ID from a secp256k1 key as `RIPEMD160(SHA256(pk))`
 #[cfg(feature = "secp256k1")]
 impl From<Secp256k1> for Id {
     fn from(pk: Secp256k1) -> Id {
@@ -79,7 +138,7 @@ impl From<Secp256k1> for Id {
     }
 }
 
-// SHA256(pk)[:20]
+/// Derive an account ID from an Ed25519 key as `SHA256(pk)[:20]`
 impl From<Ed25519> for Id {
     fn from(pk: Ed25519) -> Id {
         let digest = Sha256::digest(pk.as_bytes());
@@ -87,6 +146,15 @@ impl From<Ed25519> for Id {
     }
 }
 
+/// Derive an account ID from an Sr25519 key as `SHA256(pk)[:20]`
+#[cfg(feature = "sr25519")]
+impl From<Sr25519> for Id {
+    fn from(pk: Sr25519) -> Id {
+        let digest = Sha256::digest(&pk.to_bytes());
+        Id(digest[..LENGTH].try_into().unwrap())
+    }
+}
+
 /// Decode account ID from hex
 impl FromStr for Id {
     type Err = Error;
@@ -168,4 +236,14 @@ mod tests {
 
         assert_eq!(id_bytes.ct_eq(&id).unwrap_u8(), 1);
     }
+
+    #[test]
+    fn bech32_roundtrip() {
+        let id = Id::from_str("0CDA3F47EF3C4906693B170EF650EB968C5F4B2C").unwrap();
+        let encoded = "cosmos1pndr73l083ysv6fmzu80v58tj6x97jevhzatht";
+
+        assert_eq!(id.to_bech32("cosmos"), encoded);
+        assert_eq!(Id::from_bech32(encoded, "cosmos").unwrap(), id);
+        assert!(Id::from_bech32(encoded, "cosmosvaloper").is_err());
+    }
 }
@@ -1,13 +1,25 @@
 //! Message types serialized using the Amino serialization format
 //! <https://github.com/tendermint/amino_rs>
+//!
+//! The remote signer (privval), P2P and peer exchange messages are only available
+//! with the `amino` feature enabled.
 
 #![allow(missing_docs)]
 
 pub mod block_id;
+#[cfg(feature = "amino")]
 pub mod ed25519;
 pub mod message;
+#[cfg(feature = "amino")]
+pub mod p2p;
+#[cfg(feature = "amino")]
+pub mod pex;
+#[cfg(feature = "amino")]
 pub mod ping;
 pub mod proposal;
+#[cfg(feature = "amino")]
+pub mod registry;
+#[cfg(feature = "amino")]
 pub mod remote_error;
 pub mod signature;
 pub mod time;
@@ -17,29 +29,30 @@ pub mod vote;
 
 pub use self::{
     block_id::{BlockId, CanonicalBlockId, CanonicalPartSetHeader, PartsSetHeader},
-    ed25519::{
-        PubKeyRequest, PubKeyResponse, AMINO_NAME as PUBKEY_AMINO_NAME,
-        AMINO_PREFIX as PUBKEY_PREFIX,
-    },
-    ping::{PingRequest, PingResponse, AMINO_NAME as PING_AMINO_NAME, AMINO_PREFIX as PING_PREFIX},
-    proposal::{
-        SignProposalRequest, SignedProposalResponse, AMINO_NAME as PROPOSAL_AMINO_NAME,
-        AMINO_PREFIX as PROPOSAL_PREFIX,
-    },
-    remote_error::RemoteError,
-    signature::{SignableMsg, SignedMsgType},
+    signature::SignedMsgType,
     time::TimeMsg,
     validate::ConsensusMessage,
     version::ConsensusVersion,
-    vote::{
-        SignVoteRequest, SignedVoteResponse, AMINO_NAME as VOTE_AMINO_NAME,
-        AMINO_PREFIX as VOTE_PREFIX,
-    },
 };
 
+#[cfg(feature = "amino")]
+pub use self::{
+    ed25519::{PubKeyRequest, PubKeyResponse},
+    p2p::DefaultNodeInfo,
+    pex::{PexAddrsMessage, PexRequestMessage},
+    ping::{PingRequest, PingResponse},
+    proposal::{SignProposalRequest, SignedProposalResponse},
+    registry::{Decoder, Registered, Registry, RemoteSignerMessage},
+    remote_error::RemoteError,
+    signature::SignableMsg,
+    vote::{SignVoteRequest, SignedVoteResponse},
+};
+
+#[cfg(feature = "amino")]
 use sha2::{Digest, Sha256};
 
 /// Compute the Amino prefix for the given registered type name
+#[cfg(feature = "amino")]
 pub fn compute_prefix(name: &str) -> Vec<u8> {
     let mut sh = Sha256::default();
     sh.update(name.as_bytes());
@@ -74,6 +74,15 @@ impl block::ParseId for CanonicalBlockId {
     }
 }
 
+impl From<&block::Id> for CanonicalBlockId {
+    fn from(bid: &block::Id) -> Self {
+        CanonicalBlockId {
+            hash: bid.hash.as_bytes().to_vec(),
+            parts_header: bid.parts.as_ref().map(CanonicalPartSetHeader::from),
+        }
+    }
+}
+
 #[derive(Clone, PartialEq, Message)]
 pub struct PartsSetHeader {
     #[prost_amino(int64, tag = "1")]
@@ -130,3 +139,12 @@ impl CanonicalPartSetHeader {
             .ok()
     }
 }
+
+impl From<&parts::Header> for CanonicalPartSetHeader {
+    fn from(parts: &parts::Header) -> Self {
+        CanonicalPartSetHeader {
+            hash: parts.hash.as_bytes().to_vec(),
+            total: parts.total as i64,
+        }
+    }
+}
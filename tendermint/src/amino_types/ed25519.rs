@@ -1,54 +1,98 @@
-use super::compute_prefix;
-use crate::{
-    error,
-    public_key::{Ed25519, PublicKey},
-    Error,
-};
-use anomaly::format_err;
-use once_cell::sync::Lazy;
+use super::{registry::Registered, remote_error::RemoteError};
+use crate::{chain, error, public_key::PublicKey, Error};
 use prost_amino_derive::Message;
 use std::convert::TryFrom;
 
-// Note:On the golang side this is generic in the sense that it could everything that implements
-// github.com/tendermint/tendermint/crypto.PubKey
-// While this is meant to be used with different key-types, it currently only uses a PubKeyEd25519
-// version.
-// TODO(ismail): make this more generic (by modifying prost and adding a trait for PubKey)
-
-pub const AMINO_NAME: &str = "tendermint/remotesigner/PubKeyRequest";
-pub static AMINO_PREFIX: Lazy<Vec<u8>> = Lazy::new(|| compute_prefix(AMINO_NAME));
-
 #[derive(Clone, PartialEq, Message)]
 #[amino_name = "tendermint/remotesigner/PubKeyResponse"]
 pub struct PubKeyResponse {
-    #[prost_amino(bytes, tag = "1", amino_name = "tendermint/PubKeyEd25519")]
-    pub pub_key_ed25519: Vec<u8>,
+    /// Amino encoding of the key (see [`PublicKey::to_amino_bytes`]): on the
+    /// Go side this is a `crypto.PubKey` interface, so the key is prefixed
+    /// with its registered type, e.g. `tendermint/PubKeyEd25519` or
+    /// `tendermint/PubKeySecp256k1`
+    #[prost_amino(bytes, tag = "1")]
+    pub pub_key: Vec<u8>,
+
+    /// Error reported in place of the key, e.g. for an unknown chain. Only
+    /// newer Tendermint versions understand it.
+    #[prost_amino(message, tag = "2")]
+    pub err: Option<RemoteError>,
+}
+
+impl Registered for PubKeyResponse {
+    const AMINO_NAME: &'static str = "tendermint/remotesigner/PubKeyResponse";
+}
+
+impl PubKeyResponse {
+    /// Create a response reporting the given error in place of the key
+    pub fn error(err: RemoteError) -> Self {
+        PubKeyResponse {
+            pub_key: vec![],
+            err: Some(err),
+        }
+    }
 }
 
 #[derive(Clone, PartialEq, Message)]
 #[amino_name = "tendermint/remotesigner/PubKeyRequest"]
-pub struct PubKeyRequest {}
+pub struct PubKeyRequest {
+    /// Chain the key is requested for. Empty in the legacy form of the
+    /// request, which older Tendermint versions send.
+    #[prost_amino(string, tag = "1")]
+    pub chain_id: String,
+}
+
+impl Registered for PubKeyRequest {
+    const AMINO_NAME: &'static str = "tendermint/remotesigner/PubKeyRequest";
+}
+
+impl PubKeyRequest {
+    /// Create a request for the key of the given chain
+    pub fn new(chain_id: chain::Id) -> Self {
+        PubKeyRequest {
+            chain_id: chain_id.to_string(),
+        }
+    }
+
+    /// Create a request in the legacy form, without a chain ID
+    pub fn legacy() -> Self {
+        PubKeyRequest {
+            chain_id: String::new(),
+        }
+    }
+
+    /// Get the chain the key is requested for, or `None` for a legacy
+    /// request
+    pub fn chain_id(&self) -> Result<Option<chain::Id>, Error> {
+        if self.chain_id.is_empty() {
+            Ok(None)
+        } else {
+            self.chain_id.parse().map(Some)
+        }
+    }
+}
 
 impl TryFrom<PubKeyResponse> for PublicKey {
     type Error = Error;
 
-    // This does not check if the underlying pub_key_ed25519 has the right size.
-    // The caller needs to make sure that this is actually the case.
     fn try_from(response: PubKeyResponse) -> Result<PublicKey, Error> {
-        Ed25519::from_bytes(&response.pub_key_ed25519)
-            .map(Into::into)
-            .map_err(|_| format_err!(error::Kind::InvalidKey, "malformed Ed25519 key").into())
+        if let Some(err) = response.err {
+            return Err(error::Kind::RemoteSigner {
+                code: err.code,
+                description: err.description,
+            }
+            .into());
+        }
+
+        PublicKey::from_amino_bytes(&response.pub_key)
     }
 }
 
 impl From<PublicKey> for PubKeyResponse {
     fn from(public_key: PublicKey) -> PubKeyResponse {
-        match public_key {
-            PublicKey::Ed25519(ref pk) => PubKeyResponse {
-                pub_key_ed25519: pk.as_bytes().to_vec(),
-            },
-            #[cfg(feature = "secp256k1")]
-            PublicKey::Secp256k1(_) => panic!("secp256k1 PubKeyResponse unimplemented"),
+        PubKeyResponse {
+            pub_key: public_key.to_amino_bytes(),
+            err: None,
         }
     }
 }
@@ -56,6 +100,7 @@ impl From<PublicKey> for PubKeyResponse {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::public_key::Ed25519;
     use ed25519_dalek::PUBLIC_KEY_LENGTH;
     use prost_amino::Message;
     use std::convert::TryInto;
@@ -93,7 +138,7 @@ mod tests {
         //
 
         let want = vec![0x4, 0xcb, 0x94, 0xd6, 0x20];
-        let msg = PubKeyRequest {};
+        let msg = PubKeyRequest::legacy();
         let mut got = vec![];
         let _have = msg.encode(&mut got);
 
@@ -130,11 +175,13 @@ mod tests {
         ];
 
         let msg = PubKeyResponse {
-            pub_key_ed25519: vec![
+            pub_key: vec![
+                0x16, 0x24, 0xde, 0x64, 0x20, // key type prefix and length
                 0x79, 0xce, 0xd, 0xe0, 0x43, 0x33, 0x4a, 0xec, 0xe0, 0x8b, 0x7b, 0xb5, 0x61, 0xbc,
                 0xe7, 0xc1, 0xd4, 0x69, 0xc3, 0x44, 0x26, 0xec, 0xef, 0xc0, 0x72, 0xa, 0x52, 0x4d,
                 0x37, 0x32, 0xef, 0xed,
             ],
+            err: None,
         };
         let mut got = vec![];
         let _have = msg.encode(&mut got);
@@ -156,11 +203,13 @@ mod tests {
         ];
         let want = PublicKey::Ed25519(Ed25519::from_bytes(&raw_pk).unwrap());
         let pk = PubKeyResponse {
-            pub_key_ed25519: vec![
+            pub_key: vec![
+                0x16, 0x24, 0xde, 0x64, 0x20, // key type prefix and length
                 0xaf, 0xf3, 0x94, 0xc5, 0xb7, 0x5c, 0xfb, 0xd, 0xd9, 0x28, 0xe5, 0x8a, 0x92, 0xdd,
                 0x76, 0x55, 0x2b, 0x2e, 0x8d, 0x19, 0x6f, 0xe9, 0x12, 0x14, 0x50, 0x80, 0x6b, 0xd0,
                 0xd9, 0x3f, 0xd0, 0xcb,
             ],
+            err: None,
         };
         let orig = pk.clone();
         let got: PublicKey = pk.try_into().unwrap();
@@ -172,11 +221,84 @@ mod tests {
         assert_eq!(round_trip_pk, orig);
     }
 
+    #[test]
+    fn test_pubkey_request_with_chain_id() {
+        let msg = PubKeyRequest::new(chain::Id::from("test_chain_id"));
+        let mut encoded = vec![];
+        msg.encode(&mut encoded).unwrap();
+
+        let decoded = PubKeyRequest::decode(encoded.as_ref()).unwrap();
+        assert_eq!(
+            decoded.chain_id().unwrap(),
+            Some(chain::Id::from("test_chain_id"))
+        );
+
+        // Legacy requests only consist of the prefix
+        let legacy = PubKeyRequest::decode([0x4, 0xcb, 0x94, 0xd6, 0x20].as_ref()).unwrap();
+        assert_eq!(legacy.chain_id().unwrap(), None);
+    }
+
+    #[test]
+    fn test_pubkey_response_with_error() {
+        let msg = PubKeyResponse::error(RemoteError {
+            code: 3,
+            description: "unknown chain ID".to_owned(),
+        });
+        let mut encoded = vec![];
+        msg.encode(&mut encoded).unwrap();
+
+        let decoded = PubKeyResponse::decode(encoded.as_ref()).unwrap();
+        assert_eq!(decoded, msg);
+
+        let err = PublicKey::try_from(decoded).unwrap_err();
+        assert!(matches!(
+            error::kind(err.as_ref()),
+            Some(error::Kind::RemoteSigner { code: 3, .. })
+        ));
+    }
+
+    #[test]
+    #[cfg(feature = "secp256k1")]
+    fn test_secp256k1_pubkey_msg() {
+        let public_key = crate::private_key::PrivateKey::from_raw_secp256k1(&[0x01; 32])
+            .unwrap()
+            .public_key();
+
+        let msg = PubKeyResponse::from(public_key);
+        assert_eq!(&msg.pub_key[..5], &[0xeb, 0x5a, 0xe9, 0x87, 0x21]);
+        assert_eq!(msg.pub_key.len(), 5 + 33);
+
+        let mut encoded = vec![];
+        msg.encode(&mut encoded).unwrap();
+
+        let decoded = PubKeyResponse::decode(encoded.as_ref()).unwrap();
+        assert_eq!(PublicKey::try_from(decoded).unwrap(), public_key);
+    }
+
+    #[test]
+    #[cfg(feature = "sr25519")]
+    fn test_sr25519_pubkey_msg() {
+        let public_key: PublicKey = serde_json::from_str(
+            "{\"type\":\"tendermint/PubKeySr25519\",\"value\":\"4vKuCmq8TnGohKlhxQBRX1jjC2qlgt2NtqZZReCNLXY=\"}",
+        )
+        .unwrap();
+
+        let msg = PubKeyResponse::from(public_key);
+        assert_eq!(&msg.pub_key[..5], &[0x0d, 0xfb, 0x10, 0x05, 0x20]);
+
+        let mut encoded = vec![];
+        msg.encode(&mut encoded).unwrap();
+
+        let decoded = PubKeyResponse::decode(encoded.as_ref()).unwrap();
+        assert_eq!(PublicKey::try_from(decoded).unwrap(), public_key);
+    }
+
     #[test]
     #[should_panic]
     fn test_empty_into() {
         let empty_msg = PubKeyResponse {
-            pub_key_ed25519: vec![],
+            pub_key: vec![],
+            err: None,
         };
         // we expect this to panic:
         let _got: PublicKey = empty_msg.try_into().unwrap();
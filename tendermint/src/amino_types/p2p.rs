@@ -0,0 +1,78 @@
+use super::registry::Registered;
+use prost_amino_derive::Message;
+
+#[derive(Clone, PartialEq, Message)]
+#[amino_name = "tendermint/p2p/PacketPing"]
+pub struct PacketPing {}
+
+#[derive(Clone, PartialEq, Message)]
+#[amino_name = "tendermint/p2p/PacketPong"]
+pub struct PacketPong {}
+
+/// Part of a message sent on a channel. Go encodes the channel ID and the
+/// `eof` flag as bytes, which amino writes as varints.
+#[derive(Clone, PartialEq, Message)]
+#[amino_name = "tendermint/p2p/PacketMsg"]
+pub struct PacketMsg {
+    #[prost_amino(uint32, tag = "1")]
+    pub channel_id: u32,
+    #[prost_amino(uint32, tag = "2")]
+    pub eof: u32,
+    #[prost_amino(bytes, tag = "3")]
+    pub bytes: Vec<u8>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+#[amino_name = "tendermint/p2p/DefaultNodeInfo"]
+pub struct DefaultNodeInfo {
+    #[prost_amino(message, tag = "1")]
+    pub protocol_version: Option<ProtocolVersion>,
+    #[prost_amino(string, tag = "2")]
+    pub id: String,
+    #[prost_amino(string, tag = "3")]
+    pub listen_addr: String,
+    #[prost_amino(string, tag = "4")]
+    pub network: String,
+    #[prost_amino(string, tag = "5")]
+    pub version: String,
+    #[prost_amino(bytes, tag = "6")]
+    pub channels: Vec<u8>,
+    #[prost_amino(string, tag = "7")]
+    pub moniker: String,
+    #[prost_amino(message, tag = "8")]
+    pub other: Option<DefaultNodeInfoOther>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct ProtocolVersion {
+    #[prost_amino(uint64, tag = "1")]
+    pub p2p: u64,
+    #[prost_amino(uint64, tag = "2")]
+    pub block: u64,
+    #[prost_amino(uint64, tag = "3")]
+    pub app: u64,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct DefaultNodeInfoOther {
+    #[prost_amino(string, tag = "1")]
+    pub tx_index: String,
+    #[prost_amino(string, tag = "2")]
+    pub rpc_address: String,
+}
+
+impl Registered for PacketPing {
+    const AMINO_NAME: &'static str = "tendermint/p2p/PacketPing";
+}
+
+impl Registered for PacketPong {
+    const AMINO_NAME: &'static str = "tendermint/p2p/PacketPong";
+}
+
+impl Registered for PacketMsg {
+    const AMINO_NAME: &'static str = "tendermint/p2p/PacketMsg";
+}
+
+impl Registered for DefaultNodeInfo {
+    const AMINO_NAME: &'static str = "tendermint/p2p/DefaultNodeInfo";
+}
@@ -0,0 +1,32 @@
+use super::registry::Registered;
+use prost_amino_derive::Message;
+
+#[derive(Clone, PartialEq, Message)]
+#[amino_name = "tendermint/p2p/PexRequestMessage"]
+pub struct PexRequestMessage {}
+
+#[derive(Clone, PartialEq, Message)]
+#[amino_name = "tendermint/p2p/PexAddrsMessage"]
+pub struct PexAddrsMessage {
+    #[prost_amino(message, repeated, tag = "1")]
+    pub addrs: Vec<NetAddress>,
+}
+
+/// Peer address, with its IP in Go's `net.IP` form: 4 or 16 bytes
+#[derive(Clone, PartialEq, Message)]
+pub struct NetAddress {
+    #[prost_amino(string, tag = "1")]
+    pub id: String,
+    #[prost_amino(bytes, tag = "2")]
+    pub ip: Vec<u8>,
+    #[prost_amino(uint32, tag = "3")]
+    pub port: u32,
+}
+
+impl Registered for PexRequestMessage {
+    const AMINO_NAME: &'static str = "tendermint/p2p/PexRequestMessage";
+}
+
+impl Registered for PexAddrsMessage {
+    const AMINO_NAME: &'static str = "tendermint/p2p/PexAddrsMessage";
+}
@@ -1,10 +1,6 @@
-use super::compute_prefix;
-use once_cell::sync::Lazy;
+use super::registry::Registered;
 use prost_amino_derive::Message;
 
-pub const AMINO_NAME: &str = "tendermint/remotesigner/PingRequest";
-pub static AMINO_PREFIX: Lazy<Vec<u8>> = Lazy::new(|| compute_prefix(AMINO_NAME));
-
 #[derive(Clone, PartialEq, Message)]
 #[amino_name = "tendermint/remotesigner/PingRequest"]
 pub struct PingRequest {}
@@ -12,3 +8,11 @@ pub struct PingRequest {}
 #[derive(Clone, PartialEq, Message)]
 #[amino_name = "tendermint/remotesigner/PingResponse"]
 pub struct PingResponse {}
+
+impl Registered for PingRequest {
+    const AMINO_NAME: &'static str = "tendermint/remotesigner/PingRequest";
+}
+
+impl Registered for PingResponse {
+    const AMINO_NAME: &'static str = "tendermint/remotesigner/PingResponse";
+}
@@ -1,21 +1,20 @@
 use super::{
     block_id::{BlockId, CanonicalBlockId, CanonicalPartSetHeader},
-    compute_prefix,
-    remote_error::RemoteError,
-    signature::{SignableMsg, SignedMsgType},
+    signature::SignedMsgType,
     time::TimeMsg,
     validate::{
-        self, ConsensusMessage, Kind::InvalidMessageType, Kind::MissingConsensusMessage,
-        Kind::NegativeHeight, Kind::NegativePOLRound, Kind::NegativeRound,
+        self, ConsensusMessage, Kind::InvalidMessageType, Kind::NegativeHeight,
+        Kind::NegativePOLRound, Kind::NegativeRound,
     },
 };
-use crate::{
-    block::{self, ParseId},
-    chain, consensus, error,
+#[cfg(feature = "amino")]
+use super::{
+    message::AminoMessage, registry::Registered, remote_error::RemoteError, signature::SignableMsg,
+    validate::Kind::MissingConsensusMessage,
 };
-use bytes::BufMut;
-use once_cell::sync::Lazy;
-use prost_amino::{EncodeError, Message};
+#[cfg(feature = "amino")]
+use crate::{account, block::ParseId, consensus, Signature};
+use crate::{block, chain, error};
 use prost_amino_derive::Message;
 use std::convert::TryFrom;
 
@@ -44,9 +43,7 @@ impl block::ParseHeight for Proposal {
     }
 }
 
-pub const AMINO_NAME: &str = "tendermint/remotesigner/SignProposalRequest";
-pub static AMINO_PREFIX: Lazy<Vec<u8>> = Lazy::new(|| compute_prefix(AMINO_NAME));
-
+#[cfg(feature = "amino")]
 #[derive(Clone, PartialEq, Message)]
 #[amino_name = "tendermint/remotesigner/SignProposalRequest"]
 pub struct SignProposalRequest {
@@ -55,24 +52,50 @@ pub struct SignProposalRequest {
 }
 
 #[derive(Clone, PartialEq, Message)]
-struct CanonicalProposal {
+pub struct CanonicalProposal {
     #[prost_amino(uint32, tag = "1")]
-    msg_type: u32, /* this is a byte in golang, which is a varint encoded UInt8 (using amino's
-                    * EncodeUvarint) */
+    pub msg_type: u32, /* this is a byte in golang, which is a varint encoded UInt8 (using amino's
+                        * EncodeUvarint) */
     #[prost_amino(sfixed64)]
-    height: i64,
+    pub height: i64,
     #[prost_amino(sfixed64)]
-    round: i64,
+    pub round: i64,
     #[prost_amino(sfixed64)]
-    pol_round: i64,
+    pub pol_round: i64,
     #[prost_amino(message)]
-    block_id: Option<CanonicalBlockId>,
+    pub block_id: Option<CanonicalBlockId>,
     #[prost_amino(message)]
-    timestamp: Option<TimeMsg>,
+    pub timestamp: Option<TimeMsg>,
     #[prost_amino(string)]
     pub chain_id: String,
 }
 
+impl CanonicalProposal {
+    pub fn new(proposal: Proposal, chain_id: &str) -> CanonicalProposal {
+        CanonicalProposal {
+            chain_id: chain_id.to_string(),
+            msg_type: SignedMsgType::Proposal.to_u32(),
+            height: proposal.height,
+            block_id: match proposal.block_id {
+                Some(bid) => Some(CanonicalBlockId {
+                    hash: bid.hash,
+                    parts_header: match bid.parts_header {
+                        Some(psh) => Some(CanonicalPartSetHeader {
+                            hash: psh.hash,
+                            total: psh.total,
+                        }),
+                        None => None,
+                    },
+                }),
+                None => None,
+            },
+            pol_round: proposal.pol_round,
+            round: proposal.round,
+            timestamp: proposal.timestamp,
+        }
+    }
+}
+
 impl chain::ParseId for CanonicalProposal {
     fn parse_chain_id(&self) -> Result<chain::Id, error::Error> {
         self.chain_id.parse()
@@ -85,6 +108,7 @@ impl block::ParseHeight for CanonicalProposal {
     }
 }
 
+#[cfg(feature = "amino")]
 #[derive(Clone, PartialEq, Message)]
 #[amino_name = "tendermint/remotesigner/SignedProposalResponse"]
 pub struct SignedProposalResponse {
@@ -94,52 +118,52 @@ pub struct SignedProposalResponse {
     pub err: Option<RemoteError>,
 }
 
+#[cfg(feature = "amino")]
+impl Registered for SignProposalRequest {
+    const AMINO_NAME: &'static str = "tendermint/remotesigner/SignProposalRequest";
+}
+
+#[cfg(feature = "amino")]
+impl Registered for SignedProposalResponse {
+    const AMINO_NAME: &'static str = "tendermint/remotesigner/SignedProposalResponse";
+}
+
+#[cfg(feature = "amino")]
 impl SignableMsg for SignProposalRequest {
-    fn sign_bytes<B>(&self, chain_id: chain::Id, sign_bytes: &mut B) -> Result<bool, EncodeError>
-    where
-        B: BufMut,
-    {
-        let mut spr = self.clone();
-        if let Some(ref mut pr) = spr.proposal {
-            pr.signature = vec![];
+    fn sign_bytes(&self, chain_id: chain::Id) -> Result<Vec<u8>, validate::Error> {
+        let proposal = self.proposal.as_ref().ok_or(MissingConsensusMessage)?;
+        if let Some(ref timestamp) = proposal.timestamp {
+            timestamp.validate_basic()?;
         }
-        let proposal = spr.proposal.unwrap();
-        let cp = CanonicalProposal {
-            chain_id: chain_id.to_string(),
-            msg_type: SignedMsgType::Proposal.to_u32(),
-            height: proposal.height,
-            block_id: match proposal.block_id {
-                Some(bid) => Some(CanonicalBlockId {
-                    hash: bid.hash,
-                    parts_header: match bid.parts_header {
-                        Some(psh) => Some(CanonicalPartSetHeader {
-                            hash: psh.hash,
-                            total: psh.total,
-                        }),
-                        None => None,
-                    },
-                }),
-                None => None,
-            },
-            pol_round: proposal.pol_round,
-            round: proposal.round,
-            timestamp: proposal.timestamp,
-        };
 
-        cp.encode_length_delimited(sign_bytes)?;
-        Ok(true)
+        let cp = CanonicalProposal::new(proposal.clone(), chain_id.as_str());
+
+        Ok(cp.bytes_vec_length_delimited())
     }
-    fn set_signature(&mut self, sig: &ed25519::Signature) {
+    fn set_signature(&mut self, sig: &Signature) {
         if let Some(ref mut prop) = self.proposal {
-            prop.signature = sig.as_ref().to_vec();
+            prop.signature = sig.to_bytes();
         }
     }
+    fn signature(&self) -> Option<&[u8]> {
+        self.proposal
+            .as_ref()
+            .map(|proposal| proposal.signature.as_slice())
+            .filter(|signature| !signature.is_empty())
+    }
     fn validate(&self) -> Result<(), validate::Error> {
         match self.proposal {
             Some(ref p) => p.validate_basic(),
             None => Err(MissingConsensusMessage.into()),
         }
     }
+    fn validate_signer(&self, _validator_address: &account::Id) -> Result<(), validate::Error> {
+        // Proposals don't carry the proposer's address
+        self.proposal
+            .as_ref()
+            .map(|_| ())
+            .ok_or_else(|| MissingConsensusMessage.into())
+    }
     fn consensus_state(&self) -> Option<consensus::State> {
         match self.proposal {
             Some(ref p) => Some(consensus::State {
@@ -148,7 +172,7 @@ impl SignableMsg for SignProposalRequest {
                     Err(_err) => return None, // TODO(tarcieri): return an error?
                 },
                 round: p.round,
-                step: 3,
+                step: consensus::Step::Propose,
                 block_id: {
                     match p.block_id {
                         Some(ref b) => match b.parse_block_id() {
@@ -186,6 +210,9 @@ impl ConsensusMessage for Proposal {
         if self.pol_round < -1 {
             return Err(NegativePOLRound.into());
         }
+        if let Some(ref timestamp) = self.timestamp {
+            timestamp.validate_basic()?;
+        }
         // TODO validate proposal's block_id
 
         // signature will be missing as the KMS provides it
@@ -194,7 +221,7 @@ impl ConsensusMessage for Proposal {
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "amino"))]
 mod tests {
     use super::*;
     use crate::amino_types::block_id::PartsSetHeader;
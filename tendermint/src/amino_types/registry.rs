@@ -0,0 +1,297 @@
+//! Registry of amino message types, to recognize and decode messages by the
+//! prefix amino derives from the name of their registered type.
+//!
+//! Message types implement [`Registered`] with their type name. A
+//! [`Registry`] maps the prefixes of registered types to their names, and a
+//! [`Decoder`] dispatches on the prefix to decode messages of its registered
+//! types, e.g. into a [`RemoteSignerMessage`]: supporting another wire
+//! message only takes registering its type.
+//!
+//! The remote signer messages of Tendermint 0.33 are registered by default;
+//! signers serving other Tendermint versions can register theirs.
+
+use super::{
+    compute_prefix, PingRequest, PingResponse, PubKeyRequest, PubKeyResponse, SignProposalRequest,
+    SignVoteRequest, SignedProposalResponse, SignedVoteResponse,
+};
+use crate::{error::Kind, Error};
+use anomaly::{fail, format_err};
+use std::{collections::BTreeMap, fmt};
+
+/// Amino message type registered under a type name, whose encodings start
+/// with the prefix derived from that name.
+///
+/// The name must be the one in the type's `#[amino_name]` attribute, from
+/// which `prost_amino` derives the prefix it encodes.
+pub trait Registered: prost_amino::Message + Default {
+    /// Registered type name
+    const AMINO_NAME: &'static str;
+
+    /// Prefix of the encodings of this type
+    fn amino_prefix() -> Vec<u8> {
+        compute_prefix(Self::AMINO_NAME)
+    }
+}
+
+/// Registered amino type names, by prefix
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Registry {
+    types: BTreeMap<Vec<u8>, String>,
+}
+
+impl Registry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Registry {
+            types: BTreeMap::new(),
+        }
+    }
+
+    /// Register the given type name. Registering a name twice is allowed,
+    /// but registering a name whose prefix is taken by another isn't.
+    pub fn register(&mut self, name: &str) -> Result<&mut Self, Error> {
+        let prefix = compute_prefix(name);
+
+        match self.types.get(&prefix) {
+            Some(registered) if registered != name => fail!(
+                Kind::Parse,
+                "amino prefix of {} is already registered for {}",
+                name,
+                registered
+            ),
+            Some(_) => (),
+            None => {
+                self.types.insert(prefix, name.to_owned());
+            }
+        }
+
+        Ok(self)
+    }
+
+    /// Register the name of the given message type
+    pub fn register_type<M: Registered>(&mut self) -> Result<&mut Self, Error> {
+        self.register(M::AMINO_NAME)
+    }
+
+    /// Get the prefix of the given type name, if registered
+    pub fn prefix(&self, name: &str) -> Option<&[u8]> {
+        self.types
+            .iter()
+            .find(|(_, registered)| registered.as_str() == name)
+            .map(|(prefix, _)| prefix.as_slice())
+    }
+
+    /// Get the name of the registered type of the given amino encoded message,
+    /// if its prefix is registered
+    pub fn type_name(&self, message: &[u8]) -> Option<&str> {
+        self.types
+            .iter()
+            .find(|(prefix, _)| message.starts_with(prefix))
+            .map(|(_, name)| name.as_str())
+    }
+
+    /// Iterate over the registered type names
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.types.values().map(String::as_str)
+    }
+}
+
+impl Default for Registry {
+    /// Registry of the remote signer messages of Tendermint 0.33
+    fn default() -> Self {
+        Decoder::<RemoteSignerMessage>::default().registry
+    }
+}
+
+/// Decodes amino messages of its registered types into `T`, dispatching on
+/// their prefix
+#[derive(Clone)]
+pub struct Decoder<T> {
+    registry: Registry,
+    decoders: BTreeMap<Vec<u8>, fn(&[u8]) -> Result<T, Error>>,
+}
+
+impl<T> Decoder<T> {
+    /// Create a decoder without registered types
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Decoder {
+            registry: Registry::new(),
+            decoders: BTreeMap::new(),
+        }
+    }
+
+    /// Register the given message type, which is decoded into `T` (see
+    /// [`Registry::register`])
+    pub fn register<M>(&mut self) -> Result<&mut Self, Error>
+    where
+        M: Registered + Into<T>,
+    {
+        self.registry.register_type::<M>()?;
+        self.decoders.insert(M::amino_prefix(), decode_as::<M, T>);
+        Ok(self)
+    }
+
+    /// Get the registered type names
+    pub fn registry(&self) -> &Registry {
+        &self.registry
+    }
+
+    /// Decode the given amino encoded message (without its length prefix)
+    /// as the registered type its prefix belongs to
+    pub fn decode(&self, message: &[u8]) -> Result<T, Error> {
+        let decode = self
+            .decoders
+            .iter()
+            .find(|(prefix, _)| message.starts_with(prefix))
+            .map(|(_, decode)| decode)
+            .ok_or_else(|| format_err!(Kind::Parse, "unregistered amino message type"))?;
+
+        decode(message)
+    }
+}
+
+impl<T> fmt::Debug for Decoder<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Decoder")
+            .field("registry", &self.registry)
+            .finish()
+    }
+}
+
+fn decode_as<M, T>(message: &[u8]) -> Result<T, Error>
+where
+    M: Registered + Into<T>,
+{
+    M::decode(message)
+        .map(Into::into)
+        .map_err(|e| Kind::Parse.context(e).into())
+}
+
+/// Remote signer ("privval") message of Tendermint 0.33
+#[derive(Clone, Debug, PartialEq)]
+pub enum RemoteSignerMessage {
+    PubKeyRequest(PubKeyRequest),
+    PubKeyResponse(PubKeyResponse),
+    SignVoteRequest(SignVoteRequest),
+    SignedVoteResponse(SignedVoteResponse),
+    SignProposalRequest(SignProposalRequest),
+    SignedProposalResponse(SignedProposalResponse),
+    PingRequest(PingRequest),
+    PingResponse(PingResponse),
+}
+
+macro_rules! remote_signer_messages {
+    ($($message:ident),*) => {
+        $(
+            impl From<$message> for RemoteSignerMessage {
+                fn from(message: $message) -> Self {
+                    RemoteSignerMessage::$message(message)
+                }
+            }
+        )*
+
+        impl Default for Decoder<RemoteSignerMessage> {
+            /// Decoder of the remote signer messages of Tendermint 0.33
+            fn default() -> Self {
+                let mut decoder = Decoder::new();
+                $(
+                    decoder
+                        .register::<$message>()
+                        .expect("remote signer amino prefixes collide");
+                )*
+                decoder
+            }
+        }
+    };
+}
+
+remote_signer_messages!(
+    PubKeyRequest,
+    PubKeyResponse,
+    SignVoteRequest,
+    SignedVoteResponse,
+    SignProposalRequest,
+    SignedProposalResponse,
+    PingRequest,
+    PingResponse
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{amino_types::message::AminoMessage, chain};
+
+    /// Check the registered name of a type matches its `#[amino_name]`
+    fn assert_prefix<M: Registered>() {
+        assert!(M::default().bytes_vec().starts_with(&M::amino_prefix()));
+    }
+
+    #[test]
+    fn registered_names_match_encoded_prefixes() {
+        assert_prefix::<PubKeyRequest>();
+        assert_prefix::<PubKeyResponse>();
+        assert_prefix::<SignVoteRequest>();
+        assert_prefix::<SignedVoteResponse>();
+        assert_prefix::<SignProposalRequest>();
+        assert_prefix::<SignedProposalResponse>();
+        assert_prefix::<PingRequest>();
+        assert_prefix::<PingResponse>();
+    }
+
+    #[test]
+    fn recognizes_remote_signer_messages() {
+        let registry = Registry::default();
+        assert_eq!(registry.names().count(), 8);
+        assert_eq!(
+            registry.prefix(SignVoteRequest::AMINO_NAME),
+            Some(SignVoteRequest::amino_prefix().as_slice())
+        );
+
+        let ping = PingRequest {}.bytes_vec();
+        assert_eq!(registry.type_name(&ping), Some(PingRequest::AMINO_NAME));
+        assert_eq!(registry.type_name(&[0x0a, 0x00]), None);
+    }
+
+    #[test]
+    fn decodes_registered_messages() {
+        let decoder = Decoder::<RemoteSignerMessage>::default();
+
+        let request = PubKeyRequest::new(chain::Id::from("test_chain_id"));
+        assert_eq!(
+            decoder.decode(&request.bytes_vec()).unwrap(),
+            RemoteSignerMessage::PubKeyRequest(request)
+        );
+        assert_eq!(
+            decoder.decode(&PingResponse {}.bytes_vec()).unwrap(),
+            RemoteSignerMessage::PingResponse(PingResponse {})
+        );
+        assert!(decoder.decode(&[0x0a, 0x00]).is_err());
+
+        // Only registered types are decoded
+        let mut pings = Decoder::<RemoteSignerMessage>::new();
+        pings.register::<PingRequest>().unwrap();
+        assert_eq!(pings.registry().names().count(), 1);
+        assert!(pings.decode(&PingRequest {}.bytes_vec()).is_ok());
+        assert!(pings.decode(&PingResponse {}.bytes_vec()).is_err());
+    }
+
+    #[test]
+    fn registers_custom_types() {
+        let mut registry = Registry::new();
+        registry
+            .register("tendermint/remotesigner/SignVoteRequest")
+            .unwrap()
+            .register("example/SignVoteRequest")
+            .unwrap()
+            .register("example/SignVoteRequest")
+            .unwrap();
+
+        assert_eq!(registry.names().count(), 2);
+        assert_eq!(
+            registry.prefix("example/SignVoteRequest"),
+            Some(compute_prefix("example/SignVoteRequest").as_slice())
+        );
+        assert_eq!(registry.prefix("example/PingRequest"), None);
+    }
+}
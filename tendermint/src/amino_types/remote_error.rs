@@ -19,6 +19,21 @@ pub enum RemoteErrorCode {
 
     /// Double signing detected
     DoubleSignError = 2,
+
+    /// Request for a chain the signer doesn't sign for
+    ChainIdMismatch = 3,
+}
+
+impl RemoteErrorCode {
+    /// Parse an error code from its numeric value
+    pub fn from_i32(code: i32) -> Option<Self> {
+        match code {
+            1 => Some(RemoteErrorCode::RemoteSignerError),
+            2 => Some(RemoteErrorCode::DoubleSignError),
+            3 => Some(RemoteErrorCode::ChainIdMismatch),
+            _ => None,
+        }
+    }
 }
 
 impl RemoteError {
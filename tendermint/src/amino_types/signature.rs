@@ -1,57 +1,49 @@
+#[cfg(feature = "amino")]
 use super::validate;
-use crate::{chain, consensus};
-use bytes::BufMut;
-use prost_amino::{DecodeError, EncodeError};
+#[cfg(feature = "amino")]
+use crate::{account, chain, consensus, error::Kind, Error, PublicKey, Signature};
+#[cfg(feature = "amino")]
+use anomaly::format_err;
+#[cfg(feature = "amino")]
+use std::convert::TryFrom;
+
+pub use crate::SignedMsgType;
 
 /// Amino messages which are signable within a Tendermint network
+#[cfg(feature = "amino")]
 pub trait SignableMsg {
-    /// Sign this message as bytes
-    fn sign_bytes<B: BufMut>(
-        &self,
-        chain_id: chain::Id,
-        sign_bytes: &mut B,
-    ) -> Result<bool, EncodeError>;
+    /// Get the canonical bytes to sign for this message on the given chain.
+    ///
+    /// The canonical form has no signature field, so the bytes are the same
+    /// whether or not the message is signed already.
+    fn sign_bytes(&self, chain_id: chain::Id) -> Result<Vec<u8>, validate::Error>;
 
-    /// Set the Ed25519 signature on the underlying message
-    fn set_signature(&mut self, sig: &ed25519::Signature);
-    fn validate(&self) -> Result<(), validate::Error>;
-    fn consensus_state(&self) -> Option<consensus::State>;
-    fn height(&self) -> Option<i64>;
-    fn msg_type(&self) -> Option<SignedMsgType>;
-}
+    /// Set the signature on the underlying message: Ed25519 or, for chains
+    /// with secp256k1 consensus keys, ECDSA/secp256k1 (both are 64 bytes)
+    fn set_signature(&mut self, sig: &Signature);
 
-/// Signed message types. This follows:
-/// <https://github.com/tendermint/tendermint/blob/455d34134cc53c334ebd3195ac22ea444c4b59bb/types/signed_msg_type.go#L3-L16>
-#[derive(Copy, Clone, Debug)]
-pub enum SignedMsgType {
-    /// Votes
-    PreVote,
+    /// Get the signature of the underlying message, if it's signed
+    fn signature(&self) -> Option<&[u8]>;
 
-    /// Commits
-    PreCommit,
+    /// Verify the signature of the underlying message was produced on the
+    /// given chain by the holder of the given public key, recomputing the
+    /// canonical sign bytes
+    fn verify(&self, chain_id: chain::Id, pub_key: &PublicKey) -> Result<(), Error> {
+        let signature = self
+            .signature()
+            .ok_or_else(|| format_err!(Kind::SignatureInvalid, "message is unsigned"))?;
 
-    /// Proposals
-    Proposal,
-}
-
-impl SignedMsgType {
-    pub fn to_u32(self) -> u32 {
-        match self {
-            // Votes
-            SignedMsgType::PreVote => 0x01,
-            SignedMsgType::PreCommit => 0x02,
-            // Proposals
-            SignedMsgType::Proposal => 0x20,
-        }
+        pub_key.verify(
+            &self.sign_bytes(chain_id)?,
+            &Signature::try_from(signature)?,
+        )
     }
+    fn validate(&self) -> Result<(), validate::Error>;
 
-    #[allow(dead_code)]
-    fn from(data: u32) -> Result<SignedMsgType, DecodeError> {
-        match data {
-            0x01 => Ok(SignedMsgType::PreVote),
-            0x02 => Ok(SignedMsgType::PreCommit),
-            0x20 => Ok(SignedMsgType::Proposal),
-            _ => Err(DecodeError::new("Invalid vote type")),
-        }
-    }
+    /// Check this message may be signed by the validator with the given
+    /// address, comparing addresses in constant time
+    fn validate_signer(&self, validator_address: &account::Id) -> Result<(), validate::Error>;
+    fn consensus_state(&self) -> Option<consensus::State>;
+    fn height(&self) -> Option<i64>;
+    fn msg_type(&self) -> Option<SignedMsgType>;
 }
@@ -1,12 +1,14 @@
 //! Timestamps
 
+use super::validate::{self, ConsensusMessage, Kind::InvalidTimestamp};
 use crate::{
     error::Error,
     time::{ParseTimestamp, Time},
 };
-use chrono::{TimeZone, Utc};
 use prost_amino_derive::Message;
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use prost_types::Timestamp;
+use std::convert::TryFrom;
+use std::time::SystemTime;
 
 #[derive(Clone, PartialEq, Message)]
 pub struct TimeMsg {
@@ -19,30 +21,124 @@ pub struct TimeMsg {
     pub nanos: i32,
 }
 
+impl TimeMsg {
+    /// Is this timestamp normalized, with nanoseconds in [0, 999_999_999]
+    /// even before the epoch?
+    ///
+    /// Go only encodes timestamps this way, so canonical sign bytes with any
+    /// other form of the same time would never match those of Go signers.
+    pub fn is_normalized(&self) -> bool {
+        self.nanos >= 0 && self.nanos <= 999_999_999
+    }
+}
+
+impl ConsensusMessage for TimeMsg {
+    fn validate_basic(&self) -> Result<(), validate::Error> {
+        if self.is_normalized() {
+            Ok(())
+        } else {
+            Err(InvalidTimestamp.into())
+        }
+    }
+}
+
 impl ParseTimestamp for TimeMsg {
     fn parse_timestamp(&self) -> Result<Time, Error> {
-        Ok(Utc.timestamp(self.seconds, self.nanos as u32).into())
+        Time::try_from(Timestamp {
+            seconds: self.seconds,
+            nanos: self.nanos,
+        })
     }
 }
 
 impl From<Time> for TimeMsg {
+    /// Convert to the normalized form (see [`TimeMsg::is_normalized`]),
+    /// including for times before the epoch
     fn from(ts: Time) -> TimeMsg {
-        // TODO: non-panicking method for getting this?
-        let duration = ts.duration_since(Time::unix_epoch()).unwrap();
-        let seconds = duration.as_secs() as i64;
-        let nanos = duration.subsec_nanos() as i32;
-
+        let Timestamp { seconds, nanos } = ts.into();
         TimeMsg { seconds, nanos }
     }
 }
 
-/// Converts `Time` to a `SystemTime`.
-impl From<TimeMsg> for SystemTime {
-    fn from(time: TimeMsg) -> SystemTime {
-        if time.seconds >= 0 {
-            UNIX_EPOCH + Duration::new(time.seconds as u64, time.nanos as u32)
-        } else {
-            UNIX_EPOCH - Duration::new(time.seconds as u64, time.nanos as u32)
+/// Converts a timestamp received from a peer to a `SystemTime`, failing on
+/// timestamps which aren't normalized or which `SystemTime` can't represent
+impl TryFrom<TimeMsg> for SystemTime {
+    type Error = Error;
+
+    fn try_from(time: TimeMsg) -> Result<SystemTime, Error> {
+        time.parse_timestamp()?.to_system_time()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    #[test]
+    fn converts_to_normalized_timestamps() {
+        let before_epoch = Time::from(Utc.timestamp(-2, 250_000_000));
+        assert_eq!(
+            TimeMsg::from(before_epoch),
+            TimeMsg {
+                seconds: -2,
+                nanos: 250_000_000
+            }
+        );
+
+        let leap_second = Time::from(
+            Utc.ymd(2016, 12, 31)
+                .and_hms_nano(23, 59, 59, 1_500_000_000),
+        );
+        let msg = TimeMsg::from(leap_second);
+        assert!(msg.is_normalized());
+        assert_eq!(
+            msg.seconds,
+            Utc.ymd(2017, 1, 1).and_hms(0, 0, 0).timestamp()
+        );
+        assert_eq!(msg.nanos, 500_000_000);
+    }
+
+    #[test]
+    fn rejects_unnormalized_timestamps() {
+        for nanos in &[-1, 1_000_000_000] {
+            let msg = TimeMsg {
+                seconds: 1,
+                nanos: *nanos,
+            };
+            assert!(!msg.is_normalized());
+            assert!(msg.validate_basic().is_err());
+            assert!(msg.parse_timestamp().is_err());
+        }
+
+        let msg = TimeMsg {
+            seconds: -1,
+            nanos: 999_999_999,
+        };
+        assert!(msg.validate_basic().is_ok());
+        assert_eq!(
+            msg.parse_timestamp().unwrap(),
+            Time::from(Utc.timestamp(-1, 999_999_999))
+        );
+    }
+
+    #[test]
+    fn converts_to_system_times() {
+        let before_epoch = TimeMsg {
+            seconds: -2,
+            nanos: 250_000_000,
+        };
+        assert_eq!(
+            SystemTime::try_from(before_epoch).unwrap(),
+            std::time::UNIX_EPOCH - std::time::Duration::from_millis(1750)
+        );
+
+        for (seconds, nanos) in &[(i64::MAX, 999_999_999), (i64::MIN, 0), (1, -1)] {
+            let msg = TimeMsg {
+                seconds: *seconds,
+                nanos: *nanos,
+            };
+            assert!(SystemTime::try_from(msg).is_err());
         }
     }
 }
@@ -27,4 +27,8 @@ pub enum Kind {
     InvalidHashSize,
     #[error("negative total")]
     NegativeTotal,
+    #[error("validator address doesn't match the signer")]
+    ValidatorAddressMismatch,
+    #[error("timestamp nanoseconds must be within [0, 999999999]")]
+    InvalidTimestamp,
 }
@@ -15,8 +15,8 @@ pub struct ConsensusVersion {
 impl From<&header::Version> for ConsensusVersion {
     fn from(version: &header::Version) -> Self {
         ConsensusVersion {
-            block: version.block,
-            app: version.app,
+            block: version.block.value(),
+            app: version.app.value(),
         }
     }
 }
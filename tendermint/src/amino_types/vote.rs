@@ -1,25 +1,22 @@
 use super::{
     block_id::{BlockId, CanonicalBlockId, CanonicalPartSetHeader},
-    compute_prefix,
-    remote_error::RemoteError,
-    signature::SignableMsg,
     time::TimeMsg,
     validate,
     validate::{ConsensusMessage, Kind::*},
     SignedMsgType,
 };
-use crate::amino_types::PartsSetHeader;
-use crate::{
-    block::{self, ParseId},
-    chain, consensus,
-    error::Error,
-    vote,
+#[cfg(feature = "amino")]
+use super::{
+    message::AminoMessage, registry::Registered, remote_error::RemoteError, signature::SignableMsg,
 };
-use bytes::BufMut;
-use once_cell::sync::Lazy;
-use prost_amino::{error::EncodeError, Message};
+use crate::amino_types::PartsSetHeader;
+#[cfg(feature = "amino")]
+use crate::{account, block::ParseId, consensus, Signature};
+use crate::{block, chain, error::Error, vote};
 use prost_amino_derive::Message;
 use std::convert::TryFrom;
+#[cfg(feature = "amino")]
+use subtle::ConstantTimeEq;
 
 const VALIDATOR_ADDR_SIZE: usize = 20;
 
@@ -45,13 +42,7 @@ pub struct Vote {
 
 impl Vote {
     fn msg_type(&self) -> Option<SignedMsgType> {
-        if self.vote_type == SignedMsgType::PreVote.to_u32() {
-            Some(SignedMsgType::PreVote)
-        } else if self.vote_type == SignedMsgType::PreCommit.to_u32() {
-            Some(SignedMsgType::PreCommit)
-        } else {
-            None
-        }
+        SignedMsgType::from_u32(self.vote_type).filter(|msg_type| msg_type.is_vote())
     }
 }
 
@@ -79,9 +70,7 @@ impl block::ParseHeight for Vote {
     }
 }
 
-pub const AMINO_NAME: &str = "tendermint/remotesigner/SignVoteRequest";
-pub static AMINO_PREFIX: Lazy<Vec<u8>> = Lazy::new(|| compute_prefix(AMINO_NAME));
-
+#[cfg(feature = "amino")]
 #[derive(Clone, PartialEq, Message)]
 #[amino_name = "tendermint/remotesigner/SignVoteRequest"]
 pub struct SignVoteRequest {
@@ -89,6 +78,7 @@ pub struct SignVoteRequest {
     pub vote: Option<Vote>,
 }
 
+#[cfg(feature = "amino")]
 #[derive(Clone, PartialEq, Message)]
 #[amino_name = "tendermint/remotesigner/SignedVoteResponse"]
 pub struct SignedVoteResponse {
@@ -98,6 +88,16 @@ pub struct SignedVoteResponse {
     pub err: Option<RemoteError>,
 }
 
+#[cfg(feature = "amino")]
+impl Registered for SignVoteRequest {
+    const AMINO_NAME: &'static str = "tendermint/remotesigner/SignVoteRequest";
+}
+
+#[cfg(feature = "amino")]
+impl Registered for SignedVoteResponse {
+    const AMINO_NAME: &'static str = "tendermint/remotesigner/SignedVoteResponse";
+}
+
 #[derive(Clone, PartialEq, Message)]
 pub struct CanonicalVote {
     #[prost_amino(uint32, tag = "1")]
@@ -114,6 +114,20 @@ pub struct CanonicalVote {
     pub chain_id: String,
 }
 
+/// Vote extension in the form validators sign (Tendermint 0.38)
+#[cfg(feature = "vote-extensions")]
+#[derive(Clone, PartialEq, Message)]
+pub struct CanonicalVoteExtension {
+    #[prost_amino(bytes, tag = "1")]
+    pub extension: Vec<u8>,
+    #[prost_amino(sfixed64)]
+    pub height: i64,
+    #[prost_amino(sfixed64)]
+    pub round: i64,
+    #[prost_amino(string)]
+    pub chain_id: String,
+}
+
 impl chain::ParseId for CanonicalVote {
     fn parse_chain_id(&self) -> Result<chain::Id, Error> {
         self.chain_id.parse()
@@ -157,33 +171,46 @@ impl CanonicalVote {
     }
 }
 
+#[cfg(feature = "amino")]
 impl SignableMsg for SignVoteRequest {
-    fn sign_bytes<B>(&self, chain_id: chain::Id, sign_bytes: &mut B) -> Result<bool, EncodeError>
-    where
-        B: BufMut,
-    {
-        let mut svr = self.clone();
-        if let Some(ref mut vo) = svr.vote {
-            vo.signature = vec![];
+    fn sign_bytes(&self, chain_id: chain::Id) -> Result<Vec<u8>, validate::Error> {
+        let vote = self.vote.as_ref().ok_or(MissingConsensusMessage)?;
+        if let Some(ref timestamp) = vote.timestamp {
+            timestamp.validate_basic()?;
         }
-        let vote = svr.vote.unwrap();
-        let cv = CanonicalVote::new(vote, chain_id.as_str());
 
-        cv.encode_length_delimited(sign_bytes)?;
+        let cv = CanonicalVote::new(vote.clone(), chain_id.as_str());
 
-        Ok(true)
+        Ok(cv.bytes_vec_length_delimited())
     }
-    fn set_signature(&mut self, sig: &ed25519::Signature) {
+    fn set_signature(&mut self, sig: &Signature) {
         if let Some(ref mut vt) = self.vote {
-            vt.signature = sig.as_ref().to_vec();
+            vt.signature = sig.to_bytes();
         }
     }
+    fn signature(&self) -> Option<&[u8]> {
+        self.vote
+            .as_ref()
+            .map(|vote| vote.signature.as_slice())
+            .filter(|signature| !signature.is_empty())
+    }
     fn validate(&self) -> Result<(), validate::Error> {
         match self.vote {
             Some(ref v) => v.validate_basic(),
             None => Err(MissingConsensusMessage.into()),
         }
     }
+    fn validate_signer(&self, validator_address: &account::Id) -> Result<(), validate::Error> {
+        let vote = self.vote.as_ref().ok_or(MissingConsensusMessage)?;
+        let vote_address = account::Id::try_from(vote.validator_address.clone())
+            .map_err(|_| InvalidValidatorAddressSize)?;
+
+        if bool::from(vote_address.ct_eq(validator_address)) {
+            Ok(())
+        } else {
+            Err(ValidatorAddressMismatch.into())
+        }
+    }
     fn consensus_state(&self) -> Option<consensus::State> {
         match self.vote {
             Some(ref v) => Some(consensus::State {
@@ -192,7 +219,7 @@ impl SignableMsg for SignVoteRequest {
                     Err(_err) => return None, // TODO(tarcieri): return an error?
                 },
                 round: v.round,
-                step: 6,
+                step: consensus::Step::from(v.msg_type()?),
                 block_id: {
                     match v.block_id {
                         Some(ref b) => match b.parse_block_id() {
@@ -231,6 +258,9 @@ impl ConsensusMessage for Vote {
         if self.validator_address.len() != VALIDATOR_ADDR_SIZE {
             return Err(InvalidValidatorAddressSize.into());
         }
+        if let Some(ref timestamp) = self.timestamp {
+            timestamp.validate_basic()?;
+        }
 
         self.block_id
             .as_ref()
@@ -240,13 +270,13 @@ impl ConsensusMessage for Vote {
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "amino"))]
 mod tests {
     use super::super::PartsSetHeader;
     use super::*;
-    use crate::amino_types::message::AminoMessage;
     use crate::amino_types::SignedMsgType;
     use chrono::{DateTime, Utc};
+    use prost_amino::Message;
 
     #[test]
     fn test_vote_serialization() {
@@ -256,7 +286,7 @@ mod tests {
             nanos: dt.timestamp_subsec_nanos() as i32,
         };
         let vote = Vote {
-            vote_type: SignedMsgType::PreVote.to_u32(),
+            vote_type: SignedMsgType::Prevote.to_u32(),
             height: 12345,
             round: 2,
             timestamp: Some(t),
@@ -332,7 +362,7 @@ mod tests {
             let mut vt_precommit = Vote::default();
             vt_precommit.height = 1;
             vt_precommit.round = 1;
-            vt_precommit.vote_type = SignedMsgType::PreCommit.to_u32(); // precommit
+            vt_precommit.vote_type = SignedMsgType::Precommit.to_u32(); // precommit
             println!("{:?}", vt_precommit);
             let cv_precommit = CanonicalVote::new(vt_precommit, "");
             let got = AminoMessage::bytes_vec(&cv_precommit);
@@ -354,7 +384,7 @@ mod tests {
             let mut vt_prevote = Vote::default();
             vt_prevote.height = 1;
             vt_prevote.round = 1;
-            vt_prevote.vote_type = SignedMsgType::PreVote.to_u32();
+            vt_prevote.vote_type = SignedMsgType::Prevote.to_u32();
 
             let cv_prevote = CanonicalVote::new(vt_prevote, "");
 
@@ -465,6 +495,106 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_sign_bytes_exclude_signature() {
+        let chain_id = chain::Id::from("test_chain_id");
+        let mut svr = SignVoteRequest {
+            vote: Some(Vote {
+                vote_type: 0x01,
+                height: 12345,
+                round: 2,
+                ..Vote::default()
+            }),
+        };
+
+        let unsigned = svr.sign_bytes(chain_id).unwrap();
+        svr.set_signature(&ed25519::Signature::new([0x42; 64]).into());
+        assert_eq!(svr.sign_bytes(chain_id).unwrap(), unsigned);
+
+        assert!(SignVoteRequest { vote: None }.sign_bytes(chain_id).is_err());
+    }
+
+    #[test]
+    fn test_sign_bytes_reject_unnormalized_timestamps() {
+        let chain_id = chain::Id::from("test_chain_id");
+        let mut svr = SignVoteRequest {
+            vote: Some(Vote {
+                vote_type: 0x01,
+                height: 12345,
+                round: 2,
+                timestamp: Some(TimeMsg {
+                    seconds: 1,
+                    nanos: -1,
+                }),
+                ..Vote::default()
+            }),
+        };
+        assert!(svr.sign_bytes(chain_id).is_err());
+
+        svr.vote.as_mut().unwrap().timestamp = Some(TimeMsg {
+            seconds: 0,
+            nanos: 999_999_999,
+        });
+        assert!(svr.sign_bytes(chain_id).is_ok());
+    }
+
+    #[test]
+    fn test_verify() {
+        use ed25519_dalek::{Keypair, SecretKey, Signer};
+
+        let secret = SecretKey::from_bytes(&[0x42; 32]).unwrap();
+        let public = (&secret).into();
+        let keypair = Keypair { secret, public };
+        let pub_key = crate::PublicKey::from(public);
+
+        let chain_id = chain::Id::from("test_chain_id");
+        let mut svr = SignVoteRequest {
+            vote: Some(Vote {
+                vote_type: 0x02,
+                height: 12345,
+                round: 2,
+                ..Vote::default()
+            }),
+        };
+        assert!(svr.verify(chain_id, &pub_key).is_err());
+
+        let signature = keypair.sign(&svr.sign_bytes(chain_id).unwrap());
+        svr.set_signature(&signature.into());
+        assert!(svr.verify(chain_id, &pub_key).is_ok());
+        assert!(svr
+            .verify(chain::Id::from("other_chain_id"), &pub_key)
+            .is_err());
+
+        svr.vote.as_mut().unwrap().round = 3;
+        assert!(svr.verify(chain_id, &pub_key).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "secp256k1")]
+    fn test_verify_secp256k1() {
+        use crate::private_key::PrivateKey;
+        use signature::Signer;
+
+        let private_key = PrivateKey::from_raw_secp256k1(&[0x01; 32]).unwrap();
+        let pub_key = private_key.public_key();
+        let signer = k256::ecdsa::Signer::new(private_key.secp256k1_secret_key().unwrap()).unwrap();
+
+        let chain_id = chain::Id::from("test_chain_id");
+        let mut svr = SignVoteRequest {
+            vote: Some(Vote {
+                vote_type: 0x02,
+                height: 12345,
+                round: 2,
+                ..Vote::default()
+            }),
+        };
+
+        let signature: k256::ecdsa::Signature = signer.sign(&svr.sign_bytes(chain_id).unwrap());
+        svr.set_signature(&signature.into());
+        assert_eq!(svr.signature().map(<[u8]>::len), Some(64));
+        assert!(svr.verify(chain_id, &pub_key).is_ok());
+    }
+
     #[test]
     fn test_deserialization() {
         let encoded = vec![
@@ -506,4 +636,36 @@ mod tests {
             Err(err) => panic!(err.to_string()),
         }
     }
+
+    #[test]
+    fn test_validate_signer() {
+        let address = account::Id::new([0xA3; 20]);
+        let vote = Vote {
+            validator_address: address.as_bytes().to_vec(),
+            validator_index: 1,
+            height: 12345,
+            round: 2,
+            timestamp: None,
+            vote_type: 0x01,
+            block_id: None,
+            signature: vec![],
+        };
+        let svr = SignVoteRequest {
+            vote: Some(vote.clone()),
+        };
+
+        assert!(svr.validate_signer(&address).is_ok());
+        assert!(svr.validate_signer(&account::Id::new([0xA4; 20])).is_err());
+
+        let truncated = SignVoteRequest {
+            vote: Some(Vote {
+                validator_address: vec![0xA3; 19],
+                ..vote
+            }),
+        };
+        assert!(truncated.validate_signer(&address).is_err());
+        assert!(SignVoteRequest { vote: None }
+            .validate_signer(&address)
+            .is_err());
+    }
 }
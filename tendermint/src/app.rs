@@ -0,0 +1,68 @@
+//! Applications running on top of Tendermint
+
+use crate::error::{Error, Kind};
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+use std::{
+    fmt::{self, Debug, Display},
+    str::FromStr,
+};
+
+/// Application protocol version, as defined by the ABCI application and
+/// reported in block headers and node information.
+///
+/// Versions are ordered, so tooling can branch on the protocol an application
+/// was running when it produced a block.
+#[derive(Copy, Clone, Default, Eq, Hash, PartialEq, PartialOrd, Ord)]
+pub struct Version(pub u64);
+
+impl Version {
+    /// Get inner integer value. Alternative to `.0` or `.into()`
+    pub fn value(self) -> u64 {
+        self.0
+    }
+}
+
+impl Debug for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "app::Version({})", self.0)
+    }
+}
+
+impl Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl From<u64> for Version {
+    fn from(n: u64) -> Version {
+        Version(n)
+    }
+}
+
+impl From<Version> for u64 {
+    fn from(version: Version) -> u64 {
+        version.0
+    }
+}
+
+impl FromStr for Version {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        Ok(Version(s.parse::<u64>().map_err(|e| Kind::Parse.context(e))?))
+    }
+}
+
+impl<'de> Deserialize<'de> for Version {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Self::from_str(&String::deserialize(deserializer)?)
+            .map_err(|e| D::Error::custom(format!("{}", e)))
+    }
+}
+
+impl Serialize for Version {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_string().serialize(serializer)
+    }
+}
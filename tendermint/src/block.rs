@@ -9,6 +9,7 @@ mod meta;
 pub mod parts;
 pub mod signed_header;
 mod size;
+mod version;
 
 pub use self::{
     commit::*,
@@ -18,9 +19,13 @@ pub use self::{
     id::{Id, ParseId},
     meta::Meta,
     size::Size,
+    version::Version,
 };
-use crate::{abci::transaction, evidence, serializers};
+use crate::{abci::transaction, error::Kind, evidence, serializers, Error};
+use anomaly::fail;
+use prost::encoding::encoded_len_varint;
 use serde::{Deserialize, Deserializer, Serialize};
+use tendermint_proto::DomainType;
 
 /// Blocks consist of a header, transactions, votes (the commit), and a list of
 /// evidence of malfeasance (i.e. signing conflicting votes).
@@ -42,6 +47,97 @@ pub struct Block {
     pub last_commit: Option<Commit>,
 }
 
+impl Block {
+    /// Perform basic validation of this block, which doesn't require any
+    /// knowledge of the chain state (like Tendermint's `Block.ValidateBasic`).
+    ///
+    /// Besides validating the header and the last commit, this checks that the
+    /// last commit is for the previous block and that the header's data and
+    /// evidence hashes match the block's transactions and evidence.
+    pub fn validate_basic(&self) -> Result<(), Error> {
+        self.header.validate_basic()?;
+
+        match &self.last_commit {
+            Some(last_commit) => {
+                last_commit.validate_basic()?;
+
+                if last_commit.height.increment() != self.header.height {
+                    fail!(
+                        Kind::InvalidBlock,
+                        "last commit height {} doesn't precede block height {}",
+                        last_commit.height,
+                        self.header.height
+                    );
+                }
+
+                if self.header.last_block_id.as_ref() != Some(&last_commit.block_id) {
+                    fail!(
+                        Kind::InvalidBlock,
+                        "last commit is for a different block than the header's last block ID"
+                    );
+                }
+            }
+            None => {
+                if self.header.height.value() > 1 {
+                    fail!(
+                        Kind::InvalidBlock,
+                        "block at height {} has no last commit",
+                        self.header.height
+                    );
+                }
+            }
+        }
+
+        if self.header.data_hash != self.data.root_hash() {
+            fail!(
+                Kind::InvalidBlock,
+                "header data hash doesn't match the block's transactions"
+            );
+        }
+
+        if self.header.evidence_hash != self.evidence.root_hash()? {
+            fail!(
+                Kind::InvalidBlock,
+                "header evidence hash doesn't match the block's evidence"
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Estimate the size of the protobuf encoding of this block, as counted
+    /// against the `max_bytes` consensus parameter.
+    pub fn encoded_size(&self) -> Result<u64, Error> {
+        let txs_size = self
+            .data
+            .iter()
+            .map(|tx| embedded_len(tx.as_bytes().len()))
+            .sum();
+
+        let evidence_size = self
+            .evidence
+            .iter()
+            .map(|evidence| evidence.size().map(|size| embedded_len(embedded_len(size))))
+            .sum::<Result<usize, Error>>()?;
+
+        let mut size = embedded_len(self.header.encoded_len()?)
+            + embedded_len(txs_size)
+            + embedded_len(evidence_size);
+
+        if let Some(last_commit) = &self.last_commit {
+            size += embedded_len(last_commit.encoded_len()?);
+        }
+
+        Ok(size as u64)
+    }
+}
+
+/// Length of a protobuf message field with the given encoded length,
+/// including its tag and length prefix
+fn embedded_len(len: usize) -> usize {
+    1 + encoded_len_varint(len as u64) + len
+}
+
 pub(crate) fn parse_non_empty_commit<'de, D>(deserializer: D) -> Result<Option<Commit>, D::Error>
 where
     D: Deserializer<'de>,
@@ -1,10 +1,19 @@
 //! Commits to a Tendermint blockchain
 
 use crate::block::commit_sig::CommitSig;
-use crate::block::{Height, Id};
+use crate::block::{Header, Height, Id};
 use crate::serializers;
+use crate::vote::{self, Vote};
+use crate::{error::Kind, Error};
+use anomaly::{fail, format_err};
 use serde::{Deserialize, Serialize};
-use std::{ops::Deref, slice};
+use std::{
+    convert::{TryFrom, TryInto},
+    ops::Deref,
+    slice,
+};
+use tendermint_proto::types::Commit as RawCommit;
+use tendermint_proto::DomainType;
 
 /// Commit contains the justification (ie. a set of signatures) that a block was committed by a set
 /// of validators.
@@ -27,6 +36,151 @@ pub struct Commit {
     pub signatures: CommitSigs,
 }
 
+impl Commit {
+    /// Perform basic validation of this commit, which doesn't require any
+    /// knowledge of the validator set (like Tendermint's
+    /// `Commit.ValidateBasic`).
+    pub fn validate_basic(&self) -> Result<(), Error> {
+        if self.height.value() == 0 {
+            fail!(Kind::InvalidCommit, "commit height is zero");
+        }
+
+        if self.signatures.is_empty() {
+            fail!(Kind::InvalidCommit, "no signatures in commit");
+        }
+
+        if self.signatures.iter().all(CommitSig::is_absent) {
+            fail!(Kind::InvalidCommit, "all signatures in commit are absent");
+        }
+
+        Ok(())
+    }
+
+    /// Reconstruct the precommit signed by the validator at the given index
+    /// (like Tendermint's `Commit.GetVote`).
+    ///
+    /// Returns `None` if the index is out of range or the signature is absent.
+    /// Votes for nil are returned with no block ID.
+    pub fn vote(&self, validator_index: usize) -> Option<Vote> {
+        let commit_sig = self.signatures.get(validator_index)?;
+
+        let block_id = match commit_sig {
+            CommitSig::BlockIDFlagAbsent => return None,
+            CommitSig::BlockIDFlagCommit { .. } => Some(self.block_id.clone()),
+            CommitSig::BlockIDFlagNil { .. } => None,
+        };
+
+        Some(Vote {
+            vote_type: vote::Type::Precommit,
+            height: self.height,
+            round: self.round,
+            block_id,
+            timestamp: commit_sig.timestamp()?,
+            validator_address: commit_sig.validator_address()?,
+            validator_index: validator_index as u64,
+            signature: *commit_sig.signature()?,
+            #[cfg(feature = "vote-extensions")]
+            extension: commit_sig.extension().unwrap_or_default().to_vec(),
+            #[cfg(feature = "vote-extensions")]
+            extension_signature: commit_sig.extension_signature().copied(),
+        })
+    }
+
+    /// Reconstruct the precommit signed by the validator at the given index,
+    /// after checking that this commit is for the given header.
+    ///
+    /// The vote is exactly what the validator signed on the header's chain,
+    /// so its signature can be checked with [`Vote::verify`] and it can be
+    /// used as evidence.
+    pub fn vote_at(&self, validator_index: usize, header: &Header) -> Result<Option<Vote>, Error> {
+        self.check_header(header)?;
+        Ok(self.vote(validator_index))
+    }
+
+    /// Iterate over the precommits reconstructed from this commit's
+    /// non-absent signatures, ordered by validator index.
+    pub fn votes(&self) -> impl Iterator<Item = Vote> + '_ {
+        (0..self.signatures.len()).filter_map(move |index| self.vote(index))
+    }
+
+    /// Check that this commit is for the given header
+    pub(crate) fn check_header(&self, header: &Header) -> Result<(), Error> {
+        if self.height != header.height {
+            return Err(Kind::InvalidCommit
+                .context(Kind::HeightMismatch {
+                    expected: header.height,
+                    actual: self.height,
+                })
+                .into());
+        }
+
+        let header_hash = header.hash();
+
+        if self.block_id.hash != header_hash {
+            return Err(Kind::InvalidCommit
+                .context(Kind::HashMismatch {
+                    expected: header_hash,
+                    actual: self.block_id.hash,
+                })
+                .into());
+        }
+
+        Ok(())
+    }
+}
+
+impl DomainType<RawCommit> for Commit {}
+
+impl TryFrom<RawCommit> for Commit {
+    type Error = Error;
+
+    fn try_from(value: RawCommit) -> Result<Self, Self::Error> {
+        if value.round < 0 {
+            fail!(Kind::OutOfRange, "negative commit round: {}", value.round);
+        }
+
+        let signatures = value
+            .signatures
+            .into_iter()
+            .map(TryInto::try_into)
+            .collect::<Result<Vec<CommitSig>, Error>>()?;
+
+        Ok(Commit {
+            height: value.height.try_into()?,
+            round: value.round as u64,
+            block_id: value
+                .block_id
+                .ok_or_else(|| format_err!(Kind::Parse, "block ID is missing in commit"))?
+                .try_into()?,
+            signatures: CommitSigs::new(signatures),
+        })
+    }
+}
+
+impl TryFrom<Commit> for RawCommit {
+    type Error = Error;
+
+    fn try_from(value: Commit) -> Result<Self, Self::Error> {
+        let round = i32::try_from(value.round).map_err(|_| {
+            format_err!(Kind::OutOfRange, "commit round too large: {}", value.round)
+        })?;
+
+        Ok(RawCommit {
+            height: value.height.into(),
+            round,
+            block_id: Some(value.block_id.into()),
+            signatures: value
+                .signatures
+                .into_vec()
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+            hash: vec![],
+            bit_array: None,
+        })
+    }
+}
+
 /// CommitSigs which certify that a block is valid
 #[derive(Serialize, Deserialize, Clone, Debug, Default)]
 pub struct CommitSigs(Vec<CommitSig>);
@@ -71,3 +225,45 @@ impl PartialEq for CommitSigs {
         self.0.clone().into_iter().eq(other.0.clone().into_iter())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::test::{keypair, signed_header, vote_nil};
+
+    #[test]
+    fn reconstruct_votes_from_commit() {
+        let keypairs = vec![keypair(1), keypair(2), keypair(3), keypair(4)];
+        let mut signed_header = signed_header(&keypairs, 3);
+        vote_nil(&mut signed_header, &keypairs[1], 1);
+
+        let header = &signed_header.header;
+        let commit = &signed_header.commit;
+        let chain_id = header.chain_id;
+
+        let votes = commit.votes().collect::<Vec<_>>();
+        assert_eq!(votes.len(), 3);
+        assert_eq!(
+            votes
+                .iter()
+                .map(|vote| vote.validator_index)
+                .collect::<Vec<_>>(),
+            vec![0, 1, 2]
+        );
+        assert_eq!(votes[1].block_id, None);
+
+        for (vote, keypair) in votes.iter().zip(&keypairs) {
+            assert!(vote.verify(chain_id, &keypair.public.into()).is_ok());
+            assert_eq!(
+                commit
+                    .vote_at(vote.validator_index as usize, header)
+                    .unwrap(),
+                Some(vote.clone())
+            );
+        }
+        assert_eq!(commit.vote_at(3, header).unwrap(), None);
+
+        let mut other_header = header.clone();
+        other_header.height = other_header.height.increment();
+        assert!(commit.vote_at(0, &other_header).is_err());
+    }
+}
@@ -1,10 +1,13 @@
 //! CommitSig within Commit
 
-use crate::serializers::BlockIDFlag;
+pub use crate::serializers::BlockIDFlag;
 use crate::serializers::RawCommitSig;
-use crate::{account, Signature, Time};
+use crate::{account, error::Kind, Error, Signature, Time};
+use anomaly::{fail, format_err};
 use serde::{Deserialize, Serialize};
-use std::convert::TryFrom;
+use std::convert::{TryFrom, TryInto};
+use tendermint_proto::types as proto;
+use tendermint_proto::DomainType;
 
 /// CommitSig represents a signature of a validator.
 /// It's a part of the Commit and can be used to reconstruct the vote set given the validator set.
@@ -22,6 +25,12 @@ pub enum CommitSig {
         timestamp: Time,
         /// Signature of vote
         signature: Signature,
+        /// Vote extension (Tendermint 0.38), empty if there is none
+        #[cfg(feature = "vote-extensions")]
+        extension: Vec<u8>,
+        /// Signature of the vote extension
+        #[cfg(feature = "vote-extensions")]
+        extension_signature: Option<Signature>,
     },
     /// voted for nil.
     BlockIDFlagNil {
@@ -35,15 +44,67 @@ pub enum CommitSig {
 }
 
 impl CommitSig {
+    /// Get the `BlockIDFlag` indicating which block ID this signature is for.
+    pub fn block_id_flag(&self) -> BlockIDFlag {
+        match self {
+            Self::BlockIDFlagAbsent => BlockIDFlag::Absent,
+            Self::BlockIDFlagCommit { .. } => BlockIDFlag::Commit,
+            Self::BlockIDFlagNil { .. } => BlockIDFlag::Nil,
+        }
+    }
+
     /// Get the address of this validator if a vote was received.
     pub fn validator_address(&self) -> Option<account::Id> {
         match self {
             Self::BlockIDFlagCommit {
                 validator_address, ..
-            } => Some(*validator_address),
-            Self::BlockIDFlagNil {
+            }
+            | Self::BlockIDFlagNil {
                 validator_address, ..
             } => Some(*validator_address),
+            Self::BlockIDFlagAbsent => None,
+        }
+    }
+
+    /// Get the timestamp of the vote if one was received.
+    pub fn timestamp(&self) -> Option<Time> {
+        match self {
+            Self::BlockIDFlagCommit { timestamp, .. } | Self::BlockIDFlagNil { timestamp, .. } => {
+                Some(*timestamp)
+            }
+            Self::BlockIDFlagAbsent => None,
+        }
+    }
+
+    /// Get the signature of the vote if one was received.
+    pub fn signature(&self) -> Option<&Signature> {
+        match self {
+            Self::BlockIDFlagCommit { signature, .. } | Self::BlockIDFlagNil { signature, .. } => {
+                Some(signature)
+            }
+            Self::BlockIDFlagAbsent => None,
+        }
+    }
+
+    /// Get the vote extension, if this signature is for the committed block.
+    #[cfg(feature = "vote-extensions")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "vote-extensions")))]
+    pub fn extension(&self) -> Option<&[u8]> {
+        match self {
+            Self::BlockIDFlagCommit { extension, .. } => Some(extension),
+            _ => None,
+        }
+    }
+
+    /// Get the signature of the vote extension, if there is one.
+    #[cfg(feature = "vote-extensions")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "vote-extensions")))]
+    pub fn extension_signature(&self) -> Option<&Signature> {
+        match self {
+            Self::BlockIDFlagCommit {
+                extension_signature,
+                ..
+            } => extension_signature.as_ref(),
             _ => None,
         }
     }
@@ -87,6 +148,10 @@ impl TryFrom<RawCommitSig> for CommitSig {
                 if value.signature.is_some() {
                     return Err("signature is present for BlockIDFlagAbsent CommitSig");
                 }
+                #[cfg(feature = "vote-extensions")]
+                if !value.extension.is_empty() || value.extension_signature.is_some() {
+                    return Err("extension is present for BlockIDFlagAbsent CommitSig");
+                }
                 Ok(CommitSig::BlockIDFlagAbsent)
             }
             BlockIDFlag::Commit => {
@@ -101,6 +166,10 @@ impl TryFrom<RawCommitSig> for CommitSig {
                         validator_address: value.validator_address.unwrap(),
                         timestamp: value.timestamp.unwrap(),
                         signature: value.signature.unwrap(),
+                        #[cfg(feature = "vote-extensions")]
+                        extension: value.extension,
+                        #[cfg(feature = "vote-extensions")]
+                        extension_signature: value.extension_signature,
                     })
                 }
             }
@@ -112,6 +181,10 @@ impl TryFrom<RawCommitSig> for CommitSig {
                 } else if value.validator_address.is_none() {
                     Err("validator_address is missing for BlockIDFlagNil CommitSig")
                 } else {
+                    #[cfg(feature = "vote-extensions")]
+                    if !value.extension.is_empty() || value.extension_signature.is_some() {
+                        return Err("extension is present for BlockIDFlagNil CommitSig");
+                    }
                     Ok(CommitSig::BlockIDFlagNil {
                         validator_address: value.validator_address.unwrap(),
                         timestamp: value.timestamp.unwrap(),
@@ -131,6 +204,10 @@ impl From<CommitSig> for RawCommitSig {
                 validator_address: None,
                 timestamp: None,
                 signature: None,
+                #[cfg(feature = "vote-extensions")]
+                extension: vec![],
+                #[cfg(feature = "vote-extensions")]
+                extension_signature: None,
             },
             CommitSig::BlockIDFlagNil {
                 validator_address,
@@ -141,17 +218,230 @@ impl From<CommitSig> for RawCommitSig {
                 validator_address: Some(validator_address),
                 timestamp: Some(timestamp),
                 signature: Some(signature),
+                #[cfg(feature = "vote-extensions")]
+                extension: vec![],
+                #[cfg(feature = "vote-extensions")]
+                extension_signature: None,
             },
             CommitSig::BlockIDFlagCommit {
                 validator_address,
                 timestamp,
                 signature,
+                #[cfg(feature = "vote-extensions")]
+                extension,
+                #[cfg(feature = "vote-extensions")]
+                extension_signature,
             } => RawCommitSig {
                 block_id_flag: BlockIDFlag::Commit,
                 validator_address: Some(validator_address),
                 timestamp: Some(timestamp),
                 signature: Some(signature),
+                #[cfg(feature = "vote-extensions")]
+                extension,
+                #[cfg(feature = "vote-extensions")]
+                extension_signature,
+            },
+        }
+    }
+}
+
+impl DomainType<proto::CommitSig> for CommitSig {}
+
+impl TryFrom<proto::CommitSig> for CommitSig {
+    type Error = Error;
+
+    fn try_from(value: proto::CommitSig) -> Result<Self, Self::Error> {
+        let flag = proto::BlockIdFlag::from_i32(value.block_id_flag).ok_or_else(|| {
+            format_err!(
+                Kind::Parse,
+                "unknown block ID flag: {}",
+                value.block_id_flag
+            )
+        })?;
+
+        match flag {
+            proto::BlockIdFlag::Absent => {
+                if !value.signature.is_empty() {
+                    fail!(
+                        Kind::Parse,
+                        "signature is present for BlockIDFlagAbsent CommitSig"
+                    );
+                }
+                return Ok(CommitSig::BlockIDFlagAbsent);
+            }
+            proto::BlockIdFlag::Unknown => fail!(Kind::Parse, "unknown block ID flag"),
+            _ => (),
+        }
+
+        let validator_address = value.validator_address.try_into()?;
+        let timestamp = value
+            .timestamp
+            .ok_or_else(|| format_err!(Kind::Parse, "timestamp is missing in CommitSig"))?
+            .try_into()?;
+        let signature = value.signature.try_into()?;
+
+        if flag == proto::BlockIdFlag::Commit {
+            Ok(CommitSig::BlockIDFlagCommit {
+                validator_address,
+                timestamp,
+                signature,
+                // Vote extensions aren't part of the Tendermint 0.34 protobuf
+                #[cfg(feature = "vote-extensions")]
+                extension: vec![],
+                #[cfg(feature = "vote-extensions")]
+                extension_signature: None,
+            })
+        } else {
+            Ok(CommitSig::BlockIDFlagNil {
+                validator_address,
+                timestamp,
+                signature,
+            })
+        }
+    }
+}
+
+impl From<CommitSig> for proto::CommitSig {
+    fn from(value: CommitSig) -> Self {
+        match value {
+            CommitSig::BlockIDFlagAbsent => proto::CommitSig {
+                block_id_flag: proto::BlockIdFlag::Absent as i32,
+                validator_address: vec![],
+                timestamp: None,
+                signature: vec![],
+            },
+            CommitSig::BlockIDFlagCommit {
+                validator_address,
+                timestamp,
+                signature,
+                ..
+            } => proto::CommitSig {
+                block_id_flag: proto::BlockIdFlag::Commit as i32,
+                validator_address: validator_address.into(),
+                timestamp: Some(timestamp.into()),
+                signature: signature.into(),
+            },
+            CommitSig::BlockIDFlagNil {
+                validator_address,
+                timestamp,
+                signature,
+            } => proto::CommitSig {
+                block_id_flag: proto::BlockIdFlag::Nil as i32,
+                validator_address: validator_address.into(),
+                timestamp: Some(timestamp.into()),
+                signature: signature.into(),
             },
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commit_sigs() -> Vec<CommitSig> {
+        let validator_address = account::Id::new([0xAB; account::LENGTH]);
+        let timestamp = Time::parse_from_rfc3339("2020-09-15T10:11:12.131415161Z").unwrap();
+        let signature = Signature::Ed25519(ed25519::Signature::new([0xCD; 64]));
+
+        vec![
+            CommitSig::BlockIDFlagAbsent,
+            CommitSig::BlockIDFlagCommit {
+                validator_address,
+                timestamp,
+                signature,
+                #[cfg(feature = "vote-extensions")]
+                extension: vec![],
+                #[cfg(feature = "vote-extensions")]
+                extension_signature: None,
+            },
+            CommitSig::BlockIDFlagNil {
+                validator_address,
+                timestamp,
+                signature,
+            },
+        ]
+    }
+
+    #[test]
+    fn accessors() {
+        let sigs = commit_sigs();
+
+        assert_eq!(sigs[0].block_id_flag(), BlockIDFlag::Absent);
+        assert!(sigs[0].is_absent());
+        assert_eq!(sigs[0].validator_address(), None);
+        assert_eq!(sigs[0].timestamp(), None);
+        assert_eq!(sigs[0].signature(), None);
+
+        assert_eq!(sigs[1].block_id_flag(), BlockIDFlag::Commit);
+        assert!(sigs[1].is_commit() && !sigs[1].is_nil());
+        assert_eq!(sigs[2].block_id_flag(), BlockIDFlag::Nil);
+        assert!(sigs[2].is_nil() && !sigs[2].is_commit());
+
+        for sig in &sigs[1..] {
+            assert!(!sig.is_absent());
+            assert!(sig.validator_address().is_some());
+            assert!(sig.timestamp().is_some());
+            assert!(sig.signature().is_some());
+        }
+    }
+
+    #[test]
+    fn serde_roundtrip() {
+        for sig in commit_sigs() {
+            let json = serde_json::to_string(&sig).unwrap();
+            assert_eq!(serde_json::from_str::<CommitSig>(&json).unwrap(), sig);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "vote-extensions")]
+    fn extensions() {
+        let mut sigs = commit_sigs();
+        assert_eq!(sigs[1].extension(), Some(&[][..]));
+        assert_eq!(sigs[1].extension_signature(), None);
+        assert_eq!(sigs[2].extension(), None);
+
+        if let CommitSig::BlockIDFlagCommit {
+            extension,
+            extension_signature,
+            ..
+        } = &mut sigs[1]
+        {
+            *extension = b"extension".to_vec();
+            *extension_signature = Some(Signature::Ed25519(ed25519::Signature::new([0xEF; 64])));
+        }
+
+        let json = serde_json::to_value(&sigs[1]).unwrap();
+        assert_eq!(json["extension"], "ZXh0ZW5zaW9u");
+        assert_eq!(serde_json::from_value::<CommitSig>(json).unwrap(), sigs[1]);
+
+        // Only signatures for the committed block can carry an extension
+        let mut json = serde_json::to_value(&sigs[2]).unwrap();
+        json["extension"] = "ZXh0ZW5zaW9u".into();
+        assert!(serde_json::from_value::<CommitSig>(json).is_err());
+    }
+
+    #[test]
+    fn protobuf_roundtrip() {
+        for sig in commit_sigs() {
+            let raw = proto::CommitSig::from(sig.clone());
+            assert_eq!(CommitSig::try_from(raw).unwrap(), sig);
+        }
+    }
+
+    #[test]
+    fn protobuf_rejects_inconsistent_flags() {
+        let mut absent_with_signature = proto::CommitSig::from(commit_sigs()[1].clone());
+        absent_with_signature.block_id_flag = proto::BlockIdFlag::Absent as i32;
+        assert!(CommitSig::try_from(absent_with_signature).is_err());
+
+        let mut nil_without_timestamp = proto::CommitSig::from(commit_sigs()[2].clone());
+        nil_without_timestamp.timestamp = None;
+        assert!(CommitSig::try_from(nil_without_timestamp).is_err());
+
+        let mut unknown = proto::CommitSig::from(commit_sigs()[1].clone());
+        unknown.block_id_flag = proto::BlockIdFlag::Unknown as i32;
+        assert!(CommitSig::try_from(unknown).is_err());
+    }
+}
@@ -1,10 +1,16 @@
 //! Block headers
 
 use crate::amino_types::{message::AminoMessage, BlockId, ConsensusVersion, TimeMsg};
-use crate::merkle::simple_hash_from_byte_vectors;
+use crate::hash::{Algorithm, AppHash, DefaultHasher, Hasher};
+use crate::merkle::simple_hash_from_byte_vectors_with;
 use crate::serializers;
-use crate::{account, block, chain, Hash, Time};
+use crate::{account, app, block, chain, error::Kind, Error, Hash, Time};
+use anomaly::{fail, format_err};
 use serde::{Deserialize, Serialize};
+use std::convert::{TryFrom, TryInto};
+use tendermint_proto::types::Header as RawHeader;
+use tendermint_proto::version::Consensus as RawConsensusVersion;
+use tendermint_proto::DomainType;
 
 /// Block `Header` values contain metadata about the block and about the
 /// consensus, as well as commitments to the data in the current block, the
@@ -26,7 +32,7 @@ pub struct Header {
     pub time: Time,
 
     /// Previous block info
-    #[serde(deserialize_with = "serializers::parse_non_empty_block_id")]
+    #[serde(with = "serializers::block_id::optional")]
     pub last_block_id: Option<block::Id>,
 
     /// Commit from validators from the last block
@@ -47,8 +53,7 @@ pub struct Header {
     pub consensus_hash: Hash,
 
     /// State after txs from the previous block
-    #[serde(with = "serializers::bytes::hexstring")]
-    pub app_hash: Vec<u8>,
+    pub app_hash: AppHash,
 
     /// Root hash of all results from the txs from the previous block
     #[serde(deserialize_with = "serializers::parse_non_empty_hash")]
@@ -63,8 +68,16 @@ pub struct Header {
 }
 
 impl Header {
-    /// Hash this header
+    /// Hash this header.
+    ///
+    /// This is the Merkle root of the encoded header fields, and is the hash
+    /// referenced by the [`block::Id`] of the block containing this header.
     pub fn hash(&self) -> Hash {
+        self.hash_with(&DefaultHasher)
+    }
+
+    /// Hash this header using the given [`Hasher`]
+    pub fn hash_with<H: Hasher + ?Sized>(&self, hasher: &H) -> Hash {
         // Note that if there is an encoding problem this will
         // panic (as the golang code would):
         // https://github.com/tendermint/tendermint/blob/134fe2896275bb926b49743c1e25493f6b24cc31/types/block.go#L393
@@ -87,15 +100,116 @@ impl Header {
         fields_bytes.push(encode_hash(&self.validators_hash));
         fields_bytes.push(encode_hash(&self.next_validators_hash));
         fields_bytes.push(encode_hash(&self.consensus_hash));
-        fields_bytes.push(encode_bytes(&self.app_hash));
+        fields_bytes.push(encode_bytes(self.app_hash.as_bytes()));
         fields_bytes.push(self.last_results_hash.as_ref().map_or(vec![], encode_hash));
         fields_bytes.push(self.evidence_hash.as_ref().map_or(vec![], encode_hash));
         fields_bytes.push(encode_bytes(self.proposer_address.as_bytes()));
 
-        Hash::Sha256(simple_hash_from_byte_vectors(fields_bytes))
+        Hash::Sha256(simple_hash_from_byte_vectors_with(&fields_bytes, hasher))
+    }
+
+    /// Perform basic validation of this header, which doesn't require any
+    /// knowledge of the chain state (like Tendermint's `Header.ValidateBasic`).
+    ///
+    /// Field sizes are already enforced by the types used to represent them,
+    /// so this checks the height and the presence of the fields which refer
+    /// back to the previous block.
+    pub fn validate_basic(&self) -> Result<(), Error> {
+        if self.height.value() == 0 {
+            fail!(Kind::InvalidBlock, "header height is zero");
+        }
+
+        if self.height.value() == 1 {
+            if self.last_block_id.is_some() {
+                fail!(Kind::InvalidBlock, "first block has a last block ID");
+            }
+        } else {
+            if self.last_block_id.is_none() {
+                fail!(
+                    Kind::InvalidBlock,
+                    "header at height {} has no last block ID",
+                    self.height
+                );
+            }
+
+            if self.last_commit_hash.is_none() {
+                fail!(
+                    Kind::InvalidBlock,
+                    "header at height {} has no last commit hash",
+                    self.height
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl DomainType<RawHeader> for Header {}
+
+impl TryFrom<RawHeader> for Header {
+    type Error = Error;
+
+    fn try_from(value: RawHeader) -> Result<Self, Self::Error> {
+        Ok(Header {
+            version: value
+                .version
+                .ok_or_else(|| format_err!(Kind::Parse, "version is missing in header"))?
+                .into(),
+            chain_id: value.chain_id.parse()?,
+            height: value.height.try_into()?,
+            time: value
+                .time
+                .ok_or_else(|| format_err!(Kind::Parse, "time is missing in header"))?
+                .try_into()?,
+            last_block_id: block::Id::parse_optional_raw(value.last_block_id)?,
+            last_commit_hash: parse_optional_hash(&value.last_commit_hash)?,
+            data_hash: parse_optional_hash(&value.data_hash)?,
+            validators_hash: Hash::new(Algorithm::Sha256, &value.validators_hash)?,
+            next_validators_hash: Hash::new(Algorithm::Sha256, &value.next_validators_hash)?,
+            consensus_hash: Hash::new(Algorithm::Sha256, &value.consensus_hash)?,
+            app_hash: value.app_hash.into(),
+            last_results_hash: parse_optional_hash(&value.last_results_hash)?,
+            evidence_hash: parse_optional_hash(&value.evidence_hash)?,
+            proposer_address: value.proposer_address.try_into()?,
+        })
+    }
+}
+
+impl From<Header> for RawHeader {
+    fn from(value: Header) -> Self {
+        RawHeader {
+            version: Some(value.version.into()),
+            chain_id: value.chain_id.as_str().to_owned(),
+            height: value.height.into(),
+            time: Some(value.time.into()),
+            last_block_id: block::Id::to_optional_raw(value.last_block_id),
+            last_commit_hash: hash_bytes(value.last_commit_hash),
+            data_hash: hash_bytes(value.data_hash),
+            validators_hash: value.validators_hash.as_bytes().to_vec(),
+            next_validators_hash: value.next_validators_hash.as_bytes().to_vec(),
+            consensus_hash: value.consensus_hash.as_bytes().to_vec(),
+            app_hash: value.app_hash.into(),
+            last_results_hash: hash_bytes(value.last_results_hash),
+            evidence_hash: hash_bytes(value.evidence_hash),
+            proposer_address: value.proposer_address.into(),
+        }
+    }
+}
+
+/// Empty hashes in protobuf messages are Go's zero value, i.e. "no hash"
+fn parse_optional_hash(bytes: &[u8]) -> Result<Option<Hash>, Error> {
+    if bytes.is_empty() {
+        Ok(None)
+    } else {
+        Ok(Some(Hash::new(Algorithm::Sha256, bytes)?))
     }
 }
 
+fn hash_bytes(hash: Option<Hash>) -> Vec<u8> {
+    hash.map_or(vec![], |h| h.as_bytes().to_vec())
+}
+
 fn encode_bytes(bytes: &[u8]) -> Vec<u8> {
     let bytes_len = bytes.len();
     if bytes_len > 0 {
@@ -125,22 +239,65 @@ fn encode_varint(val: u64) -> Vec<u8> {
 #[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
 pub struct Version {
     /// Block version
-    #[serde(with = "serializers::from_str")]
-    pub block: u64,
+    pub block: block::Version,
 
     /// App version
-    #[serde(with = "serializers::from_str")]
-    pub app: u64,
+    pub app: app::Version,
+}
+
+impl From<RawConsensusVersion> for Version {
+    fn from(value: RawConsensusVersion) -> Self {
+        Version {
+            block: value.block.into(),
+            app: value.app.into(),
+        }
+    }
+}
+
+impl From<Version> for RawConsensusVersion {
+    fn from(value: Version) -> Self {
+        RawConsensusVersion {
+            block: value.block.into(),
+            app: value.app.into(),
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::Header;
+    use crate::block::Height;
     use crate::test::test_serialization_roundtrip;
+    use tendermint_proto::DomainType;
 
     #[test]
     fn serialization_roundtrip() {
         let json_data = include_str!("../../tests/support/serialization/block/header.json");
         test_serialization_roundtrip::<Header>(json_data);
     }
+
+    #[test]
+    fn validate_basic() {
+        let json_data = include_str!("../../tests/support/serialization/block/header.json");
+        let header: Header = serde_json::from_str(json_data).unwrap();
+        assert!(header.validate_basic().is_ok());
+
+        let mut missing_last_block_id = header.clone();
+        missing_last_block_id.last_block_id = None;
+        assert!(missing_last_block_id.validate_basic().is_err());
+
+        let mut zero_height = header;
+        zero_height.height = Height::from(0u64);
+        assert!(zero_height.validate_basic().is_err());
+    }
+
+    #[test]
+    fn protobuf_roundtrip() {
+        let json_data = include_str!("../../tests/support/serialization/block/header.json");
+        let header: Header = serde_json::from_str(json_data).unwrap();
+        let encoded = header.encode_vec().unwrap();
+        let decoded = Header::decode_vec(&encoded).unwrap();
+        assert_eq!(header, decoded);
+        assert_eq!(header.hash(), decoded.hash());
+    }
 }
@@ -14,14 +14,39 @@ use std::{
 pub struct Height(pub u64);
 
 impl Height {
+    /// Largest height supported by Tendermint, which represents heights as
+    /// signed 64-bit integers
+    pub const MAX: Height = Height(i64::MAX as u64);
+
     /// Get inner integer value. Alternative to `.0` or `.into()`
     pub fn value(self) -> u64 {
         self.0
     }
 
     /// Increment the block height by 1
+    ///
+    /// Panics if the result would exceed [`Height::MAX`].
     pub fn increment(self) -> Self {
-        Height(self.0.checked_add(1).expect("height overflow"))
+        self.checked_add(1).expect("height overflow")
+    }
+
+    /// Add the given number of blocks to this height, returning `None` if the
+    /// result would exceed [`Height::MAX`]
+    pub fn checked_add(self, blocks: u64) -> Option<Self> {
+        self.0
+            .checked_add(blocks)
+            .map(Height)
+            .filter(|height| *height <= Self::MAX)
+    }
+
+    /// Subtract the given number of blocks from this height, returning `None`
+    /// if the result wouldn't be a valid block height (block heights start
+    /// at 1)
+    pub fn checked_sub(self, blocks: u64) -> Option<Self> {
+        self.0
+            .checked_sub(blocks)
+            .filter(|height| *height > 0)
+            .map(Height)
     }
 }
 
@@ -77,7 +102,13 @@ impl FromStr for Height {
     type Err = Error;
 
     fn from_str(s: &str) -> Result<Self, Error> {
-        Ok(s.parse::<u64>().map_err(|_| Kind::Parse)?.into())
+        let height = Height(s.parse::<u64>().map_err(|e| Kind::Parse.context(e))?);
+
+        if height > Self::MAX {
+            return Err(Kind::OutOfRange.into());
+        }
+
+        Ok(height)
     }
 }
 
@@ -108,4 +139,25 @@ mod tests {
     fn increment_by_one() {
         assert_eq!(Height::default().increment().value(), 2);
     }
+
+    #[test]
+    fn checked_arithmetic() {
+        let height = Height::from(10u64);
+        assert_eq!(height.checked_add(5), Some(Height::from(15u64)));
+        assert_eq!(height.checked_sub(9), Some(Height::from(1u64)));
+        assert_eq!(height.checked_sub(10), None);
+        assert_eq!(height.checked_sub(11), None);
+        assert_eq!(Height::MAX.checked_add(1), None);
+        assert_eq!(Height::from(u64::MAX).checked_add(0), None);
+    }
+
+    #[test]
+    fn parse_rejects_overflow() {
+        assert_eq!(
+            "9223372036854775807".parse::<Height>().unwrap(),
+            Height::MAX
+        );
+        assert!("9223372036854775808".parse::<Height>().is_err());
+        assert!(Height::try_from(-1i64).is_err());
+    }
 }
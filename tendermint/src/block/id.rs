@@ -1,13 +1,17 @@
 use super::parts;
 use crate::{
-    error::Error,
+    error::{Error, Kind},
     hash::{Algorithm, Hash},
 };
+use anomaly::{fail, format_err};
 use serde::{Deserialize, Serialize};
 use std::{
+    convert::{TryFrom, TryInto},
     fmt::{self, Display},
     str::{self, FromStr},
 };
+use tendermint_proto::types::BlockId as RawBlockId;
+use tendermint_proto::DomainType;
 
 /// Length of a block ID prefix displayed for debugging purposes
 pub const PREFIX_LENGTH: usize = 10;
@@ -34,6 +38,7 @@ pub struct Id {
     /// way to propagate a large file over a gossip network.
     ///
     /// <https://github.com/tendermint/tendermint/wiki/Block-Structure#partset>
+    #[serde(with = "crate::serializers::block_id::optional_parts")]
     pub parts: Option<parts::Header>,
 }
 
@@ -43,6 +48,30 @@ impl Id {
         Self { hash, parts }
     }
 
+    /// Decode an optional block ID, where an empty hash (Go's zero value)
+    /// means there is no block ID (e.g. a vote for nil)
+    pub(crate) fn parse_optional_raw(value: Option<RawBlockId>) -> Result<Option<Self>, Error> {
+        match value {
+            Some(id) if !id.hash.is_empty() => Ok(Some(id.try_into()?)),
+            Some(RawBlockId {
+                part_set_header: Some(header),
+                ..
+            }) if header.total != 0 || !header.hash.is_empty() => {
+                fail!(Kind::Parse, "block ID has a part set header but no hash")
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Encode an optional block ID like Go does: block IDs are never omitted,
+    /// and `None` is encoded as the empty block ID
+    pub(crate) fn to_optional_raw(value: Option<Self>) -> Option<RawBlockId> {
+        Some(value.map(Into::into).unwrap_or_else(|| RawBlockId {
+            hash: vec![],
+            part_set_header: Some(Default::default()),
+        }))
+    }
+
     /// Get a shortened 12-character prefix of a block ID (ala git)
     pub fn prefix(&self) -> String {
         let mut result = self.to_string();
@@ -67,6 +96,38 @@ impl FromStr for Id {
     }
 }
 
+impl DomainType<RawBlockId> for Id {}
+
+impl TryFrom<RawBlockId> for Id {
+    type Error = Error;
+
+    fn try_from(value: RawBlockId) -> Result<Self, Self::Error> {
+        let hash = Hash::new(Algorithm::Sha256, &value.hash)
+            .map_err(|_| format_err!(Kind::Parse, "invalid block ID hash"))?;
+
+        // An empty part set header is Go's zero value and means "no parts"
+        let parts = match value.part_set_header {
+            Some(header) if header.total != 0 || !header.hash.is_empty() => {
+                Some(header.try_into()?)
+            }
+            _ => None,
+        };
+
+        Ok(Id { hash, parts })
+    }
+}
+
+impl From<Id> for RawBlockId {
+    fn from(value: Id) -> Self {
+        RawBlockId {
+            hash: value.hash.as_bytes().to_vec(),
+            // Go always encodes the (non-nullable) part set header, even when
+            // it's empty
+            part_set_header: Some(value.parts.map(Into::into).unwrap_or_default()),
+        }
+    }
+}
+
 /// Parse `block::Id` from a type
 pub trait ParseId {
     /// Parse `block::Id`, or return an `Error` if parsing failed
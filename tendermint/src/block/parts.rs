@@ -1,11 +1,27 @@
 //! Block parts
 
-use crate::Hash;
+use crate::block::Size;
+use crate::hash::{Algorithm, Hash};
+use crate::merkle::{self, proof::SimpleProof};
+use crate::{error::Kind, Error};
+use anomaly::{fail, format_err};
+use std::convert::{TryFrom, TryInto};
+use tendermint_proto::types::{Part as RawPart, PartSetHeader as RawPartSetHeader};
+use tendermint_proto::DomainType;
 use {
     crate::serializers,
     serde::{Deserialize, Serialize},
 };
 
+/// Size of the parts an encoded block is split into. Matches
+/// `BlockPartSizeBytes` from:
+/// <https://github.com/tendermint/tendermint/blob/v0.34.0/types/params.go>
+pub const BLOCK_PART_SIZE_BYTES: usize = 65536;
+
+/// Maximum number of parts a block of the maximum size is split into
+pub const MAX_BLOCK_PARTS_COUNT: u64 =
+    Size::MAX_BLOCK_SIZE_BYTES / BLOCK_PART_SIZE_BYTES as u64 + 1;
+
 /// Block parts header
 #[derive(Serialize, Deserialize, Clone, Debug, Hash, Eq, PartialEq, PartialOrd, Ord)]
 pub struct Header {
@@ -23,3 +39,308 @@ impl Header {
         Header { total, hash }
     }
 }
+
+impl DomainType<RawPartSetHeader> for Header {}
+
+impl TryFrom<RawPartSetHeader> for Header {
+    type Error = Error;
+
+    fn try_from(value: RawPartSetHeader) -> Result<Self, Self::Error> {
+        let hash = Hash::new(Algorithm::Sha256, &value.hash)
+            .map_err(|_| format_err!(Kind::Parse, "invalid part set header hash"))?;
+
+        Ok(Header::new(value.total.into(), hash))
+    }
+}
+
+impl From<Header> for RawPartSetHeader {
+    fn from(value: Header) -> Self {
+        RawPartSetHeader {
+            total: value.total as u32,
+            hash: value.hash.as_bytes().to_vec(),
+        }
+    }
+}
+
+/// A part of an encoded block, along with a proof of its inclusion in the
+/// block's part set
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Part {
+    /// Index of this part
+    pub index: u64,
+
+    /// Bytes of the encoded block in this part
+    pub bytes: Vec<u8>,
+
+    /// Proof of the inclusion of this part in the part set
+    pub proof: SimpleProof,
+}
+
+impl Part {
+    /// Perform basic validation of this part
+    pub fn validate_basic(&self) -> Result<(), Error> {
+        if self.bytes.len() > BLOCK_PART_SIZE_BYTES {
+            fail!(
+                Kind::Length,
+                "part is too big: {} bytes (max {})",
+                self.bytes.len(),
+                BLOCK_PART_SIZE_BYTES
+            );
+        }
+
+        if self.proof.index != self.index {
+            fail!(
+                Kind::InvalidProof,
+                "proof index {} does not match part index {}",
+                self.proof.index,
+                self.index
+            );
+        }
+
+        Ok(())
+    }
+}
+
+impl DomainType<RawPart> for Part {}
+
+impl TryFrom<RawPart> for Part {
+    type Error = Error;
+
+    fn try_from(value: RawPart) -> Result<Self, Self::Error> {
+        Ok(Part {
+            index: value.index.into(),
+            bytes: value.bytes,
+            proof: value
+                .proof
+                .ok_or_else(|| format_err!(Kind::Parse, "proof is missing in part"))?
+                .try_into()?,
+        })
+    }
+}
+
+impl From<Part> for RawPart {
+    fn from(value: Part) -> Self {
+        RawPart {
+            index: value.index as u32,
+            bytes: value.bytes,
+            proof: Some(value.proof.into()),
+        }
+    }
+}
+
+/// Set of parts an encoded block is split into for gossiping, which can be
+/// reassembled into the encoded block once all parts have been received.
+/// <https://github.com/tendermint/tendermint/blob/v0.34.0/types/part_set.go>
+#[derive(Clone, Debug)]
+pub struct PartSet {
+    header: Header,
+    parts: Vec<Option<Part>>,
+    count: u64,
+}
+
+impl PartSet {
+    /// Split the given data into parts of (at most) `part_size` bytes.
+    ///
+    /// Panics if `part_size` is zero.
+    pub fn from_data(data: &[u8], part_size: usize) -> Self {
+        let chunks = data
+            .chunks(part_size)
+            .map(|chunk| chunk.to_vec())
+            .collect::<Vec<_>>();
+        let (root_hash, proofs) = merkle::simple_proofs_from_byte_vectors(&chunks);
+
+        let parts = chunks
+            .into_iter()
+            .zip(proofs)
+            .enumerate()
+            .map(|(index, (bytes, proof))| {
+                Some(Part {
+                    index: index as u64,
+                    bytes,
+                    proof,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let count = parts.len() as u64;
+
+        PartSet {
+            header: Header::new(count, Hash::Sha256(root_hash)),
+            parts,
+            count,
+        }
+    }
+
+    /// Create an empty part set for receiving the parts described by the
+    /// given header
+    pub fn new(header: Header) -> Result<Self, Error> {
+        if header.total > MAX_BLOCK_PARTS_COUNT {
+            fail!(
+                Kind::OutOfRange,
+                "too many parts: {} (max {})",
+                header.total,
+                MAX_BLOCK_PARTS_COUNT
+            );
+        }
+
+        Ok(PartSet {
+            parts: vec![None; header.total as usize],
+            header,
+            count: 0,
+        })
+    }
+
+    /// Get the header of this part set
+    pub fn header(&self) -> &Header {
+        &self.header
+    }
+
+    /// Get the total number of parts in this part set
+    pub fn total(&self) -> u64 {
+        self.header.total
+    }
+
+    /// Get the number of parts received so far
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Have all parts been received?
+    pub fn is_complete(&self) -> bool {
+        self.count == self.total()
+    }
+
+    /// Get the part with the given index, if it has been received
+    pub fn part(&self, index: u64) -> Option<&Part> {
+        self.parts.get(index as usize)?.as_ref()
+    }
+
+    /// Add a part to this part set after verifying its inclusion proof.
+    ///
+    /// Returns `false` if the part had already been received.
+    pub fn add_part(&mut self, part: Part) -> Result<bool, Error> {
+        if part.index >= self.total() {
+            fail!(
+                Kind::OutOfRange,
+                "unexpected part index {} (total {})",
+                part.index,
+                self.total()
+            );
+        }
+
+        if self.parts[part.index as usize].is_some() {
+            return Ok(false);
+        }
+
+        part.validate_basic()?;
+
+        let Hash::Sha256(root_hash) = self.header.hash;
+        part.proof.verify(&root_hash, &part.bytes)?;
+
+        let index = part.index as usize;
+        self.parts[index] = Some(part);
+        self.count += 1;
+
+        Ok(true)
+    }
+
+    /// Reassemble the encoded block from its parts, or `None` if the part
+    /// set isn't complete yet
+    pub fn to_bytes(&self) -> Option<Vec<u8>> {
+        if !self.is_complete() {
+            return None;
+        }
+
+        let mut bytes = vec![];
+        for part in &self.parts {
+            bytes.extend_from_slice(&part.as_ref()?.bytes);
+        }
+
+        Some(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn data(len: usize) -> Vec<u8> {
+        (0..len).map(|i| i as u8).collect()
+    }
+
+    #[test]
+    fn splits_data_into_parts() {
+        let data = data(2 * BLOCK_PART_SIZE_BYTES + 1);
+        let part_set = PartSet::from_data(&data, BLOCK_PART_SIZE_BYTES);
+
+        assert_eq!(part_set.total(), 3);
+        assert!(part_set.is_complete());
+        assert_eq!(part_set.part(2).unwrap().bytes.len(), 1);
+        assert!(part_set.part(3).is_none());
+        assert_eq!(part_set.to_bytes().unwrap(), data);
+
+        for index in 0..3 {
+            part_set.part(index).unwrap().validate_basic().unwrap();
+        }
+    }
+
+    #[test]
+    fn reassembles_parts() {
+        let data = data(1000);
+        let source = PartSet::from_data(&data, 100);
+        let mut part_set = PartSet::new(source.header().clone()).unwrap();
+
+        for index in (0..source.total()).rev() {
+            assert_eq!(part_set.to_bytes(), None);
+            let part = source.part(index).unwrap().clone();
+            assert!(part_set.add_part(part.clone()).unwrap());
+            assert!(!part_set.add_part(part).unwrap());
+        }
+
+        assert!(part_set.is_complete());
+        assert_eq!(part_set.count(), 10);
+        assert_eq!(part_set.to_bytes().unwrap(), data);
+    }
+
+    #[test]
+    fn rejects_invalid_parts() {
+        let source = PartSet::from_data(&data(1000), 100);
+        let mut part_set = PartSet::new(source.header().clone()).unwrap();
+
+        let mut tampered = source.part(0).unwrap().clone();
+        tampered.bytes[0] ^= 1;
+        assert!(part_set.add_part(tampered).is_err());
+
+        let mut misplaced = source.part(0).unwrap().clone();
+        misplaced.index = 1;
+        assert!(part_set.add_part(misplaced).is_err());
+
+        let mut out_of_range = source.part(0).unwrap().clone();
+        out_of_range.index = 10;
+        assert!(part_set.add_part(out_of_range).is_err());
+
+        let foreign = PartSet::from_data(&data(999), 100);
+        assert!(part_set.add_part(foreign.part(0).unwrap().clone()).is_err());
+
+        assert_eq!(part_set.count(), 0);
+    }
+
+    #[test]
+    fn rejects_oversized_headers() {
+        let header = Header::new(
+            MAX_BLOCK_PARTS_COUNT + 1,
+            Hash::Sha256([0; merkle::HASH_SIZE]),
+        );
+        assert!(PartSet::new(header).is_err());
+    }
+
+    #[test]
+    fn part_protobuf_roundtrip() {
+        let part = PartSet::from_data(&data(1000), 100)
+            .part(3)
+            .unwrap()
+            .clone();
+        let raw = RawPart::from(part.clone());
+        assert_eq!(Part::try_from(raw).unwrap(), part);
+    }
+}
@@ -2,8 +2,12 @@
 //! It is what the rpc endpoint /commit returns and hence can be used by a
 //! light client.
 use serde::{Deserialize, Serialize};
+use std::convert::{TryFrom, TryInto};
+use tendermint_proto::types::SignedHeader as RawSignedHeader;
+use tendermint_proto::DomainType;
 
-use crate::block;
+use crate::{block, chain, error::Kind, validator, Error};
+use anomaly::{fail, format_err};
 
 /// Signed block headers
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
@@ -13,3 +17,120 @@ pub struct SignedHeader {
     /// Commit containing signatures for the header
     pub commit: block::Commit,
 }
+
+impl SignedHeader {
+    /// Check that the header and the commit are consistent with each other,
+    /// with the given chain ID, and with the given validator set (like
+    /// Tendermint's `SignedHeader.ValidateBasic`).
+    ///
+    /// This should be done before trusting a `SignedHeader` assembled from
+    /// untrusted parts. Signatures are not checked here: use
+    /// [`validator::Set::verify_commit_full`] for that.
+    pub fn validate(&self, chain_id: chain::Id, validators: &validator::Set) -> Result<(), Error> {
+        self.header.validate_basic()?;
+        self.commit.validate_basic()?;
+
+        if self.header.chain_id != chain_id {
+            return Err(Kind::InvalidBlock
+                .context(Kind::ChainIdMismatch {
+                    expected: chain_id,
+                    actual: self.header.chain_id,
+                })
+                .into());
+        }
+
+        self.commit.check_header(&self.header)?;
+
+        let validators_hash = validators.hash();
+
+        if self.header.validators_hash != validators_hash {
+            return Err(Kind::InvalidBlock
+                .context(Kind::HashMismatch {
+                    expected: self.header.validators_hash,
+                    actual: validators_hash,
+                })
+                .into());
+        }
+
+        if self.commit.signatures.len() != validators.validators().len() {
+            fail!(
+                Kind::InvalidCommit,
+                "commit has {} signatures for a set of {} validators",
+                self.commit.signatures.len(),
+                validators.validators().len()
+            );
+        }
+
+        Ok(())
+    }
+}
+
+impl DomainType<RawSignedHeader> for SignedHeader {}
+
+impl TryFrom<RawSignedHeader> for SignedHeader {
+    type Error = Error;
+
+    fn try_from(value: RawSignedHeader) -> Result<Self, Self::Error> {
+        Ok(SignedHeader {
+            header: value
+                .header
+                .ok_or_else(|| format_err!(Kind::Parse, "header is missing"))?
+                .try_into()?,
+            commit: value
+                .commit
+                .ok_or_else(|| format_err!(Kind::Parse, "commit is missing"))?
+                .try_into()?,
+        })
+    }
+}
+
+impl TryFrom<SignedHeader> for RawSignedHeader {
+    type Error = Error;
+
+    fn try_from(value: SignedHeader) -> Result<Self, Self::Error> {
+        Ok(RawSignedHeader {
+            header: Some(value.header.into()),
+            commit: Some(value.commit.try_into()?),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block::CommitSigs;
+    use crate::test::{keypair, signed_header, validator_set};
+
+    #[test]
+    fn validate_signed_header() {
+        let keypairs = vec![keypair(1), keypair(2), keypair(3)];
+        let vals = validator_set(&keypairs);
+
+        let mut signed_header = signed_header(&keypairs, 3);
+        signed_header.header.validators_hash = vals.hash();
+        signed_header.commit.block_id = block::Id::new(signed_header.header.hash(), None);
+        let chain_id = signed_header.header.chain_id;
+        assert!(signed_header.validate(chain_id, &vals).is_ok());
+
+        assert!(signed_header
+            .validate(chain::Id::from("other-chain"), &vals)
+            .is_err());
+        assert!(signed_header
+            .validate(chain_id, &validator_set(&keypairs[..2]))
+            .is_err());
+
+        let mut wrong_height = signed_header.clone();
+        wrong_height.commit.height = wrong_height.commit.height.increment();
+        assert!(wrong_height.validate(chain_id, &vals).is_err());
+
+        let mut wrong_block = signed_header.clone();
+        wrong_block.header.time = wrong_block.header.time + std::time::Duration::from_secs(1);
+        assert!(wrong_block.validate(chain_id, &vals).is_err());
+
+        let mut missing_signature = signed_header;
+        let mut signatures = missing_signature.commit.signatures.clone().into_vec();
+        signatures.pop();
+        missing_signature.commit.signatures = CommitSigs::new(signatures);
+        assert!(missing_signature.validate(chain_id, &vals).is_err());
+    }
+}
@@ -1,7 +1,8 @@
 //! Block size parameters
 
 use {
-    crate::serializers,
+    crate::{error::Kind, serializers, Error},
+    anomaly::format_err,
     serde::{Deserialize, Serialize},
 };
 
@@ -16,3 +17,99 @@ pub struct Size {
     #[serde(with = "serializers::from_str")]
     pub max_gas: i64,
 }
+
+impl Size {
+    /// Maximum value of `max_bytes` accepted by Tendermint (100 MB)
+    pub const MAX_BLOCK_SIZE_BYTES: u64 = 104_857_600;
+
+    /// Maximum size of an encoded block header
+    pub const MAX_HEADER_BYTES: u64 = 626;
+
+    /// Maximum size of the encoding of a block besides its fields
+    pub const MAX_OVERHEAD_FOR_BLOCK: u64 = 11;
+
+    /// Maximum size of an encoded commit besides its signatures
+    pub const MAX_COMMIT_OVERHEAD_BYTES: u64 = 94;
+
+    /// Maximum size of an encoded commit signature
+    pub const MAX_COMMIT_SIG_BYTES: u64 = 109;
+
+    /// Maximum size of an encoded commit signed by the given number of
+    /// validators (like Tendermint's `MaxCommitBytes`)
+    pub fn max_commit_bytes(validator_count: usize) -> u64 {
+        // Each signature in the repeated field adds a tag and length prefix
+        const PROTOBUF_OVERHEAD: u64 = 2;
+
+        Self::MAX_COMMIT_OVERHEAD_BYTES
+            + (Self::MAX_COMMIT_SIG_BYTES + PROTOBUF_OVERHEAD) * validator_count as u64
+    }
+
+    /// Maximum size of the transaction data of a block containing the given
+    /// number of bytes of evidence, and the last commit of the given number
+    /// of validators (like Tendermint's `MaxDataBytes`).
+    ///
+    /// Fails if `max_bytes` is too small to fit the header, commit and
+    /// evidence.
+    pub fn max_data_bytes(
+        &self,
+        evidence_bytes: u64,
+        validator_count: usize,
+    ) -> Result<u64, Error> {
+        let overhead = Self::MAX_OVERHEAD_FOR_BLOCK
+            + Self::MAX_HEADER_BYTES
+            + Self::max_commit_bytes(validator_count)
+            + evidence_bytes;
+
+        self.max_bytes.checked_sub(overhead).ok_or_else(|| {
+            format_err!(
+                Kind::InvalidParams,
+                "block.max_bytes={} is too small to accommodate header, last commit and evidence ({} bytes)",
+                self.max_bytes,
+                overhead
+            )
+            .into()
+        })
+    }
+
+    /// Maximum size of the transaction data of a block without evidence
+    /// (like Tendermint's `MaxDataBytesNoEvidence`)
+    pub fn max_data_bytes_no_evidence(&self, validator_count: usize) -> Result<u64, Error> {
+        self.max_data_bytes(0, validator_count)
+    }
+}
+
+impl Default for Size {
+    /// Tendermint's default block size parameters: 21 MB blocks with no gas
+    /// limit
+    fn default() -> Self {
+        Size {
+            max_bytes: 22_020_096,
+            max_gas: -1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn max_data_bytes() {
+        let size = Size {
+            max_bytes: 1_000,
+            max_gas: -1,
+        };
+
+        assert_eq!(Size::max_commit_bytes(2), 94 + 2 * 111);
+        assert_eq!(
+            size.max_data_bytes_no_evidence(1).unwrap(),
+            1_000 - 11 - 626 - 205
+        );
+        assert_eq!(
+            size.max_data_bytes(100, 1).unwrap(),
+            1_000 - 11 - 626 - 205 - 100
+        );
+        assert!(size.max_data_bytes(200, 1).is_err());
+        assert!(size.max_data_bytes_no_evidence(4).is_err());
+    }
+}
@@ -0,0 +1,95 @@
+use crate::error::{Error, Kind};
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+use std::{
+    fmt::{self, Debug, Display},
+    str::FromStr,
+};
+
+/// Block protocol version: the version of the data structures and
+/// processing rules of the blockchain, found in block headers and in the
+/// information nodes exchange when connecting to each other.
+///
+/// Versions are ordered, so they can be compared against the versions of
+/// known Tendermint releases (e.g. `version >= block::Version::TENDERMINT_V0_34`).
+#[derive(Copy, Clone, Default, Eq, Hash, PartialEq, PartialOrd, Ord)]
+pub struct Version(pub u64);
+
+impl Version {
+    /// Block protocol version of Tendermint v0.33
+    pub const TENDERMINT_V0_33: Version = Version(10);
+
+    /// Block protocol version of Tendermint v0.34
+    pub const TENDERMINT_V0_34: Version = Version(11);
+
+    /// Get inner integer value. Alternative to `.0` or `.into()`
+    pub fn value(self) -> u64 {
+        self.0
+    }
+
+    /// Can blocks of this version and the other one be part of the same
+    /// chain? Tendermint only connects to peers with the same block version.
+    pub fn is_compatible_with(self, other: Version) -> bool {
+        self == other
+    }
+}
+
+impl Debug for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "block::Version({})", self.0)
+    }
+}
+
+impl Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl From<u64> for Version {
+    fn from(n: u64) -> Version {
+        Version(n)
+    }
+}
+
+impl From<Version> for u64 {
+    fn from(version: Version) -> u64 {
+        version.0
+    }
+}
+
+impl FromStr for Version {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        Ok(Version(
+            s.parse::<u64>().map_err(|e| Kind::Parse.context(e))?,
+        ))
+    }
+}
+
+impl<'de> Deserialize<'de> for Version {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Self::from_str(&String::deserialize(deserializer)?)
+            .map_err(|e| D::Error::custom(format!("{}", e)))
+    }
+}
+
+impl Serialize for Version {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_string().serialize(serializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compares_versions() {
+        let version = "11".parse::<Version>().unwrap();
+        assert!(version >= Version::TENDERMINT_V0_34);
+        assert!(version > Version::TENDERMINT_V0_33);
+        assert!(version.is_compatible_with(Version::TENDERMINT_V0_34));
+        assert!(!version.is_compatible_with(Version::TENDERMINT_V0_33));
+    }
+}
@@ -35,6 +35,39 @@ impl Id {
     pub fn as_bytes(&self) -> &[u8] {
         &self.as_str().as_bytes()
     }
+
+    /// Is this chain ID in the `{name}-{revision}` format used by IBC to
+    /// track chain upgrades (e.g. `cosmoshub-4`)?
+    ///
+    /// The revision number must be a positive integer without leading zeros,
+    /// preceded by a single dash.
+    pub fn is_revision_format(&self) -> bool {
+        self.parse_revision_number().is_some()
+    }
+
+    /// Get the revision number of this chain ID (e.g. `4` for `cosmoshub-4`),
+    /// or `0` if it isn't in revision format.
+    pub fn revision_number(&self) -> u64 {
+        self.parse_revision_number().unwrap_or(0)
+    }
+
+    fn parse_revision_number(&self) -> Option<u64> {
+        let id = self.as_str();
+        let dash = id.rfind('-')?;
+        let (name, revision) = (&id[..dash], &id[dash + 1..]);
+
+        if name.is_empty() || name.ends_with('-') {
+            return None;
+        }
+
+        if !revision.starts_with(|c: char| ('1'..='9').contains(&c))
+            || !revision.chars().all(|c| c.is_ascii_digit())
+        {
+            return None;
+        }
+
+        revision.parse().ok()
+    }
 }
 
 impl AsRef<str> for Id {
@@ -144,6 +177,53 @@ mod tests {
         assert_eq!(&long_id.parse::<Id>().unwrap().as_str(), &long_id);
     }
 
+    #[test]
+    fn rejects_invalid_characters() {
+        for invalid in &["gaia 9000", "gaia/9000", "gaïa-9000"] {
+            assert_eq!(
+                *invalid.parse::<Id>().unwrap_err().to_string(),
+                Kind::Parse.to_string()
+            );
+        }
+    }
+
+    #[test]
+    fn revision_numbers() {
+        for (chain_id, revision) in &[
+            ("gaia-9000", 9000),
+            ("cosmoshub-4", 4),
+            ("ibc-test-chain-1", 1),
+            ("testchain", 0),
+            ("chain-01", 0),
+            ("chain-0", 0),
+            ("chain--1", 0),
+            ("chain-1a", 0),
+            ("-1", 0),
+            ("chain-99999999999999999999", 0),
+        ] {
+            let id = chain_id.parse::<Id>().unwrap();
+            assert_eq!(id.revision_number(), *revision, "{}", chain_id);
+            assert_eq!(id.is_revision_format(), *revision != 0, "{}", chain_id);
+        }
+    }
+
+    #[test]
+    fn usable_as_map_key() {
+        use std::collections::{BTreeMap, HashMap};
+
+        let ids: Vec<Id> = ["gaia-2", "gaia-10", "cosmoshub-4"]
+            .iter()
+            .map(|id| id.parse().unwrap())
+            .collect();
+
+        let hash_map: HashMap<Id, usize> = ids.iter().cloned().zip(0..).collect();
+        assert_eq!(hash_map[&Id::from("gaia-10")], 1);
+
+        let btree_map: BTreeMap<Id, usize> = ids.iter().cloned().zip(0..).collect();
+        let keys: Vec<&str> = btree_map.keys().map(Id::as_str).collect();
+        assert_eq!(keys, ["cosmoshub-4", "gaia-10", "gaia-2"]);
+    }
+
     #[test]
     fn rejects_empty_chain_ids() {
         assert_eq!(
@@ -3,9 +3,18 @@
 mod id;
 
 pub use self::id::Id;
-use crate::serializers;
+use crate::{
+    error::{Error, Kind},
+    serializers,
+};
+use anomaly::format_err;
+use serde::{de::Error as _, Deserializer, Serializer};
 pub use serde::{Deserialize, Serialize};
-use std::fmt::{self, Display};
+use std::{
+    fmt::{self, Display},
+    str::FromStr,
+};
+use subtle_encoding::hex;
 
 /// Channels
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -31,12 +40,104 @@ pub struct Channel {
     pub recently_sent: u64,
 }
 
-/// Channel collections
-#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize, Default)]
-pub struct Channels(String);
+/// Channels a node supports, as advertised in its node info: one byte per
+/// channel ID, serialized as a hex string (e.g. `"4020212223303800"`)
+#[derive(Clone, Debug, Eq, PartialEq, Default)]
+pub struct Channels(Vec<u8>);
+
+impl Channels {
+    /// Create a new channel list from the given channel ID bytes
+    pub fn new(ids: impl Into<Vec<u8>>) -> Channels {
+        Channels(ids.into())
+    }
+
+    /// Get the channel IDs as bytes
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Iterate over the channel IDs
+    pub fn ids(&self) -> impl Iterator<Item = Id> + '_ {
+        self.0.iter().map(|&id| Id(u64::from(id)))
+    }
+
+    /// Does this list include the given channel?
+    pub fn contains(&self, id: Id) -> bool {
+        self.ids().any(|other| other.value() == id.value())
+    }
+
+    /// Number of channels
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Is the channel list empty?
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl From<Vec<u8>> for Channels {
+    fn from(ids: Vec<u8>) -> Channels {
+        Channels(ids)
+    }
+}
+
+impl From<Channels> for Vec<u8> {
+    fn from(channels: Channels) -> Vec<u8> {
+        channels.0
+    }
+}
+
+impl FromStr for Channels {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        // Accept either upper or lower case hex
+        let bytes = hex::decode_upper(s)
+            .or_else(|_| hex::decode(s))
+            .map_err(|_| format_err!(Kind::Parse, "invalid channels: {:?}", s))?;
+
+        Ok(Channels(bytes))
+    }
+}
 
 impl Display for Channels {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.0)
+        for byte in &self.0 {
+            write!(f, "{:02X}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'de> Deserialize<'de> for Channels {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Self::from_str(&String::deserialize(deserializer)?)
+            .map_err(|e| D::Error::custom(format!("{}", e)))
+    }
+}
+
+impl Serialize for Channels {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_string().serialize(serializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_channels() {
+        let channels = "4020212223303800".parse::<Channels>().unwrap();
+        assert_eq!(
+            channels.as_bytes(),
+            &[0x40, 0x20, 0x21, 0x22, 0x23, 0x30, 0x38, 0x00]
+        );
+        assert!(channels.contains(Id(0x20)));
+        assert!(!channels.contains(Id(0x60)));
+        assert_eq!(channels.to_string(), "4020212223303800");
+        assert!("not hex".parse::<Channels>().is_err());
     }
 }
@@ -1,7 +1,7 @@
 use serde::{Deserialize, Serialize};
 
 /// Channel IDs
-#[derive(Copy, Clone, Debug, Deserialize, Serialize)]
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq, Ord, PartialOrd, Deserialize, Serialize)]
 pub struct Id(pub u64);
 
 impl Id {
@@ -125,8 +125,15 @@ impl TendermintConfig {
         Self::parse_toml(toml_string)
     }
 
-    /// Load `genesis.json` file from the configured location
-    pub fn load_genesis_file(&self, home: impl AsRef<Path>) -> Result<Genesis, Error> {
+    /// Load `genesis.json` file from the configured location, deserializing
+    /// its `app_state` as `AppState`
+    pub fn load_genesis_file<AppState>(
+        &self,
+        home: impl AsRef<Path>,
+    ) -> Result<Genesis<AppState>, Error>
+    where
+        AppState: de::DeserializeOwned + Default,
+    {
         let path = home.as_ref().join(&self.genesis_file);
         let genesis_json = fs::read_to_string(&path)
             .map_err(|e| format_err!(Kind::Parse, "couldn't open {}: {}", path.display(), e))?;
@@ -141,6 +148,35 @@ impl TendermintConfig {
     }
 }
 
+/// Write a key file as pretty-printed JSON, restricting its permissions to
+/// `0600` on Unix as Tendermint does
+fn write_key_file<P, T>(path: &P, value: &T) -> Result<(), Error>
+where
+    P: AsRef<Path>,
+    T: Serialize,
+{
+    let json = zeroize::Zeroizing::new(serde_json::to_string_pretty(value)?);
+
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+    options
+        .open(path)
+        .and_then(|mut file| std::io::Write::write_all(&mut file, json.as_bytes()))
+        .map_err(|e| {
+            format_err!(
+                Kind::Io,
+                "couldn't write {}: {}",
+                path.as_ref().display(),
+                e
+            )
+            .into()
+        })
+}
+
 /// Database backend
 #[derive(Copy, Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
 pub enum DbBackend {
@@ -376,14 +412,14 @@ pub struct P2PConfig {
         serialize_with = "serialize_comma_separated_list",
         deserialize_with = "deserialize_comma_separated_list"
     )]
-    pub seeds: Vec<net::Address>,
+    pub seeds: Vec<node::Address>,
 
     /// Comma separated list of nodes to keep persistent connections to
     #[serde(
         serialize_with = "serialize_comma_separated_list",
         deserialize_with = "deserialize_comma_separated_list"
     )]
-    pub persistent_peers: Vec<net::Address>,
+    pub persistent_peers: Vec<node::Address>,
 
     /// UPNP port forwarding
     pub upnp: bool,
@@ -609,7 +645,11 @@ where
         return Ok(result);
     }
 
-    for item in string.split(',') {
+    for item in string
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+    {
         result.push(
             item.parse()
                 .map_err(|e| D::Error::custom(format!("{}", e)))?,
@@ -1,16 +1,23 @@
 //! Node keys
 
+#[cfg(feature = "keygen")]
+use crate::public_key::Algorithm;
 use crate::{
     error::{Error, Kind},
     node,
-    private_key::PrivateKey,
-    public_key::PublicKey,
+    private_key::{self, PrivateKey},
+    public_key::{self, PublicKey},
 };
-use anomaly::format_err;
+use anomaly::{fail, format_err};
+#[cfg(feature = "keygen")]
+use rand_core::{CryptoRng, RngCore};
 use serde::{Deserialize, Serialize};
 use std::{fs, path::Path};
 
-/// P2P node private keys
+/// P2P node private keys, which authenticate nodes to their peers and
+/// determine their node IDs.
+///
+/// Tendermint node keys are always Ed25519 keys.
 #[derive(Serialize, Deserialize)]
 pub struct NodeKey {
     /// Private key
@@ -18,9 +25,34 @@ pub struct NodeKey {
 }
 
 impl NodeKey {
+    /// Create a node key from the given private key. Fails unless it's an
+    /// Ed25519 key.
+    pub fn new(priv_key: PrivateKey) -> Result<Self, Error> {
+        #[allow(unreachable_patterns)]
+        match priv_key {
+            PrivateKey::Ed25519(_) => Ok(NodeKey { priv_key }),
+            other => fail!(
+                Kind::InvalidKey,
+                "node keys must be Ed25519 keys, not {}",
+                other.algorithm()
+            ),
+        }
+    }
+
+    /// Generate a new random (Ed25519) node key
+    #[cfg(feature = "keygen")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "keygen")))]
+    pub fn generate<R: CryptoRng + RngCore>(rng: &mut R) -> Self {
+        let priv_key = PrivateKey::generate(Algorithm::Ed25519, rng)
+            .expect("Ed25519 key generation is always supported");
+
+        NodeKey { priv_key }
+    }
+
     /// Parse `node_key.json`
     pub fn parse_json<T: AsRef<str>>(json_string: T) -> Result<Self, Error> {
-        Ok(serde_json::from_str(json_string.as_ref())?)
+        let result = serde_json::from_str::<Self>(json_string.as_ref())?;
+        Self::new(result.priv_key)
     }
 
     /// Load `node_key.json` from a file
@@ -40,19 +72,42 @@ impl NodeKey {
         Self::parse_json(json_string)
     }
 
+    /// Save this key as `node_key.json` at the given path
+    pub fn save_json_file<P>(&self, path: &P) -> Result<(), Error>
+    where
+        P: AsRef<Path>,
+    {
+        super::write_key_file(path, self)
+    }
+
     /// Get the public key for this keypair
     pub fn public_key(&self) -> PublicKey {
+        self.priv_key.public_key()
+    }
+
+    /// Get the Ed25519 keypair of this node key, e.g. to authenticate secret
+    /// connections with
+    pub fn ed25519_keypair(&self) -> private_key::Ed25519 {
+        #[allow(unreachable_patterns)]
         match &self.priv_key {
-            PrivateKey::Ed25519(keypair) => keypair.public.into(),
+            PrivateKey::Ed25519(keypair) => private_key::Ed25519::from_bytes(&keypair.to_bytes())
+                .expect("Ed25519 keypair round trips"),
+            _ => unreachable!("node keys are Ed25519 keys"),
         }
     }
 
-    /// Get node ID for this keypair
-    pub fn node_id(&self) -> node::Id {
+    /// Get the Ed25519 public key of this node key
+    pub fn ed25519_public_key(&self) -> public_key::Ed25519 {
         #[allow(unreachable_patterns)]
-        match &self.public_key() {
-            PublicKey::Ed25519(pubkey) => node::Id::from(*pubkey),
-            _ => unreachable!(),
+        match &self.priv_key {
+            PrivateKey::Ed25519(keypair) => keypair.public,
+            _ => unreachable!("node keys are Ed25519 keys"),
         }
     }
+
+    /// Get node ID for this keypair: the first 20 bytes of the SHA-256 hash
+    /// of its public key
+    pub fn node_id(&self) -> node::Id {
+        node::Id::from(self.ed25519_public_key())
+    }
 }
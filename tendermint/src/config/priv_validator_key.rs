@@ -1,13 +1,18 @@
 //! Validator private keys
 
+#[cfg(feature = "keygen")]
+use crate::public_key::Algorithm;
 use crate::public_key::TendermintKey;
 use crate::{
     account,
     error::{Error, Kind},
     private_key::PrivateKey,
     public_key::PublicKey,
+    serializers,
 };
 use anomaly::format_err;
+#[cfg(feature = "keygen")]
+use rand_core::{CryptoRng, RngCore};
 use serde::{Deserialize, Serialize};
 use std::{fs, path::Path};
 
@@ -18,6 +23,7 @@ pub struct PrivValidatorKey {
     pub address: account::Id,
 
     /// Public key
+    #[serde(with = "serializers::public_key")]
     pub pub_key: PublicKey,
 
     /// Private key
@@ -25,6 +31,28 @@ pub struct PrivValidatorKey {
 }
 
 impl PrivValidatorKey {
+    /// Create a validator key from the given private key, deriving its
+    /// address and public key. Fails if the key can't be used for consensus.
+    pub fn new(priv_key: PrivateKey) -> Result<Self, Error> {
+        let pub_key = *TendermintKey::new_consensus_key(priv_key.public_key())?.public_key();
+
+        Ok(PrivValidatorKey {
+            address: pub_key.address(),
+            pub_key,
+            priv_key,
+        })
+    }
+
+    /// Generate a new random validator key with the given algorithm
+    #[cfg(feature = "keygen")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "keygen")))]
+    pub fn generate<R>(algorithm: Algorithm, rng: &mut R) -> Result<Self, Error>
+    where
+        R: CryptoRng + RngCore,
+    {
+        Self::new(PrivateKey::generate(algorithm, rng)?)
+    }
+
     /// Parse `priv_validator_key.json`
     pub fn parse_json<T: AsRef<str>>(json_string: T) -> Result<Self, Error> {
         let result = serde_json::from_str::<Self>(json_string.as_ref())?;
@@ -52,6 +80,14 @@ impl PrivValidatorKey {
         Self::parse_json(json_string)
     }
 
+    /// Save this key as `priv_validator_key.json` at the given path
+    pub fn save_json_file<P>(&self, path: &P) -> Result<(), Error>
+    where
+        P: AsRef<Path>,
+    {
+        super::write_key_file(path, self)
+    }
+
     /// Get the consensus public key for this validator private key
     pub fn consensus_pubkey(&self) -> TendermintKey {
         TendermintKey::new_consensus_key(self.priv_key.public_key()).unwrap()
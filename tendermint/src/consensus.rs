@@ -2,5 +2,6 @@
 
 pub mod params;
 pub mod state;
+pub mod step;
 
-pub use self::{params::Params, state::State};
+pub use self::{params::Params, state::State, step::Step};
@@ -1,10 +1,14 @@
 //! Tendermint consensus parameters
 
-use crate::{block, evidence, public_key};
+use crate::{block, error::Kind, evidence, public_key, Error};
+use anomaly::fail;
 use serde::{Deserialize, Serialize};
+use std::time::Duration;
 
 /// Tendermint consensus parameters
-#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
+///
+/// The `Default` value matches Tendermint's defaults.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, Eq, PartialEq)]
 pub struct Params {
     /// Block size parameters
     pub block: block::Size,
@@ -16,9 +20,131 @@ pub struct Params {
     pub validator: ValidatorParams,
 }
 
+impl Params {
+    /// Check these parameters are acceptable to Tendermint (like its
+    /// `ConsensusParams.Validate`)
+    pub fn validate(&self) -> Result<(), Error> {
+        if self.block.max_bytes == 0 {
+            fail!(
+                Kind::InvalidParams,
+                "block.max_bytes must be greater than 0"
+            );
+        }
+
+        if self.block.max_bytes > block::Size::MAX_BLOCK_SIZE_BYTES {
+            fail!(
+                Kind::InvalidParams,
+                "block.max_bytes is too big: {} > {}",
+                self.block.max_bytes,
+                block::Size::MAX_BLOCK_SIZE_BYTES
+            );
+        }
+
+        if self.block.max_gas < -1 {
+            fail!(
+                Kind::InvalidParams,
+                "block.max_gas must be greater or equal to -1, got {}",
+                self.block.max_gas
+            );
+        }
+
+        if self.evidence.max_age_num_blocks == 0 {
+            fail!(
+                Kind::InvalidParams,
+                "evidence.max_age_num_blocks must be greater than 0"
+            );
+        }
+
+        if self.evidence.max_age_duration.0 == Duration::from_secs(0) {
+            fail!(
+                Kind::InvalidParams,
+                "evidence.max_age_duration must be greater than 0"
+            );
+        }
+
+        if self.validator.pub_key_types.is_empty() {
+            fail!(
+                Kind::InvalidParams,
+                "validator.pub_key_types must not be empty"
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Check that light clients using the given trusting period can be held
+    /// accountable: evidence of an attack on a light client must still be
+    /// accepted by the chain for as long as the light client trusts a header,
+    /// so the trusting period must be shorter than the maximum evidence age.
+    pub fn validate_trusting_period(&self, trusting_period: Duration) -> Result<(), Error> {
+        if trusting_period >= self.evidence.max_age_duration.0 {
+            fail!(
+                Kind::InvalidParams,
+                "trusting period ({:?}) must be shorter than evidence.max_age_duration ({:?})",
+                trusting_period,
+                self.evidence.max_age_duration.0
+            );
+        }
+
+        Ok(())
+    }
+}
+
 /// Validator consensus parameters
 #[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
 pub struct ValidatorParams {
     /// Allowed algorithms for validator signing
     pub pub_key_types: Vec<public_key::Algorithm>,
 }
+
+impl Default for ValidatorParams {
+    /// Only Ed25519 validator keys are allowed by default
+    fn default() -> Self {
+        ValidatorParams {
+            pub_key_types: vec![public_key::Algorithm::Ed25519],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_params_are_valid() {
+        let params = Params::default();
+        assert!(params.validate().is_ok());
+        assert_eq!(params.block.max_bytes, 22_020_096);
+        assert_eq!(
+            params.validator.pub_key_types,
+            vec![public_key::Algorithm::Ed25519]
+        );
+    }
+
+    #[test]
+    fn invalid_params() {
+        let mut params = Params::default();
+        params.block.max_bytes = block::Size::MAX_BLOCK_SIZE_BYTES + 1;
+        assert!(params.validate().is_err());
+
+        let mut params = Params::default();
+        params.block.max_gas = -2;
+        assert!(params.validate().is_err());
+
+        let mut params = Params::default();
+        params.evidence.max_age_num_blocks = 0;
+        assert!(params.validate().is_err());
+
+        let mut params = Params::default();
+        params.validator.pub_key_types.clear();
+        assert!(params.validate().is_err());
+    }
+
+    #[test]
+    fn trusting_period() {
+        let params = Params::default();
+        let day = Duration::from_secs(24 * 60 * 60);
+        assert!(params.validate_trusting_period(day).is_ok());
+        assert!(params.validate_trusting_period(3 * day).is_err());
+    }
+}
@@ -3,6 +3,7 @@
 pub use crate::block;
 pub use std::{cmp::Ordering, fmt};
 use {
+    super::Step,
     crate::serializers,
     serde::{Deserialize, Serialize},
 };
@@ -12,7 +13,7 @@ use {
 pub const NIL_PLACEHOLDER: &str = "<nil>";
 
 /// Tendermint consensus state
-#[derive(Serialize, Deserialize, Clone, Debug, Default, Eq, PartialEq)]
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
 pub struct State {
     /// Current block height
     pub height: block::Height,
@@ -21,8 +22,8 @@ pub struct State {
     #[serde(with = "serializers::from_str")]
     pub round: i64,
 
-    /// Current consensus step
-    pub step: i8,
+    /// Current consensus step (serialized as its numeric value)
+    pub step: Step,
 
     /// Block ID being proposed (if available)
     pub block_id: Option<block::Id>,
@@ -67,41 +68,62 @@ impl PartialOrd for State {
 #[cfg(test)]
 mod tests {
     use super::State;
-    use crate::block;
+    use crate::{block, consensus::Step};
 
     #[test]
     fn state_ord_test() {
         let new = State {
             height: block::Height::from(9001u64),
             round: 0,
-            step: 0,
+            step: Step::Propose,
             block_id: None,
         };
 
         let old = State {
             height: block::Height::from(1001u64),
             round: 1,
-            step: 0,
+            step: Step::Propose,
             block_id: None,
         };
 
         let older = State {
             height: block::Height::from(1001u64),
             round: 0,
-            step: 0,
+            step: Step::Propose,
             block_id: None,
         };
 
         let oldest = State {
             height: block::Height::default(),
             round: 0,
-            step: 0,
+            step: Step::Propose,
             block_id: None,
         };
 
+        let later_step = State {
+            step: Step::Prevote,
+            ..oldest.clone()
+        };
+
         assert!(old < new);
         assert!(older < old);
         assert!(oldest < older);
         assert!(oldest < new);
+        assert!(oldest < later_step);
+    }
+
+    #[test]
+    fn state_json() {
+        let state: State = serde_json::from_str(
+            r#"{"height": "1234", "round": "1", "step": 3, "block_id": null}"#,
+        )
+        .unwrap();
+        assert_eq!(state.step, Step::Precommit);
+        assert_eq!(serde_json::to_value(&state).unwrap()["step"], 3);
+
+        assert!(serde_json::from_str::<State>(
+            r#"{"height": "1234", "round": "1", "step": 6, "block_id": null}"#
+        )
+        .is_err());
     }
 }
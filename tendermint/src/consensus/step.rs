@@ -0,0 +1,111 @@
+//! Consensus steps at which validators sign messages
+
+use crate::{error::Kind, vote, Error, SignedMsgType};
+use anomaly::fail;
+use serde::{Deserialize, Serialize};
+use std::{convert::TryFrom, fmt};
+
+/// Step at which a message is signed, numbered as in Tendermint's `FilePV`
+/// (and its `priv_validator_state.json`).
+///
+/// Steps order messages signed at the same height and round: a validator
+/// must never sign at an earlier step than it signed at before.
+///
+/// Steps are serialized as their numeric value, and unknown values are
+/// rejected when deserializing.
+#[repr(i8)]
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(try_from = "i8", into = "i8")]
+pub enum Step {
+    /// Proposals
+    Propose = 1,
+
+    /// Prevotes
+    Prevote = 2,
+
+    /// Precommits
+    Precommit = 3,
+}
+
+impl Step {
+    /// Get the numeric value of this step
+    pub fn value(self) -> i8 {
+        self as i8
+    }
+}
+
+impl From<SignedMsgType> for Step {
+    fn from(msg_type: SignedMsgType) -> Step {
+        match msg_type {
+            SignedMsgType::Proposal => Step::Propose,
+            SignedMsgType::Prevote => Step::Prevote,
+            SignedMsgType::Precommit => Step::Precommit,
+        }
+    }
+}
+
+impl From<vote::Type> for Step {
+    fn from(vote_type: vote::Type) -> Step {
+        SignedMsgType::from(vote_type).into()
+    }
+}
+
+impl From<Step> for i8 {
+    fn from(step: Step) -> i8 {
+        step.value()
+    }
+}
+
+impl TryFrom<i8> for Step {
+    type Error = Error;
+
+    fn try_from(value: i8) -> Result<Self, Error> {
+        match value {
+            1 => Ok(Step::Propose),
+            2 => Ok(Step::Prevote),
+            3 => Ok(Step::Precommit),
+            _ => fail!(Kind::OutOfRange, "invalid signing step: {}", value),
+        }
+    }
+}
+
+impl fmt::Display for Step {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Step::Propose => write!(f, "propose"),
+            Step::Prevote => write!(f, "prevote"),
+            Step::Precommit => write!(f, "precommit"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn steps_of_signed_messages() {
+        for (msg_type, value) in &[
+            (SignedMsgType::Proposal, 1),
+            (SignedMsgType::Prevote, 2),
+            (SignedMsgType::Precommit, 3),
+        ] {
+            let step = Step::from(*msg_type);
+            assert_eq!(step.value(), *value);
+            assert_eq!(Step::try_from(*value).unwrap(), step);
+        }
+
+        assert_eq!(Step::from(vote::Type::Precommit), Step::Precommit);
+        assert!(Step::Propose < Step::Prevote && Step::Prevote < Step::Precommit);
+
+        assert!(Step::try_from(0).is_err());
+        assert!(Step::try_from(6).is_err());
+    }
+
+    #[test]
+    fn step_json() {
+        assert_eq!(serde_json::to_string(&Step::Prevote).unwrap(), "2");
+        assert_eq!(serde_json::from_str::<Step>("3").unwrap(), Step::Precommit);
+        assert!(serde_json::from_str::<Step>("6").is_err());
+    }
+}
@@ -1,6 +1,12 @@
 //! Error types
+//!
+//! Errors carry a [`Kind`] describing what went wrong, along with a chain of
+//! sources with further detail. Use [`kind`] and [`kinds`] to match on them
+//! rather than parsing error messages.
 
+use crate::{block, chain, Hash};
 use anomaly::{BoxError, Context};
+use std::error::Error as StdError;
 use thiserror::Error;
 
 /// Error type
@@ -40,6 +46,78 @@ pub enum Kind {
     /// Signature invalid
     #[error("bad signature")]
     SignatureInvalid,
+
+    /// Block or block header is malformed
+    #[error("invalid block")]
+    InvalidBlock,
+
+    /// Commit invalid or insufficiently signed
+    #[error("invalid commit")]
+    InvalidCommit,
+
+    /// Consensus parameters are invalid
+    #[error("invalid consensus parameters")]
+    InvalidParams,
+
+    /// Evidence of malfeasance is malformed or unverifiable
+    #[error("invalid evidence")]
+    InvalidEvidence,
+
+    /// Merkle proof invalid
+    #[error("invalid proof")]
+    InvalidProof,
+
+    /// Vote is malformed or doesn't belong where it was cast
+    #[error("invalid vote")]
+    InvalidVote,
+
+    /// Validator cast conflicting votes
+    #[error("conflicting vote")]
+    ConflictingVote,
+
+    /// Proposal is malformed
+    #[error("invalid proposal")]
+    InvalidProposal,
+
+    /// Hash differs from the expected one
+    #[error("hash mismatch: expected {expected}, got {actual}")]
+    HashMismatch {
+        /// Expected hash
+        expected: Hash,
+        /// Actual hash
+        actual: Hash,
+    },
+
+    /// Block height differs from the expected one
+    #[error("height mismatch: expected {expected}, got {actual}")]
+    HeightMismatch {
+        /// Expected height
+        expected: block::Height,
+        /// Actual height
+        actual: block::Height,
+    },
+
+    /// Chain ID differs from the expected one
+    #[error("chain ID mismatch: expected {expected}, got {actual}")]
+    ChainIdMismatch {
+        /// Expected chain ID
+        expected: chain::Id,
+        /// Actual chain ID
+        actual: chain::Id,
+    },
+
+    /// Signing would regress from, or conflict with, what was signed before
+    #[error("double sign attempt")]
+    DoubleSign,
+
+    /// Remote signer refused or failed to sign
+    #[error("remote signer error {code}: {description}")]
+    RemoteSigner {
+        /// Error code
+        code: i32,
+        /// Error description
+        description: String,
+    },
 }
 
 impl Kind {
@@ -48,3 +126,64 @@ impl Kind {
         Context::new(self, Some(source.into()))
     }
 }
+
+/// Get the kind of the given error, if it is a Tendermint error
+pub fn kind<'a>(error: &'a (dyn StdError + 'static)) -> Option<&'a Kind> {
+    error
+        .downcast_ref::<Context<Kind>>()
+        .map(Context::kind)
+        .or_else(|| error.downcast_ref::<Kind>())
+}
+
+/// Iterate over the kinds of the given error and of its sources, outermost
+/// first, e.g. an `InvalidCommit` followed by the `HeightMismatch` which
+/// caused it
+pub fn kinds<'a>(error: &'a (dyn StdError + 'static)) -> impl Iterator<Item = &'a Kind> {
+    let mut next = Some(error);
+
+    std::iter::from_fn(move || {
+        while let Some(error) = next {
+            next = error.source();
+
+            if let Some(kind) = kind(error) {
+                return Some(kind);
+            }
+        }
+
+        None
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anomaly::format_err;
+
+    #[test]
+    fn kinds_in_source_chain() {
+        let mismatch = Kind::HeightMismatch {
+            expected: block::Height::from(2u64),
+            actual: block::Height::from(1u64),
+        };
+        let error: Error = Kind::InvalidCommit.context(mismatch.clone()).into();
+
+        assert_eq!(kind(error.as_ref()), Some(&Kind::InvalidCommit));
+        assert_eq!(
+            kinds(error.as_ref()).collect::<Vec<_>>(),
+            vec![&Kind::InvalidCommit, &mismatch]
+        );
+        assert_eq!(
+            error.source().unwrap().to_string(),
+            "height mismatch: expected 2, got 1"
+        );
+
+        let message: Error = format_err!(Kind::Parse, "bad input").into();
+        assert_eq!(
+            kinds(message.as_ref()).collect::<Vec<_>>(),
+            vec![&Kind::Parse]
+        );
+
+        let bare: Error = Kind::Io.into();
+        assert_eq!(kind(bare.as_ref()), Some(&Kind::Io));
+    }
+}
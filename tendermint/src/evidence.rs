@@ -1,14 +1,31 @@
 //! Evidence of malfeasance by validators (i.e. signing conflicting votes).
 
+use std::convert::{TryFrom, TryInto};
 use std::slice;
 use {
-    crate::{block::signed_header::SignedHeader, serializers, PublicKey, Vote},
+    crate::{
+        account, block,
+        block::signed_header::SignedHeader,
+        chain,
+        error::Kind,
+        merkle::simple_hash_from_byte_vectors,
+        serializers,
+        signature::{DefaultVerifier, Verifier},
+        validator, vote, Error, Hash, PublicKey, Time, Vote,
+    },
+    anomaly::{fail, format_err},
+    prost::Message,
     serde::{Deserialize, Serialize},
+    sha2::{Digest, Sha256},
+    tendermint_proto::types::{
+        ConflictingHeadersEvidence as RawConflictingHeadersEvidence,
+        DuplicateVoteEvidence as RawDuplicateVoteEvidence, LightBlock as RawLightBlock,
+        LightClientAttackEvidence as RawLightClientAttackEvidence, Validator as RawValidator,
+    },
 };
 
 /// Evidence of malfeasance by validators (i.e. signing conflicting votes).
-/// encoded using an Amino prefix. There is currently only a single type of
-/// evidence: `DuplicateVoteEvidence`.
+/// encoded using an Amino prefix.
 ///
 /// <https://github.com/tendermint/spec/blob/d46cd7f573a2c6a2399fcab2cde981330aa63f37/spec/core/data_structures.md#evidence>
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
@@ -21,6 +38,55 @@ pub enum Evidence {
     /// Conflicting headers evidence
     #[serde(rename = "tendermint/ConflictingHeadersEvidence")]
     ConflictingHeaders(Box<ConflictingHeadersEvidence>),
+
+    /// Light client attack evidence
+    #[serde(rename = "tendermint/LightClientAttackEvidence")]
+    LightClientAttack(Box<LightClientAttackEvidence>),
+}
+
+impl Evidence {
+    /// Encode this evidence as the protobuf bytes which are hashed into the
+    /// `evidence_hash` of the header of the block containing it.
+    ///
+    /// [`DuplicateVoteEvidence`] doesn't record when the evidence was
+    /// committed, so its (optional) timestamp is omitted.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Error> {
+        Ok(match self {
+            Evidence::DuplicateVote(evidence) => encode_raw(&RawDuplicateVoteEvidence {
+                vote_a: Some(evidence.vote_a.clone().try_into()?),
+                vote_b: Some(evidence.vote_b.clone().try_into()?),
+                timestamp: None,
+            }),
+            Evidence::ConflictingHeaders(evidence) => encode_raw(&RawConflictingHeadersEvidence {
+                h1: Some(evidence.h1.clone().try_into()?),
+                h2: Some(evidence.h2.clone().try_into()?),
+            }),
+            Evidence::LightClientAttack(evidence) => encode_raw(
+                &RawLightClientAttackEvidence::try_from(evidence.as_ref().clone())?,
+            ),
+        })
+    }
+
+    /// Hash of this evidence: the SHA-256 digest of [`Evidence::to_bytes`]
+    pub fn hash(&self) -> Result<Hash, Error> {
+        let mut hash_bytes = [0u8; 32];
+        hash_bytes.copy_from_slice(&Sha256::digest(&self.to_bytes()?));
+        Ok(Hash::Sha256(hash_bytes))
+    }
+
+    /// Size of this evidence in bytes, as counted against the maximum amount
+    /// of evidence a block may contain
+    pub fn size(&self) -> Result<usize, Error> {
+        self.to_bytes().map(|bytes| bytes.len())
+    }
+}
+
+/// Encode a protobuf message into a freshly allocated `Vec<u8>`
+fn encode_raw<M: Message>(message: &M) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(message.encoded_len());
+    // Encoding can only fail if the buffer is too small, which a `Vec` never is
+    message.encode(&mut bytes).unwrap();
+    bytes
 }
 
 /// Duplicate vote evidence
@@ -34,6 +100,136 @@ pub struct DuplicateVoteEvidence {
     vote_b: Vote,
 }
 
+impl DuplicateVoteEvidence {
+    /// Create evidence that the validator with the given public key signed
+    /// both of the given conflicting votes.
+    ///
+    /// The votes are ordered by block ID, so the same pair of votes always
+    /// produces the same evidence. Signatures are not checked here: use
+    /// [`DuplicateVoteEvidence::verify`] for that.
+    pub fn new(pub_key: PublicKey, vote_a: Vote, vote_b: Vote) -> Result<Self, Error> {
+        let (vote_a, vote_b) = if vote_a.block_id <= vote_b.block_id {
+            (vote_a, vote_b)
+        } else {
+            (vote_b, vote_a)
+        };
+
+        let evidence = Self {
+            pub_key,
+            vote_a,
+            vote_b,
+        };
+        evidence.check_conflicting()?;
+        Ok(evidence)
+    }
+
+    /// Public key of the validator which signed the conflicting votes
+    pub fn pub_key(&self) -> &PublicKey {
+        &self.pub_key
+    }
+
+    /// First of the conflicting votes
+    pub fn vote_a(&self) -> &Vote {
+        &self.vote_a
+    }
+
+    /// Second of the conflicting votes
+    pub fn vote_b(&self) -> &Vote {
+        &self.vote_b
+    }
+
+    /// Height at which the conflicting votes were cast
+    pub fn height(&self) -> block::Height {
+        self.vote_a.height
+    }
+
+    /// Verify this evidence: the votes must be for the same height, round and
+    /// step, be cast by the same validator (the holder of this evidence's
+    /// public key) for different blocks, and both carry valid signatures for
+    /// the given chain.
+    pub fn verify(&self, chain_id: chain::Id) -> Result<(), Error> {
+        self.verify_with(chain_id, &DefaultVerifier)
+    }
+
+    /// Like [`DuplicateVoteEvidence::verify`], checking signatures with the
+    /// given [`Verifier`]
+    pub fn verify_with<V>(&self, chain_id: chain::Id, verifier: &V) -> Result<(), Error>
+    where
+        V: Verifier + ?Sized,
+    {
+        self.check_conflicting()?;
+
+        for (name, vote) in &[("A", &self.vote_a), ("B", &self.vote_b)] {
+            verifier
+                .verify(
+                    &self.pub_key,
+                    &vote.to_signable_bytes(chain_id),
+                    &vote.signature,
+                )
+                .map_err(|e| {
+                    format_err!(
+                        Kind::InvalidEvidence,
+                        "invalid signature on vote {}: {}",
+                        name,
+                        e
+                    )
+                })?;
+        }
+
+        Ok(())
+    }
+
+    /// Check the votes conflict, i.e. differ only in their block ID
+    fn check_conflicting(&self) -> Result<(), Error> {
+        let (a, b) = (&self.vote_a, &self.vote_b);
+
+        if a.height != b.height || a.round != b.round || a.vote_type != b.vote_type {
+            fail!(
+                Kind::InvalidEvidence,
+                "votes are for different height/round/step: {}/{}/{:?} vs {}/{}/{:?}",
+                a.height,
+                a.round,
+                a.vote_type,
+                b.height,
+                b.round,
+                b.vote_type
+            );
+        }
+
+        if a.validator_address != b.validator_address {
+            fail!(
+                Kind::InvalidEvidence,
+                "votes are from different validators: {} vs {}",
+                a.validator_address,
+                b.validator_address
+            );
+        }
+
+        if a.validator_index != b.validator_index {
+            fail!(
+                Kind::InvalidEvidence,
+                "votes have different validator indexes: {} vs {}",
+                a.validator_index,
+                b.validator_index
+            );
+        }
+
+        if a.block_id == b.block_id {
+            fail!(Kind::InvalidEvidence, "votes are for the same block ID");
+        }
+
+        if account::Id::from(self.pub_key) != a.validator_address {
+            fail!(
+                Kind::InvalidEvidence,
+                "public key doesn't match validator address {}",
+                a.validator_address
+            );
+        }
+
+        Ok(())
+    }
+}
+
 /// Conflicting headers evidence.
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 pub struct ConflictingHeadersEvidence {
@@ -50,6 +246,130 @@ impl ConflictingHeadersEvidence {
     }
 }
 
+/// Evidence of a light client attack: a block which conflicts with the block
+/// at the same height on the main chain, signed by validators which were
+/// trusted at a common height.
+///
+/// <https://github.com/tendermint/spec/blob/master/spec/core/data_structures.md#lightclientattackevidence>
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct LightClientAttackEvidence {
+    /// The conflicting block, along with the validator set which signed it
+    #[serde(rename = "ConflictingBlock")]
+    pub conflicting_block: ConflictingBlock,
+
+    /// Height of the last block both chains agree on
+    #[serde(rename = "CommonHeight")]
+    pub common_height: block::Height,
+
+    /// Validators which signed the conflicting block
+    #[serde(rename = "ByzantineValidators")]
+    pub byzantine_validators: Vec<validator::Info>,
+
+    /// Total voting power of the validator set at the common height
+    #[serde(rename = "TotalVotingPower")]
+    pub total_voting_power: vote::Power,
+
+    /// Timestamp of the block at the common height
+    #[serde(rename = "Timestamp")]
+    pub timestamp: Time,
+}
+
+impl LightClientAttackEvidence {
+    /// Height of the conflicting block
+    pub fn height(&self) -> block::Height {
+        self.conflicting_block.signed_header.header.height
+    }
+}
+
+impl TryFrom<RawLightClientAttackEvidence> for LightClientAttackEvidence {
+    type Error = Error;
+
+    fn try_from(value: RawLightClientAttackEvidence) -> Result<Self, Self::Error> {
+        if value.total_voting_power < 0 {
+            fail!(
+                Kind::OutOfRange,
+                "negative total voting power: {}",
+                value.total_voting_power
+            );
+        }
+
+        Ok(LightClientAttackEvidence {
+            conflicting_block: value
+                .conflicting_block
+                .ok_or_else(|| format_err!(Kind::Parse, "conflicting block is missing"))?
+                .try_into()?,
+            common_height: value.common_height.try_into()?,
+            byzantine_validators: value
+                .byzantine_validators
+                .into_iter()
+                .map(TryInto::try_into)
+                .collect::<Result<Vec<validator::Info>, Error>>()?,
+            total_voting_power: vote::Power::new(value.total_voting_power as u64),
+            timestamp: value
+                .timestamp
+                .ok_or_else(|| format_err!(Kind::Parse, "timestamp is missing"))?
+                .try_into()?,
+        })
+    }
+}
+
+impl TryFrom<LightClientAttackEvidence> for RawLightClientAttackEvidence {
+    type Error = Error;
+
+    fn try_from(value: LightClientAttackEvidence) -> Result<Self, Self::Error> {
+        Ok(RawLightClientAttackEvidence {
+            conflicting_block: Some(value.conflicting_block.try_into()?),
+            common_height: value.common_height.into(),
+            byzantine_validators: value
+                .byzantine_validators
+                .into_iter()
+                .map(TryInto::try_into)
+                .collect::<Result<Vec<RawValidator>, Error>>()?,
+            total_voting_power: value.total_voting_power.value() as i64,
+            timestamp: Some(value.timestamp.into()),
+        })
+    }
+}
+
+/// A signed header together with the validator set which signed it (a
+/// "light block"), as found in [`LightClientAttackEvidence`].
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct ConflictingBlock {
+    /// Signed header of the conflicting block
+    pub signed_header: SignedHeader,
+
+    /// Validator set which signed the conflicting block
+    pub validator_set: validator::Set,
+}
+
+impl TryFrom<RawLightBlock> for ConflictingBlock {
+    type Error = Error;
+
+    fn try_from(value: RawLightBlock) -> Result<Self, Self::Error> {
+        Ok(ConflictingBlock {
+            signed_header: value
+                .signed_header
+                .ok_or_else(|| format_err!(Kind::Parse, "signed header is missing"))?
+                .try_into()?,
+            validator_set: value
+                .validator_set
+                .ok_or_else(|| format_err!(Kind::Parse, "validator set is missing"))?
+                .try_into()?,
+        })
+    }
+}
+
+impl TryFrom<ConflictingBlock> for RawLightBlock {
+    type Error = Error;
+
+    fn try_from(value: ConflictingBlock) -> Result<Self, Self::Error> {
+        Ok(RawLightBlock {
+            signed_header: Some(value.signed_header.try_into()?),
+            validator_set: Some(value.validator_set.try_into()?),
+        })
+    }
+}
+
 /// Evidence data is a wrapper for a list of `Evidence`.
 ///
 /// <https://github.com/tendermint/spec/blob/d46cd7f573a2c6a2399fcab2cde981330aa63f37/spec/core/data_structures.md#evidencedata>
@@ -78,6 +398,35 @@ impl Data {
     pub fn iter(&self) -> slice::Iter<'_, Evidence> {
         self.as_ref().iter()
     }
+
+    /// Compute the Merkle root of the evidence, which should match the
+    /// `evidence_hash` of the header of the block containing it.
+    ///
+    /// Returns `None` if there is no evidence.
+    pub fn root_hash(&self) -> Result<Option<Hash>, Error> {
+        if self.as_ref().is_empty() {
+            return Ok(None);
+        }
+
+        let evidence_bytes = self
+            .iter()
+            .map(Evidence::to_bytes)
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        Ok(Some(Hash::Sha256(simple_hash_from_byte_vectors(
+            evidence_bytes,
+        ))))
+    }
+
+    /// Total size of the evidence in bytes
+    pub fn size(&self) -> Result<usize, Error> {
+        self.iter().map(Evidence::size).sum()
+    }
+
+    /// Does the evidence fit within the given maximum number of bytes?
+    pub fn fits_within(&self, max_bytes: u64) -> Result<bool, Error> {
+        Ok(self.size()? as u64 <= max_bytes)
+    }
 }
 
 impl AsRef<[Evidence]> for Data {
@@ -97,6 +446,17 @@ pub struct Params {
     pub max_age_duration: Duration,
 }
 
+impl Default for Params {
+    /// Tendermint's default evidence parameters: evidence expires after
+    /// 100000 blocks and 48 hours
+    fn default() -> Self {
+        Params {
+            max_age_num_blocks: 100_000,
+            max_age_duration: Duration(std::time::Duration::from_secs(48 * 60 * 60)),
+        }
+    }
+}
+
 /// Duration is a wrapper around std::time::Duration
 /// essentially, to keep the usages look cleaner
 /// i.e. you can avoid using serde annotations everywhere
@@ -108,3 +468,133 @@ impl From<Duration> for std::time::Duration {
         d.0
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{block::CommitSig, hash::Hash, signature::Signature};
+    use ed25519_dalek::{Keypair, SecretKey, Signer};
+    use std::str::FromStr;
+
+    fn signed_vote(keypair: &Keypair, chain_id: chain::Id, block_hash: u8) -> Vote {
+        let pub_key = PublicKey::from(keypair.public);
+        let mut vote = Vote {
+            vote_type: vote::Type::Prevote,
+            height: block::Height::from(10u64),
+            round: 1,
+            block_id: Some(block::Id::new(Hash::Sha256([block_hash; 32]), None)),
+            timestamp: Time::from_str("2020-09-01T00:00:00Z").unwrap(),
+            validator_address: account::Id::from(pub_key),
+            validator_index: 0,
+            signature: Signature::Ed25519(ed25519::Signature::new([0; 64])),
+            #[cfg(feature = "vote-extensions")]
+            extension: vec![],
+            #[cfg(feature = "vote-extensions")]
+            extension_signature: None,
+        };
+        vote.signature = keypair.sign(&vote.to_signable_bytes(chain_id)).into();
+        vote
+    }
+
+    #[test]
+    fn duplicate_vote_evidence() {
+        let secret = SecretKey::from_bytes(&[1; 32]).unwrap();
+        let public = (&secret).into();
+        let keypair = Keypair { secret, public };
+        let pub_key = PublicKey::from(public);
+        let chain_id = chain::Id::from("test-chain");
+
+        let vote_a = signed_vote(&keypair, chain_id, 0xAA);
+        let vote_b = signed_vote(&keypair, chain_id, 0xBB);
+
+        let evidence = DuplicateVoteEvidence::new(pub_key, vote_b.clone(), vote_a.clone()).unwrap();
+        assert_eq!(evidence.vote_a(), &vote_a);
+        assert_eq!(evidence.height(), block::Height::from(10u64));
+        assert!(evidence.verify(chain_id).is_ok());
+        assert!(evidence.verify(chain::Id::from("other-chain")).is_err());
+
+        assert!(DuplicateVoteEvidence::new(pub_key, vote_a.clone(), vote_a.clone()).is_err());
+
+        let mut other_round = vote_b;
+        other_round.round = 2;
+        assert!(DuplicateVoteEvidence::new(pub_key, vote_a, other_round).is_err());
+    }
+
+    #[test]
+    fn evidence_hash_and_size() {
+        let secret = SecretKey::from_bytes(&[1; 32]).unwrap();
+        let public = (&secret).into();
+        let keypair = Keypair { secret, public };
+        let pub_key = PublicKey::from(public);
+        let chain_id = chain::Id::from("test-chain");
+
+        let evidence = Evidence::DuplicateVote(
+            DuplicateVoteEvidence::new(
+                pub_key,
+                signed_vote(&keypair, chain_id, 0xAA),
+                signed_vote(&keypair, chain_id, 0xBB),
+            )
+            .unwrap(),
+        );
+
+        let bytes = evidence.to_bytes().unwrap();
+        assert_eq!(evidence.size().unwrap(), bytes.len());
+        assert_eq!(
+            evidence.hash().unwrap().as_bytes(),
+            Sha256::digest(&bytes).as_slice()
+        );
+
+        let data = Data::new(vec![evidence.clone(), evidence]);
+        assert_eq!(data.size().unwrap(), 2 * bytes.len());
+        assert!(data.fits_within(2 * bytes.len() as u64).unwrap());
+        assert!(!data.fits_within(2 * bytes.len() as u64 - 1).unwrap());
+        assert_eq!(
+            data.root_hash().unwrap(),
+            Some(Hash::Sha256(simple_hash_from_byte_vectors(vec![
+                bytes.clone(),
+                bytes
+            ])))
+        );
+
+        assert_eq!(Data::default().root_hash().unwrap(), None);
+        assert_eq!(Data::default().size().unwrap(), 0);
+    }
+
+    #[test]
+    fn light_client_attack_evidence_protobuf_roundtrip() {
+        let header: block::Header = serde_json::from_str(include_str!(
+            "../tests/support/serialization/block/header.json"
+        ))
+        .unwrap();
+        let commit = block::Commit {
+            height: header.height,
+            round: 0,
+            block_id: block::Id::new(header.hash(), None),
+            signatures: block::CommitSigs::new(vec![CommitSig::BlockIDFlagAbsent]),
+        };
+
+        let secret = SecretKey::from_bytes(&[2; 32]).unwrap();
+        let public = ed25519_dalek::PublicKey::from(&secret);
+        let mut validator = validator::Info::new(PublicKey::from(public), vote::Power::new(10));
+        validator.proposer_priority = Some(validator::ProposerPriority::new(0));
+
+        let evidence = LightClientAttackEvidence {
+            conflicting_block: ConflictingBlock {
+                signed_header: SignedHeader { header, commit },
+                validator_set: validator::Set::new(vec![validator]),
+            },
+            common_height: block::Height::from(10u64),
+            byzantine_validators: vec![validator],
+            total_voting_power: vote::Power::new(10),
+            timestamp: Time::from_str("2020-09-01T00:00:00Z").unwrap(),
+        };
+
+        let raw = RawLightClientAttackEvidence::try_from(evidence.clone()).unwrap();
+        assert_eq!(raw.total_voting_power, 10);
+        assert_eq!(LightClientAttackEvidence::try_from(raw).unwrap(), evidence);
+
+        let evidence = Evidence::LightClientAttack(Box::new(evidence));
+        let json = serde_json::to_string(&evidence).unwrap();
+        assert!(json.contains("\"type\":\"tendermint/LightClientAttackEvidence\""));
+    }
+}
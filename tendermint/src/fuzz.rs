@@ -0,0 +1,247 @@
+//! Fuzz targets for the decoders of messages received from peers, which are
+//! this crate's main attack surface: remote signer requests (in both their
+//! amino and protobuf encodings) and protobuf encoded domain types.
+//!
+//! Each target decodes arbitrary bytes and, if they decode, exercises what
+//! callers do with the decoded message, e.g. computing sign bytes or
+//! re-encoding it. A target only panics on a bug: either a decoder panics,
+//! or a decoded message doesn't survive re-encoding.
+//!
+//! The [cargo-fuzz] harnesses in `tendermint/fuzz` call these targets, and so
+//! can other fuzzers or regression tests replaying a crashing input:
+//!
+//! ```
+//! tendermint::fuzz::privval_amino(&[0x0a, 0x00]);
+//! ```
+//!
+//! Decoders allocate in proportion to their input, up to the limits of the
+//! framing they read: running the harnesses with libFuzzer's
+//! `-malloc_limit_mb` catches decoders trusting lengths read from the input.
+//!
+//! [cargo-fuzz]: https://github.com/rust-fuzz/cargo-fuzz
+
+use crate::{
+    abci::responses,
+    account,
+    amino_types::{Decoder, Registry, RemoteSignerMessage, SignableMsg, TimeMsg},
+    block, chain,
+    privval::{
+        self,
+        codec::{self, Codec},
+        Frame, Protocol,
+    },
+    proposal::Proposal,
+    time::ParseTimestamp,
+    vote::Vote,
+    PublicKey,
+};
+use std::{convert::TryFrom, fmt::Debug, time::SystemTime};
+use tendermint_proto::{
+    abci::ResponseCheckTx as RawResponseCheckTx,
+    privval::Message as RawMessage,
+    types::{
+        BlockId as RawBlockId, Commit as RawCommit, Header as RawHeader, Proposal as RawProposal,
+        SignedHeader as RawSignedHeader, Vote as RawVote,
+    },
+    DomainType,
+};
+
+/// Chain ID the sign bytes of fuzzed signing requests are computed for
+pub const CHAIN_ID: &str = "fuzz-chain";
+
+/// Decode an amino encoded remote signer message (without its length
+/// prefix), as sent by Tendermint 0.33 validators, and handle signing
+/// requests the way a signer does
+pub fn privval_amino(data: &[u8]) {
+    let message = match Decoder::<RemoteSignerMessage>::default().decode(data) {
+        Ok(message) => message,
+        Err(_) => return,
+    };
+
+    match message {
+        RemoteSignerMessage::SignVoteRequest(request) => {
+            signable(&request);
+            if let Some(timestamp) = request.vote.and_then(|vote| vote.timestamp) {
+                timestamp_msg(timestamp);
+            }
+        }
+        RemoteSignerMessage::SignProposalRequest(request) => {
+            signable(&request);
+            if let Some(timestamp) = request.proposal.and_then(|proposal| proposal.timestamp) {
+                timestamp_msg(timestamp);
+            }
+        }
+        _ => (),
+    }
+}
+
+/// Read a length-prefixed remote signer frame, as a signer reads the first
+/// request of a validator of either protocol version, then decode it
+/// according to the detected protocol
+pub fn privval(data: &[u8]) {
+    let bytes = match codec::read_frame_bytes(&mut &data[..]) {
+        Ok(bytes) => bytes,
+        Err(_) => return,
+    };
+
+    match Protocol::detect(&bytes, &Registry::default()) {
+        Protocol::Amino => privval_amino(&bytes),
+        Protocol::Protobuf => {
+            if let Ok(frame) = Frame::decode(&bytes) {
+                privval_frame(frame);
+            }
+        }
+    }
+}
+
+/// Decode a protobuf encoded domain type: the first byte selects the type,
+/// and the rest is its encoding
+pub fn proto(data: &[u8]) {
+    let (selector, bytes) = match data.split_first() {
+        Some(split) => split,
+        None => return,
+    };
+
+    match selector % 8 {
+        0 => {
+            if let Some(header) = round_trip::<RawHeader, block::Header>(bytes) {
+                header.hash();
+                let _ = header.validate_basic();
+            }
+        }
+        1 => {
+            if let Some(commit) = round_trip::<RawCommit, block::Commit>(bytes) {
+                let _ = commit.validate_basic();
+            }
+        }
+        2 => {
+            round_trip::<RawSignedHeader, block::signed_header::SignedHeader>(bytes);
+        }
+        3 => {
+            round_trip::<RawBlockId, block::Id>(bytes);
+        }
+        4 => {
+            round_trip::<RawVote, Vote>(bytes);
+        }
+        5 => {
+            round_trip::<RawProposal, Proposal>(bytes);
+        }
+        6 => {
+            if let Some(message) = round_trip::<RawMessage, privval::Message>(bytes) {
+                privval_frame(Frame::Message(message));
+            }
+        }
+        _ => {
+            round_trip::<RawResponseCheckTx, responses::CheckTx>(bytes);
+        }
+    }
+}
+
+/// Handle a decoded amino signing request the way a signer does
+fn signable<M: SignableMsg>(message: &M) {
+    let chain_id: chain::Id = CHAIN_ID.parse().expect("valid chain ID");
+
+    let _ = message.validate();
+    let _ = message.validate_signer(&account::Id::new([0; account::LENGTH]));
+    let _ = message.consensus_state();
+    let _ = message.height();
+    let _ = message.msg_type();
+    let _ = message.sign_bytes(chain_id);
+    let _ = message.verify(chain_id, &public_key());
+}
+
+/// Convert a timestamp received from a peer, as signers do to check it
+fn timestamp_msg(timestamp: TimeMsg) {
+    let _ = timestamp.parse_timestamp();
+    let _ = SystemTime::try_from(timestamp);
+}
+
+/// Check a decoded protobuf remote signer frame survives re-encoding,
+/// including past the maximum frame size Tendermint enforces: re-encoding
+/// may add the Go zero values of missing fields
+fn privval_frame(frame: Frame) {
+    let bytes = frame
+        .clone()
+        .encode_length_delimited()
+        .expect("decoded privval frame fails to encode");
+    let decoded = Codec::new(usize::MAX)
+        .read_frame(&mut bytes.as_slice())
+        .expect("encoded privval frame fails to decode");
+
+    assert_eq!(decoded, frame, "privval frame changed by re-encoding");
+}
+
+/// Decode a domain type, checking it survives re-encoding
+fn round_trip<T, D>(bytes: &[u8]) -> Option<D>
+where
+    T: prost::Message + TryFrom<D> + Default,
+    D: DomainType<T> + Clone + Debug + PartialEq,
+    <D as TryFrom<T>>::Error: Into<crate::Error>,
+    <T as TryFrom<D>>::Error: Into<crate::Error>,
+{
+    let value = D::decode_vec(bytes).ok()?;
+    let encoded = value
+        .encode_vec()
+        .expect("decoded domain type fails to encode");
+    let decoded = D::decode_vec(&encoded).expect("encoded domain type fails to decode");
+
+    assert_eq!(decoded, value, "domain type changed by re-encoding");
+    Some(value)
+}
+
+/// Public key fuzzed signatures are checked against
+fn public_key() -> PublicKey {
+    let secret = ed25519_dalek::SecretKey::from_bytes(&[0x42; 32]).expect("secret key is 32 bytes");
+    PublicKey::Ed25519((&secret).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::amino_types::{
+        message::AminoMessage, vote::Vote as AminoVote, PingRequest, SignVoteRequest,
+    };
+
+    #[test]
+    fn ignores_garbage() {
+        let inputs: &[&[u8]] = &[
+            &[],
+            &[0x00],
+            &[0xff; 16],
+            // Length prefix far over the maximum message size
+            &[0xff, 0xff, 0xff, 0xff, 0x0f, 0x0a],
+            &[
+                0x0a, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x01,
+            ],
+        ];
+
+        for data in inputs {
+            privval_amino(data);
+            privval(data);
+            proto(data);
+        }
+    }
+
+    #[test]
+    fn handles_malformed_amino_votes() {
+        let request = SignVoteRequest {
+            vote: Some(AminoVote {
+                vote_type: 0x01,
+                height: i64::MIN,
+                round: -1,
+                timestamp: Some(TimeMsg {
+                    seconds: i64::MAX,
+                    nanos: -1,
+                }),
+                validator_address: vec![0xab; 3],
+                validator_index: -1,
+                signature: vec![0xcd; 7],
+                ..AminoVote::default()
+            }),
+        };
+
+        privval_amino(&request.bytes_vec());
+        privval(&request.bytes_vec_length_delimited());
+        privval(&PingRequest {}.bytes_vec_length_delimited());
+    }
+}
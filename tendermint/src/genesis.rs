@@ -1,6 +1,6 @@
 //! Genesis data
 
-use crate::{chain, consensus, validator, Time};
+use crate::{chain, consensus, hash::AppHash, validator, Time};
 use serde::{Deserialize, Serialize};
 
 /// Genesis data
@@ -19,8 +19,8 @@ pub struct Genesis<AppState = serde_json::Value> {
     pub validators: Vec<validator::Info>,
 
     /// App hash
-    #[serde(skip_serializing_if = "Vec::is_empty", with = "serde_bytes")]
-    pub app_hash: Vec<u8>,
+    #[serde(default, skip_serializing_if = "AppHash::is_empty")]
+    pub app_hash: AppHash,
 
     /// App state
     #[serde(default)]
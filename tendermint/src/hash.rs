@@ -2,15 +2,59 @@
 
 use crate::error::{Error, Kind};
 use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+use sha2::{Digest, Sha256};
 use std::{
     fmt::{self, Debug, Display},
     str::FromStr,
 };
+use subtle::{self, ConstantTimeEq};
 use subtle_encoding::{Encoding, Hex};
 
 /// Output size for the SHA-256 hash function
 pub const SHA256_HASH_SIZE: usize = 32;
 
+/// Backend used to compute the SHA-256 digests in Tendermint data structures:
+/// header hashes, transaction hashes, validator set hashes, and the Merkle
+/// trees underlying them.
+///
+/// [`DefaultHasher`] computes digests in software with the `sha2` crate.
+/// Implement this trait to compute them some other way, e.g. in hardware or
+/// with a constant-time implementation.
+pub trait Hasher {
+    /// Compute the digest of `data`
+    fn digest(&self, data: &[u8]) -> [u8; SHA256_HASH_SIZE];
+
+    /// Compute the digest of the concatenation of `parts`.
+    ///
+    /// By default the parts are copied into one buffer which is passed to
+    /// [`Hasher::digest`]; implementations which can hash incrementally
+    /// should override this.
+    fn digest_parts(&self, parts: &[&[u8]]) -> [u8; SHA256_HASH_SIZE] {
+        self.digest(&parts.concat())
+    }
+}
+
+/// Software SHA-256 using the `sha2` crate
+#[derive(Copy, Clone, Debug, Default)]
+pub struct DefaultHasher;
+
+impl Hasher for DefaultHasher {
+    fn digest(&self, data: &[u8]) -> [u8; SHA256_HASH_SIZE] {
+        self.digest_parts(&[data])
+    }
+
+    fn digest_parts(&self, parts: &[&[u8]]) -> [u8; SHA256_HASH_SIZE] {
+        let mut hasher = Sha256::new();
+        for part in parts {
+            hasher.update(part);
+        }
+
+        let mut hash_bytes = [0u8; SHA256_HASH_SIZE];
+        hash_bytes.copy_from_slice(&hasher.finalize());
+        hash_bytes
+    }
+}
+
 /// Hash algorithms
 #[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
 pub enum Algorithm {
@@ -68,6 +112,13 @@ impl Hash {
     }
 }
 
+impl ConstantTimeEq for Hash {
+    #[inline]
+    fn ct_eq(&self, other: &Hash) -> subtle::Choice {
+        self.as_bytes().ct_eq(other.as_bytes())
+    }
+}
+
 impl Debug for Hash {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -111,3 +162,131 @@ impl Serialize for Hash {
         self.to_string().serialize(serializer)
     }
 }
+
+/// Application state hashes (`AppHash`), as computed by the ABCI application.
+///
+/// Unlike [`Hash`], app hashes are opaque to Tendermint: applications may use
+/// roots of any length, and the hash is empty before the first block.
+#[derive(Clone, Default, Eq, Hash, PartialEq, PartialOrd, Ord)]
+pub struct AppHash(Vec<u8>);
+
+impl AppHash {
+    /// Decode an `AppHash` from (upper or lower case) hexadecimal
+    pub fn from_hex(s: &str) -> Result<AppHash, Error> {
+        Hex::upper_case()
+            .decode(s.as_bytes())
+            .or_else(|_| Hex::lower_case().decode(s.as_bytes()))
+            .map(AppHash)
+            .map_err(|e| Kind::Parse.context(e).into())
+    }
+
+    /// Borrow the `AppHash` as a byte slice
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Is this app hash empty?
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl AsRef<[u8]> for AppHash {
+    fn as_ref(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+impl From<Vec<u8>> for AppHash {
+    fn from(bytes: Vec<u8>) -> AppHash {
+        AppHash(bytes)
+    }
+}
+
+impl From<AppHash> for Vec<u8> {
+    fn from(app_hash: AppHash) -> Vec<u8> {
+        app_hash.0
+    }
+}
+
+impl From<Hash> for AppHash {
+    fn from(hash: Hash) -> AppHash {
+        AppHash(hash.as_bytes().to_vec())
+    }
+}
+
+impl Debug for AppHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "AppHash({})", self)
+    }
+}
+
+impl Display for AppHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            Hex::upper_case().encode_to_string(&self.0).unwrap()
+        )
+    }
+}
+
+impl FromStr for AppHash {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        Self::from_hex(s)
+    }
+}
+
+impl<'de> Deserialize<'de> for AppHash {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let hex = Option::<String>::deserialize(deserializer)?.unwrap_or_default();
+        Self::from_hex(&hex).map_err(|e| D::Error::custom(format!("{}", e)))
+    }
+}
+
+impl Serialize for AppHash {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_string().serialize(serializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn app_hash_of_any_length() {
+        for hex in &["", "0000000000000000", "6170705f68617368"] {
+            let app_hash = hex.parse::<AppHash>().unwrap();
+            assert_eq!(app_hash.as_bytes().len(), hex.len() / 2);
+            assert_eq!(app_hash.to_string(), hex.to_uppercase());
+        }
+
+        assert!("not hex".parse::<AppHash>().is_err());
+    }
+
+    #[test]
+    fn app_hash_serde() {
+        let app_hash: AppHash = serde_json::from_str(r#""6170705F68617368""#).unwrap();
+        assert_eq!(app_hash.as_bytes(), b"app_hash");
+        assert_eq!(
+            serde_json::to_string(&app_hash).unwrap(),
+            r#""6170705F68617368""#
+        );
+
+        let empty: AppHash = serde_json::from_str("null").unwrap();
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn constant_time_eq() {
+        let hash = Hash::Sha256([0xAB; SHA256_HASH_SIZE]);
+        let mut other_bytes = [0xAB; SHA256_HASH_SIZE];
+        other_bytes[SHA256_HASH_SIZE - 1] = 0;
+
+        assert!(bool::from(hash.ct_eq(&hash)));
+        assert!(!bool::from(hash.ct_eq(&Hash::Sha256(other_bytes))));
+    }
+}
@@ -24,6 +24,7 @@ pub mod error;
 
 pub mod abci;
 pub mod account;
+pub mod app;
 pub mod amino_types;
 pub mod block;
 pub mod chain;
@@ -31,16 +32,26 @@ pub mod channel;
 pub mod config;
 pub mod consensus;
 pub mod evidence;
+#[cfg(feature = "fuzzing")]
+#[cfg_attr(docsrs, doc(cfg(feature = "fuzzing")))]
+pub mod fuzz;
 pub mod genesis;
 pub mod hash;
 pub mod merkle;
 mod moniker;
 pub mod net;
 pub mod node;
+pub mod p2p;
 pub mod private_key;
+pub mod privval;
+pub mod proposal;
 pub mod public_key;
 pub mod serializers;
 pub mod signature;
+mod signed_msg_type;
+#[cfg(feature = "strategies")]
+#[cfg_attr(docsrs, doc(cfg(feature = "strategies")))]
+pub mod strategies;
 pub mod time;
 mod timeout;
 pub mod trust_threshold;
@@ -58,8 +69,10 @@ pub use crate::{
     hash::Hash,
     moniker::Moniker,
     private_key::PrivateKey,
+    proposal::Proposal,
     public_key::{PublicKey, TendermintKey},
     signature::Signature,
+    signed_msg_type::SignedMsgType,
     time::Time,
     timeout::Timeout,
     version::Version,
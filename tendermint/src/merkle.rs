@@ -1,8 +1,10 @@
 //! Merkle tree used in Tendermint networks
 
+pub mod ics23;
 pub mod proof;
 
-use sha2::{Digest, Sha256};
+use crate::hash::{DefaultHasher, Hasher};
+use proof::SimpleProof;
 
 /// Size of Merkle root hash
 pub const HASH_SIZE: usize = 32;
@@ -10,24 +12,139 @@ pub const HASH_SIZE: usize = 32;
 /// Hash is the output of the cryptographic digest function
 pub type Hash = [u8; HASH_SIZE];
 
+/// Domain separation prefix of leaf hashes
+const LEAF_PREFIX: &[u8] = &[0x00];
+
+/// Domain separation prefix of inner node hashes
+const INNER_PREFIX: &[u8] = &[0x01];
+
 /// Compute a simple Merkle root from vectors of arbitrary byte vectors.
 /// The leaves of the tree are the bytes of the given byte vectors in
 /// the given order.
 pub fn simple_hash_from_byte_vectors(byte_vecs: Vec<Vec<u8>>) -> Hash {
-    simple_hash_from_byte_slices_inner(byte_vecs.as_slice())
+    simple_hash_from_byte_vectors_with(&byte_vecs, &DefaultHasher)
+}
+
+/// Compute a simple Merkle root from vectors of arbitrary byte vectors using
+/// the given [`Hasher`]
+pub fn simple_hash_from_byte_vectors_with<H>(byte_vecs: &[Vec<u8>], hasher: &H) -> Hash
+where
+    H: Hasher + ?Sized,
+{
+    simple_hash_from_byte_slices_inner(byte_vecs, hasher)
+}
+
+/// Compute a simple Merkle root from vectors of arbitrary byte vectors, along
+/// with a proof of the inclusion of each of them in the tree.
+pub fn simple_proofs_from_byte_vectors(byte_vecs: &[Vec<u8>]) -> (Hash, Vec<SimpleProof>) {
+    simple_proofs_from_byte_vectors_with(byte_vecs, &DefaultHasher)
+}
+
+/// Compute a simple Merkle root and inclusion proofs from vectors of
+/// arbitrary byte vectors using the given [`Hasher`]
+pub fn simple_proofs_from_byte_vectors_with<H>(
+    byte_vecs: &[Vec<u8>],
+    hasher: &H,
+) -> (Hash, Vec<SimpleProof>)
+where
+    H: Hasher + ?Sized,
+{
+    let leaf_hashes = byte_vecs
+        .iter()
+        .map(|bytes| leaf_hash_with(bytes, hasher))
+        .collect::<Vec<_>>();
+    let mut aunts = vec![vec![]; leaf_hashes.len()];
+    let root_hash = simple_trails_inner(&leaf_hashes, &mut aunts, hasher);
+
+    let total = leaf_hashes.len() as u64;
+    let proofs = leaf_hashes
+        .into_iter()
+        .zip(aunts)
+        .enumerate()
+        .map(|(index, (leaf_hash, aunts))| SimpleProof {
+            total,
+            index: index as u64,
+            leaf_hash,
+            aunts,
+        })
+        .collect();
+
+    (root_hash, proofs)
+}
+
+// recurse into subtrees, appending each subtree's sibling to the aunts of
+// all of its leaves
+fn simple_trails_inner<H>(leaf_hashes: &[Hash], aunts: &mut [Vec<Hash>], hasher: &H) -> Hash
+where
+    H: Hasher + ?Sized,
+{
+    let length = leaf_hashes.len();
+    match length {
+        0 => [0; HASH_SIZE],
+        1 => leaf_hashes[0],
+        _ => {
+            let k = get_split_point(length);
+            let (left_aunts, right_aunts) = aunts.split_at_mut(k);
+            let left = simple_trails_inner(&leaf_hashes[..k], left_aunts, hasher);
+            let right = simple_trails_inner(&leaf_hashes[k..], right_aunts, hasher);
+            left_aunts.iter_mut().for_each(|a| a.push(right));
+            right_aunts.iter_mut().for_each(|a| a.push(left));
+            inner_hash_with(&left, &right, hasher)
+        }
+    }
+}
+
+// recompute the root hash from a leaf hash and its aunts, or `None` if they
+// are inconsistent with the given index and total
+fn compute_hash_from_aunts<H>(
+    index: u64,
+    total: u64,
+    leaf_hash: Hash,
+    aunts: &[Hash],
+    hasher: &H,
+) -> Option<Hash>
+where
+    H: Hasher + ?Sized,
+{
+    if index >= total {
+        return None;
+    }
+
+    if total == 1 {
+        return if aunts.is_empty() {
+            Some(leaf_hash)
+        } else {
+            None
+        };
+    }
+
+    let (last, rest) = aunts.split_last()?;
+    let num_left = get_split_point(total as usize) as u64;
+
+    if index < num_left {
+        let left = compute_hash_from_aunts(index, num_left, leaf_hash, rest, hasher)?;
+        Some(inner_hash_with(&left, last, hasher))
+    } else {
+        let right =
+            compute_hash_from_aunts(index - num_left, total - num_left, leaf_hash, rest, hasher)?;
+        Some(inner_hash_with(last, &right, hasher))
+    }
 }
 
 // recurse into subtrees
-fn simple_hash_from_byte_slices_inner(byte_slices: &[Vec<u8>]) -> Hash {
+fn simple_hash_from_byte_slices_inner<H>(byte_slices: &[Vec<u8>], hasher: &H) -> Hash
+where
+    H: Hasher + ?Sized,
+{
     let length = byte_slices.len();
     match length {
         0 => [0; HASH_SIZE],
-        1 => leaf_hash(byte_slices[0].as_slice()),
+        1 => leaf_hash_with(byte_slices[0].as_slice(), hasher),
         _ => {
             let k = get_split_point(length);
-            let left = simple_hash_from_byte_slices_inner(&byte_slices[..k]);
-            let right = simple_hash_from_byte_slices_inner(&byte_slices[k..]);
-            inner_hash(&left, &right)
+            let left = simple_hash_from_byte_slices_inner(&byte_slices[..k], hasher);
+            let right = simple_hash_from_byte_slices_inner(&byte_slices[k..], hasher);
+            inner_hash_with(&left, &right, hasher)
         }
     }
 }
@@ -44,35 +161,21 @@ fn get_split_point(length: usize) -> usize {
 
 // tmhash(0x00 || leaf)
 fn leaf_hash(bytes: &[u8]) -> Hash {
-    // make a new array starting with 0 and copy in the bytes
-    let mut leaf_bytes = Vec::with_capacity(bytes.len() + 1);
-    leaf_bytes.push(0x00);
-    leaf_bytes.extend_from_slice(bytes);
-
-    // hash it !
-    let digest = Sha256::digest(&leaf_bytes);
-
-    // copy the GenericArray out
-    let mut hash_bytes = [0u8; HASH_SIZE];
-    hash_bytes.copy_from_slice(&digest);
-    hash_bytes
+    leaf_hash_with(bytes, &DefaultHasher)
+}
+
+fn leaf_hash_with<H: Hasher + ?Sized>(bytes: &[u8], hasher: &H) -> Hash {
+    hasher.digest_parts(&[LEAF_PREFIX, bytes])
 }
 
 // tmhash(0x01 || left || right)
+#[cfg(test)]
 fn inner_hash(left: &[u8], right: &[u8]) -> Hash {
-    // make a new array starting with 0x1 and copy in the bytes
-    let mut inner_bytes = Vec::with_capacity(left.len() + right.len() + 1);
-    inner_bytes.push(0x01);
-    inner_bytes.extend_from_slice(left);
-    inner_bytes.extend_from_slice(right);
-
-    // hash it !
-    let digest = Sha256::digest(&inner_bytes);
-
-    // copy the GenericArray out
-    let mut hash_bytes = [0u8; HASH_SIZE];
-    hash_bytes.copy_from_slice(&digest);
-    hash_bytes
+    inner_hash_with(left, right, &DefaultHasher)
+}
+
+fn inner_hash_with<H: Hasher + ?Sized>(left: &[u8], right: &[u8], hasher: &H) -> Hash {
+    hasher.digest_parts(&[INNER_PREFIX, left, right])
 }
 
 #[cfg(test)]
@@ -128,6 +231,88 @@ mod tests {
         assert_eq!(leaf_root, &root);
     }
 
+    #[test]
+    fn test_simple_proofs() {
+        for total in 0..20 {
+            let items: Vec<Vec<u8>> = (0..total).map(|i| vec![i as u8; i + 1]).collect();
+            let (root, proofs) = simple_proofs_from_byte_vectors(&items);
+
+            assert_eq!(root, simple_hash_from_byte_vectors(items.clone()));
+            assert_eq!(proofs.len(), total);
+
+            for (i, proof) in proofs.iter().enumerate() {
+                assert_eq!(proof.total, total as u64);
+                assert_eq!(proof.index, i as u64);
+                assert!(proof.verify(&root, &items[i]).is_ok());
+
+                // wrong leaf
+                assert!(proof.verify(&root, b"not a leaf").is_err());
+
+                // wrong index
+                let mut wrong_index = proof.clone();
+                wrong_index.index = (wrong_index.index + 1) % wrong_index.total;
+                if total > 1 {
+                    assert!(wrong_index.verify(&root, &items[i]).is_err());
+                }
+
+                // tampered aunt
+                if let Some(aunt) = proof.aunts.first() {
+                    let mut tampered = proof.clone();
+                    tampered.aunts[0] = inner_hash(aunt, aunt);
+                    assert!(tampered.verify(&root, &items[i]).is_err());
+                }
+
+                // missing aunt
+                let mut truncated = proof.clone();
+                if truncated.aunts.pop().is_some() {
+                    assert_eq!(truncated.compute_root_hash(), None);
+                }
+            }
+        }
+    }
+
+    /// Hasher relying on the default `digest_parts`, which concatenates the
+    /// parts before hashing them
+    struct ConcatHasher;
+
+    impl Hasher for ConcatHasher {
+        fn digest(&self, data: &[u8]) -> Hash {
+            DefaultHasher.digest(data)
+        }
+    }
+
+    /// Hasher which isn't SHA-256 at all
+    struct XorHasher;
+
+    impl Hasher for XorHasher {
+        fn digest(&self, data: &[u8]) -> Hash {
+            let mut hash = [0u8; HASH_SIZE];
+            for (i, byte) in data.iter().enumerate() {
+                hash[i % HASH_SIZE] ^= byte;
+            }
+            hash
+        }
+    }
+
+    #[test]
+    fn test_custom_hashers() {
+        let items: Vec<Vec<u8>> = (0..7).map(|i| vec![i as u8; i + 1]).collect();
+        let root = simple_hash_from_byte_vectors(items.clone());
+
+        assert_eq!(
+            simple_hash_from_byte_vectors_with(&items, &ConcatHasher),
+            root
+        );
+        assert_ne!(simple_hash_from_byte_vectors_with(&items, &XorHasher), root);
+
+        let (xor_root, proofs) = simple_proofs_from_byte_vectors_with(&items, &XorHasher);
+        assert_eq!(
+            xor_root,
+            simple_hash_from_byte_vectors_with(&items, &XorHasher)
+        );
+        assert_eq!(proofs.len(), items.len());
+    }
+
     #[test]
     fn test_rfc6962_node() {
         let node_hash_hex = "aa217fe888e47007fa15edab33c2b492a722cb106c64667fc2b044444de66bbb";
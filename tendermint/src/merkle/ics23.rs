@@ -0,0 +1,1187 @@
+//! ICS-23 commitment proofs
+//!
+//! Domain types for the proofs defined by the Cosmos Interchain Standards
+//! (ICS-23), along with their verification against a commitment root:
+//!
+//! <https://github.com/cosmos/ics/tree/master/spec/ics-023-vector-commitments>
+//! <https://github.com/confio/ics23>
+
+use crate::{
+    error::{Error, Kind},
+    Hash,
+};
+use anomaly::{fail, format_err};
+use ripemd160::Ripemd160;
+use sha2::{Digest, Sha256, Sha512};
+use std::convert::{TryFrom, TryInto};
+use tendermint_proto::ics23::{
+    batch_entry, commitment_proof, compressed_batch_entry, BatchEntry as RawBatchEntry,
+    BatchProof as RawBatchProof, CommitmentProof as RawCommitmentProof,
+    CompressedBatchProof as RawCompressedBatchProof,
+    CompressedExistenceProof as RawCompressedExistenceProof, ExistenceProof as RawExistenceProof,
+    HashOp as RawHashOp, InnerOp as RawInnerOp, InnerSpec as RawInnerSpec, LeafOp as RawLeafOp,
+    LengthOp as RawLengthOp, NonExistenceProof as RawNonExistenceProof, ProofSpec as RawProofSpec,
+};
+
+/// Root of a commitment (e.g. the application hash of a block) which
+/// proofs are verified against
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct CommitmentRoot(Vec<u8>);
+
+impl CommitmentRoot {
+    /// Create a new commitment root from raw bytes
+    pub fn new<V>(into_vec: V) -> CommitmentRoot
+    where
+        V: Into<Vec<u8>>,
+    {
+        CommitmentRoot(into_vec.into())
+    }
+
+    /// Borrow the commitment root as a byte slice
+    pub fn as_bytes(&self) -> &[u8] {
+        self.0.as_slice()
+    }
+}
+
+impl AsRef<[u8]> for CommitmentRoot {
+    fn as_ref(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+impl From<Vec<u8>> for CommitmentRoot {
+    fn from(bytes: Vec<u8>) -> CommitmentRoot {
+        CommitmentRoot(bytes)
+    }
+}
+
+impl From<Hash> for CommitmentRoot {
+    fn from(hash: Hash) -> CommitmentRoot {
+        CommitmentRoot(hash.as_bytes().to_vec())
+    }
+}
+
+/// Hash operations applied by leaf and inner nodes
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub enum HashOp {
+    /// Pass the data through unmodified
+    NoHash,
+
+    /// SHA-256
+    Sha256,
+
+    /// SHA-512
+    Sha512,
+
+    /// Keccak (unsupported for verification)
+    Keccak,
+
+    /// RIPEMD-160
+    Ripemd160,
+
+    /// RIPEMD-160(SHA-256(data))
+    Bitcoin,
+}
+
+impl HashOp {
+    /// Apply this hash operation to the given data
+    pub fn apply(self, data: &[u8]) -> Result<Vec<u8>, Error> {
+        match self {
+            HashOp::NoHash => Ok(data.to_vec()),
+            HashOp::Sha256 => Ok(Sha256::digest(data).to_vec()),
+            HashOp::Sha512 => Ok(Sha512::digest(data).to_vec()),
+            HashOp::Ripemd160 => Ok(Ripemd160::digest(data).to_vec()),
+            HashOp::Bitcoin => Ok(Ripemd160::digest(&Sha256::digest(data)).to_vec()),
+            HashOp::Keccak => fail!(Kind::InvalidProof, "unsupported hash operation: Keccak"),
+        }
+    }
+}
+
+impl TryFrom<i32> for HashOp {
+    type Error = Error;
+
+    fn try_from(value: i32) -> Result<Self, Error> {
+        match RawHashOp::from_i32(value) {
+            Some(RawHashOp::NoHash) => Ok(HashOp::NoHash),
+            Some(RawHashOp::Sha256) => Ok(HashOp::Sha256),
+            Some(RawHashOp::Sha512) => Ok(HashOp::Sha512),
+            Some(RawHashOp::Keccak) => Ok(HashOp::Keccak),
+            Some(RawHashOp::Ripemd160) => Ok(HashOp::Ripemd160),
+            Some(RawHashOp::Bitcoin) => Ok(HashOp::Bitcoin),
+            None => fail!(Kind::Parse, "invalid hash operation: {}", value),
+        }
+    }
+}
+
+impl From<HashOp> for i32 {
+    fn from(op: HashOp) -> i32 {
+        let raw = match op {
+            HashOp::NoHash => RawHashOp::NoHash,
+            HashOp::Sha256 => RawHashOp::Sha256,
+            HashOp::Sha512 => RawHashOp::Sha512,
+            HashOp::Keccak => RawHashOp::Keccak,
+            HashOp::Ripemd160 => RawHashOp::Ripemd160,
+            HashOp::Bitcoin => RawHashOp::Bitcoin,
+        };
+        raw as i32
+    }
+}
+
+/// Length prefixing operations applied to keys and values in leaf nodes
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub enum LengthOp {
+    /// Don't include any length information
+    NoPrefix,
+
+    /// Protobuf varint encoding of the length
+    VarProto,
+
+    /// RLP encoding of the length (unsupported for verification)
+    VarRlp,
+
+    /// Big-endian 32-bit length
+    Fixed32Big,
+
+    /// Little-endian 32-bit length
+    Fixed32Little,
+
+    /// Big-endian 64-bit length
+    Fixed64Big,
+
+    /// Little-endian 64-bit length
+    Fixed64Little,
+
+    /// No length prefix, but the data must be exactly 32 bytes
+    Require32Bytes,
+
+    /// No length prefix, but the data must be exactly 64 bytes
+    Require64Bytes,
+}
+
+impl LengthOp {
+    /// Prefix the given data with its length as specified by this operation
+    pub fn apply(self, data: &[u8]) -> Result<Vec<u8>, Error> {
+        let mut result = match self {
+            LengthOp::NoPrefix => vec![],
+            LengthOp::VarProto => {
+                let mut prefix = vec![];
+                prost_amino::encoding::encode_varint(data.len() as u64, &mut prefix);
+                prefix
+            }
+            LengthOp::Fixed32Big => (data.len() as u32).to_be_bytes().to_vec(),
+            LengthOp::Fixed32Little => (data.len() as u32).to_le_bytes().to_vec(),
+            LengthOp::Fixed64Big => (data.len() as u64).to_be_bytes().to_vec(),
+            LengthOp::Fixed64Little => (data.len() as u64).to_le_bytes().to_vec(),
+            LengthOp::Require32Bytes => {
+                if data.len() != 32 {
+                    fail!(Kind::InvalidProof, "expected 32 bytes, got {}", data.len());
+                }
+                vec![]
+            }
+            LengthOp::Require64Bytes => {
+                if data.len() != 64 {
+                    fail!(Kind::InvalidProof, "expected 64 bytes, got {}", data.len());
+                }
+                vec![]
+            }
+            LengthOp::VarRlp => fail!(Kind::InvalidProof, "unsupported length operation: VarRlp"),
+        };
+
+        result.extend_from_slice(data);
+        Ok(result)
+    }
+}
+
+impl TryFrom<i32> for LengthOp {
+    type Error = Error;
+
+    fn try_from(value: i32) -> Result<Self, Error> {
+        match RawLengthOp::from_i32(value) {
+            Some(RawLengthOp::NoPrefix) => Ok(LengthOp::NoPrefix),
+            Some(RawLengthOp::VarProto) => Ok(LengthOp::VarProto),
+            Some(RawLengthOp::VarRlp) => Ok(LengthOp::VarRlp),
+            Some(RawLengthOp::Fixed32Big) => Ok(LengthOp::Fixed32Big),
+            Some(RawLengthOp::Fixed32Little) => Ok(LengthOp::Fixed32Little),
+            Some(RawLengthOp::Fixed64Big) => Ok(LengthOp::Fixed64Big),
+            Some(RawLengthOp::Fixed64Little) => Ok(LengthOp::Fixed64Little),
+            Some(RawLengthOp::Require32Bytes) => Ok(LengthOp::Require32Bytes),
+            Some(RawLengthOp::Require64Bytes) => Ok(LengthOp::Require64Bytes),
+            None => fail!(Kind::Parse, "invalid length operation: {}", value),
+        }
+    }
+}
+
+impl From<LengthOp> for i32 {
+    fn from(op: LengthOp) -> i32 {
+        let raw = match op {
+            LengthOp::NoPrefix => RawLengthOp::NoPrefix,
+            LengthOp::VarProto => RawLengthOp::VarProto,
+            LengthOp::VarRlp => RawLengthOp::VarRlp,
+            LengthOp::Fixed32Big => RawLengthOp::Fixed32Big,
+            LengthOp::Fixed32Little => RawLengthOp::Fixed32Little,
+            LengthOp::Fixed64Big => RawLengthOp::Fixed64Big,
+            LengthOp::Fixed64Little => RawLengthOp::Fixed64Little,
+            LengthOp::Require32Bytes => RawLengthOp::Require32Bytes,
+            LengthOp::Require64Bytes => RawLengthOp::Require64Bytes,
+        };
+        raw as i32
+    }
+}
+
+/// Leaf node operation: turns a key/value pair into the leaf hash
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LeafOp {
+    /// Hash applied to the full leaf preimage
+    pub hash: HashOp,
+
+    /// Hash applied to the key before length-prefixing it
+    pub prehash_key: HashOp,
+
+    /// Hash applied to the value before length-prefixing it
+    pub prehash_value: HashOp,
+
+    /// Length prefix applied to the (pre-hashed) key and value
+    pub length: LengthOp,
+
+    /// Fixed prefix distinguishing leaf nodes from inner nodes
+    pub prefix: Vec<u8>,
+}
+
+impl LeafOp {
+    /// Compute the leaf hash for the given key and value
+    pub fn apply(&self, key: &[u8], value: &[u8]) -> Result<Vec<u8>, Error> {
+        if key.is_empty() {
+            fail!(Kind::InvalidProof, "leaf op needs key");
+        }
+        if value.is_empty() {
+            fail!(Kind::InvalidProof, "leaf op needs value");
+        }
+
+        let mut preimage = self.prefix.clone();
+        preimage.extend(self.length.apply(&self.prehash_key.apply(key)?)?);
+        preimage.extend(self.length.apply(&self.prehash_value.apply(value)?)?);
+        self.hash.apply(&preimage)
+    }
+
+    fn check_against_spec(&self, spec: &LeafOp) -> Result<(), Error> {
+        if self.hash != spec.hash {
+            fail!(
+                Kind::InvalidProof,
+                "unexpected leaf hash op: {:?}",
+                self.hash
+            );
+        }
+        if self.prehash_key != spec.prehash_key {
+            fail!(
+                Kind::InvalidProof,
+                "unexpected leaf prehash key op: {:?}",
+                self.prehash_key
+            );
+        }
+        if self.prehash_value != spec.prehash_value {
+            fail!(
+                Kind::InvalidProof,
+                "unexpected leaf prehash value op: {:?}",
+                self.prehash_value
+            );
+        }
+        if self.length != spec.length {
+            fail!(
+                Kind::InvalidProof,
+                "unexpected leaf length op: {:?}",
+                self.length
+            );
+        }
+        if !self.prefix.starts_with(&spec.prefix) {
+            fail!(
+                Kind::InvalidProof,
+                "leaf prefix doesn't start with spec prefix"
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Inner node operation: hashes a child together with its siblings
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct InnerOp {
+    /// Hash applied to `prefix || child || suffix`
+    pub hash: HashOp,
+
+    /// Bytes preceding the child hash (including any left siblings)
+    pub prefix: Vec<u8>,
+
+    /// Bytes following the child hash (including any right siblings)
+    pub suffix: Vec<u8>,
+}
+
+impl InnerOp {
+    /// Compute the hash of the parent node given the hash of the child
+    pub fn apply(&self, child: &[u8]) -> Result<Vec<u8>, Error> {
+        if child.is_empty() {
+            fail!(Kind::InvalidProof, "inner op needs child value");
+        }
+
+        let mut preimage = self.prefix.clone();
+        preimage.extend_from_slice(child);
+        preimage.extend_from_slice(&self.suffix);
+        self.hash.apply(&preimage)
+    }
+
+    fn check_against_spec(&self, spec: &ProofSpec) -> Result<(), Error> {
+        let inner_spec = &spec.inner_spec;
+
+        if self.hash != inner_spec.hash {
+            fail!(
+                Kind::InvalidProof,
+                "unexpected inner hash op: {:?}",
+                self.hash
+            );
+        }
+        if !spec.leaf_spec.prefix.is_empty() && self.prefix.starts_with(&spec.leaf_spec.prefix) {
+            fail!(Kind::InvalidProof, "inner prefix starts with leaf prefix");
+        }
+        if self.prefix.len() < inner_spec.min_prefix_length {
+            fail!(Kind::InvalidProof, "inner prefix too short");
+        }
+
+        let max_left_child_bytes =
+            inner_spec.child_order.len().saturating_sub(1) * inner_spec.child_size;
+        if self.prefix.len() > inner_spec.max_prefix_length + max_left_child_bytes {
+            fail!(Kind::InvalidProof, "inner prefix too long");
+        }
+        if inner_spec.child_size == 0 || self.suffix.len() % inner_spec.child_size != 0 {
+            fail!(
+                Kind::InvalidProof,
+                "inner suffix is not a multiple of the child size"
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Proof that a key/value pair is included under a commitment root
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ExistenceProof {
+    /// Key being proven
+    pub key: Vec<u8>,
+
+    /// Value being proven
+    pub value: Vec<u8>,
+
+    /// Operation computing the leaf hash
+    pub leaf: LeafOp,
+
+    /// Operations from the leaf up to the root
+    pub path: Vec<InnerOp>,
+}
+
+impl ExistenceProof {
+    /// Compute the commitment root implied by this proof
+    pub fn calculate_root(&self) -> Result<CommitmentRoot, Error> {
+        let mut hash = self.leaf.apply(&self.key, &self.value)?;
+        for step in &self.path {
+            hash = step.apply(&hash)?;
+        }
+        Ok(CommitmentRoot(hash))
+    }
+
+    /// Verify that this proof commits the given key/value pair to `root`
+    /// according to `spec`
+    pub fn verify(
+        &self,
+        spec: &ProofSpec,
+        root: &CommitmentRoot,
+        key: &[u8],
+        value: &[u8],
+    ) -> Result<(), Error> {
+        self.check_against_spec(spec)?;
+
+        if self.key != key {
+            fail!(Kind::InvalidProof, "provided key doesn't match proof");
+        }
+        if self.value != value {
+            fail!(Kind::InvalidProof, "provided value doesn't match proof");
+        }
+        if &self.calculate_root()? != root {
+            fail!(
+                Kind::InvalidProof,
+                "calculated root doesn't match provided root"
+            );
+        }
+        Ok(())
+    }
+
+    fn check_against_spec(&self, spec: &ProofSpec) -> Result<(), Error> {
+        self.leaf.check_against_spec(&spec.leaf_spec)?;
+
+        if spec.min_depth > 0 && self.path.len() < spec.min_depth {
+            fail!(
+                Kind::InvalidProof,
+                "inner path too short: {}",
+                self.path.len()
+            );
+        }
+        if spec.max_depth > 0 && self.path.len() > spec.max_depth {
+            fail!(
+                Kind::InvalidProof,
+                "inner path too long: {}",
+                self.path.len()
+            );
+        }
+
+        for step in &self.path {
+            step.check_against_spec(spec)?;
+        }
+        Ok(())
+    }
+}
+
+/// Proof that a key is absent under a commitment root, given by proofs of
+/// its immediate neighbors
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct NonExistenceProof {
+    /// Key proven to be absent
+    pub key: Vec<u8>,
+
+    /// Proof of the neighbor to the left of the key (if any)
+    pub left: Option<ExistenceProof>,
+
+    /// Proof of the neighbor to the right of the key (if any)
+    pub right: Option<ExistenceProof>,
+}
+
+impl NonExistenceProof {
+    /// Verify that this proof shows `key` is absent from the tree committed
+    /// to by `root` according to `spec`
+    pub fn verify(&self, spec: &ProofSpec, root: &CommitmentRoot, key: &[u8]) -> Result<(), Error> {
+        if let Some(left) = &self.left {
+            left.verify(spec, root, &left.key, &left.value)?;
+            if key <= left.key.as_slice() {
+                fail!(Kind::InvalidProof, "key is not right of left proof");
+            }
+        }
+
+        if let Some(right) = &self.right {
+            right.verify(spec, root, &right.key, &right.value)?;
+            if key >= right.key.as_slice() {
+                fail!(Kind::InvalidProof, "key is not left of right proof");
+            }
+        }
+
+        let inner_spec = &spec.inner_spec;
+        match (&self.left, &self.right) {
+            (None, None) => fail!(Kind::InvalidProof, "both left and right proofs missing"),
+            (None, Some(right)) => {
+                if !is_left_most(inner_spec, &right.path) {
+                    fail!(Kind::InvalidProof, "right proof is not left-most");
+                }
+            }
+            (Some(left), None) => {
+                if !is_right_most(inner_spec, &left.path) {
+                    fail!(Kind::InvalidProof, "left proof is not right-most");
+                }
+            }
+            (Some(left), Some(right)) => {
+                if !is_left_neighbor(inner_spec, &left.path, &right.path) {
+                    fail!(
+                        Kind::InvalidProof,
+                        "left and right proofs are not neighbors"
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Entries of a [`BatchProof`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum BatchEntry {
+    /// Existence proof
+    Exist(ExistenceProof),
+
+    /// Non-existence proof
+    Nonexist(NonExistenceProof),
+}
+
+/// Multiple proofs against the same commitment root
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct BatchProof {
+    /// Individual proofs
+    pub entries: Vec<BatchEntry>,
+}
+
+/// Commitment proofs: existence, non-existence, or batches thereof.
+///
+/// Compressed batch proofs are expanded when converted from their proto
+/// representation.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum CommitmentProof {
+    /// Existence proof
+    Exist(ExistenceProof),
+
+    /// Non-existence proof
+    Nonexist(NonExistenceProof),
+
+    /// Batch of proofs
+    Batch(BatchProof),
+}
+
+impl CommitmentProof {
+    /// Verify that this proof commits `key` to `value` under `root`
+    pub fn verify_membership(
+        &self,
+        spec: &ProofSpec,
+        root: &CommitmentRoot,
+        key: &[u8],
+        value: &[u8],
+    ) -> Result<(), Error> {
+        let proof = match self {
+            CommitmentProof::Exist(proof) => Some(proof),
+            CommitmentProof::Batch(batch) => batch.entries.iter().find_map(|entry| match entry {
+                BatchEntry::Exist(proof) if proof.key == key => Some(proof),
+                _ => None,
+            }),
+            CommitmentProof::Nonexist(_) => None,
+        };
+
+        match proof {
+            Some(proof) => proof.verify(spec, root, key, value),
+            None => fail!(Kind::InvalidProof, "no existence proof for key"),
+        }
+    }
+
+    /// Verify that this proof shows `key` is absent under `root`
+    pub fn verify_non_membership(
+        &self,
+        spec: &ProofSpec,
+        root: &CommitmentRoot,
+        key: &[u8],
+    ) -> Result<(), Error> {
+        let proof = match self {
+            CommitmentProof::Nonexist(proof) => Some(proof),
+            CommitmentProof::Batch(batch) => batch.entries.iter().find_map(|entry| match entry {
+                BatchEntry::Nonexist(proof) if proof.brackets(key) => Some(proof),
+                _ => None,
+            }),
+            CommitmentProof::Exist(_) => None,
+        };
+
+        match proof {
+            Some(proof) => proof.verify(spec, root, key),
+            None => fail!(Kind::InvalidProof, "no non-existence proof for key"),
+        }
+    }
+}
+
+impl NonExistenceProof {
+    /// Whether the neighbors in this proof surround the given key
+    fn brackets(&self, key: &[u8]) -> bool {
+        self.left
+            .as_ref()
+            .map_or(true, |left| left.key.as_slice() < key)
+            && self
+                .right
+                .as_ref()
+                .map_or(true, |right| right.key.as_slice() > key)
+    }
+}
+
+/// Tree layout expected by a verifier, preventing proofs from being
+/// reinterpreted against a different tree structure
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ProofSpec {
+    /// Expected leaf operation (the prefix only needs to match the start)
+    pub leaf_spec: LeafOp,
+
+    /// Expected layout of inner nodes
+    pub inner_spec: InnerSpec,
+
+    /// Maximum number of inner operations (0 for unbounded)
+    pub max_depth: usize,
+
+    /// Minimum number of inner operations (0 for unbounded)
+    pub min_depth: usize,
+}
+
+impl ProofSpec {
+    /// Proof spec for IAVL trees (e.g. the Cosmos SDK stores)
+    pub fn iavl() -> ProofSpec {
+        ProofSpec {
+            leaf_spec: LeafOp {
+                hash: HashOp::Sha256,
+                prehash_key: HashOp::NoHash,
+                prehash_value: HashOp::Sha256,
+                length: LengthOp::VarProto,
+                prefix: vec![0],
+            },
+            inner_spec: InnerSpec {
+                child_order: vec![0, 1],
+                child_size: 33,
+                min_prefix_length: 4,
+                max_prefix_length: 12,
+                empty_child: vec![],
+                hash: HashOp::Sha256,
+            },
+            max_depth: 0,
+            min_depth: 0,
+        }
+    }
+
+    /// Proof spec for Tendermint simple Merkle trees
+    pub fn tendermint() -> ProofSpec {
+        ProofSpec {
+            leaf_spec: LeafOp {
+                hash: HashOp::Sha256,
+                prehash_key: HashOp::NoHash,
+                prehash_value: HashOp::Sha256,
+                length: LengthOp::VarProto,
+                prefix: vec![0],
+            },
+            inner_spec: InnerSpec {
+                child_order: vec![0, 1],
+                child_size: 32,
+                min_prefix_length: 1,
+                max_prefix_length: 1,
+                empty_child: vec![],
+                hash: HashOp::Sha256,
+            },
+            max_depth: 0,
+            min_depth: 0,
+        }
+    }
+}
+
+/// Layout of inner nodes, used to determine whether two proofs are neighbors
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct InnerSpec {
+    /// Ordering of the children of a node (e.g. `[0, 1]` for left then right)
+    pub child_order: Vec<usize>,
+
+    /// Size in bytes of each child hash
+    pub child_size: usize,
+
+    /// Minimum length of the fixed part of an inner prefix
+    pub min_prefix_length: usize,
+
+    /// Maximum length of the fixed part of an inner prefix
+    pub max_prefix_length: usize,
+
+    /// Preimage used for an empty child
+    pub empty_child: Vec<u8>,
+
+    /// Hash operation all inner nodes must use
+    pub hash: HashOp,
+}
+
+impl InnerSpec {
+    /// Expected (min prefix, max prefix, suffix) lengths of a step taking the
+    /// given branch
+    fn padding(&self, branch: usize) -> Option<(usize, usize, usize)> {
+        let idx = self.child_order.iter().position(|&b| b == branch)?;
+        let prefix = idx * self.child_size;
+        let suffix = (self.child_order.len() - 1 - idx) * self.child_size;
+        Some((
+            prefix + self.min_prefix_length,
+            prefix + self.max_prefix_length,
+            suffix,
+        ))
+    }
+
+    /// Which branch the given step takes, judging by its padding
+    fn order_from_padding(&self, step: &InnerOp) -> Option<usize> {
+        (0..self.child_order.len()).find(|&branch| {
+            self.padding(branch)
+                .map_or(false, |padding| has_padding(step, padding))
+        })
+    }
+}
+
+fn has_padding(step: &InnerOp, (min_prefix, max_prefix, suffix): (usize, usize, usize)) -> bool {
+    step.prefix.len() >= min_prefix
+        && step.prefix.len() <= max_prefix
+        && step.suffix.len() == suffix
+}
+
+fn is_left_most(spec: &InnerSpec, path: &[InnerOp]) -> bool {
+    match spec.padding(0) {
+        Some(padding) => path.iter().all(|step| has_padding(step, padding)),
+        None => false,
+    }
+}
+
+fn is_right_most(spec: &InnerSpec, path: &[InnerOp]) -> bool {
+    match spec.padding(spec.child_order.len().saturating_sub(1)) {
+        Some(padding) => path.iter().all(|step| has_padding(step, padding)),
+        None => false,
+    }
+}
+
+fn is_left_step(spec: &InnerSpec, left: &InnerOp, right: &InnerOp) -> bool {
+    match (
+        spec.order_from_padding(left),
+        spec.order_from_padding(right),
+    ) {
+        (Some(left_idx), Some(right_idx)) => right_idx == left_idx + 1,
+        _ => false,
+    }
+}
+
+fn is_left_neighbor(spec: &InnerSpec, left: &[InnerOp], right: &[InnerOp]) -> bool {
+    let (mut left_top, mut left_rest) = match left.split_last() {
+        Some(split) => split,
+        None => return false,
+    };
+    let (mut right_top, mut right_rest) = match right.split_last() {
+        Some(split) => split,
+        None => return false,
+    };
+
+    // Walk down from the root while both paths share the same nodes
+    while left_top.prefix == right_top.prefix && left_top.suffix == right_top.suffix {
+        match (left_rest.split_last(), right_rest.split_last()) {
+            (Some(l), Some(r)) => {
+                left_top = l.0;
+                left_rest = l.1;
+                right_top = r.0;
+                right_rest = r.1;
+            }
+            _ => return false,
+        }
+    }
+
+    is_left_step(spec, left_top, right_top)
+        && is_right_most(spec, left_rest)
+        && is_left_most(spec, right_rest)
+}
+
+impl TryFrom<RawLeafOp> for LeafOp {
+    type Error = Error;
+
+    fn try_from(value: RawLeafOp) -> Result<Self, Error> {
+        Ok(LeafOp {
+            hash: value.hash.try_into()?,
+            prehash_key: value.prehash_key.try_into()?,
+            prehash_value: value.prehash_value.try_into()?,
+            length: value.length.try_into()?,
+            prefix: value.prefix,
+        })
+    }
+}
+
+impl From<LeafOp> for RawLeafOp {
+    fn from(value: LeafOp) -> RawLeafOp {
+        RawLeafOp {
+            hash: value.hash.into(),
+            prehash_key: value.prehash_key.into(),
+            prehash_value: value.prehash_value.into(),
+            length: value.length.into(),
+            prefix: value.prefix,
+        }
+    }
+}
+
+impl TryFrom<RawInnerOp> for InnerOp {
+    type Error = Error;
+
+    fn try_from(value: RawInnerOp) -> Result<Self, Error> {
+        Ok(InnerOp {
+            hash: value.hash.try_into()?,
+            prefix: value.prefix,
+            suffix: value.suffix,
+        })
+    }
+}
+
+impl From<InnerOp> for RawInnerOp {
+    fn from(value: InnerOp) -> RawInnerOp {
+        RawInnerOp {
+            hash: value.hash.into(),
+            prefix: value.prefix,
+            suffix: value.suffix,
+        }
+    }
+}
+
+impl TryFrom<RawExistenceProof> for ExistenceProof {
+    type Error = Error;
+
+    fn try_from(value: RawExistenceProof) -> Result<Self, Error> {
+        let leaf = value
+            .leaf
+            .ok_or_else(|| format_err!(Kind::Parse, "missing leaf op"))?;
+
+        Ok(ExistenceProof {
+            key: value.key,
+            value: value.value,
+            leaf: leaf.try_into()?,
+            path: value
+                .path
+                .into_iter()
+                .map(TryInto::try_into)
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+impl From<ExistenceProof> for RawExistenceProof {
+    fn from(value: ExistenceProof) -> RawExistenceProof {
+        RawExistenceProof {
+            key: value.key,
+            value: value.value,
+            leaf: Some(value.leaf.into()),
+            path: value.path.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl TryFrom<RawNonExistenceProof> for NonExistenceProof {
+    type Error = Error;
+
+    fn try_from(value: RawNonExistenceProof) -> Result<Self, Error> {
+        Ok(NonExistenceProof {
+            key: value.key,
+            left: value.left.map(TryInto::try_into).transpose()?,
+            right: value.right.map(TryInto::try_into).transpose()?,
+        })
+    }
+}
+
+impl From<NonExistenceProof> for RawNonExistenceProof {
+    fn from(value: NonExistenceProof) -> RawNonExistenceProof {
+        RawNonExistenceProof {
+            key: value.key,
+            left: value.left.map(Into::into),
+            right: value.right.map(Into::into),
+        }
+    }
+}
+
+impl TryFrom<RawBatchEntry> for BatchEntry {
+    type Error = Error;
+
+    fn try_from(value: RawBatchEntry) -> Result<Self, Error> {
+        match value.proof {
+            Some(batch_entry::Proof::Exist(proof)) => Ok(BatchEntry::Exist(proof.try_into()?)),
+            Some(batch_entry::Proof::Nonexist(proof)) => {
+                Ok(BatchEntry::Nonexist(proof.try_into()?))
+            }
+            None => fail!(Kind::Parse, "missing batch entry proof"),
+        }
+    }
+}
+
+impl From<BatchEntry> for RawBatchEntry {
+    fn from(value: BatchEntry) -> RawBatchEntry {
+        let proof = match value {
+            BatchEntry::Exist(proof) => batch_entry::Proof::Exist(proof.into()),
+            BatchEntry::Nonexist(proof) => batch_entry::Proof::Nonexist(proof.into()),
+        };
+        RawBatchEntry { proof: Some(proof) }
+    }
+}
+
+impl TryFrom<RawBatchProof> for BatchProof {
+    type Error = Error;
+
+    fn try_from(value: RawBatchProof) -> Result<Self, Error> {
+        Ok(BatchProof {
+            entries: value
+                .entries
+                .into_iter()
+                .map(TryInto::try_into)
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+impl From<BatchProof> for RawBatchProof {
+    fn from(value: BatchProof) -> RawBatchProof {
+        RawBatchProof {
+            entries: value.entries.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl TryFrom<RawCompressedBatchProof> for BatchProof {
+    type Error = Error;
+
+    fn try_from(value: RawCompressedBatchProof) -> Result<Self, Error> {
+        let lookup = value
+            .lookup_inners
+            .into_iter()
+            .map(TryInto::try_into)
+            .collect::<Result<Vec<InnerOp>, _>>()?;
+
+        let decompress = |proof: RawCompressedExistenceProof| -> Result<ExistenceProof, Error> {
+            let leaf = proof
+                .leaf
+                .ok_or_else(|| format_err!(Kind::Parse, "missing leaf op"))?;
+            let path = proof
+                .path
+                .into_iter()
+                .map(|idx| {
+                    usize::try_from(idx)
+                        .ok()
+                        .and_then(|idx| lookup.get(idx))
+                        .cloned()
+                        .ok_or_else(|| format_err!(Kind::Parse, "invalid inner op index: {}", idx))
+                })
+                .collect::<Result<_, _>>()?;
+
+            Ok(ExistenceProof {
+                key: proof.key,
+                value: proof.value,
+                leaf: leaf.try_into()?,
+                path,
+            })
+        };
+
+        let mut entries = Vec::with_capacity(value.entries.len());
+        for entry in value.entries {
+            entries.push(match entry.proof {
+                Some(compressed_batch_entry::Proof::Exist(proof)) => {
+                    BatchEntry::Exist(decompress(proof)?)
+                }
+                Some(compressed_batch_entry::Proof::Nonexist(proof)) => {
+                    BatchEntry::Nonexist(NonExistenceProof {
+                        key: proof.key,
+                        left: proof.left.map(&decompress).transpose()?,
+                        right: proof.right.map(&decompress).transpose()?,
+                    })
+                }
+                None => fail!(Kind::Parse, "missing batch entry proof"),
+            });
+        }
+
+        Ok(BatchProof { entries })
+    }
+}
+
+impl TryFrom<RawCommitmentProof> for CommitmentProof {
+    type Error = Error;
+
+    fn try_from(value: RawCommitmentProof) -> Result<Self, Error> {
+        match value.proof {
+            Some(commitment_proof::Proof::Exist(proof)) => {
+                Ok(CommitmentProof::Exist(proof.try_into()?))
+            }
+            Some(commitment_proof::Proof::Nonexist(proof)) => {
+                Ok(CommitmentProof::Nonexist(proof.try_into()?))
+            }
+            Some(commitment_proof::Proof::Batch(proof)) => {
+                Ok(CommitmentProof::Batch(proof.try_into()?))
+            }
+            Some(commitment_proof::Proof::Compressed(proof)) => {
+                Ok(CommitmentProof::Batch(proof.try_into()?))
+            }
+            None => fail!(Kind::Parse, "missing commitment proof"),
+        }
+    }
+}
+
+impl From<CommitmentProof> for RawCommitmentProof {
+    fn from(value: CommitmentProof) -> RawCommitmentProof {
+        let proof = match value {
+            CommitmentProof::Exist(proof) => commitment_proof::Proof::Exist(proof.into()),
+            CommitmentProof::Nonexist(proof) => commitment_proof::Proof::Nonexist(proof.into()),
+            CommitmentProof::Batch(proof) => commitment_proof::Proof::Batch(proof.into()),
+        };
+        RawCommitmentProof { proof: Some(proof) }
+    }
+}
+
+impl TryFrom<RawInnerSpec> for InnerSpec {
+    type Error = Error;
+
+    fn try_from(value: RawInnerSpec) -> Result<Self, Error> {
+        let to_usize = |n: i32| {
+            usize::try_from(n).map_err(|_| format_err!(Kind::Parse, "negative value: {}", n))
+        };
+
+        Ok(InnerSpec {
+            child_order: value
+                .child_order
+                .into_iter()
+                .map(to_usize)
+                .collect::<Result<_, _>>()?,
+            child_size: to_usize(value.child_size)?,
+            min_prefix_length: to_usize(value.min_prefix_length)?,
+            max_prefix_length: to_usize(value.max_prefix_length)?,
+            empty_child: value.empty_child,
+            hash: value.hash.try_into()?,
+        })
+    }
+}
+
+impl From<InnerSpec> for RawInnerSpec {
+    fn from(value: InnerSpec) -> RawInnerSpec {
+        RawInnerSpec {
+            child_order: value.child_order.into_iter().map(|n| n as i32).collect(),
+            child_size: value.child_size as i32,
+            min_prefix_length: value.min_prefix_length as i32,
+            max_prefix_length: value.max_prefix_length as i32,
+            empty_child: value.empty_child,
+            hash: value.hash.into(),
+        }
+    }
+}
+
+impl TryFrom<RawProofSpec> for ProofSpec {
+    type Error = Error;
+
+    fn try_from(value: RawProofSpec) -> Result<Self, Error> {
+        let leaf_spec = value
+            .leaf_spec
+            .ok_or_else(|| format_err!(Kind::Parse, "missing leaf spec"))?;
+        let inner_spec = value
+            .inner_spec
+            .ok_or_else(|| format_err!(Kind::Parse, "missing inner spec"))?;
+
+        Ok(ProofSpec {
+            leaf_spec: leaf_spec.try_into()?,
+            inner_spec: inner_spec.try_into()?,
+            max_depth: usize::try_from(value.max_depth)
+                .map_err(|_| format_err!(Kind::Parse, "negative max depth"))?,
+            min_depth: usize::try_from(value.min_depth)
+                .map_err(|_| format_err!(Kind::Parse, "negative min depth"))?,
+        })
+    }
+}
+
+impl From<ProofSpec> for RawProofSpec {
+    fn from(value: ProofSpec) -> RawProofSpec {
+        RawProofSpec {
+            leaf_spec: Some(value.leaf_spec.into()),
+            inner_spec: Some(value.inner_spec.into()),
+            max_depth: value.max_depth as i32,
+            min_depth: value.min_depth as i32,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use subtle_encoding::hex;
+
+    // Tendermint simple Merkle tree with two leaves: ("a", "apple") and ("c", "cherry")
+    const LEFT_LEAF_HASH: &str = "5fa436c7e4af21eea2ed433ee9b370e4ce6429b9e31cd8c9969998d6532ccc38";
+    const RIGHT_LEAF_HASH: &str =
+        "1298536a6a93557f2e55ff934e6e29fb68e454045d89ceaf50e01e96358655c1";
+    const ROOT_HASH: &str = "6befaf3c7e0ed1b674f6734753b4a0660f05fef0bffcb58724c17a82097ffae0";
+
+    fn inner_op(prefix: Vec<u8>, suffix: Vec<u8>) -> InnerOp {
+        InnerOp {
+            hash: HashOp::Sha256,
+            prefix,
+            suffix,
+        }
+    }
+
+    fn left_proof() -> ExistenceProof {
+        ExistenceProof {
+            key: b"a".to_vec(),
+            value: b"apple".to_vec(),
+            leaf: ProofSpec::tendermint().leaf_spec,
+            path: vec![inner_op(vec![1], hex::decode(RIGHT_LEAF_HASH).unwrap())],
+        }
+    }
+
+    fn right_proof() -> ExistenceProof {
+        let mut prefix = vec![1];
+        prefix.extend(hex::decode(LEFT_LEAF_HASH).unwrap());
+
+        ExistenceProof {
+            key: b"c".to_vec(),
+            value: b"cherry".to_vec(),
+            leaf: ProofSpec::tendermint().leaf_spec,
+            path: vec![inner_op(prefix, vec![])],
+        }
+    }
+
+    fn root() -> CommitmentRoot {
+        CommitmentRoot::new(hex::decode(ROOT_HASH).unwrap())
+    }
+
+    #[test]
+    fn verifies_existence() {
+        let spec = ProofSpec::tendermint();
+        let left = CommitmentProof::Exist(left_proof());
+        let right = CommitmentProof::Exist(right_proof());
+
+        left.verify_membership(&spec, &root(), b"a", b"apple")
+            .unwrap();
+        right
+            .verify_membership(&spec, &root(), b"c", b"cherry")
+            .unwrap();
+
+        assert!(left
+            .verify_membership(&spec, &root(), b"a", b"avocado")
+            .is_err());
+        assert!(left
+            .verify_membership(&ProofSpec::iavl(), &root(), b"a", b"apple")
+            .is_err());
+    }
+
+    #[test]
+    fn verifies_non_existence() {
+        let spec = ProofSpec::tendermint();
+        let between = NonExistenceProof {
+            key: b"b".to_vec(),
+            left: Some(left_proof()),
+            right: Some(right_proof()),
+        };
+        let after = NonExistenceProof {
+            key: b"d".to_vec(),
+            left: Some(right_proof()),
+            right: None,
+        };
+
+        between.verify(&spec, &root(), b"b").unwrap();
+        after.verify(&spec, &root(), b"d").unwrap();
+
+        // Neighbors are not adjacent when swapped around
+        let swapped = NonExistenceProof {
+            key: b"b".to_vec(),
+            left: None,
+            right: Some(right_proof()),
+        };
+        assert!(swapped.verify(&spec, &root(), b"b").is_err());
+    }
+
+    #[test]
+    fn verifies_batch() {
+        let spec = ProofSpec::tendermint();
+        let batch = CommitmentProof::Batch(BatchProof {
+            entries: vec![
+                BatchEntry::Exist(left_proof()),
+                BatchEntry::Nonexist(NonExistenceProof {
+                    key: b"b".to_vec(),
+                    left: Some(left_proof()),
+                    right: Some(right_proof()),
+                }),
+            ],
+        });
+
+        batch
+            .verify_membership(&spec, &root(), b"a", b"apple")
+            .unwrap();
+        batch.verify_non_membership(&spec, &root(), b"b").unwrap();
+        assert!(batch
+            .verify_membership(&spec, &root(), b"c", b"cherry")
+            .is_err());
+    }
+
+    #[test]
+    fn proto_roundtrip() {
+        let proof = CommitmentProof::Exist(left_proof());
+        let raw = RawCommitmentProof::from(proof.clone());
+        assert_eq!(CommitmentProof::try_from(raw).unwrap(), proof);
+
+        let spec = ProofSpec::iavl();
+        let raw = RawProofSpec::from(spec.clone());
+        assert_eq!(ProofSpec::try_from(raw).unwrap(), spec);
+    }
+}
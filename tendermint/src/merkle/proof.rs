@@ -1,7 +1,17 @@
 //! Merkle proofs
 
-use crate::serializers;
+use super::{compute_hash_from_aunts, leaf_hash, Hash, HASH_SIZE};
+use crate::hash::DefaultHasher;
+use crate::{error::Kind, serializers, Error};
+use anomaly::{fail, format_err};
 use serde::{Deserialize, Serialize};
+use std::convert::{TryFrom, TryInto};
+use tendermint_proto::crypto::Proof as RawSimpleProof;
+use tendermint_proto::DomainType;
+
+/// Maximum number of aunts a `SimpleProof` may contain. Matches `MaxAunts`
+/// from: <https://github.com/tendermint/tendermint/blob/v0.34.0/crypto/merkle/proof.go>
+pub const MAX_AUNTS: usize = 100;
 
 /// Proof is Merkle proof defined by the list of ProofOps
 /// <https://github.com/tendermint/tendermint/blob/c8483531d8e756f7fbb812db1dd16d841cdf298a/crypto/merkle/merkle.proto#L26>
@@ -28,6 +38,153 @@ pub struct ProofOp {
     pub data: Vec<u8>,
 }
 
+/// SimpleProof is a proof of the inclusion of a leaf in a simple Merkle tree,
+/// as computed by [`simple_proofs_from_byte_vectors`](super::simple_proofs_from_byte_vectors).
+///
+/// The aunts are the sibling hashes on the path from the leaf to the root,
+/// ordered from the bottom of the tree up.
+/// <https://github.com/tendermint/tendermint/blob/v0.34.0/crypto/merkle/proof.go>
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "SimpleProofJson", into = "SimpleProofJson")]
+pub struct SimpleProof {
+    /// Total number of leaves in the tree
+    pub total: u64,
+    /// Index of the proven leaf
+    pub index: u64,
+    /// Hash of the proven leaf
+    pub leaf_hash: Hash,
+    /// Sibling hashes from the leaf up to the root
+    pub aunts: Vec<Hash>,
+}
+
+impl SimpleProof {
+    /// Compute the root hash implied by this proof, or `None` if the proof
+    /// is malformed
+    pub fn compute_root_hash(&self) -> Option<Hash> {
+        compute_hash_from_aunts(
+            self.index,
+            self.total,
+            self.leaf_hash,
+            &self.aunts,
+            &DefaultHasher,
+        )
+    }
+
+    /// Verify that this proof proves the inclusion of `leaf` in the tree with
+    /// the given root hash
+    pub fn verify(&self, root_hash: &Hash, leaf: &[u8]) -> Result<(), Error> {
+        if self.leaf_hash != leaf_hash(leaf) {
+            fail!(Kind::InvalidProof, "leaf hash does not match proof");
+        }
+
+        match self.compute_root_hash() {
+            Some(computed) if &computed == root_hash => Ok(()),
+            Some(_) => fail!(Kind::InvalidProof, "root hash does not match proof"),
+            None => fail!(Kind::InvalidProof, "malformed proof"),
+        }
+    }
+}
+
+impl DomainType<RawSimpleProof> for SimpleProof {}
+
+impl TryFrom<RawSimpleProof> for SimpleProof {
+    type Error = Error;
+
+    fn try_from(value: RawSimpleProof) -> Result<Self, Self::Error> {
+        if value.total < 0 {
+            fail!(Kind::OutOfRange, "negative proof total: {}", value.total);
+        }
+
+        if value.index < 0 {
+            fail!(Kind::OutOfRange, "negative proof index: {}", value.index);
+        }
+
+        if value.aunts.len() > MAX_AUNTS {
+            fail!(
+                Kind::InvalidProof,
+                "too many aunts: {} (max {})",
+                value.aunts.len(),
+                MAX_AUNTS
+            );
+        }
+
+        let aunts = value
+            .aunts
+            .iter()
+            .map(|aunt| proof_hash(aunt))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(SimpleProof {
+            total: value.total as u64,
+            index: value.index as u64,
+            leaf_hash: proof_hash(&value.leaf_hash)?,
+            aunts,
+        })
+    }
+}
+
+impl From<SimpleProof> for RawSimpleProof {
+    fn from(value: SimpleProof) -> Self {
+        RawSimpleProof {
+            total: value.total as i64,
+            index: value.index as i64,
+            leaf_hash: value.leaf_hash.to_vec(),
+            aunts: value.aunts.iter().map(|aunt| aunt.to_vec()).collect(),
+        }
+    }
+}
+
+/// JSON representation of a `SimpleProof`, as produced by Tendermint
+#[derive(Serialize, Deserialize)]
+struct SimpleProofJson {
+    #[serde(with = "serializers::from_str")]
+    total: i64,
+    #[serde(with = "serializers::from_str")]
+    index: i64,
+    #[serde(with = "serializers::bytes::base64string")]
+    leaf_hash: Vec<u8>,
+    #[serde(default, with = "serializers::bytes::vec_base64string")]
+    aunts: Vec<Vec<u8>>,
+}
+
+impl TryFrom<SimpleProofJson> for SimpleProof {
+    type Error = Error;
+
+    fn try_from(value: SimpleProofJson) -> Result<Self, Self::Error> {
+        SimpleProof::try_from(RawSimpleProof {
+            total: value.total,
+            index: value.index,
+            leaf_hash: value.leaf_hash,
+            aunts: value.aunts,
+        })
+    }
+}
+
+impl From<SimpleProof> for SimpleProofJson {
+    fn from(value: SimpleProof) -> Self {
+        let raw = RawSimpleProof::from(value);
+
+        SimpleProofJson {
+            total: raw.total,
+            index: raw.index,
+            leaf_hash: raw.leaf_hash,
+            aunts: raw.aunts,
+        }
+    }
+}
+
+fn proof_hash(bytes: &[u8]) -> Result<Hash, Error> {
+    bytes.try_into().map_err(|_| {
+        format_err!(
+            Kind::InvalidProof,
+            "expected {}-byte hash, got {} bytes",
+            HASH_SIZE,
+            bytes.len()
+        )
+        .into()
+    })
+}
+
 #[cfg(test)]
 mod test {
     use super::Proof;
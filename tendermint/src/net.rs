@@ -51,7 +51,16 @@ impl<'de> Deserialize<'de> for Address {
 impl Display for Address {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Address::Tcp { host, port, .. } => write!(f, "{}{}:{}", TCP_PREFIX, host, port),
+            Address::Tcp {
+                peer_id: None,
+                host,
+                port,
+            } => write!(f, "{}{}:{}", TCP_PREFIX, host, port),
+            Address::Tcp {
+                peer_id: Some(peer_id),
+                host,
+                port,
+            } => write!(f, "{}{}@{}:{}", TCP_PREFIX, peer_id, host, port),
             Address::Unix { path } => write!(f, "{}{}", UNIX_PREFIX, path.display()),
         }
     }
@@ -166,4 +175,12 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn display_tcp_addr_roundtrip() {
+        for tcp_addr in &[EXAMPLE_TCP_ADDR, "tcp://35.192.61.41:26656"] {
+            let addr = tcp_addr.parse::<Address>().unwrap();
+            assert_eq!(addr.to_string().parse::<Address>().unwrap(), addr);
+        }
+    }
 }
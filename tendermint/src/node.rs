@@ -1,7 +1,9 @@
 //! Nodes in Tendermint blockchain networks
 
+mod address;
 mod id;
 pub mod info;
 
+pub use self::address::Address;
 pub use self::id::Id;
 pub use self::info::Info;
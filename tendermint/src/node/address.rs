@@ -0,0 +1,215 @@
+//! Peer addresses (`id@host:port`)
+
+use crate::{
+    error::{Error, Kind},
+    net, node,
+};
+use anomaly::{fail, format_err};
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+use std::{
+    convert::TryFrom,
+    fmt::{self, Display},
+    str::FromStr,
+};
+
+/// Address of a peer in the P2P network, consisting of its node ID and the
+/// host and port it can be dialed at (e.g. `id@host:port`).
+///
+/// Parses addresses with or without a `tcp://` prefix, and displays them in
+/// the `id@host:port` form used by `persistent_peers`, `seeds`, and the
+/// address book.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Address {
+    /// Node ID of the peer
+    pub id: node::Id,
+
+    /// Hostname or IP address (without brackets for IPv6)
+    pub host: String,
+
+    /// Port
+    pub port: u16,
+}
+
+impl Address {
+    /// Create a new peer address
+    pub fn new(id: node::Id, host: impl Into<String>, port: u16) -> Self {
+        Address {
+            id,
+            host: host.into(),
+            port,
+        }
+    }
+}
+
+impl Display for Address {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Node IDs are written in lower case hex in peer addresses
+        for byte in self.id.as_bytes() {
+            write!(f, "{:02x}", byte)?;
+        }
+
+        if self.host.contains(':') {
+            write!(f, "@[{}]:{}", self.host, self.port)
+        } else {
+            write!(f, "@{}:{}", self.host, self.port)
+        }
+    }
+}
+
+impl FromStr for Address {
+    type Err = Error;
+
+    fn from_str(addr: &str) -> Result<Self, Error> {
+        let without_prefix = if addr.starts_with(net::TCP_PREFIX) {
+            &addr[net::TCP_PREFIX.len()..]
+        } else if addr.contains("://") {
+            fail!(Kind::Parse, "invalid peer address prefix: {:?}", addr)
+        } else {
+            addr
+        };
+
+        let at = without_prefix
+            .find('@')
+            .ok_or_else(|| format_err!(Kind::Parse, "peer address missing node ID: {}", addr))?;
+
+        let id = without_prefix[..at]
+            .parse()
+            .map_err(|_| format_err!(Kind::Parse, "invalid node ID in peer address: {}", addr))?;
+
+        let (host, port) = split_host_port(&without_prefix[at + 1..])
+            .ok_or_else(|| format_err!(Kind::Parse, "invalid peer address: {}", addr))?;
+
+        Ok(Address::new(id, host, port))
+    }
+}
+
+/// Split `host:port` (or `[host]:port` for IPv6 addresses) into its parts
+fn split_host_port(authority: &str) -> Option<(&str, u16)> {
+    let colon = authority.rfind(':')?;
+    let (host, port) = (&authority[..colon], &authority[colon + 1..]);
+
+    let host = if host.starts_with('[') && host.ends_with(']') {
+        &host[1..host.len() - 1]
+    } else if host.contains(|c: char| c == ':' || c == '[' || c == ']') {
+        return None;
+    } else {
+        host
+    };
+
+    if host.is_empty() || host.contains('@') {
+        return None;
+    }
+
+    Some((host, port.parse().ok()?))
+}
+
+impl From<Address> for net::Address {
+    fn from(addr: Address) -> net::Address {
+        net::Address::Tcp {
+            peer_id: Some(addr.id),
+            host: addr.host,
+            port: addr.port,
+        }
+    }
+}
+
+impl TryFrom<net::Address> for Address {
+    type Error = Error;
+
+    fn try_from(addr: net::Address) -> Result<Self, Error> {
+        match addr {
+            net::Address::Tcp {
+                peer_id: Some(id),
+                host,
+                port,
+            } => Ok(Address::new(id, host, port)),
+            other => fail!(Kind::Parse, "not a peer address: {}", other),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Address {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Self::from_str(&String::deserialize(deserializer)?)
+            .map_err(|e| D::Error::custom(format!("{}", e)))
+    }
+}
+
+impl Serialize for Address {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_string().serialize(serializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE_NODE_ID: &str = "abd636b766dcefb5322d8ca40011ec2cb35efbc2";
+
+    #[test]
+    fn parses_peer_addresses() {
+        let id = EXAMPLE_NODE_ID.parse::<node::Id>().unwrap();
+
+        for (addr, host) in &[
+            (
+                "abd636b766dcefb5322d8ca40011ec2cb35efbc2@35.192.61.41:26656",
+                "35.192.61.41",
+            ),
+            (
+                "tcp://abd636b766dcefb5322d8ca40011ec2cb35efbc2@35.192.61.41:26656",
+                "35.192.61.41",
+            ),
+            (
+                "ABD636B766DCEFB5322D8CA40011EC2CB35EFBC2@seed.example.com:26656",
+                "seed.example.com",
+            ),
+            (
+                "abd636b766dcefb5322d8ca40011ec2cb35efbc2@[::1]:26656",
+                "::1",
+            ),
+        ] {
+            let parsed = addr.parse::<Address>().unwrap();
+            assert_eq!(parsed, Address::new(id, *host, 26656));
+        }
+    }
+
+    #[test]
+    fn displays_peer_addresses() {
+        for addr in &[
+            "abd636b766dcefb5322d8ca40011ec2cb35efbc2@35.192.61.41:26656",
+            "abd636b766dcefb5322d8ca40011ec2cb35efbc2@[2001:db8::1]:26656",
+        ] {
+            assert_eq!(&addr.parse::<Address>().unwrap().to_string(), addr);
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_peer_addresses() {
+        for addr in &[
+            "35.192.61.41:26656",
+            "abd636b766dcefb5322d8ca40011ec2cb35efbc2@35.192.61.41",
+            "abd636b766dcefb5322d8ca40011ec2cb35efbc2@:26656",
+            "abd636b766dcefb5322d8ca40011ec2cb35efbc2@35.192.61.41:99999",
+            "abd636b766dcefb5322d8ca40011ec2cb35efb@35.192.61.41:26656",
+            "not-a-node-id@35.192.61.41:26656",
+            "abd636b766dcefb5322d8ca40011ec2cb35efbc2@::1:26656",
+            "unix://abd636b766dcefb5322d8ca40011ec2cb35efbc2@35.192.61.41:26656",
+        ] {
+            assert!(addr.parse::<Address>().is_err(), "{}", addr);
+        }
+    }
+
+    #[test]
+    fn converts_to_and_from_net_addresses() {
+        let addr = format!("{}@35.192.61.41:26656", EXAMPLE_NODE_ID)
+            .parse::<Address>()
+            .unwrap();
+
+        let net_addr = net::Address::from(addr.clone());
+        assert_eq!(Address::try_from(net_addr).unwrap(), addr);
+
+        let without_id = "tcp://35.192.61.41:26656".parse::<net::Address>().unwrap();
+        assert!(Address::try_from(without_id).is_err());
+    }
+}
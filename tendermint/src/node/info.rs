@@ -1,8 +1,28 @@
-//! Node information (used in RPC responses)
+//! Node information (used in RPC responses and the P2P handshake)
 
-use crate::{chain, channel::Channels, net, node, serializers, Moniker, Version};
+#[cfg(feature = "amino")]
+use crate::amino_types::{message::AminoMessage, p2p as amino};
+use crate::{
+    app, block, chain,
+    channel::Channels,
+    error::{Error, Kind},
+    net, node, serializers, Moniker, Version,
+};
+use anomaly::{fail, format_err};
 use serde::{Deserialize, Serialize};
-use std::fmt::{self, Display};
+use std::{
+    collections::HashSet,
+    convert::TryFrom,
+    fmt::{self, Display},
+};
+use tendermint_proto::p2p::{
+    DefaultNodeInfo as RawDefaultNodeInfo, DefaultNodeInfoOther as RawDefaultNodeInfoOther,
+    ProtocolVersion as RawProtocolVersion,
+};
+use tendermint_proto::DomainType;
+
+/// Maximum number of channels a node may advertise
+pub const MAX_NUM_CHANNELS: usize = 16;
 
 /// Node information
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
@@ -40,12 +60,38 @@ pub struct ProtocolVersionInfo {
     pub p2p: u64,
 
     /// Block version
-    #[serde(with = "serializers::from_str")]
-    pub block: u64,
+    pub block: block::Version,
 
     /// App version
-    #[serde(with = "serializers::from_str")]
-    pub app: u64,
+    pub app: app::Version,
+}
+
+impl ProtocolVersionInfo {
+    /// Can a node with this protocol version connect to a node with the
+    /// other one? Tendermint requires peers to use the same block version.
+    pub fn is_compatible_with(&self, other: &ProtocolVersionInfo) -> bool {
+        self.block.is_compatible_with(other.block)
+    }
+}
+
+impl From<RawProtocolVersion> for ProtocolVersionInfo {
+    fn from(value: RawProtocolVersion) -> Self {
+        ProtocolVersionInfo {
+            p2p: value.p2p,
+            block: block::Version(value.block),
+            app: app::Version(value.app),
+        }
+    }
+}
+
+impl From<ProtocolVersionInfo> for RawProtocolVersion {
+    fn from(value: ProtocolVersionInfo) -> Self {
+        RawProtocolVersion {
+            p2p: value.p2p,
+            block: value.block.0,
+            app: value.app.0,
+        }
+    }
 }
 
 /// Listen address information
@@ -69,6 +115,185 @@ impl ListenAddress {
     }
 }
 
+impl Info {
+    /// Get the peer address of this node, i.e. its ID along with the host
+    /// and port from its listen address
+    pub fn address(&self) -> Option<node::Address> {
+        match self.listen_addr.to_net_address()? {
+            net::Address::Tcp { host, port, .. } => Some(node::Address::new(self.id, host, port)),
+            net::Address::Unix { .. } => None,
+        }
+    }
+
+    /// Check that this node info is well-formed, following the rules
+    /// Tendermint applies to `DefaultNodeInfo` during the P2P handshake:
+    ///
+    /// - the listen address must be a dialable `host:port`
+    /// - the version, if present, must be printable ASCII
+    /// - at most [`MAX_NUM_CHANNELS`] channels, with no duplicates
+    /// - the moniker must be non-empty printable ASCII
+    pub fn validate(&self) -> Result<(), Error> {
+        if self.address().is_none() {
+            fail!(
+                Kind::Protocol,
+                "invalid listen address: {:?}",
+                self.listen_addr.to_string()
+            );
+        }
+
+        let version = self.version.as_str();
+        if !version.is_empty() && !is_ascii_text(version) {
+            fail!(
+                Kind::Protocol,
+                "version must be valid ASCII text: {:?}",
+                version
+            );
+        }
+
+        if self.channels.len() > MAX_NUM_CHANNELS {
+            fail!(
+                Kind::Protocol,
+                "too many channels: {} (max {})",
+                self.channels.len(),
+                MAX_NUM_CHANNELS
+            );
+        }
+
+        let mut seen = HashSet::new();
+        for &id in self.channels.as_bytes() {
+            if !seen.insert(id) {
+                fail!(Kind::Protocol, "duplicate channel id: {:#04x}", id);
+            }
+        }
+
+        if !is_ascii_text(self.moniker.as_ref()) {
+            fail!(
+                Kind::Protocol,
+                "moniker must be non-empty ASCII text: {:?}",
+                self.moniker.as_ref()
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Can a node with this node info connect to the other node? Both must
+    /// use compatible block versions, be on the same network, and share at
+    /// least one channel.
+    pub fn is_compatible_with(&self, other: &Info) -> bool {
+        self.protocol_version
+            .is_compatible_with(&other.protocol_version)
+            && self.network == other.network
+            && (self.channels.is_empty()
+                || self.channels.ids().any(|id| other.channels.contains(id)))
+    }
+}
+
+/// Is the given string non-empty, printable ASCII which isn't just spaces?
+fn is_ascii_text(s: &str) -> bool {
+    s.bytes().all(|b| (0x20..=0x7e).contains(&b)) && !s.trim_matches(' ').is_empty()
+}
+
+impl DomainType<RawDefaultNodeInfo> for Info {}
+
+impl TryFrom<RawDefaultNodeInfo> for Info {
+    type Error = Error;
+
+    fn try_from(value: RawDefaultNodeInfo) -> Result<Self, Self::Error> {
+        let other = value
+            .other
+            .ok_or_else(|| format_err!(Kind::Parse, "other is missing in node info"))?;
+
+        Ok(Info {
+            protocol_version: value
+                .protocol_version
+                .ok_or_else(|| {
+                    format_err!(Kind::Parse, "protocol version is missing in node info")
+                })?
+                .into(),
+            id: value.default_node_id.parse()?,
+            listen_addr: ListenAddress::new(value.listen_addr),
+            network: value.network.parse()?,
+            version: value.version.parse()?,
+            channels: value.channels.into(),
+            moniker: value.moniker.parse()?,
+            other: OtherInfo::try_from(other)?,
+        })
+    }
+}
+
+impl From<Info> for RawDefaultNodeInfo {
+    fn from(value: Info) -> Self {
+        RawDefaultNodeInfo {
+            protocol_version: Some(value.protocol_version.into()),
+            // Node IDs are written in lower case hex on the wire
+            default_node_id: value.id.to_string().to_lowercase(),
+            listen_addr: value.listen_addr.to_string(),
+            network: value.network.as_str().to_owned(),
+            version: value.version.to_string(),
+            channels: value.channels.into(),
+            moniker: value.moniker.to_string(),
+            other: Some(value.other.into()),
+        }
+    }
+}
+
+#[cfg(feature = "amino")]
+#[cfg_attr(docsrs, doc(cfg(feature = "amino")))]
+impl Info {
+    /// Decode an amino encoded node info (without its length prefix), as
+    /// exchanged by Tendermint 0.33 during the P2P handshake
+    pub fn decode_amino(bytes: &[u8]) -> Result<Self, Error> {
+        use prost_amino::Message as _;
+
+        let info = amino::DefaultNodeInfo::decode(bytes).map_err(|e| Kind::Parse.context(e))?;
+
+        // Amino and protobuf node info have the same fields
+        Info::try_from(RawDefaultNodeInfo {
+            protocol_version: info.protocol_version.map(|version| RawProtocolVersion {
+                p2p: version.p2p,
+                block: version.block,
+                app: version.app,
+            }),
+            default_node_id: info.id,
+            listen_addr: info.listen_addr,
+            network: info.network,
+            version: info.version,
+            channels: info.channels,
+            moniker: info.moniker,
+            other: info.other.map(|other| RawDefaultNodeInfoOther {
+                tx_index: other.tx_index,
+                rpc_address: other.rpc_address,
+            }),
+        })
+    }
+
+    /// Amino encode this node info (without a length prefix), as exchanged
+    /// by Tendermint 0.33 during the P2P handshake
+    pub fn encode_amino(&self) -> Vec<u8> {
+        let info = RawDefaultNodeInfo::from(self.clone());
+
+        amino::DefaultNodeInfo {
+            protocol_version: info.protocol_version.map(|version| amino::ProtocolVersion {
+                p2p: version.p2p,
+                block: version.block,
+                app: version.app,
+            }),
+            id: info.default_node_id,
+            listen_addr: info.listen_addr,
+            network: info.network,
+            version: info.version,
+            channels: info.channels,
+            moniker: info.moniker,
+            other: info.other.map(|other| amino::DefaultNodeInfoOther {
+                tx_index: other.tx_index,
+                rpc_address: other.rpc_address,
+            }),
+        }
+        .bytes_vec()
+    }
+}
+
 impl Display for ListenAddress {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}", self.0)
@@ -85,6 +310,37 @@ pub struct OtherInfo {
     pub rpc_address: net::Address,
 }
 
+impl TryFrom<RawDefaultNodeInfoOther> for OtherInfo {
+    type Error = Error;
+
+    fn try_from(value: RawDefaultNodeInfoOther) -> Result<Self, Self::Error> {
+        let tx_index = match value.tx_index.as_str() {
+            "on" => TxIndexStatus::On,
+            // An empty status means the node didn't report one
+            "off" | "" => TxIndexStatus::Off,
+            other => fail!(Kind::Parse, "invalid tx_index status: {:?}", other),
+        };
+
+        Ok(OtherInfo {
+            tx_index,
+            rpc_address: value.rpc_address.parse()?,
+        })
+    }
+}
+
+impl From<OtherInfo> for RawDefaultNodeInfoOther {
+    fn from(value: OtherInfo) -> Self {
+        RawDefaultNodeInfoOther {
+            tx_index: match value.tx_index {
+                TxIndexStatus::On => "on",
+                TxIndexStatus::Off => "off",
+            }
+            .to_owned(),
+            rpc_address: value.rpc_address.to_string(),
+        }
+    }
+}
+
 /// Transaction index status
 #[derive(Copy, Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub enum TxIndexStatus {
@@ -111,3 +367,77 @@ impl From<TxIndexStatus> for bool {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn example_info() -> Info {
+        serde_json::from_str(
+            r#"{
+                "protocol_version": { "p2p": "7", "block": "10", "app": "1" },
+                "id": "6b90d376f9bfdd83c6d9351bf7b2f458b74deacb",
+                "listen_addr": "tcp://0.0.0.0:26656",
+                "network": "cosmoshub-2",
+                "version": "0.32.1",
+                "channels": "4020212223303800",
+                "moniker": "technodrome",
+                "other": { "tx_index": "on", "rpc_address": "tcp://0.0.0.0:26657" }
+            }"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn validates_node_info() {
+        let info = example_info();
+        info.validate().unwrap();
+        assert!(info.is_compatible_with(&info));
+
+        let mut duplicate_channels = info.clone();
+        duplicate_channels.channels = Channels::new(vec![0x40, 0x20, 0x40]);
+        assert!(duplicate_channels.validate().is_err());
+
+        let mut too_many_channels = info.clone();
+        too_many_channels.channels =
+            Channels::new((0..=MAX_NUM_CHANNELS as u8).collect::<Vec<_>>());
+        assert!(too_many_channels.validate().is_err());
+
+        let mut empty_moniker = info.clone();
+        empty_moniker.moniker = "  ".parse().unwrap();
+        assert!(empty_moniker.validate().is_err());
+
+        let mut bad_listen_addr = info;
+        bad_listen_addr.listen_addr = ListenAddress::new("0.0.0.0".to_owned());
+        assert!(bad_listen_addr.validate().is_err());
+    }
+
+    #[test]
+    fn proto_round_trip() {
+        let info = example_info();
+        let raw = RawDefaultNodeInfo::from(info.clone());
+        assert_eq!(
+            raw.default_node_id,
+            "6b90d376f9bfdd83c6d9351bf7b2f458b74deacb"
+        );
+        assert_eq!(
+            raw.channels,
+            vec![0x40, 0x20, 0x21, 0x22, 0x23, 0x30, 0x38, 0x00]
+        );
+        assert_eq!(raw.other.as_ref().unwrap().tx_index, "on");
+
+        let encoded = info.encode_vec().unwrap();
+        assert_eq!(Info::decode_vec(&encoded).unwrap(), info);
+    }
+
+    #[test]
+    #[cfg(feature = "amino")]
+    fn amino_round_trip() {
+        use crate::amino_types::Registered;
+
+        let info = example_info();
+        let encoded = info.encode_amino();
+        assert!(encoded.starts_with(&amino::DefaultNodeInfo::amino_prefix()));
+        assert_eq!(Info::decode_amino(&encoded).unwrap(), info);
+    }
+}
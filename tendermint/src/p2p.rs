@@ -0,0 +1,54 @@
+//! Peer-to-peer networking primitives, which both Tendermint's peers and its
+//! remote signers build upon
+
+pub mod addrbook;
+pub mod dialing;
+pub mod handshake;
+pub mod mconnection;
+pub mod pex;
+mod protocol;
+#[cfg(feature = "secret-connection")]
+#[cfg_attr(docsrs, doc(cfg(feature = "secret-connection")))]
+pub mod secret_connection;
+pub mod transport;
+
+pub use self::protocol::Protocol;
+
+use crate::error::{Error, Kind};
+use anomaly::fail;
+use std::io::Read;
+
+/// Read a message prefixed with its length as an unsigned varint, refusing
+/// messages longer than `max_len`
+pub(crate) fn read_length_delimited<R: Read>(
+    reader: &mut R,
+    max_len: usize,
+) -> Result<Vec<u8>, Error> {
+    let mut len = 0usize;
+    let mut shift = 0;
+    let mut byte = [0u8];
+
+    loop {
+        reader
+            .read_exact(&mut byte)
+            .map_err(|e| Kind::Io.context(e))?;
+
+        // Lengths are checked byte by byte, so the shift can't overflow
+        len |= usize::from(byte[0] & 0x7f) << shift;
+        if len > max_len || (shift > 7 && byte[0] & 0x80 != 0) {
+            fail!(Kind::Length, "message exceeds maximum of {} bytes", max_len);
+        }
+
+        if byte[0] & 0x80 == 0 {
+            break;
+        }
+        shift += 7;
+    }
+
+    let mut message = vec![0u8; len];
+    reader
+        .read_exact(&mut message)
+        .map_err(|e| Kind::Io.context(e))?;
+
+    Ok(message)
+}
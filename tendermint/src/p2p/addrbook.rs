@@ -0,0 +1,811 @@
+//! Address book: the peers a node knows of, as learned from its
+//! configuration and from peer exchange, persisted in the format of
+//! Tendermint's `addrbook.json`.
+//!
+//! Like Tendermint's, the book keeps addresses in buckets picked from a
+//! hash of the book's secret key and the network groups of the address and
+//! of the peer which sent it, so that no single peer or network can fill
+//! the book. Addresses start in one of the "new" buckets, and move to an
+//! "old" bucket once we've connected to them (see [`AddrBook::mark_good`]).
+//! Misbehaving peers are banned for a while (see [`AddrBook::mark_bad`]).
+
+use super::pex::NetAddress;
+use crate::{
+    error::{Error, Kind},
+    node, Time,
+};
+use anomaly::{fail, format_err};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::{HashMap, HashSet},
+    convert::TryInto,
+    fs,
+    io::Write,
+    net::IpAddr,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+/// Number of buckets of new addresses
+pub const NEW_BUCKET_COUNT: usize = 256;
+
+/// Number of buckets of old addresses
+pub const OLD_BUCKET_COUNT: usize = 64;
+
+/// Maximum number of addresses in a bucket of new addresses
+pub const NEW_BUCKET_SIZE: usize = 64;
+
+/// Maximum number of addresses in a bucket of old addresses
+pub const OLD_BUCKET_SIZE: usize = 64;
+
+/// Number of new buckets the addresses of a network group, sent by peers of
+/// a network group, can land in
+const NEW_BUCKETS_PER_GROUP: u64 = 32;
+
+/// Number of old buckets the addresses of a network group can land in
+const OLD_BUCKETS_PER_GROUP: u64 = 4;
+
+/// Maximum number of new buckets an address is kept in
+const MAX_NEW_BUCKETS_PER_ADDRESS: usize = 4;
+
+/// Number of addresses below which the book wants more
+const NEED_ADDRESS_THRESHOLD: usize = 1000;
+
+/// Attempts after which an address we never connected to is bad
+const NUM_RETRIES: u32 = 3;
+
+/// Attempts after which an address we haven't connected to in
+/// `MIN_BAD_DAYS` is bad
+const MAX_FAILURES: u32 = 10;
+
+/// Days without attempts after which an address is bad
+const NUM_MISSING_DAYS: u64 = 30;
+
+/// Days without connecting after which an address failing too often is bad
+const MIN_BAD_DAYS: u64 = 7;
+
+/// Zero value of Go's `time.Time`, which stands for "never"
+const ZERO_TIME: &str = "0001-01-01T00:00:00Z";
+
+/// Kind of bucket an address is in
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub enum BucketType {
+    /// Addresses we haven't connected to
+    New,
+
+    /// Addresses we've connected to
+    Old,
+}
+
+impl BucketType {
+    fn to_u8(self) -> u8 {
+        match self {
+            BucketType::New => 1,
+            BucketType::Old => 2,
+        }
+    }
+
+    fn from_u8(value: u8) -> Result<Self, Error> {
+        match value {
+            1 => Ok(BucketType::New),
+            2 => Ok(BucketType::Old),
+            _ => fail!(Kind::Parse, "invalid bucket type: {}", value),
+        }
+    }
+}
+
+/// Address in the book, along with what we know of it
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct KnownAddress {
+    /// Address of the peer
+    pub addr: NetAddress,
+
+    /// Address of the peer which told us about it
+    pub src: NetAddress,
+
+    /// Buckets the address is in
+    pub buckets: Vec<usize>,
+
+    /// Number of connection attempts since the last successful one
+    pub attempts: u32,
+
+    /// Kind of buckets the address is in
+    pub bucket_type: BucketType,
+
+    /// Time of the last connection attempt
+    pub last_attempt: Option<Time>,
+
+    /// Time of the last successful connection
+    pub last_success: Option<Time>,
+
+    /// End of the peer's last ban
+    pub last_ban_time: Option<Time>,
+}
+
+impl KnownAddress {
+    fn new(addr: NetAddress, src: NetAddress) -> Self {
+        KnownAddress {
+            addr,
+            src,
+            buckets: vec![],
+            attempts: 0,
+            bucket_type: BucketType::New,
+            last_attempt: None,
+            last_success: None,
+            last_ban_time: None,
+        }
+    }
+
+    /// Have we connected to this address?
+    pub fn is_old(&self) -> bool {
+        self.bucket_type == BucketType::Old
+    }
+
+    /// Is the peer banned at the given time?
+    pub fn is_banned(&self, now: Time) -> bool {
+        self.last_ban_time.map_or(false, |end| end.after(now))
+    }
+
+    /// Is this address bad at the given time, i.e. one to evict first when
+    /// its bucket is full? Following Tendermint, new addresses are bad
+    /// unless attempted in the last minute, if not attempted in a month, or
+    /// failing too often.
+    pub fn is_bad(&self, now: Time) -> bool {
+        if self.is_old() {
+            return false;
+        }
+
+        let ago = |secs: u64| now - Duration::from_secs(secs);
+
+        if self.last_attempt.map_or(false, |t| t.after(ago(60))) {
+            return false;
+        }
+
+        if self
+            .last_attempt
+            .map_or(true, |t| t.before(ago(NUM_MISSING_DAYS * 86400)))
+        {
+            return true;
+        }
+
+        if self.last_success.is_none() && self.attempts >= NUM_RETRIES {
+            return true;
+        }
+
+        self.last_success
+            .map_or(true, |t| t.before(ago(MIN_BAD_DAYS * 86400)))
+            && self.attempts >= MAX_FAILURES
+    }
+}
+
+/// Book of peer addresses
+#[derive(Clone, Debug)]
+pub struct AddrBook {
+    key: String,
+    strict: bool,
+    our_ids: HashSet<node::Id>,
+    addrs: HashMap<node::Id, KnownAddress>,
+    new_buckets: Vec<Vec<node::Id>>,
+    old_buckets: Vec<Vec<node::Id>>,
+    bad_peers: HashMap<node::Id, KnownAddress>,
+}
+
+impl AddrBook {
+    /// Create an empty book with the given secret key, which spreads
+    /// addresses over buckets differently from other nodes' books.
+    ///
+    /// The book is strict: it refuses non-routable addresses, as
+    /// Tendermint's `addr_book_strict` option does by default.
+    pub fn new(key: impl Into<String>) -> Self {
+        AddrBook {
+            key: key.into(),
+            strict: true,
+            our_ids: HashSet::new(),
+            addrs: HashMap::new(),
+            new_buckets: vec![vec![]; NEW_BUCKET_COUNT],
+            old_buckets: vec![vec![]; OLD_BUCKET_COUNT],
+            bad_peers: HashMap::new(),
+        }
+    }
+
+    /// Refuse non-routable addresses or not, e.g. to accept private
+    /// addresses on a private network
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Get the secret key of the book
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Register one of our own IDs, whose addresses won't be added
+    pub fn add_our_id(&mut self, id: node::Id) {
+        self.our_ids.insert(id);
+    }
+
+    /// Number of addresses in the book, not counting banned ones
+    pub fn size(&self) -> usize {
+        self.addrs.len()
+    }
+
+    /// Is the book empty?
+    pub fn is_empty(&self) -> bool {
+        self.addrs.is_empty()
+    }
+
+    /// Does the book want more addresses, e.g. from peer exchange?
+    pub fn need_more_addrs(&self) -> bool {
+        self.size() < NEED_ADDRESS_THRESHOLD
+    }
+
+    /// Get what we know of the peer with the given ID
+    pub fn get(&self, id: &node::Id) -> Option<&KnownAddress> {
+        self.addrs.get(id)
+    }
+
+    /// Iterate over the addresses in the book
+    pub fn addresses(&self) -> impl Iterator<Item = &KnownAddress> {
+        self.addrs.values()
+    }
+
+    /// Is the peer with the given ID one we've connected to?
+    pub fn is_good(&self, id: &node::Id) -> bool {
+        self.addrs.get(id).map_or(false, KnownAddress::is_old)
+    }
+
+    /// Is the peer with the given ID banned at the given time?
+    pub fn is_banned(&self, id: &node::Id, now: Time) -> bool {
+        self.bad_peers
+            .get(id)
+            .map_or(false, |known| known.is_banned(now))
+    }
+
+    /// Add an address, sent by the peer with the `src` address (or by
+    /// ourselves, e.g. from our configuration), evicting a bad or the
+    /// oldest address if its bucket is full
+    pub fn add_address(
+        &mut self,
+        addr: NetAddress,
+        src: NetAddress,
+        now: Time,
+    ) -> Result<(), Error> {
+        if self.our_ids.contains(&addr.id) {
+            fail!(Kind::Protocol, "cannot add our own address {}", addr);
+        }
+
+        if self.is_banned(&addr.id, now) {
+            fail!(Kind::Protocol, "{} is banned", addr);
+        }
+
+        if self.strict && !is_routable(addr.ip) {
+            fail!(Kind::Protocol, "{} isn't routable", addr);
+        }
+
+        let known = match self.addrs.get(&addr.id) {
+            Some(known) if known.is_old() => return Ok(()),
+            Some(known) if known.buckets.len() >= MAX_NEW_BUCKETS_PER_ADDRESS => return Ok(()),
+            Some(known) => known.clone(),
+            None => KnownAddress::new(addr, src),
+        };
+
+        let bucket = self.new_bucket(&addr, &src);
+        self.add_to_new_bucket(known, bucket, now);
+        Ok(())
+    }
+
+    /// Remove the address of the given peer
+    pub fn remove_address(&mut self, id: &node::Id) -> Option<KnownAddress> {
+        let known = self.addrs.remove(id)?;
+        self.remove_from_buckets(&known);
+        Some(known)
+    }
+
+    /// Record an attempt to connect to the given peer
+    pub fn mark_attempt(&mut self, id: &node::Id, now: Time) {
+        if let Some(known) = self.addrs.get_mut(id) {
+            known.last_attempt = Some(now);
+            known.attempts += 1;
+        }
+    }
+
+    /// Record a successful connection to the given peer, moving its address
+    /// to an old bucket
+    pub fn mark_good(&mut self, id: &node::Id, now: Time) {
+        let mut known = match self.addrs.get_mut(id) {
+            Some(known) => {
+                known.last_attempt = Some(now);
+                known.last_success = Some(now);
+                known.attempts = 0;
+
+                if known.is_old() {
+                    return;
+                }
+                known.clone()
+            }
+            None => return,
+        };
+
+        self.remove_from_buckets(&known);
+
+        // Make room in the old bucket by moving its oldest address back to
+        // the new buckets
+        let bucket = self.old_bucket(&known.addr);
+        if self.old_buckets[bucket].len() >= OLD_BUCKET_SIZE {
+            if let Some(oldest) = self.oldest_in(&self.old_buckets[bucket]) {
+                let mut demoted = self.addrs[&oldest].clone();
+                self.remove_from_buckets(&demoted);
+                demoted.bucket_type = BucketType::New;
+                demoted.buckets.clear();
+
+                let new_bucket = self.new_bucket(&demoted.addr, &demoted.src);
+                self.add_to_new_bucket(demoted, new_bucket, now);
+            }
+        }
+
+        known.bucket_type = BucketType::Old;
+        known.buckets = vec![bucket];
+        self.old_buckets[bucket].push(known.addr.id);
+        self.addrs.insert(known.addr.id, known);
+    }
+
+    /// Ban the given peer for the given duration, removing its address from
+    /// the book until [`AddrBook::reinstate_bad_peers`] adds it back
+    pub fn mark_bad(&mut self, id: &node::Id, ban: Duration, now: Time) {
+        if let Some(mut known) = self.remove_address(id) {
+            known.last_ban_time = Some(now + ban);
+            known.buckets.clear();
+            known.bucket_type = BucketType::New;
+            self.bad_peers.insert(*id, known);
+        }
+    }
+
+    /// Add back the addresses of the peers whose ban is over
+    pub fn reinstate_bad_peers(&mut self, now: Time) {
+        let reinstated = self
+            .bad_peers
+            .iter()
+            .filter(|(_, known)| !known.is_banned(now))
+            .map(|(id, _)| *id)
+            .collect::<Vec<_>>();
+
+        for id in reinstated {
+            let known = self.bad_peers.remove(&id).expect("bad peer exists");
+            let bucket = self.new_bucket(&known.addr, &known.src);
+            self.add_to_new_bucket(known, bucket, now);
+        }
+    }
+
+    /// Parse the contents of an `addrbook.json` file. Banned peers aren't
+    /// saved, as in Tendermint.
+    pub fn from_json(json: &str) -> Result<Self, Error> {
+        let file: AddrBookFile = serde_json::from_str(json)
+            .map_err(|e| format_err!(Kind::Parse, "invalid address book: {}", e))?;
+
+        let mut book = AddrBook::new(file.key);
+        for entry in file.addrs {
+            let known = KnownAddress::try_from_file(entry)?;
+
+            for &bucket in &known.buckets {
+                let buckets = match known.bucket_type {
+                    BucketType::New => &mut book.new_buckets,
+                    BucketType::Old => &mut book.old_buckets,
+                };
+
+                buckets
+                    .get_mut(bucket)
+                    .ok_or_else(|| format_err!(Kind::Parse, "invalid bucket: {}", bucket))?
+                    .push(known.addr.id);
+            }
+
+            book.addrs.insert(known.addr.id, known);
+        }
+
+        Ok(book)
+    }
+
+    /// Serialize the book as the contents of an `addrbook.json` file
+    pub fn to_json(&self) -> Result<String, Error> {
+        let file = AddrBookFile {
+            key: self.key.clone(),
+            addrs: self.addrs.values().map(KnownAddressFile::from).collect(),
+        };
+
+        serde_json::to_string_pretty(&file)
+            .map_err(|e| format_err!(Kind::Parse, "couldn't serialize address book: {}", e).into())
+    }
+
+    /// Load an `addrbook.json` file (see [`AddrBook::from_json`])
+    pub fn load_json_file<P>(path: &P) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+    {
+        let json = fs::read_to_string(path).map_err(|e| {
+            format_err!(Kind::Io, "couldn't open {}: {}", path.as_ref().display(), e)
+        })?;
+
+        Self::from_json(&json)
+    }
+
+    /// Atomically save the book as an `addrbook.json` file: write a
+    /// temporary file next to it, sync it, then rename it over the file
+    pub fn save_json_file<P>(&self, path: &P) -> Result<(), Error>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let json = self.to_json()?;
+
+        let mut tmp_path = path.to_owned().into_os_string();
+        tmp_path.push(".tmp");
+        let tmp_path = PathBuf::from(tmp_path);
+
+        fs::File::create(&tmp_path)
+            .and_then(|mut file| {
+                file.write_all(json.as_bytes())?;
+                file.sync_all()
+            })
+            .and_then(|_| fs::rename(&tmp_path, path))
+            .map_err(|e| format_err!(Kind::Io, "couldn't write {}: {}", path.display(), e).into())
+    }
+
+    /// Add an address to a new bucket, evicting a bad or the oldest address
+    /// of the bucket if it's full
+    fn add_to_new_bucket(&mut self, mut known: KnownAddress, bucket: usize, now: Time) {
+        if known.buckets.contains(&bucket) {
+            return;
+        }
+
+        if self.new_buckets[bucket].len() >= NEW_BUCKET_SIZE {
+            let ids = &self.new_buckets[bucket];
+            let evicted = ids
+                .iter()
+                .find(|id| self.addrs[*id].is_bad(now))
+                .copied()
+                .or_else(|| self.oldest_in(ids));
+
+            if let Some(evicted) = evicted {
+                self.remove_from_bucket(&evicted, bucket);
+            }
+        }
+
+        known.buckets.push(bucket);
+        self.new_buckets[bucket].push(known.addr.id);
+        self.addrs.insert(known.addr.id, known);
+    }
+
+    /// Remove an address from a new bucket, and from the book if it isn't
+    /// in any other
+    fn remove_from_bucket(&mut self, id: &node::Id, bucket: usize) {
+        self.new_buckets[bucket].retain(|other| other != id);
+
+        if let Some(known) = self.addrs.get_mut(id) {
+            known.buckets.retain(|&other| other != bucket);
+            if known.buckets.is_empty() {
+                self.addrs.remove(id);
+            }
+        }
+    }
+
+    fn remove_from_buckets(&mut self, known: &KnownAddress) {
+        let buckets = match known.bucket_type {
+            BucketType::New => &mut self.new_buckets,
+            BucketType::Old => &mut self.old_buckets,
+        };
+
+        for &bucket in &known.buckets {
+            buckets[bucket].retain(|id| *id != known.addr.id);
+        }
+    }
+
+    /// Address of the given bucket we attempted to connect to the longest
+    /// ago
+    fn oldest_in(&self, ids: &[node::Id]) -> Option<node::Id> {
+        ids.iter()
+            .min_by_key(|id| self.addrs[*id].last_attempt)
+            .copied()
+    }
+
+    fn new_bucket(&self, addr: &NetAddress, src: &NetAddress) -> usize {
+        let group = hash64(&[
+            self.key.as_bytes(),
+            group_key(addr.ip).as_bytes(),
+            group_key(src.ip).as_bytes(),
+        ]) % NEW_BUCKETS_PER_GROUP;
+
+        let bucket = hash64(&[
+            self.key.as_bytes(),
+            group_key(src.ip).as_bytes(),
+            &group.to_be_bytes(),
+        ]);
+
+        (bucket % NEW_BUCKET_COUNT as u64) as usize
+    }
+
+    fn old_bucket(&self, addr: &NetAddress) -> usize {
+        let group =
+            hash64(&[self.key.as_bytes(), addr.to_string().as_bytes()]) % OLD_BUCKETS_PER_GROUP;
+
+        let bucket = hash64(&[
+            self.key.as_bytes(),
+            group_key(addr.ip).as_bytes(),
+            &group.to_be_bytes(),
+        ]);
+
+        (bucket % OLD_BUCKET_COUNT as u64) as usize
+    }
+}
+
+/// First 8 bytes of the double SHA-256 hash of the given data, as a
+/// big-endian integer
+fn hash64(data: &[&[u8]]) -> u64 {
+    let mut hasher = Sha256::new();
+    for part in data {
+        hasher.update(part);
+    }
+
+    let hash = Sha256::digest(&hasher.finalize());
+    u64::from_be_bytes(hash[..8].try_into().expect("hash is 32 bytes"))
+}
+
+/// Network group of an IP address: its /16 network for IPv4, its /32 network
+/// for IPv6
+fn group_key(ip: IpAddr) -> String {
+    if !is_routable(ip) {
+        return "unroutable".to_owned();
+    }
+
+    match ip {
+        IpAddr::V4(ip) => {
+            let octets = ip.octets();
+            format!("{}.{}.0.0/16", octets[0], octets[1])
+        }
+        IpAddr::V6(ip) => {
+            let segments = ip.segments();
+            match ip.to_ipv4() {
+                // IPv4-mapped addresses are grouped as IPv4 ones
+                Some(ipv4) if segments[5] == 0xffff => group_key(IpAddr::V4(ipv4)),
+                _ => format!("{:x}:{:x}::/32", segments[0], segments[1]),
+            }
+        }
+    }
+}
+
+/// Can the given IP address be reached over the internet?
+fn is_routable(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let octets = ip.octets();
+            !(ip.is_private()
+                || ip.is_loopback()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_documentation()
+                // Shared address space (RFC 6598) and benchmarking (RFC 2544)
+                || (octets[0] == 100 && octets[1] & 0xc0 == 64)
+                || (octets[0] == 198 && octets[1] & 0xfe == 18))
+        }
+        IpAddr::V6(ip) => {
+            let segments = ip.segments();
+            if let Some(ipv4) = ip.to_ipv4() {
+                if segments[5] == 0xffff {
+                    return is_routable(IpAddr::V4(ipv4));
+                }
+            }
+
+            !(ip.is_loopback()
+                || ip.is_unspecified()
+                // Unique local (RFC 4193), link local and documentation
+                // (RFC 3849) addresses
+                || segments[0] & 0xfe00 == 0xfc00
+                || segments[0] & 0xffc0 == 0xfe80
+                || (segments[0] == 0x2001 && segments[1] == 0x0db8))
+        }
+    }
+}
+
+/// `addrbook.json` contents
+#[derive(Deserialize, Serialize)]
+struct AddrBookFile {
+    key: String,
+    addrs: Vec<KnownAddressFile>,
+}
+
+#[derive(Deserialize, Serialize)]
+struct KnownAddressFile {
+    addr: NetAddressFile,
+    src: NetAddressFile,
+    buckets: Vec<usize>,
+    attempts: u32,
+    bucket_type: u8,
+    last_attempt: String,
+    last_success: String,
+    last_ban_time: String,
+}
+
+/// Go's `NetAddress` in JSON, with its ID in lower case hex
+#[derive(Deserialize, Serialize)]
+struct NetAddressFile {
+    id: String,
+    ip: IpAddr,
+    port: u16,
+}
+
+impl From<&KnownAddress> for KnownAddressFile {
+    fn from(known: &KnownAddress) -> Self {
+        KnownAddressFile {
+            addr: known.addr.into(),
+            src: known.src.into(),
+            buckets: known.buckets.clone(),
+            attempts: known.attempts,
+            bucket_type: known.bucket_type.to_u8(),
+            last_attempt: format_time(known.last_attempt),
+            last_success: format_time(known.last_success),
+            last_ban_time: format_time(known.last_ban_time),
+        }
+    }
+}
+
+impl KnownAddress {
+    fn try_from_file(file: KnownAddressFile) -> Result<Self, Error> {
+        Ok(KnownAddress {
+            addr: file.addr.try_into()?,
+            src: file.src.try_into()?,
+            buckets: file.buckets,
+            attempts: file.attempts,
+            bucket_type: BucketType::from_u8(file.bucket_type)?,
+            last_attempt: parse_time(&file.last_attempt)?,
+            last_success: parse_time(&file.last_success)?,
+            last_ban_time: parse_time(&file.last_ban_time)?,
+        })
+    }
+}
+
+impl From<NetAddress> for NetAddressFile {
+    fn from(addr: NetAddress) -> Self {
+        NetAddressFile {
+            id: addr.id.to_string().to_lowercase(),
+            ip: addr.ip,
+            port: addr.port,
+        }
+    }
+}
+
+impl std::convert::TryFrom<NetAddressFile> for NetAddress {
+    type Error = Error;
+
+    fn try_from(file: NetAddressFile) -> Result<Self, Error> {
+        Ok(NetAddress::new(file.id.parse()?, file.ip, file.port))
+    }
+}
+
+fn format_time(time: Option<Time>) -> String {
+    time.map_or_else(|| ZERO_TIME.to_owned(), |time| time.to_rfc3339())
+}
+
+fn parse_time(time: &str) -> Result<Option<Time>, Error> {
+    if time.starts_with("0001-01-01T00:00:00") {
+        Ok(None)
+    } else {
+        Time::parse_from_rfc3339(time).map(Some)
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::env;
+
+    fn addr(id: u8, ip: &str) -> NetAddress {
+        let id = format!("{:02x}", id).repeat(20);
+        NetAddress::new(id.parse().unwrap(), ip.parse().unwrap(), 26656)
+    }
+
+    fn now() -> Time {
+        Time::parse_from_rfc3339("2020-10-16T11:25:29Z").unwrap()
+    }
+
+    #[test]
+    fn adds_addresses() {
+        let mut book = AddrBook::new("0123456789abcdef01234567");
+        let src = addr(1, "93.184.216.34");
+        book.add_our_id(src.id);
+
+        book.add_address(addr(2, "1.1.1.1"), src, now()).unwrap();
+        book.add_address(addr(2, "1.1.1.1"), src, now()).unwrap();
+        assert_eq!(book.size(), 1);
+        assert_eq!(book.get(&addr(2, "1.1.1.1").id).unwrap().buckets.len(), 1);
+        assert!(book.need_more_addrs());
+
+        assert!(book.add_address(src, src, now()).is_err());
+        assert!(book
+            .add_address(addr(3, "192.168.1.1"), src, now())
+            .is_err());
+
+        let mut lax = AddrBook::new("0123456789abcdef01234567").strict(false);
+        lax.add_address(addr(3, "192.168.1.1"), src, now()).unwrap();
+        assert_eq!(lax.size(), 1);
+    }
+
+    #[test]
+    fn marks_good_and_bad_peers() {
+        let mut book = AddrBook::new("0123456789abcdef01234567");
+        let src = addr(1, "93.184.216.34");
+        let peer = addr(2, "1.1.1.1");
+        book.add_address(peer, src, now()).unwrap();
+
+        book.mark_attempt(&peer.id, now());
+        assert_eq!(book.get(&peer.id).unwrap().attempts, 1);
+
+        book.mark_good(&peer.id, now());
+        let known = book.get(&peer.id).unwrap();
+        assert!(known.is_old() && book.is_good(&peer.id));
+        assert_eq!(known.attempts, 0);
+        assert_eq!(known.last_success, Some(now()));
+        assert_eq!(known.buckets, vec![book.old_bucket(&peer)]);
+
+        let ban = Duration::from_secs(3600);
+        book.mark_bad(&peer.id, ban, now());
+        assert!(book.get(&peer.id).is_none());
+        assert!(book.is_banned(&peer.id, now()));
+        assert!(book.add_address(peer, src, now()).is_err());
+
+        book.reinstate_bad_peers(now());
+        assert!(book.get(&peer.id).is_none());
+        book.reinstate_bad_peers(now() + ban);
+        assert!(!book.get(&peer.id).unwrap().is_old());
+    }
+
+    #[test]
+    fn evicts_bad_addresses_from_full_buckets() {
+        let mut book = AddrBook::new("0123456789abcdef01234567");
+        let src = addr(1, "93.184.216.34");
+
+        // Addresses of the same /16 network sent by the same peer share a
+        // bucket
+        for i in 0..=NEW_BUCKET_SIZE as u8 {
+            book.add_address(addr(i + 2, &format!("1.1.1.{}", i + 1)), src, now())
+                .unwrap();
+        }
+
+        assert_eq!(book.size(), NEW_BUCKET_SIZE);
+    }
+
+    #[test]
+    fn persists_addrbook_json() {
+        let path = env::temp_dir().join(format!("tendermint-addrbook-{}.json", std::process::id()));
+
+        let mut book = AddrBook::new("0123456789abcdef01234567");
+        let src = addr(1, "93.184.216.34");
+        book.add_address(addr(2, "1.1.1.1"), src, now()).unwrap();
+        book.add_address(addr(3, "2606:4700::1111"), src, now())
+            .unwrap();
+        book.mark_good(&addr(2, "1.1.1.1").id, now());
+        book.save_json_file(&path).unwrap();
+
+        let json: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        let entry = json["addrs"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|entry| entry["bucket_type"] == 2)
+            .unwrap();
+        assert_eq!(entry["addr"]["id"], "02".repeat(20));
+        assert_eq!(entry["addr"]["ip"], "1.1.1.1");
+        assert_eq!(entry["last_ban_time"], ZERO_TIME);
+
+        let loaded = AddrBook::load_json_file(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.key(), book.key());
+        assert_eq!(loaded.size(), 2);
+        for known in book.addresses() {
+            assert_eq!(loaded.get(&known.addr.id), Some(known));
+        }
+        assert_eq!(loaded.old_buckets, book.old_buckets);
+    }
+}
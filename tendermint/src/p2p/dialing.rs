@@ -0,0 +1,617 @@
+//! Dialing peers: which peers to connect to and when, within the limits of
+//! a node's connections.
+//!
+//! A [`DialManager`] keeps track of the peers we know the address of (e.g.
+//! from the address book) and of our connections to them. Like Tendermint's
+//! switch, it:
+//!
+//! - refuses inbound connections beyond `max_inbound`, and dials no more than
+//!   `max_outbound` peers, not counting persistent peers
+//! - backs off exponentially from peers which failed to connect, and forgets
+//!   them after `max_attempts` failures
+//! - keeps reconnecting to persistent peers (e.g. `P2PConfig::persistent_peers`)
+//!   whenever they fail or disconnect
+//!
+//! The manager doesn't keep time itself: it's told the time of each call,
+//! and when it next has peers to dial ([`DialManager::next_wakeup`]). What
+//! happens to connections is reported as [`Event`]s.
+
+use super::transport::Transport;
+use crate::node;
+use std::{
+    cmp,
+    collections::{HashMap, VecDeque},
+    time::{Duration, Instant},
+};
+use thiserror::Error;
+
+/// Default maximum number of inbound connections, as in Tendermint
+pub const DEFAULT_MAX_INBOUND: usize = 40;
+
+/// Default maximum number of outbound connections, as in Tendermint
+pub const DEFAULT_MAX_OUTBOUND: usize = 10;
+
+/// Default number of failed dials after which a peer is forgotten, as in
+/// Tendermint's peer exchange
+pub const DEFAULT_MAX_ATTEMPTS: u32 = 16;
+
+/// Limits of a [`DialManager`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Config {
+    /// Maximum number of inbound connections
+    pub max_inbound: usize,
+
+    /// Maximum number of outbound connections, not counting persistent peers
+    pub max_outbound: usize,
+
+    /// Delay before dialing a peer again after its first failure, doubling
+    /// with each further failure
+    pub min_backoff: Duration,
+
+    /// Maximum delay before dialing a peer again
+    pub max_backoff: Duration,
+
+    /// Number of failed dials after which a peer which isn't persistent is
+    /// forgotten
+    pub max_attempts: u32,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            max_inbound: DEFAULT_MAX_INBOUND,
+            max_outbound: DEFAULT_MAX_OUTBOUND,
+            min_backoff: Duration::from_secs(5),
+            max_backoff: Duration::from_secs(600),
+            max_attempts: DEFAULT_MAX_ATTEMPTS,
+        }
+    }
+}
+
+impl From<&crate::config::P2PConfig> for Config {
+    fn from(config: &crate::config::P2PConfig) -> Self {
+        Config {
+            max_inbound: config.max_num_inbound_peers as usize,
+            max_outbound: config.max_num_outbound_peers as usize,
+            ..Config::default()
+        }
+    }
+}
+
+/// Direction of a connection
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Direction {
+    /// The peer dialed us
+    Inbound,
+
+    /// We dialed the peer
+    Outbound,
+}
+
+/// Reasons an inbound connection is refused
+#[derive(Clone, Debug, Eq, PartialEq, Error)]
+pub enum Rejection {
+    /// We're already connected to, or dialing, the peer
+    #[error("already connected to {0}")]
+    AlreadyConnected(node::Id),
+
+    /// We have as many inbound connections as we accept
+    #[error("too many inbound connections (maximum is {0})")]
+    TooManyInbound(usize),
+}
+
+/// What happened to the connections of a [`DialManager`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Event<A> {
+    /// We're dialing the peer at the given address
+    Dialing {
+        /// The peer's ID
+        peer: node::Id,
+        /// Address dialed
+        address: A,
+    },
+
+    /// We're connected to the peer
+    Connected {
+        /// The peer's ID
+        peer: node::Id,
+        /// Who dialed whom
+        direction: Direction,
+    },
+
+    /// Dialing the peer failed
+    DialFailed {
+        /// The peer's ID
+        peer: node::Id,
+        /// When the peer will be dialed again, unless it's forgotten
+        retry_at: Option<Instant>,
+    },
+
+    /// An inbound connection was refused
+    Rejected {
+        /// The peer's ID
+        peer: node::Id,
+        /// Why
+        reason: Rejection,
+    },
+
+    /// The peer disconnected
+    Disconnected {
+        /// The peer's ID
+        peer: node::Id,
+        /// When the peer will be dialed again, if it's persistent
+        reconnect_at: Option<Instant>,
+    },
+}
+
+/// Status of our connection to a peer
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum Status {
+    /// Waiting to be dialed
+    Idle,
+
+    /// Being dialed
+    Dialing,
+
+    /// Connected
+    Connected(Direction),
+}
+
+#[derive(Clone, Debug)]
+struct Peer<A> {
+    address: Option<A>,
+    persistent: bool,
+    status: Status,
+    failures: u32,
+    next_attempt: Instant,
+}
+
+/// Decides which peers to dial and which connections to accept
+#[derive(Clone, Debug)]
+pub struct DialManager<A> {
+    config: Config,
+    peers: HashMap<node::Id, Peer<A>>,
+    events: VecDeque<Event<A>>,
+}
+
+impl<A: Clone> DialManager<A> {
+    /// Create a manager with the given limits, knowing of no peer yet
+    pub fn new(config: Config) -> Self {
+        DialManager {
+            config,
+            peers: HashMap::new(),
+            events: VecDeque::new(),
+        }
+    }
+
+    /// Add a peer to dial, at the given time at the earliest. Persistent
+    /// peers are dialed regardless of the outbound limit, and redialed
+    /// whenever they fail or disconnect.
+    ///
+    /// Peers we already know of keep their status, but use the given address
+    /// from then on.
+    pub fn add_peer(&mut self, id: node::Id, address: A, persistent: bool, now: Instant) {
+        let peer = self.peers.entry(id).or_insert(Peer {
+            address: None,
+            persistent,
+            status: Status::Idle,
+            failures: 0,
+            next_attempt: now,
+        });
+
+        peer.address = Some(address);
+        peer.persistent |= persistent;
+    }
+
+    /// Forget a peer which isn't connected, e.g. one banned from the
+    /// address book
+    pub fn remove_peer(&mut self, id: &node::Id) {
+        if self
+            .peers
+            .get(id)
+            .map_or(false, |peer| peer.status == Status::Idle)
+        {
+            self.peers.remove(id);
+        }
+    }
+
+    /// Is the given peer persistent?
+    pub fn is_persistent(&self, id: &node::Id) -> bool {
+        self.peers.get(id).map_or(false, |peer| peer.persistent)
+    }
+
+    /// Is the given peer connected, or being dialed?
+    pub fn is_connected(&self, id: &node::Id) -> bool {
+        self.peers
+            .get(id)
+            .map_or(false, |peer| peer.status != Status::Idle)
+    }
+
+    /// Number of inbound connections
+    pub fn num_inbound(&self) -> usize {
+        self.count(|peer| peer.status == Status::Connected(Direction::Inbound))
+    }
+
+    /// Number of outbound connections, including the peers being dialed but
+    /// not persistent peers
+    pub fn num_outbound(&self) -> usize {
+        self.count(|peer| {
+            !peer.persistent
+                && matches!(
+                    peer.status,
+                    Status::Dialing | Status::Connected(Direction::Outbound)
+                )
+        })
+    }
+
+    /// Time at which peers are next due to be dialed, if any are waiting
+    pub fn next_wakeup(&self) -> Option<Instant> {
+        self.peers
+            .values()
+            .filter(|peer| peer.status == Status::Idle && peer.address.is_some())
+            .map(|peer| peer.next_attempt)
+            .min()
+    }
+
+    /// Pick the peers to dial at the given time: all persistent peers which
+    /// are due, then the peers which are due the longest, up to the outbound
+    /// limit. Each is considered dialed until [`DialManager::dial_succeeded`]
+    /// or [`DialManager::dial_failed`] is called.
+    pub fn peers_to_dial(&mut self, now: Instant) -> Vec<(node::Id, A)> {
+        let mut due = self
+            .peers
+            .iter()
+            .filter(|(_, peer)| peer.status == Status::Idle && peer.next_attempt <= now)
+            .filter_map(|(id, peer)| {
+                peer.address
+                    .as_ref()
+                    .map(|_| (*id, peer.persistent, peer.next_attempt))
+            })
+            .collect::<Vec<_>>();
+
+        // Persistent peers first, then the peers which waited the longest
+        due.sort_by_key(|&(id, persistent, next_attempt)| (!persistent, next_attempt, id));
+
+        let mut outbound = self.num_outbound();
+        let mut dials = vec![];
+
+        for (id, persistent, _) in due {
+            if !persistent {
+                if outbound >= self.config.max_outbound {
+                    continue;
+                }
+                outbound += 1;
+            }
+
+            let peer = self.peers.get_mut(&id).expect("peer exists");
+            peer.status = Status::Dialing;
+            let address = peer.address.clone().expect("peer has an address");
+
+            self.events.push_back(Event::Dialing {
+                peer: id,
+                address: address.clone(),
+            });
+            dials.push((id, address));
+        }
+
+        dials
+    }
+
+    /// Record a successful dial
+    pub fn dial_succeeded(&mut self, id: &node::Id) {
+        if let Some(peer) = self.peers.get_mut(id) {
+            if peer.status == Status::Dialing {
+                peer.status = Status::Connected(Direction::Outbound);
+                peer.failures = 0;
+                self.events.push_back(Event::Connected {
+                    peer: *id,
+                    direction: Direction::Outbound,
+                });
+            }
+        }
+    }
+
+    /// Record a failed dial at the given time, backing off from the peer or
+    /// forgetting it once it failed too many times
+    pub fn dial_failed(&mut self, id: &node::Id, now: Instant) {
+        let peer = match self.peers.get_mut(id) {
+            Some(peer) if peer.status == Status::Dialing => peer,
+            _ => return,
+        };
+
+        peer.failures = peer.failures.saturating_add(1);
+        peer.status = Status::Idle;
+
+        let retry_at = if peer.persistent || peer.failures < self.config.max_attempts {
+            peer.next_attempt = now + backoff(&self.config, peer.failures);
+            Some(peer.next_attempt)
+        } else {
+            self.peers.remove(id);
+            None
+        };
+
+        self.events.push_back(Event::DialFailed {
+            peer: *id,
+            retry_at,
+        });
+    }
+
+    /// Decide whether to accept an inbound connection from the given peer
+    pub fn accept(&mut self, id: node::Id, now: Instant) -> Result<(), Rejection> {
+        let result = self.check_inbound(&id);
+
+        match &result {
+            Ok(()) => {
+                let peer = self.peers.entry(id).or_insert(Peer {
+                    address: None,
+                    persistent: false,
+                    status: Status::Idle,
+                    failures: 0,
+                    next_attempt: now,
+                });
+                peer.status = Status::Connected(Direction::Inbound);
+                peer.failures = 0;
+
+                self.events.push_back(Event::Connected {
+                    peer: id,
+                    direction: Direction::Inbound,
+                });
+            }
+            Err(reason) => self.events.push_back(Event::Rejected {
+                peer: id,
+                reason: reason.clone(),
+            }),
+        }
+
+        result
+    }
+
+    /// Record that the given peer disconnected at the given time. Persistent
+    /// peers are dialed again after the minimum backoff, others are
+    /// forgotten unless we know their address.
+    pub fn disconnected(&mut self, id: &node::Id, now: Instant) {
+        let peer = match self.peers.get_mut(id) {
+            Some(peer) if matches!(peer.status, Status::Connected(_)) => peer,
+            _ => return,
+        };
+
+        peer.status = Status::Idle;
+
+        let reconnect_at = if peer.persistent {
+            peer.next_attempt = now + self.config.min_backoff;
+            Some(peer.next_attempt)
+        } else {
+            // Like Tendermint, only persistent peers are redialed: others
+            // wait to be added again, e.g. from the address book
+            self.peers.remove(id);
+            None
+        };
+
+        self.events.push_back(Event::Disconnected {
+            peer: *id,
+            reconnect_at,
+        });
+    }
+
+    /// Dial the peers due at the given time with the given transport,
+    /// recording the outcome of each dial, and return the connections made
+    pub fn dial<T>(&mut self, transport: &mut T, now: Instant) -> Vec<(node::Id, T::Connection)>
+    where
+        T: Transport<Address = A>,
+    {
+        let mut connections = vec![];
+
+        for (id, address) in self.peers_to_dial(now) {
+            match transport.dial(&address) {
+                Ok(connection) => {
+                    self.dial_succeeded(&id);
+                    connections.push((id, connection));
+                }
+                Err(_) => self.dial_failed(&id, now),
+            }
+        }
+
+        connections
+    }
+
+    /// Take the next event, if any happened
+    pub fn next_event(&mut self) -> Option<Event<A>> {
+        self.events.pop_front()
+    }
+
+    fn check_inbound(&self, id: &node::Id) -> Result<(), Rejection> {
+        let peer = self.peers.get(id);
+
+        if peer.map_or(false, |peer| peer.status != Status::Idle) {
+            return Err(Rejection::AlreadyConnected(*id));
+        }
+
+        // Persistent peers are always welcome
+        if !peer.map_or(false, |peer| peer.persistent)
+            && self.num_inbound() >= self.config.max_inbound
+        {
+            return Err(Rejection::TooManyInbound(self.config.max_inbound));
+        }
+
+        Ok(())
+    }
+
+    fn count(&self, f: impl Fn(&Peer<A>) -> bool) -> usize {
+        self.peers.values().filter(|peer| f(peer)).count()
+    }
+}
+
+/// Delay before dialing a peer again after the given number of failures:
+/// the minimum backoff, doubled with each failure after the first
+fn backoff(config: &Config, failures: u32) -> Duration {
+    let factor = 2u32.saturating_pow(failures.saturating_sub(1));
+
+    config
+        .min_backoff
+        .checked_mul(factor)
+        .map_or(config.max_backoff, |delay| {
+            cmp::min(delay, config.max_backoff)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::p2p::transport::MemoryNetwork;
+
+    fn id(byte: u8) -> node::Id {
+        node::Id::new([byte; 20])
+    }
+
+    fn config() -> Config {
+        Config {
+            max_inbound: 1,
+            max_outbound: 2,
+            min_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(4),
+            max_attempts: 3,
+        }
+    }
+
+    fn events<A>(manager: &mut DialManager<A>) -> Vec<Event<A>>
+    where
+        A: Clone,
+    {
+        std::iter::from_fn(|| manager.next_event()).collect()
+    }
+
+    #[test]
+    fn respects_connection_limits() {
+        let now = Instant::now();
+        let mut manager = DialManager::new(config());
+        for i in 1..=3 {
+            manager.add_peer(id(i), i, false, now);
+        }
+        manager.add_peer(id(4), 4, true, now);
+
+        let dials = manager.peers_to_dial(now);
+        assert_eq!(dials.len(), 3);
+        assert_eq!(dials[0], (id(4), 4));
+        assert_eq!(manager.num_outbound(), 2);
+        assert!(manager.peers_to_dial(now).is_empty());
+
+        assert_eq!(manager.accept(id(5), now), Ok(()));
+        assert_eq!(
+            manager.accept(id(6), now),
+            Err(Rejection::TooManyInbound(1))
+        );
+        assert_eq!(
+            manager.accept(id(4), now),
+            Err(Rejection::AlreadyConnected(id(4)))
+        );
+        assert_eq!(manager.num_inbound(), 1);
+
+        manager.add_peer(id(7), 7, true, now);
+        assert_eq!(manager.accept(id(7), now), Ok(()));
+        assert_eq!(manager.num_inbound(), 2);
+    }
+
+    #[test]
+    fn backs_off_exponentially() {
+        let config = config();
+        let delays = (1..=5)
+            .map(|failures| backoff(&config, failures).as_secs())
+            .collect::<Vec<_>>();
+        assert_eq!(delays, [1, 2, 4, 4, 4]);
+
+        let now = Instant::now();
+        let mut manager = DialManager::new(config);
+        manager.add_peer(id(1), 1, false, now);
+
+        let mut time = now;
+        for failures in 1..3 {
+            assert_eq!(manager.peers_to_dial(time).len(), 1);
+            manager.dial_failed(&id(1), time);
+
+            let retry_at = time + backoff(&manager.config, failures);
+            assert_eq!(manager.next_wakeup(), Some(retry_at));
+            assert!(manager
+                .peers_to_dial(retry_at - Duration::from_millis(1))
+                .is_empty());
+            time = retry_at;
+        }
+
+        // Forgotten after the third failure
+        assert_eq!(manager.peers_to_dial(time).len(), 1);
+        manager.dial_failed(&id(1), time);
+        assert_eq!(manager.next_wakeup(), None);
+        assert!(matches!(
+            events(&mut manager).last(),
+            Some(Event::DialFailed { retry_at: None, .. })
+        ));
+    }
+
+    #[test]
+    fn redials_persistent_peers() {
+        let now = Instant::now();
+        let mut manager = DialManager::new(config());
+        manager.add_peer(id(1), 1, true, now);
+        manager.add_peer(id(2), 2, false, now);
+
+        assert_eq!(manager.peers_to_dial(now).len(), 2);
+        for i in 1..=2 {
+            manager.dial_succeeded(&id(i));
+            manager.disconnected(&id(i), now);
+        }
+
+        let reconnect_at = now + Duration::from_secs(1);
+        assert_eq!(manager.next_wakeup(), Some(reconnect_at));
+        assert!(!manager.is_connected(&id(2)));
+
+        // Persistent peers are never forgotten
+        let mut time = reconnect_at;
+        for _ in 0..5 {
+            assert_eq!(manager.peers_to_dial(time), vec![(id(1), 1)]);
+            manager.dial_failed(&id(1), time);
+            time = manager.next_wakeup().unwrap();
+        }
+
+        assert_eq!(
+            events(&mut manager)[..4],
+            [
+                Event::Dialing {
+                    peer: id(1),
+                    address: 1
+                },
+                Event::Dialing {
+                    peer: id(2),
+                    address: 2
+                },
+                Event::Connected {
+                    peer: id(1),
+                    direction: Direction::Outbound
+                },
+                Event::Disconnected {
+                    peer: id(1),
+                    reconnect_at: Some(reconnect_at)
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn dials_with_transport() {
+        let now = Instant::now();
+        let mut network = MemoryNetwork::new();
+        let _listener = network.listen(&"up".to_owned()).unwrap();
+
+        let mut manager = DialManager::new(config());
+        manager.add_peer(id(1), "up".to_owned(), false, now);
+        manager.add_peer(id(2), "down".to_owned(), false, now);
+
+        let connections = manager.dial(&mut network, now);
+        assert_eq!(connections.len(), 1);
+        assert_eq!(connections[0].0, id(1));
+        assert!(manager.is_connected(&id(1)));
+        assert!(!manager.is_connected(&id(2)));
+        assert_eq!(
+            manager.next_wakeup(),
+            Some(now + manager.config.min_backoff)
+        );
+    }
+}
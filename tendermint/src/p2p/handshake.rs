@@ -0,0 +1,357 @@
+//! P2P handshake: once their secret connection is established, peers
+//! exchange their node info, then check that they can talk to each other
+//! before multiplexing their channels over the connection.
+//!
+//! A peer is accepted if its node info is well-formed (see
+//! [`node::Info::validate`]), claims the ID it authenticated with in the
+//! secret connection, isn't ourselves, uses the same block version and
+//! network, and shares a channel with us.
+//!
+//! Node info is encoded in the [`Protocol`] of our block version: amino for
+//! Tendermint 0.33 networks, protobuf for 0.34 and later. The peer's encoding
+//! is detected from its node info, and must match ours.
+
+use super::{read_length_delimited, Protocol};
+use crate::{
+    block, chain,
+    channel::Channels,
+    error::{Error, Kind},
+    node,
+};
+use std::io::{Read, Write};
+use tendermint_proto::DomainType;
+use thiserror::Error;
+
+/// Maximum size of the node info message
+pub const MAX_NODE_INFO_SIZE: usize = 10240;
+
+/// Reasons a peer is refused during the handshake
+#[derive(Clone, Debug, Eq, PartialEq, Error)]
+pub enum Rejection {
+    /// The peer's node info isn't well-formed
+    #[error("invalid node info: {0}")]
+    InvalidNodeInfo(String),
+
+    /// The peer's node info claims another ID than the one it authenticated
+    /// with
+    #[error("peer authenticated as {authenticated} but claims to be {claimed}")]
+    IdMismatch {
+        /// ID derived from the peer's secret connection key
+        authenticated: node::Id,
+        /// ID in the peer's node info
+        claimed: node::Id,
+    },
+
+    /// We connected to ourselves
+    #[error("connected to ourselves ({0})")]
+    SelfConnection(node::Id),
+
+    /// The peer uses another block protocol version
+    #[error("incompatible block versions: ours is {local}, the peer's is {peer}")]
+    BlockVersionMismatch {
+        /// Our block version
+        local: block::Version,
+        /// The peer's block version
+        peer: block::Version,
+    },
+
+    /// The peer is on another network
+    #[error("network mismatch: ours is {local}, the peer's is {peer}")]
+    NetworkMismatch {
+        /// Our network
+        local: chain::Id,
+        /// The peer's network
+        peer: chain::Id,
+    },
+
+    /// The peer encoded its node info in another protocol than ours
+    #[error("protocol mismatch: ours is {local}, the peer's is {peer}")]
+    ProtocolMismatch {
+        /// Our protocol
+        local: Protocol,
+        /// The peer's protocol
+        peer: Protocol,
+    },
+
+    /// The peer supports none of our channels
+    #[error("no common channels: ours are {local}, the peer's are {peer}")]
+    NoCommonChannels {
+        /// Our channels
+        local: Channels,
+        /// The peer's channels
+        peer: Channels,
+    },
+}
+
+/// Result of a successful handshake
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Outcome {
+    /// The peer's node info
+    pub peer_info: node::Info,
+
+    /// Channels both we and the peer support, in our order
+    pub channels: Channels,
+
+    /// Protocol to speak with the peer, e.g. to frame the packets of the
+    /// multiplexed connection with
+    pub protocol: Protocol,
+}
+
+/// Check whether we can talk to the peer with the given node info, which
+/// authenticated with the given ID
+pub fn check(
+    local_info: &node::Info,
+    peer_info: node::Info,
+    authenticated_id: node::Id,
+) -> Result<Outcome, Rejection> {
+    peer_info
+        .validate()
+        .map_err(|e| Rejection::InvalidNodeInfo(e.to_string()))?;
+
+    if peer_info.id != authenticated_id {
+        return Err(Rejection::IdMismatch {
+            authenticated: authenticated_id,
+            claimed: peer_info.id,
+        });
+    }
+
+    if peer_info.id == local_info.id {
+        return Err(Rejection::SelfConnection(peer_info.id));
+    }
+
+    let local_version = &local_info.protocol_version;
+    let peer_version = &peer_info.protocol_version;
+    if !local_version.is_compatible_with(peer_version) {
+        return Err(Rejection::BlockVersionMismatch {
+            local: local_version.block,
+            peer: peer_version.block,
+        });
+    }
+
+    if local_info.network != peer_info.network {
+        return Err(Rejection::NetworkMismatch {
+            local: local_info.network.clone(),
+            peer: peer_info.network.clone(),
+        });
+    }
+
+    let channels = Channels::new(
+        local_info
+            .channels
+            .as_bytes()
+            .iter()
+            .copied()
+            .filter(|&id| peer_info.channels.as_bytes().contains(&id))
+            .collect::<Vec<_>>(),
+    );
+
+    // Like Tendermint, a node without channels accepts any peer
+    if channels.is_empty() && !local_info.channels.is_empty() {
+        return Err(Rejection::NoCommonChannels {
+            local: local_info.channels.clone(),
+            peer: peer_info.channels,
+        });
+    }
+
+    Ok(Outcome {
+        protocol: Protocol::for_block_version(peer_info.protocol_version.block),
+        peer_info,
+        channels,
+    })
+}
+
+/// Send our node info over the given connection, receive the peer's and
+/// [`check`] it. The peer's ID is the one it authenticated with, e.g. its
+/// secret connection's `remote_peer_id`.
+///
+/// Rejected peers are reported as [`Kind::Protocol`] errors, with the
+/// [`Rejection`] as their source.
+pub fn exchange<IoHandler>(
+    io_handler: &mut IoHandler,
+    local_info: &node::Info,
+    peer_id: node::Id,
+) -> Result<Outcome, Error>
+where
+    IoHandler: Read + Write,
+{
+    let local_protocol = Protocol::for_block_version(local_info.protocol_version.block);
+    let message = match local_protocol {
+        Protocol::Protobuf => {
+            let mut message = Vec::with_capacity(MAX_NODE_INFO_SIZE);
+            local_info.encode_length_delimited(&mut message)?;
+            message
+        }
+        #[cfg(feature = "amino")]
+        Protocol::Amino => {
+            let info = local_info.encode_amino();
+            let mut message = Vec::with_capacity(info.len() + 2);
+            prost_amino::encode_length_delimiter(info.len(), &mut message)
+                .expect("buffer is large enough");
+            message.extend_from_slice(&info);
+            message
+        }
+        #[cfg(not(feature = "amino"))]
+        Protocol::Amino => return Err(Protocol::amino_disabled()),
+    };
+
+    io_handler
+        .write_all(&message)
+        .and_then(|_| io_handler.flush())
+        .map_err(|e| Kind::Io.context(e))?;
+
+    let message = read_length_delimited(io_handler, MAX_NODE_INFO_SIZE)?;
+    let peer_protocol = Protocol::detect_node_info(&message);
+    if peer_protocol != local_protocol {
+        let rejection = Rejection::ProtocolMismatch {
+            local: local_protocol,
+            peer: peer_protocol,
+        };
+        return Err(Kind::Protocol.context(rejection).into());
+    }
+
+    let peer_info = match peer_protocol {
+        Protocol::Protobuf => node::Info::decode(message.as_slice())?,
+        #[cfg(feature = "amino")]
+        Protocol::Amino => node::Info::decode_amino(&message)?,
+        #[cfg(not(feature = "amino"))]
+        Protocol::Amino => return Err(Protocol::amino_disabled()),
+    };
+
+    check(local_info, peer_info, peer_id).map_err(|e| Kind::Protocol.context(e).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{net::TcpListener, net::TcpStream, thread};
+
+    fn node_info(id: &str) -> node::Info {
+        serde_json::from_str(&format!(
+            r#"{{
+                "protocol_version": {{ "p2p": "8", "block": "11", "app": "1" }},
+                "id": "{}",
+                "listen_addr": "tcp://0.0.0.0:26656",
+                "network": "cosmoshub-4",
+                "version": "0.34.0",
+                "channels": "40202122233038",
+                "moniker": "node",
+                "other": {{ "tx_index": "on", "rpc_address": "tcp://0.0.0.0:26657" }}
+            }}"#,
+            id
+        ))
+        .unwrap()
+    }
+
+    fn local() -> node::Info {
+        node_info("6b90d376f9bfdd83c6d9351bf7b2f458b74deacb")
+    }
+
+    fn peer() -> node::Info {
+        node_info("1a7b6bcf3d6fb055ab3aebca415847531b626699")
+    }
+
+    #[test]
+    fn accepts_compatible_peers() {
+        let mut peer = peer();
+        peer.channels = Channels::new(vec![0x38, 0x20, 0x60]);
+
+        let outcome = check(&local(), peer.clone(), peer.id).unwrap();
+        assert_eq!(outcome.channels, Channels::new(vec![0x20, 0x38]));
+        assert_eq!(outcome.peer_info, peer);
+        assert_eq!(outcome.protocol, Protocol::Protobuf);
+    }
+
+    #[test]
+    fn rejects_incompatible_peers() {
+        let local = local();
+
+        let peer = peer();
+        assert!(matches!(
+            check(&local, peer, local.id),
+            Err(Rejection::IdMismatch { .. })
+        ));
+
+        assert_eq!(
+            check(&local, local.clone(), local.id),
+            Err(Rejection::SelfConnection(local.id))
+        );
+
+        let mut peer = self::peer();
+        peer.protocol_version.block = block::Version::TENDERMINT_V0_33;
+        assert!(matches!(
+            check(&local, peer.clone(), peer.id),
+            Err(Rejection::BlockVersionMismatch { .. })
+        ));
+
+        let mut peer = self::peer();
+        peer.network = "gaia-testnet".parse().unwrap();
+        assert!(matches!(
+            check(&local, peer.clone(), peer.id),
+            Err(Rejection::NetworkMismatch { .. })
+        ));
+
+        let mut peer = self::peer();
+        peer.channels = Channels::new(vec![0x60]);
+        assert!(matches!(
+            check(&local, peer.clone(), peer.id),
+            Err(Rejection::NoCommonChannels { .. })
+        ));
+
+        let mut peer = self::peer();
+        peer.moniker = " ".parse().unwrap();
+        assert!(matches!(
+            check(&local, peer.clone(), peer.id),
+            Err(Rejection::InvalidNodeInfo(_))
+        ));
+    }
+
+    #[test]
+    #[cfg(feature = "amino")]
+    fn negotiates_protocol() {
+        use crate::p2p::transport::MemoryConnection;
+        use std::error::Error as _;
+
+        let amino = |info: node::Info| {
+            let mut info = info;
+            info.protocol_version.block = block::Version::TENDERMINT_V0_33;
+            info
+        };
+
+        let (mut local_conn, mut peer_conn) = MemoryConnection::pair();
+        let peer_thread =
+            thread::spawn(move || exchange(&mut peer_conn, &amino(peer()), local().id));
+
+        let outcome = exchange(&mut local_conn, &amino(local()), peer().id).unwrap();
+        assert_eq!(outcome.protocol, Protocol::Amino);
+        assert_eq!(outcome.peer_info, amino(peer()));
+        peer_thread.join().unwrap().unwrap();
+
+        // A 0.33 peer can't talk to a 0.34 one
+        let (mut local_conn, mut peer_conn) = MemoryConnection::pair();
+        let peer_thread =
+            thread::spawn(move || exchange(&mut peer_conn, &amino(peer()), local().id));
+
+        let error = exchange(&mut local_conn, &local(), peer().id).unwrap_err();
+        assert!(matches!(
+            error.source().and_then(|e| e.downcast_ref::<Rejection>()),
+            Some(Rejection::ProtocolMismatch { .. })
+        ));
+        assert!(peer_thread.join().unwrap().is_err());
+    }
+
+    #[test]
+    fn exchanges_node_info() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let peer_thread = thread::spawn(move || {
+            let (mut conn, _) = listener.accept().unwrap();
+            exchange(&mut conn, &peer(), local().id)
+        });
+
+        let mut conn = TcpStream::connect(addr).unwrap();
+        let outcome = exchange(&mut conn, &local(), peer().id).unwrap();
+        assert_eq!(outcome.peer_info, peer());
+        assert_eq!(peer_thread.join().unwrap().unwrap().peer_info, local());
+    }
+}
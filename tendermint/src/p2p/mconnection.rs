@@ -0,0 +1,784 @@
+//! Multiplexed connections (`MConnection`): the framing Tendermint peers use
+//! over their secret connections to multiplex the messages of several
+//! reactors, each on its own channel.
+//!
+//! Messages are split into [`PacketMsg`] packets of up to
+//! [`MAX_PACKET_MSG_PAYLOAD_SIZE`] bytes, the last of which has its `eof`
+//! flag set. Channels with pending messages take turns sending a packet,
+//! the one which sent the least recently (relative to its priority) first,
+//! so that large messages don't hold up those of other channels. Peers keep
+//! each other alive with [`Packet::Ping`] and [`Packet::Pong`].
+//!
+//! Like Tendermint, connections throttle the packets they send and receive
+//! to the rates of their [`Config`], so a peer neither floods nor starves
+//! the other.
+//!
+//! Packets are length-delimited protobuf messages, as in Tendermint 0.34, or
+//! amino messages, as in Tendermint 0.33 (see [`Protocol`]):
+//!
+//! <https://github.com/tendermint/spec/blob/master/spec/p2p/connection.md>
+
+mod limiter;
+
+use self::limiter::Limiter;
+use super::{read_length_delimited, Protocol};
+#[cfg(feature = "amino")]
+use crate::amino_types::{message::AminoMessage, p2p as amino, Registered};
+use crate::{
+    channel,
+    error::{Error, Kind},
+};
+use anomaly::{fail, format_err};
+use std::{
+    collections::VecDeque,
+    convert::TryFrom,
+    fmt::Display,
+    io::{Read, Write},
+    thread,
+    time::Instant,
+};
+use tendermint_proto::p2p::{
+    packet::Sum, Packet as RawPacket, PacketMsg as RawPacketMsg, PacketPing as RawPacketPing,
+    PacketPong as RawPacketPong,
+};
+use tendermint_proto::DomainType;
+
+/// Maximum size of the data of a packet
+pub const MAX_PACKET_MSG_PAYLOAD_SIZE: usize = 1024;
+
+/// Default capacity of a channel's send queue, in messages
+pub const DEFAULT_SEND_QUEUE_CAPACITY: usize = 1;
+
+/// Default maximum size of a message received on a channel (21 MB)
+pub const DEFAULT_RECV_MESSAGE_CAPACITY: usize = 22_020_096;
+
+/// Default maximum rate at which packets are sent or received, in bytes per
+/// second (5 MB/s, as configured by Tendermint)
+pub const DEFAULT_TRANSFER_RATE: u64 = 5_120_000;
+
+/// Room left for the packet's other fields besides its data
+const PACKET_OVERHEAD_SIZE: usize = 32;
+
+/// Packet of a multiplexed connection
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Packet {
+    /// Keepalive request
+    Ping,
+
+    /// Keepalive response
+    Pong,
+
+    /// Part of a message
+    Msg(PacketMsg),
+}
+
+/// Part of a message sent on a channel
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PacketMsg {
+    /// Channel the message is sent on
+    pub channel_id: channel::Id,
+
+    /// Is this the last part of the message?
+    pub eof: bool,
+
+    /// Data of this part of the message
+    pub data: Vec<u8>,
+}
+
+impl DomainType<RawPacket> for Packet {}
+
+impl TryFrom<RawPacket> for Packet {
+    type Error = Error;
+
+    fn try_from(value: RawPacket) -> Result<Self, Self::Error> {
+        match value.sum {
+            Some(Sum::PacketPing(_)) => Ok(Packet::Ping),
+            Some(Sum::PacketPong(_)) => Ok(Packet::Pong),
+            Some(Sum::PacketMsg(msg)) => Ok(Packet::Msg(PacketMsg {
+                channel_id: parse_channel_id(msg.channel_id)?,
+                eof: msg.eof,
+                data: msg.data,
+            })),
+            None => Err(format_err!(Kind::Parse, "empty packet").into()),
+        }
+    }
+}
+
+impl From<Packet> for RawPacket {
+    fn from(value: Packet) -> Self {
+        let sum = match value {
+            Packet::Ping => Sum::PacketPing(RawPacketPing {}),
+            Packet::Pong => Sum::PacketPong(RawPacketPong {}),
+            Packet::Msg(msg) => Sum::PacketMsg(RawPacketMsg {
+                // Channel IDs are checked to fit in a byte when sending
+                channel_id: msg.channel_id.value() as i32,
+                eof: msg.eof,
+                data: msg.data,
+            }),
+        };
+
+        RawPacket { sum: Some(sum) }
+    }
+}
+
+#[cfg(feature = "amino")]
+#[cfg_attr(docsrs, doc(cfg(feature = "amino")))]
+impl Packet {
+    /// Decode an amino encoded packet (without its length prefix), as sent
+    /// by Tendermint 0.33
+    pub fn decode_amino(bytes: &[u8]) -> Result<Self, Error> {
+        use prost_amino::Message as _;
+
+        if bytes.starts_with(&amino::PacketPing::amino_prefix()) {
+            amino::PacketPing::decode(bytes).map_err(|e| Kind::Parse.context(e))?;
+            Ok(Packet::Ping)
+        } else if bytes.starts_with(&amino::PacketPong::amino_prefix()) {
+            amino::PacketPong::decode(bytes).map_err(|e| Kind::Parse.context(e))?;
+            Ok(Packet::Pong)
+        } else if bytes.starts_with(&amino::PacketMsg::amino_prefix()) {
+            let msg = amino::PacketMsg::decode(bytes).map_err(|e| Kind::Parse.context(e))?;
+            Ok(Packet::Msg(PacketMsg {
+                channel_id: parse_channel_id(msg.channel_id)?,
+                eof: msg.eof != 0,
+                data: msg.bytes,
+            }))
+        } else {
+            fail!(Kind::Parse, "unknown amino packet type")
+        }
+    }
+
+    /// Amino encode this packet (without a length prefix), as sent by
+    /// Tendermint 0.33
+    pub fn encode_amino(&self) -> Vec<u8> {
+        match self {
+            Packet::Ping => amino::PacketPing {}.bytes_vec(),
+            Packet::Pong => amino::PacketPong {}.bytes_vec(),
+            Packet::Msg(msg) => amino::PacketMsg {
+                channel_id: msg.channel_id.value() as u32,
+                eof: msg.eof.into(),
+                bytes: msg.data.clone(),
+            }
+            .bytes_vec(),
+        }
+    }
+}
+
+/// Channel IDs are single bytes
+fn parse_channel_id<T>(id: T) -> Result<channel::Id, Error>
+where
+    T: Copy + Display,
+    u8: TryFrom<T>,
+{
+    match u8::try_from(id) {
+        Ok(id) => Ok(channel::Id(id.into())),
+        Err(_) => Err(format_err!(Kind::OutOfRange, "invalid channel ID: {}", id).into()),
+    }
+}
+
+/// Description of a channel of a multiplexed connection
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct ChannelDescriptor {
+    /// Channel ID, which must fit in a byte
+    pub id: channel::Id,
+
+    /// Priority of the channel: channels get to send in proportion to it
+    pub priority: u64,
+
+    /// Number of messages which can wait to be sent
+    pub send_queue_capacity: usize,
+
+    /// Maximum size of a received message
+    pub recv_message_capacity: usize,
+}
+
+impl ChannelDescriptor {
+    /// Describe a channel with the given ID and priority, and the default
+    /// capacities
+    pub fn new(id: channel::Id, priority: u64) -> Self {
+        ChannelDescriptor {
+            id,
+            priority,
+            send_queue_capacity: DEFAULT_SEND_QUEUE_CAPACITY,
+            recv_message_capacity: DEFAULT_RECV_MESSAGE_CAPACITY,
+        }
+    }
+}
+
+/// Configuration of a multiplexed connection
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Config {
+    /// Maximum rate at which packets are sent, in bytes per second, or 0 for
+    /// no limit
+    pub send_rate: u64,
+
+    /// Maximum rate at which packets are received, in bytes per second, or 0
+    /// for no limit
+    pub recv_rate: u64,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            send_rate: DEFAULT_TRANSFER_RATE,
+            recv_rate: DEFAULT_TRANSFER_RATE,
+        }
+    }
+}
+
+impl From<&crate::config::P2PConfig> for Config {
+    fn from(config: &crate::config::P2PConfig) -> Self {
+        Config {
+            send_rate: config.send_rate.bytes_per_sec(),
+            recv_rate: config.recv_rate.bytes_per_sec(),
+        }
+    }
+}
+
+/// Channel of a multiplexed connection
+#[derive(Debug)]
+struct Channel {
+    desc: ChannelDescriptor,
+    send_queue: VecDeque<Vec<u8>>,
+    sending: Vec<u8>,
+    sending_offset: usize,
+    recently_sent: u64,
+    recving: Vec<u8>,
+}
+
+impl Channel {
+    fn new(desc: ChannelDescriptor) -> Self {
+        Channel {
+            desc,
+            send_queue: VecDeque::with_capacity(desc.send_queue_capacity),
+            sending: vec![],
+            sending_offset: 0,
+            recently_sent: 0,
+            recving: vec![],
+        }
+    }
+
+    /// Is a message being sent, or waiting to be?
+    fn is_send_pending(&self) -> bool {
+        self.sending_offset < self.sending.len() || !self.send_queue.is_empty()
+    }
+
+    /// Take the next packet of the message being sent, starting the next
+    /// queued message if the previous one was sent
+    fn next_packet_msg(&mut self) -> PacketMsg {
+        if self.sending_offset >= self.sending.len() {
+            self.sending = self.send_queue.pop_front().unwrap_or_default();
+            self.sending_offset = 0;
+        }
+
+        let remaining = &self.sending[self.sending_offset..];
+        let eof = remaining.len() <= MAX_PACKET_MSG_PAYLOAD_SIZE;
+        let data = remaining[..remaining.len().min(MAX_PACKET_MSG_PAYLOAD_SIZE)].to_vec();
+        self.sending_offset += data.len();
+        self.recently_sent += data.len() as u64;
+
+        PacketMsg {
+            channel_id: self.desc.id,
+            eof,
+            data,
+        }
+    }
+
+    /// Add a received packet, returning the message once complete
+    fn recv_packet_msg(&mut self, packet: PacketMsg) -> Result<Option<Vec<u8>>, Error> {
+        let len = self.recving.len() + packet.data.len();
+        if len > self.desc.recv_message_capacity {
+            fail!(
+                Kind::Length,
+                "message on channel {} exceeds maximum of {} bytes",
+                self.desc.id.value(),
+                self.desc.recv_message_capacity
+            );
+        }
+
+        self.recving.extend_from_slice(&packet.data);
+
+        if packet.eof {
+            Ok(Some(std::mem::take(&mut self.recving)))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+/// What was received on a multiplexed connection
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Received {
+    /// Complete message on a channel
+    Message {
+        /// Channel the message was received on
+        channel_id: channel::Id,
+
+        /// The message
+        data: Vec<u8>,
+    },
+
+    /// Response to one of our pings
+    Pong,
+}
+
+/// Connection multiplexing messages on several channels, usually over a
+/// secret connection
+pub struct MConnection<IoHandler> {
+    io_handler: IoHandler,
+    channels: Vec<Channel>,
+    protocol: Protocol,
+    send_limiter: Limiter,
+    recv_limiter: Limiter,
+}
+
+impl<IoHandler> MConnection<IoHandler>
+where
+    IoHandler: Read + Write,
+{
+    /// Multiplex the given channels over the given connection
+    pub fn new(io_handler: IoHandler, descriptors: &[ChannelDescriptor]) -> Result<Self, Error> {
+        let mut channels: Vec<Channel> = Vec::with_capacity(descriptors.len());
+
+        for desc in descriptors {
+            if desc.id.value() > u64::from(u8::MAX) {
+                fail!(Kind::OutOfRange, "invalid channel ID: {}", desc.id.value());
+            }
+
+            if channels.iter().any(|channel| channel.desc.id == desc.id) {
+                fail!(Kind::Protocol, "duplicate channel {}", desc.id.value());
+            }
+
+            if desc.priority == 0 {
+                fail!(
+                    Kind::OutOfRange,
+                    "channel {} has no priority",
+                    desc.id.value()
+                );
+            }
+
+            channels.push(Channel::new(*desc));
+        }
+
+        Ok(MConnection {
+            io_handler,
+            channels,
+            protocol: Protocol::Protobuf,
+            send_limiter: limiter(DEFAULT_TRANSFER_RATE),
+            recv_limiter: limiter(DEFAULT_TRANSFER_RATE),
+        })
+    }
+
+    /// Apply the given configuration (the default one otherwise)
+    pub fn config(mut self, config: Config) -> Self {
+        self.send_limiter = limiter(config.send_rate);
+        self.recv_limiter = limiter(config.recv_rate);
+        self
+    }
+
+    /// Encode packets in the given protocol (protobuf by default), e.g. the
+    /// one negotiated during the handshake
+    pub fn protocol(mut self, protocol: Protocol) -> Self {
+        self.protocol = protocol;
+        self
+    }
+
+    /// Queue a message to be sent on the given channel, returning `false` if
+    /// the channel's send queue is full
+    pub fn try_send(&mut self, channel_id: channel::Id, msg: Vec<u8>) -> Result<bool, Error> {
+        let channel = self.channel_mut(channel_id)?;
+
+        if channel.send_queue.len() >= channel.desc.send_queue_capacity {
+            return Ok(false);
+        }
+
+        channel.send_queue.push_back(msg);
+        Ok(true)
+    }
+
+    /// Can a message be queued on the given channel?
+    pub fn can_send(&self, channel_id: channel::Id) -> bool {
+        self.channels
+            .iter()
+            .find(|channel| channel.desc.id == channel_id)
+            .map_or(false, |channel| {
+                channel.send_queue.len() < channel.desc.send_queue_capacity
+            })
+    }
+
+    /// Send a keepalive ping, which the peer answers with a pong
+    pub fn send_ping(&mut self) -> Result<(), Error> {
+        self.write_packet(Packet::Ping)?;
+        self.io_handler
+            .flush()
+            .map_err(|e| Kind::Io.context(e).into())
+    }
+
+    /// Send a packet of the channel with a message pending which sent the
+    /// least recently relative to its priority, returning `false` if no
+    /// channel has any message pending. Packets aren't flushed.
+    pub fn send_packet_msg(&mut self) -> Result<bool, Error> {
+        let channel = self
+            .channels
+            .iter_mut()
+            .filter(|channel| channel.is_send_pending())
+            .min_by(|a, b| {
+                let a_ratio = a.recently_sent as f64 / a.desc.priority as f64;
+                let b_ratio = b.recently_sent as f64 / b.desc.priority as f64;
+                a_ratio.partial_cmp(&b_ratio).expect("ratios are finite")
+            });
+
+        let packet = match channel {
+            Some(channel) => channel.next_packet_msg(),
+            None => return Ok(false),
+        };
+
+        self.write_packet(Packet::Msg(packet))?;
+        Ok(true)
+    }
+
+    /// Send all pending messages, then flush the connection
+    pub fn flush(&mut self) -> Result<(), Error> {
+        while self.send_packet_msg()? {}
+        self.io_handler
+            .flush()
+            .map_err(|e| Kind::Io.context(e).into())
+    }
+
+    /// Decay how much each channel sent recently, as Tendermint does every
+    /// two seconds, so that channels which sent a lot long ago get their
+    /// turn again
+    pub fn update_stats(&mut self) {
+        for channel in &mut self.channels {
+            channel.recently_sent = channel.recently_sent * 8 / 10;
+        }
+    }
+
+    /// Receive packets until a message is complete or a pong arrives,
+    /// answering the peer's pings along the way
+    pub fn recv(&mut self) -> Result<Received, Error> {
+        loop {
+            match self.read_packet()? {
+                Packet::Ping => {
+                    self.write_packet(Packet::Pong)?;
+                    self.io_handler.flush().map_err(|e| Kind::Io.context(e))?;
+                }
+                Packet::Pong => return Ok(Received::Pong),
+                Packet::Msg(packet) => {
+                    let channel_id = packet.channel_id;
+                    if let Some(data) = self.channel_mut(channel_id)?.recv_packet_msg(packet)? {
+                        return Ok(Received::Message { channel_id, data });
+                    }
+                }
+            }
+        }
+    }
+
+    /// Get the status of each channel, as Tendermint reports it over RPC
+    pub fn status(&self) -> Vec<channel::Channel> {
+        self.channels
+            .iter()
+            .map(|channel| channel::Channel {
+                id: channel.desc.id,
+                send_queue_capacity: channel.desc.send_queue_capacity as u64,
+                send_queue_size: channel.send_queue.len() as u64,
+                priority: channel.desc.priority,
+                recently_sent: channel.recently_sent,
+            })
+            .collect()
+    }
+
+    /// Get the underlying connection
+    pub fn into_inner(self) -> IoHandler {
+        self.io_handler
+    }
+
+    fn channel_mut(&mut self, channel_id: channel::Id) -> Result<&mut Channel, Error> {
+        self.channels
+            .iter_mut()
+            .find(|channel| channel.desc.id == channel_id)
+            .ok_or_else(|| {
+                format_err!(Kind::Protocol, "unknown channel {}", channel_id.value()).into()
+            })
+    }
+
+    fn write_packet(&mut self, packet: Packet) -> Result<(), Error> {
+        let bytes = match self.protocol {
+            Protocol::Protobuf => {
+                let mut bytes =
+                    Vec::with_capacity(MAX_PACKET_MSG_PAYLOAD_SIZE + PACKET_OVERHEAD_SIZE);
+                packet.encode_length_delimited(&mut bytes)?;
+                bytes
+            }
+            #[cfg(feature = "amino")]
+            Protocol::Amino => {
+                let bytes = packet.encode_amino();
+                let mut prefixed = Vec::with_capacity(bytes.len() + 2);
+                prost_amino::encode_length_delimiter(bytes.len(), &mut prefixed)
+                    .expect("buffer is large enough");
+                prefixed.extend_from_slice(&bytes);
+                prefixed
+            }
+            #[cfg(not(feature = "amino"))]
+            Protocol::Amino => return Err(Protocol::amino_disabled()),
+        };
+
+        throttle(&mut self.send_limiter, bytes.len());
+        self.io_handler
+            .write_all(&bytes)
+            .map_err(|e| Kind::Io.context(e).into())
+    }
+
+    fn read_packet(&mut self) -> Result<Packet, Error> {
+        let packet = read_length_delimited(
+            &mut self.io_handler,
+            MAX_PACKET_MSG_PAYLOAD_SIZE + PACKET_OVERHEAD_SIZE,
+        )?;
+        throttle(&mut self.recv_limiter, packet.len());
+
+        match self.protocol {
+            Protocol::Protobuf => Packet::decode(packet.as_slice()).map_err(Into::into),
+            #[cfg(feature = "amino")]
+            Protocol::Amino => Packet::decode_amino(&packet),
+            #[cfg(not(feature = "amino"))]
+            Protocol::Amino => Err(Protocol::amino_disabled()),
+        }
+    }
+}
+
+/// Limiter of the given rate, which lets full packets through
+fn limiter(rate: u64) -> Limiter {
+    Limiter::new(rate, MAX_PACKET_MSG_PAYLOAD_SIZE + PACKET_OVERHEAD_SIZE)
+}
+
+/// Wait until the given number of bytes can be transferred
+fn throttle(limiter: &mut Limiter, bytes: usize) {
+    let delay = limiter.delay(bytes, Instant::now());
+    if delay.as_nanos() > 0 {
+        thread::sleep(delay);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{self, Cursor};
+
+    /// Connection reading from a buffer and writing to another
+    #[derive(Default)]
+    struct Buffers {
+        input: Cursor<Vec<u8>>,
+        output: Vec<u8>,
+    }
+
+    impl Read for Buffers {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.input.read(buf)
+        }
+    }
+
+    impl Write for Buffers {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.output.write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn descriptors() -> Vec<ChannelDescriptor> {
+        vec![
+            ChannelDescriptor::new(channel::Id(0x20), 1),
+            ChannelDescriptor::new(channel::Id(0x30), 1),
+        ]
+    }
+
+    /// Connection receiving what the given one sent
+    fn receiver(sender: MConnection<Buffers>) -> MConnection<Buffers> {
+        let input = Cursor::new(sender.into_inner().output);
+        MConnection::new(
+            Buffers {
+                input,
+                output: vec![],
+            },
+            &descriptors(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn packet_round_trip() {
+        let packets = [
+            Packet::Ping,
+            Packet::Pong,
+            Packet::Msg(PacketMsg {
+                channel_id: channel::Id(0x40),
+                eof: true,
+                data: b"block part".to_vec(),
+            }),
+        ];
+
+        for packet in &packets {
+            let bytes = packet.encode_vec().unwrap();
+            assert_eq!(&Packet::decode_vec(&bytes).unwrap(), packet);
+        }
+
+        let mut raw = RawPacket::from(packets[2].clone());
+        if let Some(Sum::PacketMsg(msg)) = &mut raw.sum {
+            msg.channel_id = 256;
+        }
+        assert!(Packet::try_from(raw).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "amino")]
+    fn amino_round_trip() {
+        let packets = [
+            Packet::Ping,
+            Packet::Pong,
+            Packet::Msg(PacketMsg {
+                channel_id: channel::Id(0x40),
+                eof: false,
+                data: b"block part".to_vec(),
+            }),
+        ];
+
+        for packet in &packets {
+            let bytes = packet.encode_amino();
+            assert_eq!(&Packet::decode_amino(&bytes).unwrap(), packet);
+        }
+
+        assert!(Packet::decode_amino(&[0x0a, 0x00]).is_err());
+
+        let mut sender = MConnection::new(Buffers::default(), &descriptors())
+            .unwrap()
+            .protocol(Protocol::Amino);
+        sender.try_send(channel::Id(0x20), b"tx".to_vec()).unwrap();
+        sender.flush().unwrap();
+
+        let input = Cursor::new(sender.into_inner().output);
+        let mut receiver = MConnection::new(
+            Buffers {
+                input,
+                output: vec![],
+            },
+            &descriptors(),
+        )
+        .unwrap()
+        .protocol(Protocol::Amino);
+        assert_eq!(
+            receiver.recv().unwrap(),
+            Received::Message {
+                channel_id: channel::Id(0x20),
+                data: b"tx".to_vec()
+            }
+        );
+    }
+
+    #[test]
+    fn multiplexes_channels() {
+        let large = vec![0xab; 3 * MAX_PACKET_MSG_PAYLOAD_SIZE];
+        let small = b"vote".to_vec();
+
+        let mut sender = MConnection::new(Buffers::default(), &descriptors()).unwrap();
+        assert!(sender.try_send(channel::Id(0x20), large.clone()).unwrap());
+        assert!(!sender.try_send(channel::Id(0x20), vec![]).unwrap());
+        assert!(!sender.can_send(channel::Id(0x20)));
+        assert!(sender.try_send(channel::Id(0x30), small.clone()).unwrap());
+        assert!(sender.try_send(channel::Id(0x60), vec![]).is_err());
+        sender.flush().unwrap();
+
+        // The small message gets its turn after the first part of the large one
+        let mut receiver = receiver(sender);
+        assert_eq!(
+            receiver.recv().unwrap(),
+            Received::Message {
+                channel_id: channel::Id(0x30),
+                data: small
+            }
+        );
+        assert_eq!(
+            receiver.recv().unwrap(),
+            Received::Message {
+                channel_id: channel::Id(0x20),
+                data: large
+            }
+        );
+    }
+
+    #[test]
+    fn throttles_packets() {
+        let config = Config {
+            send_rate: 10_000,
+            recv_rate: 0,
+        };
+
+        let mut sender = MConnection::new(Buffers::default(), &descriptors())
+            .unwrap()
+            .config(config);
+        sender
+            .try_send(channel::Id(0x20), vec![0; 3 * MAX_PACKET_MSG_PAYLOAD_SIZE])
+            .unwrap();
+
+        let status = sender.status();
+        assert_eq!(status[0].send_queue_size, 1);
+        assert_eq!(status[1].send_queue_size, 0);
+
+        // The first packet fits in the burst, the other two wait for it
+        let start = std::time::Instant::now();
+        sender.flush().unwrap();
+        assert!(start.elapsed() >= std::time::Duration::from_millis(150));
+        assert_eq!(
+            sender.status()[0].recently_sent,
+            3 * MAX_PACKET_MSG_PAYLOAD_SIZE as u64
+        );
+    }
+
+    #[test]
+    fn answers_pings() {
+        let mut sender = MConnection::new(Buffers::default(), &descriptors()).unwrap();
+        sender.send_ping().unwrap();
+        sender.try_send(channel::Id(0x20), b"tx".to_vec()).unwrap();
+        sender.flush().unwrap();
+
+        let mut receiver = receiver(sender);
+        receiver.recv().unwrap();
+
+        let pong = Cursor::new(receiver.into_inner().output);
+        let mut sender = MConnection::new(
+            Buffers {
+                input: pong,
+                output: vec![],
+            },
+            &descriptors(),
+        )
+        .unwrap();
+        assert_eq!(sender.recv().unwrap(), Received::Pong);
+    }
+
+    #[test]
+    fn rejects_oversized_messages() {
+        let mut sender = MConnection::new(Buffers::default(), &descriptors()).unwrap();
+        sender.try_send(channel::Id(0x20), vec![0; 100]).unwrap();
+        sender.flush().unwrap();
+
+        let input = Cursor::new(sender.into_inner().output);
+        let mut descriptors = descriptors();
+        descriptors[0].recv_message_capacity = 99;
+        let mut receiver = MConnection::new(
+            Buffers {
+                input,
+                output: vec![],
+            },
+            &descriptors,
+        )
+        .unwrap();
+        assert!(receiver.recv().is_err());
+
+        let invalid = ChannelDescriptor::new(channel::Id(0x100), 1);
+        assert!(MConnection::new(Buffers::default(), &[invalid]).is_err());
+
+        let duplicate = [descriptors[1], descriptors[1]];
+        assert!(MConnection::new(Buffers::default(), &duplicate).is_err());
+    }
+}
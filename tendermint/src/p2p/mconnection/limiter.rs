@@ -0,0 +1,81 @@
+//! Transfer rate limiting
+
+use std::time::{Duration, Instant};
+
+/// Token bucket limiting the rate of a transfer. It holds up to a tenth of
+/// a second of transfer (or at least a full packet), so short bursts go
+/// through right away while the average rate stays under the limit.
+#[derive(Clone, Debug)]
+pub(super) struct Limiter {
+    /// Maximum rate in bytes per second, or 0 for no limit
+    rate: u64,
+
+    /// Capacity of the bucket, in bytes
+    burst: f64,
+
+    /// Bytes which can be transferred without waiting. Negative when
+    /// transfers are ahead of the rate.
+    available: f64,
+
+    /// When the bucket was last refilled
+    last: Option<Instant>,
+}
+
+impl Limiter {
+    /// Limit transfers to the given number of bytes per second, or not at
+    /// all if 0, letting at least `min_burst` bytes through at once
+    pub(super) fn new(rate: u64, min_burst: usize) -> Self {
+        let burst = (rate / 10).max(min_burst as u64) as f64;
+
+        Limiter {
+            rate,
+            burst,
+            available: burst,
+            last: None,
+        }
+    }
+
+    /// Account for the transfer of the given number of bytes at the given
+    /// time, returning how long to wait to stay under the rate
+    pub(super) fn delay(&mut self, bytes: usize, now: Instant) -> Duration {
+        if self.rate == 0 {
+            return Duration::from_secs(0);
+        }
+
+        if let Some(last) = self.last {
+            let elapsed = now.saturating_duration_since(last).as_secs_f64();
+            self.available = (self.available + elapsed * self.rate as f64).min(self.burst);
+        }
+        self.last = Some(now);
+        self.available -= bytes as f64;
+
+        if self.available >= 0.0 {
+            Duration::from_secs(0)
+        } else {
+            Duration::from_secs_f64(-self.available / self.rate as f64)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn limits_transfer_rate() {
+        let start = Instant::now();
+        let mut limiter = Limiter::new(10_000, 1000);
+
+        // A burst of up to a tenth of a second goes through right away
+        assert_eq!(limiter.delay(1000, start), Duration::from_secs(0));
+        assert_eq!(limiter.delay(500, start), Duration::from_millis(50));
+
+        // Waiting pays the debt back, then refills the bucket up to its size
+        let later = start + Duration::from_secs(10);
+        assert_eq!(limiter.delay(1000, later), Duration::from_secs(0));
+        assert_eq!(limiter.delay(100, later), Duration::from_millis(10));
+
+        let mut unlimited = Limiter::new(0, 1024);
+        assert_eq!(unlimited.delay(1 << 30, start), Duration::from_secs(0));
+    }
+}
@@ -0,0 +1,287 @@
+//! Peer exchange (PEX): the protocol nodes use to request peer addresses
+//! from each other and share those in their address books.
+//!
+//! Messages are encoded with protobuf as in Tendermint 0.34 (see
+//! [`DomainType`]), or with amino as in Tendermint 0.33 (with the `amino`
+//! feature).
+
+#[cfg(feature = "amino")]
+use crate::amino_types::{
+    message::AminoMessage, pex as amino, PexAddrsMessage, PexRequestMessage, Registered,
+};
+use crate::{
+    error::{Error, Kind},
+    node,
+};
+#[cfg(feature = "amino")]
+use anomaly::fail;
+use anomaly::format_err;
+use std::{
+    convert::TryFrom,
+    fmt::{self, Display},
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+};
+use tendermint_proto::p2p::{
+    message::Sum, Message as RawMessage, NetAddress as RawNetAddress, PexAddrs as RawPexAddrs,
+    PexRequest as RawPexRequest,
+};
+use tendermint_proto::DomainType;
+
+/// PEX message
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Message {
+    /// Request for peer addresses
+    Request,
+
+    /// Peer addresses, in response to a request
+    Addrs(Vec<NetAddress>),
+}
+
+/// Address of a peer, as exchanged over PEX
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub struct NetAddress {
+    /// Node ID of the peer
+    pub id: node::Id,
+
+    /// IP address
+    pub ip: IpAddr,
+
+    /// Port
+    pub port: u16,
+}
+
+impl NetAddress {
+    /// Create a new PEX peer address
+    pub fn new(id: node::Id, ip: IpAddr, port: u16) -> Self {
+        NetAddress { id, ip, port }
+    }
+}
+
+impl From<NetAddress> for node::Address {
+    fn from(address: NetAddress) -> node::Address {
+        node::Address::new(address.id, address.ip.to_string(), address.port)
+    }
+}
+
+impl Display for NetAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", node::Address::from(*self))
+    }
+}
+
+impl DomainType<RawMessage> for Message {}
+
+impl TryFrom<RawMessage> for Message {
+    type Error = Error;
+
+    fn try_from(value: RawMessage) -> Result<Self, Self::Error> {
+        match value.sum {
+            Some(Sum::PexRequest(_)) => Ok(Message::Request),
+            Some(Sum::PexAddrs(addrs)) => addrs
+                .addrs
+                .into_iter()
+                .map(NetAddress::try_from)
+                .collect::<Result<_, _>>()
+                .map(Message::Addrs),
+            None => Err(format_err!(Kind::Parse, "empty PEX message").into()),
+        }
+    }
+}
+
+impl From<Message> for RawMessage {
+    fn from(value: Message) -> Self {
+        let sum = match value {
+            Message::Request => Sum::PexRequest(RawPexRequest {}),
+            Message::Addrs(addrs) => Sum::PexAddrs(RawPexAddrs {
+                addrs: addrs.into_iter().map(Into::into).collect(),
+            }),
+        };
+
+        RawMessage { sum: Some(sum) }
+    }
+}
+
+impl TryFrom<RawNetAddress> for NetAddress {
+    type Error = Error;
+
+    fn try_from(value: RawNetAddress) -> Result<Self, Self::Error> {
+        Ok(NetAddress {
+            id: value.id.parse()?,
+            ip: value
+                .ip
+                .parse()
+                .map_err(|_| format_err!(Kind::Parse, "invalid peer IP address: {}", value.ip))?,
+            port: parse_port(value.port)?,
+        })
+    }
+}
+
+impl From<NetAddress> for RawNetAddress {
+    fn from(value: NetAddress) -> Self {
+        RawNetAddress {
+            // Node IDs are written in lower case hex on the wire
+            id: value.id.to_string().to_lowercase(),
+            ip: value.ip.to_string(),
+            port: value.port.into(),
+        }
+    }
+}
+
+#[cfg(feature = "amino")]
+#[cfg_attr(docsrs, doc(cfg(feature = "amino")))]
+impl Message {
+    /// Decode an amino encoded message (without its length prefix), as sent
+    /// by Tendermint 0.33
+    pub fn decode_amino(bytes: &[u8]) -> Result<Self, Error> {
+        use prost_amino::Message as _;
+
+        if bytes.starts_with(&PexRequestMessage::amino_prefix()) {
+            PexRequestMessage::decode(bytes).map_err(|e| Kind::Parse.context(e))?;
+            Ok(Message::Request)
+        } else if bytes.starts_with(&PexAddrsMessage::amino_prefix()) {
+            PexAddrsMessage::decode(bytes)
+                .map_err(|e| Kind::Parse.context(e))?
+                .addrs
+                .into_iter()
+                .map(NetAddress::try_from)
+                .collect::<Result<_, _>>()
+                .map(Message::Addrs)
+        } else {
+            fail!(Kind::Parse, "unknown amino PEX message type")
+        }
+    }
+
+    /// Amino encode this message (without a length prefix), as sent by
+    /// Tendermint 0.33
+    pub fn encode_amino(&self) -> Vec<u8> {
+        match self {
+            Message::Request => PexRequestMessage {}.bytes_vec(),
+            Message::Addrs(addrs) => PexAddrsMessage {
+                addrs: addrs.iter().copied().map(Into::into).collect(),
+            }
+            .bytes_vec(),
+        }
+    }
+}
+
+#[cfg(feature = "amino")]
+impl TryFrom<amino::NetAddress> for NetAddress {
+    type Error = Error;
+
+    fn try_from(value: amino::NetAddress) -> Result<Self, Self::Error> {
+        let ip = match value.ip.len() {
+            4 => {
+                let mut octets = [0u8; 4];
+                octets.copy_from_slice(&value.ip);
+                IpAddr::V4(Ipv4Addr::from(octets))
+            }
+            16 => {
+                let mut octets = [0u8; 16];
+                octets.copy_from_slice(&value.ip);
+                let ip = Ipv6Addr::from(octets);
+
+                // Go keeps IPv4 addresses in their IPv4-mapped IPv6 form
+                match ip.segments() {
+                    [0, 0, 0, 0, 0, 0xffff, ..] => IpAddr::V4(Ipv4Addr::new(
+                        octets[12], octets[13], octets[14], octets[15],
+                    )),
+                    _ => IpAddr::V6(ip),
+                }
+            }
+            len => fail!(Kind::Length, "invalid peer IP address length: {}", len),
+        };
+
+        Ok(NetAddress {
+            id: value.id.parse()?,
+            ip,
+            port: parse_port(value.port)?,
+        })
+    }
+}
+
+#[cfg(feature = "amino")]
+impl From<NetAddress> for amino::NetAddress {
+    fn from(value: NetAddress) -> Self {
+        let ip = match value.ip {
+            IpAddr::V4(ip) => ip.octets().to_vec(),
+            IpAddr::V6(ip) => ip.octets().to_vec(),
+        };
+
+        amino::NetAddress {
+            id: value.id.to_string().to_lowercase(),
+            ip,
+            port: value.port.into(),
+        }
+    }
+}
+
+fn parse_port(port: u32) -> Result<u16, Error> {
+    u16::try_from(port)
+        .map_err(|_| format_err!(Kind::OutOfRange, "invalid peer port: {}", port).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn addrs() -> Vec<NetAddress> {
+        let id = "1a7b6bcf3d6fb055ab3aebca415847531b626699".parse().unwrap();
+
+        vec![
+            NetAddress::new(id, "203.0.113.7".parse().unwrap(), 26656),
+            NetAddress::new(id, "2001:db8::7".parse().unwrap(), 26656),
+        ]
+    }
+
+    #[test]
+    fn protobuf_round_trip() {
+        for message in &[Message::Request, Message::Addrs(addrs())] {
+            let bytes = message.encode_vec().unwrap();
+            assert_eq!(&Message::decode_vec(&bytes).unwrap(), message);
+        }
+
+        assert_eq!(
+            addrs()[1].to_string(),
+            "1a7b6bcf3d6fb055ab3aebca415847531b626699@[2001:db8::7]:26656"
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_addresses() {
+        let raw = RawNetAddress::from(addrs()[0]);
+
+        let mut port = raw.clone();
+        port.port = 65536;
+        assert!(NetAddress::try_from(port).is_err());
+
+        let mut ip = raw;
+        ip.ip = "example.com".to_owned();
+        assert!(NetAddress::try_from(ip).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "amino")]
+    fn amino_round_trip() {
+        for message in &[Message::Request, Message::Addrs(addrs())] {
+            let bytes = message.encode_amino();
+            assert_eq!(&Message::decode_amino(&bytes).unwrap(), message);
+        }
+
+        assert!(Message::decode_amino(&[0x0a, 0x00]).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "amino")]
+    fn amino_ipv4_mapped_addresses() {
+        let mut ip = vec![0u8; 10];
+        ip.extend_from_slice(&[0xff, 0xff, 203, 0, 113, 7]);
+
+        let address = NetAddress::try_from(amino::NetAddress {
+            id: "1a7b6bcf3d6fb055ab3aebca415847531b626699".to_owned(),
+            ip,
+            port: 26656,
+        })
+        .unwrap();
+        assert_eq!(address, addrs()[0]);
+    }
+}
@@ -0,0 +1,95 @@
+//! Versions of the P2P wire protocol, which Tendermint 0.34 switched from
+//! amino to protobuf: peers exchange their node info, and frame the packets
+//! of their multiplexed connections, in the encoding of their version.
+//!
+//! Peers write their node info before reading the peer's, so the node info
+//! is written in the encoding of our block version, and the peer's encoding
+//! is detected from its node info (see [`Protocol::detect_node_info`]).
+//! Peers must use the same block version anyway, hence the same protocol.
+
+#[cfg(feature = "amino")]
+use crate::amino_types::{DefaultNodeInfo, Registered};
+use crate::block;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+#[cfg(not(feature = "amino"))]
+use {
+    crate::{error::Kind, Error},
+    anomaly::format_err,
+};
+
+/// P2P protocol version
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Protocol {
+    /// Amino messages (Tendermint 0.33 and earlier). Needs the `amino`
+    /// feature.
+    Amino,
+
+    /// Protobuf messages (Tendermint 0.34 and later)
+    Protobuf,
+}
+
+impl Protocol {
+    /// Protocol spoken by nodes with the given block version
+    pub fn for_block_version(version: block::Version) -> Self {
+        if version < block::Version::TENDERMINT_V0_34 {
+            Protocol::Amino
+        } else {
+            Protocol::Protobuf
+        }
+    }
+
+    /// Detect the protocol of the given node info message (without its
+    /// length prefix): amino if it starts with the amino prefix of
+    /// `DefaultNodeInfo`, protobuf otherwise
+    pub fn detect_node_info(message: &[u8]) -> Self {
+        #[cfg(feature = "amino")]
+        {
+            if message.starts_with(&DefaultNodeInfo::amino_prefix()) {
+                return Protocol::Amino;
+            }
+        }
+
+        #[cfg(not(feature = "amino"))]
+        let _ = message;
+
+        Protocol::Protobuf
+    }
+
+    /// Error for amino messages when the `amino` feature is disabled
+    #[cfg(not(feature = "amino"))]
+    pub(crate) fn amino_disabled() -> Error {
+        format_err!(
+            Kind::Protocol,
+            "amino P2P messages need the `amino` feature"
+        )
+        .into()
+    }
+}
+
+impl fmt::Display for Protocol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Protocol::Amino => write!(f, "amino"),
+            Protocol::Protobuf => write!(f, "protobuf"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn protocol_by_block_version() {
+        assert_eq!(
+            Protocol::for_block_version(block::Version::TENDERMINT_V0_33),
+            Protocol::Amino
+        );
+        assert_eq!(
+            Protocol::for_block_version(block::Version::TENDERMINT_V0_34),
+            Protocol::Protobuf
+        );
+    }
+}
@@ -0,0 +1,699 @@
+//! Secret connections: Tendermint's authenticated encryption protocol, which
+//! secures the connections between peers, and between validators and their
+//! remote signers over TCP.
+//!
+//! The handshake is a station-to-station protocol: peers exchange ephemeral
+//! X25519 public keys, derive ChaCha20-Poly1305 keys from their
+//! Diffie-Hellman secret, then authenticate each other by signing a challenge
+//! derived from a Merlin transcript of the exchange with their Ed25519 keys.
+//! Messages are then sent in sealed frames of up to [`DATA_MAX_SIZE`] bytes.
+//!
+//! [`SecretConnection`] secures blocking connections, and
+//! [`AsyncSecretConnection`] asynchronous ones.
+//!
+//! Each direction of a connection counts its frames to derive their nonces,
+//! which are never reused: once a direction has used up its 2^64 nonces, its
+//! frames fail with [`NoncesExhausted`]. Long-lived connections can instead
+//! rekey each direction after a number of frames, deriving its next key from
+//! the current one (see [`SecretConnection::rekey_after`]). Tendermint doesn't
+//! rekey, so both peers must enable it with the same number of frames.
+//!
+//! This is the protocol of Tendermint 0.34, which exchanges protobuf
+//! handshake messages:
+//!
+//! <https://github.com/tendermint/spec/blob/master/spec/p2p/peer.md#authenticated-encryption-handshake>
+
+mod asynchronous;
+mod kdf;
+mod nonce;
+
+pub use self::{
+    asynchronous::{AsyncSecretConnection, ReadHalf, WriteHalf},
+    nonce::{Direction, NoncesExhausted},
+};
+
+use self::{kdf::Kdf, nonce::Nonce};
+use crate::{error::Kind, node, public_key::Ed25519, signature, Error};
+use anomaly::{fail, format_err};
+use chacha20poly1305::{
+    aead::{generic_array::GenericArray, AeadInPlace, NewAead},
+    ChaCha20Poly1305,
+};
+use ed25519_dalek::{Keypair, Signer as _, Verifier as _};
+use merlin::Transcript;
+use prost::Message as _;
+use rand_core::OsRng;
+use std::{
+    cmp,
+    convert::{TryFrom, TryInto},
+    io::{self, Read, Write},
+};
+use subtle::ConstantTimeEq;
+use tendermint_proto::{
+    crypto::{public_key::Sum, PublicKey as RawPublicKey},
+    p2p::AuthSigMessage,
+};
+use x25519_dalek::{EphemeralSecret, PublicKey as EphemeralPublic};
+use zeroize::Zeroizing;
+
+/// Maximum size of the data in a frame
+pub const DATA_MAX_SIZE: usize = 1024;
+
+/// Size of the length of the data, which starts each frame
+const DATA_LEN_SIZE: usize = 4;
+
+/// Size of a frame before it's sealed: frames are padded to this size
+const TOTAL_FRAME_SIZE: usize = DATA_MAX_SIZE + DATA_LEN_SIZE;
+
+/// Size of the authentication tag of a sealed frame
+const TAG_SIZE: usize = 16;
+
+/// Size of a sealed frame on the wire
+pub const SEALED_FRAME_SIZE: usize = TOTAL_FRAME_SIZE + TAG_SIZE;
+
+/// Maximum size of a handshake message
+const MAX_HANDSHAKE_MESSAGE_SIZE: usize = 1024;
+
+/// Ephemeral public key message of the handshake (`gogoproto.BytesValue`)
+#[derive(Clone, PartialEq, prost::Message)]
+struct EphemeralPublicKey {
+    #[prost(bytes, tag = "1")]
+    value: Vec<u8>,
+}
+
+/// Encrypted and authenticated connection to a peer
+pub struct SecretConnection<IoHandler> {
+    io_handler: IoHandler,
+    sender: Sender,
+    receiver: Receiver,
+    recv_buffer: Vec<u8>,
+    remote_pubkey: Option<Ed25519>,
+}
+
+impl<IoHandler> SecretConnection<IoHandler>
+where
+    IoHandler: Read + Write,
+{
+    /// Perform the handshake over the given connection, authenticating with
+    /// the given key
+    pub fn new(mut io_handler: IoHandler, local_keypair: Keypair) -> Result<Self, Error> {
+        let handshake = Handshake::new(local_keypair);
+
+        io_handler
+            .write_all(&handshake.local_eph_pubkey_message())
+            .and_then(|_| io_handler.flush())
+            .map_err(|e| Kind::Io.context(e))?;
+
+        let remote_eph_pubkey = read_length_delimited(&mut io_handler)?;
+        let (authentication, sender, receiver) = handshake.got_eph_pubkey(&remote_eph_pubkey)?;
+
+        let mut conn = SecretConnection {
+            io_handler,
+            sender,
+            receiver,
+            recv_buffer: vec![],
+            remote_pubkey: None,
+        };
+
+        conn.write_all(&authentication.local_auth_sig_message())
+            .and_then(|_| conn.flush())
+            .map_err(|e| Kind::Io.context(e))?;
+
+        let remote_auth_sig = read_length_delimited(&mut conn)?;
+        conn.remote_pubkey = Some(authentication.got_auth_sig(&remote_auth_sig)?);
+
+        Ok(conn)
+    }
+}
+
+impl<IoHandler> SecretConnection<IoHandler> {
+    /// Rekey each direction of the connection after the given number of
+    /// frames, which must be the peer's too. Frames already sent or received
+    /// count towards the first rekeying.
+    pub fn rekey_after(mut self, frames: u64) -> Self {
+        self.sender.rekey_after(frames);
+        self.receiver.rekey_after(frames);
+        self
+    }
+
+    /// Get the authenticated public key of the peer
+    pub fn remote_pubkey(&self) -> Ed25519 {
+        self.remote_pubkey
+            .expect("secret connection is authenticated")
+    }
+
+    /// Get the ID of the peer, derived from its public key
+    pub fn remote_peer_id(&self) -> node::Id {
+        self.remote_pubkey().into()
+    }
+}
+
+impl<IoHandler: Read> Read for SecretConnection<IoHandler> {
+    fn read(&mut self, data: &mut [u8]) -> io::Result<usize> {
+        // Frames may be empty, which mustn't be taken for the end of the stream
+        while self.recv_buffer.is_empty() {
+            let mut sealed = [0u8; SEALED_FRAME_SIZE];
+            if !read_sealed_frame(&mut self.io_handler, &mut sealed)? {
+                return Ok(0);
+            }
+
+            let chunk = self
+                .receiver
+                .open(&mut sealed)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            self.recv_buffer.extend_from_slice(chunk);
+        }
+
+        let n = cmp::min(data.len(), self.recv_buffer.len());
+        data[..n].copy_from_slice(&self.recv_buffer[..n]);
+        self.recv_buffer.drain(..n);
+
+        Ok(n)
+    }
+}
+
+impl<IoHandler: Write> Write for SecretConnection<IoHandler> {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        let mut sealed = [0u8; SEALED_FRAME_SIZE];
+
+        for chunk in data.chunks(DATA_MAX_SIZE) {
+            self.sender
+                .seal(chunk, &mut sealed)
+                .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+            self.io_handler.write_all(&sealed)?;
+        }
+
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.io_handler.flush()
+    }
+}
+
+/// Handshake of a secret connection, independent of how its messages are
+/// exchanged:
+///
+/// 1. each peer sends its [`Handshake::local_eph_pubkey_message`], then
+///    passes the peer's to [`Handshake::got_eph_pubkey`], which returns the
+///    ciphers of the connection
+/// 2. over the encrypted connection, each peer sends its
+///    [`Authentication::local_auth_sig_message`], then passes the peer's to
+///    [`Authentication::got_auth_sig`], which authenticates the peer
+pub(crate) struct Handshake {
+    local_keypair: Keypair,
+    local_eph_secret: EphemeralSecret,
+    local_eph_pubkey: EphemeralPublic,
+}
+
+impl Handshake {
+    /// Start a handshake authenticated with the given key
+    pub(crate) fn new(local_keypair: Keypair) -> Self {
+        let local_eph_secret = EphemeralSecret::new(OsRng);
+        let local_eph_pubkey = EphemeralPublic::from(&local_eph_secret);
+
+        Handshake {
+            local_keypair,
+            local_eph_secret,
+            local_eph_pubkey,
+        }
+    }
+
+    /// Length-delimited message carrying the local ephemeral public key
+    pub(crate) fn local_eph_pubkey_message(&self) -> Vec<u8> {
+        let message = EphemeralPublicKey {
+            value: self.local_eph_pubkey.as_bytes().to_vec(),
+        };
+
+        let mut bytes = Vec::with_capacity(message.encoded_len() + 1);
+        message
+            .encode_length_delimited(&mut bytes)
+            .expect("buffer is large enough");
+        bytes
+    }
+
+    /// Derive the connection's ciphers and the challenge to sign from the
+    /// peer's ephemeral public key message (without its length prefix)
+    pub(crate) fn got_eph_pubkey(
+        self,
+        message: &[u8],
+    ) -> Result<(Authentication, Sender, Receiver), Error> {
+        let message = EphemeralPublicKey::decode(message).map_err(|e| Kind::Parse.context(e))?;
+        let remote_eph_pubkey: [u8; 32] = message.value.as_slice().try_into().map_err(|_| {
+            format_err!(
+                Kind::InvalidKey,
+                "ephemeral public key of {} bytes",
+                message.value.len()
+            )
+        })?;
+
+        let local_eph_pubkey = *self.local_eph_pubkey.as_bytes();
+
+        // A peer echoing our own key back would have us talk to ourselves
+        if local_eph_pubkey == remote_eph_pubkey {
+            fail!(Kind::Crypto, "peer sent our own ephemeral public key");
+        }
+
+        let shared_secret = self
+            .local_eph_secret
+            .diffie_hellman(&EphemeralPublic::from(remote_eph_pubkey));
+
+        // Low order points yield an all-zero secret, which the peer would know
+        // without knowing our ephemeral secret
+        if bool::from(shared_secret.as_bytes().ct_eq(&[0u8; 32])) {
+            fail!(Kind::Crypto, "low order ephemeral public key");
+        }
+
+        let loc_is_least = local_eph_pubkey < remote_eph_pubkey;
+        let (low, high) = if loc_is_least {
+            (&local_eph_pubkey, &remote_eph_pubkey)
+        } else {
+            (&remote_eph_pubkey, &local_eph_pubkey)
+        };
+
+        let mut transcript = Transcript::new(b"TENDERMINT_SECRET_CONNECTION_TRANSCRIPT_HASH");
+        transcript.append_message(b"EPHEMERAL_LOWER_PUBLIC_KEY", low);
+        transcript.append_message(b"EPHEMERAL_UPPER_PUBLIC_KEY", high);
+        transcript.append_message(b"DH_SECRET", shared_secret.as_bytes());
+
+        let mut challenge = [0u8; 32];
+        transcript.challenge_bytes(b"SECRET_CONNECTION_MAC", &mut challenge);
+
+        let kdf = Kdf::derive_secrets(shared_secret.as_bytes(), loc_is_least);
+        let local_signature = self.local_keypair.sign(&challenge);
+
+        let authentication = Authentication {
+            challenge,
+            local_auth_sig: AuthSigMessage {
+                pub_key: Some(RawPublicKey {
+                    sum: Some(Sum::Ed25519(self.local_keypair.public.to_bytes().to_vec())),
+                }),
+                sig: local_signature.to_bytes().to_vec(),
+            },
+        };
+
+        Ok((
+            authentication,
+            Sender::new(&kdf.send_secret),
+            Receiver::new(&kdf.recv_secret),
+        ))
+    }
+}
+
+/// Mutual authentication of the peers of a secret connection, over the
+/// encrypted connection
+pub(crate) struct Authentication {
+    challenge: [u8; 32],
+    local_auth_sig: AuthSigMessage,
+}
+
+impl Authentication {
+    /// Length-delimited message carrying the local public key and its
+    /// signature of the challenge
+    pub(crate) fn local_auth_sig_message(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.local_auth_sig.encoded_len() + 2);
+        self.local_auth_sig
+            .encode_length_delimited(&mut bytes)
+            .expect("buffer is large enough");
+        bytes
+    }
+
+    /// Check the peer's signature of the challenge from its authentication
+    /// message (without its length prefix), returning its public key
+    pub(crate) fn got_auth_sig(self, message: &[u8]) -> Result<Ed25519, Error> {
+        let message = AuthSigMessage::decode(message).map_err(|e| Kind::Parse.context(e))?;
+
+        let remote_pubkey = match message.pub_key.and_then(|pub_key| pub_key.sum) {
+            Some(Sum::Ed25519(bytes)) => Ed25519::from_bytes(&bytes)
+                .map_err(|_| format_err!(Kind::InvalidKey, "malformed peer public key"))?,
+            None => fail!(Kind::InvalidKey, "missing peer public key"),
+        };
+
+        let remote_signature = signature::Ed25519::try_from(message.sig.as_slice())
+            .map_err(|_| format_err!(Kind::SignatureInvalid, "malformed challenge signature"))?;
+
+        remote_pubkey
+            .verify(&self.challenge, &remote_signature)
+            .map_err(|_| format_err!(Kind::SignatureInvalid, "challenge verification failed"))?;
+
+        Ok(remote_pubkey)
+    }
+}
+
+/// Cipher of the frames of one direction of a secret connection, which
+/// takes a fresh nonce for each frame and, if enabled, rekeys after a given
+/// number of frames
+struct FrameCipher {
+    key: Zeroizing<[u8; 32]>,
+    cipher: ChaCha20Poly1305,
+    nonce: Nonce,
+    rekey_after: Option<u64>,
+}
+
+impl FrameCipher {
+    fn new(key: &[u8; 32], direction: Direction) -> Self {
+        FrameCipher {
+            key: Zeroizing::new(*key),
+            cipher: ChaCha20Poly1305::new(GenericArray::from_slice(key)),
+            nonce: Nonce::new(direction),
+            rekey_after: None,
+        }
+    }
+
+    /// Take the nonce of the next frame, rekeying first if the current key
+    /// sealed its share of frames
+    fn next_nonce(&mut self) -> Result<[u8; nonce::SIZE], Error> {
+        if let Some(frames) = self.rekey_after {
+            if self.nonce.count() >= frames {
+                *self.key = kdf::rekey(&self.key);
+                self.cipher = ChaCha20Poly1305::new(GenericArray::from_slice(&*self.key));
+                self.nonce.reset();
+            }
+        }
+
+        self.nonce
+            .next()
+            .map_err(|e| Kind::Crypto.context(e).into())
+    }
+}
+
+/// Seals the frames sent on a secret connection
+pub(crate) struct Sender(FrameCipher);
+
+impl Sender {
+    fn new(key: &[u8; 32]) -> Self {
+        Sender(FrameCipher::new(key, Direction::Send))
+    }
+
+    /// Rekey after sealing the given number of frames with a key
+    pub(crate) fn rekey_after(&mut self, frames: u64) {
+        self.0.rekey_after = Some(frames);
+    }
+
+    /// Seal a chunk of at most [`DATA_MAX_SIZE`] bytes into the next frame
+    pub(crate) fn seal(
+        &mut self,
+        chunk: &[u8],
+        sealed: &mut [u8; SEALED_FRAME_SIZE],
+    ) -> Result<(), Error> {
+        debug_assert!(chunk.len() <= DATA_MAX_SIZE);
+
+        let nonce = self.0.next_nonce()?;
+        let (frame, tag) = sealed.split_at_mut(TOTAL_FRAME_SIZE);
+        let (len, data) = frame.split_at_mut(DATA_LEN_SIZE);
+        len.copy_from_slice(&(chunk.len() as u32).to_le_bytes());
+        data[..chunk.len()].copy_from_slice(chunk);
+        for byte in &mut data[chunk.len()..] {
+            *byte = 0;
+        }
+
+        let computed_tag = self
+            .0
+            .cipher
+            .encrypt_in_place_detached(GenericArray::from_slice(&nonce), b"", frame)
+            .map_err(|_| format_err!(Kind::Crypto, "couldn't seal frame"))?;
+        tag.copy_from_slice(&computed_tag);
+
+        Ok(())
+    }
+}
+
+/// Opens the frames received on a secret connection
+pub(crate) struct Receiver(FrameCipher);
+
+impl Receiver {
+    fn new(key: &[u8; 32]) -> Self {
+        Receiver(FrameCipher::new(key, Direction::Receive))
+    }
+
+    /// Rekey after opening the given number of frames with a key
+    pub(crate) fn rekey_after(&mut self, frames: u64) {
+        self.0.rekey_after = Some(frames);
+    }
+
+    /// Open the next frame in place, returning its data. A frame failing
+    /// authentication still uses up its nonce: its connection can't go on.
+    pub(crate) fn open<'a>(
+        &mut self,
+        sealed: &'a mut [u8; SEALED_FRAME_SIZE],
+    ) -> Result<&'a [u8], Error> {
+        let nonce = self.0.next_nonce()?;
+        let (frame, tag) = sealed.split_at_mut(TOTAL_FRAME_SIZE);
+
+        self.0
+            .cipher
+            .decrypt_in_place_detached(
+                GenericArray::from_slice(&nonce),
+                b"",
+                frame,
+                GenericArray::from_slice(tag),
+            )
+            .map_err(|_| format_err!(Kind::Crypto, "frame authentication failed"))?;
+
+        let len = u32::from_le_bytes(frame[..DATA_LEN_SIZE].try_into().unwrap()) as usize;
+        if len > DATA_MAX_SIZE {
+            fail!(
+                Kind::Length,
+                "frame data of {} bytes exceeds maximum of {}",
+                len,
+                DATA_MAX_SIZE
+            );
+        }
+
+        Ok(&frame[DATA_LEN_SIZE..DATA_LEN_SIZE + len])
+    }
+}
+
+/// Read a sealed frame, returning `false` if the connection was closed
+/// before it started
+fn read_sealed_frame<R: Read>(
+    reader: &mut R,
+    sealed: &mut [u8; SEALED_FRAME_SIZE],
+) -> io::Result<bool> {
+    let mut read = 0;
+
+    while read < SEALED_FRAME_SIZE {
+        match reader.read(&mut sealed[read..]) {
+            Ok(0) if read == 0 => return Ok(false),
+            Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+            Ok(n) => read += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => (),
+            Err(e) => return Err(e),
+        }
+    }
+
+    Ok(true)
+}
+
+/// Unsigned varint length prefix of a handshake message, decoded byte by byte
+#[derive(Default)]
+struct LengthPrefix {
+    len: usize,
+    shift: u32,
+}
+
+impl LengthPrefix {
+    /// Add the next byte of the prefix, returning the length once complete
+    fn push(&mut self, byte: u8) -> Result<Option<usize>, Error> {
+        // Lengths are checked byte by byte, so the shift can't overflow
+        self.len |= usize::from(byte & 0x7f) << self.shift;
+        if self.len > MAX_HANDSHAKE_MESSAGE_SIZE || (self.shift > 7 && byte & 0x80 != 0) {
+            fail!(
+                Kind::Length,
+                "handshake message exceeds maximum of {} bytes",
+                MAX_HANDSHAKE_MESSAGE_SIZE
+            );
+        }
+
+        if byte & 0x80 == 0 {
+            Ok(Some(self.len))
+        } else {
+            self.shift += 7;
+            Ok(None)
+        }
+    }
+}
+
+/// Read a handshake message prefixed with its length as an unsigned varint
+fn read_length_delimited<R: Read>(reader: &mut R) -> Result<Vec<u8>, Error> {
+    let mut prefix = LengthPrefix::default();
+    let mut byte = [0u8];
+
+    let len = loop {
+        reader
+            .read_exact(&mut byte)
+            .map_err(|e| Kind::Io.context(e))?;
+
+        if let Some(len) = prefix.push(byte[0])? {
+            break len;
+        }
+    };
+
+    let mut message = vec![0u8; len];
+    reader
+        .read_exact(&mut message)
+        .map_err(|e| Kind::Io.context(e))?;
+
+    Ok(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{net::TcpListener, net::TcpStream, thread};
+
+    fn keypair() -> Keypair {
+        Keypair::generate(&mut OsRng)
+    }
+
+    #[test]
+    fn peers_authenticate_each_other() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let server_keypair = keypair();
+        let server_pubkey = server_keypair.public;
+        let client_keypair = keypair();
+        let client_pubkey = client_keypair.public;
+
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut conn = SecretConnection::new(stream, server_keypair).unwrap();
+            assert_eq!(conn.remote_pubkey(), client_pubkey);
+
+            let mut message = vec![0u8; 3000];
+            conn.read_exact(&mut message).unwrap();
+            conn.write_all(&message).unwrap();
+        });
+
+        let stream = TcpStream::connect(addr).unwrap();
+        let mut conn = SecretConnection::new(stream, client_keypair).unwrap();
+        assert_eq!(conn.remote_pubkey(), server_pubkey);
+        assert_eq!(conn.remote_peer_id(), node::Id::from(server_pubkey));
+
+        // Spans several frames
+        let message = (0..3000).map(|i| i as u8).collect::<Vec<_>>();
+        conn.write_all(&message).unwrap();
+
+        let mut echo = vec![0u8; 3000];
+        conn.read_exact(&mut echo).unwrap();
+        assert_eq!(echo, message);
+
+        server.join().unwrap();
+        assert_eq!(conn.read(&mut echo).unwrap(), 0);
+    }
+
+    /// Complete the handshake between two peers in memory
+    fn handshake() -> ((Sender, Receiver), (Sender, Receiver)) {
+        let (a, b) = (Handshake::new(keypair()), Handshake::new(keypair()));
+        let (a_message, b_message) = (a.local_eph_pubkey_message(), b.local_eph_pubkey_message());
+
+        let (a_auth, a_sender, a_receiver) = a.got_eph_pubkey(&unprefixed(&b_message)).unwrap();
+        let (b_auth, b_sender, b_receiver) = b.got_eph_pubkey(&unprefixed(&a_message)).unwrap();
+
+        let b_pubkey = b_auth.got_auth_sig(&unprefixed(&a_auth.local_auth_sig_message()));
+        assert!(b_pubkey.is_ok());
+
+        ((a_sender, a_receiver), (b_sender, b_receiver))
+    }
+
+    /// Strip the length prefix of a short message
+    fn unprefixed(message: &[u8]) -> Vec<u8> {
+        assert!(message[0] < 0x80);
+        message[1..].to_vec()
+    }
+
+    #[test]
+    fn frames_are_authenticated() {
+        let ((mut a_sender, _), (_, mut b_receiver)) = handshake();
+        let mut sealed = [0u8; SEALED_FRAME_SIZE];
+
+        a_sender.seal(b"hello", &mut sealed).unwrap();
+        let mut tampered = sealed;
+        tampered[7] ^= 1;
+        assert!(b_receiver.open(&mut tampered).is_err());
+        assert_eq!(b_receiver.open(&mut sealed).unwrap(), b"hello");
+
+        // Replayed frames don't match the next nonce
+        a_sender.seal(b"world", &mut sealed).unwrap();
+        let mut replayed = sealed;
+        assert_eq!(b_receiver.open(&mut sealed).unwrap(), b"world");
+        assert!(b_receiver.open(&mut replayed).is_err());
+    }
+
+    #[test]
+    fn rekeys_after_frames() {
+        let ((mut a_sender, _), (_, mut b_receiver)) = handshake();
+        a_sender.rekey_after(2);
+        b_receiver.rekey_after(2);
+
+        let mut sealed = [0u8; SEALED_FRAME_SIZE];
+        let mut frames = vec![];
+        for _ in 0..5 {
+            a_sender.seal(b"tx", &mut sealed).unwrap();
+            frames.push(sealed);
+            assert_eq!(b_receiver.open(&mut sealed).unwrap(), b"tx");
+        }
+
+        // Nonces start over with each key
+        assert_ne!(frames[0][..], frames[2][..]);
+        assert_ne!(frames[2][..], frames[4][..]);
+
+        // Peers must agree on rekeying
+        let ((mut a_sender, _), (_, mut b_receiver)) = handshake();
+        a_sender.rekey_after(1);
+        a_sender.seal(b"tx", &mut sealed).unwrap();
+        assert!(b_receiver.open(&mut sealed).is_ok());
+        a_sender.seal(b"tx", &mut sealed).unwrap();
+        assert!(b_receiver.open(&mut sealed).is_err());
+    }
+
+    #[test]
+    fn never_reuses_nonces() {
+        use std::error::Error as _;
+
+        let ((mut a_sender, _), _) = handshake();
+        a_sender.0.nonce = Nonce::starting_at(Direction::Send, u64::MAX);
+
+        let mut sealed = [0u8; SEALED_FRAME_SIZE];
+        a_sender.seal(b"tx", &mut sealed).unwrap();
+
+        for _ in 0..2 {
+            let error = a_sender.seal(b"tx", &mut sealed).unwrap_err();
+            assert_eq!(
+                error
+                    .source()
+                    .and_then(|e| e.downcast_ref::<NoncesExhausted>()),
+                Some(&NoncesExhausted(Direction::Send))
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_low_order_points() {
+        let message = EphemeralPublicKey { value: vec![0; 32] };
+        let mut bytes = vec![];
+        message.encode(&mut bytes).unwrap();
+
+        assert!(Handshake::new(keypair()).got_eph_pubkey(&bytes).is_err());
+    }
+
+    #[test]
+    fn rejects_forged_challenge_signatures() {
+        let (a, b) = (Handshake::new(keypair()), Handshake::new(keypair()));
+        let a_message = a.local_eph_pubkey_message();
+        let b_message = b.local_eph_pubkey_message();
+        let (a_auth, ..) = a.got_eph_pubkey(&unprefixed(&b_message)).unwrap();
+        let (b_auth, ..) = b.got_eph_pubkey(&unprefixed(&a_message)).unwrap();
+
+        // A signature by another key than the one claimed
+        let mut forged =
+            AuthSigMessage::decode(unprefixed(&a_auth.local_auth_sig_message()).as_slice())
+                .unwrap();
+        forged.sig = keypair().sign(&[0; 32]).to_bytes().to_vec();
+
+        let mut bytes = vec![];
+        forged.encode(&mut bytes).unwrap();
+        assert!(b_auth.got_auth_sig(&bytes).is_err());
+    }
+}
@@ -0,0 +1,413 @@
+//! Secret connections over asynchronous connections.
+//!
+//! These use the `futures` I/O traits, like the rest of this crate: Tokio
+//! streams can be adapted with `tokio-util`'s `compat` module.
+
+use super::{Handshake, LengthPrefix, Receiver, Sender, DATA_MAX_SIZE, SEALED_FRAME_SIZE};
+use crate::{error::Kind, node, public_key::Ed25519, Error};
+use ed25519_dalek::Keypair;
+use futures::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+    ready,
+};
+use std::{
+    cmp, io,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// Encrypted and authenticated asynchronous connection to a peer
+pub struct AsyncSecretConnection<IoHandler> {
+    io_handler: IoHandler,
+    reading: Reading,
+    writing: Writing,
+    remote_pubkey: Option<Ed25519>,
+}
+
+impl<IoHandler> AsyncSecretConnection<IoHandler>
+where
+    IoHandler: AsyncRead + AsyncWrite + Unpin,
+{
+    /// Perform the handshake over the given connection, authenticating with
+    /// the given key
+    pub async fn new(mut io_handler: IoHandler, local_keypair: Keypair) -> Result<Self, Error> {
+        let handshake = Handshake::new(local_keypair);
+
+        io_handler
+            .write_all(&handshake.local_eph_pubkey_message())
+            .await
+            .map_err(|e| Kind::Io.context(e))?;
+        io_handler.flush().await.map_err(|e| Kind::Io.context(e))?;
+
+        let remote_eph_pubkey = read_length_delimited(&mut io_handler).await?;
+        let (authentication, sender, receiver) = handshake.got_eph_pubkey(&remote_eph_pubkey)?;
+
+        let mut conn = AsyncSecretConnection {
+            io_handler,
+            reading: Reading::new(receiver),
+            writing: Writing::new(sender),
+            remote_pubkey: None,
+        };
+
+        conn.write_all(&authentication.local_auth_sig_message())
+            .await
+            .map_err(|e| Kind::Io.context(e))?;
+        conn.flush().await.map_err(|e| Kind::Io.context(e))?;
+
+        let remote_auth_sig = read_length_delimited(&mut conn).await?;
+        conn.remote_pubkey = Some(authentication.got_auth_sig(&remote_auth_sig)?);
+
+        Ok(conn)
+    }
+
+    /// Split the connection into halves which can be read from and written
+    /// to independently, e.g. from different tasks
+    pub fn split(self) -> (ReadHalf<IoHandler>, WriteHalf<IoHandler>) {
+        let remote_pubkey = self.remote_pubkey();
+        let (reader, writer) = self.io_handler.split();
+
+        let read_half = ReadHalf {
+            io_handler: reader,
+            reading: self.reading,
+            remote_pubkey,
+        };
+
+        let write_half = WriteHalf {
+            io_handler: writer,
+            writing: self.writing,
+            remote_pubkey,
+        };
+
+        (read_half, write_half)
+    }
+}
+
+impl<IoHandler> AsyncSecretConnection<IoHandler> {
+    /// Rekey each direction of the connection after the given number of
+    /// frames (see `SecretConnection::rekey_after`)
+    pub fn rekey_after(mut self, frames: u64) -> Self {
+        self.writing.sender.rekey_after(frames);
+        self.reading.receiver.rekey_after(frames);
+        self
+    }
+
+    /// Get the authenticated public key of the peer
+    pub fn remote_pubkey(&self) -> Ed25519 {
+        self.remote_pubkey
+            .expect("secret connection is authenticated")
+    }
+
+    /// Get the ID of the peer, derived from its public key
+    pub fn remote_peer_id(&self) -> node::Id {
+        self.remote_pubkey().into()
+    }
+}
+
+impl<IoHandler: AsyncRead + Unpin> AsyncRead for AsyncSecretConnection<IoHandler> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        data: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        this.reading.poll_read(cx, &mut this.io_handler, data)
+    }
+}
+
+impl<IoHandler: AsyncWrite + Unpin> AsyncWrite for AsyncSecretConnection<IoHandler> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        data: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        this.writing.poll_write(cx, &mut this.io_handler, data)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        this.writing.poll_flush(cx, &mut this.io_handler)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        this.writing.poll_close(cx, &mut this.io_handler)
+    }
+}
+
+/// Reading half of a split [`AsyncSecretConnection`]
+pub struct ReadHalf<IoHandler> {
+    io_handler: futures::io::ReadHalf<IoHandler>,
+    reading: Reading,
+    remote_pubkey: Ed25519,
+}
+
+impl<IoHandler> ReadHalf<IoHandler> {
+    /// Get the authenticated public key of the peer
+    pub fn remote_pubkey(&self) -> Ed25519 {
+        self.remote_pubkey
+    }
+}
+
+impl<IoHandler: AsyncRead> AsyncRead for ReadHalf<IoHandler> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        data: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        this.reading.poll_read(cx, &mut this.io_handler, data)
+    }
+}
+
+/// Writing half of a split [`AsyncSecretConnection`]
+pub struct WriteHalf<IoHandler> {
+    io_handler: futures::io::WriteHalf<IoHandler>,
+    writing: Writing,
+    remote_pubkey: Ed25519,
+}
+
+impl<IoHandler> WriteHalf<IoHandler> {
+    /// Get the authenticated public key of the peer
+    pub fn remote_pubkey(&self) -> Ed25519 {
+        self.remote_pubkey
+    }
+}
+
+impl<IoHandler: AsyncWrite> AsyncWrite for WriteHalf<IoHandler> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        data: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        this.writing.poll_write(cx, &mut this.io_handler, data)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        this.writing.poll_flush(cx, &mut this.io_handler)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        this.writing.poll_close(cx, &mut this.io_handler)
+    }
+}
+
+/// Reads and opens the frames received on a connection
+struct Reading {
+    receiver: Receiver,
+    sealed: [u8; SEALED_FRAME_SIZE],
+    sealed_len: usize,
+    recv_buffer: Vec<u8>,
+}
+
+impl Reading {
+    fn new(receiver: Receiver) -> Self {
+        Reading {
+            receiver,
+            sealed: [0u8; SEALED_FRAME_SIZE],
+            sealed_len: 0,
+            recv_buffer: vec![],
+        }
+    }
+
+    fn poll_read<R: AsyncRead + Unpin>(
+        &mut self,
+        cx: &mut Context<'_>,
+        io_handler: &mut R,
+        data: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        // Frames may be empty, which mustn't be taken for the end of the stream
+        while self.recv_buffer.is_empty() {
+            while self.sealed_len < SEALED_FRAME_SIZE {
+                let n =
+                    ready!(Pin::new(&mut *io_handler)
+                        .poll_read(cx, &mut self.sealed[self.sealed_len..]))?;
+
+                match n {
+                    0 if self.sealed_len == 0 => return Poll::Ready(Ok(0)),
+                    0 => return Poll::Ready(Err(io::ErrorKind::UnexpectedEof.into())),
+                    n => self.sealed_len += n,
+                }
+            }
+
+            self.sealed_len = 0;
+            let chunk = self
+                .receiver
+                .open(&mut self.sealed)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            self.recv_buffer.extend_from_slice(chunk);
+        }
+
+        let n = cmp::min(data.len(), self.recv_buffer.len());
+        data[..n].copy_from_slice(&self.recv_buffer[..n]);
+        self.recv_buffer.drain(..n);
+
+        Poll::Ready(Ok(n))
+    }
+}
+
+/// Seals and writes the frames sent on a connection, one at a time
+struct Writing {
+    sender: Sender,
+    sealed: [u8; SEALED_FRAME_SIZE],
+    /// Bytes of the sealed frame which are still to be written
+    pending: usize,
+}
+
+impl Writing {
+    fn new(sender: Sender) -> Self {
+        Writing {
+            sender,
+            sealed: [0u8; SEALED_FRAME_SIZE],
+            pending: 0,
+        }
+    }
+
+    /// Write the rest of the last sealed frame
+    fn poll_write_sealed<W: AsyncWrite + Unpin>(
+        &mut self,
+        cx: &mut Context<'_>,
+        io_handler: &mut W,
+    ) -> Poll<io::Result<()>> {
+        while self.pending > 0 {
+            let written = SEALED_FRAME_SIZE - self.pending;
+            let n = ready!(Pin::new(&mut *io_handler).poll_write(cx, &self.sealed[written..]))?;
+
+            if n == 0 {
+                return Poll::Ready(Err(io::ErrorKind::WriteZero.into()));
+            }
+
+            self.pending -= n;
+        }
+
+        Poll::Ready(Ok(()))
+    }
+
+    /// Seal the first chunk of the given data, once the previous frame is
+    /// written
+    fn poll_write<W: AsyncWrite + Unpin>(
+        &mut self,
+        cx: &mut Context<'_>,
+        io_handler: &mut W,
+        data: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        if data.is_empty() {
+            return Poll::Ready(Ok(0));
+        }
+
+        ready!(self.poll_write_sealed(cx, io_handler))?;
+
+        let chunk = &data[..cmp::min(data.len(), DATA_MAX_SIZE)];
+        self.sender
+            .seal(chunk, &mut self.sealed)
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+        self.pending = SEALED_FRAME_SIZE;
+
+        Poll::Ready(Ok(chunk.len()))
+    }
+
+    fn poll_flush<W: AsyncWrite + Unpin>(
+        &mut self,
+        cx: &mut Context<'_>,
+        io_handler: &mut W,
+    ) -> Poll<io::Result<()>> {
+        ready!(self.poll_write_sealed(cx, io_handler))?;
+        Pin::new(io_handler).poll_flush(cx)
+    }
+
+    fn poll_close<W: AsyncWrite + Unpin>(
+        &mut self,
+        cx: &mut Context<'_>,
+        io_handler: &mut W,
+    ) -> Poll<io::Result<()>> {
+        ready!(self.poll_write_sealed(cx, io_handler))?;
+        Pin::new(io_handler).poll_close(cx)
+    }
+}
+
+/// Read a handshake message prefixed with its length as an unsigned varint
+async fn read_length_delimited<R: AsyncRead + Unpin>(reader: &mut R) -> Result<Vec<u8>, Error> {
+    let mut prefix = LengthPrefix::default();
+    let mut byte = [0u8];
+
+    let len = loop {
+        reader
+            .read_exact(&mut byte)
+            .await
+            .map_err(|e| Kind::Io.context(e))?;
+
+        if let Some(len) = prefix.push(byte[0])? {
+            break len;
+        }
+    };
+
+    let mut message = vec![0u8; len];
+    reader
+        .read_exact(&mut message)
+        .await
+        .map_err(|e| Kind::Io.context(e))?;
+
+    Ok(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::SecretConnection;
+    use super::*;
+    use futures::{executor::block_on, io::AllowStdIo};
+    use rand_core::OsRng;
+    use std::{
+        io::{Read, Write},
+        net::{TcpListener, TcpStream},
+        thread,
+    };
+
+    #[test]
+    fn interoperates_with_blocking_connections() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let server_keypair = Keypair::generate(&mut OsRng);
+        let server_pubkey = server_keypair.public;
+        let client_keypair = Keypair::generate(&mut OsRng);
+        let client_pubkey = client_keypair.public;
+
+        // Echo server serving each half of the connection from its own task
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+
+            block_on(async {
+                let conn = AsyncSecretConnection::new(AllowStdIo::new(stream), server_keypair)
+                    .await
+                    .unwrap();
+                assert_eq!(conn.remote_pubkey(), client_pubkey);
+
+                let (mut reader, mut writer) = conn.split();
+                assert_eq!(writer.remote_pubkey(), client_pubkey);
+
+                let mut message = vec![0u8; 3000];
+                reader.read_exact(&mut message).await.unwrap();
+                writer.write_all(&message).await.unwrap();
+                writer.close().await.unwrap();
+            })
+        });
+
+        let stream = TcpStream::connect(addr).unwrap();
+        let mut conn = SecretConnection::new(stream, client_keypair).unwrap();
+        assert_eq!(conn.remote_pubkey(), server_pubkey);
+
+        // Spans several frames
+        let message = (0..3000).map(|i| i as u8).collect::<Vec<_>>();
+        conn.write_all(&message).unwrap();
+
+        let mut echo = vec![0u8; 3000];
+        conn.read_exact(&mut echo).unwrap();
+        assert_eq!(echo, message);
+
+        server.join().unwrap();
+        assert_eq!(conn.read(&mut echo).unwrap(), 0);
+    }
+}
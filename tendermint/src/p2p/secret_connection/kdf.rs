@@ -0,0 +1,108 @@
+//! Derivation of a secret connection's keys from its Diffie-Hellman secret
+
+use hkdf::Hkdf;
+use sha2::Sha256;
+use zeroize::Zeroize;
+
+/// HKDF info string of Tendermint's key derivation
+const INFO: &[u8] = b"TENDERMINT_SECRET_CONNECTION_KEY_AND_CHALLENGE_GEN";
+
+/// HKDF info string of the derivation of a key from the previous one when
+/// rekeying
+const REKEY_INFO: &[u8] = b"TENDERMINT_SECRET_CONNECTION_REKEY";
+
+/// Keys of the frames received and sent on a secret connection
+#[derive(Zeroize)]
+#[zeroize(drop)]
+pub(super) struct Kdf {
+    /// Key of the frames received from the peer
+    pub(super) recv_secret: [u8; 32],
+
+    /// Key of the frames sent to the peer
+    pub(super) send_secret: [u8; 32],
+}
+
+impl Kdf {
+    /// Derive the keys from the Diffie-Hellman secret: the peer whose
+    /// ephemeral public key sorts lowest receives with the first key and
+    /// sends with the second one.
+    ///
+    /// Like Tendermint 0.34, only 64 of the 96 bytes Tendermint 0.33 derived
+    /// are used: the challenge comes from the handshake's transcript instead.
+    pub(super) fn derive_secrets(shared_secret: &[u8; 32], loc_is_least: bool) -> Self {
+        let mut key_material = [0u8; 96];
+        Hkdf::<Sha256>::new(None, shared_secret)
+            .expand(INFO, &mut key_material)
+            .expect("96 bytes is a valid HKDF-SHA256 output length");
+
+        let mut first = [0u8; 32];
+        let mut second = [0u8; 32];
+        first.copy_from_slice(&key_material[..32]);
+        second.copy_from_slice(&key_material[32..64]);
+        key_material.zeroize();
+
+        if loc_is_least {
+            Kdf {
+                recv_secret: first,
+                send_secret: second,
+            }
+        } else {
+            Kdf {
+                recv_secret: second,
+                send_secret: first,
+            }
+        }
+    }
+}
+
+/// Derive the next key of one direction of a connection from its current
+/// one. Both peers derive the same key, and the current one can't be
+/// recovered from it.
+pub(super) fn rekey(key: &[u8; 32]) -> [u8; 32] {
+    let mut next = [0u8; 32];
+    Hkdf::<Sha256>::new(None, key)
+        .expand(REKEY_INFO, &mut next)
+        .expect("32 bytes is a valid HKDF-SHA256 output length");
+    next
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use subtle_encoding::hex;
+
+    #[test]
+    fn peers_derive_matching_keys() {
+        let shared_secret = [0x42; 32];
+        let least = Kdf::derive_secrets(&shared_secret, true);
+        let other = Kdf::derive_secrets(&shared_secret, false);
+
+        assert_eq!(least.send_secret, other.recv_secret);
+        assert_eq!(least.recv_secret, other.send_secret);
+        assert_ne!(least.send_secret, least.recv_secret);
+
+        let next = rekey(&least.send_secret);
+        assert_eq!(next, rekey(&other.recv_secret));
+        assert_ne!(next, least.send_secret);
+    }
+
+    /// Keys derived by Tendermint's `deriveSecrets`: each line holds the
+    /// shared secret, `locIsLeast`, the receive and send keys, and the
+    /// challenge Tendermint 0.33 derived
+    #[test]
+    fn derives_tendermint_keys() {
+        let golden = include_str!("../../../tests/support/TestDeriveSecretsAndChallenge.golden");
+
+        for line in golden.lines() {
+            let fields = line.split(',').collect::<Vec<_>>();
+            let decode = |field: &str| hex::decode(field).unwrap();
+
+            let mut shared_secret = [0u8; 32];
+            shared_secret.copy_from_slice(&decode(fields[0]));
+            let kdf = Kdf::derive_secrets(&shared_secret, fields[1] == "true");
+
+            assert_eq!(kdf.recv_secret.to_vec(), decode(fields[2]));
+            assert_eq!(kdf.send_secret.to_vec(), decode(fields[3]));
+        }
+    }
+}
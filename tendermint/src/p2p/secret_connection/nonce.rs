@@ -0,0 +1,119 @@
+//! Nonces of the frames sent or received on a secret connection
+
+use std::fmt::{self, Display};
+use thiserror::Error;
+
+/// Size of a nonce
+pub(super) const SIZE: usize = 12;
+
+/// Direction of the frames of a secret connection
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Direction {
+    /// Frames we send
+    Send,
+
+    /// Frames we receive
+    Receive,
+}
+
+impl Display for Direction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Direction::Send => write!(f, "sent"),
+            Direction::Receive => write!(f, "received"),
+        }
+    }
+}
+
+/// The nonces of the frames of one direction of a secret connection ran out.
+///
+/// Frames can't be sealed or opened without reusing a nonce, which would
+/// break the encryption: the connection must be closed, unless it rekeys
+/// (see `SecretConnection::rekey_after`).
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Error)]
+#[error("nonces of {0} frames exhausted")]
+pub struct NoncesExhausted(pub Direction);
+
+/// Counter of the nonces of one direction of a connection: like Tendermint's,
+/// the first four bytes of a nonce are zero and its last eight count the
+/// frames before it, in little endian
+pub(super) struct Nonce {
+    direction: Direction,
+    /// Counter of the next nonce, if any is left
+    next: Option<u64>,
+}
+
+impl Nonce {
+    /// Start counting the nonces of the given direction
+    pub(super) fn new(direction: Direction) -> Self {
+        Nonce {
+            direction,
+            next: Some(0),
+        }
+    }
+
+    /// Start counting at the given counter, e.g. to test the exhaustion of
+    /// nonces
+    #[cfg(test)]
+    pub(super) fn starting_at(direction: Direction, counter: u64) -> Self {
+        Nonce {
+            direction,
+            next: Some(counter),
+        }
+    }
+
+    /// Take the nonce of the next frame. Each nonce is only returned once:
+    /// once they run out, every call fails.
+    pub(super) fn next(&mut self) -> Result<[u8; SIZE], NoncesExhausted> {
+        let counter = self.next.ok_or(NoncesExhausted(self.direction))?;
+        self.next = counter.checked_add(1);
+
+        let mut nonce = [0u8; SIZE];
+        nonce[4..].copy_from_slice(&counter.to_le_bytes());
+        Ok(nonce)
+    }
+
+    /// Number of nonces taken so far, saturating once they run out
+    pub(super) fn count(&self) -> u64 {
+        self.next.unwrap_or(u64::MAX)
+    }
+
+    /// Count from zero again, e.g. with a new key
+    pub(super) fn reset(&mut self) {
+        self.next = Some(0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_in_little_endian() {
+        let mut nonce = Nonce::new(Direction::Send);
+        assert_eq!(nonce.next().unwrap(), [0; SIZE]);
+
+        for _ in 1..0x102 {
+            nonce.next().unwrap();
+        }
+        assert_eq!(nonce.count(), 0x102);
+        assert_eq!(nonce.next().unwrap(), [0, 0, 0, 0, 2, 1, 0, 0, 0, 0, 0, 0]);
+
+        nonce.reset();
+        assert_eq!(nonce.next().unwrap(), [0; SIZE]);
+    }
+
+    #[test]
+    fn never_reuses_nonces() {
+        let mut nonce = Nonce::starting_at(Direction::Receive, u64::MAX);
+        assert_eq!(
+            nonce.next().unwrap(),
+            [0, 0, 0, 0, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]
+        );
+
+        for _ in 0..2 {
+            assert_eq!(nonce.next(), Err(NoncesExhausted(Direction::Receive)));
+        }
+        assert_eq!(nonce.count(), u64::MAX);
+    }
+}
@@ -0,0 +1,186 @@
+//! Transports: how peers (and remote signers) open connections to each
+//! other, independently of the protocols spoken over them.
+//!
+//! A [`Transport`] dials addresses and listens for connections. Connections
+//! can be upgraded (e.g. with the secret connection handshake) by an
+//! [`Upgrade`], which [`Upgraded`] applies to every connection of a
+//! transport. [`TcpTransport`] opens TCP connections, and [`MemoryNetwork`]
+//! connects peers in memory, e.g. to test the protocols spoken over
+//! connections without sockets.
+//!
+//! Transports make blocking connections, so that the handshake and
+//! multiplexed connections can be driven over them. A transport dialing a
+//! remote signer serves as a `privval::client::Connector` through
+//! [`Dialer`].
+
+mod memory;
+mod tcp;
+
+pub use self::{
+    memory::{MemoryConnection, MemoryListener, MemoryNetwork},
+    tcp::{TcpListener, TcpTransport},
+};
+
+use crate::{privval::client::Connector, Error};
+use std::io::{self, Read, Write};
+
+/// Opens connections to peers, and listens for theirs
+pub trait Transport {
+    /// Addresses of peers
+    type Address;
+
+    /// Connections to peers
+    type Connection: Read + Write;
+
+    /// Listens for connections from peers
+    type Listener: Listener<Connection = Self::Connection>;
+
+    /// Connect to the peer at the given address
+    fn dial(&mut self, address: &Self::Address) -> Result<Self::Connection, Error>;
+
+    /// Listen for connections at the given address
+    fn listen(&mut self, address: &Self::Address) -> Result<Self::Listener, Error>;
+}
+
+/// Listens for connections from peers
+pub trait Listener {
+    /// Connections from peers
+    type Connection: Read + Write;
+
+    /// Wait for a peer to connect
+    fn accept(&mut self) -> Result<Self::Connection, Error>;
+}
+
+/// Upgrades connections, e.g. by authenticating and encrypting them
+pub trait Upgrade<Connection> {
+    /// Upgraded connection
+    type Output: Read + Write;
+
+    /// Upgrade the given connection
+    fn upgrade(&mut self, connection: Connection) -> Result<Self::Output, Error>;
+}
+
+impl<F, C, O> Upgrade<C> for F
+where
+    F: FnMut(C) -> Result<O, Error>,
+    O: Read + Write,
+{
+    type Output = O;
+
+    fn upgrade(&mut self, connection: C) -> Result<O, Error> {
+        self(connection)
+    }
+}
+
+/// Transport upgrading its connections, both dialed and accepted
+#[derive(Clone, Debug)]
+pub struct Upgraded<T, U> {
+    transport: T,
+    upgrade: U,
+}
+
+impl<T, U> Upgraded<T, U> {
+    /// Upgrade the connections of the given transport
+    pub fn new(transport: T, upgrade: U) -> Self {
+        Upgraded { transport, upgrade }
+    }
+}
+
+impl<T, U> Transport for Upgraded<T, U>
+where
+    T: Transport,
+    U: Upgrade<T::Connection> + Clone,
+{
+    type Address = T::Address;
+    type Connection = U::Output;
+    type Listener = UpgradedListener<T::Listener, U>;
+
+    fn dial(&mut self, address: &Self::Address) -> Result<Self::Connection, Error> {
+        let connection = self.transport.dial(address)?;
+        self.upgrade.upgrade(connection)
+    }
+
+    fn listen(&mut self, address: &Self::Address) -> Result<Self::Listener, Error> {
+        Ok(UpgradedListener {
+            listener: self.transport.listen(address)?,
+            upgrade: self.upgrade.clone(),
+        })
+    }
+}
+
+/// Listener upgrading the connections it accepts
+#[derive(Debug)]
+pub struct UpgradedListener<L, U> {
+    listener: L,
+    upgrade: U,
+}
+
+impl<L, U> Listener for UpgradedListener<L, U>
+where
+    L: Listener,
+    U: Upgrade<L::Connection>,
+{
+    type Connection = U::Output;
+
+    fn accept(&mut self) -> Result<Self::Connection, Error> {
+        let connection = self.listener.accept()?;
+        self.upgrade.upgrade(connection)
+    }
+}
+
+/// Upgrades connections with the secret connection handshake, authenticating
+/// with the given key
+#[cfg(feature = "secret-connection")]
+#[cfg_attr(docsrs, doc(cfg(feature = "secret-connection")))]
+pub struct SecretConnectionUpgrade {
+    keypair: ed25519_dalek::Keypair,
+}
+
+#[cfg(feature = "secret-connection")]
+impl SecretConnectionUpgrade {
+    /// Authenticate connections with the given key, e.g. a node key's
+    /// `ed25519_keypair`
+    pub fn new(keypair: ed25519_dalek::Keypair) -> Self {
+        SecretConnectionUpgrade { keypair }
+    }
+}
+
+#[cfg(feature = "secret-connection")]
+impl Clone for SecretConnectionUpgrade {
+    fn clone(&self) -> Self {
+        Self::new(
+            ed25519_dalek::Keypair::from_bytes(&self.keypair.to_bytes())
+                .expect("Ed25519 keypair round trips"),
+        )
+    }
+}
+
+#[cfg(feature = "secret-connection")]
+impl<C: Read + Write> Upgrade<C> for SecretConnectionUpgrade {
+    type Output = super::secret_connection::SecretConnection<C>;
+
+    fn upgrade(&mut self, connection: C) -> Result<Self::Output, Error> {
+        super::secret_connection::SecretConnection::new(connection, self.clone().keypair)
+    }
+}
+
+/// Dials a single address with a transport, e.g. a validator's
+/// `priv_validator_laddr` for a remote signer client
+#[derive(Clone, Debug)]
+pub struct Dialer<T: Transport> {
+    /// Transport connections are made with
+    pub transport: T,
+
+    /// Address to dial
+    pub address: T::Address,
+}
+
+impl<T: Transport> Connector for Dialer<T> {
+    type Stream = T::Connection;
+
+    fn connect(&mut self) -> io::Result<Self::Stream> {
+        self.transport
+            .dial(&self.address)
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))
+    }
+}
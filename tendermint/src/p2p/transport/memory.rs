@@ -0,0 +1,213 @@
+//! In-memory transport
+
+use super::{Listener, Transport};
+use crate::error::{Error, Kind};
+use anomaly::{fail, format_err};
+use std::{
+    cmp,
+    collections::HashMap,
+    io::{self, Read, Write},
+    sync::{
+        mpsc::{channel, Receiver, Sender},
+        Arc, Mutex,
+    },
+};
+
+/// Network of peers connected in memory, listening on and dialing addresses
+/// which are plain names.
+///
+/// Cloning a network yields a handle to the same network.
+#[derive(Clone, Debug, Default)]
+pub struct MemoryNetwork {
+    listeners: Arc<Mutex<HashMap<String, Sender<MemoryConnection>>>>,
+}
+
+impl MemoryNetwork {
+    /// Create a new network, with nobody listening on it
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Transport for MemoryNetwork {
+    type Address = String;
+    type Connection = MemoryConnection;
+    type Listener = MemoryListener;
+
+    fn dial(&mut self, address: &String) -> Result<MemoryConnection, Error> {
+        let listeners = self.listeners.lock().expect("poisoned lock");
+        let listener = listeners
+            .get(address)
+            .ok_or_else(|| format_err!(Kind::Io, "nobody listens on {}", address))?;
+
+        let (local, remote) = MemoryConnection::pair();
+        listener
+            .send(remote)
+            .map_err(|_| format_err!(Kind::Io, "listener of {} is gone", address))?;
+
+        Ok(local)
+    }
+
+    fn listen(&mut self, address: &String) -> Result<MemoryListener, Error> {
+        let mut listeners = self.listeners.lock().expect("poisoned lock");
+        if listeners.contains_key(address) {
+            fail!(Kind::Io, "{} is already listened on", address);
+        }
+
+        let (sender, connections) = channel();
+        listeners.insert(address.clone(), sender);
+
+        Ok(MemoryListener {
+            network: self.clone(),
+            address: address.clone(),
+            connections,
+        })
+    }
+}
+
+/// Listens for connections on a [`MemoryNetwork`]. The address is freed once
+/// the listener is dropped.
+#[derive(Debug)]
+pub struct MemoryListener {
+    network: MemoryNetwork,
+    address: String,
+    connections: Receiver<MemoryConnection>,
+}
+
+impl Listener for MemoryListener {
+    type Connection = MemoryConnection;
+
+    fn accept(&mut self) -> Result<MemoryConnection, Error> {
+        self.connections
+            .recv()
+            .map_err(|e| Kind::Io.context(e).into())
+    }
+}
+
+impl Drop for MemoryListener {
+    fn drop(&mut self) {
+        if let Ok(mut listeners) = self.network.listeners.lock() {
+            listeners.remove(&self.address);
+        }
+    }
+}
+
+/// End of an in-memory connection. Reads block until the other end writes;
+/// once the other end is dropped, reads reach the end of the stream and
+/// writes fail.
+#[derive(Debug)]
+pub struct MemoryConnection {
+    incoming: Receiver<Vec<u8>>,
+    outgoing: Sender<Vec<u8>>,
+    buffer: Vec<u8>,
+}
+
+impl MemoryConnection {
+    /// Create both ends of a connection
+    pub fn pair() -> (Self, Self) {
+        let (a_outgoing, b_incoming) = channel();
+        let (b_outgoing, a_incoming) = channel();
+
+        let a = MemoryConnection {
+            incoming: a_incoming,
+            outgoing: a_outgoing,
+            buffer: vec![],
+        };
+
+        let b = MemoryConnection {
+            incoming: b_incoming,
+            outgoing: b_outgoing,
+            buffer: vec![],
+        };
+
+        (a, b)
+    }
+}
+
+impl Read for MemoryConnection {
+    fn read(&mut self, data: &mut [u8]) -> io::Result<usize> {
+        while self.buffer.is_empty() {
+            match self.incoming.recv() {
+                Ok(chunk) => self.buffer = chunk,
+                // The other end hung up
+                Err(_) => return Ok(0),
+            }
+        }
+
+        let n = cmp::min(data.len(), self.buffer.len());
+        data[..n].copy_from_slice(&self.buffer[..n]);
+        self.buffer.drain(..n);
+
+        Ok(n)
+    }
+}
+
+impl Write for MemoryConnection {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        self.outgoing
+            .send(data.to_vec())
+            .map_err(|_| io::Error::from(io::ErrorKind::BrokenPipe))?;
+
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        channel,
+        p2p::mconnection::{ChannelDescriptor, MConnection, Received},
+    };
+    use std::thread;
+
+    #[test]
+    fn dials_listeners() {
+        let mut network = MemoryNetwork::new();
+        let address = "validator".to_owned();
+        let mut listener = network.listen(&address).unwrap();
+        assert!(network.listen(&address).is_err());
+
+        let mut local = network.dial(&address).unwrap();
+        let mut remote = listener.accept().unwrap();
+
+        local.write_all(b"ping").unwrap();
+        let mut message = [0u8; 4];
+        remote.read_exact(&mut message).unwrap();
+        assert_eq!(&message, b"ping");
+
+        drop(remote);
+        assert_eq!(local.read(&mut message).unwrap(), 0);
+        assert!(local.write_all(b"ping").is_err());
+
+        drop(listener);
+        assert!(network.dial(&address).is_err());
+    }
+
+    #[test]
+    fn multiplexes_connections() {
+        let (local, remote) = MemoryConnection::pair();
+        let channels = [ChannelDescriptor::new(channel::Id(0x30), 1)];
+
+        let peer = thread::spawn(move || {
+            let mut mconn = MConnection::new(remote, &channels).unwrap();
+            mconn.recv().unwrap()
+        });
+
+        let mut mconn = MConnection::new(local, &channels).unwrap();
+        mconn.try_send(channel::Id(0x30), b"tx".to_vec()).unwrap();
+        mconn.flush().unwrap();
+
+        assert_eq!(
+            peer.join().unwrap(),
+            Received::Message {
+                channel_id: channel::Id(0x30),
+                data: b"tx".to_vec()
+            }
+        );
+    }
+}
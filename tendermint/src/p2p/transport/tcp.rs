@@ -0,0 +1,122 @@
+//! TCP transport
+
+use super::{Listener, Transport};
+use crate::{
+    error::{Error, Kind},
+    net,
+};
+use anomaly::{fail, format_err};
+use std::{
+    net::{SocketAddr, TcpStream},
+    time::Duration,
+};
+
+/// Opens TCP connections to `tcp://` addresses
+#[derive(Clone, Debug, Default)]
+pub struct TcpTransport {
+    /// Read and write timeout of connections, none by default
+    pub timeout: Option<Duration>,
+}
+
+impl TcpTransport {
+    fn configure(&self, stream: &TcpStream) -> Result<(), Error> {
+        stream
+            .set_read_timeout(self.timeout)
+            .and_then(|_| stream.set_write_timeout(self.timeout))
+            .and_then(|_| stream.set_nodelay(true))
+            .map_err(|e| Kind::Io.context(e).into())
+    }
+}
+
+impl Transport for TcpTransport {
+    type Address = net::Address;
+    type Connection = TcpStream;
+    type Listener = TcpListener;
+
+    fn dial(&mut self, address: &net::Address) -> Result<TcpStream, Error> {
+        let (host, port) = host_and_port(address)?;
+        let stream = TcpStream::connect((host, port))
+            .map_err(|e| format_err!(Kind::Io, "couldn't connect to {}: {}", address, e))?;
+
+        self.configure(&stream)?;
+        Ok(stream)
+    }
+
+    fn listen(&mut self, address: &net::Address) -> Result<TcpListener, Error> {
+        let (host, port) = host_and_port(address)?;
+        let listener = std::net::TcpListener::bind((host, port))
+            .map_err(|e| format_err!(Kind::Io, "couldn't bind {}: {}", address, e))?;
+
+        Ok(TcpListener {
+            listener,
+            transport: self.clone(),
+        })
+    }
+}
+
+/// Listens for TCP connections
+#[derive(Debug)]
+pub struct TcpListener {
+    listener: std::net::TcpListener,
+    transport: TcpTransport,
+}
+
+impl TcpListener {
+    /// Get the address the listener is bound to, e.g. to find out which port
+    /// was picked when listening on port 0
+    pub fn local_addr(&self) -> Result<SocketAddr, Error> {
+        self.listener
+            .local_addr()
+            .map_err(|e| Kind::Io.context(e).into())
+    }
+}
+
+impl Listener for TcpListener {
+    type Connection = TcpStream;
+
+    fn accept(&mut self) -> Result<TcpStream, Error> {
+        let (stream, _) = self.listener.accept().map_err(|e| Kind::Io.context(e))?;
+        self.transport.configure(&stream)?;
+        Ok(stream)
+    }
+}
+
+fn host_and_port(address: &net::Address) -> Result<(&str, u16), Error> {
+    match address {
+        net::Address::Tcp { host, port, .. } => Ok((host, *port)),
+        net::Address::Unix { .. } => fail!(Kind::Protocol, "{} isn't a TCP address", address),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        io::{Read, Write},
+        thread,
+    };
+
+    #[test]
+    fn dials_listeners() {
+        let mut transport = TcpTransport::default();
+        let mut listener = transport
+            .listen(&"tcp://127.0.0.1:0".parse().unwrap())
+            .unwrap();
+        let address = format!("tcp://{}", listener.local_addr().unwrap());
+
+        let peer = thread::spawn(move || {
+            let mut connection = listener.accept().unwrap();
+            connection.write_all(b"hello").unwrap();
+        });
+
+        let mut connection = transport.dial(&address.parse().unwrap()).unwrap();
+        let mut message = vec![];
+        connection.read_to_end(&mut message).unwrap();
+        assert_eq!(message, b"hello");
+        peer.join().unwrap();
+
+        assert!(transport
+            .dial(&"unix:///tmp/tendermint.sock".parse().unwrap())
+            .is_err());
+    }
+}
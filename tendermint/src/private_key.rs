@@ -2,11 +2,32 @@
 
 pub use ed25519_dalek::{Keypair as Ed25519, EXPANDED_SECRET_KEY_LENGTH as ED25519_KEYPAIR_SIZE};
 
-use crate::public_key::PublicKey;
+#[cfg(feature = "secp256k1")]
+pub use k256::SecretKey as Secp256k1;
+
+#[cfg(feature = "keystore")]
+mod keystore;
+
+use crate::{
+    error::{Error, Kind},
+    public_key::{Algorithm, PublicKey},
+};
+use anomaly::{fail, format_err};
+#[cfg(feature = "keygen")]
+use rand_core::{CryptoRng, RngCore};
 use serde::{de, ser, Deserialize, Serialize};
-use subtle_encoding::{Base64, Encoding};
+#[cfg(feature = "keystore")]
+use std::{fs, io::Write, path::Path};
+use subtle_encoding::{base64, hex, Base64, Encoding};
 use zeroize::Zeroizing;
 
+/// Size of an Ed25519 secret key seed
+pub const ED25519_SEED_SIZE: usize = ed25519_dalek::SECRET_KEY_LENGTH;
+
+/// Size of a secp256k1 secret key
+#[cfg(feature = "secp256k1")]
+pub const SECP256K1_SECRET_KEY_SIZE: usize = 32;
+
 /// Private keys as parsed from configuration files
 #[derive(Serialize, Deserialize)]
 #[non_exhaustive]
@@ -19,22 +40,258 @@ pub enum PrivateKey {
         deserialize_with = "deserialize_ed25519_keypair"
     )]
     Ed25519(Ed25519),
+
+    /// ECDSA/secp256k1 keys
+    #[cfg(feature = "secp256k1")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "secp256k1")))]
+    #[serde(
+        rename = "tendermint/PrivKeySecp256k1",
+        serialize_with = "serialize_secp256k1_secret_key",
+        deserialize_with = "deserialize_secp256k1_secret_key"
+    )]
+    Secp256k1(Secp256k1),
 }
 
 impl PrivateKey {
+    /// Generate a new random private key for the given algorithm
+    #[cfg(feature = "keygen")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "keygen")))]
+    pub fn generate<R>(algorithm: Algorithm, rng: &mut R) -> Result<Self, Error>
+    where
+        R: CryptoRng + RngCore,
+    {
+        let mut bytes = Zeroizing::new([0u8; 32]);
+
+        match algorithm {
+            Algorithm::Ed25519 => {
+                rng.fill_bytes(&mut *bytes);
+                Self::from_raw_ed25519(&*bytes)
+            }
+            #[cfg(feature = "secp256k1")]
+            Algorithm::Secp256k1 => loop {
+                // Retry in the (astronomically unlikely) event the random
+                // bytes aren't a valid scalar
+                rng.fill_bytes(&mut *bytes);
+                if let Ok(private_key) = Self::from_raw_secp256k1(&*bytes) {
+                    return Ok(private_key);
+                }
+            },
+            other => fail!(
+                Kind::InvalidKey,
+                "unsupported key generation algorithm: {}",
+                other
+            ),
+        }
+    }
+
+    /// Parse a private key of the given algorithm from its raw bytes
+    /// (see [`PrivateKey::from_raw_ed25519`] and
+    /// [`PrivateKey::from_raw_secp256k1`])
+    pub fn from_raw(algorithm: Algorithm, bytes: &[u8]) -> Result<Self, Error> {
+        match algorithm {
+            Algorithm::Ed25519 => Self::from_raw_ed25519(bytes),
+            #[cfg(feature = "secp256k1")]
+            Algorithm::Secp256k1 => Self::from_raw_secp256k1(bytes),
+            other => fail!(
+                Kind::InvalidKey,
+                "unsupported private key algorithm: {}",
+                other
+            ),
+        }
+    }
+
+    /// Parse an Ed25519 private key from either a 32-byte seed or a 64-byte
+    /// keypair (seed followed by the public key, as used by Tendermint).
+    ///
+    /// The public key is always derived from the seed, and a keypair whose
+    /// public half doesn't match it is rejected.
+    pub fn from_raw_ed25519(bytes: &[u8]) -> Result<Self, Error> {
+        if bytes.len() != ED25519_SEED_SIZE && bytes.len() != ED25519_KEYPAIR_SIZE {
+            fail!(
+                Kind::Length,
+                "invalid Ed25519 private key size: {}",
+                bytes.len()
+            );
+        }
+
+        let secret = ed25519_dalek::SecretKey::from_bytes(&bytes[..ED25519_SEED_SIZE])
+            .map_err(|_| format_err!(Kind::InvalidKey, "malformed Ed25519 seed"))?;
+        let public = ed25519_dalek::PublicKey::from(&secret);
+
+        if bytes.len() == ED25519_KEYPAIR_SIZE
+            && bytes[ED25519_SEED_SIZE..] != public.as_bytes()[..]
+        {
+            fail!(
+                Kind::InvalidKey,
+                "Ed25519 public key doesn't match the secret key"
+            );
+        }
+
+        Ok(PrivateKey::Ed25519(Ed25519 { secret, public }))
+    }
+
+    /// Parse a secp256k1 private key from its raw 32-byte secret scalar
+    #[cfg(feature = "secp256k1")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "secp256k1")))]
+    pub fn from_raw_secp256k1(bytes: &[u8]) -> Result<Self, Error> {
+        if bytes.len() != SECP256K1_SECRET_KEY_SIZE {
+            fail!(
+                Kind::Length,
+                "invalid secp256k1 private key size: {}",
+                bytes.len()
+            );
+        }
+
+        let secret_key = Secp256k1::from_bytes(bytes)
+            .map_err(|_| format_err!(Kind::InvalidKey, "malformed secp256k1 secret key"))?;
+
+        // Reject scalars with no corresponding public key (zero or >= n)
+        k256::PublicKey::from_secret_key(&secret_key, true)
+            .map_err(|_| format_err!(Kind::InvalidKey, "invalid secp256k1 secret key"))?;
+
+        Ok(PrivateKey::Secp256k1(secret_key))
+    }
+
+    /// Parse a private key of the given algorithm from the base64 encoding
+    /// of its raw bytes, as it appears in `priv_validator_key.json`
+    pub fn from_base64(algorithm: Algorithm, encoded: &str) -> Result<Self, Error> {
+        let bytes =
+            Zeroizing::new(base64::decode(encoded.trim()).map_err(|e| Kind::Parse.context(e))?);
+        Self::from_raw(algorithm, &bytes)
+    }
+
+    /// Parse a private key of the given algorithm from the (upper or lower
+    /// case) hex encoding of its raw bytes
+    pub fn from_hex(algorithm: Algorithm, encoded: &str) -> Result<Self, Error> {
+        let encoded = encoded.trim();
+        let bytes = Zeroizing::new(
+            hex::decode_upper(encoded)
+                .or_else(|_| hex::decode(encoded))
+                .map_err(|e| Kind::Parse.context(e))?,
+        );
+        Self::from_raw(algorithm, &bytes)
+    }
+
+    /// Get the algorithm of this private key
+    pub fn algorithm(&self) -> Algorithm {
+        match self {
+            PrivateKey::Ed25519(_) => Algorithm::Ed25519,
+            #[cfg(feature = "secp256k1")]
+            PrivateKey::Secp256k1(_) => Algorithm::Secp256k1,
+        }
+    }
+
+    /// Serialize this key as raw bytes: the 64-byte keypair for Ed25519, or
+    /// the 32-byte secret scalar for secp256k1
+    pub fn to_bytes(&self) -> Zeroizing<Vec<u8>> {
+        match self {
+            PrivateKey::Ed25519(keypair) => Zeroizing::new(keypair.to_bytes().to_vec()),
+            #[cfg(feature = "secp256k1")]
+            PrivateKey::Secp256k1(secret_key) => Zeroizing::new(secret_key.as_bytes().to_vec()),
+        }
+    }
+
+    /// Serialize this key's raw bytes as base64
+    pub fn to_base64(&self) -> Zeroizing<String> {
+        Zeroizing::new(String::from_utf8(base64::encode(&*self.to_bytes())).unwrap())
+    }
+
+    /// Serialize this key's raw bytes as upper case hex
+    pub fn to_hex(&self) -> Zeroizing<String> {
+        Zeroizing::new(String::from_utf8(hex::encode_upper(&*self.to_bytes())).unwrap())
+    }
+
     /// Get the public key associated with this private key
     pub fn public_key(&self) -> PublicKey {
         match self {
             PrivateKey::Ed25519(private_key) => private_key.public.into(),
+            #[cfg(feature = "secp256k1")]
+            PrivateKey::Secp256k1(secret_key) => {
+                // A valid secret key always has a corresponding public key
+                k256::PublicKey::from_secret_key(secret_key, true)
+                    .expect("invalid secp256k1 secret key")
+                    .into()
+            }
         }
     }
 
     /// If applicable, borrow the Ed25519 keypair
     pub fn ed25519_keypair(&self) -> Option<&Ed25519> {
+        #[allow(unreachable_patterns)]
         match self {
             PrivateKey::Ed25519(keypair) => Some(keypair),
+            _ => None,
         }
     }
+
+    /// If applicable, borrow the ECDSA/secp256k1 secret key
+    #[cfg(feature = "secp256k1")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "secp256k1")))]
+    pub fn secp256k1_secret_key(&self) -> Option<&Secp256k1> {
+        match self {
+            PrivateKey::Secp256k1(secret_key) => Some(secret_key),
+            _ => None,
+        }
+    }
+
+    /// Encrypt this key with the given passphrase, producing the ASCII-armored
+    /// keystore format used by the Cosmos SDK (bcrypt + xsalsa20poly1305)
+    #[cfg(feature = "keystore")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "keystore")))]
+    pub fn to_encrypted_armor(&self, passphrase: &str) -> String {
+        keystore::encrypt(self, passphrase)
+    }
+
+    /// Decrypt a key in the ASCII-armored keystore format with the given
+    /// passphrase
+    #[cfg(feature = "keystore")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "keystore")))]
+    pub fn from_encrypted_armor(armored: &str, passphrase: &str) -> Result<Self, Error> {
+        keystore::decrypt(armored, passphrase)
+    }
+
+    /// Load and decrypt a key from an ASCII-armored keystore file
+    #[cfg(feature = "keystore")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "keystore")))]
+    pub fn load_encrypted_file<P>(path: &P, passphrase: &str) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+    {
+        let armored = fs::read_to_string(path).map_err(|e| {
+            format_err!(Kind::Io, "couldn't open {}: {}", path.as_ref().display(), e)
+        })?;
+
+        Self::from_encrypted_armor(&armored, passphrase)
+    }
+
+    /// Encrypt this key and save it to an ASCII-armored keystore file.
+    ///
+    /// On Unix the file is created with `0600` permissions.
+    #[cfg(feature = "keystore")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "keystore")))]
+    pub fn save_encrypted_file<P>(&self, path: &P, passphrase: &str) -> Result<(), Error>
+    where
+        P: AsRef<Path>,
+    {
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+        options
+            .open(path)
+            .and_then(|mut file| file.write_all(self.to_encrypted_armor(passphrase).as_bytes()))
+            .map_err(|e| {
+                format_err!(
+                    Kind::Io,
+                    "couldn't write {}: {}",
+                    path.as_ref().display(),
+                    e
+                )
+                .into()
+            })
+    }
 }
 
 /// Serialize an Ed25519 keypair as Base64
@@ -63,5 +320,138 @@ where
         return Err(D::Error::custom("invalid Ed25519 keypair size"));
     }
 
-    Ed25519::from_bytes(&*keypair_bytes).map_err(D::Error::custom)
+    match PrivateKey::from_raw_ed25519(&*keypair_bytes).map_err(D::Error::custom)? {
+        PrivateKey::Ed25519(keypair) => Ok(keypair),
+        _ => unreachable!("Ed25519 private key parsed as another algorithm"),
+    }
+}
+
+/// Serialize a secp256k1 secret key as Base64
+#[cfg(feature = "secp256k1")]
+fn serialize_secp256k1_secret_key<S>(
+    secret_key: &Secp256k1,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: ser::Serializer,
+{
+    Zeroizing::new(String::from_utf8(Base64::default().encode(secret_key.as_bytes())).unwrap())
+        .serialize(serializer)
+}
+
+/// Deserialize a secp256k1 secret key from Base64
+#[cfg(feature = "secp256k1")]
+fn deserialize_secp256k1_secret_key<'de, D>(deserializer: D) -> Result<Secp256k1, D::Error>
+where
+    D: de::Deserializer<'de>,
+{
+    use de::Error;
+    let string = Zeroizing::new(String::deserialize(deserializer)?);
+    let secret_key_bytes = Zeroizing::new(
+        Base64::default()
+            .decode(string.as_bytes())
+            .map_err(D::Error::custom)?,
+    );
+
+    // Reject scalars with no corresponding public key, as `public_key` can't
+    // derive one from them
+    match PrivateKey::from_raw_secp256k1(&secret_key_bytes).map_err(D::Error::custom)? {
+        PrivateKey::Secp256k1(secret_key) => Ok(secret_key),
+        _ => unreachable!("secp256k1 private key parsed as another algorithm"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE_KEYPAIR_BASE64: &str =
+        "skHDGUYe2pOhwfSrXZQ6KeKnmKgTOn+f++Vmj4OOqIHya/SyouhM63pTw/Gud0CHebIAZHgvutvfDjZZWe5FNA==";
+
+    #[test]
+    fn ed25519_import_export() {
+        let private_key =
+            PrivateKey::from_base64(Algorithm::Ed25519, EXAMPLE_KEYPAIR_BASE64).unwrap();
+        assert_eq!(private_key.algorithm(), Algorithm::Ed25519);
+        assert_eq!(&*private_key.to_base64(), EXAMPLE_KEYPAIR_BASE64);
+
+        let from_hex = PrivateKey::from_hex(Algorithm::Ed25519, &private_key.to_hex()).unwrap();
+        assert_eq!(from_hex.public_key(), private_key.public_key());
+
+        // The first half of the keypair is the seed the rest is derived from
+        let from_seed =
+            PrivateKey::from_raw_ed25519(&private_key.to_bytes()[..ED25519_SEED_SIZE]).unwrap();
+        assert_eq!(*from_seed.to_bytes(), *private_key.to_bytes());
+
+        assert!(PrivateKey::from_raw_ed25519(&[0u8; 16]).is_err());
+        assert!(PrivateKey::from_base64(Algorithm::Ed25519, "not base64").is_err());
+        assert!(PrivateKey::from_raw(Algorithm::Sr25519, &[0u8; 32]).is_err());
+    }
+
+    #[test]
+    fn ed25519_rejects_mismatched_public_key() {
+        let private_key =
+            PrivateKey::from_base64(Algorithm::Ed25519, EXAMPLE_KEYPAIR_BASE64).unwrap();
+        let mut keypair_bytes = private_key.to_bytes();
+        keypair_bytes[ED25519_KEYPAIR_SIZE - 1] ^= 1;
+        assert!(PrivateKey::from_raw_ed25519(&keypair_bytes).is_err());
+
+        let json = serde_json::to_string(&private_key).unwrap();
+        assert!(serde_json::from_str::<PrivateKey>(&json).is_ok());
+
+        let mismatched = json.replace(
+            EXAMPLE_KEYPAIR_BASE64,
+            &String::from_utf8(Base64::default().encode(&keypair_bytes[..])).unwrap(),
+        );
+        assert!(serde_json::from_str::<PrivateKey>(&mismatched).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "secp256k1")]
+    fn secp256k1_import_export() {
+        let private_key = PrivateKey::from_raw_secp256k1(&[1u8; 32]).unwrap();
+        assert_eq!(private_key.algorithm(), Algorithm::Secp256k1);
+
+        let from_base64 =
+            PrivateKey::from_base64(Algorithm::Secp256k1, &private_key.to_base64()).unwrap();
+        assert_eq!(from_base64.public_key(), private_key.public_key());
+
+        assert!(PrivateKey::from_raw_secp256k1(&[0u8; 32]).is_err());
+        assert!(PrivateKey::from_raw_secp256k1(&[1u8; 31]).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "secp256k1")]
+    fn secp256k1_json() {
+        let private_key = PrivateKey::from_raw_secp256k1(&[1u8; 32]).unwrap();
+        let json = serde_json::to_string(&private_key).unwrap();
+        let parsed: PrivateKey = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.public_key(), private_key.public_key());
+
+        // A zero scalar has the right length but no public key
+        let zero = r#"{
+            "type": "tendermint/PrivKeySecp256k1",
+            "value": "AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA="
+        }"#;
+        assert!(serde_json::from_str::<PrivateKey>(zero).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "keygen")]
+    fn generates_keys() {
+        let mut rng = rand_core::OsRng;
+        let first = PrivateKey::generate(Algorithm::Ed25519, &mut rng).unwrap();
+        let second = PrivateKey::generate(Algorithm::Ed25519, &mut rng).unwrap();
+        assert_ne!(first.public_key(), second.public_key());
+
+        #[cfg(feature = "secp256k1")]
+        assert_eq!(
+            PrivateKey::generate(Algorithm::Secp256k1, &mut rng)
+                .unwrap()
+                .algorithm(),
+            Algorithm::Secp256k1
+        );
+
+        assert!(PrivateKey::generate(Algorithm::Sr25519, &mut rng).is_err());
+    }
 }
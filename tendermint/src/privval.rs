@@ -0,0 +1,696 @@
+//! Messages of the remote signer ("privval") protocol of Tendermint 0.34,
+//! which replaced the amino messages in [`amino_types`] with protobuf:
+//!
+//! <https://github.com/tendermint/tendermint/blob/v0.34.0/proto/tendermint/privval/types.proto>
+//!
+//! Unlike their amino counterparts, requests carry the chain ID, so the sign
+//! bytes can be computed from a request alone.
+//!
+//! [`amino_types`]: crate::amino_types
+
+pub mod async_signer;
+pub mod batch;
+pub mod client;
+pub mod codec;
+pub mod last_sign_state;
+pub mod listener;
+pub mod protocol;
+pub mod validate;
+#[cfg(feature = "vote-extensions")]
+mod vote_extension;
+
+pub use self::batch::{Batch, Frame};
+pub use self::last_sign_state::LastSignState;
+pub use self::protocol::Protocol;
+pub use self::validate::Rejection;
+pub use crate::amino_types::remote_error::RemoteErrorCode;
+
+use crate::amino_types::remote_error::RemoteError;
+use crate::{
+    block, chain, consensus, error::Kind, proposal::Proposal, public_key, signature, vote::Vote,
+    Error, PublicKey, Signature,
+};
+use anomaly::{fail, format_err};
+use std::convert::{TryFrom, TryInto};
+use tendermint_proto::crypto::{public_key::Sum as RawPublicKeySum, PublicKey as RawPublicKey};
+use tendermint_proto::privval::{
+    message::Sum, Message as RawMessage, PingRequest as RawPingRequest,
+    PingResponse as RawPingResponse, PubKeyRequest as RawPubKeyRequest,
+    PubKeyResponse as RawPubKeyResponse, RemoteSignerError as RawRemoteSignerError,
+    SignProposalRequest as RawSignProposalRequest, SignVoteRequest as RawSignVoteRequest,
+    SignedProposalResponse as RawSignedProposalResponse,
+    SignedVoteResponse as RawSignedVoteResponse,
+};
+use tendermint_proto::types::{Proposal as RawProposal, Vote as RawVote};
+use tendermint_proto::DomainType;
+
+/// Error reported by the remote signer in place of a public key or signature,
+/// e.g. when it refuses to sign.
+///
+/// Tendermint itself always reports code `0`; the codes of
+/// [`RemoteErrorCode`] are used by other signers.
+#[derive(Clone, Debug, Eq, PartialEq, thiserror::Error)]
+#[error("remote signer error {code}: {description}")]
+pub struct RemoteSignerError {
+    /// Error code
+    pub code: i32,
+
+    /// Error description
+    pub description: String,
+}
+
+impl RemoteSignerError {
+    /// Create a new error with the given code and description
+    pub fn new(code: RemoteErrorCode, description: impl Into<String>) -> Self {
+        RemoteSignerError {
+            code: code as i32,
+            description: description.into(),
+        }
+    }
+
+    /// Create a new double signing error at the given height
+    pub fn double_sign(height: block::Height) -> Self {
+        Self::new(
+            RemoteErrorCode::DoubleSignError,
+            format!("double signing requested at height: {}", height),
+        )
+    }
+
+    /// Create a new error for a request on a chain the signer doesn't sign
+    /// for
+    pub fn chain_id_mismatch(chain_id: chain::Id) -> Self {
+        Self::new(
+            RemoteErrorCode::ChainIdMismatch,
+            format!("unknown chain ID: {}", chain_id),
+        )
+    }
+
+    /// Get the error code, if it's a known one
+    pub fn code(&self) -> Option<RemoteErrorCode> {
+        RemoteErrorCode::from_i32(self.code)
+    }
+}
+
+impl From<Rejection> for RemoteSignerError {
+    fn from(rejection: Rejection) -> Self {
+        let code = match rejection {
+            Rejection::UnknownChainId { .. } => RemoteErrorCode::ChainIdMismatch,
+            _ => RemoteErrorCode::DoubleSignError,
+        };
+
+        Self::new(code, rejection.to_string())
+    }
+}
+
+impl DomainType<RawRemoteSignerError> for RemoteSignerError {}
+
+impl From<RawRemoteSignerError> for RemoteSignerError {
+    fn from(value: RawRemoteSignerError) -> Self {
+        RemoteSignerError {
+            code: value.code,
+            description: value.description,
+        }
+    }
+}
+
+impl From<RemoteSignerError> for RawRemoteSignerError {
+    fn from(value: RemoteSignerError) -> Self {
+        RawRemoteSignerError {
+            code: value.code,
+            description: value.description,
+        }
+    }
+}
+
+impl From<RemoteError> for RemoteSignerError {
+    fn from(value: RemoteError) -> Self {
+        RemoteSignerError {
+            code: value.code,
+            description: value.description,
+        }
+    }
+}
+
+impl From<RemoteSignerError> for RemoteError {
+    fn from(value: RemoteSignerError) -> Self {
+        RemoteError {
+            code: value.code,
+            description: value.description,
+        }
+    }
+}
+
+/// Request for the consensus public key of the validator on a chain
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PubKeyRequest {
+    /// Chain ID
+    pub chain_id: chain::Id,
+}
+
+impl DomainType<RawPubKeyRequest> for PubKeyRequest {}
+
+impl TryFrom<RawPubKeyRequest> for PubKeyRequest {
+    type Error = Error;
+
+    fn try_from(value: RawPubKeyRequest) -> Result<Self, Self::Error> {
+        Ok(PubKeyRequest {
+            chain_id: value.chain_id.parse()?,
+        })
+    }
+}
+
+impl From<PubKeyRequest> for RawPubKeyRequest {
+    fn from(value: PubKeyRequest) -> Self {
+        RawPubKeyRequest {
+            chain_id: value.chain_id.to_string(),
+        }
+    }
+}
+
+/// Response to a [`PubKeyRequest`]: either the public key or an error.
+///
+/// Only Ed25519 keys can be sent, as they are the only keys the protobuf
+/// encoding supports.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PubKeyResponse {
+    /// Consensus public key
+    PublicKey(public_key::Ed25519),
+
+    /// Error reported by the signer
+    Error(RemoteSignerError),
+}
+
+impl DomainType<RawPubKeyResponse> for PubKeyResponse {}
+
+impl TryFrom<RawPubKeyResponse> for PubKeyResponse {
+    type Error = Error;
+
+    fn try_from(value: RawPubKeyResponse) -> Result<Self, Self::Error> {
+        match (value.pub_key, value.error) {
+            (_, Some(error)) => Ok(PubKeyResponse::Error(error.into())),
+            (Some(pub_key), None) => match PublicKey::try_from(pub_key)? {
+                PublicKey::Ed25519(pub_key) => Ok(PubKeyResponse::PublicKey(pub_key)),
+                #[allow(unreachable_patterns)]
+                other => fail!(
+                    Kind::InvalidKey,
+                    "unsupported consensus key type: {}",
+                    other.algorithm()
+                ),
+            },
+            (None, None) => Err(format_err!(
+                Kind::Parse,
+                "public key response has neither a key nor an error"
+            )
+            .into()),
+        }
+    }
+}
+
+impl From<PubKeyResponse> for RawPubKeyResponse {
+    fn from(value: PubKeyResponse) -> Self {
+        match value {
+            PubKeyResponse::PublicKey(pub_key) => RawPubKeyResponse {
+                pub_key: Some(RawPublicKey {
+                    sum: Some(RawPublicKeySum::Ed25519(pub_key.as_bytes().to_vec())),
+                }),
+                error: None,
+            },
+            PubKeyResponse::Error(error) => RawPubKeyResponse {
+                pub_key: None,
+                error: Some(error.into()),
+            },
+        }
+    }
+}
+
+/// Request to sign a vote on a chain
+#[derive(Clone, Debug, PartialEq)]
+pub struct SignVoteRequest {
+    /// Vote to sign. Its signature is not sent, as the signer provides it.
+    pub vote: Vote,
+
+    /// Chain ID
+    pub chain_id: chain::Id,
+
+    /// Don't sign the vote's extension, even if it's a precommit for a block
+    /// (Tendermint 0.38). Like the vote's extension, only carried by
+    /// [`Frame`]s.
+    #[cfg(feature = "vote-extensions")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "vote-extensions")))]
+    pub skip_extension_signing: bool,
+}
+
+impl SignVoteRequest {
+    /// Create a request to sign the given vote on the given chain
+    pub fn new(vote: Vote, chain_id: chain::Id) -> Self {
+        SignVoteRequest {
+            vote,
+            chain_id,
+            #[cfg(feature = "vote-extensions")]
+            skip_extension_signing: false,
+        }
+    }
+
+    /// Bytes the signer is asked to sign
+    pub fn to_signable_bytes(&self) -> Vec<u8> {
+        self.vote.to_signable_bytes(self.chain_id)
+    }
+
+    /// Bytes of the vote's extension the signer is asked to sign along with
+    /// the vote, if any: like Tendermint 0.38's `FilePV`, only precommits for
+    /// a block have their extension signed.
+    #[cfg(feature = "vote-extensions")]
+    pub fn to_extension_signable_bytes(&self) -> Option<Vec<u8>> {
+        if self.skip_extension_signing || !self.vote.is_precommit() || self.vote.block_id.is_none()
+        {
+            return None;
+        }
+
+        Some(self.vote.extension_sign_bytes(self.chain_id))
+    }
+
+    /// Consensus state the vote would be signed at
+    pub fn consensus_state(&self) -> consensus::State {
+        validate::vote_state(&self.vote)
+    }
+
+    /// Check signing this vote is safe after signing at the given state
+    pub fn validate(&self, last: &consensus::State) -> Result<(), Rejection> {
+        validate::check_hrs(last, &self.consensus_state())
+    }
+}
+
+impl DomainType<RawSignVoteRequest> for SignVoteRequest {}
+
+impl TryFrom<RawSignVoteRequest> for SignVoteRequest {
+    type Error = Error;
+
+    fn try_from(value: RawSignVoteRequest) -> Result<Self, Self::Error> {
+        let mut vote = value
+            .vote
+            .ok_or_else(|| format_err!(Kind::Parse, "vote is missing in sign request"))?;
+        vote.signature = unsigned_placeholder(vote.signature);
+
+        Ok(SignVoteRequest {
+            vote: vote.try_into()?,
+            chain_id: value.chain_id.parse()?,
+            #[cfg(feature = "vote-extensions")]
+            skip_extension_signing: false,
+        })
+    }
+}
+
+impl TryFrom<SignVoteRequest> for RawSignVoteRequest {
+    type Error = Error;
+
+    fn try_from(value: SignVoteRequest) -> Result<Self, Self::Error> {
+        let mut vote = RawVote::try_from(value.vote)?;
+        vote.signature = vec![];
+
+        Ok(RawSignVoteRequest {
+            vote: Some(vote),
+            chain_id: value.chain_id.to_string(),
+        })
+    }
+}
+
+/// Response to a [`SignVoteRequest`]: either the signed vote or an error
+#[derive(Clone, Debug, PartialEq)]
+pub enum SignedVoteResponse {
+    /// Signed vote
+    Vote(Vote),
+
+    /// Error reported by the signer, e.g. on a double sign attempt
+    Error(RemoteSignerError),
+}
+
+impl DomainType<RawSignedVoteResponse> for SignedVoteResponse {}
+
+impl TryFrom<RawSignedVoteResponse> for SignedVoteResponse {
+    type Error = Error;
+
+    fn try_from(value: RawSignedVoteResponse) -> Result<Self, Self::Error> {
+        match (value.vote, value.error) {
+            (_, Some(error)) => Ok(SignedVoteResponse::Error(error.into())),
+            (Some(vote), None) => Ok(SignedVoteResponse::Vote(vote.try_into()?)),
+            (None, None) => Err(format_err!(
+                Kind::Parse,
+                "signed vote response has neither a vote nor an error"
+            )
+            .into()),
+        }
+    }
+}
+
+impl TryFrom<SignedVoteResponse> for RawSignedVoteResponse {
+    type Error = Error;
+
+    fn try_from(value: SignedVoteResponse) -> Result<Self, Self::Error> {
+        Ok(match value {
+            SignedVoteResponse::Vote(vote) => RawSignedVoteResponse {
+                vote: Some(vote.try_into()?),
+                error: None,
+            },
+            SignedVoteResponse::Error(error) => RawSignedVoteResponse {
+                vote: None,
+                error: Some(error.into()),
+            },
+        })
+    }
+}
+
+/// Request to sign a proposal on a chain
+#[derive(Clone, Debug, PartialEq)]
+pub struct SignProposalRequest {
+    /// Proposal to sign. Its signature is not sent, as the signer provides it.
+    pub proposal: Proposal,
+
+    /// Chain ID
+    pub chain_id: chain::Id,
+}
+
+impl SignProposalRequest {
+    /// Create a request to sign the given proposal on the given chain
+    pub fn new(proposal: Proposal, chain_id: chain::Id) -> Self {
+        SignProposalRequest { proposal, chain_id }
+    }
+
+    /// Bytes the signer is asked to sign
+    pub fn to_signable_bytes(&self) -> Vec<u8> {
+        self.proposal.to_signable_bytes(self.chain_id)
+    }
+
+    /// Consensus state the proposal would be signed at
+    pub fn consensus_state(&self) -> consensus::State {
+        validate::proposal_state(&self.proposal)
+    }
+
+    /// Check signing this proposal is safe after signing at the given state
+    pub fn validate(&self, last: &consensus::State) -> Result<(), Rejection> {
+        validate::check_hrs(last, &self.consensus_state())
+    }
+}
+
+impl DomainType<RawSignProposalRequest> for SignProposalRequest {}
+
+impl TryFrom<RawSignProposalRequest> for SignProposalRequest {
+    type Error = Error;
+
+    fn try_from(value: RawSignProposalRequest) -> Result<Self, Self::Error> {
+        let mut proposal = value
+            .proposal
+            .ok_or_else(|| format_err!(Kind::Parse, "proposal is missing in sign request"))?;
+        proposal.signature = unsigned_placeholder(proposal.signature);
+
+        Ok(SignProposalRequest {
+            proposal: proposal.try_into()?,
+            chain_id: value.chain_id.parse()?,
+        })
+    }
+}
+
+impl TryFrom<SignProposalRequest> for RawSignProposalRequest {
+    type Error = Error;
+
+    fn try_from(value: SignProposalRequest) -> Result<Self, Self::Error> {
+        let mut proposal = RawProposal::try_from(value.proposal)?;
+        proposal.signature = vec![];
+
+        Ok(RawSignProposalRequest {
+            proposal: Some(proposal),
+            chain_id: value.chain_id.to_string(),
+        })
+    }
+}
+
+/// Response to a [`SignProposalRequest`]: either the signed proposal or an
+/// error
+#[derive(Clone, Debug, PartialEq)]
+pub enum SignedProposalResponse {
+    /// Signed proposal
+    Proposal(Proposal),
+
+    /// Error reported by the signer, e.g. on a double sign attempt
+    Error(RemoteSignerError),
+}
+
+impl DomainType<RawSignedProposalResponse> for SignedProposalResponse {}
+
+impl TryFrom<RawSignedProposalResponse> for SignedProposalResponse {
+    type Error = Error;
+
+    fn try_from(value: RawSignedProposalResponse) -> Result<Self, Self::Error> {
+        match (value.proposal, value.error) {
+            (_, Some(error)) => Ok(SignedProposalResponse::Error(error.into())),
+            (Some(proposal), None) => Ok(SignedProposalResponse::Proposal(proposal.try_into()?)),
+            (None, None) => Err(format_err!(
+                Kind::Parse,
+                "signed proposal response has neither a proposal nor an error"
+            )
+            .into()),
+        }
+    }
+}
+
+impl TryFrom<SignedProposalResponse> for RawSignedProposalResponse {
+    type Error = Error;
+
+    fn try_from(value: SignedProposalResponse) -> Result<Self, Self::Error> {
+        Ok(match value {
+            SignedProposalResponse::Proposal(proposal) => RawSignedProposalResponse {
+                proposal: Some(proposal.try_into()?),
+                error: None,
+            },
+            SignedProposalResponse::Error(error) => RawSignedProposalResponse {
+                proposal: None,
+                error: Some(error.into()),
+            },
+        })
+    }
+}
+
+/// Messages exchanged between a validator and its remote signer
+#[derive(Clone, Debug, PartialEq)]
+pub enum Message {
+    /// Public key request
+    PubKeyRequest(PubKeyRequest),
+
+    /// Public key response
+    PubKeyResponse(PubKeyResponse),
+
+    /// Vote signing request
+    SignVoteRequest(SignVoteRequest),
+
+    /// Vote signing response
+    SignedVoteResponse(SignedVoteResponse),
+
+    /// Proposal signing request
+    SignProposalRequest(SignProposalRequest),
+
+    /// Proposal signing response
+    SignedProposalResponse(SignedProposalResponse),
+
+    /// Ping request, keeping the connection alive
+    PingRequest,
+
+    /// Ping response
+    PingResponse,
+}
+
+impl DomainType<RawMessage> for Message {}
+
+impl TryFrom<RawMessage> for Message {
+    type Error = Error;
+
+    fn try_from(value: RawMessage) -> Result<Self, Self::Error> {
+        let sum = value
+            .sum
+            .ok_or_else(|| format_err!(Kind::Parse, "empty privval message"))?;
+
+        Ok(match sum {
+            Sum::PubKeyRequest(msg) => Message::PubKeyRequest(msg.try_into()?),
+            Sum::PubKeyResponse(msg) => Message::PubKeyResponse(msg.try_into()?),
+            Sum::SignVoteRequest(msg) => Message::SignVoteRequest(msg.try_into()?),
+            Sum::SignedVoteResponse(msg) => Message::SignedVoteResponse(msg.try_into()?),
+            Sum::SignProposalRequest(msg) => Message::SignProposalRequest(msg.try_into()?),
+            Sum::SignedProposalResponse(msg) => Message::SignedProposalResponse(msg.try_into()?),
+            Sum::PingRequest(_) => Message::PingRequest,
+            Sum::PingResponse(_) => Message::PingResponse,
+        })
+    }
+}
+
+impl TryFrom<Message> for RawMessage {
+    type Error = Error;
+
+    fn try_from(value: Message) -> Result<Self, Self::Error> {
+        let sum = match value {
+            Message::PubKeyRequest(msg) => Sum::PubKeyRequest(msg.into()),
+            Message::PubKeyResponse(msg) => Sum::PubKeyResponse(msg.into()),
+            Message::SignVoteRequest(msg) => Sum::SignVoteRequest(msg.try_into()?),
+            Message::SignedVoteResponse(msg) => Sum::SignedVoteResponse(msg.try_into()?),
+            Message::SignProposalRequest(msg) => Sum::SignProposalRequest(msg.try_into()?),
+            Message::SignedProposalResponse(msg) => Sum::SignedProposalResponse(msg.try_into()?),
+            Message::PingRequest => Sum::PingRequest(RawPingRequest {}),
+            Message::PingResponse => Sum::PingResponse(RawPingResponse {}),
+        };
+
+        Ok(RawMessage { sum: Some(sum) })
+    }
+}
+
+macro_rules! impl_from_message {
+    ($($variant:ident),+) => {
+        $(
+            impl From<$variant> for Message {
+                fn from(msg: $variant) -> Message {
+                    Message::$variant(msg)
+                }
+            }
+        )+
+    };
+}
+
+impl_from_message!(
+    PubKeyRequest,
+    PubKeyResponse,
+    SignVoteRequest,
+    SignedVoteResponse,
+    SignProposalRequest,
+    SignedProposalResponse
+);
+
+/// Requests carry unsigned votes and proposals, which have no domain type
+/// representation: stand in an all-zero signature for a missing one.
+fn unsigned_placeholder(signature: Vec<u8>) -> Vec<u8> {
+    if signature.is_empty() {
+        Signature::Ed25519(signature::Ed25519::new([0; 64])).into()
+    } else {
+        signature
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{account, vote, Time};
+    use std::str::FromStr;
+
+    fn example_vote() -> Vote {
+        Vote {
+            vote_type: vote::Type::Precommit,
+            height: block::Height::from(12345u64),
+            round: 2,
+            block_id: None,
+            timestamp: Time::from_str("2017-12-25T03:00:01.234Z").unwrap(),
+            validator_address: account::Id::new([0xa3; 20]),
+            validator_index: 56789,
+            signature: Signature::Ed25519(signature::Ed25519::new([0; 64])),
+            #[cfg(feature = "vote-extensions")]
+            extension: vec![],
+            #[cfg(feature = "vote-extensions")]
+            extension_signature: None,
+        }
+    }
+
+    #[test]
+    fn sign_vote_request_round_trip() {
+        let chain_id = chain::Id::from("test_chain_id");
+        let request = SignVoteRequest::new(example_vote(), chain_id);
+        let message = Message::from(request.clone());
+
+        let bytes = message.encode_vec().unwrap();
+        let decoded = Message::decode_vec(&bytes).unwrap();
+        assert_eq!(decoded, message);
+
+        // The request doesn't carry a signature
+        let raw = RawSignVoteRequest::try_from(request.clone()).unwrap();
+        assert!(raw.vote.as_ref().unwrap().signature.is_empty());
+        assert_eq!(raw.chain_id, "test_chain_id");
+
+        assert_eq!(
+            request.to_signable_bytes(),
+            example_vote().to_signable_bytes(chain_id)
+        );
+    }
+
+    #[cfg(feature = "vote-extensions")]
+    #[test]
+    fn extension_signable_bytes() {
+        let mut request = SignVoteRequest::new(example_vote(), chain::Id::from("test_chain_id"));
+        assert_eq!(request.to_extension_signable_bytes(), None);
+
+        request.vote.block_id = Some(block::Id::new(crate::hash::Hash::Sha256([0x42; 32]), None));
+        assert_eq!(
+            request.to_extension_signable_bytes(),
+            Some(request.vote.extension_sign_bytes(request.chain_id))
+        );
+
+        request.skip_extension_signing = true;
+        assert_eq!(request.to_extension_signable_bytes(), None);
+    }
+
+    #[test]
+    fn signed_vote_response_round_trip() {
+        let signed = SignedVoteResponse::Vote(example_vote());
+        let bytes = signed.encode_vec().unwrap();
+        assert_eq!(SignedVoteResponse::decode_vec(&bytes).unwrap(), signed);
+
+        let error = SignedVoteResponse::Error(RemoteSignerError::double_sign(block::Height::from(
+            12345u64,
+        )));
+        let bytes = error.encode_vec().unwrap();
+        assert_eq!(SignedVoteResponse::decode_vec(&bytes).unwrap(), error);
+
+        assert!(SignedVoteResponse::try_from(RawSignedVoteResponse::default()).is_err());
+    }
+
+    #[test]
+    fn pub_key_response_round_trip() {
+        let secret = ed25519_dalek::SecretKey::from_bytes(&[0x42; 32]).unwrap();
+        let pub_key = public_key::Ed25519::from(&secret);
+
+        let response = Message::from(PubKeyResponse::PublicKey(pub_key));
+        let bytes = response.encode_vec().unwrap();
+        assert_eq!(Message::decode_vec(&bytes).unwrap(), response);
+    }
+
+    #[test]
+    fn remote_signer_errors() {
+        let chain_id = chain::Id::from("test_chain_id");
+        let request = SignVoteRequest::new(example_vote(), chain_id);
+        let last = consensus::State {
+            height: block::Height::from(12346u64),
+            ..request.consensus_state()
+        };
+
+        let error = RemoteSignerError::from(request.validate(&last).unwrap_err());
+        assert_eq!(error.code(), Some(RemoteErrorCode::DoubleSignError));
+
+        let response = Message::from(SignedVoteResponse::Error(error.clone()));
+        let bytes = response.encode_vec().unwrap();
+        assert_eq!(Message::decode_vec(&bytes).unwrap(), response);
+
+        assert_eq!(
+            RemoteSignerError::chain_id_mismatch(chain_id).code(),
+            Some(RemoteErrorCode::ChainIdMismatch)
+        );
+
+        // Tendermint reports every error with code 0
+        let tendermint_error = RemoteSignerError {
+            code: 0,
+            description: "some error".to_owned(),
+        };
+        assert_eq!(tendermint_error.code(), None);
+    }
+
+    #[test]
+    fn rejects_empty_messages() {
+        assert!(Message::try_from(RawMessage::default()).is_err());
+        assert!(SignVoteRequest::try_from(RawSignVoteRequest::default()).is_err());
+        assert!(PubKeyRequest::try_from(RawPubKeyRequest::default()).is_err());
+
+        let ping = Message::PingRequest.encode_vec().unwrap();
+        assert_eq!(Message::decode_vec(&ping).unwrap(), Message::PingRequest);
+    }
+}
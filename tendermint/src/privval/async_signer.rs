@@ -0,0 +1,204 @@
+//! Asynchronous counterpart of the remote signer client's [`Signer`], for
+//! signers which await their signatures, e.g. from a networked HSM, a cloud
+//! KMS or a threshold signing cluster, without blocking the task serving the
+//! validator's connection.
+//!
+//! [`Signer`]: super::client::Signer
+
+use super::{
+    client::{self, Signer},
+    codec, Batch, Frame, Message, PubKeyResponse, RemoteSignerError, SignProposalRequest,
+    SignVoteRequest, SignedProposalResponse, SignedVoteResponse,
+};
+use crate::{chain, error::Kind, proposal::Proposal, public_key, vote::Vote, Error};
+use anomaly::fail;
+use async_trait::async_trait;
+use futures::io::{AsyncRead, AsyncWrite};
+
+/// Local signer the client delegates the validator's requests to, which
+/// signs asynchronously.
+///
+/// Like [`Signer`]s, implementations are responsible for refusing to double
+/// sign.
+#[async_trait]
+pub trait AsyncSigner: Send {
+    /// Get the consensus public key for the given chain
+    async fn public_key(
+        &mut self,
+        chain_id: chain::Id,
+    ) -> Result<public_key::Ed25519, RemoteSignerError>;
+
+    /// Sign the requested vote, returning it with its signature set
+    async fn sign_vote(&mut self, request: SignVoteRequest) -> Result<Vote, RemoteSignerError>;
+
+    /// Sign the requested proposal, returning it with its signature set
+    async fn sign_proposal(
+        &mut self,
+        request: SignProposalRequest,
+    ) -> Result<Proposal, RemoteSignerError>;
+}
+
+/// Adapter serving a synchronous [`Signer`] as an [`AsyncSigner`].
+///
+/// The signer is called in place, blocking the task for as long as it takes
+/// to sign: this suits signers which sign in memory, not ones which wait on
+/// the network.
+#[derive(Debug)]
+pub struct Blocking<S>(pub S);
+
+#[async_trait]
+impl<S> AsyncSigner for Blocking<S>
+where
+    S: Signer + Send,
+{
+    async fn public_key(
+        &mut self,
+        chain_id: chain::Id,
+    ) -> Result<public_key::Ed25519, RemoteSignerError> {
+        self.0.public_key(chain_id)
+    }
+
+    async fn sign_vote(&mut self, request: SignVoteRequest) -> Result<Vote, RemoteSignerError> {
+        self.0.sign_vote(request)
+    }
+
+    async fn sign_proposal(
+        &mut self,
+        request: SignProposalRequest,
+    ) -> Result<Proposal, RemoteSignerError> {
+        self.0.sign_proposal(request)
+    }
+}
+
+/// Serve requests read from the given asynchronous connection until it is
+/// closed.
+///
+/// Like [`client::serve`], batches of requests are answered with a batch of
+/// responses in the same order, signed one after the other.
+pub async fn serve<T, S>(stream: &mut T, signer: &mut S) -> Result<(), Error>
+where
+    T: AsyncRead + AsyncWrite + Unpin,
+    S: AsyncSigner,
+{
+    loop {
+        let response = match codec::read_frame_async(stream).await {
+            Ok(Frame::Message(request)) => handle_request(signer, request).await?.into(),
+            Ok(Frame::Batch(Batch::Requests(requests))) => {
+                let mut responses = Vec::with_capacity(requests.len());
+
+                for request in requests {
+                    responses.push(handle_request(signer, request).await?);
+                }
+
+                Batch::Responses(responses).into()
+            }
+            Ok(Frame::Batch(Batch::Responses(_))) => fail!(
+                Kind::Protocol,
+                "unexpected batch of responses from validator"
+            ),
+            Err(e) if client::is_eof(&e) => return Ok(()),
+            Err(e) => return Err(e),
+        };
+
+        codec::write_frame_async(stream, response).await?;
+    }
+}
+
+/// Handle a single request from the validator, returning the response
+pub async fn handle_request<S: AsyncSigner>(
+    signer: &mut S,
+    request: Message,
+) -> Result<Message, Error> {
+    let response: Message = match request {
+        Message::PubKeyRequest(request) => match signer.public_key(request.chain_id).await {
+            Ok(pub_key) => PubKeyResponse::PublicKey(pub_key),
+            Err(e) => PubKeyResponse::Error(e),
+        }
+        .into(),
+        Message::SignVoteRequest(request) => match signer.sign_vote(request).await {
+            Ok(vote) => SignedVoteResponse::Vote(vote),
+            Err(e) => SignedVoteResponse::Error(e),
+        }
+        .into(),
+        Message::SignProposalRequest(request) => match signer.sign_proposal(request).await {
+            Ok(proposal) => SignedProposalResponse::Proposal(proposal),
+            Err(e) => SignedProposalResponse::Error(e),
+        }
+        .into(),
+        Message::PingRequest => Message::PingResponse,
+        other => fail!(
+            Kind::Protocol,
+            "unexpected message from validator: {:?}",
+            other
+        ),
+    };
+
+    Ok(response)
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::privval::{client::tests, listener::PrivValidator, listener::SignerListener};
+    use futures::{executor::block_on, io::AllowStdIo};
+    use std::{os::unix::net::UnixStream, thread};
+
+    /// Signer which hands requests to another task, as a signer backed by a
+    /// network service would
+    struct Remote(Blocking<tests::SoftSigner>);
+
+    #[async_trait]
+    impl AsyncSigner for Remote {
+        async fn public_key(
+            &mut self,
+            chain_id: chain::Id,
+        ) -> Result<public_key::Ed25519, RemoteSignerError> {
+            self.0.public_key(chain_id).await
+        }
+
+        async fn sign_vote(&mut self, request: SignVoteRequest) -> Result<Vote, RemoteSignerError> {
+            let (sender, receiver) = futures::channel::oneshot::channel();
+            let mut signer = tests::soft_signer();
+            thread::spawn(move || sender.send(signer.sign_vote(request)));
+            receiver.await.expect("signing thread exited")
+        }
+
+        async fn sign_proposal(
+            &mut self,
+            request: SignProposalRequest,
+        ) -> Result<Proposal, RemoteSignerError> {
+            self.0.sign_proposal(request).await
+        }
+    }
+
+    #[test]
+    fn serves_requests_with_async_signer() {
+        let (validator, kms) = UnixStream::pair().unwrap();
+        let signer = thread::spawn(move || {
+            let mut signer = Remote(Blocking(tests::soft_signer()));
+            block_on(serve(&mut AllowStdIo::new(kms), &mut signer))
+        });
+
+        let chain_id = chain::Id::from("test_chain_id");
+        let mut listener = SignerListener::new(AllowStdIo::new(validator));
+
+        block_on(async {
+            let pub_key = listener.public_key(chain_id).await.unwrap();
+            assert_eq!(pub_key, tests::soft_signer().0.public);
+
+            let vote = listener
+                .sign_vote(tests::vote_at(10), chain_id)
+                .await
+                .unwrap();
+            assert!(vote.verify(chain_id, &pub_key.into()).is_ok());
+
+            assert!(listener
+                .sign_vote(tests::vote_at(9), chain_id)
+                .await
+                .is_err());
+        });
+
+        drop(listener);
+        assert!(signer.join().unwrap().is_ok());
+    }
+}
@@ -0,0 +1,179 @@
+//! Batches of signing requests, e.g. a proposal and a prevote for pipelined
+//! rounds, sent in one frame to save round trips to slow signers (HSMs).
+//!
+//! This is an extension of the privval protocol which Tendermint doesn't
+//! support: a batch is encoded as fields of the privval `Message` which
+//! Tendermint doesn't define, so only peers built with this crate
+//! understand it, and only if both sides opt in by sending batches.
+
+#[cfg(feature = "vote-extensions")]
+use super::vote_extension;
+use super::Message;
+use crate::{error::Kind, Error};
+use anomaly::fail;
+use prost::Message as _;
+#[cfg(not(feature = "vote-extensions"))]
+use std::convert::TryFrom;
+use tendermint_proto::privval::Message as RawMessage;
+
+/// Frame exchanged on a privval connection: a single message, or a batch
+#[derive(Clone, Debug, PartialEq)]
+pub enum Frame {
+    /// Single message, as Tendermint sends
+    Message(Message),
+
+    /// Batch of requests or responses
+    Batch(Batch),
+}
+
+impl From<Message> for Frame {
+    fn from(message: Message) -> Frame {
+        Frame::Message(message)
+    }
+}
+
+impl From<Batch> for Frame {
+    fn from(batch: Batch) -> Frame {
+        Frame::Batch(batch)
+    }
+}
+
+/// Batch of requests, or of their responses in the same order
+#[derive(Clone, Debug, PartialEq)]
+pub enum Batch {
+    /// Requests sent by the validator
+    Requests(Vec<Message>),
+
+    /// Responses sent by the signer
+    Responses(Vec<Message>),
+}
+
+/// Batch fields, using tags the privval `Message` oneof leaves free.
+///
+/// The messages are kept encoded, as a `Message` (repeated) field is on the
+/// wire, to decode them along with their vote extension fields.
+#[derive(Clone, PartialEq, prost::Message)]
+struct RawBatch {
+    #[prost(bytes, repeated, tag = "100")]
+    requests: Vec<Vec<u8>>,
+
+    #[prost(bytes, repeated, tag = "101")]
+    responses: Vec<Vec<u8>>,
+}
+
+impl Frame {
+    /// Decode a frame (without its length prefix)
+    pub fn decode(bytes: &[u8]) -> Result<Frame, Error> {
+        let raw = RawMessage::decode(bytes).map_err(|e| Kind::Parse.context(e))?;
+
+        if raw.sum.is_some() {
+            return Ok(Frame::Message(decode_raw(bytes, raw)?));
+        }
+
+        let raw = RawBatch::decode(bytes).map_err(|e| Kind::Parse.context(e))?;
+        let parse = |messages: Vec<Vec<u8>>| {
+            messages
+                .iter()
+                .map(|bytes| decode_message(bytes))
+                .collect::<Result<Vec<_>, _>>()
+        };
+
+        match (raw.requests.is_empty(), raw.responses.is_empty()) {
+            (false, true) => Ok(Batch::Requests(parse(raw.requests)?).into()),
+            (true, false) => Ok(Batch::Responses(parse(raw.responses)?).into()),
+            (true, true) => fail!(Kind::Parse, "empty privval message"),
+            (false, false) => fail!(Kind::Parse, "batch has both requests and responses"),
+        }
+    }
+
+    /// Encode this frame, prefixed with its length
+    pub fn encode_length_delimited(self) -> Result<Vec<u8>, Error> {
+        let body = match self {
+            Frame::Message(message) => encode_message(message)?,
+            Frame::Batch(Batch::Requests(requests)) => encode_batch(RawBatch {
+                requests: encode_messages(requests)?,
+                responses: vec![],
+            })?,
+            Frame::Batch(Batch::Responses(responses)) => encode_batch(RawBatch {
+                requests: vec![],
+                responses: encode_messages(responses)?,
+            })?,
+        };
+
+        let mut bytes = Vec::with_capacity(body.len() + 2);
+        prost::encoding::encode_varint(body.len() as u64, &mut bytes);
+        bytes.extend_from_slice(&body);
+
+        Ok(bytes)
+    }
+}
+
+fn decode_message(bytes: &[u8]) -> Result<Message, Error> {
+    let raw = RawMessage::decode(bytes).map_err(|e| Kind::Parse.context(e))?;
+    decode_raw(bytes, raw)
+}
+
+/// Decode a message from its encoding and its already decoded protobuf type
+#[cfg(feature = "vote-extensions")]
+fn decode_raw(bytes: &[u8], raw: RawMessage) -> Result<Message, Error> {
+    vote_extension::decode(bytes, raw)
+}
+
+/// Decode a message from its encoding and its already decoded protobuf type
+#[cfg(not(feature = "vote-extensions"))]
+fn decode_raw(_bytes: &[u8], raw: RawMessage) -> Result<Message, Error> {
+    Message::try_from(raw)
+}
+
+#[cfg(feature = "vote-extensions")]
+fn encode_message(message: Message) -> Result<Vec<u8>, Error> {
+    let mut bytes = vec![];
+    vote_extension::encode(message, &mut bytes)?;
+    Ok(bytes)
+}
+
+#[cfg(not(feature = "vote-extensions"))]
+fn encode_message(message: Message) -> Result<Vec<u8>, Error> {
+    let mut bytes = vec![];
+    RawMessage::try_from(message)?
+        .encode(&mut bytes)
+        .map_err(|e| Kind::Parse.context(e))?;
+    Ok(bytes)
+}
+
+fn encode_messages(messages: Vec<Message>) -> Result<Vec<Vec<u8>>, Error> {
+    messages.into_iter().map(encode_message).collect()
+}
+
+fn encode_batch(batch: RawBatch) -> Result<Vec<u8>, Error> {
+    let mut bytes = vec![];
+    batch
+        .encode(&mut bytes)
+        .map_err(|e| Kind::Parse.context(e))?;
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{chain, privval::PubKeyRequest};
+
+    #[test]
+    fn batch_round_trip() {
+        let request = Message::from(PubKeyRequest {
+            chain_id: chain::Id::from("test_chain_id"),
+        });
+
+        for frame in vec![
+            Frame::Message(request.clone()),
+            Batch::Requests(vec![request.clone(), Message::PingRequest]).into(),
+            Batch::Responses(vec![Message::PingResponse]).into(),
+        ] {
+            let bytes = frame.clone().encode_length_delimited().unwrap();
+            assert_eq!(usize::from(bytes[0]), bytes.len() - 1);
+            assert_eq!(Frame::decode(&bytes[1..]).unwrap(), frame);
+        }
+
+        assert!(Frame::decode(&[]).is_err());
+    }
+}
@@ -0,0 +1,828 @@
+//! Remote signer client: connects to a validator's `priv_validator_laddr` and
+//! serves its signing requests with a local [`Signer`].
+//!
+//! Tendermint expects its remote signer to dial in. Over UNIX domain sockets
+//! messages are sent in the clear; over TCP the connection must first be
+//! upgraded with Tendermint's secret connection handshake, so TCP connections
+//! are made by a [`Connector`] supplied by the caller, e.g. one wrapping its
+//! streams in a `p2p::secret_connection::SecretConnection` (with the
+//! `secret-connection` feature).
+
+use super::{
+    codec, validate::check_chain_id, Batch, Frame, Message, PubKeyResponse, RemoteSignerError,
+    SignProposalRequest, SignVoteRequest, SignedProposalResponse, SignedVoteResponse,
+};
+use crate::{chain, error::Kind, net, proposal::Proposal, public_key, vote::Vote, Error};
+use anomaly::fail;
+use std::{
+    io::{self, Read, Write},
+    thread,
+    time::Duration,
+};
+
+/// Local signer the client delegates the validator's requests to.
+///
+/// Implementations are responsible for refusing to double sign, which they
+/// report by returning a [`RemoteSignerError`] (see
+/// [`RemoteSignerError::double_sign`]).
+pub trait Signer {
+    /// Get the consensus public key for the given chain
+    fn public_key(&mut self, chain_id: chain::Id)
+        -> Result<public_key::Ed25519, RemoteSignerError>;
+
+    /// Sign the requested vote, returning it with its signature set, and its
+    /// extension signature if the request has extension sign bytes (see
+    /// [`SignVoteRequest::to_extension_signable_bytes`], with the
+    /// `vote-extensions` feature)
+    fn sign_vote(&mut self, request: SignVoteRequest) -> Result<Vote, RemoteSignerError>;
+
+    /// Sign the requested proposal, returning it with its signature set
+    fn sign_proposal(
+        &mut self,
+        request: SignProposalRequest,
+    ) -> Result<Proposal, RemoteSignerError>;
+}
+
+/// [`Signer`] which only signs for the given chains, refusing requests for
+/// any other chain before they reach the inner signer
+#[derive(Clone, Debug)]
+pub struct ChainIdFilter<S> {
+    chain_ids: Vec<chain::Id>,
+    signer: S,
+}
+
+impl<S> ChainIdFilter<S> {
+    /// Only sign for the given chains with the given signer
+    pub fn new<I>(chain_ids: I, signer: S) -> Self
+    where
+        I: IntoIterator<Item = chain::Id>,
+    {
+        ChainIdFilter {
+            chain_ids: chain_ids.into_iter().collect(),
+            signer,
+        }
+    }
+
+    /// Get the chains signed for
+    pub fn chain_ids(&self) -> &[chain::Id] {
+        &self.chain_ids
+    }
+
+    /// Get the inner signer
+    pub fn inner(&self) -> &S {
+        &self.signer
+    }
+}
+
+impl<S: Signer> Signer for ChainIdFilter<S> {
+    fn public_key(
+        &mut self,
+        chain_id: chain::Id,
+    ) -> Result<public_key::Ed25519, RemoteSignerError> {
+        check_chain_id(&self.chain_ids, &chain_id)?;
+        self.signer.public_key(chain_id)
+    }
+
+    fn sign_vote(&mut self, request: SignVoteRequest) -> Result<Vote, RemoteSignerError> {
+        check_chain_id(&self.chain_ids, &request.chain_id)?;
+        self.signer.sign_vote(request)
+    }
+
+    fn sign_proposal(
+        &mut self,
+        request: SignProposalRequest,
+    ) -> Result<Proposal, RemoteSignerError> {
+        check_chain_id(&self.chain_ids, &request.chain_id)?;
+        self.signer.sign_proposal(request)
+    }
+}
+
+/// Opens connections to the validator
+pub trait Connector {
+    /// Connection to the validator
+    type Stream: Read + Write;
+
+    /// Connect to the validator
+    fn connect(&mut self) -> io::Result<Self::Stream>;
+
+    /// Apply the given timeouts to the connections opened from now on.
+    ///
+    /// Connectors which can't apply them ignore them, which is the default:
+    /// closures can't, so a closure must set its own timeouts.
+    fn set_timeouts(&mut self, _timeouts: Timeouts) {}
+}
+
+impl<F, S> Connector for F
+where
+    F: FnMut() -> io::Result<S>,
+    S: Read + Write,
+{
+    type Stream = S;
+
+    fn connect(&mut self) -> io::Result<S> {
+        self()
+    }
+}
+
+/// Connects to a validator listening on a UNIX domain socket
+#[cfg(unix)]
+#[derive(Clone, Debug)]
+pub struct UnixConnector {
+    /// Path to the socket
+    pub path: std::path::PathBuf,
+
+    /// Timeouts of the connections. UNIX domain sockets have no handshake,
+    /// and connecting fails right away when nothing listens on the socket,
+    /// so only the read and write timeouts apply.
+    pub timeouts: Timeouts,
+}
+
+#[cfg(unix)]
+impl UnixConnector {
+    /// Connect to the given `unix://` address, e.g. a validator's
+    /// `priv_validator_laddr`
+    pub fn from_address(address: &net::Address) -> Result<Self, Error> {
+        match address {
+            net::Address::Unix { path } => Ok(UnixConnector {
+                path: path.clone(),
+                timeouts: Timeouts::default(),
+            }),
+            net::Address::Tcp { .. } => fail!(
+                Kind::Protocol,
+                "{} requires a secret connection, which needs a custom connector",
+                address
+            ),
+        }
+    }
+}
+
+#[cfg(unix)]
+impl Connector for UnixConnector {
+    type Stream = std::os::unix::net::UnixStream;
+
+    fn connect(&mut self) -> io::Result<Self::Stream> {
+        let stream = std::os::unix::net::UnixStream::connect(&self.path)?;
+        stream.set_read_timeout(self.timeouts.read)?;
+        stream.set_write_timeout(self.timeouts.write)?;
+        Ok(stream)
+    }
+
+    fn set_timeouts(&mut self, timeouts: Timeouts) {
+        self.timeouts = timeouts;
+    }
+}
+
+/// Connects to a validator listening on TCP, securing the connections with
+/// Tendermint's secret connection handshake
+#[cfg(feature = "secret-connection")]
+#[cfg_attr(docsrs, doc(cfg(feature = "secret-connection")))]
+pub struct TcpConnector {
+    /// Hostname or IP address of the validator
+    pub host: String,
+
+    /// Port of the validator
+    pub port: u16,
+
+    /// ID of the validator's node key, checked after the handshake if set
+    pub peer_id: Option<crate::node::Id>,
+
+    /// Key the client authenticates with
+    pub identity: ed25519_dalek::Keypair,
+
+    /// Timeouts of the connections. The handshake timeout applies to each
+    /// read and write of the handshake, after which the connection uses the
+    /// read and write timeouts.
+    pub timeouts: Timeouts,
+}
+
+#[cfg(feature = "secret-connection")]
+impl TcpConnector {
+    /// Connect to the given `tcp://` address, e.g. a validator's
+    /// `priv_validator_laddr`, authenticating with the given key
+    pub fn from_address(
+        address: &net::Address,
+        identity: ed25519_dalek::Keypair,
+    ) -> Result<Self, Error> {
+        match address {
+            net::Address::Tcp {
+                peer_id,
+                host,
+                port,
+            } => Ok(TcpConnector {
+                host: host.clone(),
+                port: *port,
+                peer_id: *peer_id,
+                identity,
+                timeouts: Timeouts::default(),
+            }),
+            net::Address::Unix { .. } => fail!(
+                Kind::Protocol,
+                "{} isn't a TCP address (see `UnixConnector`)",
+                address
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "secret-connection")]
+impl Connector for TcpConnector {
+    type Stream = crate::p2p::secret_connection::SecretConnection<std::net::TcpStream>;
+
+    fn connect(&mut self) -> io::Result<Self::Stream> {
+        let stream = std::net::TcpStream::connect((self.host.as_str(), self.port))?;
+        stream.set_nodelay(true)?;
+
+        // Socket options are shared with the stream the handshake consumes
+        let socket = stream.try_clone()?;
+        socket.set_read_timeout(self.timeouts.handshake)?;
+        socket.set_write_timeout(self.timeouts.handshake)?;
+
+        let identity = ed25519_dalek::Keypair::from_bytes(&self.identity.to_bytes())
+            .expect("Ed25519 keypair round trips");
+        let connection = crate::p2p::secret_connection::SecretConnection::new(stream, identity)
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+
+        if let Some(peer_id) = self.peer_id {
+            let remote_peer_id = connection.remote_peer_id();
+
+            if remote_peer_id != peer_id {
+                return Err(io::Error::new(
+                    io::ErrorKind::PermissionDenied,
+                    format!("expected peer {}, connected to {}", peer_id, remote_peer_id),
+                ));
+            }
+        }
+
+        socket.set_read_timeout(self.timeouts.read)?;
+        socket.set_write_timeout(self.timeouts.write)?;
+        Ok(connection)
+    }
+
+    fn set_timeouts(&mut self, timeouts: Timeouts) {
+        self.timeouts = timeouts;
+    }
+}
+
+/// Timeouts of a connection to the validator. None are set by default.
+///
+/// Tendermint pings its remote signer whenever it has no request to send
+/// (every 2/3 of its own read/write timeout, 5 seconds by default), so a read
+/// timeout a little above that detects a validator gone silent, e.g. behind
+/// a broken network link, which the client then reconnects to.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct Timeouts {
+    /// Maximum time to wait for the next request from the validator
+    pub read: Option<Duration>,
+
+    /// Maximum time to wait for a response to be written
+    pub write: Option<Duration>,
+
+    /// Maximum time to establish a connection, including its handshake
+    /// (e.g. Tendermint's secret connection handshake over TCP)
+    pub handshake: Option<Duration>,
+}
+
+/// Exponential backoff between reconnection attempts
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Backoff {
+    /// Delay before the first retry
+    pub min: Duration,
+
+    /// Maximum delay between retries
+    pub max: Duration,
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Backoff {
+            min: Duration::from_millis(100),
+            max: Duration::from_secs(10),
+        }
+    }
+}
+
+impl Backoff {
+    /// Delay before the given retry, counting from zero
+    pub fn delay(&self, retry: u32) -> Duration {
+        let factor = 1u32.checked_shl(retry).unwrap_or_else(u32::max_value);
+        self.min
+            .checked_mul(factor)
+            .map_or(self.max, |delay| delay.min(self.max))
+    }
+}
+
+/// Change of the state of a [`Client`]'s connection to the validator
+#[derive(Debug)]
+pub enum Event<'a> {
+    /// Connecting, after the given number of consecutive failed attempts
+    Connecting {
+        /// Number of consecutive failed attempts
+        retry: u32,
+    },
+
+    /// Connected: the validator's requests are being served
+    Connected,
+
+    /// The connection failed, timed out or was closed, or couldn't be made
+    Disconnected {
+        /// Why the connection was lost
+        error: &'a Error,
+    },
+
+    /// Waiting before reconnecting
+    Backoff {
+        /// Time until the next attempt
+        delay: Duration,
+    },
+}
+
+/// Remote signer client
+pub struct Client<C, S> {
+    connector: C,
+    signer: S,
+    backoff: Backoff,
+    max_retries: Option<u32>,
+    on_event: Option<Box<dyn FnMut(&Event<'_>) + Send>>,
+}
+
+impl<C, S> Client<C, S>
+where
+    C: Connector,
+    S: Signer,
+{
+    /// Create a client which connects with the given connector and signs
+    /// with the given signer, retrying forever with the default backoff
+    pub fn new(connector: C, signer: S) -> Self {
+        Client {
+            connector,
+            signer,
+            backoff: Backoff::default(),
+            max_retries: None,
+            on_event: None,
+        }
+    }
+
+    /// Set the backoff between reconnection attempts
+    pub fn backoff(mut self, backoff: Backoff) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Give up after the given number of consecutive failed attempts
+    pub fn max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = Some(max_retries);
+        self
+    }
+
+    /// Set the timeouts of the connections to the validator (see
+    /// [`Connector::set_timeouts`]). Timing out drops the connection, which
+    /// is then reestablished like any other failed connection.
+    pub fn timeouts(mut self, timeouts: Timeouts) -> Self {
+        self.connector.set_timeouts(timeouts);
+        self
+    }
+
+    /// Call the given function on each change of the connection state, e.g.
+    /// to log it or export it as a metric
+    pub fn on_event<F>(mut self, on_event: F) -> Self
+    where
+        F: FnMut(&Event<'_>) + Send + 'static,
+    {
+        self.on_event = Some(Box::new(on_event));
+        self
+    }
+
+    /// Get the signer
+    pub fn signer(&self) -> &S {
+        &self.signer
+    }
+
+    /// Connect to the validator and serve its requests, reconnecting
+    /// whenever the connection fails, times out or is closed.
+    ///
+    /// Only returns once the maximum number of retries is exhausted, with the
+    /// last error.
+    pub fn run(&mut self) -> Result<(), Error> {
+        let mut retry = 0;
+
+        loop {
+            self.emit(&Event::Connecting { retry });
+
+            let error = match self.connector.connect() {
+                Ok(mut stream) => {
+                    retry = 0;
+                    self.emit(&Event::Connected);
+
+                    match serve(&mut stream, &mut self.signer) {
+                        Ok(()) => Kind::Io.into(),
+                        Err(e) => e,
+                    }
+                }
+                Err(e) => Kind::Io.context(e).into(),
+            };
+
+            self.emit(&Event::Disconnected { error: &error });
+
+            if self.max_retries.map_or(false, |max| retry >= max) {
+                return Err(error);
+            }
+
+            let delay = self.backoff.delay(retry);
+            self.emit(&Event::Backoff { delay });
+            thread::sleep(delay);
+            retry = retry.saturating_add(1);
+        }
+    }
+
+    fn emit(&mut self, event: &Event<'_>) {
+        if let Some(on_event) = self.on_event.as_mut() {
+            on_event(event);
+        }
+    }
+}
+
+/// Serve requests read from the given connection until it is closed.
+///
+/// Batches of requests are answered with a batch of responses in the same
+/// order.
+pub fn serve<T, S>(stream: &mut T, signer: &mut S) -> Result<(), Error>
+where
+    T: Read + Write,
+    S: Signer,
+{
+    loop {
+        match codec::read_frame(stream) {
+            Ok(frame) => respond(stream, signer, frame)?,
+            Err(e) if is_eof(&e) => return Ok(()),
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Answer a single frame of requests from the validator, e.g. the first one
+/// read by [`Protocol::negotiate`]
+///
+/// [`Protocol::negotiate`]: super::Protocol::negotiate
+pub fn respond<W, S>(writer: &mut W, signer: &mut S, frame: Frame) -> Result<(), Error>
+where
+    W: Write,
+    S: Signer,
+{
+    let response = match frame {
+        Frame::Message(request) => handle_request(signer, request)?.into(),
+        Frame::Batch(Batch::Requests(requests)) => Batch::Responses(
+            requests
+                .into_iter()
+                .map(|request| handle_request(signer, request))
+                .collect::<Result<_, _>>()?,
+        )
+        .into(),
+        Frame::Batch(Batch::Responses(_)) => fail!(
+            Kind::Protocol,
+            "unexpected batch of responses from validator"
+        ),
+    };
+
+    codec::write_frame(writer, response)
+}
+
+/// Handle a single request from the validator, returning the response
+pub fn handle_request<S: Signer>(signer: &mut S, request: Message) -> Result<Message, Error> {
+    let response: Message = match request {
+        Message::PubKeyRequest(request) => match signer.public_key(request.chain_id) {
+            Ok(pub_key) => PubKeyResponse::PublicKey(pub_key),
+            Err(e) => PubKeyResponse::Error(e),
+        }
+        .into(),
+        Message::SignVoteRequest(request) => match signer.sign_vote(request) {
+            Ok(vote) => SignedVoteResponse::Vote(vote),
+            Err(e) => SignedVoteResponse::Error(e),
+        }
+        .into(),
+        Message::SignProposalRequest(request) => match signer.sign_proposal(request) {
+            Ok(proposal) => SignedProposalResponse::Proposal(proposal),
+            Err(e) => SignedProposalResponse::Error(e),
+        }
+        .into(),
+        Message::PingRequest => Message::PingResponse,
+        other => fail!(
+            Kind::Protocol,
+            "unexpected message from validator: {:?}",
+            other
+        ),
+    };
+
+    Ok(response)
+}
+
+/// Did reading fail because the connection was closed?
+pub(super) fn is_eof(error: &Error) -> bool {
+    let mut source: Option<&(dyn std::error::Error + 'static)> = Some(error.as_ref());
+
+    while let Some(e) = source {
+        if let Some(e) = e.downcast_ref::<io::Error>() {
+            return e.kind() == io::ErrorKind::UnexpectedEof;
+        }
+        source = e.source();
+    }
+
+    false
+}
+
+#[cfg(all(test, unix))]
+pub(crate) mod tests {
+    use super::*;
+    use crate::{
+        account, block,
+        privval::{PubKeyRequest, RemoteErrorCode},
+        signature::Ed25519,
+        vote, Signature, Time,
+    };
+    use ed25519_dalek::{Keypair, SecretKey, Signer as _};
+    use std::{os::unix::net::UnixStream, str::FromStr};
+
+    /// Signs with an in-memory key, refusing to sign votes below height 10
+    pub(crate) struct SoftSigner(pub(crate) Keypair);
+
+    impl Signer for SoftSigner {
+        fn public_key(&mut self, _: chain::Id) -> Result<public_key::Ed25519, RemoteSignerError> {
+            Ok(self.0.public)
+        }
+
+        fn sign_vote(&mut self, request: SignVoteRequest) -> Result<Vote, RemoteSignerError> {
+            if request.vote.height.value() < 10 {
+                return Err(RemoteSignerError::double_sign(request.vote.height));
+            }
+
+            #[cfg(feature = "vote-extensions")]
+            let extension_signature = request
+                .to_extension_signable_bytes()
+                .map(|bytes| self.0.sign(&bytes).into());
+
+            let signature = self.0.sign(&request.to_signable_bytes());
+            Ok(Vote {
+                signature: signature.into(),
+                #[cfg(feature = "vote-extensions")]
+                extension_signature,
+                ..request.vote
+            })
+        }
+
+        fn sign_proposal(
+            &mut self,
+            request: SignProposalRequest,
+        ) -> Result<Proposal, RemoteSignerError> {
+            let signature = self.0.sign(&request.to_signable_bytes());
+            Ok(Proposal {
+                signature: signature.into(),
+                ..request.proposal
+            })
+        }
+    }
+
+    pub(crate) fn soft_signer() -> SoftSigner {
+        let secret = SecretKey::from_bytes(&[0x42; 32]).unwrap();
+        let public = (&secret).into();
+        SoftSigner(Keypair { secret, public })
+    }
+
+    pub(crate) fn vote_at(height: u64) -> Vote {
+        Vote {
+            vote_type: vote::Type::Prevote,
+            height: block::Height::from(height),
+            round: 0,
+            block_id: None,
+            timestamp: Time::from_str("2020-09-01T12:34:56Z").unwrap(),
+            validator_address: account::Id::new([0xa3; 20]),
+            validator_index: 0,
+            signature: Signature::Ed25519(Ed25519::new([0; 64])),
+            #[cfg(feature = "vote-extensions")]
+            extension: vec![],
+            #[cfg(feature = "vote-extensions")]
+            extension_signature: None,
+        }
+    }
+
+    #[test]
+    fn serves_requests_until_closed() {
+        let (mut validator, mut kms) = UnixStream::pair().unwrap();
+        let signer = thread::spawn(move || {
+            let mut signer = soft_signer();
+            serve(&mut kms, &mut signer)
+        });
+
+        let chain_id = chain::Id::from("test_chain_id");
+        let pub_key = soft_signer().0.public;
+
+        codec::write_message(&mut validator, PubKeyRequest { chain_id }.into()).unwrap();
+        assert_eq!(
+            codec::read_message(&mut validator).unwrap(),
+            Message::from(PubKeyResponse::PublicKey(pub_key))
+        );
+
+        let request = SignVoteRequest::new(vote_at(10), chain_id);
+        codec::write_message(&mut validator, request.into()).unwrap();
+        match codec::read_message(&mut validator).unwrap() {
+            Message::SignedVoteResponse(SignedVoteResponse::Vote(vote)) => {
+                assert!(vote.verify(chain_id, &pub_key.into()).is_ok())
+            }
+            other => panic!("unexpected response: {:?}", other),
+        }
+
+        let request = SignVoteRequest::new(vote_at(9), chain_id);
+        codec::write_message(&mut validator, request.into()).unwrap();
+        assert!(matches!(
+            codec::read_message(&mut validator).unwrap(),
+            Message::SignedVoteResponse(SignedVoteResponse::Error(_))
+        ));
+
+        codec::write_message(&mut validator, Message::PingRequest).unwrap();
+        assert_eq!(
+            codec::read_message(&mut validator).unwrap(),
+            Message::PingResponse
+        );
+
+        drop(validator);
+        assert!(signer.join().unwrap().is_ok());
+    }
+
+    #[cfg(feature = "vote-extensions")]
+    #[test]
+    fn signs_vote_extensions() {
+        let (mut validator, mut kms) = UnixStream::pair().unwrap();
+        let signer = thread::spawn(move || {
+            let mut signer = soft_signer();
+            serve(&mut kms, &mut signer)
+        });
+
+        let chain_id = chain::Id::from("test_chain_id");
+        let pub_key = crate::PublicKey::from(soft_signer().0.public);
+
+        let mut precommit = vote_at(10);
+        precommit.vote_type = vote::Type::Precommit;
+        precommit.block_id = Some(block::Id::new(crate::hash::Hash::Sha256([0x42; 32]), None));
+        precommit.extension = b"extension".to_vec();
+
+        let request = SignVoteRequest::new(precommit.clone(), chain_id);
+        codec::write_message(&mut validator, request.into()).unwrap();
+        match codec::read_message(&mut validator).unwrap() {
+            Message::SignedVoteResponse(SignedVoteResponse::Vote(vote)) => {
+                assert!(vote.verify(chain_id, &pub_key).is_ok());
+                assert!(vote.verify_extension(chain_id, &pub_key).is_ok());
+            }
+            other => panic!("unexpected response: {:?}", other),
+        }
+
+        let mut request = SignVoteRequest::new(precommit, chain_id);
+        request.skip_extension_signing = true;
+        codec::write_message(&mut validator, request.into()).unwrap();
+        match codec::read_message(&mut validator).unwrap() {
+            Message::SignedVoteResponse(SignedVoteResponse::Vote(vote)) => {
+                assert!(vote.verify(chain_id, &pub_key).is_ok());
+                assert_eq!(vote.extension_signature, None);
+            }
+            other => panic!("unexpected response: {:?}", other),
+        }
+
+        drop(validator);
+        assert!(signer.join().unwrap().is_ok());
+    }
+
+    #[test]
+    fn refuses_unknown_chains() {
+        let chain_id = chain::Id::from("test_chain_id");
+        let mut signer = ChainIdFilter::new(vec![chain_id], soft_signer());
+
+        let request = SignVoteRequest::new(vote_at(10), chain_id);
+        assert!(matches!(
+            handle_request(&mut signer, request.into()).unwrap(),
+            Message::SignedVoteResponse(SignedVoteResponse::Vote(_))
+        ));
+
+        let other_chain = chain::Id::from("other_chain_id");
+        let request = SignVoteRequest::new(vote_at(10), other_chain);
+        match handle_request(&mut signer, request.into()).unwrap() {
+            Message::SignedVoteResponse(SignedVoteResponse::Error(e)) => {
+                assert_eq!(e.code(), Some(RemoteErrorCode::ChainIdMismatch))
+            }
+            other => panic!("unexpected response: {:?}", other),
+        }
+
+        let request = PubKeyRequest {
+            chain_id: other_chain,
+        };
+        assert!(matches!(
+            handle_request(&mut signer, request.into()).unwrap(),
+            Message::PubKeyResponse(PubKeyResponse::Error(_))
+        ));
+    }
+
+    #[test]
+    fn gives_up_after_max_retries() {
+        let mut attempts = 0;
+        let connector = || {
+            attempts += 1;
+            Err::<UnixStream, _>(io::Error::from(io::ErrorKind::ConnectionRefused))
+        };
+
+        let backoff = Backoff {
+            min: Duration::from_millis(1),
+            max: Duration::from_millis(1),
+        };
+
+        let result = Client::new(connector, soft_signer())
+            .backoff(backoff)
+            .max_retries(2)
+            .run();
+
+        assert!(result.is_err());
+        assert_eq!(attempts, 3);
+    }
+
+    #[test]
+    fn reconnects_when_validator_is_silent() {
+        let path = std::env::temp_dir().join(format!(
+            "tendermint-privval-client-{}.sock",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+        let listener = std::os::unix::net::UnixListener::bind(&path).unwrap();
+
+        let connector =
+            UnixConnector::from_address(&net::Address::Unix { path: path.clone() }).unwrap();
+        let events = std::sync::Arc::new(std::sync::Mutex::new(vec![]));
+        let mut client = Client::new(connector, soft_signer())
+            .backoff(Backoff {
+                min: Duration::from_millis(1),
+                max: Duration::from_millis(1),
+            })
+            .max_retries(1)
+            .timeouts(Timeouts {
+                read: Some(Duration::from_millis(50)),
+                ..Timeouts::default()
+            })
+            .on_event({
+                let events = events.clone();
+                move |event| {
+                    events.lock().unwrap().push(match event {
+                        Event::Connecting { retry } => format!("connecting {}", retry),
+                        Event::Connected => "connected".to_owned(),
+                        Event::Disconnected { .. } => "disconnected".to_owned(),
+                        Event::Backoff { .. } => "backoff".to_owned(),
+                    })
+                }
+            });
+        let signer = thread::spawn(move || client.run());
+
+        // Accept the client, then send it nothing and stop listening, so that
+        // it times out and fails to reconnect
+        let (_validator, _) = listener.accept().unwrap();
+        drop(listener);
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(signer.join().unwrap().is_err());
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec![
+                "connecting 0",
+                "connected",
+                "disconnected",
+                "backoff",
+                "connecting 1",
+                "disconnected"
+            ]
+        );
+    }
+
+    #[cfg(feature = "secret-connection")]
+    #[test]
+    fn handshake_times_out() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = net::Address::Tcp {
+            peer_id: None,
+            host: "127.0.0.1".to_owned(),
+            port: listener.local_addr().unwrap().port(),
+        };
+
+        let mut connector = TcpConnector::from_address(&address, soft_signer().0).unwrap();
+        connector.set_timeouts(Timeouts {
+            handshake: Some(Duration::from_millis(50)),
+            ..Timeouts::default()
+        });
+
+        // The validator accepts the connection but never answers the
+        // handshake
+        let validator = thread::spawn(move || listener.accept().unwrap());
+        assert!(connector.connect().is_err());
+        drop(validator.join().unwrap());
+    }
+
+    #[test]
+    fn backoff_is_capped() {
+        let backoff = Backoff::default();
+        assert_eq!(backoff.delay(0), Duration::from_millis(100));
+        assert_eq!(backoff.delay(3), Duration::from_millis(800));
+        assert_eq!(backoff.delay(10), Duration::from_secs(10));
+        assert_eq!(backoff.delay(100), Duration::from_secs(10));
+    }
+}
@@ -0,0 +1,363 @@
+//! Framing of privval messages on a connection: like Tendermint's `protoio`,
+//! each message is prefixed with its length as an unsigned varint.
+//!
+//! Frames can also hold a [`Batch`] of messages, which Tendermint doesn't
+//! support.
+//!
+//! [`Batch`]: super::Batch
+
+use super::{Frame, Message};
+use crate::{error::Kind, Error};
+use anomaly::{fail, format_err};
+use futures::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use std::io::{Read, Write};
+
+/// Maximum size of an encoded message, as enforced by Tendermint
+pub const MAX_MESSAGE_SIZE: usize = 10 * 1024;
+
+/// Read a length-prefixed message
+pub fn read_message<R: Read>(reader: &mut R) -> Result<Message, Error> {
+    single(read_frame(reader)?)
+}
+
+/// Write a length-prefixed message
+pub fn write_message<W: Write>(writer: &mut W, message: Message) -> Result<(), Error> {
+    write_frame(writer, message.into())
+}
+
+/// Read a length-prefixed message from an asynchronous reader
+pub async fn read_message_async<R>(reader: &mut R) -> Result<Message, Error>
+where
+    R: AsyncRead + Unpin,
+{
+    single(read_frame_async(reader).await?)
+}
+
+/// Write a length-prefixed message to an asynchronous writer
+pub async fn write_message_async<W>(writer: &mut W, message: Message) -> Result<(), Error>
+where
+    W: AsyncWrite + Unpin,
+{
+    write_frame_async(writer, message.into()).await
+}
+
+/// Read a length-prefixed message or batch
+pub fn read_frame<R: Read>(reader: &mut R) -> Result<Frame, Error> {
+    Codec::default().read_frame(reader)
+}
+
+/// Read a length-prefixed frame without decoding it, returning its contents
+/// (e.g. to detect its [`Protocol`] first)
+///
+/// [`Protocol`]: super::Protocol
+pub fn read_frame_bytes<R: Read>(reader: &mut R) -> Result<Vec<u8>, Error> {
+    Codec::default().read_frame_bytes(reader)
+}
+
+/// Write a length-prefixed message or batch
+pub fn write_frame<W: Write>(writer: &mut W, frame: Frame) -> Result<(), Error> {
+    Codec::default().write_frame(writer, frame)
+}
+
+/// Read a length-prefixed message or batch from an asynchronous reader
+pub async fn read_frame_async<R>(reader: &mut R) -> Result<Frame, Error>
+where
+    R: AsyncRead + Unpin,
+{
+    Codec::default().read_frame_async(reader).await
+}
+
+/// Write a length-prefixed message or batch to an asynchronous writer
+pub async fn write_frame_async<W>(writer: &mut W, frame: Frame) -> Result<(), Error>
+where
+    W: AsyncWrite + Unpin,
+{
+    Codec::default().write_frame_async(writer, frame).await
+}
+
+/// Framing with a maximum frame size, which the free functions of this
+/// module enforce with the default of [`MAX_MESSAGE_SIZE`].
+///
+/// Frames are read defensively: lengths above the maximum are refused as
+/// soon as their prefix exceeds it, before anything is allocated, and the
+/// buffer of a frame only grows as its contents arrive. A connection closed
+/// between frames fails with an I/O error of kind `UnexpectedEof`, while one
+/// closed in the middle of a frame fails with a [`Kind::Parse`] error.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Codec {
+    max_frame_size: usize,
+}
+
+impl Default for Codec {
+    fn default() -> Self {
+        Codec::new(MAX_MESSAGE_SIZE)
+    }
+}
+
+impl Codec {
+    /// Frame messages of at most the given size in bytes (excluding their
+    /// length prefix), e.g. to allow large batches
+    pub fn new(max_frame_size: usize) -> Self {
+        Codec { max_frame_size }
+    }
+
+    /// Get the maximum frame size in bytes
+    pub fn max_frame_size(&self) -> usize {
+        self.max_frame_size
+    }
+
+    /// Read a length-prefixed message or batch
+    pub fn read_frame<R: Read>(&self, reader: &mut R) -> Result<Frame, Error> {
+        Frame::decode(&self.read_frame_bytes(reader)?)
+    }
+
+    /// Read a length-prefixed frame without decoding it, returning its
+    /// contents
+    pub fn read_frame_bytes<R: Read>(&self, reader: &mut R) -> Result<Vec<u8>, Error> {
+        let mut length = Length::new(self.max_frame_size);
+        let mut byte = [0u8];
+
+        let len = loop {
+            if let Err(e) = reader.read_exact(&mut byte) {
+                return Err(length.eof(e));
+            }
+
+            if let Some(len) = length.push(byte[0])? {
+                break len;
+            }
+        };
+
+        let mut bytes = Vec::new();
+        reader
+            .take(len as u64)
+            .read_to_end(&mut bytes)
+            .map_err(|e| Kind::Io.context(e))?;
+
+        check_complete(&bytes, len)?;
+        Ok(bytes)
+    }
+
+    /// Write a length-prefixed message or batch
+    pub fn write_frame<W: Write>(&self, writer: &mut W, frame: Frame) -> Result<(), Error> {
+        let bytes = self.encode(frame)?;
+
+        writer
+            .write_all(&bytes)
+            .and_then(|_| writer.flush())
+            .map_err(|e| Kind::Io.context(e))?;
+
+        Ok(())
+    }
+
+    /// Read a length-prefixed message or batch from an asynchronous reader
+    pub async fn read_frame_async<R>(&self, reader: &mut R) -> Result<Frame, Error>
+    where
+        R: AsyncRead + Unpin,
+    {
+        let mut length = Length::new(self.max_frame_size);
+        let mut byte = [0u8];
+
+        let len = loop {
+            if let Err(e) = reader.read_exact(&mut byte).await {
+                return Err(length.eof(e));
+            }
+
+            if let Some(len) = length.push(byte[0])? {
+                break len;
+            }
+        };
+
+        let mut bytes = Vec::new();
+        reader
+            .take(len as u64)
+            .read_to_end(&mut bytes)
+            .await
+            .map_err(|e| Kind::Io.context(e))?;
+
+        check_complete(&bytes, len)?;
+        Frame::decode(&bytes)
+    }
+
+    /// Write a length-prefixed message or batch to an asynchronous writer
+    pub async fn write_frame_async<W>(&self, writer: &mut W, frame: Frame) -> Result<(), Error>
+    where
+        W: AsyncWrite + Unpin,
+    {
+        let bytes = self.encode(frame)?;
+
+        writer
+            .write_all(&bytes)
+            .await
+            .map_err(|e| Kind::Io.context(e))?;
+        writer.flush().await.map_err(|e| Kind::Io.context(e))?;
+
+        Ok(())
+    }
+
+    /// Encode a frame with its length prefix, refusing to send frames the
+    /// peer would refuse
+    fn encode(&self, frame: Frame) -> Result<Vec<u8>, Error> {
+        let bytes = frame.encode_length_delimited()?;
+        let len = prost::encoding::decode_varint(&mut bytes.as_slice())
+            .map_err(|e| Kind::Parse.context(e))? as usize;
+
+        if len > self.max_frame_size {
+            fail!(
+                Kind::Length,
+                "message of {} bytes exceeds maximum of {}",
+                len,
+                self.max_frame_size
+            );
+        }
+
+        Ok(bytes)
+    }
+}
+
+fn single(frame: Frame) -> Result<Message, Error> {
+    match frame {
+        Frame::Message(message) => Ok(message),
+        Frame::Batch(_) => fail!(Kind::Protocol, "unexpected batch"),
+    }
+}
+
+/// Check a frame's contents were read in full
+fn check_complete(bytes: &[u8], len: usize) -> Result<(), Error> {
+    if bytes.len() < len {
+        fail!(
+            Kind::Parse,
+            "truncated frame: got {} of {} bytes",
+            bytes.len(),
+            len
+        );
+    }
+
+    Ok(())
+}
+
+/// Unsigned varint length prefix, read a byte at a time
+struct Length {
+    value: u64,
+    bytes: u32,
+    max: u64,
+}
+
+impl Length {
+    fn new(max: usize) -> Self {
+        Length {
+            value: 0,
+            bytes: 0,
+            max: max as u64,
+        }
+    }
+
+    /// Add the next byte, returning the length once it is complete
+    fn push(&mut self, byte: u8) -> Result<Option<usize>, Error> {
+        if self.bytes == 10 || (self.bytes == 9 && byte > 1) {
+            fail!(Kind::Parse, "malformed message length prefix");
+        }
+
+        self.value |= u64::from(byte & 0x7f) << (7 * self.bytes);
+        self.bytes += 1;
+
+        // The remaining bytes can only add to the length, so there's no need
+        // to wait for them to refuse it
+        if self.value > self.max {
+            fail!(
+                Kind::Length,
+                "message of at least {} bytes exceeds maximum of {}",
+                self.value,
+                self.max
+            );
+        }
+
+        if byte & 0x80 != 0 {
+            return Ok(None);
+        }
+
+        Ok(Some(self.value as usize))
+    }
+
+    /// Error for a connection closed while reading the length prefix: a
+    /// closed connection if no byte of the prefix was read, a truncated frame
+    /// otherwise
+    fn eof(&self, error: std::io::Error) -> Error {
+        if self.bytes == 0 || error.kind() != std::io::ErrorKind::UnexpectedEof {
+            Kind::Io.context(error).into()
+        } else {
+            format_err!(Kind::Parse, "truncated frame length prefix").into()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{chain, privval::PubKeyRequest};
+
+    #[test]
+    fn round_trip() {
+        let request = Message::from(PubKeyRequest {
+            chain_id: chain::Id::from("test_chain_id"),
+        });
+
+        let mut wire = vec![];
+        write_message(&mut wire, request.clone()).unwrap();
+        write_message(&mut wire, Message::PingRequest).unwrap();
+
+        let mut reader = wire.as_slice();
+        assert_eq!(read_message(&mut reader).unwrap(), request);
+        assert_eq!(read_message(&mut reader).unwrap(), Message::PingRequest);
+        assert!(read_message(&mut reader).is_err());
+    }
+
+    #[test]
+    fn rejects_oversized_messages() {
+        let mut wire = vec![0x80, 0x80, 0x01]; // 16 KiB
+        wire.resize(wire.len() + 16 * 1024, 0);
+        assert!(read_message(&mut wire.as_slice()).is_err());
+    }
+
+    #[test]
+    fn enforces_configured_maximum() {
+        let request = Message::from(PubKeyRequest {
+            chain_id: chain::Id::from("test_chain_id"),
+        });
+        let codec = Codec::new(8);
+
+        let mut wire = vec![];
+        assert!(codec
+            .write_frame(&mut wire, request.clone().into())
+            .is_err());
+        assert!(wire.is_empty());
+
+        write_message(&mut wire, request).unwrap();
+        assert!(codec.read_frame(&mut wire.as_slice()).is_err());
+
+        // Huge lengths are refused before the frame is read
+        let huge = [0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01];
+        let err = codec.read_frame_bytes(&mut huge.as_ref()).unwrap_err();
+        assert_eq!(crate::error::kind(err.as_ref()), Some(&Kind::Length));
+    }
+
+    #[test]
+    fn rejects_truncated_frames() {
+        let mut wire = vec![];
+        write_message(&mut wire, Message::PingRequest).unwrap();
+
+        // Closing the connection between frames...
+        let mut reader = wire.as_slice();
+        assert_eq!(read_message(&mut reader).unwrap(), Message::PingRequest);
+        let closed = read_message(&mut reader).unwrap_err();
+        assert_eq!(crate::error::kind(closed.as_ref()), Some(&Kind::Io));
+
+        // ...isn't the same as in the middle of one
+        let mut wire = vec![0x80, 0x01]; // 128 bytes
+        wire.resize(wire.len() + 100, 0);
+        let truncated = read_frame_bytes(&mut wire.as_slice()).unwrap_err();
+        assert_eq!(crate::error::kind(truncated.as_ref()), Some(&Kind::Parse));
+
+        let truncated = read_frame_bytes(&mut [0x80].as_ref()).unwrap_err();
+        assert_eq!(crate::error::kind(truncated.as_ref()), Some(&Kind::Parse));
+    }
+}
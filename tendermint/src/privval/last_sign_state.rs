@@ -0,0 +1,379 @@
+//! Double signing protection: the last height, round and step a validator
+//! signed at, persisted in the format of Tendermint's
+//! `priv_validator_state.json`:
+//!
+//! <https://github.com/tendermint/tendermint/blob/v0.34.0/privval/file.go#L78-L89>
+//!
+//! The block ID is stored alongside, so signing the same block again at the
+//! same step (e.g. after the validator lost a response) is allowed, while
+//! signing a different one is refused. Nil votes are stored with a `null`
+//! block ID: a state file without a block ID, such as one written by
+//! Tendermint, leaves the block signed at its step unknown, so signing again
+//! at that step is refused altogether.
+
+use super::validate::{check_hrs, check_hrs_unknown_block, proposal_state, vote_state};
+use crate::{
+    block,
+    consensus::{self, Step},
+    error::Kind,
+    proposal::Proposal,
+    vote, Error,
+};
+use anomaly::{fail, format_err};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::{
+    convert::TryFrom,
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+/// Last signed consensus state, persisted to a file
+#[derive(Clone, Debug)]
+pub struct LastSignState {
+    path: PathBuf,
+    state: Option<consensus::State>,
+    block_id_known: bool,
+}
+
+impl LastSignState {
+    /// Load the state from the given file, creating it (at height 0) if it
+    /// doesn't exist yet
+    pub fn load_or_create<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let path = path.as_ref().to_owned();
+
+        if !path.exists() {
+            let last_sign_state = LastSignState {
+                path,
+                state: None,
+                block_id_known: false,
+            };
+            last_sign_state.persist()?;
+            return Ok(last_sign_state);
+        }
+
+        let json = fs::read_to_string(&path)
+            .map_err(|e| format_err!(Kind::Io, "couldn't open {}: {}", path.display(), e))?;
+
+        let file: StateFile = serde_json::from_str(&json)
+            .map_err(|e| format_err!(Kind::Parse, "couldn't parse {}: {}", path.display(), e))?;
+
+        let block_id_known = file.block_id.is_some();
+        let state = file
+            .consensus_state()
+            .map_err(|e| format_err!(Kind::Parse, "couldn't parse {}: {}", path.display(), e))?;
+
+        Ok(LastSignState {
+            path,
+            state,
+            block_id_known,
+        })
+    }
+
+    /// Get the last signed state, or `None` if nothing was signed yet
+    pub fn state(&self) -> Option<&consensus::State> {
+        self.state.as_ref()
+    }
+
+    /// Is the block ID signed at the last signed state known? It isn't for
+    /// states imported from Tendermint, whose block ID is `None` even if a
+    /// block was signed.
+    pub fn block_id_known(&self) -> bool {
+        self.block_id_known
+    }
+
+    /// Check signing at the given state doesn't regress from, or conflict
+    /// with, the last signed state. The [`Rejection`] is the source of the
+    /// returned error.
+    ///
+    /// [`Rejection`]: super::Rejection
+    pub fn check(&self, new_state: &consensus::State) -> Result<(), Error> {
+        let last = match &self.state {
+            Some(last) => last,
+            None => return Ok(()),
+        };
+
+        let checked = if self.block_id_known {
+            check_hrs(last, new_state)
+        } else {
+            check_hrs_unknown_block(last, new_state)
+        };
+
+        checked.map_err(|e| Kind::DoubleSign.context(e).into())
+    }
+
+    /// Check the given state, then record it on disk.
+    ///
+    /// Signers must call this before releasing a signature, and must not
+    /// release it if this fails.
+    pub fn update(&mut self, new_state: consensus::State) -> Result<(), Error> {
+        self.check(&new_state)?;
+
+        let old_state = std::mem::replace(&mut self.state, Some(new_state));
+        let old_block_id_known = std::mem::replace(&mut self.block_id_known, true);
+
+        self.persist().map_err(|e| {
+            self.state = old_state;
+            self.block_id_known = old_block_id_known;
+            e
+        })
+    }
+
+    /// Check and record the state a vote is signed at
+    pub fn update_for_vote(&mut self, vote: &vote::Vote) -> Result<(), Error> {
+        self.update(vote_state(vote))
+    }
+
+    /// Check and record the state a proposal is signed at
+    pub fn update_for_proposal(&mut self, proposal: &Proposal) -> Result<(), Error> {
+        self.update(proposal_state(proposal))
+    }
+
+    /// Atomically write the state to disk: write a temporary file next to
+    /// the state file, sync it, then rename it over the state file
+    fn persist(&self) -> Result<(), Error> {
+        let json = serde_json::to_string_pretty(&StateFile::from(self))
+            .map_err(|e| format_err!(Kind::Parse, "couldn't serialize state: {}", e))?;
+
+        let mut tmp_path = self.path.clone().into_os_string();
+        tmp_path.push(".tmp");
+        let tmp_path = PathBuf::from(tmp_path);
+
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+        options
+            .open(&tmp_path)
+            .and_then(|mut file| {
+                file.write_all(json.as_bytes())?;
+                file.sync_all()
+            })
+            .and_then(|_| fs::rename(&tmp_path, &self.path))
+            .map_err(|e| {
+                format_err!(Kind::Io, "couldn't write {}: {}", self.path.display(), e).into()
+            })
+    }
+}
+
+/// `priv_validator_state.json` contents.
+///
+/// Tendermint's `signature` and `signbytes` fields aren't kept: without them
+/// Tendermint refuses to sign again at the same step, which is the safe
+/// choice.
+///
+/// Step 0 means nothing was signed yet. The block ID is missing if unknown,
+/// and `null` for nil votes.
+#[derive(Deserialize, Serialize)]
+struct StateFile {
+    height: block::Height,
+    round: i64,
+    step: i8,
+    #[serde(
+        default,
+        deserialize_with = "deserialize_present",
+        skip_serializing_if = "Option::is_none"
+    )]
+    block_id: Option<Option<block::Id>>,
+}
+
+/// Deserialize a field which is present, even if `null`, as `Some`
+fn deserialize_present<'de, D>(deserializer: D) -> Result<Option<Option<block::Id>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Option::<block::Id>::deserialize(deserializer).map(Some)
+}
+
+impl StateFile {
+    /// Get the last signed state, rejecting unknown steps
+    fn consensus_state(self) -> Result<Option<consensus::State>, Error> {
+        if self.step == 0 {
+            // Tendermint only writes step 0 before signing anything
+            if self.height.value() != 0 || self.round != 0 {
+                fail!(
+                    Kind::Parse,
+                    "step 0 at height {} and round {}",
+                    self.height,
+                    self.round
+                );
+            }
+
+            return Ok(None);
+        }
+
+        Ok(Some(consensus::State {
+            height: self.height,
+            round: self.round,
+            step: Step::try_from(self.step)?,
+            block_id: self.block_id.flatten(),
+        }))
+    }
+}
+
+impl From<&LastSignState> for StateFile {
+    fn from(last_sign_state: &LastSignState) -> Self {
+        let state = match &last_sign_state.state {
+            Some(state) => state,
+            None => {
+                return StateFile {
+                    height: block::Height::from(0u64),
+                    round: 0,
+                    step: 0,
+                    block_id: None,
+                }
+            }
+        };
+
+        StateFile {
+            height: state.height,
+            round: state.round,
+            step: state.step.into(),
+            block_id: if last_sign_state.block_id_known {
+                Some(state.block_id.clone())
+            } else {
+                None
+            },
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::{
+        error,
+        hash::Hash,
+        privval::{client::tests::vote_at, Rejection},
+    };
+    use std::env;
+    use std::error::Error as _;
+
+    fn state_path(name: &str) -> PathBuf {
+        let path = env::temp_dir().join(format!(
+            "tendermint-last-sign-state-{}-{}.json",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn reads_tendermint_state_file() {
+        let path = state_path("tendermint");
+        fs::write(
+            &path,
+            r#"{
+  "height": "1234",
+  "round": 1,
+  "step": 3,
+  "signature": "BB8nGLMDBbdKjwIsfNOHFkY6CrL8rXf9QwUB9ltT5KwX4BkIUlaFx4n6qRg+TDwoUnKrPnIiN0Y5P+YyGjFlAA==",
+  "signbytes": "7A080211D204000000000000190100000000000000"
+}"#,
+        )
+        .unwrap();
+
+        let last_sign_state = LastSignState::load_or_create(&path).unwrap();
+        let state = last_sign_state.state().unwrap();
+        assert_eq!(state.height.value(), 1234);
+        assert_eq!(state.round, 1);
+        assert_eq!(state.step, Step::Precommit);
+        assert_eq!(state.block_id, None);
+        assert!(!last_sign_state.block_id_known());
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn refuses_the_step_of_imported_states() {
+        let path = state_path("imported");
+        fs::write(&path, r#"{"height": "10", "round": 0, "step": 2}"#).unwrap();
+        let mut last_sign_state = LastSignState::load_or_create(&path).unwrap();
+
+        // Tendermint may have signed a block at this step: a nil vote would
+        // conflict with it
+        let mut nil_vote = vote_at(10);
+        nil_vote.block_id = None;
+        assert_eq!(
+            vote_state(&nil_vote).step,
+            last_sign_state.state().unwrap().step
+        );
+        let error = last_sign_state.update_for_vote(&nil_vote).unwrap_err();
+        assert_eq!(error::kind(error.as_ref()), Some(&Kind::DoubleSign));
+        assert!(matches!(
+            error.source().and_then(|e| e.downcast_ref::<Rejection>()),
+            Some(Rejection::UnknownBlock { .. })
+        ));
+
+        // Later steps are signed, after which their block ID is known
+        let mut precommit = nil_vote.clone();
+        precommit.vote_type = vote::Type::Precommit;
+        last_sign_state.update_for_vote(&precommit).unwrap();
+        last_sign_state.update_for_vote(&precommit).unwrap();
+
+        let reloaded = LastSignState::load_or_create(&path).unwrap();
+        assert!(reloaded.block_id_known());
+        assert!(fs::read_to_string(&path)
+            .unwrap()
+            .contains("\"block_id\": null"));
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn refuses_unknown_steps() {
+        let path = state_path("unknown-step");
+        fs::write(&path, r#"{"height": "1234", "round": 1, "step": 6}"#).unwrap();
+
+        let error = LastSignState::load_or_create(&path).unwrap_err();
+        assert_eq!(error::kind(error.as_ref()), Some(&Kind::Parse));
+
+        // Nothing can have been signed at step 0 past height 0
+        fs::write(&path, r#"{"height": "1234", "round": 1, "step": 0}"#).unwrap();
+        assert!(LastSignState::load_or_create(&path).is_err());
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn refuses_regressions_and_conflicts() {
+        let path = state_path("regressions");
+        let mut last_sign_state = LastSignState::load_or_create(&path).unwrap();
+        assert_eq!(last_sign_state.state(), None);
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "{\n  \"height\": \"0\",\n  \"round\": 0,\n  \"step\": 0\n}"
+        );
+        assert_eq!(LastSignState::load_or_create(&path).unwrap().state(), None);
+
+        let mut vote = vote_at(10);
+        vote.block_id = Some(block::Id::new(Hash::Sha256([1; 32]), None));
+        last_sign_state.update_for_vote(&vote).unwrap();
+
+        // Signing the same vote again is allowed
+        last_sign_state.update_for_vote(&vote).unwrap();
+
+        // Signing a different block at the same step isn't
+        let mut conflicting = vote.clone();
+        conflicting.block_id = Some(block::Id::new(Hash::Sha256([2; 32]), None));
+        let error = last_sign_state.update_for_vote(&conflicting).unwrap_err();
+        assert_eq!(error::kind(error.as_ref()), Some(&Kind::DoubleSign));
+        assert!(matches!(
+            error.source().and_then(|e| e.downcast_ref::<Rejection>()),
+            Some(Rejection::ConflictingBlock { .. })
+        ));
+
+        // Neither is signing at an earlier height
+        assert!(last_sign_state.update_for_vote(&vote_at(9)).is_err());
+
+        // The state survives a restart
+        let reloaded = LastSignState::load_or_create(&path).unwrap();
+        assert_eq!(reloaded.state(), last_sign_state.state());
+        assert_eq!(reloaded.state().unwrap().step, Step::Prevote);
+
+        fs::remove_file(&path).unwrap();
+    }
+}
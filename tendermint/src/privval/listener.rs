@@ -0,0 +1,341 @@
+//! Validator side of the remote signer protocol: the validator listens for
+//! its signer (e.g. a KMS) to connect, then sends it signing requests.
+//!
+//! Signers connecting over a UNIX domain socket can be accepted with a
+//! [`UnixSignerListener`]. Otherwise accepting connections is left to the
+//! caller's runtime: the [`SignerListener`] takes over a connection once it's
+//! been accepted (and, for TCP, upgraded to a secret connection).
+
+use super::{
+    codec, Batch, Frame, Message, PubKeyRequest, PubKeyResponse, RemoteSignerError,
+    SignProposalRequest, SignVoteRequest, SignedProposalResponse, SignedVoteResponse,
+};
+use crate::{chain, error::Kind, net, proposal::Proposal, public_key, vote::Vote, Error};
+use anomaly::{fail, format_err};
+use async_trait::async_trait;
+use futures::io::{AsyncRead, AsyncWrite};
+#[cfg(unix)]
+use {
+    super::client::Timeouts,
+    futures::io::AllowStdIo,
+    std::{
+        fs,
+        os::unix::net::{UnixListener, UnixStream},
+        path::PathBuf,
+    },
+};
+
+/// Signs votes and proposals on behalf of a validator, whether locally or
+/// through a remote signer
+#[async_trait]
+pub trait PrivValidator {
+    /// Get the consensus public key for the given chain
+    async fn public_key(&mut self, chain_id: chain::Id) -> Result<public_key::Ed25519, Error>;
+
+    /// Sign the given vote, returning it with its signature set
+    async fn sign_vote(&mut self, vote: Vote, chain_id: chain::Id) -> Result<Vote, Error>;
+
+    /// Sign the given proposal, returning it with its signature set
+    async fn sign_proposal(
+        &mut self,
+        proposal: Proposal,
+        chain_id: chain::Id,
+    ) -> Result<Proposal, Error>;
+}
+
+/// Connection from a remote signer, over which the validator sends requests
+#[derive(Debug)]
+pub struct SignerListener<S> {
+    stream: S,
+}
+
+impl<S> SignerListener<S>
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send,
+{
+    /// Take over an accepted connection from a remote signer
+    pub fn new(stream: S) -> Self {
+        SignerListener { stream }
+    }
+
+    /// Check the remote signer is still responsive
+    pub async fn ping(&mut self) -> Result<(), Error> {
+        match self.request(Message::PingRequest).await? {
+            Message::PingResponse => Ok(()),
+            other => unexpected(other),
+        }
+    }
+
+    /// Send a batch of requests, e.g. a proposal and a prevote, in one round
+    /// trip, returning their responses in the same order.
+    ///
+    /// Only signers built with this crate support batches: Tendermint's
+    /// signers don't.
+    pub async fn request_batch(&mut self, requests: Vec<Message>) -> Result<Vec<Message>, Error> {
+        let count = requests.len();
+        codec::write_frame_async(&mut self.stream, Batch::Requests(requests).into()).await?;
+
+        match codec::read_frame_async(&mut self.stream).await? {
+            Frame::Batch(Batch::Responses(responses)) if responses.len() == count => Ok(responses),
+            Frame::Batch(Batch::Responses(responses)) => fail!(
+                Kind::Protocol,
+                "expected {} responses from remote signer, got {}",
+                count,
+                responses.len()
+            ),
+            other => fail!(
+                Kind::Protocol,
+                "unexpected response to batch from remote signer: {:?}",
+                other
+            ),
+        }
+    }
+
+    /// Close the connection, returning the underlying stream
+    pub fn into_inner(self) -> S {
+        self.stream
+    }
+
+    async fn request(&mut self, request: Message) -> Result<Message, Error> {
+        codec::write_message_async(&mut self.stream, request).await?;
+        codec::read_message_async(&mut self.stream).await
+    }
+}
+
+#[async_trait]
+impl<S> PrivValidator for SignerListener<S>
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send,
+{
+    async fn public_key(&mut self, chain_id: chain::Id) -> Result<public_key::Ed25519, Error> {
+        match self.request(PubKeyRequest { chain_id }.into()).await? {
+            Message::PubKeyResponse(PubKeyResponse::PublicKey(pub_key)) => Ok(pub_key),
+            Message::PubKeyResponse(PubKeyResponse::Error(e)) => Err(remote_error(e)),
+            other => unexpected(other),
+        }
+    }
+
+    async fn sign_vote(&mut self, vote: Vote, chain_id: chain::Id) -> Result<Vote, Error> {
+        let request = SignVoteRequest::new(vote, chain_id);
+
+        match self.request(request.into()).await? {
+            Message::SignedVoteResponse(SignedVoteResponse::Vote(vote)) => Ok(vote),
+            Message::SignedVoteResponse(SignedVoteResponse::Error(e)) => Err(remote_error(e)),
+            other => unexpected(other),
+        }
+    }
+
+    async fn sign_proposal(
+        &mut self,
+        proposal: Proposal,
+        chain_id: chain::Id,
+    ) -> Result<Proposal, Error> {
+        let request = SignProposalRequest::new(proposal, chain_id);
+
+        match self.request(request.into()).await? {
+            Message::SignedProposalResponse(SignedProposalResponse::Proposal(proposal)) => {
+                Ok(proposal)
+            }
+            Message::SignedProposalResponse(SignedProposalResponse::Error(e)) => {
+                Err(remote_error(e))
+            }
+            other => unexpected(other),
+        }
+    }
+}
+
+/// Listens for a remote signer on a UNIX domain socket, removing the socket
+/// file when dropped.
+///
+/// Accepting connections, and I/O on them, block the current thread: drive
+/// the accepted [`SignerListener`] on a thread of its own, e.g. with
+/// `futures::executor::block_on`.
+#[cfg(unix)]
+#[derive(Debug)]
+pub struct UnixSignerListener {
+    listener: UnixListener,
+    path: PathBuf,
+    timeouts: Timeouts,
+}
+
+#[cfg(unix)]
+impl UnixSignerListener {
+    /// Listen on the given `unix://` address, e.g. a validator's
+    /// `priv_validator_laddr`, replacing any stale socket file
+    pub fn bind(address: &net::Address) -> Result<Self, Error> {
+        let path = match address {
+            net::Address::Unix { path } => path.clone(),
+            net::Address::Tcp { .. } => fail!(
+                Kind::Protocol,
+                "{} requires a secret connection, which needs a custom listener",
+                address
+            ),
+        };
+
+        if path.exists() {
+            fs::remove_file(&path)
+                .map_err(|e| format_err!(Kind::Io, "couldn't remove {}: {}", path.display(), e))?;
+        }
+
+        let listener = UnixListener::bind(&path)
+            .map_err(|e| format_err!(Kind::Io, "couldn't bind {}: {}", path.display(), e))?;
+
+        Ok(UnixSignerListener {
+            listener,
+            path,
+            timeouts: Timeouts::default(),
+        })
+    }
+
+    /// Set the read and write timeouts of the connections accepted from now
+    /// on, so that a hung signer fails requests instead of blocking the
+    /// validator. Failed connections must be dropped for the signer to
+    /// reconnect.
+    pub fn set_timeouts(&mut self, timeouts: Timeouts) {
+        self.timeouts = timeouts;
+    }
+
+    /// Wait for the remote signer to connect
+    pub fn accept(&self) -> Result<SignerListener<AllowStdIo<UnixStream>>, Error> {
+        let (stream, _) = self.listener.accept().map_err(|e| Kind::Io.context(e))?;
+
+        stream
+            .set_read_timeout(self.timeouts.read)
+            .and_then(|_| stream.set_write_timeout(self.timeouts.write))
+            .map_err(|e| Kind::Io.context(e))?;
+
+        Ok(SignerListener::new(AllowStdIo::new(stream)))
+    }
+}
+
+#[cfg(unix)]
+impl Drop for UnixSignerListener {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+fn remote_error(error: RemoteSignerError) -> Error {
+    Kind::RemoteSigner {
+        code: error.code,
+        description: error.description,
+    }
+    .into()
+}
+
+fn unexpected<T>(response: Message) -> Result<T, Error> {
+    fail!(
+        Kind::Protocol,
+        "unexpected response from remote signer: {:?}",
+        response
+    )
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::{block, error, privval::client, signature::Ed25519, Signature};
+    use futures::executor::block_on;
+    use std::{env, thread};
+
+    #[test]
+    fn signs_with_remote_signer() {
+        let (validator, mut kms) = UnixStream::pair().unwrap();
+        let signer = thread::spawn(move || {
+            let mut signer = client::tests::soft_signer();
+            client::serve(&mut kms, &mut signer)
+        });
+
+        let chain_id = chain::Id::from("test_chain_id");
+        let mut listener = SignerListener::new(AllowStdIo::new(validator));
+
+        block_on(async {
+            listener.ping().await.unwrap();
+
+            let pub_key = listener.public_key(chain_id).await.unwrap();
+            assert_eq!(pub_key, client::tests::soft_signer().0.public);
+
+            let vote = listener
+                .sign_vote(client::tests::vote_at(10), chain_id)
+                .await
+                .unwrap();
+            assert!(vote.verify(chain_id, &pub_key.into()).is_ok());
+
+            let proposal = Proposal {
+                height: block::Height::from(11u64),
+                round: 0,
+                pol_round: None,
+                block_id: None,
+                timestamp: None,
+                signature: Signature::Ed25519(Ed25519::new([0; 64])),
+            };
+            let responses = listener
+                .request_batch(vec![
+                    SignProposalRequest::new(proposal, chain_id).into(),
+                    SignVoteRequest::new(client::tests::vote_at(11), chain_id).into(),
+                ])
+                .await
+                .unwrap();
+            match responses.as_slice() {
+                [Message::SignedProposalResponse(SignedProposalResponse::Proposal(proposal)), Message::SignedVoteResponse(SignedVoteResponse::Vote(vote))] =>
+                {
+                    assert!(proposal.verify(chain_id, &pub_key.into()).is_ok());
+                    assert!(vote.verify(chain_id, &pub_key.into()).is_ok());
+                }
+                other => panic!("unexpected responses: {:?}", other),
+            }
+
+            let refused = listener
+                .sign_vote(client::tests::vote_at(9), chain_id)
+                .await
+                .unwrap_err();
+            assert!(matches!(
+                error::kind(refused.as_ref()),
+                Some(Kind::RemoteSigner { .. })
+            ));
+        });
+
+        drop(listener);
+        assert!(signer.join().unwrap().is_ok());
+    }
+
+    #[test]
+    fn accepts_signer_on_unix_socket() {
+        let path = env::temp_dir().join(format!("tendermint-privval-{}.sock", std::process::id()));
+        let address = net::Address::Unix { path: path.clone() };
+        let unix_listener = UnixSignerListener::bind(&address).unwrap();
+
+        let connector = client::UnixConnector::from_address(&address).unwrap();
+        let signer = thread::spawn(move || {
+            client::Client::new(connector, client::tests::soft_signer())
+                .max_retries(0)
+                .run()
+        });
+
+        let mut listener = unix_listener.accept().unwrap();
+        block_on(async {
+            listener.ping().await.unwrap();
+            assert_eq!(
+                listener
+                    .public_key(chain::Id::from("test_chain_id"))
+                    .await
+                    .unwrap(),
+                client::tests::soft_signer().0.public
+            );
+        });
+
+        drop(listener);
+        drop(unix_listener);
+        assert!(!path.exists());
+
+        // The client gives up once it can't reconnect
+        assert!(signer.join().unwrap().is_err());
+    }
+
+    #[test]
+    fn tcp_requires_secret_connection() {
+        let address = "tcp://127.0.0.1:26659".parse::<net::Address>().unwrap();
+        assert!(UnixSignerListener::bind(&address).is_err());
+        assert!(client::UnixConnector::from_address(&address).is_err());
+    }
+}
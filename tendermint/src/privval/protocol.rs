@@ -0,0 +1,163 @@
+//! Versions of the privval protocol, which Tendermint 0.34 switched from amino
+//! to protobuf messages.
+//!
+//! Both frame messages with their length as an unsigned varint, so a signer
+//! serving validators of either version can tell which protocol a validator
+//! speaks from its first request (see [`Protocol::negotiate`]).
+
+#[cfg(feature = "amino")]
+use crate::amino_types::Registry;
+use crate::{error::Kind, Error};
+use anomaly::{fail, format_err};
+use serde::{Deserialize, Serialize};
+use std::{fmt, str::FromStr};
+
+/// First Tendermint minor version (of 0.x) speaking the protobuf protocol
+const FIRST_PROTOBUF_MINOR_VERSION: u64 = 34;
+
+/// Privval protocol version
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Protocol {
+    /// Amino messages, each starting with the prefix of its registered type
+    /// name (Tendermint 0.33 and earlier). Signing requests don't carry the
+    /// chain ID, which signers must know from their configuration.
+    Amino,
+
+    /// Protobuf privval `Message`s (Tendermint 0.34 and later), which carry
+    /// the chain ID in each request
+    Protobuf,
+}
+
+impl Protocol {
+    /// Protocol spoken by the given Tendermint version, e.g. `v0.33.9` or
+    /// `0.34.0`
+    pub fn for_tendermint_version(version: &str) -> Result<Self, Error> {
+        let version = version.trim_start_matches('v');
+        let mut parts = version.split('.');
+
+        let mut next = || {
+            parts
+                .next()
+                .and_then(|part| part.parse::<u64>().ok())
+                .ok_or_else(|| format_err!(Kind::Parse, "invalid Tendermint version: {}", version))
+        };
+
+        match (next()?, next()?) {
+            (0, minor) if minor < FIRST_PROTOBUF_MINOR_VERSION => Ok(Protocol::Amino),
+            _ => Ok(Protocol::Protobuf),
+        }
+    }
+
+    /// Detect the protocol of the given message (without its length prefix):
+    /// amino if it starts with a registered amino prefix, protobuf otherwise
+    #[cfg(feature = "amino")]
+    pub fn detect(message: &[u8], registry: &Registry) -> Self {
+        if registry.type_name(message).is_some() {
+            Protocol::Amino
+        } else {
+            Protocol::Protobuf
+        }
+    }
+
+    /// Read the first message from a validator and detect its protocol,
+    /// returning it along with the message (without its length prefix), for
+    /// the caller to answer in that protocol.
+    ///
+    /// Protobuf messages can be answered with [`client::respond`], then
+    /// served with [`client::serve`].
+    ///
+    /// [`client::respond`]: super::client::respond
+    /// [`client::serve`]: super::client::serve
+    #[cfg(feature = "amino")]
+    pub fn negotiate<R: std::io::Read>(
+        reader: &mut R,
+        registry: &Registry,
+    ) -> Result<(Self, Vec<u8>), Error> {
+        let message = super::codec::read_frame_bytes(reader)?;
+        Ok((Protocol::detect(&message, registry), message))
+    }
+}
+
+impl fmt::Display for Protocol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Protocol::Amino => write!(f, "amino"),
+            Protocol::Protobuf => write!(f, "protobuf"),
+        }
+    }
+}
+
+impl FromStr for Protocol {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        match s {
+            "amino" => Ok(Protocol::Amino),
+            "protobuf" => Ok(Protocol::Protobuf),
+            _ => fail!(Kind::Parse, "invalid privval protocol: {}", s),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn protocol_by_tendermint_version() {
+        for (version, protocol) in &[
+            ("v0.33.9", Protocol::Amino),
+            ("0.32.0", Protocol::Amino),
+            ("v0.34.0-rc4", Protocol::Protobuf),
+            ("0.34.1", Protocol::Protobuf),
+            ("1.0.0", Protocol::Protobuf),
+        ] {
+            assert_eq!(
+                Protocol::for_tendermint_version(version).unwrap(),
+                *protocol
+            );
+        }
+
+        assert!(Protocol::for_tendermint_version("latest").is_err());
+        assert!(Protocol::for_tendermint_version("v0").is_err());
+    }
+
+    #[test]
+    fn parses_protocol_names() {
+        for protocol in &[Protocol::Amino, Protocol::Protobuf] {
+            assert_eq!(protocol.to_string().parse::<Protocol>().unwrap(), *protocol);
+        }
+
+        assert!("json".parse::<Protocol>().is_err());
+    }
+
+    #[cfg(feature = "amino")]
+    #[test]
+    fn detects_protocol_of_first_message() {
+        use crate::{
+            amino_types::{message::AminoMessage, PingRequest},
+            chain,
+            privval::{batch::Frame, Message, PubKeyRequest},
+        };
+
+        let registry = Registry::default();
+
+        let amino = PingRequest {}.bytes_vec_length_delimited();
+        let (protocol, message) = Protocol::negotiate(&mut amino.as_slice(), &registry).unwrap();
+        assert_eq!(protocol, Protocol::Amino);
+        assert_eq!(message, &amino[1..]);
+
+        for message in vec![
+            Message::PingRequest,
+            PubKeyRequest {
+                chain_id: chain::Id::from("test_chain_id"),
+            }
+            .into(),
+        ] {
+            let protobuf = Frame::from(message).encode_length_delimited().unwrap();
+            let (protocol, _) = Protocol::negotiate(&mut protobuf.as_slice(), &registry).unwrap();
+            assert_eq!(protocol, Protocol::Protobuf);
+        }
+    }
+}
@@ -0,0 +1,221 @@
+//! Consensus safety checks on signing requests: a validator must never sign
+//! at an earlier height, round or step than it signed before, nor sign a
+//! different block at the same height, round and step.
+
+use crate::{
+    chain,
+    consensus::{self, Step},
+    proposal::Proposal,
+    vote::Vote,
+};
+use std::cmp::Ordering;
+use thiserror::Error;
+
+/// State a vote is signed at
+pub fn vote_state(vote: &Vote) -> consensus::State {
+    consensus::State {
+        height: vote.height,
+        round: vote.round as i64,
+        step: Step::from(vote.vote_type),
+        block_id: vote.block_id.clone(),
+    }
+}
+
+/// State a proposal is signed at
+pub fn proposal_state(proposal: &Proposal) -> consensus::State {
+    consensus::State {
+        height: proposal.height,
+        round: proposal.round as i64,
+        step: Step::Propose,
+        block_id: proposal.block_id.clone(),
+    }
+}
+
+/// Reasons a signing request is refused
+#[derive(Clone, Debug, Eq, PartialEq, Error)]
+pub enum Rejection {
+    /// Requested height is below the last signed height
+    #[error("height regression: last signed at {last}, requested {requested}")]
+    HeightRegression {
+        /// Last signed state
+        last: consensus::State,
+        /// Requested state
+        requested: consensus::State,
+    },
+
+    /// Requested round is below the last signed round at the same height
+    #[error("round regression: last signed at {last}, requested {requested}")]
+    RoundRegression {
+        /// Last signed state
+        last: consensus::State,
+        /// Requested state
+        requested: consensus::State,
+    },
+
+    /// Requested step is below the last signed step at the same height and
+    /// round
+    #[error("step regression: last signed at {last}, requested {requested}")]
+    StepRegression {
+        /// Last signed state
+        last: consensus::State,
+        /// Requested state
+        requested: consensus::State,
+    },
+
+    /// A different block was signed at the same height, round and step
+    #[error(
+        "conflicting block at {requested}: last signed {}, requested {}",
+        .last.block_id_prefix(),
+        .requested.block_id_prefix()
+    )]
+    ConflictingBlock {
+        /// Last signed state
+        last: consensus::State,
+        /// Requested state
+        requested: consensus::State,
+    },
+
+    /// A block was signed at the same height, round and step, but which one
+    /// isn't known, e.g. because the state was imported from Tendermint
+    #[error("unknown block signed at {last}, requested {}", .requested.block_id_prefix())]
+    UnknownBlock {
+        /// Last signed state
+        last: consensus::State,
+        /// Requested state
+        requested: consensus::State,
+    },
+
+    /// Request for a chain the signer doesn't sign for
+    #[error("unknown chain ID: {chain_id}")]
+    UnknownChainId {
+        /// Requested chain ID
+        chain_id: chain::Id,
+    },
+}
+
+/// Check signing at the `requested` state is safe after signing at `last`.
+///
+/// Signing the same block again at the same height, round and step is
+/// allowed, so a signer can answer a request repeated after a lost response.
+pub fn check_hrs(last: &consensus::State, requested: &consensus::State) -> Result<(), Rejection> {
+    let rejection: fn(consensus::State, consensus::State) -> Rejection = match (
+        requested.height.cmp(&last.height),
+        requested.round.cmp(&last.round),
+        requested.step.cmp(&last.step),
+    ) {
+        (Ordering::Greater, _, _)
+        | (Ordering::Equal, Ordering::Greater, _)
+        | (Ordering::Equal, Ordering::Equal, Ordering::Greater) => return Ok(()),
+        (Ordering::Less, _, _) => |last, requested| Rejection::HeightRegression { last, requested },
+        (Ordering::Equal, Ordering::Less, _) => {
+            |last, requested| Rejection::RoundRegression { last, requested }
+        }
+        (Ordering::Equal, Ordering::Equal, Ordering::Less) => {
+            |last, requested| Rejection::StepRegression { last, requested }
+        }
+        (Ordering::Equal, Ordering::Equal, Ordering::Equal) => {
+            if requested.block_id == last.block_id {
+                return Ok(());
+            }
+            |last, requested| Rejection::ConflictingBlock { last, requested }
+        }
+    };
+
+    Err(rejection(last.clone(), requested.clone()))
+}
+
+/// Check signing at the `requested` state is safe after signing at `last`,
+/// whose block ID isn't known (see [`Rejection::UnknownBlock`]).
+///
+/// A nil vote and a vote for an unknown block look the same, so any request
+/// at the same height, round and step is refused.
+pub fn check_hrs_unknown_block(
+    last: &consensus::State,
+    requested: &consensus::State,
+) -> Result<(), Rejection> {
+    if (requested.height, requested.round, requested.step) == (last.height, last.round, last.step) {
+        return Err(Rejection::UnknownBlock {
+            last: last.clone(),
+            requested: requested.clone(),
+        });
+    }
+
+    check_hrs(last, requested)
+}
+
+/// Check the requested chain is one of the chains the signer signs for
+pub fn check_chain_id(allowed: &[chain::Id], chain_id: &chain::Id) -> Result<(), Rejection> {
+    if allowed.contains(chain_id) {
+        Ok(())
+    } else {
+        Err(Rejection::UnknownChainId {
+            chain_id: *chain_id,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{block, hash::Hash};
+
+    fn state(height: u64, round: i64, step: Step, block_byte: Option<u8>) -> consensus::State {
+        consensus::State {
+            height: block::Height::from(height),
+            round,
+            step,
+            block_id: block_byte.map(|byte| block::Id::new(Hash::Sha256([byte; 32]), None)),
+        }
+    }
+
+    #[test]
+    fn hrs_rules() {
+        let last = state(10, 1, Step::Prevote, Some(1));
+
+        assert!(check_hrs(&last, &state(11, 0, Step::Propose, None)).is_ok());
+        assert!(check_hrs(&last, &state(10, 2, Step::Propose, None)).is_ok());
+        assert!(check_hrs(&last, &state(10, 1, Step::Precommit, None)).is_ok());
+        assert!(check_hrs(&last, &last).is_ok());
+
+        let rejection = |requested| check_hrs(&last, &requested).unwrap_err();
+
+        assert!(matches!(
+            rejection(state(9, 5, Step::Precommit, Some(1))),
+            Rejection::HeightRegression { .. }
+        ));
+        assert!(matches!(
+            rejection(state(10, 0, Step::Precommit, Some(1))),
+            Rejection::RoundRegression { .. }
+        ));
+        assert!(matches!(
+            rejection(state(10, 1, Step::Propose, Some(1))),
+            Rejection::StepRegression { .. }
+        ));
+        assert!(matches!(
+            rejection(state(10, 1, Step::Prevote, Some(2))),
+            Rejection::ConflictingBlock { .. }
+        ));
+        assert!(matches!(
+            rejection(state(10, 1, Step::Prevote, None)),
+            Rejection::ConflictingBlock { .. }
+        ));
+    }
+
+    #[test]
+    fn unknown_blocks_refuse_the_same_step() {
+        let last = state(10, 1, Step::Prevote, None);
+
+        for block_byte in &[None, Some(1)] {
+            assert!(matches!(
+                check_hrs_unknown_block(&last, &state(10, 1, Step::Prevote, *block_byte)),
+                Err(Rejection::UnknownBlock { .. })
+            ));
+        }
+
+        assert!(check_hrs_unknown_block(&last, &state(10, 1, Step::Precommit, None)).is_ok());
+        assert!(matches!(
+            check_hrs_unknown_block(&last, &state(9, 1, Step::Precommit, None)),
+            Err(Rejection::HeightRegression { .. })
+        ));
+    }
+}
@@ -0,0 +1,254 @@
+//! Vote extension fields of the vote signing messages, with which signers of
+//! chains running Tendermint 0.38 (ABCI++) sign a precommit and its extension
+//! in the same exchange.
+//!
+//! The Tendermint 0.34 protobuf definitions this crate is built from lack
+//! these fields, so they're encoded here with the field numbers of
+//! Tendermint 0.38's `Vote` and `SignVoteRequest`:
+//!
+//! <https://github.com/cometbft/cometbft/blob/v0.38.0/proto/tendermint/privval/types.proto>
+//!
+//! Peers without vote extensions skip them as unknown fields, and messages
+//! without extension fields are encoded exactly as before. Only [`Frame`]s
+//! carry them: converting a [`Message`] to its protobuf type drops them.
+//!
+//! [`Frame`]: super::Frame
+
+use super::{Message, SignVoteRequest, SignedVoteResponse};
+use crate::{error::Kind, vote::Vote, Error};
+use prost::encoding;
+use prost::Message as _;
+use std::convert::{TryFrom, TryInto};
+use tendermint_proto::privval::{Message as RawMessage, SignVoteRequest as RawSignVoteRequest};
+use tendermint_proto::types::Vote as RawVote;
+
+/// Field of the `Message` oneof holding a `SignVoteRequest`
+const SIGN_VOTE_REQUEST_TAG: u32 = 3;
+
+/// Field of the `Message` oneof holding a `SignedVoteResponse`
+const SIGNED_VOTE_RESPONSE_TAG: u32 = 4;
+
+/// Vote extension fields of a `Vote`
+#[derive(Clone, PartialEq, prost::Message)]
+struct RawVoteExtension {
+    #[prost(bytes, tag = "9")]
+    extension: Vec<u8>,
+
+    #[prost(bytes, tag = "10")]
+    extension_signature: Vec<u8>,
+}
+
+/// Vote extension fields of a `SignVoteRequest`
+#[derive(Clone, PartialEq, prost::Message)]
+struct RawSignVoteRequestExtension {
+    #[prost(message, optional, tag = "1")]
+    vote: Option<RawVoteExtension>,
+
+    #[prost(bool, tag = "3")]
+    skip_extension_signing: bool,
+}
+
+/// Vote extension fields of a `SignedVoteResponse`
+#[derive(Clone, PartialEq, prost::Message)]
+struct RawSignedVoteResponseExtension {
+    #[prost(message, optional, tag = "1")]
+    vote: Option<RawVoteExtension>,
+}
+
+/// Vote extension fields of a `Message`
+#[derive(Clone, PartialEq, prost::Message)]
+struct RawMessageExtension {
+    #[prost(message, optional, tag = "3")]
+    sign_vote_request: Option<RawSignVoteRequestExtension>,
+
+    #[prost(message, optional, tag = "4")]
+    signed_vote_response: Option<RawSignedVoteResponseExtension>,
+}
+
+/// Decode a message from its encoding and its already decoded protobuf
+/// type, adding the vote extension fields the latter lacks
+pub(super) fn decode(bytes: &[u8], raw: RawMessage) -> Result<Message, Error> {
+    let mut message = Message::try_from(raw)?;
+    let fields = RawMessageExtension::decode(bytes).map_err(|e| Kind::Parse.context(e))?;
+
+    match &mut message {
+        Message::SignVoteRequest(request) => {
+            if let Some(fields) = fields.sign_vote_request {
+                request.skip_extension_signing = fields.skip_extension_signing;
+
+                if let Some(vote_fields) = fields.vote {
+                    set_extension(&mut request.vote, vote_fields)?;
+                }
+            }
+        }
+        Message::SignedVoteResponse(SignedVoteResponse::Vote(vote)) => {
+            if let Some(vote_fields) = fields.signed_vote_response.and_then(|fields| fields.vote) {
+                set_extension(vote, vote_fields)?;
+            }
+        }
+        _ => (),
+    }
+
+    Ok(message)
+}
+
+/// Encode a message, including the vote extension fields of vote signing
+/// messages
+pub(super) fn encode(message: Message, buf: &mut Vec<u8>) -> Result<(), Error> {
+    match message {
+        Message::SignVoteRequest(request)
+            if !request.vote.extension.is_empty() || request.skip_extension_signing =>
+        {
+            encode_sign_vote_request(request, buf)
+        }
+        Message::SignedVoteResponse(SignedVoteResponse::Vote(vote))
+            if !vote.extension.is_empty() || vote.extension_signature.is_some() =>
+        {
+            encode_signed_vote(vote, buf)
+        }
+        message => RawMessage::try_from(message)?
+            .encode(buf)
+            .map_err(|e| Kind::Parse.context(e).into()),
+    }
+}
+
+fn encode_sign_vote_request(request: SignVoteRequest, buf: &mut Vec<u8>) -> Result<(), Error> {
+    // Like its signature, the vote's extension signature isn't sent
+    let extension = RawVoteExtension {
+        extension: request.vote.extension.clone(),
+        extension_signature: vec![],
+    };
+    let skip_extension_signing = request.skip_extension_signing;
+    let raw = RawSignVoteRequest::try_from(request)?;
+
+    let mut body = vec![];
+    if let Some(vote) = raw.vote {
+        encoding::bytes::encode(1, &encode_vote(vote, extension)?, &mut body);
+    }
+    if !raw.chain_id.is_empty() {
+        encoding::string::encode(2, &raw.chain_id, &mut body);
+    }
+    if skip_extension_signing {
+        encoding::bool::encode(3, &skip_extension_signing, &mut body);
+    }
+
+    encoding::bytes::encode(SIGN_VOTE_REQUEST_TAG, &body, buf);
+    Ok(())
+}
+
+fn encode_signed_vote(vote: Vote, buf: &mut Vec<u8>) -> Result<(), Error> {
+    let extension = RawVoteExtension {
+        extension: vote.extension.clone(),
+        extension_signature: vote
+            .extension_signature
+            .clone()
+            .map(Into::into)
+            .unwrap_or_default(),
+    };
+
+    let vote = RawVote::try_from(vote)?;
+
+    let mut body = vec![];
+    encoding::bytes::encode(1, &encode_vote(vote, extension)?, &mut body);
+
+    encoding::bytes::encode(SIGNED_VOTE_RESPONSE_TAG, &body, buf);
+    Ok(())
+}
+
+/// Encode a vote followed by its extension fields, which come last in
+/// Tendermint 0.38's `Vote`
+fn encode_vote(vote: RawVote, extension: RawVoteExtension) -> Result<Vec<u8>, Error> {
+    let mut bytes = vec![];
+
+    vote.encode(&mut bytes)
+        .and_then(|_| extension.encode(&mut bytes))
+        .map_err(|e| Kind::Parse.context(e))?;
+
+    Ok(bytes)
+}
+
+fn set_extension(vote: &mut Vote, fields: RawVoteExtension) -> Result<(), Error> {
+    vote.extension = fields.extension;
+    vote.extension_signature = if fields.extension_signature.is_empty() {
+        None
+    } else {
+        Some(fields.extension_signature.try_into()?)
+    };
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{account, block, chain, hash::Hash, signature, vote, Signature, Time};
+    use std::str::FromStr;
+
+    fn precommit() -> Vote {
+        Vote {
+            vote_type: vote::Type::Precommit,
+            height: block::Height::from(12345u64),
+            round: 2,
+            block_id: Some(block::Id::new(Hash::Sha256([0x42; 32]), None)),
+            timestamp: Time::from_str("2017-12-25T03:00:01.234Z").unwrap(),
+            validator_address: account::Id::new([0xa3; 20]),
+            validator_index: 56789,
+            signature: Signature::Ed25519(signature::Ed25519::new([0; 64])),
+            extension: b"extension".to_vec(),
+            extension_signature: None,
+        }
+    }
+
+    fn round_trip(message: Message) -> Message {
+        let mut bytes = vec![];
+        encode(message, &mut bytes).unwrap();
+        decode(&bytes, RawMessage::decode(bytes.as_slice()).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn sign_vote_request_round_trip() {
+        let mut request = SignVoteRequest::new(precommit(), chain::Id::from("test_chain_id"));
+        assert_eq!(round_trip(request.clone().into()), request.clone().into());
+
+        request.skip_extension_signing = true;
+        assert_eq!(round_trip(request.clone().into()), request.into());
+    }
+
+    #[test]
+    fn signed_vote_round_trip() {
+        let mut vote = precommit();
+        vote.signature = Signature::Ed25519(signature::Ed25519::new([1; 64]));
+        vote.extension_signature = Some(Signature::Ed25519(signature::Ed25519::new([2; 64])));
+
+        let message = Message::from(SignedVoteResponse::Vote(vote));
+        assert_eq!(round_trip(message.clone()), message);
+    }
+
+    #[test]
+    fn extension_fields_are_appended_to_the_vote() {
+        let mut vote = precommit();
+        vote.extension_signature = Some(Signature::Ed25519(signature::Ed25519::new([2; 64])));
+
+        let mut bytes = vec![];
+        encode(SignedVoteResponse::Vote(vote.clone()).into(), &mut bytes).unwrap();
+
+        // Peers without vote extensions read the vote without them...
+        let mut without_extension = vote.clone();
+        without_extension.extension = vec![];
+        without_extension.extension_signature = None;
+        assert_eq!(
+            Message::try_from(RawMessage::decode(bytes.as_slice()).unwrap()).unwrap(),
+            SignedVoteResponse::Vote(without_extension).into()
+        );
+
+        // ...and others find them in the vote itself
+        let fields = RawMessageExtension::decode(bytes.as_slice()).unwrap();
+        assert_eq!(
+            fields.signed_vote_response.and_then(|fields| fields.vote),
+            Some(RawVoteExtension {
+                extension: vote.extension,
+                extension_signature: vec![2; 64],
+            })
+        );
+    }
+}
@@ -0,0 +1,290 @@
+//! Proposals from validators
+
+mod canonical_proposal;
+
+pub use self::canonical_proposal::CanonicalProposal;
+use crate::amino_types::{self, TimeMsg};
+use crate::block::parts::{self, PartSet};
+use crate::{
+    block, chain, error::Kind, serializers, Error, PublicKey, Signature, SignedMsgType, Time,
+};
+use anomaly::{fail, format_err};
+use serde::{Deserialize, Serialize};
+use std::convert::{TryFrom, TryInto};
+use tendermint_proto::types::Proposal as RawProposal;
+use tendermint_proto::DomainType;
+
+/// Proposal to commit a block, made by the proposer of a round.
+///
+/// <https://github.com/tendermint/spec/blob/d46cd7f573a2c6a2399fcab2cde981330aa63f37/spec/core/data_structures.md#proposal>
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Proposal {
+    /// Block height
+    pub height: block::Height,
+
+    /// Round
+    #[serde(with = "serializers::from_str")]
+    pub round: u64,
+
+    /// Proof-of-lock round, if the proposed block was locked on in an
+    /// earlier round (`-1` on the wire otherwise)
+    #[serde(with = "pol_round")]
+    pub pol_round: Option<u64>,
+
+    /// Block ID
+    #[serde(with = "serializers::block_id::optional")]
+    pub block_id: Option<block::Id>,
+
+    /// Timestamp
+    pub timestamp: Option<Time>,
+
+    /// Signature
+    pub signature: Signature,
+}
+
+impl Proposal {
+    /// Perform basic validation of this proposal (like Tendermint's
+    /// `Proposal.ValidateBasic`): the POL round, if any, must precede the
+    /// proposal's round, and the block ID must be complete, i.e. include a
+    /// non-empty part set header.
+    pub fn validate_basic(&self) -> Result<(), Error> {
+        if let Some(pol_round) = self.pol_round {
+            if pol_round >= self.round {
+                fail!(
+                    Kind::InvalidProposal,
+                    "POL round {} is not before proposal round {}",
+                    pol_round,
+                    self.round
+                );
+            }
+        }
+
+        let parts = self.part_set_header().ok_or_else(|| {
+            format_err!(Kind::InvalidProposal, "proposal has no complete block ID")
+        })?;
+
+        if parts.total == 0 || parts.total > parts::MAX_BLOCK_PARTS_COUNT {
+            fail!(
+                Kind::InvalidProposal,
+                "invalid number of block parts: {}",
+                parts.total
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Is the proposed block one which was locked on in an earlier round?
+    ///
+    /// If so, validators expect +2/3 prevotes for it in [`Proposal::pol_round`].
+    pub fn has_pol(&self) -> bool {
+        self.pol_round.is_some()
+    }
+
+    /// Does this proposal propose the block with the given ID?
+    pub fn proposes(&self, block_id: &block::Id) -> bool {
+        self.block_id.as_ref() == Some(block_id)
+    }
+
+    /// Header of the part set of the proposed block, which is used to gossip
+    /// and reassemble the block
+    pub fn part_set_header(&self) -> Option<&parts::Header> {
+        self.block_id.as_ref()?.parts.as_ref()
+    }
+
+    /// Create an empty part set to collect the parts of the proposed block
+    pub fn new_part_set(&self) -> Result<PartSet, Error> {
+        let header = self
+            .part_set_header()
+            .ok_or_else(|| format_err!(Kind::InvalidProposal, "proposal has no part set header"))?;
+
+        PartSet::new(header.clone())
+    }
+
+    /// Bytes of the canonicalized proposal which are signed on the given chain
+    pub fn to_signable_bytes(&self, chain_id: chain::Id) -> Vec<u8> {
+        CanonicalProposal::new(self.clone(), chain_id).to_signable_bytes()
+    }
+
+    /// Verify this proposal's signature was produced on the given chain by the
+    /// holder of the given public key
+    pub fn verify(&self, chain_id: chain::Id, pub_key: &PublicKey) -> Result<(), Error> {
+        pub_key.verify(&self.to_signable_bytes(chain_id), &self.signature)
+    }
+}
+
+impl DomainType<RawProposal> for Proposal {}
+
+impl TryFrom<RawProposal> for Proposal {
+    type Error = Error;
+
+    fn try_from(value: RawProposal) -> Result<Self, Self::Error> {
+        if SignedMsgType::try_from(value.r#type).ok() != Some(SignedMsgType::Proposal) {
+            fail!(Kind::Parse, "invalid proposal type: {}", value.r#type);
+        }
+
+        if value.round < 0 {
+            fail!(Kind::OutOfRange, "negative proposal round: {}", value.round);
+        }
+
+        let pol_round = match value.pol_round {
+            -1 => None,
+            round if round >= 0 => Some(round as u64),
+            round => fail!(Kind::OutOfRange, "invalid POL round: {}", round),
+        };
+
+        Ok(Proposal {
+            height: value.height.try_into()?,
+            round: value.round as u64,
+            pol_round,
+            block_id: block::Id::parse_optional_raw(value.block_id)?,
+            timestamp: value.timestamp.map(TryInto::try_into).transpose()?,
+            signature: value.signature.try_into()?,
+        })
+    }
+}
+
+impl TryFrom<Proposal> for RawProposal {
+    type Error = Error;
+
+    fn try_from(value: Proposal) -> Result<Self, Self::Error> {
+        let round = |round: u64| {
+            i32::try_from(round)
+                .map_err(|_| format_err!(Kind::OutOfRange, "proposal round too large: {}", round))
+        };
+
+        Ok(RawProposal {
+            r#type: SignedMsgType::Proposal.into(),
+            height: value.height.into(),
+            round: round(value.round)?,
+            pol_round: value.pol_round.map(round).transpose()?.unwrap_or(-1),
+            block_id: block::Id::to_optional_raw(value.block_id),
+            timestamp: value.timestamp.map(Into::into),
+            signature: value.signature.into(),
+        })
+    }
+}
+
+impl From<&Proposal> for amino_types::proposal::Proposal {
+    fn from(proposal: &Proposal) -> Self {
+        amino_types::proposal::Proposal {
+            msg_type: SignedMsgType::Proposal.to_u32(),
+            height: proposal.height.value() as i64,
+            round: proposal.round as i64,
+            pol_round: proposal.pol_round.map(|r| r as i64).unwrap_or(-1),
+            block_id: proposal.block_id.as_ref().map(Into::into),
+            timestamp: proposal.timestamp.map(TimeMsg::from),
+            signature: proposal.signature.as_bytes().to_vec(),
+        }
+    }
+}
+
+/// Serialize POL rounds like Tendermint: as a string, with `-1` meaning none.
+/// Any other negative round is rejected.
+mod pol_round {
+    use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S>(pol_round: &Option<u64>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match pol_round {
+            Some(round) => round.to_string(),
+            None => "-1".to_owned(),
+        }
+        .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<u64>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let pol_round = String::deserialize(deserializer)?
+            .parse::<i64>()
+            .map_err(|e| D::Error::custom(format!("{}", e)))?;
+
+        match pol_round {
+            -1 => Ok(None),
+            round if round >= 0 => Ok(Some(round as u64)),
+            round => Err(D::Error::custom(format!("invalid POL round: {}", round))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hash::Hash;
+    use std::str::FromStr;
+
+    fn example_proposal() -> Proposal {
+        Proposal {
+            height: block::Height::from(12345u64),
+            round: 2,
+            pol_round: None,
+            block_id: Some(block::Id::new(
+                Hash::Sha256([0xDE; 32]),
+                Some(parts::Header::new(3, Hash::Sha256([0xAD; 32]))),
+            )),
+            timestamp: Some(Time::from_str("2018-02-11T07:09:22.765Z").unwrap()),
+            signature: Signature::Ed25519(ed25519::Signature::new([0; 64])),
+        }
+    }
+
+    #[test]
+    fn validates_pol_round() {
+        let mut proposal = example_proposal();
+        assert!(proposal.validate_basic().is_ok());
+        assert!(!proposal.has_pol());
+
+        proposal.pol_round = Some(1);
+        assert!(proposal.validate_basic().is_ok());
+        assert!(proposal.has_pol());
+
+        proposal.pol_round = Some(2);
+        assert!(proposal.validate_basic().is_err());
+    }
+
+    #[test]
+    fn requires_complete_block_id() {
+        let mut proposal = example_proposal();
+        assert_eq!(proposal.new_part_set().unwrap().total(), 3);
+        assert!(proposal.proposes(proposal.block_id.as_ref().unwrap()));
+
+        proposal.block_id.as_mut().unwrap().parts = None;
+        assert!(proposal.validate_basic().is_err());
+        assert!(proposal.new_part_set().is_err());
+
+        proposal.block_id = None;
+        assert!(proposal.validate_basic().is_err());
+    }
+
+    #[test]
+    fn serde_pol_round() {
+        let mut proposal = example_proposal();
+        let json = serde_json::to_value(&proposal).unwrap();
+        assert_eq!(json["pol_round"], "-1");
+        assert_eq!(serde_json::from_value::<Proposal>(json).unwrap(), proposal);
+
+        proposal.pol_round = Some(1);
+        let mut json = serde_json::to_value(&proposal).unwrap();
+        assert_eq!(json["pol_round"], "1");
+        assert_eq!(
+            serde_json::from_value::<Proposal>(json.clone()).unwrap(),
+            proposal
+        );
+
+        json["pol_round"] = "-2".into();
+        assert!(serde_json::from_value::<Proposal>(json).is_err());
+    }
+
+    #[test]
+    fn protobuf_rejects_negative_pol_round() {
+        let mut raw = RawProposal::try_from(example_proposal()).unwrap();
+        assert_eq!(raw.pol_round, -1);
+        assert_eq!(Proposal::try_from(raw.clone()).unwrap(), example_proposal());
+
+        raw.pol_round = -2;
+        assert!(Proposal::try_from(raw).is_err());
+    }
+}
@@ -0,0 +1,99 @@
+use super::Proposal;
+use crate::amino_types::{self, message::AminoMessage, TimeMsg};
+use crate::{block, chain, SignedMsgType, Time};
+
+/// CanonicalProposal is the form of a [`Proposal`] which proposers sign: it
+/// omits the signature and includes the chain ID.
+///
+/// <https://github.com/tendermint/spec/blob/d46cd7f573a2c6a2399fcab2cde981330aa63f37/spec/core/encoding.md#signed-messages>
+#[derive(Clone, Debug, PartialEq)]
+pub struct CanonicalProposal {
+    /// Block height
+    pub height: block::Height,
+
+    /// Round
+    pub round: u64,
+
+    /// Proof-of-lock round
+    pub pol_round: Option<u64>,
+
+    /// Block ID
+    pub block_id: Option<block::Id>,
+
+    /// Timestamp
+    pub timestamp: Option<Time>,
+
+    /// Chain ID
+    pub chain_id: chain::Id,
+}
+
+impl CanonicalProposal {
+    /// Canonicalize the given proposal for signing on the given chain
+    pub fn new(proposal: Proposal, chain_id: chain::Id) -> CanonicalProposal {
+        CanonicalProposal {
+            height: proposal.height,
+            round: proposal.round,
+            pol_round: proposal.pol_round,
+            block_id: proposal.block_id,
+            timestamp: proposal.timestamp,
+            chain_id,
+        }
+    }
+
+    /// Serialize this proposal as the length-prefixed bytes which are signed
+    pub fn to_signable_bytes(&self) -> Vec<u8> {
+        amino_types::proposal::CanonicalProposal::from(self).bytes_vec_length_delimited()
+    }
+}
+
+impl From<&CanonicalProposal> for amino_types::proposal::CanonicalProposal {
+    fn from(proposal: &CanonicalProposal) -> Self {
+        amino_types::proposal::CanonicalProposal {
+            msg_type: SignedMsgType::Proposal.to_u32(),
+            height: proposal.height.value() as i64,
+            round: proposal.round as i64,
+            pol_round: proposal.pol_round.map(|r| r as i64).unwrap_or(-1),
+            block_id: proposal.block_id.as_ref().map(Into::into),
+            timestamp: proposal.timestamp.map(TimeMsg::from),
+            chain_id: proposal.chain_id.as_str().to_owned(),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "amino"))]
+mod tests {
+    use super::*;
+    use crate::amino_types::{SignProposalRequest, SignableMsg};
+    use crate::{
+        hash::{self, Hash},
+        Signature,
+    };
+    use std::str::FromStr;
+
+    #[test]
+    fn sign_bytes_match_amino_proposal() {
+        let proposal = Proposal {
+            height: block::Height::from(12345u64),
+            round: 23456,
+            pol_round: None,
+            block_id: Some(block::Id::new(
+                Hash::from_hex_upper(
+                    hash::Algorithm::Sha256,
+                    "DEADBEEFDEADBEEFDEADBEEFDEADBEEFDEADBEEFDEADBEEFDEADBEEFDEADBEEF",
+                )
+                .unwrap(),
+                None,
+            )),
+            timestamp: Some(Time::from_str("2018-02-11T07:09:22.765Z").unwrap()),
+            signature: Signature::Ed25519(ed25519::Signature::new([0; 64])),
+        };
+        let chain_id = chain::Id::from("test_chain_id");
+
+        let request = SignProposalRequest {
+            proposal: Some((&proposal).into()),
+        };
+        let want = request.sign_bytes(chain_id).unwrap();
+
+        assert_eq!(proposal.to_signable_bytes(chain_id), want);
+    }
+}
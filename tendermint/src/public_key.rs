@@ -5,15 +5,31 @@ pub use ed25519_dalek::PublicKey as Ed25519;
 #[cfg(feature = "secp256k1")]
 pub use k256::PublicKey as Secp256k1;
 
+#[cfg(feature = "sr25519")]
+pub use schnorrkel::PublicKey as Sr25519;
+
 use crate::{
+    account,
     error::{self, Error},
     signature::Signature,
 };
 use anomaly::{fail, format_err};
 use serde::{de, ser, Deserialize, Serialize};
+#[cfg(feature = "secp256k1")]
+use signature::Signature as _;
 use signature::Verifier as _;
-use std::{cmp::Ordering, fmt, ops::Deref, str::FromStr};
-use subtle_encoding::{base64, bech32, hex};
+use std::{cmp::Ordering, convert::TryFrom, fmt, ops::Deref, str::FromStr};
+use subtle_encoding::base64;
+#[cfg(feature = "amino")]
+use subtle_encoding::{bech32, hex};
+use tendermint_proto::crypto::{public_key::Sum, PublicKey as RawPublicKey};
+
+#[cfg(feature = "amino")]
+#[cfg_attr(docsrs, doc(cfg(feature = "amino")))]
+pub mod multisig;
+
+#[cfg(feature = "amino")]
+pub use multisig::{Multisignature, ThresholdPublicKey};
 
 /// Public keys allowed in Tendermint protocols
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
@@ -37,6 +53,16 @@ pub enum PublicKey {
         deserialize_with = "deserialize_secp256k1_base64"
     )]
     Secp256k1(Secp256k1),
+
+    /// Sr25519 keys
+    #[cfg(feature = "sr25519")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "sr25519")))]
+    #[serde(
+        rename = "tendermint/PubKeySr25519",
+        serialize_with = "serialize_sr25519_base64",
+        deserialize_with = "deserialize_sr25519_base64"
+    )]
+    Sr25519(Sr25519),
 }
 
 impl PublicKey {
@@ -52,6 +78,63 @@ impl PublicKey {
         Ed25519::from_bytes(bytes).map(Into::into).ok()
     }
 
+    /// From raw Sr25519 public key bytes
+    #[cfg(feature = "sr25519")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "sr25519")))]
+    pub fn from_raw_sr25519(bytes: &[u8]) -> Option<PublicKey> {
+        Sr25519::from_bytes(bytes).map(Into::into).ok()
+    }
+
+    /// From raw public key bytes of the given algorithm
+    pub fn from_raw(algorithm: Algorithm, bytes: &[u8]) -> Result<PublicKey, Error> {
+        let public_key = match algorithm {
+            Algorithm::Ed25519 => PublicKey::from_raw_ed25519(bytes),
+            #[cfg(feature = "secp256k1")]
+            Algorithm::Secp256k1 => PublicKey::from_raw_secp256k1(bytes),
+            #[cfg(feature = "sr25519")]
+            Algorithm::Sr25519 => PublicKey::from_raw_sr25519(bytes),
+            #[allow(unreachable_patterns)]
+            other => fail!(
+                error::Kind::InvalidKey,
+                "unsupported public key algorithm: {}",
+                other
+            ),
+        };
+
+        public_key.ok_or_else(|| {
+            format_err!(
+                error::Kind::InvalidKey,
+                "malformed {} public key",
+                algorithm
+            )
+            .into()
+        })
+    }
+
+    /// Parse a public key of the given algorithm from the base64 encoding of
+    /// its raw bytes
+    pub fn from_base64(algorithm: Algorithm, encoded: &str) -> Result<PublicKey, Error> {
+        let bytes =
+            base64::decode(encoded.trim()).map_err(|e| format_err!(error::Kind::Parse, "{}", e))?;
+        PublicKey::from_raw(algorithm, &bytes)
+    }
+
+    /// Serialize the raw bytes of this key as base64
+    pub fn to_base64(self) -> String {
+        String::from_utf8(base64::encode(self.as_bytes())).unwrap()
+    }
+
+    /// Get the algorithm of this public key
+    pub fn algorithm(&self) -> Algorithm {
+        match self {
+            PublicKey::Ed25519(_) => Algorithm::Ed25519,
+            #[cfg(feature = "secp256k1")]
+            PublicKey::Secp256k1(_) => Algorithm::Secp256k1,
+            #[cfg(feature = "sr25519")]
+            PublicKey::Sr25519(_) => Algorithm::Sr25519,
+        }
+    }
+
     /// Get Ed25519 public key
     pub fn ed25519(self) -> Option<Ed25519> {
         #[allow(unreachable_patterns)]
@@ -71,6 +154,16 @@ impl PublicKey {
         }
     }
 
+    /// Get Sr25519 public key
+    #[cfg(feature = "sr25519")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "sr25519")))]
+    pub fn sr25519(self) -> Option<Sr25519> {
+        match self {
+            PublicKey::Sr25519(pk) => Some(pk),
+            _ => None,
+        }
+    }
+
     /// Verify the given [`Signature`] using this public key
     pub fn verify(&self, msg: &[u8], signature: &Signature) -> Result<(), Error> {
         match self {
@@ -82,11 +175,40 @@ impl PublicKey {
                     )
                     .into()
                 }),
+                #[cfg(feature = "secp256k1")]
+                Signature::Secp256k1(_) => fail!(
+                    error::Kind::SignatureInvalid,
+                    "ECDSA/secp256k1 signature can't be verified with an Ed25519 key"
+                ),
             },
             #[cfg(feature = "secp256k1")]
-            PublicKey::Secp256k1(_) => fail!(
+            PublicKey::Secp256k1(pk) => {
+                let verifier = k256::ecdsa::Verifier::new(pk).map_err(|_| {
+                    format_err!(error::Kind::InvalidKey, "malformed secp256k1 public key")
+                })?;
+
+                // Signatures don't carry a type tag on the wire, so a secp256k1
+                // signature may have been decoded as Ed25519
+                let sig =
+                    k256::ecdsa::Signature::from_bytes(signature.as_bytes()).map_err(|_| {
+                        format_err!(
+                            error::Kind::SignatureInvalid,
+                            "malformed ECDSA/secp256k1 signature"
+                        )
+                    })?;
+
+                verifier.verify(msg, &sig).map_err(|_| {
+                    format_err!(
+                        error::Kind::SignatureInvalid,
+                        "ECDSA/secp256k1 signature verification failed"
+                    )
+                    .into()
+                })
+            }
+            #[cfg(feature = "sr25519")]
+            PublicKey::Sr25519(_) => fail!(
                 error::Kind::InvalidKey,
-                "unsupported signature algorithm (ECDSA/secp256k1)"
+                "unsupported signature algorithm (sr25519)"
             ),
         }
     }
@@ -97,6 +219,8 @@ impl PublicKey {
             PublicKey::Ed25519(pk) => pk.as_bytes(),
             #[cfg(feature = "secp256k1")]
             PublicKey::Secp256k1(pk) => pk.as_bytes(),
+            #[cfg(feature = "sr25519")]
+            PublicKey::Sr25519(pk) => pk.as_compressed().as_bytes(),
         }
     }
 
@@ -105,7 +229,25 @@ impl PublicKey {
         self.as_bytes().to_vec()
     }
 
+    /// Derive the address of this key, which depends on the key type:
+    ///
+    /// - Ed25519 and Sr25519: the first 20 bytes of `SHA256(key)`, as used
+    ///   for validator (consensus) addresses
+    /// - Secp256k1: `RIPEMD160(SHA256(key))` of the compressed key, as used
+    ///   for account addresses
+    pub fn address(&self) -> account::Id {
+        match *self {
+            PublicKey::Ed25519(pk) => account::Id::from(pk),
+            #[cfg(feature = "secp256k1")]
+            PublicKey::Secp256k1(pk) => account::Id::from(pk),
+            #[cfg(feature = "sr25519")]
+            PublicKey::Sr25519(pk) => account::Id::from(pk),
+        }
+    }
+
     /// Serialize this key as amino bytes
+    #[cfg(feature = "amino")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "amino")))]
     pub fn to_amino_bytes(self) -> Vec<u8> {
         match self {
             PublicKey::Ed25519(ref pk) => {
@@ -120,18 +262,82 @@ impl PublicKey {
                 key_bytes.extend(pk.as_bytes());
                 key_bytes
             }
+            #[cfg(feature = "sr25519")]
+            PublicKey::Sr25519(ref pk) => {
+                let mut key_bytes = vec![0x0D, 0xFB, 0x10, 0x05, 0x20];
+                key_bytes.extend(&pk.to_bytes());
+                key_bytes
+            }
+        }
+    }
+
+    /// Parse a key from its amino encoding (as produced by [`PublicKey::to_amino_bytes`])
+    #[cfg(feature = "amino")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "amino")))]
+    pub fn from_amino_bytes(bytes: &[u8]) -> Result<PublicKey, Error> {
+        if bytes.len() < 5 {
+            fail!(error::Kind::Length, "amino public key too short");
         }
+
+        let (prefix, key_bytes) = bytes.split_at(5);
+
+        let public_key = match prefix {
+            [0x16, 0x24, 0xDE, 0x64, 0x20] => PublicKey::from_raw_ed25519(key_bytes),
+            #[cfg(feature = "secp256k1")]
+            [0xEB, 0x5A, 0xE9, 0x87, 0x21] => PublicKey::from_raw_secp256k1(key_bytes),
+            #[cfg(feature = "sr25519")]
+            [0x0D, 0xFB, 0x10, 0x05, 0x20] => PublicKey::from_raw_sr25519(key_bytes),
+            _ => fail!(error::Kind::InvalidKey, "unknown amino public key prefix"),
+        };
+
+        public_key
+            .ok_or_else(|| format_err!(error::Kind::InvalidKey, "malformed public key").into())
     }
 
     /// Serialize this key as Bech32 with the given human readable prefix
+    #[cfg(feature = "amino")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "amino")))]
     pub fn to_bech32(self, hrp: &str) -> String {
         bech32::encode(hrp, self.to_amino_bytes())
     }
 
+    /// Parse a key from Bech32 (e.g. a `cosmosvalconspub` key), checking it has
+    /// the expected human readable prefix
+    #[cfg(feature = "amino")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "amino")))]
+    pub fn from_bech32(encoded: &str, hrp: &str) -> Result<PublicKey, Error> {
+        let (actual_hrp, bytes) = bech32::decode(encoded)
+            .map_err(|e| format_err!(error::Kind::Parse, "invalid bech32: {}", e))?;
+
+        if actual_hrp != hrp {
+            fail!(
+                error::Kind::Parse,
+                "expected bech32 prefix {:?}, got {:?}",
+                hrp,
+                actual_hrp
+            );
+        }
+
+        PublicKey::from_amino_bytes(&bytes)
+    }
+
     /// Serialize this key as hexadecimal
+    #[cfg(feature = "amino")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "amino")))]
     pub fn to_hex(self) -> String {
         String::from_utf8(hex::encode_upper(self.to_amino_bytes())).unwrap()
     }
+
+    /// Parse a key from hexadecimal (as produced by [`PublicKey::to_hex`])
+    #[cfg(feature = "amino")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "amino")))]
+    pub fn from_hex(encoded: &str) -> Result<PublicKey, Error> {
+        let bytes = hex::decode_upper(encoded)
+            .or_else(|_| hex::decode(encoded))
+            .map_err(|e| format_err!(error::Kind::Parse, "invalid hex: {}", e))?;
+
+        PublicKey::from_amino_bytes(&bytes)
+    }
 }
 
 impl From<Ed25519> for PublicKey {
@@ -147,6 +353,44 @@ impl From<Secp256k1> for PublicKey {
     }
 }
 
+#[cfg(feature = "sr25519")]
+impl From<Sr25519> for PublicKey {
+    fn from(pk: Sr25519) -> PublicKey {
+        PublicKey::Sr25519(pk)
+    }
+}
+
+impl TryFrom<RawPublicKey> for PublicKey {
+    type Error = Error;
+
+    fn try_from(value: RawPublicKey) -> Result<Self, Self::Error> {
+        match value.sum {
+            Some(Sum::Ed25519(bytes)) => PublicKey::from_raw_ed25519(&bytes).ok_or_else(|| {
+                format_err!(error::Kind::InvalidKey, "malformed Ed25519 key").into()
+            }),
+            None => fail!(error::Kind::InvalidKey, "public key is missing"),
+        }
+    }
+}
+
+impl TryFrom<PublicKey> for RawPublicKey {
+    type Error = Error;
+
+    /// Only Ed25519 keys can be represented in the protobuf encoding
+    fn try_from(value: PublicKey) -> Result<Self, Self::Error> {
+        let sum = match value {
+            PublicKey::Ed25519(pk) => Sum::Ed25519(pk.as_bytes().to_vec()),
+            #[allow(unreachable_patterns)]
+            other => fail!(
+                error::Kind::InvalidKey,
+                "{} keys are not supported in protobuf encoding",
+                other.algorithm()
+            ),
+        };
+        Ok(RawPublicKey { sum: Some(sum) })
+    }
+}
+
 impl PartialOrd for PublicKey {
     fn partial_cmp(&self, other: &PublicKey) -> Option<Ordering> {
         Some(self.cmp(other))
@@ -155,19 +399,10 @@ impl PartialOrd for PublicKey {
 
 impl Ord for PublicKey {
     fn cmp(&self, other: &Self) -> Ordering {
-        match self {
-            PublicKey::Ed25519(a) => match other {
-                PublicKey::Ed25519(b) => a.as_bytes().cmp(b.as_bytes()),
-                #[cfg(feature = "secp256k1")]
-                PublicKey::Secp256k1(_) => Ordering::Less,
-            },
-            #[cfg(feature = "secp256k1")]
-            PublicKey::Secp256k1(a) => match other {
-                PublicKey::Ed25519(_) => Ordering::Greater,
-                #[cfg(feature = "secp256k1")]
-                PublicKey::Secp256k1(b) => a.as_bytes().cmp(b.as_bytes()),
-            },
-        }
+        // Order by algorithm first, then by key bytes
+        self.algorithm()
+            .cmp(&other.algorithm())
+            .then_with(|| self.as_bytes().cmp(other.as_bytes()))
     }
 }
 
@@ -188,6 +423,8 @@ impl TendermintKey {
             PublicKey::Ed25519(_) => Ok(TendermintKey::AccountKey(public_key)),
             #[cfg(feature = "secp256k1")]
             PublicKey::Secp256k1(_) => Ok(TendermintKey::AccountKey(public_key)),
+            #[cfg(feature = "sr25519")]
+            PublicKey::Sr25519(_) => Ok(TendermintKey::AccountKey(public_key)),
         }
     }
 
@@ -195,11 +432,12 @@ impl TendermintKey {
     pub fn new_consensus_key(public_key: PublicKey) -> Result<TendermintKey, Error> {
         #[allow(unreachable_patterns)]
         match public_key {
-            PublicKey::Ed25519(_) => Ok(TendermintKey::AccountKey(public_key)),
-            _ => fail!(
-                error::Kind::InvalidKey,
-                "only ed25519 consensus keys are supported"
-            ),
+            PublicKey::Ed25519(_) => Ok(TendermintKey::ConsensusKey(public_key)),
+            #[cfg(feature = "secp256k1")]
+            PublicKey::Secp256k1(_) => Ok(TendermintKey::ConsensusKey(public_key)),
+            #[cfg(feature = "sr25519")]
+            PublicKey::Sr25519(_) => Ok(TendermintKey::ConsensusKey(public_key)),
+            _ => fail!(error::Kind::InvalidKey, "unsupported consensus key type"),
         }
     }
 
@@ -222,13 +460,16 @@ impl Deref for TendermintKey {
 }
 
 /// Public key algorithms
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
 pub enum Algorithm {
     /// ed25519
     Ed25519,
 
     /// secp256k1
     Secp256k1,
+
+    /// sr25519
+    Sr25519,
 }
 
 impl Algorithm {
@@ -237,6 +478,7 @@ impl Algorithm {
         match self {
             Algorithm::Ed25519 => "ed25519",
             Algorithm::Secp256k1 => "secp256k1",
+            Algorithm::Sr25519 => "sr25519",
         }
     }
 }
@@ -254,6 +496,7 @@ impl FromStr for Algorithm {
         match s {
             "ed25519" => Ok(Algorithm::Ed25519),
             "secp256k1" => Ok(Algorithm::Secp256k1),
+            "sr25519" => Ok(Algorithm::Sr25519),
             _ => Err(error::Kind::Parse.into()),
         }
     }
@@ -294,6 +537,17 @@ where
         .serialize(serializer)
 }
 
+/// Serialize the bytes of an Sr25519 public key as Base64. Used for serializing JSON
+#[cfg(feature = "sr25519")]
+fn serialize_sr25519_base64<S>(pk: &Sr25519, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: ser::Serializer,
+{
+    String::from_utf8(base64::encode(&pk.to_bytes()))
+        .unwrap()
+        .serialize(serializer)
+}
+
 fn deserialize_ed25519_base64<'de, D>(deserializer: D) -> Result<Ed25519, D::Error>
 where
     D: de::Deserializer<'de>,
@@ -315,15 +569,29 @@ where
     Secp256k1::from_bytes(&bytes).ok_or_else(|| D::Error::custom("invalid secp256k1 key"))
 }
 
+#[cfg(feature = "sr25519")]
+fn deserialize_sr25519_base64<'de, D>(deserializer: D) -> Result<Sr25519, D::Error>
+where
+    D: de::Deserializer<'de>,
+{
+    use de::Error;
+    let encoded = String::deserialize(deserializer)?;
+    let bytes = base64::decode(&encoded).map_err(D::Error::custom)?;
+    Sr25519::from_bytes(&bytes).map_err(|_| D::Error::custom("invalid sr25519 key"))
+}
+
 #[cfg(test)]
 mod tests {
     use super::{PublicKey, TendermintKey};
+    use crate::account;
     use subtle_encoding::hex;
 
+    #[cfg(feature = "amino")]
     const EXAMPLE_CONSENSUS_KEY: &str =
         "4A25C6640A1F72B9C975338294EF51B6D1C33158BB6ECBA69FBC3FB5A33C9DCE";
 
     #[test]
+    #[cfg(feature = "amino")]
     fn test_consensus_serialization() {
         let example_key = TendermintKey::ConsensusKey(
             PublicKey::from_raw_ed25519(&hex::decode_upper(EXAMPLE_CONSENSUS_KEY).unwrap())
@@ -334,10 +602,47 @@ mod tests {
             example_key.to_bech32("cosmosvalconspub"),
             "cosmosvalconspub1zcjduepqfgjuveq2raetnjt4xwpffm63kmguxv2chdhvhf5lhslmtgeunh8qmf7exk"
         );
+        assert_eq!(
+            &PublicKey::from_bech32(
+                "cosmosvalconspub1zcjduepqfgjuveq2raetnjt4xwpffm63kmguxv2chdhvhf5lhslmtgeunh8qmf7exk",
+                "cosmosvalconspub"
+            )
+            .unwrap(),
+            example_key.public_key()
+        );
+        assert!(PublicKey::from_bech32(
+            "cosmosvalconspub1zcjduepqfgjuveq2raetnjt4xwpffm63kmguxv2chdhvhf5lhslmtgeunh8qmf7exk",
+            "cosmospub"
+        )
+        .is_err());
     }
 
     #[test]
-    #[cfg(feature = "secp256k1")]
+    #[cfg(feature = "amino")]
+    fn test_base64_and_hex_round_trip() {
+        use super::Algorithm;
+
+        let example_key =
+            PublicKey::from_raw_ed25519(&hex::decode_upper(EXAMPLE_CONSENSUS_KEY).unwrap())
+                .unwrap();
+
+        assert_eq!(
+            example_key.to_base64(),
+            "SiXGZAofcrnJdTOClO9RttHDMVi7bsumn7w/taM8nc4="
+        );
+        assert_eq!(
+            PublicKey::from_base64(Algorithm::Ed25519, &example_key.to_base64()).unwrap(),
+            example_key
+        );
+        assert_eq!(
+            PublicKey::from_hex(&example_key.to_hex()).unwrap(),
+            example_key
+        );
+        assert!(PublicKey::from_base64(Algorithm::Ed25519, "AAAA").is_err());
+    }
+
+    #[test]
+    #[cfg(all(feature = "amino", feature = "secp256k1"))]
     fn test_account_serialization() {
         const EXAMPLE_ACCOUNT_KEY: &str =
             "02A1633CAFCC01EBFB6D78E39F687A1F0995C62FC95F51EAD10A02EE0BE551B5DC";
@@ -368,4 +673,81 @@ mod tests {
         let reserialized_json = serde_json::to_string(&pubkey).unwrap();
         assert_eq!(reserialized_json.as_str(), json_string);
     }
+
+    #[test]
+    #[cfg(feature = "secp256k1")]
+    fn secp256k1_verification() {
+        use crate::{private_key::PrivateKey, signature::Signature};
+        use signature::Signer;
+
+        let json_string = "{\"type\":\"tendermint/PrivKeySecp256k1\",\"value\":\"AQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQE=\"}";
+        let private_key: PrivateKey = serde_json::from_str(json_string).unwrap();
+        let public_key = private_key.public_key();
+        assert!(public_key.secp256k1().is_some());
+        assert!(TendermintKey::new_consensus_key(public_key).is_ok());
+
+        let signer = k256::ecdsa::Signer::new(private_key.secp256k1_secret_key().unwrap()).unwrap();
+        let signature: k256::ecdsa::Signature = signer.sign(b"hello world");
+        let signature = Signature::from(signature);
+
+        assert!(public_key.verify(b"hello world", &signature).is_ok());
+        assert!(public_key.verify(b"goodbye world", &signature).is_err());
+
+        // Untagged signatures round-trip through JSON
+        let reserialized: Signature =
+            serde_json::from_str(&serde_json::to_string(&signature).unwrap()).unwrap();
+        assert!(public_key.verify(b"hello world", &reserialized).is_ok());
+    }
+
+    #[test]
+    #[cfg(feature = "sr25519")]
+    fn sr25519_json_parsing() {
+        let json_string = "{\"type\":\"tendermint/PubKeySr25519\",\"value\":\"4vKuCmq8TnGohKlhxQBRX1jjC2qlgt2NtqZZReCNLXY=\"}";
+        let pubkey: PublicKey = serde_json::from_str(json_string).unwrap();
+
+        assert!(pubkey.sr25519().is_some());
+        #[cfg(feature = "amino")]
+        assert_eq!(
+            pubkey.to_hex(),
+            "0DFB100520E2F2AE0A6ABC4E71A884A961C500515F58E30B6AA582DD8DB6A65945E08D2D76"
+        );
+
+        let reserialized_json = serde_json::to_string(&pubkey).unwrap();
+        assert_eq!(reserialized_json.as_str(), json_string);
+    }
+
+    #[test]
+    fn ed25519_address() {
+        let public_key = PublicKey::from_raw_ed25519(
+            &hex::decode_upper("14253D61EF42D166D02E68D540D07FDF8D65A9AF0ACAA46302688E788A8521E2")
+                .unwrap(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            public_key.address(),
+            "0CDA3F47EF3C4906693B170EF650EB968C5F4B2C"
+                .parse::<account::Id>()
+                .unwrap()
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "secp256k1")]
+    fn secp256k1_address() {
+        let public_key = PublicKey::from_raw_secp256k1(
+            &hex::decode_upper(
+                "02950E1CDFCB133D6024109FD489F734EEB4502418E538C28481F22BCE276F248C",
+            )
+            .unwrap(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            public_key.address(),
+            "7C2BB42A8BE69791EC763E51F5A49BCD41E82237"
+                .parse::<account::Id>()
+                .unwrap()
+        );
+    }
 }
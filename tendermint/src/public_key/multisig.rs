@@ -0,0 +1,463 @@
+//! Threshold multisignature public keys (`PubKeyMultisigThreshold`)
+
+use super::{Algorithm, PublicKey};
+use crate::{
+    account,
+    amino_types::message::AminoMessage,
+    error::{Error, Kind},
+    serializers,
+    signature::{Ed25519 as Ed25519Signature, Signature},
+};
+use anomaly::{fail, format_err};
+use prost_amino::Message as _;
+use prost_amino_derive::Message;
+use serde::{de, ser, Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::convert::{TryFrom, TryInto};
+
+#[cfg(feature = "secp256k1")]
+use crate::signature::Secp256k1 as Secp256k1Signature;
+#[cfg(feature = "secp256k1")]
+use signature::Signature as _;
+
+/// Amino name of threshold multisig public keys
+pub const AMINO_NAME: &str = "tendermint/PubKeyMultisigThreshold";
+
+/// Public key which requires valid signatures from at least `threshold` of
+/// its member keys
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ThresholdPublicKey {
+    threshold: usize,
+    pubkeys: Vec<PublicKey>,
+}
+
+impl ThresholdPublicKey {
+    /// Create a new threshold multisig public key
+    pub fn new(threshold: usize, pubkeys: Vec<PublicKey>) -> Result<Self, Error> {
+        if threshold == 0 {
+            fail!(Kind::InvalidKey, "multisig threshold must be positive");
+        }
+
+        if threshold > pubkeys.len() {
+            fail!(
+                Kind::InvalidKey,
+                "multisig threshold {} exceeds number of keys ({})",
+                threshold,
+                pubkeys.len()
+            );
+        }
+
+        Ok(Self { threshold, pubkeys })
+    }
+
+    /// Number of signatures required for a valid multisignature
+    pub fn threshold(&self) -> usize {
+        self.threshold
+    }
+
+    /// Member public keys, in signing order
+    pub fn pubkeys(&self) -> &[PublicKey] {
+        &self.pubkeys
+    }
+
+    /// Serialize this key as amino bytes
+    pub fn to_amino_bytes(&self) -> Vec<u8> {
+        AminoThresholdPubKey {
+            threshold: self.threshold as u64,
+            pubkeys: self.pubkeys.iter().map(|pk| pk.to_amino_bytes()).collect(),
+        }
+        .bytes_vec()
+    }
+
+    /// Parse a key from its amino encoding (as produced by
+    /// [`ThresholdPublicKey::to_amino_bytes`])
+    pub fn from_amino_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let msg = AminoThresholdPubKey::decode(bytes)
+            .map_err(|e| format_err!(Kind::Parse, "malformed multisig public key: {}", e))?;
+
+        let threshold = usize::try_from(msg.threshold).map_err(|_| {
+            format_err!(
+                Kind::InvalidKey,
+                "multisig threshold too large: {}",
+                msg.threshold
+            )
+        })?;
+
+        let pubkeys = msg
+            .pubkeys
+            .iter()
+            .map(|pubkey| PublicKey::from_amino_bytes(pubkey))
+            .collect::<Result<_, _>>()?;
+
+        Self::new(threshold, pubkeys)
+    }
+
+    /// Verify a multisignature over the given message
+    pub fn verify(&self, msg: &[u8], multisig: &Multisignature) -> Result<(), Error> {
+        if multisig.signers.len() != self.pubkeys.len() {
+            fail!(
+                Kind::SignatureInvalid,
+                "multisignature has {} signer bits, expected {}",
+                multisig.signers.len(),
+                self.pubkeys.len()
+            );
+        }
+
+        let num_signers = multisig.signers.iter().filter(|signed| **signed).count();
+
+        if num_signers != multisig.signatures.len() {
+            fail!(
+                Kind::SignatureInvalid,
+                "multisignature has {} signatures for {} signers",
+                multisig.signatures.len(),
+                num_signers
+            );
+        }
+
+        if num_signers < self.threshold {
+            fail!(
+                Kind::SignatureInvalid,
+                "multisignature has {} signatures, {} required",
+                num_signers,
+                self.threshold
+            );
+        }
+
+        let signers = self
+            .pubkeys
+            .iter()
+            .zip(&multisig.signers)
+            .filter(|(_, signed)| **signed)
+            .map(|(pubkey, _)| pubkey);
+
+        for (pubkey, sig_bytes) in signers.zip(&multisig.signatures) {
+            pubkey.verify(msg, &decode_signature(pubkey, sig_bytes)?)?;
+        }
+
+        Ok(())
+    }
+}
+
+// SHA256(amino(pk))[:20]
+impl From<&ThresholdPublicKey> for account::Id {
+    fn from(pk: &ThresholdPublicKey) -> account::Id {
+        let digest = Sha256::digest(&pk.to_amino_bytes());
+        account::Id::new(digest[..account::LENGTH].try_into().unwrap())
+    }
+}
+
+impl Serialize for ThresholdPublicKey {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        ThresholdPublicKeyJson::Threshold(ThresholdPublicKeyValue {
+            threshold: self.threshold as u64,
+            pubkeys: self.pubkeys.clone(),
+        })
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for ThresholdPublicKey {
+    fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use de::Error as _;
+        let ThresholdPublicKeyJson::Threshold(value) =
+            ThresholdPublicKeyJson::deserialize(deserializer)?;
+
+        let threshold = usize::try_from(value.threshold).map_err(D::Error::custom)?;
+        Self::new(threshold, value.pubkeys).map_err(D::Error::custom)
+    }
+}
+
+/// Signatures from a subset of the members of a [`ThresholdPublicKey`]
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Multisignature {
+    signers: Vec<bool>,
+    signatures: Vec<Vec<u8>>,
+}
+
+impl Multisignature {
+    /// Create an empty multisignature for a key with `num_keys` members
+    pub fn new(num_keys: usize) -> Self {
+        Self {
+            signers: vec![false; num_keys],
+            signatures: vec![],
+        }
+    }
+
+    /// Add the signature of the member key at `index`
+    pub fn add_signature(&mut self, index: usize, signature: &Signature) -> Result<(), Error> {
+        if index >= self.signers.len() {
+            fail!(
+                Kind::OutOfRange,
+                "signer index {} out of range ({} keys)",
+                index,
+                self.signers.len()
+            );
+        }
+
+        // Signatures are stored in the order of the member keys
+        let position = self.signers[..index].iter().filter(|s| **s).count();
+
+        if self.signers[index] {
+            self.signatures[position] = signature.to_bytes();
+        } else {
+            self.signers[index] = true;
+            self.signatures.insert(position, signature.to_bytes());
+        }
+
+        Ok(())
+    }
+
+    /// Which member keys signed
+    pub fn signers(&self) -> &[bool] {
+        &self.signers
+    }
+
+    /// Raw signatures, in the order of the member keys
+    pub fn signatures(&self) -> &[Vec<u8>] {
+        &self.signatures
+    }
+
+    /// Decode a multisignature from amino bytes
+    pub fn decode(bytes: &[u8]) -> Result<Self, Error> {
+        let msg = AminoMultisignature::decode(bytes)
+            .map_err(|e| format_err!(Kind::Parse, "malformed multisignature: {}", e))?;
+
+        let signers = match msg.bit_array {
+            Some(bit_array) => bit_array.to_bits()?,
+            None => vec![],
+        };
+
+        Ok(Self {
+            signers,
+            signatures: msg.sigs,
+        })
+    }
+
+    /// Serialize this multisignature as amino bytes
+    pub fn to_amino_bytes(&self) -> Vec<u8> {
+        AminoMultisignature {
+            bit_array: Some(AminoCompactBitArray::from_bits(&self.signers)),
+            sigs: self.signatures.clone(),
+        }
+        .bytes_vec()
+    }
+}
+
+/// Interpret raw signature bytes according to the signer's key type
+fn decode_signature(pubkey: &PublicKey, bytes: &[u8]) -> Result<Signature, Error> {
+    match pubkey.algorithm() {
+        Algorithm::Ed25519 => Ed25519Signature::from_bytes(bytes)
+            .map(Into::into)
+            .map_err(|_| format_err!(Kind::SignatureInvalid, "malformed Ed25519 signature").into()),
+        #[cfg(feature = "secp256k1")]
+        Algorithm::Secp256k1 => Secp256k1Signature::from_bytes(bytes)
+            .map(Into::into)
+            .map_err(|_| {
+                format_err!(
+                    Kind::SignatureInvalid,
+                    "malformed ECDSA/secp256k1 signature"
+                )
+                .into()
+            }),
+        algorithm => fail!(
+            Kind::SignatureInvalid,
+            "unsupported multisig member key type: {}",
+            algorithm
+        ),
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", content = "value")]
+enum ThresholdPublicKeyJson {
+    #[serde(rename = "tendermint/PubKeyMultisigThreshold")]
+    Threshold(ThresholdPublicKeyValue),
+}
+
+#[derive(Serialize, Deserialize)]
+struct ThresholdPublicKeyValue {
+    #[serde(with = "serializers::from_str")]
+    threshold: u64,
+    pubkeys: Vec<PublicKey>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+#[amino_name = "tendermint/PubKeyMultisigThreshold"]
+struct AminoThresholdPubKey {
+    #[prost_amino(uint64, tag = "1")]
+    threshold: u64,
+    /// Amino-encoded member keys (including their type prefixes)
+    #[prost_amino(bytes, repeated, tag = "2")]
+    pubkeys: Vec<Vec<u8>>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct AminoMultisignature {
+    #[prost_amino(message, tag = "1")]
+    bit_array: Option<AminoCompactBitArray>,
+    #[prost_amino(bytes, repeated, tag = "2")]
+    sigs: Vec<Vec<u8>>,
+}
+
+/// Go's `CompactBitArray`: bits packed MSB-first, with the number of bits used
+/// in the last byte stored separately
+#[derive(Clone, PartialEq, Message)]
+struct AminoCompactBitArray {
+    #[prost_amino(uint32, tag = "1")]
+    extra_bits_stored: u32,
+    #[prost_amino(bytes, tag = "2")]
+    elems: Vec<u8>,
+}
+
+impl AminoCompactBitArray {
+    fn from_bits(bits: &[bool]) -> Self {
+        let mut elems = vec![0u8; (bits.len() + 7) / 8];
+
+        for (i, _) in bits.iter().enumerate().filter(|(_, bit)| **bit) {
+            elems[i / 8] |= 1 << (7 - (i % 8));
+        }
+
+        Self {
+            extra_bits_stored: (bits.len() % 8) as u32,
+            elems,
+        }
+    }
+
+    fn to_bits(&self) -> Result<Vec<bool>, Error> {
+        if self.extra_bits_stored >= 8 || (self.elems.is_empty() && self.extra_bits_stored != 0) {
+            fail!(Kind::Parse, "malformed multisignature bit array");
+        }
+
+        let size = match self.extra_bits_stored {
+            0 => self.elems.len() * 8,
+            extra => (self.elems.len() - 1) * 8 + extra as usize,
+        };
+
+        Ok((0..size)
+            .map(|i| self.elems[i / 8] & (1 << (7 - (i % 8))) != 0)
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{Keypair, SecretKey, Signer};
+    #[cfg(feature = "secp256k1")]
+    use subtle_encoding::hex;
+
+    fn keypair(seed: u8) -> Keypair {
+        let secret = SecretKey::from_bytes(&[seed; 32]).unwrap();
+        let public = (&secret).into();
+        Keypair { secret, public }
+    }
+
+    #[test]
+    fn verify_multisignature() {
+        let keypairs = vec![keypair(1), keypair(2), keypair(3)];
+        let pubkey =
+            ThresholdPublicKey::new(2, keypairs.iter().map(|kp| kp.public.into()).collect())
+                .unwrap();
+
+        let msg = b"multisig payload";
+        let mut multisig = Multisignature::new(3);
+        multisig
+            .add_signature(2, &keypairs[2].sign(msg).into())
+            .unwrap();
+        assert!(pubkey.verify(msg, &multisig).is_err());
+
+        multisig
+            .add_signature(0, &keypairs[0].sign(msg).into())
+            .unwrap();
+        assert_eq!(multisig.signers(), &[true, false, true]);
+        assert!(pubkey.verify(msg, &multisig).is_ok());
+        assert!(pubkey.verify(b"something else", &multisig).is_err());
+
+        let decoded = Multisignature::decode(&multisig.to_amino_bytes()).unwrap();
+        assert_eq!(decoded, multisig);
+        assert!(pubkey.verify(msg, &decoded).is_ok());
+    }
+
+    #[test]
+    fn amino_and_json_encoding() {
+        let pubkey =
+            ThresholdPublicKey::new(1, vec![keypair(1).public.into(), keypair(2).public.into()])
+                .unwrap();
+
+        let amino_bytes = pubkey.to_amino_bytes();
+        assert_eq!(&amino_bytes[..6], &[0x22, 0xC1, 0xF7, 0xE2, 0x08, 0x01]);
+        assert_eq!(
+            &amino_bytes[6..13],
+            &[0x12, 0x25, 0x16, 0x24, 0xDE, 0x64, 0x20]
+        );
+
+        let json = serde_json::to_string(&pubkey).unwrap();
+        assert!(json.starts_with(
+            "{\"type\":\"tendermint/PubKeyMultisigThreshold\",\"value\":{\"threshold\":\"1\""
+        ));
+        assert_eq!(
+            serde_json::from_str::<ThresholdPublicKey>(&json).unwrap(),
+            pubkey
+        );
+    }
+
+    /// Key of a multisig account of the cosmoshub genesis in `rpc/tests/support`
+    #[cfg(feature = "secp256k1")]
+    const GENESIS_MULTISIG_JSON: &str = r#"{
+        "type": "tendermint/PubKeyMultisigThreshold",
+        "value": {
+            "threshold": "2",
+            "pubkeys": [
+                {
+                    "type": "tendermint/PubKeySecp256k1",
+                    "value": "AlHMIiMT4+ubmUFOJswrv9fwUJ33cWolv2e5ddzm51P9"
+                },
+                {
+                    "type": "tendermint/PubKeySecp256k1",
+                    "value": "ApYO482K2n1xA754WsviWNSRZ9IyA9zodACZKCSedq10"
+                },
+                {
+                    "type": "tendermint/PubKeySecp256k1",
+                    "value": "AiRJtkfOtRkVHpB/4edZmTi/AKTNq+jPQjFWb0X3Hn22"
+                }
+            ]
+        }
+    }"#;
+
+    #[test]
+    #[cfg(feature = "secp256k1")]
+    fn amino_round_trip() {
+        let pubkey: ThresholdPublicKey = serde_json::from_str(GENESIS_MULTISIG_JSON).unwrap();
+        let amino_bytes = hex::decode_upper(
+            "22C1F7E208021226EB5AE987210251CC222313E3EB9B99414E26CC2BBFD7F0509DF7716A25BF67B975DCE6\
+             E753FD1226EB5AE9872102960EE3CD8ADA7D7103BE785ACBE258D49167D23203DCE874009928249E76AD74\
+             1226EB5AE98721022449B647CEB519151E907FE1E7599938BF00A4CDABE8CF4231566F45F71E7DB6",
+        )
+        .unwrap();
+
+        // The account address Go derived from its amino encoding, i.e. the
+        // delegator address (cosmos1kj0h4kn4z5xvedu2nd9c4a9a559wvpuvemr0vq)
+        // of the gentx it signed
+        assert_eq!(
+            account::Id::from(&pubkey).to_string(),
+            "B49F7ADA75150CCCB78A9B4B8AF4BDA50AE6078C"
+        );
+        assert_eq!(pubkey.to_amino_bytes(), amino_bytes);
+        assert_eq!(
+            ThresholdPublicKey::from_amino_bytes(&amino_bytes).unwrap(),
+            pubkey
+        );
+
+        assert!(ThresholdPublicKey::from_amino_bytes(&amino_bytes[..40]).is_err());
+        assert!(
+            ThresholdPublicKey::from_amino_bytes(&pubkey.pubkeys()[0].to_amino_bytes()).is_err()
+        );
+    }
+
+    #[test]
+    fn invalid_threshold() {
+        let pubkeys = vec![keypair(1).public.into()];
+        assert!(ThresholdPublicKey::new(0, pubkeys.clone()).is_err());
+        assert!(ThresholdPublicKey::new(2, pubkeys).is_err());
+    }
+}
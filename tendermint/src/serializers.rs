@@ -36,18 +36,30 @@
 //! Vec<u8>              <-> HexString:            #[serde(with="serializers::bytes::hexstring")]
 //! Vec<u8>              <-> Base64String:         #[serde(with="serializers::bytes::base64string")]
 //! Vec<u8>              <-> String:               #[serde(with="serializers::bytes::string")]
+//! PublicKey            <-> amino JSON:           #[serde(with="serializers::public_key")]
+//! PublicKey            <-> HexString:            #[serde(with="serializers::public_key::hexstring")]
+//! Option<block::Id>    <-> BlockID (empty = nil) #[serde(with="serializers::block_id::optional")]
+//!
+//! Protobuf's canonical JSON mapping, for gRPC gateways (see [`proto_json`]):
+//! Vec<u8>              <-> Base64String:         #[serde(with="serializers::proto_json::bytes")]
+//! i64, u64, Height     <-> string:               #[serde(with="serializers::proto_json::int64")]
+//! Time                 <-> RFC 3339 string       #[serde(with="serializers::proto_json::timestamp")]
+//! std::time::Duration  <-> seconds as "1.5s"     #[serde(with="serializers::proto_json::duration")]
 //!
 //! Notes:
 //! * Any type that has the "FromStr" trait can be serialized into a string with
 //!   serializers::primitives::string.
 //! * serializers::bytes::* deserializes a null value into an empty vec![].
 
+pub mod block_id;
 pub mod bytes;
 pub mod from_str;
+pub mod proto_json;
+pub mod public_key;
 pub mod time_duration;
 
 mod raw_commit_sig;
-pub(crate) use raw_commit_sig::BlockIDFlag;
+pub use raw_commit_sig::BlockIDFlag;
 pub(crate) use raw_commit_sig::RawCommitSig;
 
 #[cfg(test)]
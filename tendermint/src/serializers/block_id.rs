@@ -0,0 +1,194 @@
+//! Block IDs as Tendermint serializes them, where the zero value (an empty
+//! hash and an empty part set header) means "no block ID", e.g. in nil votes
+//! and in the header of the first block.
+
+use crate::{
+    block::{self, parts},
+    Hash,
+};
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+use std::str::FromStr;
+
+/// Serialize and deserialize an `Option<block::Id>`, where `None` is the
+/// empty block ID
+pub mod optional {
+    use super::*;
+
+    /// Serialize an `Option<block::Id>`, writing `None` as the empty block ID
+    pub fn serialize<S>(block_id: &Option<block::Id>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match block_id {
+            Some(block_id) => JsonBlockId::from(block_id),
+            None => JsonBlockId::default(),
+        }
+        .serialize(serializer)
+    }
+
+    /// Deserialize an `Option<block::Id>`, reading `null` and the empty block
+    /// ID as `None`.
+    ///
+    /// Like Tendermint, a block ID with a part set header but no hash is
+    /// rejected rather than treated as empty.
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<block::Id>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let json = match <Option<JsonBlockId>>::deserialize(deserializer)? {
+            Some(json) => json,
+            None => return Ok(None),
+        };
+
+        let parts = json.parts.parse().map_err(D::Error::custom)?;
+
+        if json.hash.is_empty() {
+            return match parts {
+                None => Ok(None),
+                Some(_) => Err(D::Error::custom(
+                    "block ID has a part set header but no hash",
+                )),
+            };
+        }
+
+        let hash = Hash::from_str(&json.hash).map_err(|e| D::Error::custom(format!("{}", e)))?;
+        Ok(Some(block::Id::new(hash, parts)))
+    }
+}
+
+/// Serialize and deserialize an `Option<parts::Header>`, where `None` is the
+/// empty part set header
+pub mod optional_parts {
+    use super::*;
+
+    /// Serialize an `Option<parts::Header>`, writing `None` as the empty part
+    /// set header
+    pub fn serialize<S>(parts: &Option<parts::Header>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match parts {
+            Some(parts) => JsonPartSetHeader::from(parts),
+            None => JsonPartSetHeader::default(),
+        }
+        .serialize(serializer)
+    }
+
+    /// Deserialize an `Option<parts::Header>`, reading `null` and the empty
+    /// part set header as `None`
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<parts::Header>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        <Option<JsonPartSetHeader>>::deserialize(deserializer)?
+            .unwrap_or_default()
+            .parse()
+            .map_err(D::Error::custom)
+    }
+}
+
+/// Block ID in the JSON form produced by Tendermint
+#[derive(Default, Deserialize, Serialize)]
+struct JsonBlockId {
+    hash: String,
+    #[serde(default, deserialize_with = "super::null_as_default")]
+    parts: JsonPartSetHeader,
+}
+
+impl From<&block::Id> for JsonBlockId {
+    fn from(block_id: &block::Id) -> Self {
+        JsonBlockId {
+            hash: block_id.hash.to_string(),
+            parts: block_id
+                .parts
+                .as_ref()
+                .map(JsonPartSetHeader::from)
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// Part set header in the JSON form produced by Tendermint
+#[derive(Default, Deserialize, Serialize)]
+struct JsonPartSetHeader {
+    #[serde(with = "super::from_str")]
+    total: u64,
+    hash: String,
+}
+
+impl JsonPartSetHeader {
+    /// Parse this header, treating the zero value as `None`
+    fn parse(self) -> Result<Option<parts::Header>, String> {
+        if self.total == 0 && self.hash.is_empty() {
+            return Ok(None);
+        }
+
+        let hash = Hash::from_str(&self.hash)
+            .map_err(|e| format!("invalid part set header hash: {}", e))?;
+
+        Ok(Some(parts::Header::new(self.total, hash)))
+    }
+}
+
+impl From<&parts::Header> for JsonPartSetHeader {
+    fn from(parts: &parts::Header) -> Self {
+        JsonPartSetHeader {
+            total: parts.total,
+            hash: parts.hash.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Deserialize, PartialEq, Serialize)]
+    struct Vote {
+        #[serde(with = "super::optional")]
+        block_id: Option<block::Id>,
+    }
+
+    const EMPTY_BLOCK_ID: &str = r#"{"block_id":{"hash":"","parts":{"total":"0","hash":""}}}"#;
+
+    #[test]
+    fn nil_block_id_round_trip() {
+        let vote = serde_json::from_str::<Vote>(EMPTY_BLOCK_ID).unwrap();
+        assert_eq!(vote.block_id, None);
+        assert_eq!(serde_json::to_string(&vote).unwrap(), EMPTY_BLOCK_ID);
+
+        let null = serde_json::from_str::<Vote>(r#"{"block_id":null}"#).unwrap();
+        assert_eq!(null.block_id, None);
+    }
+
+    #[test]
+    fn block_id_round_trip() {
+        let json = r#"{"block_id":{"hash":"1ED5B68A21D82D091C25D535F52C2D47419162618245C60D69B162A366F97A45","parts":{"total":"1","hash":"BBA710736635FA20CDB4F48732563869E90871D31FE9E7DE3D900CD4334D8775"}}}"#;
+        let vote = serde_json::from_str::<Vote>(json).unwrap();
+        assert_eq!(
+            vote.block_id
+                .as_ref()
+                .unwrap()
+                .parts
+                .as_ref()
+                .unwrap()
+                .total,
+            1
+        );
+        assert_eq!(serde_json::to_string(&vote).unwrap(), json);
+
+        // Block IDs without parts still have an (empty) part set header
+        let without_parts = Vote {
+            block_id: Some(block::Id::new(vote.block_id.unwrap().hash, None)),
+        };
+        let json = serde_json::to_string(&without_parts).unwrap();
+        assert!(json.ends_with(r#""parts":{"total":"0","hash":""}}}"#));
+        assert_eq!(serde_json::from_str::<Vote>(&json).unwrap(), without_parts);
+    }
+
+    #[test]
+    fn rejects_parts_without_hash() {
+        let json = r#"{"block_id":{"hash":"","parts":{"total":"1","hash":"BBA710736635FA20CDB4F48732563869E90871D31FE9E7DE3D900CD4334D8775"}}}"#;
+        assert!(serde_json::from_str::<Vote>(json).is_err());
+    }
+}
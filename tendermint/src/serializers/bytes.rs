@@ -54,6 +54,39 @@ pub mod base64string {
     }
 }
 
+/// Serialize into a list of base64strings, deserialize from a list of
+/// base64strings
+pub mod vec_base64string {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use subtle_encoding::base64;
+
+    /// Deserialize a list of base64strings into Vec<Vec<u8>>
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Vec<Vec<u8>>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Option::<Vec<String>>::deserialize(deserializer)?
+            .unwrap_or_default()
+            .iter()
+            .map(|string| base64::decode(string).map_err(serde::de::Error::custom))
+            .collect()
+    }
+
+    /// Serialize from a list of T into a list of base64strings
+    pub fn serialize<S, T>(value: &[T], serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+        T: AsRef<[u8]>,
+    {
+        let base64_strings = value
+            .iter()
+            .map(|bytes| String::from_utf8(base64::encode(bytes.as_ref())))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(serde::ser::Error::custom)?;
+        serializer.collect_seq(base64_strings)
+    }
+}
+
 /// Serialize into string, deserialize from string
 pub(crate) mod string {
     use serde::{Deserialize, Deserializer, Serializer};
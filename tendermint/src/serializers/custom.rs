@@ -26,38 +26,7 @@ pub fn parse_non_empty_block_id<'de, D>(deserializer: D) -> Result<Option<block:
 where
     D: Deserializer<'de>,
 {
-    #[derive(Deserialize)]
-    struct Parts {
-        #[serde(with = "super::from_str")]
-        total: u64,
-        hash: String,
-    }
-    #[derive(Deserialize)]
-    struct BlockId {
-        hash: String,
-        parts: Parts,
-    }
-    if let Some(tmp_id) = <Option<BlockId>>::deserialize(deserializer)? {
-        if tmp_id.hash.is_empty() {
-            Ok(None)
-        } else {
-            Ok(Some(block::Id {
-                hash: Hash::from_str(&tmp_id.hash)
-                    .map_err(|err| D::Error::custom(format!("{}", err)))?,
-                parts: if tmp_id.parts.hash.is_empty() {
-                    None
-                } else {
-                    Some(block::parts::Header {
-                        total: tmp_id.parts.total,
-                        hash: Hash::from_str(&tmp_id.parts.hash)
-                            .map_err(|err| D::Error::custom(format!("{}", err)))?,
-                    })
-                },
-            }))
-        }
-    } else {
-        Ok(None)
-    }
+    super::block_id::optional::deserialize(deserializer)
 }
 
 /// Parse null as default
@@ -0,0 +1,272 @@
+//! Serializers following protobuf's canonical JSON mapping, as used by gRPC
+//! gateways, rather than the JSON produced by the Tendermint RPC:
+//!
+//! <https://developers.google.com/protocol-buffers/docs/proto3#json>
+//!
+//! Bytes are standard base64 (URL-safe and unpadded base64 is accepted too),
+//! 64-bit integers are strings (numbers are accepted too), timestamps are
+//! RFC 3339 in UTC and durations are seconds suffixed with `s`, both with 0,
+//! 3, 6 or 9 fractional digits.
+
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+
+/// Serialize and deserialize `Vec<u8>` as a base64 string
+pub mod bytes {
+    use super::*;
+    use subtle_encoding::base64;
+
+    /// Deserialize a standard or URL-safe, padded or unpadded base64 string
+    /// (or `null`) into `Vec<u8>`
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Vec<u8>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let string = Option::<String>::deserialize(deserializer)?.unwrap_or_default();
+        let mut standard = string.replace('-', "+").replace('_', "/");
+
+        while standard.len() % 4 != 0 {
+            standard.push('=');
+        }
+
+        base64::decode(&standard).map_err(D::Error::custom)
+    }
+
+    /// Serialize from `T` into a padded standard base64 string
+    pub fn serialize<S, T>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+        T: AsRef<[u8]>,
+    {
+        String::from_utf8(base64::encode(value.as_ref()))
+            .map_err(serde::ser::Error::custom)?
+            .serialize(serializer)
+    }
+}
+
+/// Serialize and deserialize 64-bit integers (or anything else implementing
+/// `Display` and `FromStr`, e.g. `block::Height`) as strings
+pub mod int64 {
+    use super::*;
+    use std::{fmt::Display, str::FromStr};
+
+    /// JSON forms of a 64-bit integer
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Repr {
+        String(String),
+        Signed(i64),
+        Unsigned(u64),
+    }
+
+    /// Deserialize `T` from a string or a number
+    pub fn deserialize<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+    where
+        D: Deserializer<'de>,
+        T: FromStr,
+        T::Err: Display,
+    {
+        let string = match Repr::deserialize(deserializer)? {
+            Repr::String(string) => string,
+            Repr::Signed(value) => value.to_string(),
+            Repr::Unsigned(value) => value.to_string(),
+        };
+
+        string
+            .parse()
+            .map_err(|e| D::Error::custom(format!("{}", e)))
+    }
+
+    /// Serialize `T` as a string
+    pub fn serialize<S, T>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+        T: Display,
+    {
+        value.to_string().serialize(serializer)
+    }
+}
+
+/// Serialize and deserialize `Time` as an RFC 3339 timestamp
+pub mod timestamp {
+    use super::*;
+    use crate::Time;
+    use chrono::{DateTime, Utc};
+
+    /// Deserialize `Time` from an RFC 3339 timestamp with any UTC offset
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Time, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Time::parse_from_rfc3339(&String::deserialize(deserializer)?)
+            .map_err(|e| D::Error::custom(format!("{}", e)))
+    }
+
+    /// Serialize `Time` as an RFC 3339 timestamp in UTC with 0, 3, 6 or 9
+    /// fractional digits
+    pub fn serialize<S>(value: &Time, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let datetime = DateTime::<Utc>::from(*value);
+        let seconds = datetime.format("%Y-%m-%dT%H:%M:%S");
+
+        format!(
+            "{}{}Z",
+            seconds,
+            fractional_digits(datetime.timestamp_subsec_nanos())
+        )
+        .serialize(serializer)
+    }
+}
+
+/// Serialize and deserialize `std::time::Duration` as seconds suffixed with
+/// `s`, e.g. `"1.500s"`
+pub mod duration {
+    use super::*;
+    use std::time::Duration;
+
+    /// Deserialize `Duration` from seconds with up to 9 fractional digits,
+    /// suffixed with `s`
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Duration, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let string = String::deserialize(deserializer)?;
+        parse(&string).ok_or_else(|| D::Error::custom(format!("invalid duration: {:?}", string)))
+    }
+
+    /// Serialize `Duration` as seconds with 0, 3, 6 or 9 fractional digits,
+    /// suffixed with `s`
+    pub fn serialize<S>(value: &Duration, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        format!(
+            "{}{}s",
+            value.as_secs(),
+            fractional_digits(value.subsec_nanos())
+        )
+        .serialize(serializer)
+    }
+
+    fn parse(string: &str) -> Option<Duration> {
+        if !string.ends_with('s') {
+            return None;
+        }
+
+        let seconds = &string[..string.len() - 1];
+        let (whole, fraction) = match seconds.find('.') {
+            Some(dot) => (&seconds[..dot], &seconds[dot + 1..]),
+            None => (seconds, ""),
+        };
+
+        let digits_only = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+
+        if whole.is_empty() || !digits_only(whole) || fraction.len() > 9 || !digits_only(fraction) {
+            return None;
+        }
+
+        let nanos = if fraction.is_empty() {
+            0
+        } else {
+            format!("{:0<9}", fraction).parse().ok()?
+        };
+
+        Some(Duration::new(whole.parse().ok()?, nanos))
+    }
+}
+
+/// Format nanoseconds as a decimal point followed by 3, 6 or 9 digits, or as
+/// nothing if they are zero
+fn fractional_digits(nanos: u32) -> String {
+    if nanos == 0 {
+        String::new()
+    } else if nanos % 1_000_000 == 0 {
+        format!(".{:03}", nanos / 1_000_000)
+    } else if nanos % 1_000 == 0 {
+        format!(".{:06}", nanos / 1_000)
+    } else {
+        format!(".{:09}", nanos)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{block, Time};
+    use serde::{Deserialize, Serialize};
+    use std::time::Duration;
+
+    #[derive(Debug, Deserialize, PartialEq, Serialize)]
+    struct Message {
+        #[serde(with = "super::bytes")]
+        data: Vec<u8>,
+        #[serde(with = "super::int64")]
+        height: block::Height,
+        #[serde(with = "super::int64")]
+        offset: i64,
+        #[serde(with = "super::timestamp")]
+        time: Time,
+        #[serde(with = "super::duration")]
+        timeout: Duration,
+    }
+
+    #[test]
+    fn canonical_json_round_trip() {
+        let message = Message {
+            data: vec![0xfb, 0xff, 0x01],
+            height: block::Height::from(12345u64),
+            offset: -1,
+            time: "2020-09-01T12:34:56.120Z".parse().unwrap(),
+            timeout: Duration::from_millis(1500),
+        };
+
+        let json = serde_json::to_string(&message).unwrap();
+        assert_eq!(
+            json,
+            r#"{"data":"+/8B","height":"12345","offset":"-1","time":"2020-09-01T12:34:56.120Z","timeout":"1.500s"}"#
+        );
+        assert_eq!(serde_json::from_str::<Message>(&json).unwrap(), message);
+    }
+
+    #[test]
+    fn accepts_alternative_forms() {
+        let message = serde_json::from_str::<Message>(
+            r#"{"data":"-_8B","height":12345,"offset":-1,"time":"2020-09-01T14:34:56.12+02:00","timeout":"1.5s"}"#,
+        )
+        .unwrap();
+
+        assert_eq!(message.data, vec![0xfb, 0xff, 0x01]);
+        assert_eq!(message.height.value(), 12345);
+        assert_eq!(message.offset, -1);
+        assert_eq!(
+            message.time,
+            "2020-09-01T12:34:56.120Z".parse::<Time>().unwrap()
+        );
+        assert_eq!(message.timeout, Duration::from_millis(1500));
+
+        let unpadded = serde_json::from_str::<Message>(
+            r#"{"data":"AQ","height":"1","offset":"0","time":"2020-09-01T12:34:56Z","timeout":"0s"}"#,
+        )
+        .unwrap();
+        assert_eq!(unpadded.data, vec![0x01]);
+        assert_eq!(
+            serde_json::to_value(&unpadded).unwrap()["time"],
+            "2020-09-01T12:34:56Z"
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_durations() {
+        for timeout in &["1.5", "s", "-1s", "1.0000000001s", "1.s5"] {
+            let json = format!(
+                r#"{{"data":"","height":"1","offset":"0","time":"2020-09-01T12:34:56Z","timeout":"{}"}}"#,
+                timeout
+            );
+            assert!(
+                serde_json::from_str::<Message>(&json).is_err(),
+                "{}",
+                timeout
+            );
+        }
+    }
+}
@@ -0,0 +1,319 @@
+//! PublicKey serialization compatible with the JSON produced by the various
+//! Tendermint versions.
+//!
+//! Deserialization accepts any of the following forms:
+//! - amino JSON: `{"type": "tendermint/PubKeyEd25519", "value": "<base64>"}`
+//! - ABCI validator updates: `{"type": "ed25519", "data": "<base64>"}`
+//! - protobuf JSON: `{"Sum": {"type": "tendermint.crypto.PublicKey_Ed25519",
+//!   "value": {"ed25519": "<base64>"}}}` or `{"ed25519": "<base64>"}`
+//! - a bare hex string of the raw key bytes (or, with the `amino` feature,
+//!   of the amino-encoded key)
+//! - with the `amino` feature, a bare Bech32 string of the amino-encoded key
+//!   (e.g. `cosmosvalconspub1...`)
+//!
+//! Serialization uses the amino JSON form by default, or a bare hex string
+//! with [`hexstring`].
+
+use crate::{
+    error::{Error, Kind},
+    PublicKey,
+};
+use anomaly::{fail, format_err};
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+#[cfg(feature = "amino")]
+use subtle_encoding::bech32;
+use subtle_encoding::{base64, hex};
+
+/// Serialize a `PublicKey` in the amino JSON form
+pub fn serialize<S>(public_key: &PublicKey, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    public_key.serialize(serializer)
+}
+
+/// Deserialize a `PublicKey` from any of the supported forms
+pub fn deserialize<'de, D>(deserializer: D) -> Result<PublicKey, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let public_key = match Repr::deserialize(deserializer)? {
+        Repr::AminoJson(public_key) => Ok(public_key),
+        Repr::Abci(key) => key.decode(),
+        Repr::Protobuf { sum } => sum.decode(),
+        Repr::ProtobufJson(key) => key.decode(),
+        Repr::String(encoded) => decode_string(&encoded),
+    };
+
+    public_key.map_err(|e| D::Error::custom(format!("{}", e)))
+}
+
+/// Serialize and deserialize a `PublicKey` as a hex string of the raw key
+/// bytes, accepting any of the supported forms when deserializing
+pub mod hexstring {
+    use super::*;
+
+    /// Serialize a `PublicKey` as an upper case hex string of the raw key bytes
+    pub fn serialize<S>(public_key: &PublicKey, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        String::from_utf8(hex::encode_upper(public_key.as_bytes()))
+            .unwrap()
+            .serialize(serializer)
+    }
+
+    /// Deserialize a `PublicKey` from any of the supported forms
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<PublicKey, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        super::deserialize(deserializer)
+    }
+}
+
+/// Public key encodings produced by Tendermint
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Repr {
+    AminoJson(PublicKey),
+    Abci(AbciKey),
+    Protobuf {
+        #[serde(rename = "Sum")]
+        sum: ProtobufSum,
+    },
+    ProtobufJson(ProtobufKey),
+    String(String),
+}
+
+/// Public keys in ABCI validator updates
+#[derive(Deserialize)]
+#[serde(tag = "type", content = "data")]
+enum AbciKey {
+    #[serde(rename = "ed25519")]
+    Ed25519(String),
+
+    #[cfg(feature = "secp256k1")]
+    #[serde(rename = "secp256k1")]
+    Secp256k1(String),
+}
+
+/// The `Sum` oneof of a protobuf `PublicKey`
+#[derive(Deserialize)]
+#[serde(tag = "type", content = "value")]
+enum ProtobufSum {
+    #[serde(rename = "tendermint.crypto.PublicKey_Ed25519")]
+    Ed25519 { ed25519: String },
+
+    #[cfg(feature = "secp256k1")]
+    #[serde(rename = "tendermint.crypto.PublicKey_Secp256K1")]
+    Secp256k1 { secp256k1: String },
+}
+
+/// Protobuf `PublicKey` in its canonical JSON mapping
+#[derive(Deserialize)]
+enum ProtobufKey {
+    #[serde(rename = "ed25519")]
+    Ed25519(String),
+
+    #[cfg(feature = "secp256k1")]
+    #[serde(rename = "secp256k1")]
+    Secp256k1(String),
+}
+
+impl AbciKey {
+    fn decode(self) -> Result<PublicKey, Error> {
+        match self {
+            AbciKey::Ed25519(encoded) => decode_ed25519(&encoded),
+            #[cfg(feature = "secp256k1")]
+            AbciKey::Secp256k1(encoded) => decode_secp256k1(&encoded),
+        }
+    }
+}
+
+impl ProtobufSum {
+    fn decode(self) -> Result<PublicKey, Error> {
+        match self {
+            ProtobufSum::Ed25519 { ed25519 } => decode_ed25519(&ed25519),
+            #[cfg(feature = "secp256k1")]
+            ProtobufSum::Secp256k1 { secp256k1 } => decode_secp256k1(&secp256k1),
+        }
+    }
+}
+
+impl ProtobufKey {
+    fn decode(self) -> Result<PublicKey, Error> {
+        match self {
+            ProtobufKey::Ed25519(encoded) => decode_ed25519(&encoded),
+            #[cfg(feature = "secp256k1")]
+            ProtobufKey::Secp256k1(encoded) => decode_secp256k1(&encoded),
+        }
+    }
+}
+
+fn decode_ed25519(encoded: &str) -> Result<PublicKey, Error> {
+    let bytes = base64::decode(encoded).map_err(|e| Kind::Parse.context(e))?;
+    PublicKey::from_raw_ed25519(&bytes)
+        .ok_or_else(|| format_err!(Kind::InvalidKey, "malformed Ed25519 key").into())
+}
+
+#[cfg(feature = "secp256k1")]
+fn decode_secp256k1(encoded: &str) -> Result<PublicKey, Error> {
+    let bytes = base64::decode(encoded).map_err(|e| Kind::Parse.context(e))?;
+    PublicKey::from_raw_secp256k1(&bytes)
+        .ok_or_else(|| format_err!(Kind::InvalidKey, "malformed secp256k1 key").into())
+}
+
+/// Decode a bare hex or Bech32 encoded public key
+fn decode_string(encoded: &str) -> Result<PublicKey, Error> {
+    if let Ok(bytes) = hex::decode_upper(encoded).or_else(|_| hex::decode(encoded)) {
+        return decode_raw_or_amino(&bytes);
+    }
+
+    #[cfg(feature = "amino")]
+    {
+        if let Ok((_, bytes)) = bech32::decode(encoded) {
+            return PublicKey::from_amino_bytes(&bytes);
+        }
+    }
+
+    fail!(Kind::Parse, "unrecognized public key encoding: {}", encoded)
+}
+
+fn decode_raw_or_amino(bytes: &[u8]) -> Result<PublicKey, Error> {
+    let raw_key = match bytes.len() {
+        32 => PublicKey::from_raw_ed25519(bytes),
+        #[cfg(feature = "secp256k1")]
+        33 => PublicKey::from_raw_secp256k1(bytes),
+        _ => None,
+    };
+
+    match raw_key {
+        Some(public_key) => Ok(public_key),
+        None => decode_amino(bytes),
+    }
+}
+
+#[cfg(feature = "amino")]
+fn decode_amino(bytes: &[u8]) -> Result<PublicKey, Error> {
+    PublicKey::from_amino_bytes(bytes)
+}
+
+#[cfg(not(feature = "amino"))]
+fn decode_amino(bytes: &[u8]) -> Result<PublicKey, Error> {
+    fail!(
+        Kind::InvalidKey,
+        "unrecognized {}-byte public key",
+        bytes.len()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Deserialize, Serialize)]
+    struct Key {
+        #[serde(with = "crate::serializers::public_key")]
+        pub_key: PublicKey,
+    }
+
+    #[derive(Debug, Deserialize, Serialize)]
+    struct HexKey {
+        #[serde(with = "crate::serializers::public_key::hexstring")]
+        pub_key: PublicKey,
+    }
+
+    const EXAMPLE_KEY_HEX: &str =
+        "4A25C6640A1F72B9C975338294EF51B6D1C33158BB6ECBA69FBC3FB5A33C9DCE";
+    const EXAMPLE_KEY_BASE64: &str = "SiXGZAofcrnJdTOClO9RttHDMVi7bsumn7w/taM8nc4=";
+
+    fn example_key() -> PublicKey {
+        PublicKey::from_raw_ed25519(&hex::decode_upper(EXAMPLE_KEY_HEX).unwrap()).unwrap()
+    }
+
+    fn parse(pub_key_json: &str) -> PublicKey {
+        serde_json::from_str::<Key>(&format!(r#"{{"pub_key": {}}}"#, pub_key_json))
+            .unwrap()
+            .pub_key
+    }
+
+    #[test]
+    fn deserializes_json_forms() {
+        for pub_key_json in &[
+            format!(
+                r#"{{"type": "tendermint/PubKeyEd25519", "value": "{}"}}"#,
+                EXAMPLE_KEY_BASE64
+            ),
+            format!(r#"{{"type": "ed25519", "data": "{}"}}"#, EXAMPLE_KEY_BASE64),
+            format!(
+                r#"{{"Sum": {{"type": "tendermint.crypto.PublicKey_Ed25519", "value": {{"ed25519": "{}"}}}}}}"#,
+                EXAMPLE_KEY_BASE64
+            ),
+            format!(r#"{{"ed25519": "{}"}}"#, EXAMPLE_KEY_BASE64),
+            format!(r#""{}""#, EXAMPLE_KEY_HEX),
+            format!(r#""{}""#, EXAMPLE_KEY_HEX.to_lowercase()),
+        ] {
+            assert_eq!(parse(pub_key_json), example_key(), "{}", pub_key_json);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "amino")]
+    fn deserializes_amino_strings() {
+        assert_eq!(
+            parse(
+                r#""cosmosvalconspub1zcjduepqfgjuveq2raetnjt4xwpffm63kmguxv2chdhvhf5lhslmtgeunh8qmf7exk""#
+            ),
+            example_key()
+        );
+        assert_eq!(
+            parse(&format!(r#""{}""#, example_key().to_hex())),
+            example_key()
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_keys() {
+        for pub_key_json in &[
+            r#"{"type": "tendermint/PubKeyEd25519", "value": "AAAA"}"#,
+            r#"{"type": "ed25519", "data": "not base64"}"#,
+            r#"{"type": "unknown", "data": "AAAA"}"#,
+            r#""not a key""#,
+            r#""ABCD""#,
+        ] {
+            let json = format!(r#"{{"pub_key": {}}}"#, pub_key_json);
+            assert!(serde_json::from_str::<Key>(&json).is_err(), "{}", json);
+        }
+    }
+
+    #[test]
+    fn serializes_amino_json_by_default() {
+        let json = serde_json::to_string(&Key {
+            pub_key: example_key(),
+        })
+        .unwrap();
+
+        assert_eq!(
+            json,
+            format!(
+                r#"{{"pub_key":{{"type":"tendermint/PubKeyEd25519","value":"{}"}}}}"#,
+                EXAMPLE_KEY_BASE64
+            )
+        );
+    }
+
+    #[test]
+    fn serializes_hexstring() {
+        let json = serde_json::to_string(&HexKey {
+            pub_key: example_key(),
+        })
+        .unwrap();
+
+        assert_eq!(json, format!(r#"{{"pub_key":"{}"}}"#, EXAMPLE_KEY_HEX));
+        assert_eq!(
+            serde_json::from_str::<HexKey>(&json).unwrap().pub_key,
+            example_key()
+        );
+    }
+}
@@ -8,7 +8,7 @@ use std::str::FromStr;
 // Implements decision: https://github.com/tendermint/tendermint/blob/master/docs/architecture/adr-025-commit.md#decision
 
 /// indicate which BlockID the signature is for
-#[derive(Serialize_repr, Deserialize_repr, PartialEq, Debug)]
+#[derive(Serialize_repr, Deserialize_repr, Copy, Clone, PartialEq, Eq, Debug)]
 #[repr(u8)]
 pub enum BlockIDFlag {
     /// vote is not included in the Commit.Precommits
@@ -34,6 +34,22 @@ pub struct RawCommitSig {
     /// Signature
     #[serde(default, deserialize_with = "option_signature")]
     pub signature: Option<Signature>,
+    /// Vote extension (Tendermint 0.38)
+    #[cfg(feature = "vote-extensions")]
+    #[serde(
+        default,
+        skip_serializing_if = "Vec::is_empty",
+        with = "crate::serializers::bytes::base64string"
+    )]
+    pub extension: Vec<u8>,
+    /// Vote extension signature (Tendermint 0.38)
+    #[cfg(feature = "vote-extensions")]
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "option_signature"
+    )]
+    pub extension_signature: Option<Signature>,
 }
 
 fn option_signature<'de, D>(deserializer: D) -> Result<Option<Signature>, D::Error>
@@ -121,6 +121,7 @@ fn deserialize_commit_sig_commit_vote() {
         validator_address,
         timestamp,
         signature,
+        ..
     } = result
     {
         assert_eq!(
@@ -1,13 +1,18 @@
 //! Cryptographic (a.k.a. digital) signatures
 
 pub use ed25519::{Signature as Ed25519, SIGNATURE_LENGTH as ED25519_SIGNATURE_SIZE};
-pub use signature::{Signer, Verifier};
+pub use signature::Signer;
 
 #[cfg(feature = "secp256k1")]
 pub use k256::ecdsa::Signature as Secp256k1;
 
+use crate::error::{Error, Kind};
+use crate::PublicKey;
+use anomaly::{fail, format_err};
 use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
 use signature::Signature as _;
+use std::convert::TryFrom;
+use subtle::{self, ConstantTimeEq};
 use subtle_encoding::base64;
 
 /// Signatures
@@ -16,6 +21,11 @@ use subtle_encoding::base64;
 pub enum Signature {
     /// Ed25519 block signature
     Ed25519(Ed25519),
+
+    /// ECDSA/secp256k1 signature
+    #[cfg(feature = "secp256k1")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "secp256k1")))]
+    Secp256k1(Secp256k1),
 }
 
 impl Signature {
@@ -23,13 +33,27 @@ impl Signature {
     pub fn algorithm(&self) -> Algorithm {
         match self {
             Signature::Ed25519(_) => Algorithm::Ed25519,
+            #[cfg(feature = "secp256k1")]
+            Signature::Secp256k1(_) => Algorithm::EcdsaSecp256k1,
         }
     }
 
     /// Get Ed25519 signature
     pub fn ed25519(self) -> Option<Ed25519> {
+        #[allow(unreachable_patterns)]
         match self {
             Signature::Ed25519(sig) => Some(sig),
+            _ => None,
+        }
+    }
+
+    /// Get ECDSA/secp256k1 signature
+    #[cfg(feature = "secp256k1")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "secp256k1")))]
+    pub fn secp256k1(self) -> Option<Secp256k1> {
+        match self {
+            Signature::Secp256k1(sig) => Some(sig),
+            _ => None,
         }
     }
 
@@ -48,24 +72,76 @@ impl AsRef<[u8]> for Signature {
     fn as_ref(&self) -> &[u8] {
         match self {
             Signature::Ed25519(sig) => sig.as_ref(),
+            #[cfg(feature = "secp256k1")]
+            Signature::Secp256k1(sig) => sig.as_ref(),
         }
     }
 }
 
+impl ConstantTimeEq for Signature {
+    #[inline]
+    fn ct_eq(&self, other: &Signature) -> subtle::Choice {
+        // Ed25519 and compact secp256k1 signatures have the same length
+        subtle::Choice::from((self.algorithm() == other.algorithm()) as u8)
+            & self.as_bytes().ct_eq(other.as_bytes())
+    }
+}
+
 impl From<Ed25519> for Signature {
     fn from(pk: Ed25519) -> Signature {
         Signature::Ed25519(pk)
     }
 }
 
+#[cfg(feature = "secp256k1")]
+impl From<Secp256k1> for Signature {
+    fn from(sig: Secp256k1) -> Signature {
+        Signature::Secp256k1(sig)
+    }
+}
+
+/// Signatures are encoded without a type tag, and both Ed25519 and
+/// ECDSA/secp256k1 signatures are 64 bytes, so they are decoded as Ed25519
+/// whenever the bytes are a valid Ed25519 signature. Verifying with a
+/// secp256k1 [`PublicKey`] reinterprets the bytes.
+///
+/// [`PublicKey`]: crate::PublicKey
+impl TryFrom<&[u8]> for Signature {
+    type Error = Error;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        match Ed25519::from_bytes(bytes) {
+            Ok(sig) => Ok(sig.into()),
+            #[cfg(feature = "secp256k1")]
+            Err(_) => Secp256k1::from_bytes(bytes).map(Into::into).map_err(|e| {
+                format_err!(Kind::SignatureInvalid, "malformed signature: {}", e).into()
+            }),
+            #[cfg(not(feature = "secp256k1"))]
+            Err(e) => Err(format_err!(Kind::SignatureInvalid, "malformed signature: {}", e).into()),
+        }
+    }
+}
+
+impl TryFrom<Vec<u8>> for Signature {
+    type Error = Error;
+
+    fn try_from(bytes: Vec<u8>) -> Result<Self, Self::Error> {
+        Signature::try_from(bytes.as_slice())
+    }
+}
+
+impl From<Signature> for Vec<u8> {
+    fn from(signature: Signature) -> Vec<u8> {
+        signature.to_bytes()
+    }
+}
+
 impl<'de> Deserialize<'de> for Signature {
     fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
         let bytes = base64::decode(String::deserialize(deserializer)?.as_bytes())
             .map_err(D::Error::custom)?;
 
-        Ed25519::from_bytes(&bytes)
-            .map(Into::into)
-            .map_err(D::Error::custom)
+        Signature::try_from(bytes.as_slice()).map_err(D::Error::custom)
     }
 }
 
@@ -77,6 +153,85 @@ impl Serialize for Signature {
     }
 }
 
+/// Backend used to check signatures when verifying commits and evidence.
+///
+/// [`DefaultVerifier`] checks signatures in software. Implement this trait to
+/// check them some other way, e.g. with an HSM or hardware acceleration.
+pub trait Verifier {
+    /// Verify that `signature` is a valid signature of `msg` by the holder of
+    /// `public_key`
+    fn verify(
+        &self,
+        public_key: &PublicKey,
+        msg: &[u8],
+        signature: &Signature,
+    ) -> Result<(), Error>;
+
+    /// Verify several signatures, succeeding only if all of them are valid.
+    ///
+    /// Implementations may check the signatures together, in which case the
+    /// error doesn't need to identify the invalid signature. By default the
+    /// signatures are checked one by one.
+    fn verify_batch(&self, batch: &[(&PublicKey, &[u8], &Signature)]) -> Result<(), Error> {
+        batch
+            .iter()
+            .try_for_each(|(public_key, msg, signature)| self.verify(public_key, msg, signature))
+    }
+}
+
+/// Software signature verification, batching Ed25519 signatures
+#[derive(Copy, Clone, Debug, Default)]
+pub struct DefaultVerifier;
+
+impl Verifier for DefaultVerifier {
+    fn verify(
+        &self,
+        public_key: &PublicKey,
+        msg: &[u8],
+        signature: &Signature,
+    ) -> Result<(), Error> {
+        public_key.verify(msg, signature)
+    }
+
+    fn verify_batch(&self, batch: &[(&PublicKey, &[u8], &Signature)]) -> Result<(), Error> {
+        let mut messages = vec![];
+        let mut signatures = vec![];
+        let mut public_keys = vec![];
+
+        for (public_key, msg, signature) in batch {
+            match (*public_key, *signature) {
+                (PublicKey::Ed25519(pk), Signature::Ed25519(sig)) => {
+                    messages.push(*msg);
+                    signatures.push(*sig);
+                    public_keys.push(*pk);
+                }
+                #[allow(unreachable_patterns)]
+                _ => self.verify(public_key, msg, signature)?,
+            }
+        }
+
+        // Batching only pays off with more than one signature
+        if messages.len() < 2 {
+            return messages
+                .iter()
+                .zip(signatures)
+                .zip(public_keys)
+                .try_for_each(|((msg, sig), pk)| {
+                    PublicKey::Ed25519(pk).verify(msg, &Signature::Ed25519(sig))
+                });
+        }
+
+        if ed25519_dalek::verify_batch(&messages, &signatures, &public_keys).is_err() {
+            fail!(
+                Kind::SignatureInvalid,
+                "Ed25519 batch signature verification failed"
+            );
+        }
+
+        Ok(())
+    }
+}
+
 /// Digital signature algorithms
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum Algorithm {
@@ -0,0 +1,153 @@
+//! Types of messages validators sign
+
+use crate::error::{Error, Kind};
+use anomaly::format_err;
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+use std::{
+    convert::TryFrom,
+    fmt::{self, Display},
+};
+use tendermint_proto::types::SignedMsgType as RawSignedMsgType;
+
+/// Types of signed messages, distinguishing votes from proposals in the
+/// bytes validators sign. The numeric values are shared by the protobuf and
+/// amino encodings:
+///
+/// <https://github.com/tendermint/tendermint/blob/455d34134cc53c334ebd3195ac22ea444c4b59bb/types/signed_msg_type.go#L3-L16>
+#[repr(u8)]
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub enum SignedMsgType {
+    /// Prevotes
+    Prevote = 0x01,
+
+    /// Precommits
+    Precommit = 0x02,
+
+    /// Proposals
+    Proposal = 0x20,
+}
+
+impl SignedMsgType {
+    /// Parse a message type from its numeric value
+    pub fn from_u32(value: u32) -> Option<SignedMsgType> {
+        match value {
+            0x01 => Some(SignedMsgType::Prevote),
+            0x02 => Some(SignedMsgType::Precommit),
+            0x20 => Some(SignedMsgType::Proposal),
+            _ => None,
+        }
+    }
+
+    /// Serialize this type as a byte
+    pub fn to_u8(self) -> u8 {
+        self as u8
+    }
+
+    /// Serialize this type as a 32-bit unsigned integer
+    pub fn to_u32(self) -> u32 {
+        self as u32
+    }
+
+    /// Is this a vote (prevote or precommit)?
+    pub fn is_vote(self) -> bool {
+        match self {
+            SignedMsgType::Prevote | SignedMsgType::Precommit => true,
+            SignedMsgType::Proposal => false,
+        }
+    }
+}
+
+impl Display for SignedMsgType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SignedMsgType::Prevote => write!(f, "prevote"),
+            SignedMsgType::Precommit => write!(f, "precommit"),
+            SignedMsgType::Proposal => write!(f, "proposal"),
+        }
+    }
+}
+
+impl TryFrom<i32> for SignedMsgType {
+    type Error = Error;
+
+    fn try_from(value: i32) -> Result<Self, Error> {
+        u32::try_from(value)
+            .ok()
+            .and_then(SignedMsgType::from_u32)
+            .ok_or_else(|| {
+                format_err!(Kind::Parse, "invalid signed message type: {}", value).into()
+            })
+    }
+}
+
+impl From<SignedMsgType> for i32 {
+    fn from(msg_type: SignedMsgType) -> i32 {
+        RawSignedMsgType::from(msg_type) as i32
+    }
+}
+
+impl TryFrom<RawSignedMsgType> for SignedMsgType {
+    type Error = Error;
+
+    fn try_from(value: RawSignedMsgType) -> Result<Self, Error> {
+        match value {
+            RawSignedMsgType::Prevote => Ok(SignedMsgType::Prevote),
+            RawSignedMsgType::Precommit => Ok(SignedMsgType::Precommit),
+            RawSignedMsgType::Proposal => Ok(SignedMsgType::Proposal),
+            RawSignedMsgType::Unknown => {
+                Err(format_err!(Kind::Parse, "unknown signed message type").into())
+            }
+        }
+    }
+}
+
+impl From<SignedMsgType> for RawSignedMsgType {
+    fn from(msg_type: SignedMsgType) -> RawSignedMsgType {
+        match msg_type {
+            SignedMsgType::Prevote => RawSignedMsgType::Prevote,
+            SignedMsgType::Precommit => RawSignedMsgType::Precommit,
+            SignedMsgType::Proposal => RawSignedMsgType::Proposal,
+        }
+    }
+}
+
+impl Serialize for SignedMsgType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_u8().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for SignedMsgType {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let byte = u8::deserialize(deserializer)?;
+        SignedMsgType::from_u32(byte.into())
+            .ok_or_else(|| D::Error::custom(format!("invalid signed message type: {}", byte)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numeric_mappings_agree() {
+        for &msg_type in &[
+            SignedMsgType::Prevote,
+            SignedMsgType::Precommit,
+            SignedMsgType::Proposal,
+        ] {
+            let raw = RawSignedMsgType::from(msg_type);
+            assert_eq!(raw as i32, msg_type.to_u32() as i32);
+            assert_eq!(SignedMsgType::try_from(raw).unwrap(), msg_type);
+            assert_eq!(
+                SignedMsgType::try_from(i32::from(msg_type)).unwrap(),
+                msg_type
+            );
+            assert_eq!(SignedMsgType::from_u32(msg_type.to_u32()), Some(msg_type));
+        }
+
+        assert!(SignedMsgType::try_from(0i32).is_err());
+        assert!(SignedMsgType::try_from(-1i32).is_err());
+        assert!(SignedMsgType::try_from(RawSignedMsgType::Unknown).is_err());
+    }
+}
@@ -0,0 +1,290 @@
+//! [`proptest`] strategies generating structurally valid domain types, e.g.
+//! for property tests of the crates built on them:
+//!
+//! ```
+//! use proptest::prelude::*;
+//! use tendermint::strategies;
+//!
+//! proptest! {
+//!     #[test]
+//!     fn headers_hash(header in strategies::header()) {
+//!         header.hash();
+//!     }
+//! }
+//! ```
+//!
+//! Generated values pass their `validate_basic` checks and round-trip
+//! through their protobuf encoding, but their signatures are arbitrary:
+//! they don't verify against the generated public keys.
+
+use crate::{
+    account, app, block, chain,
+    hash::{AppHash, Hash, SHA256_HASH_SIZE},
+    signature::{self, Signature},
+    validator, vote, PublicKey, Time,
+};
+use chrono::{TimeZone, Utc};
+use proptest::{collection::vec, option, prelude::*};
+use std::ops::RangeInclusive;
+
+/// Latest time generated: the end of year 9999, the latest protobuf
+/// timestamps represent
+const MAX_UNIX_SECONDS: i64 = 253_402_300_799;
+
+/// Maximum voting power of generated validators, so that the total voting
+/// power of generated sets stays far below Tendermint's maximum
+const MAX_VOTING_POWER: u64 = 1 << 40;
+
+/// Generate block heights, which are positive and fit in an `i64`
+pub fn height() -> impl Strategy<Value = block::Height> {
+    (1..=i64::MAX as u64).prop_map(block::Height::from)
+}
+
+/// Generate times between the Unix epoch and the end of year 9999
+pub fn time() -> impl Strategy<Value = Time> {
+    (0..=MAX_UNIX_SECONDS, 0..1_000_000_000u32)
+        .prop_map(|(seconds, nanos)| Utc.timestamp(seconds, nanos).into())
+}
+
+/// Generate SHA-256 hashes
+pub fn hash() -> impl Strategy<Value = Hash> {
+    any::<[u8; SHA256_HASH_SIZE]>().prop_map(Hash::Sha256)
+}
+
+/// Generate app hashes, which are empty or of up to 32 bytes
+pub fn app_hash() -> impl Strategy<Value = AppHash> {
+    vec(any::<u8>(), 0..=32).prop_map(AppHash::from)
+}
+
+/// Generate account IDs, e.g. validator addresses
+pub fn account_id() -> impl Strategy<Value = account::Id> {
+    any::<[u8; account::LENGTH]>().prop_map(account::Id::new)
+}
+
+/// Generate chain IDs, like `cosmoshub-4`
+pub fn chain_id() -> impl Strategy<Value = chain::Id> {
+    "[a-z][a-z0-9]{0,19}(-[1-9][0-9]{0,4})?".prop_map(|id| id.parse().unwrap())
+}
+
+/// Generate block IDs, with their part set headers
+pub fn block_id() -> impl Strategy<Value = block::Id> {
+    (hash(), 1..=u32::MAX as u64, hash()).prop_map(|(hash, total, parts_hash)| {
+        block::Id::new(hash, Some(block::parts::Header::new(total, parts_hash)))
+    })
+}
+
+/// Generate Ed25519 public keys, from random secret keys
+pub fn public_key() -> impl Strategy<Value = PublicKey> {
+    any::<[u8; 32]>().prop_map(|seed| {
+        let secret = ed25519_dalek::SecretKey::from_bytes(&seed).expect("seed is 32 bytes");
+        PublicKey::Ed25519((&secret).into())
+    })
+}
+
+/// Generate Ed25519 signatures, which verify nothing
+pub fn signature() -> impl Strategy<Value = Signature> {
+    (any::<[u8; 32]>(), any::<[u8; 32]>()).prop_map(|(r, s)| {
+        let mut bytes = [0u8; signature::ED25519_SIGNATURE_SIZE];
+        bytes[..32].copy_from_slice(&r);
+        bytes[32..].copy_from_slice(&s);
+
+        // Ed25519 signatures have the highest bits of `s` cleared
+        bytes[63] &= 0x0f;
+        Signature::Ed25519(signature::Ed25519::new(bytes))
+    })
+}
+
+/// Generate validators with some voting power, whose address is derived
+/// from their public key
+pub fn validator() -> impl Strategy<Value = validator::Info> {
+    (public_key(), 1..=MAX_VOTING_POWER)
+        .prop_map(|(pub_key, power)| validator::Info::new(pub_key, vote::Power::new(power)))
+}
+
+/// Generate validator sets with a number of validators in the given range
+pub fn validator_set(size: RangeInclusive<usize>) -> impl Strategy<Value = validator::Set> {
+    vec(validator(), size).prop_map(validator::Set::new)
+}
+
+/// Generate block headers: the first block has no last block ID, and later
+/// ones refer to their last block and commit
+pub fn header() -> impl Strategy<Value = block::Header> {
+    let ids = (chain_id(), height(), time(), any::<(u64, u64)>());
+    let last_block = (block_id(), hash());
+    let hashes = (
+        option::of(hash()),
+        hash(),
+        hash(),
+        hash(),
+        app_hash(),
+        option::of(hash()),
+        option::of(hash()),
+    );
+
+    (ids, any::<bool>(), last_block, hashes, account_id()).prop_map(
+        |(ids, first, last_block, hashes, proposer_address)| {
+            let (chain_id, height, time, (block_version, app_version)) = ids;
+            let height = if first {
+                block::Height::from(1u64)
+            } else {
+                height
+            };
+            let (last_block_id, last_commit_hash) = if height.value() == 1 {
+                (None, None)
+            } else {
+                (Some(last_block.0), Some(last_block.1))
+            };
+            let (
+                data_hash,
+                validators_hash,
+                next_validators_hash,
+                consensus_hash,
+                app_hash,
+                last_results_hash,
+                evidence_hash,
+            ) = hashes;
+
+            block::Header {
+                version: block::header::Version {
+                    block: block::Version(block_version),
+                    app: app::Version(app_version),
+                },
+                chain_id,
+                height,
+                time,
+                last_block_id,
+                last_commit_hash,
+                data_hash,
+                validators_hash,
+                next_validators_hash,
+                consensus_hash,
+                app_hash,
+                last_results_hash,
+                evidence_hash,
+                proposer_address,
+            }
+        },
+    )
+}
+
+/// Generate prevotes and precommits, for a block or nil
+pub fn vote() -> impl Strategy<Value = vote::Vote> {
+    let vote_type = prop_oneof![Just(vote::Type::Prevote), Just(vote::Type::Precommit)];
+
+    (
+        vote_type,
+        height(),
+        0..100u64,
+        option::of(block_id()),
+        time(),
+        account_id(),
+        0..150u64,
+        signature(),
+    )
+        .prop_map(
+            |(vote_type, height, round, block_id, timestamp, address, index, signature)| {
+                vote::Vote {
+                    vote_type,
+                    height,
+                    round,
+                    block_id,
+                    timestamp,
+                    validator_address: address,
+                    validator_index: index,
+                    signature,
+                    #[cfg(feature = "vote-extensions")]
+                    extension: vec![],
+                    #[cfg(feature = "vote-extensions")]
+                    extension_signature: None,
+                }
+            },
+        )
+}
+
+/// Generate commit signatures, for the block, nil or absent
+pub fn commit_sig() -> impl Strategy<Value = block::CommitSig> {
+    let present = (account_id(), time(), signature());
+
+    prop_oneof![
+        Just(block::CommitSig::BlockIDFlagAbsent),
+        present
+            .clone()
+            .prop_map(|(validator_address, timestamp, signature)| {
+                block::CommitSig::BlockIDFlagCommit {
+                    validator_address,
+                    timestamp,
+                    signature,
+                    #[cfg(feature = "vote-extensions")]
+                    extension: vec![],
+                    #[cfg(feature = "vote-extensions")]
+                    extension_signature: None,
+                }
+            }),
+        present.prop_map(|(validator_address, timestamp, signature)| {
+            block::CommitSig::BlockIDFlagNil {
+                validator_address,
+                timestamp,
+                signature,
+            }
+        }),
+    ]
+}
+
+/// Generate commits of up to 10 signatures, of which at least one isn't
+/// absent
+pub fn commit() -> impl Strategy<Value = block::Commit> {
+    let signatures = vec(commit_sig(), 1..=10).prop_filter("all signatures absent", |sigs| {
+        !sigs.iter().all(block::CommitSig::is_absent)
+    });
+
+    (height(), 0..100u64, block_id(), signatures).prop_map(
+        |(height, round, block_id, signatures)| block::Commit {
+            height,
+            round,
+            block_id,
+            signatures: block::CommitSigs::new(signatures),
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::convert::TryFrom;
+    use tendermint_proto::DomainType;
+
+    proptest! {
+        #[test]
+        fn headers_are_valid(header in header()) {
+            prop_assert!(header.validate_basic().is_ok());
+
+            let bytes = header.encode_vec().unwrap();
+            prop_assert_eq!(block::Header::decode_vec(&bytes).unwrap(), header);
+        }
+
+        #[test]
+        fn commits_are_valid(commit in commit()) {
+            prop_assert!(commit.validate_basic().is_ok());
+        }
+
+        #[test]
+        fn votes_round_trip(vote in vote()) {
+            let bytes = vote.encode_vec().unwrap();
+            prop_assert_eq!(vote::Vote::decode_vec(&bytes).unwrap(), vote);
+        }
+
+        #[test]
+        fn validator_sets_have_their_validators(set in validator_set(1..=8)) {
+            for validator in set.validators() {
+                prop_assert_eq!(account::Id::from(validator.pub_key), validator.address);
+                prop_assert!(validator.voting_power.value() <= MAX_VOTING_POWER);
+            }
+        }
+
+        #[test]
+        fn times_round_trip(time in time()) {
+            let timestamp = prost_types::Timestamp::from(time);
+            prop_assert_eq!(Time::try_from(timestamp).unwrap(), time);
+        }
+    }
+}
@@ -1,3 +1,11 @@
+use crate::{
+    account,
+    block::{self, signed_header::SignedHeader, Commit, CommitSig, CommitSigs},
+    validator,
+    vote::{self, SignedVote, Vote},
+    Signature,
+};
+use ed25519_dalek::{Keypair, SecretKey, Signer};
 use serde::{de::DeserializeOwned, Serialize};
 use std::fmt::Debug;
 
@@ -25,3 +33,117 @@ where
 
     assert_eq!(parsed0, parsed1);
 }
+
+/// Ed25519 keypair with a secret key of `seed` bytes
+pub fn keypair(seed: u8) -> Keypair {
+    let secret = SecretKey::from_bytes(&[seed; 32]).unwrap();
+    let public = (&secret).into();
+    Keypair { secret, public }
+}
+
+/// Sign the header from the serialization fixtures with the first
+/// `signers` of the given keypairs, the others being absent
+pub fn signed_header(keypairs: &[Keypair], signers: usize) -> SignedHeader {
+    let header: block::Header = serde_json::from_str(include_str!(
+        "../tests/support/serialization/block/header.json"
+    ))
+    .unwrap();
+    let block_id = block::Id::new(header.hash(), None);
+
+    let signatures = keypairs
+        .iter()
+        .enumerate()
+        .map(|(index, keypair)| {
+            if index >= signers {
+                return CommitSig::BlockIDFlagAbsent;
+            }
+
+            let validator_address = account::Id::from(keypair.public);
+            let vote = Vote {
+                vote_type: vote::Type::Precommit,
+                height: header.height,
+                round: 1,
+                block_id: Some(block_id.clone()),
+                timestamp: header.time,
+                validator_address,
+                validator_index: index as u64,
+                signature: Signature::Ed25519(keypair.sign(&[])),
+                #[cfg(feature = "vote-extensions")]
+                extension: vec![],
+                #[cfg(feature = "vote-extensions")]
+                extension_signature: None,
+            };
+            let sign_bytes = SignedVote::new(
+                (&vote).into(),
+                header.chain_id.as_str(),
+                validator_address,
+                vote.signature,
+            )
+            .sign_bytes();
+
+            CommitSig::BlockIDFlagCommit {
+                validator_address,
+                timestamp: header.time,
+                signature: Signature::Ed25519(keypair.sign(&sign_bytes)),
+                #[cfg(feature = "vote-extensions")]
+                extension: vec![],
+                #[cfg(feature = "vote-extensions")]
+                extension_signature: None,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    SignedHeader {
+        commit: Commit {
+            height: header.height,
+            round: 1,
+            block_id,
+            signatures: CommitSigs::new(signatures),
+        },
+        header,
+    }
+}
+
+/// Replace the signature at `index` with a precommit for nil
+pub fn vote_nil(signed_header: &mut SignedHeader, keypair: &Keypair, index: usize) {
+    let validator_address = account::Id::from(keypair.public);
+    let vote = Vote {
+        vote_type: vote::Type::Precommit,
+        height: signed_header.header.height,
+        round: signed_header.commit.round,
+        block_id: None,
+        timestamp: signed_header.header.time,
+        validator_address,
+        validator_index: index as u64,
+        signature: Signature::Ed25519(keypair.sign(&[])),
+        #[cfg(feature = "vote-extensions")]
+        extension: vec![],
+        #[cfg(feature = "vote-extensions")]
+        extension_signature: None,
+    };
+    let sign_bytes = SignedVote::new(
+        (&vote).into(),
+        signed_header.header.chain_id.as_str(),
+        validator_address,
+        vote.signature,
+    )
+    .sign_bytes();
+
+    let mut signatures = signed_header.commit.signatures.clone().into_vec();
+    signatures[index] = CommitSig::BlockIDFlagNil {
+        validator_address,
+        timestamp: signed_header.header.time,
+        signature: Signature::Ed25519(keypair.sign(&sign_bytes)),
+    };
+    signed_header.commit.signatures = CommitSigs::new(signatures);
+}
+
+/// Validator set of the given keypairs, with equal voting powers
+pub fn validator_set(keypairs: &[Keypair]) -> validator::Set {
+    validator::Set::new(
+        keypairs
+            .iter()
+            .map(|keypair| validator::Info::new(keypair.public.into(), vote::Power::new(10)))
+            .collect(),
+    )
+}
@@ -2,10 +2,13 @@
 
 use crate::error::{Error, Kind};
 
-use chrono::{DateTime, SecondsFormat, Utc};
-use serde::{Deserialize, Serialize};
+use anomaly::{fail, format_err};
+use chrono::{DateTime, FixedOffset, NaiveDateTime, SecondsFormat, TimeZone, Timelike, Utc};
+use prost_types::Timestamp;
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
 use tai64::TAI64N;
 
+use std::convert::TryFrom;
 use std::fmt;
 use std::ops::{Add, Sub};
 use std::str::FromStr;
@@ -13,7 +16,11 @@ use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 /// Tendermint timestamps
 /// <https://github.com/tendermint/spec/blob/d46cd7f573a2c6a2399fcab2cde981330aa63f37/spec/core/data_structures.md#time>
-#[derive(Serialize, Deserialize, Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+///
+/// Timestamps are serialized in the canonical form produced by Go's
+/// `time.RFC3339Nano` in UTC (see [`Time::to_canonical_string`]), and parsed
+/// from any RFC 3339 date (see [`Time::parse_from_rfc3339`]).
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
 pub struct Time(DateTime<Utc>);
 
 impl Time {
@@ -24,11 +31,13 @@ impl Time {
 
     /// Get the `UNIX_EPOCH` time ("1970-01-01 00:00:00 UTC") as a `Timestamp`
     pub fn unix_epoch() -> Self {
-        UNIX_EPOCH.into()
+        Time(Utc.timestamp(0, 0))
     }
 
     /// Calculate the amount of time which has passed since another `Timestamp`
     /// as a `std::time::Duration`
+    ///
+    /// Returns an error if `other` is later than this timestamp.
     pub fn duration_since(&self, other: Time) -> Result<Duration, Error> {
         self.0
             .signed_duration_since(other.0)
@@ -36,20 +45,88 @@ impl Time {
             .map_err(|_| Kind::OutOfRange.into())
     }
 
-    /// Parse a timestamp from an RFC 3339 date
+    /// Calculate the absolute amount of time between this timestamp and
+    /// another, regardless of which one comes first
+    pub fn abs_duration_since(&self, other: Time) -> Duration {
+        let duration = if self >= &other {
+            self.duration_since(other)
+        } else {
+            other.duration_since(*self)
+        };
+        duration.expect("timestamps out of range")
+    }
+
+    /// Add the given duration to this timestamp, returning `None` if the result
+    /// is out of range
+    pub fn checked_add(self, duration: Duration) -> Option<Self> {
+        let duration = chrono::Duration::from_std(duration).ok()?;
+        self.0.checked_add_signed(duration).map(Time)
+    }
+
+    /// Subtract the given duration from this timestamp, returning `None` if
+    /// the result is out of range
+    pub fn checked_sub(self, duration: Duration) -> Option<Self> {
+        let duration = chrono::Duration::from_std(duration).ok()?;
+        self.0.checked_sub_signed(duration).map(Time)
+    }
+
+    /// Is this timestamp strictly earlier than the other one?
+    pub fn before(&self, other: Time) -> bool {
+        self < &other
+    }
+
+    /// Is this timestamp strictly later than the other one?
+    pub fn after(&self, other: Time) -> bool {
+        self > &other
+    }
+
+    /// Parse a timestamp from an RFC 3339 date, as emitted by the various
+    /// Tendermint and Cosmos SDK versions: with 0 to 9 fractional second
+    /// digits, and either `Z` or a numeric UTC offset (e.g. `+02:00`).
     pub fn parse_from_rfc3339(s: &str) -> Result<Time, Error> {
-        Ok(Time(DateTime::parse_from_rfc3339(s)?.with_timezone(&Utc)))
+        parse_rfc3339(s)
+            .map(|t| Time(t.with_timezone(&Utc)))
+            .ok_or_else(|| format_err!(Kind::Parse, "invalid RFC 3339 timestamp: {:?}", s).into())
     }
 
-    /// Return an RFC 3339 and ISO 8601 date and time string with 6 subseconds digits and Z.
+    /// Return an RFC 3339 and ISO 8601 date and time string with 9 subseconds digits and Z.
     pub fn to_rfc3339(&self) -> String {
         self.0.to_rfc3339_opts(SecondsFormat::Nanos, true)
     }
 
+    /// Return the canonical string form of this timestamp, as produced by Go's
+    /// `time.RFC3339Nano` in UTC: trailing zeros of the subseconds are
+    /// removed, and so is the decimal point if the subseconds are zero.
+    pub fn to_canonical_string(&self) -> String {
+        let rfc3339 = self.0.to_rfc3339_opts(SecondsFormat::Nanos, true);
+        let (datetime, nanos) = rfc3339.split_at(rfc3339.len() - "000000000Z".len());
+        let nanos = nanos[..9].trim_end_matches('0');
+
+        if nanos.is_empty() {
+            format!("{}Z", datetime.trim_end_matches('.'))
+        } else {
+            format!("{}{}Z", datetime, nanos)
+        }
+    }
+
     /// Convert this timestamp to a `SystemTime`
+    ///
+    /// Timestamps before the Unix epoch are supported where the platform's
+    /// `SystemTime` can represent them.
     pub fn to_system_time(&self) -> Result<SystemTime, Error> {
-        let duration_since_epoch = self.duration_since(Self::unix_epoch())?;
-        Ok(UNIX_EPOCH + duration_since_epoch)
+        let since_epoch = self.0.signed_duration_since(Self::unix_epoch().0);
+
+        let system_time = match since_epoch.to_std() {
+            Ok(duration) => UNIX_EPOCH.checked_add(duration),
+            Err(_) => (-since_epoch)
+                .to_std()
+                .ok()
+                .and_then(|duration| UNIX_EPOCH.checked_sub(duration)),
+        };
+
+        system_time.ok_or_else(|| {
+            format_err!(Kind::OutOfRange, "time not representable as SystemTime").into()
+        })
     }
 }
 
@@ -67,6 +144,83 @@ impl FromStr for Time {
     }
 }
 
+impl<'de> Deserialize<'de> for Time {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Self::parse_from_rfc3339(&String::deserialize(deserializer)?)
+            .map_err(|e| D::Error::custom(format!("{}", e)))
+    }
+}
+
+impl Serialize for Time {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_canonical_string().serialize(serializer)
+    }
+}
+
+/// Parse an RFC 3339 date (`YYYY-MM-DDTHH:MM:SS[.fraction](Z|±HH:MM)`)
+fn parse_rfc3339(s: &str) -> Option<DateTime<FixedOffset>> {
+    if s.len() < 20 || !s.is_ascii() {
+        return None;
+    }
+
+    let (datetime, mut rest) = s.split_at(19);
+
+    // RFC 3339 allows a lower case `t` (or a space) to separate the date and time
+    let datetime = match datetime.as_bytes()[10] {
+        b'T' | b't' | b' ' => NaiveDateTime::parse_from_str(
+            &format!("{}T{}", &datetime[..10], &datetime[11..]),
+            "%Y-%m-%dT%H:%M:%S",
+        )
+        .ok()?,
+        _ => return None,
+    };
+
+    let mut nanos = 0;
+
+    if rest.starts_with('.') {
+        let digits = rest[1..]
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len() - 1);
+
+        if digits == 0 || digits > 9 {
+            return None;
+        }
+
+        nanos = rest[1..=digits].parse::<u32>().ok()? * 10u32.pow(9 - digits as u32);
+        rest = &rest[digits + 1..];
+    }
+
+    let offset = match rest {
+        "Z" | "z" => FixedOffset::east(0),
+        _ if rest.len() == 6 && rest.as_bytes()[3] == b':' => {
+            let sign = match rest.as_bytes()[0] {
+                b'+' => 1,
+                b'-' => -1,
+                _ => return None,
+            };
+
+            let hours = parse_digits(&rest[1..3]).filter(|h| *h < 24)?;
+            let minutes = parse_digits(&rest[4..6]).filter(|m| *m < 60)?;
+            FixedOffset::east(sign * (hours * 3600 + minutes * 60))
+        }
+        _ => return None,
+    };
+
+    // Leap seconds are represented by chrono as nanoseconds beyond 1 second
+    let datetime = datetime.with_nanosecond(datetime.nanosecond() + nanos)?;
+
+    offset.from_local_datetime(&datetime).single()
+}
+
+/// Parse a string consisting only of ASCII digits
+fn parse_digits(s: &str) -> Option<i32> {
+    if s.bytes().all(|b| b.is_ascii_digit()) {
+        s.parse().ok()
+    } else {
+        None
+    }
+}
+
 impl From<DateTime<Utc>> for Time {
     fn from(t: DateTime<Utc>) -> Time {
         Time(t)
@@ -103,12 +257,64 @@ impl From<Time> for TAI64N {
     }
 }
 
+impl TryFrom<Timestamp> for Time {
+    type Error = Error;
+
+    fn try_from(value: Timestamp) -> Result<Self, Self::Error> {
+        // Like Google's well-known type, nanoseconds must be normalized to
+        // [0, 999_999_999] even for timestamps before the epoch
+        if value.nanos < 0 || value.nanos > 999_999_999 {
+            fail!(
+                Kind::OutOfRange,
+                "timestamp nanoseconds out of range: {}",
+                value.nanos
+            );
+        }
+
+        Utc.timestamp_opt(value.seconds, value.nanos as u32)
+            .single()
+            .map(Time)
+            .ok_or_else(|| {
+                format_err!(
+                    Kind::OutOfRange,
+                    "timestamp out of range: {}s {}ns",
+                    value.seconds,
+                    value.nanos
+                )
+                .into()
+            })
+    }
+}
+
+impl From<Time> for Timestamp {
+    /// Convert to the normalized form of Google's well-known type, with
+    /// nanoseconds in [0, 999_999_999], which is the only one Go produces
+    fn from(value: Time) -> Self {
+        let seconds = value.0.timestamp();
+        let nanos = value.0.timestamp_subsec_nanos();
+
+        // chrono represents leap seconds with nanoseconds past a second, which
+        // Go has no notion of: carry them into the seconds
+        if nanos > 999_999_999 {
+            Timestamp {
+                seconds: seconds + 1,
+                nanos: (nanos - 1_000_000_000) as i32,
+            }
+        } else {
+            Timestamp {
+                seconds,
+                nanos: nanos as i32,
+            }
+        }
+    }
+}
+
 impl Add<Duration> for Time {
     type Output = Self;
 
     fn add(self, rhs: Duration) -> Self::Output {
-        let st: SystemTime = self.into();
-        (st + rhs).into()
+        self.checked_add(rhs)
+            .expect("overflow when adding duration to time")
     }
 }
 
@@ -116,8 +322,8 @@ impl Sub<Duration> for Time {
     type Output = Self;
 
     fn sub(self, rhs: Duration) -> Self::Output {
-        let st: SystemTime = self.into();
-        (st - rhs).into()
+        self.checked_sub(rhs)
+            .expect("overflow when subtracting duration from time")
     }
 }
 
@@ -126,3 +332,113 @@ pub trait ParseTimestamp {
     /// Parse `Timestamp`, or return an `Error` if parsing failed
     fn parse_timestamp(&self) -> Result<Time, Error>;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn arithmetic() {
+        let t = Time::from_str("2020-09-01T00:00:00.999999999Z").unwrap();
+        let later = t + Duration::new(1, 1);
+        assert_eq!(later.to_rfc3339(), "2020-09-01T00:00:02.000000000Z");
+        assert_eq!(later - Duration::new(1, 1), t);
+        assert_eq!(later.duration_since(t).unwrap(), Duration::new(1, 1));
+        assert!(t.duration_since(later).is_err());
+        assert_eq!(t.abs_duration_since(later), Duration::new(1, 1));
+        assert!(t.before(later) && later.after(t));
+        assert!(!t.after(t) && !t.before(t));
+        assert!(t.checked_add(Duration::from_secs(u64::MAX)).is_none());
+    }
+
+    #[test]
+    fn before_unix_epoch() {
+        let t = Time::unix_epoch() - Duration::new(1, 500_000_000);
+        assert_eq!(t.to_rfc3339(), "1969-12-31T23:59:58.500000000Z");
+        assert_eq!(
+            t.to_system_time().unwrap(),
+            UNIX_EPOCH - Duration::new(1, 500_000_000)
+        );
+
+        let timestamp = Timestamp::from(t);
+        assert_eq!((timestamp.seconds, timestamp.nanos), (-2, 500_000_000));
+        assert_eq!(Time::try_from(timestamp).unwrap(), t);
+    }
+
+    #[test]
+    fn parses_variable_precision() {
+        let expected = Time::from_str("2020-09-01T12:34:56.120000000Z").unwrap();
+
+        for s in &[
+            "2020-09-01T12:34:56.12Z",
+            "2020-09-01T12:34:56.120Z",
+            "2020-09-01T12:34:56.120000000Z",
+            "2020-09-01t12:34:56.12z",
+            "2020-09-01 12:34:56.12Z",
+            "2020-09-01T14:34:56.12+02:00",
+            "2020-09-01T09:04:56.12-03:30",
+        ] {
+            assert_eq!(Time::from_str(s).unwrap(), expected, "{}", s);
+        }
+
+        assert_eq!(
+            Time::from_str("2020-09-01T12:34:56Z").unwrap(),
+            Time::from_str("2020-09-01T12:34:56.000000000Z").unwrap()
+        );
+        assert_eq!(
+            Time::from_str("2020-09-01T12:34:56.000000001Z")
+                .unwrap()
+                .duration_since(Time::from_str("2020-09-01T12:34:56Z").unwrap())
+                .unwrap(),
+            Duration::new(0, 1)
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_timestamps() {
+        for s in &[
+            "",
+            "2020-09-01",
+            "2020-09-01T12:34:56",
+            "2020-09-01T12:34:56.Z",
+            "2020-09-01T12:34:56.1234567890Z",
+            "2020-09-01T12:34:56+0200",
+            "2020-09-01T12:34:56+24:00",
+            "2020-09-01X12:34:56Z",
+            "2020-13-01T12:34:56Z",
+            "2020-09-01T12:34:56Zjunk",
+        ] {
+            assert!(Time::from_str(s).is_err(), "{}", s);
+        }
+    }
+
+    #[test]
+    fn serializes_canonical_form() {
+        for (s, canonical) in &[
+            ("2020-09-01T12:34:56.120000000Z", "2020-09-01T12:34:56.12Z"),
+            ("2020-09-01T14:34:56+02:00", "2020-09-01T12:34:56Z"),
+            (
+                "2020-09-01T12:34:56.000000001Z",
+                "2020-09-01T12:34:56.000000001Z",
+            ),
+        ] {
+            let t = Time::from_str(s).unwrap();
+            assert_eq!(t.to_canonical_string(), *canonical);
+
+            let json = serde_json::to_string(&t).unwrap();
+            assert_eq!(json, format!("\"{}\"", canonical));
+            assert_eq!(serde_json::from_str::<Time>(&json).unwrap(), t);
+        }
+    }
+
+    #[test]
+    fn unnormalized_timestamps() {
+        for nanos in &[-1, 1_000_000_000] {
+            let timestamp = Timestamp {
+                seconds: 0,
+                nanos: *nanos,
+            };
+            assert!(Time::try_from(timestamp).is_err());
+        }
+    }
+}
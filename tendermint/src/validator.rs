@@ -1,11 +1,23 @@
 //! Tendermint validators
 
+use anomaly::{fail, format_err};
 use prost_amino_derive::Message;
 use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
-use subtle_encoding::base64;
+use std::collections::HashSet;
+use std::convert::{TryFrom, TryInto};
+use tendermint_proto::types::{Validator as RawValidator, ValidatorSet as RawValidatorSet};
 
 use crate::amino_types::message::AminoMessage;
-use crate::{account, hash::Hash, merkle, vote, Error, PublicKey, Signature};
+use crate::block::signed_header::SignedHeader;
+use crate::signature::{DefaultVerifier, Verifier};
+use crate::trust_threshold::{TrustThreshold, TrustThresholdFraction};
+use crate::vote::SignedVote;
+use crate::{
+    account,
+    error::Kind,
+    hash::{DefaultHasher, Hash, Hasher},
+    merkle, serializers, vote, Error, PublicKey, Signature,
+};
 
 /// Validator set contains a vector of validators
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
@@ -43,13 +55,21 @@ impl Set {
 
     /// Compute the hash of this validator set
     pub fn hash(&self) -> Hash {
+        self.hash_with(&DefaultHasher)
+    }
+
+    /// Compute the hash of this validator set using the given [`Hasher`]
+    pub fn hash_with<H: Hasher + ?Sized>(&self, hasher: &H) -> Hash {
         let validator_bytes: Vec<Vec<u8>> = self
             .validators()
             .iter()
             .map(|validator| validator.hash_bytes())
             .collect();
 
-        Hash::Sha256(merkle::simple_hash_from_byte_vectors(validator_bytes))
+        Hash::Sha256(merkle::simple_hash_from_byte_vectors_with(
+            &validator_bytes,
+            hasher,
+        ))
     }
 
     /// Compute the total voting power within this validator set
@@ -58,6 +78,183 @@ impl Set {
             total + val_info.voting_power.value()
         })
     }
+
+    /// Verify that more than 2/3 of the voting power of this validator set
+    /// committed to the given signed header.
+    ///
+    /// Every signature in the commit is checked (including votes for nil).
+    pub fn verify_commit_full(&self, signed_header: &SignedHeader) -> Result<(), Error> {
+        self.verify_commit_full_with(signed_header, &DefaultVerifier)
+    }
+
+    /// Like [`Set::verify_commit_full`], checking signatures with the given
+    /// [`Verifier`]
+    pub fn verify_commit_full_with<V>(
+        &self,
+        signed_header: &SignedHeader,
+        verifier: &V,
+    ) -> Result<(), Error>
+    where
+        V: Verifier + ?Sized,
+    {
+        self.verify_commit(signed_header, true, verifier)
+    }
+
+    /// Verify that more than 2/3 of the voting power of this validator set
+    /// committed to the given signed header.
+    ///
+    /// Only signatures for the committed block are checked, and verification
+    /// stops as soon as enough voting power has been tallied.
+    ///
+    /// In both modes the commit must contain exactly one entry per validator.
+    pub fn verify_commit_light(&self, signed_header: &SignedHeader) -> Result<(), Error> {
+        self.verify_commit_light_with(signed_header, &DefaultVerifier)
+    }
+
+    /// Like [`Set::verify_commit_light`], checking signatures with the given
+    /// [`Verifier`]
+    pub fn verify_commit_light_with<V>(
+        &self,
+        signed_header: &SignedHeader,
+        verifier: &V,
+    ) -> Result<(), Error>
+    where
+        V: Verifier + ?Sized,
+    {
+        self.verify_commit(signed_header, false, verifier)
+    }
+
+    fn verify_commit<V>(
+        &self,
+        signed_header: &SignedHeader,
+        full: bool,
+        verifier: &V,
+    ) -> Result<(), Error>
+    where
+        V: Verifier + ?Sized,
+    {
+        let header = &signed_header.header;
+        let commit = &signed_header.commit;
+
+        commit.check_header(header)?;
+
+        if commit.signatures.len() != self.validators.len() {
+            fail!(
+                Kind::InvalidCommit,
+                "commit has {} signatures, expected {}",
+                commit.signatures.len(),
+                self.validators.len()
+            );
+        }
+
+        let total_power = self.total_power();
+        let mut tallied_power = 0u64;
+        let mut seen_validators = HashSet::new();
+        let mut signed_votes = vec![];
+
+        for (index, commit_sig) in commit.signatures.iter().enumerate() {
+            // Light verification only counts votes for the committed block
+            if !full && !commit_sig.is_commit() {
+                continue;
+            }
+
+            let vote = match commit.vote(index) {
+                Some(vote) => vote,
+                None => continue,
+            };
+
+            if !seen_validators.insert(vote.validator_address) {
+                fail!(
+                    Kind::InvalidCommit,
+                    "duplicate vote from validator {}",
+                    vote.validator_address
+                );
+            }
+
+            let validator = self.validator(vote.validator_address).ok_or_else(|| {
+                format_err!(
+                    Kind::InvalidCommit,
+                    "validator {} not in validator set",
+                    vote.validator_address
+                )
+            })?;
+
+            signed_votes.push((
+                validator,
+                SignedVote::new(
+                    (&vote).into(),
+                    header.chain_id.as_str(),
+                    vote.validator_address,
+                    vote.signature,
+                ),
+            ));
+
+            if commit_sig.is_commit() {
+                tallied_power += validator.power();
+            }
+
+            if !full
+                && TrustThresholdFraction::TWO_THIRDS.is_enough_power(tallied_power, total_power)
+            {
+                break;
+            }
+        }
+
+        if !TrustThresholdFraction::TWO_THIRDS.is_enough_power(tallied_power, total_power) {
+            fail!(
+                Kind::InvalidCommit,
+                "insufficient voting power: {} of {} signed",
+                tallied_power,
+                total_power
+            );
+        }
+
+        verify_signed_votes(&signed_votes, verifier)
+    }
+}
+
+/// Verify the signatures on the given votes as a batch.
+///
+/// If the batch fails to verify, the votes are checked one-by-one so the
+/// error identifies the offending validator. Batch verification may reject
+/// signatures which verify one-by-one, so the votes are valid if they all do.
+fn verify_signed_votes<V>(signed_votes: &[(Info, SignedVote)], verifier: &V) -> Result<(), Error>
+where
+    V: Verifier + ?Sized,
+{
+    let sign_bytes = signed_votes
+        .iter()
+        .map(|(_, signed_vote)| signed_vote.sign_bytes())
+        .collect::<Vec<_>>();
+
+    let batch = signed_votes
+        .iter()
+        .zip(&sign_bytes)
+        .map(|((validator, signed_vote), sign_bytes)| {
+            (
+                &validator.pub_key,
+                sign_bytes.as_slice(),
+                signed_vote.signature(),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    if verifier.verify_batch(&batch).is_ok() {
+        return Ok(());
+    }
+
+    for ((validator, _), (public_key, msg, signature)) in signed_votes.iter().zip(&batch) {
+        verifier.verify(public_key, msg, signature).map_err(|e| {
+            format_err!(
+                Kind::SignatureInvalid,
+                "invalid signature from validator {}: {}",
+                validator.address,
+                e
+            )
+        })?;
+    }
+
+    Ok(())
 }
 
 // TODO: maybe add a type (with an Option<Vec<Info>> field) instead
@@ -79,6 +276,7 @@ pub struct Info {
     pub address: account::Id,
 
     /// Validator public key
+    #[serde(with = "serializers::public_key")]
     pub pub_key: PublicKey,
 
     /// Validator voting power
@@ -102,13 +300,79 @@ impl Info {
     }
 }
 
+impl TryFrom<RawValidator> for Info {
+    type Error = Error;
+
+    fn try_from(value: RawValidator) -> Result<Self, Self::Error> {
+        if value.voting_power < 0 {
+            fail!(
+                Kind::OutOfRange,
+                "negative voting power: {}",
+                value.voting_power
+            );
+        }
+
+        Ok(Info {
+            address: value.address.try_into()?,
+            pub_key: value
+                .pub_key
+                .ok_or_else(|| format_err!(Kind::InvalidKey, "validator public key is missing"))?
+                .try_into()?,
+            voting_power: vote::Power::new(value.voting_power as u64),
+            proposer_priority: Some(ProposerPriority::new(value.proposer_priority)),
+        })
+    }
+}
+
+impl TryFrom<Info> for RawValidator {
+    type Error = Error;
+
+    fn try_from(value: Info) -> Result<Self, Self::Error> {
+        Ok(RawValidator {
+            address: value.address.into(),
+            pub_key: Some(value.pub_key.try_into()?),
+            voting_power: value.voting_power.value() as i64,
+            proposer_priority: value.proposer_priority.map_or(0, ProposerPriority::value),
+        })
+    }
+}
+
+impl TryFrom<RawValidatorSet> for Set {
+    type Error = Error;
+
+    fn try_from(value: RawValidatorSet) -> Result<Self, Self::Error> {
+        let validators = value
+            .validators
+            .into_iter()
+            .map(TryInto::try_into)
+            .collect::<Result<Vec<Info>, Error>>()?;
+
+        Ok(Set::new(validators))
+    }
+}
+
+impl TryFrom<Set> for RawValidatorSet {
+    type Error = Error;
+
+    fn try_from(value: Set) -> Result<Self, Self::Error> {
+        let total_voting_power = value.total_power() as i64;
+        let validators = value
+            .validators
+            .into_iter()
+            .map(TryInto::try_into)
+            .collect::<Result<Vec<RawValidator>, Error>>()?;
+
+        Ok(RawValidatorSet {
+            validators,
+            proposer: None,
+            total_voting_power,
+        })
+    }
+}
+
 impl From<PublicKey> for account::Id {
     fn from(pub_key: PublicKey) -> account::Id {
-        match pub_key {
-            PublicKey::Ed25519(pk) => account::Id::from(pk),
-            #[cfg(feature = "secp256k1")]
-            PublicKey::Secp256k1(pk) => account::Id::from(pk),
-        }
+        pub_key.address()
     }
 }
 
@@ -198,7 +462,7 @@ impl Serialize for ProposerPriority {
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Update {
     /// Validator public key
-    #[serde(deserialize_with = "deserialize_public_key")]
+    #[serde(deserialize_with = "serializers::public_key::deserialize")]
     pub pub_key: PublicKey,
 
     /// New voting power
@@ -206,39 +470,14 @@ pub struct Update {
     pub power: vote::Power,
 }
 
-/// Validator updates use a slightly different public key format than the one
-/// implemented in `tendermint::PublicKey`.
-///
-/// This is an internal thunk type to parse the `validator_updates` format and
-/// then convert to `tendermint::PublicKey` in `deserialize_public_key` below.
-#[derive(Serialize, Deserialize)]
-#[serde(tag = "type", content = "data")]
-enum PK {
-    /// Ed25519 keys
-    #[serde(rename = "ed25519")]
-    Ed25519(String),
-}
-
-fn deserialize_public_key<'de, D>(deserializer: D) -> Result<PublicKey, D::Error>
-where
-    D: Deserializer<'de>,
-{
-    match &PK::deserialize(deserializer)? {
-        PK::Ed25519(base64_value) => {
-            let bytes =
-                base64::decode(base64_value).map_err(|e| D::Error::custom(format!("{}", e)))?;
-
-            PublicKey::from_raw_ed25519(&bytes)
-                .ok_or_else(|| D::Error::custom("error parsing Ed25519 key"))
-        }
-    }
-}
-
 #[cfg(test)]
 mod tests {
+    use ed25519_dalek::Signer;
     use subtle_encoding::hex;
 
     use super::*;
+    use crate::block::{self, CommitSig, CommitSigs};
+    use crate::test::{keypair, signed_header, validator_set, vote_nil};
 
     // make a validator from a hex ed25519 pubkey and a voting power
     fn make_validator(pk_string: &str, vp: u64) -> Info {
@@ -282,4 +521,162 @@ mod tests {
             148_151_478_422_287_875 + 158_095_448_483_785_107 + 770_561_664_770_006_272
         );
     }
+
+    #[test]
+    fn verify_commit() {
+        let keypairs = vec![keypair(1), keypair(2), keypair(3)];
+        let vals = validator_set(&keypairs);
+
+        let fully_signed = signed_header(&keypairs, 3);
+        assert!(vals.verify_commit_full(&fully_signed).is_ok());
+        assert!(vals.verify_commit_light(&fully_signed).is_ok());
+
+        // exactly 2/3 is not enough
+        let partially_signed = signed_header(&keypairs, 2);
+        assert!(vals.verify_commit_full(&partially_signed).is_err());
+        assert!(vals.verify_commit_light(&partially_signed).is_err());
+    }
+
+    #[test]
+    fn verify_commit_rejects_bad_signatures() {
+        let keypairs = vec![keypair(1), keypair(2), keypair(3)];
+        let vals = validator_set(&keypairs);
+
+        let mut bad_signature = signed_header(&keypairs, 3);
+        let mut signatures = bad_signature.commit.signatures.clone().into_vec();
+        if let CommitSig::BlockIDFlagCommit { signature, .. } = &mut signatures[2] {
+            *signature = Signature::Ed25519(keypairs[2].sign(b"something else"));
+        }
+        bad_signature.commit.signatures = CommitSigs::new(signatures);
+        assert!(vals.verify_commit_full(&bad_signature).is_err());
+
+        let mut wrong_block = signed_header(&keypairs, 3);
+        wrong_block.commit.block_id = block::Id::new(Hash::Sha256([0xab; 32]), None);
+        assert!(vals.verify_commit_full(&wrong_block).is_err());
+        assert!(vals.verify_commit_light(&wrong_block).is_err());
+    }
+
+    #[test]
+    fn verify_commit_light_checks_signature_count() {
+        let keypairs = vec![keypair(1), keypair(2), keypair(3)];
+        let vals = validator_set(&keypairs);
+
+        let mut missing_signature = signed_header(&keypairs, 3);
+        let mut signatures = missing_signature.commit.signatures.clone().into_vec();
+        signatures.pop();
+        missing_signature.commit.signatures = CommitSigs::new(signatures);
+        assert!(vals.verify_commit_light(&missing_signature).is_err());
+
+        let mut extra_signature = signed_header(&keypairs, 3);
+        let mut signatures = extra_signature.commit.signatures.clone().into_vec();
+        signatures.push(CommitSig::BlockIDFlagAbsent);
+        extra_signature.commit.signatures = CommitSigs::new(signatures);
+        assert!(vals.verify_commit_light(&extra_signature).is_err());
+    }
+
+    #[test]
+    fn verify_commit_with_custom_verifier() {
+        use std::cell::Cell;
+
+        // a verifier which counts the signatures it is asked to check, and
+        // only accepts valid signatures from the first validator
+        struct OnlyFirst {
+            first: PublicKey,
+            checked: Cell<usize>,
+        }
+
+        impl Verifier for OnlyFirst {
+            fn verify(
+                &self,
+                public_key: &PublicKey,
+                msg: &[u8],
+                signature: &Signature,
+            ) -> Result<(), Error> {
+                self.checked.set(self.checked.get() + 1);
+
+                if *public_key != self.first {
+                    fail!(Kind::SignatureInvalid, "rejected by verifier");
+                }
+
+                public_key.verify(msg, signature)
+            }
+        }
+
+        let keypairs = vec![keypair(1), keypair(2), keypair(3)];
+        let vals = validator_set(&keypairs);
+        let signed_header = signed_header(&keypairs, 3);
+
+        let verifier = OnlyFirst {
+            first: keypairs[0].public.into(),
+            checked: Cell::new(0),
+        };
+
+        assert!(vals
+            .verify_commit_full_with(&signed_header, &verifier)
+            .is_err());
+        assert!(verifier.checked.get() > 0);
+        assert!(vals
+            .verify_commit_full_with(&signed_header, &DefaultVerifier)
+            .is_ok());
+    }
+
+    #[test]
+    fn verify_commit_falls_back_to_single_signatures() {
+        // a verifier whose batches never verify
+        struct FailingBatches;
+
+        impl Verifier for FailingBatches {
+            fn verify(
+                &self,
+                public_key: &PublicKey,
+                msg: &[u8],
+                signature: &Signature,
+            ) -> Result<(), Error> {
+                public_key.verify(msg, signature)
+            }
+
+            fn verify_batch(
+                &self,
+                _batch: &[(&PublicKey, &[u8], &Signature)],
+            ) -> Result<(), Error> {
+                fail!(Kind::SignatureInvalid, "batch rejected");
+            }
+        }
+
+        let keypairs = vec![keypair(1), keypair(2), keypair(3)];
+        let vals = validator_set(&keypairs);
+        let signed_header = signed_header(&keypairs, 3);
+
+        assert!(vals
+            .verify_commit_full_with(&signed_header, &FailingBatches)
+            .is_ok());
+    }
+
+    #[test]
+    fn verify_commit_does_not_tally_nil_votes() {
+        let keypairs = vec![keypair(1), keypair(2), keypair(3), keypair(4)];
+        let vals = validator_set(&keypairs);
+
+        let mut one_nil = signed_header(&keypairs, 4);
+        vote_nil(&mut one_nil, &keypairs[3], 3);
+        assert_eq!(one_nil.commit.vote(3).unwrap().block_id, None);
+        assert!(vals.verify_commit_full(&one_nil).is_ok());
+        assert!(vals.verify_commit_light(&one_nil).is_ok());
+
+        // every validator signed, but only half voted for the block
+        let mut two_nil = one_nil.clone();
+        vote_nil(&mut two_nil, &keypairs[2], 2);
+        assert!(vals.verify_commit_full(&two_nil).is_err());
+        assert!(vals.verify_commit_light(&two_nil).is_err());
+
+        // nil votes are only checked by full verification
+        let mut bad_nil = one_nil;
+        let mut signatures = bad_nil.commit.signatures.clone().into_vec();
+        if let CommitSig::BlockIDFlagNil { signature, .. } = &mut signatures[3] {
+            *signature = Signature::Ed25519(keypairs[3].sign(b"something else"));
+        }
+        bad_nil.commit.signatures = CommitSigs::new(signatures);
+        assert!(vals.verify_commit_full(&bad_nil).is_err());
+        assert!(vals.verify_commit_light(&bad_nil).is_ok());
+    }
 }
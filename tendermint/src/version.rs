@@ -1,5 +1,9 @@
+use crate::error::Error;
 use serde::{Deserialize, Serialize};
-use std::fmt::{self, Debug, Display};
+use std::{
+    fmt::{self, Debug, Display},
+    str::FromStr,
+};
 
 /// Tendermint version
 #[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
@@ -10,3 +14,18 @@ impl Display for Version {
         write!(f, "{}", self.0)
     }
 }
+
+impl Version {
+    /// Borrow the version as a string
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl FromStr for Version {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        Ok(Version(s.to_owned()))
+    }
+}
@@ -1,11 +1,23 @@
 //! Votes from validators
 
+mod canonical_vote;
+#[cfg(feature = "vote-extensions")]
+mod canonical_vote_extension;
 mod power;
+mod set;
 
+pub use self::canonical_vote::CanonicalVote;
+#[cfg(feature = "vote-extensions")]
+pub use self::canonical_vote_extension::CanonicalVoteExtension;
 pub use self::power::Power;
+pub use self::set::VoteSet;
 use crate::amino_types::message::AminoMessage;
-use crate::{account, block, Signature, Time};
+use crate::{account, block, chain, error::Kind, Error, PublicKey, Signature, SignedMsgType, Time};
 use crate::{amino_types, hash};
+use anomaly::{fail, format_err};
+use std::convert::{TryFrom, TryInto};
+use tendermint_proto::types::Vote as RawVote;
+use tendermint_proto::DomainType;
 use {
     crate::serializers,
     serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer},
@@ -29,7 +41,7 @@ pub struct Vote {
     pub round: u64,
 
     /// Block ID
-    #[serde(deserialize_with = "serializers::parse_non_empty_block_id")]
+    #[serde(with = "serializers::block_id::optional")]
     pub block_id: Option<block::Id>,
 
     /// Timestamp
@@ -44,6 +56,23 @@ pub struct Vote {
 
     /// Signature
     pub signature: Signature,
+
+    /// Application-defined data attached to a precommit (Tendermint 0.38
+    /// vote extensions). Empty for votes without an extension.
+    #[cfg(feature = "vote-extensions")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "vote-extensions")))]
+    #[serde(
+        default,
+        skip_serializing_if = "Vec::is_empty",
+        with = "serializers::bytes::base64string"
+    )]
+    pub extension: Vec<u8>,
+
+    /// Signature over the canonical form of this vote's extension
+    #[cfg(feature = "vote-extensions")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "vote-extensions")))]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extension_signature: Option<Signature>,
 }
 
 impl Vote {
@@ -70,6 +99,118 @@ impl Vote {
             None => None,
         }
     }
+
+    /// Bytes of the canonicalized vote which are signed on the given chain
+    pub fn to_signable_bytes(&self, chain_id: chain::Id) -> Vec<u8> {
+        CanonicalVote::new(self.clone(), chain_id).to_signable_bytes()
+    }
+
+    /// Verify this vote's signature was produced on the given chain by the
+    /// holder of the given public key
+    pub fn verify(&self, chain_id: chain::Id, pub_key: &PublicKey) -> Result<(), Error> {
+        pub_key.verify(&self.to_signable_bytes(chain_id), &self.signature)
+    }
+
+    /// Bytes of the canonicalized vote extension which are signed on the
+    /// given chain
+    #[cfg(feature = "vote-extensions")]
+    pub fn extension_sign_bytes(&self, chain_id: chain::Id) -> Vec<u8> {
+        CanonicalVoteExtension::new(self.clone(), chain_id).to_signable_bytes()
+    }
+
+    /// Verify this vote's extension signature was produced on the given chain
+    /// by the holder of the given public key.
+    ///
+    /// Like Tendermint, only precommits for a block carry a signed extension
+    /// (which may itself be empty); any other vote must have neither.
+    #[cfg(feature = "vote-extensions")]
+    pub fn verify_extension(&self, chain_id: chain::Id, pub_key: &PublicKey) -> Result<(), Error> {
+        if !self.is_precommit() || self.block_id.is_none() {
+            if !self.extension.is_empty() || self.extension_signature.is_some() {
+                fail!(
+                    Kind::InvalidVote,
+                    "extension is only allowed in precommits for a block"
+                );
+            }
+
+            return Ok(());
+        }
+
+        let extension_signature = self
+            .extension_signature
+            .as_ref()
+            .ok_or_else(|| format_err!(Kind::InvalidVote, "extension signature is missing"))?;
+
+        pub_key.verify(&self.extension_sign_bytes(chain_id), extension_signature)
+    }
+}
+
+impl DomainType<RawVote> for Vote {}
+
+impl TryFrom<RawVote> for Vote {
+    type Error = Error;
+
+    fn try_from(value: RawVote) -> Result<Self, Self::Error> {
+        if value.round < 0 {
+            fail!(Kind::OutOfRange, "negative vote round: {}", value.round);
+        }
+
+        if value.validator_index < 0 {
+            fail!(
+                Kind::OutOfRange,
+                "negative validator index: {}",
+                value.validator_index
+            );
+        }
+
+        Ok(Vote {
+            vote_type: SignedMsgType::try_from(value.r#type)
+                .and_then(Type::try_from)
+                .map_err(|_| format_err!(Kind::Parse, "invalid vote type: {}", value.r#type))?,
+            height: value.height.try_into()?,
+            round: value.round as u64,
+            block_id: block::Id::parse_optional_raw(value.block_id)?,
+            timestamp: value
+                .timestamp
+                .ok_or_else(|| format_err!(Kind::Parse, "timestamp is missing in vote"))?
+                .try_into()?,
+            validator_address: value.validator_address.try_into()?,
+            validator_index: value.validator_index as u64,
+            signature: value.signature.try_into()?,
+            // Vote extensions aren't part of the Tendermint 0.34 protobuf
+            #[cfg(feature = "vote-extensions")]
+            extension: vec![],
+            #[cfg(feature = "vote-extensions")]
+            extension_signature: None,
+        })
+    }
+}
+
+impl TryFrom<Vote> for RawVote {
+    type Error = Error;
+
+    fn try_from(value: Vote) -> Result<Self, Self::Error> {
+        let round = i32::try_from(value.round)
+            .map_err(|_| format_err!(Kind::OutOfRange, "vote round too large: {}", value.round))?;
+        let validator_index = i32::try_from(value.validator_index).map_err(|_| {
+            format_err!(
+                Kind::OutOfRange,
+                "validator index too large: {}",
+                value.validator_index
+            )
+        })?;
+
+        Ok(RawVote {
+            r#type: SignedMsgType::from(value.vote_type).into(),
+            height: value.height.into(),
+            round,
+            block_id: block::Id::to_optional_raw(value.block_id),
+            timestamp: Some(value.timestamp.into()),
+            validator_address: value.validator_address.into(),
+            validator_index,
+            signature: value.signature.into(),
+        })
+    }
 }
 
 /// SignedVote is the union of a canonicalized vote, the signature on
@@ -127,21 +268,38 @@ pub enum Type {
 impl Type {
     /// Deserialize this type from a byte
     pub fn from_u8(byte: u8) -> Option<Type> {
-        match byte {
-            1 => Some(Type::Prevote),
-            2 => Some(Type::Precommit),
-            _ => None,
-        }
+        SignedMsgType::from_u32(byte.into()).and_then(|msg_type| Type::try_from(msg_type).ok())
     }
 
     /// Serialize this type as a byte
     pub fn to_u8(self) -> u8 {
-        self as u8
+        SignedMsgType::from(self).to_u8()
     }
 
     /// Serialize this type as a 32-bit unsigned integer
     pub fn to_u32(self) -> u32 {
-        self as u32
+        SignedMsgType::from(self).to_u32()
+    }
+}
+
+impl From<Type> for SignedMsgType {
+    fn from(vote_type: Type) -> SignedMsgType {
+        match vote_type {
+            Type::Prevote => SignedMsgType::Prevote,
+            Type::Precommit => SignedMsgType::Precommit,
+        }
+    }
+}
+
+impl TryFrom<SignedMsgType> for Type {
+    type Error = Error;
+
+    fn try_from(msg_type: SignedMsgType) -> Result<Self, Error> {
+        match msg_type {
+            SignedMsgType::Prevote => Ok(Type::Prevote),
+            SignedMsgType::Precommit => Ok(Type::Precommit),
+            SignedMsgType::Proposal => fail!(Kind::Parse, "not a vote type: {}", msg_type),
+        }
     }
 }
 
@@ -157,3 +315,187 @@ impl<'de> Deserialize<'de> for Type {
         Type::from_u8(byte).ok_or_else(|| D::Error::custom(format!("invalid vote type: {}", byte)))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+    use tendermint_proto::types::PartSetHeader as RawPartSetHeader;
+
+    #[test]
+    fn protobuf_roundtrip() {
+        let vote = Vote {
+            vote_type: Type::Prevote,
+            height: block::Height::from(12345u64),
+            round: 2,
+            block_id: None,
+            timestamp: Time::from_str("2018-02-11T07:09:22.765Z").unwrap(),
+            validator_address: account::Id::new([0xA3; account::LENGTH]),
+            validator_index: 56789,
+            signature: Signature::Ed25519(ed25519::Signature::new([0; 64])),
+            #[cfg(feature = "vote-extensions")]
+            extension: vec![],
+            #[cfg(feature = "vote-extensions")]
+            extension_signature: None,
+        };
+
+        let encoded = vote.encode_vec().unwrap();
+        assert_eq!(Vote::decode_vec(&encoded).unwrap(), vote);
+
+        let mut raw = RawVote::try_from(vote).unwrap();
+        raw.r#type = SignedMsgType::Proposal.into();
+        assert!(Vote::try_from(raw).is_err());
+    }
+
+    #[test]
+    fn protobuf_rejects_out_of_range_fields() {
+        let vote = Vote {
+            vote_type: Type::Prevote,
+            height: block::Height::from(12345u64),
+            round: i32::MAX as u64 + 1,
+            block_id: None,
+            timestamp: Time::from_str("2018-02-11T07:09:22.765Z").unwrap(),
+            validator_address: account::Id::new([0xA3; account::LENGTH]),
+            validator_index: 56789,
+            signature: Signature::Ed25519(ed25519::Signature::new([0; 64])),
+            #[cfg(feature = "vote-extensions")]
+            extension: vec![],
+            #[cfg(feature = "vote-extensions")]
+            extension_signature: None,
+        };
+        assert!(vote.encode_vec().is_err());
+
+        let vote = Vote {
+            round: 2,
+            validator_index: i32::MAX as u64 + 1,
+            ..vote
+        };
+        assert!(RawVote::try_from(vote).is_err());
+    }
+
+    #[test]
+    fn nil_vote_encoding() {
+        let vote = Vote {
+            vote_type: Type::Precommit,
+            height: block::Height::from(12345u64),
+            round: 2,
+            block_id: None,
+            timestamp: Time::from_str("2018-02-11T07:09:22.765Z").unwrap(),
+            validator_address: account::Id::new([0xA3; account::LENGTH]),
+            validator_index: 56789,
+            signature: Signature::Ed25519(ed25519::Signature::new([0; 64])),
+            #[cfg(feature = "vote-extensions")]
+            extension: vec![],
+            #[cfg(feature = "vote-extensions")]
+            extension_signature: None,
+        };
+
+        // Like Go, nil votes carry an empty (rather than absent) block ID
+        let raw = RawVote::try_from(vote.clone()).unwrap();
+        let raw_block_id = raw.block_id.clone().unwrap();
+        assert!(raw_block_id.hash.is_empty());
+        assert_eq!(raw_block_id.part_set_header, Some(Default::default()));
+        assert_eq!(Vote::try_from(raw.clone()).unwrap(), vote);
+
+        let json = serde_json::to_value(&vote).unwrap();
+        assert_eq!(
+            json["block_id"],
+            serde_json::json!({"hash": "", "parts": {"total": "0", "hash": ""}})
+        );
+        assert_eq!(serde_json::from_value::<Vote>(json).unwrap(), vote);
+
+        // A part set header without a block hash isn't a nil block ID
+        let mut invalid = raw;
+        invalid.block_id.as_mut().unwrap().part_set_header = Some(RawPartSetHeader {
+            total: 1,
+            hash: vec![0xAB; 32],
+        });
+        assert!(Vote::try_from(invalid).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "vote-extensions")]
+    fn extension_serialization() {
+        let mut vote = Vote {
+            vote_type: Type::Precommit,
+            height: block::Height::from(12345u64),
+            round: 2,
+            block_id: None,
+            timestamp: Time::from_str("2018-02-11T07:09:22.765Z").unwrap(),
+            validator_address: account::Id::new([0xA3; account::LENGTH]),
+            validator_index: 56789,
+            signature: Signature::Ed25519(ed25519::Signature::new([0; 64])),
+            extension: vec![],
+            extension_signature: None,
+        };
+
+        // Votes without an extension serialize as before
+        let json = serde_json::to_value(&vote).unwrap();
+        assert!(json.get("extension").is_none());
+        assert!(json.get("extension_signature").is_none());
+        assert_eq!(serde_json::from_value::<Vote>(json).unwrap(), vote);
+
+        vote.extension = b"extension".to_vec();
+        vote.extension_signature = Some(Signature::Ed25519(ed25519::Signature::new([1; 64])));
+
+        let json = serde_json::to_value(&vote).unwrap();
+        assert_eq!(json["extension"], "ZXh0ZW5zaW9u");
+        assert_eq!(serde_json::from_value::<Vote>(json).unwrap(), vote);
+
+        // The Tendermint 0.34 protobuf has no room for extensions
+        let decoded = Vote::decode_vec(&vote.encode_vec().unwrap()).unwrap();
+        assert!(decoded.extension.is_empty());
+        assert_eq!(decoded.extension_signature, None);
+    }
+
+    #[test]
+    #[cfg(feature = "vote-extensions")]
+    fn extension_signatures() {
+        use ed25519_dalek::{Keypair, SecretKey, Signer};
+
+        let secret = SecretKey::from_bytes(&[7; 32]).unwrap();
+        let public = (&secret).into();
+        let keypair = Keypair { secret, public };
+        let pub_key = PublicKey::from(keypair.public);
+        let chain_id = chain::Id::from("test-chain");
+
+        let mut vote = Vote {
+            vote_type: Type::Precommit,
+            height: block::Height::from(12345u64),
+            round: 2,
+            block_id: Some(block::Id::new(hash::Hash::Sha256([0xAB; 32]), None)),
+            timestamp: Time::from_str("2018-02-11T07:09:22.765Z").unwrap(),
+            validator_address: account::Id::from(keypair.public),
+            validator_index: 0,
+            signature: Signature::Ed25519(ed25519::Signature::new([0; 64])),
+            extension: b"extension".to_vec(),
+            extension_signature: None,
+        };
+
+        // Precommits for a block must carry a signed extension
+        assert!(vote.verify_extension(chain_id, &pub_key).is_err());
+
+        let sign_bytes = vote.extension_sign_bytes(chain_id);
+        assert_ne!(sign_bytes, vote.to_signable_bytes(chain_id));
+        vote.extension_signature = Some(keypair.sign(&sign_bytes).into());
+        assert!(vote.verify_extension(chain_id, &pub_key).is_ok());
+
+        vote.extension = b"tampered".to_vec();
+        assert!(vote.verify_extension(chain_id, &pub_key).is_err());
+
+        // Nil precommits and prevotes can't have one
+        vote.block_id = None;
+        assert!(vote.verify_extension(chain_id, &pub_key).is_err());
+        vote.extension = vec![];
+        vote.extension_signature = None;
+        assert!(vote.verify_extension(chain_id, &pub_key).is_ok());
+    }
+
+    #[test]
+    fn vote_types_are_signed_msg_types() {
+        assert_eq!(Type::Prevote.to_u32(), SignedMsgType::Prevote.to_u32());
+        assert_eq!(Type::Precommit.to_u8(), SignedMsgType::Precommit.to_u8());
+        assert_eq!(Type::from_u8(0x20), None);
+        assert!(Type::try_from(SignedMsgType::Proposal).is_err());
+    }
+}
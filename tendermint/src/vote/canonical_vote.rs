@@ -0,0 +1,111 @@
+use super::{Type, Vote};
+use crate::amino_types::{self, message::AminoMessage, TimeMsg};
+use crate::{block, chain, Time};
+
+/// CanonicalVote is the form of a [`Vote`] which validators sign: it omits the
+/// validator's address and index, and includes the chain ID.
+///
+/// <https://github.com/tendermint/spec/blob/d46cd7f573a2c6a2399fcab2cde981330aa63f37/spec/core/encoding.md#signed-messages>
+#[derive(Clone, Debug, PartialEq)]
+pub struct CanonicalVote {
+    /// Type of vote (prevote or precommit)
+    pub vote_type: Type,
+
+    /// Block height
+    pub height: block::Height,
+
+    /// Round
+    pub round: u64,
+
+    /// Block ID
+    pub block_id: Option<block::Id>,
+
+    /// Timestamp
+    pub timestamp: Time,
+
+    /// Chain ID
+    pub chain_id: chain::Id,
+}
+
+impl CanonicalVote {
+    /// Canonicalize the given vote for signing on the given chain
+    pub fn new(vote: Vote, chain_id: chain::Id) -> CanonicalVote {
+        CanonicalVote {
+            vote_type: vote.vote_type,
+            height: vote.height,
+            round: vote.round,
+            block_id: vote.block_id,
+            timestamp: vote.timestamp,
+            chain_id,
+        }
+    }
+
+    /// Serialize this vote as the length-prefixed bytes which are signed
+    pub fn to_signable_bytes(&self) -> Vec<u8> {
+        amino_types::vote::CanonicalVote::from(self).bytes_vec_length_delimited()
+    }
+}
+
+impl From<&CanonicalVote> for amino_types::vote::CanonicalVote {
+    fn from(vote: &CanonicalVote) -> Self {
+        amino_types::vote::CanonicalVote {
+            vote_type: vote.vote_type.to_u32(),
+            height: vote.height.value() as i64,
+            round: vote.round as i64,
+            block_id: vote.block_id.as_ref().map(Into::into),
+            timestamp: Some(TimeMsg::from(vote.timestamp)),
+            chain_id: vote.chain_id.as_str().to_owned(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        account,
+        hash::{self, Hash},
+        vote::SignedVote,
+        Signature,
+    };
+    use std::str::FromStr;
+
+    #[test]
+    fn sign_bytes_match_amino_vote() {
+        let vote = Vote {
+            vote_type: Type::Precommit,
+            height: block::Height::from(12345u64),
+            round: 2,
+            block_id: Some(block::Id::new(
+                Hash::from_hex_upper(
+                    hash::Algorithm::Sha256,
+                    "DEADBEEFDEADBEEFDEADBEEFDEADBEEFDEADBEEFDEADBEEFDEADBEEFDEADBEEF",
+                )
+                .unwrap(),
+                None,
+            )),
+            timestamp: Time::from_str("2018-02-11T07:09:22.765Z").unwrap(),
+            validator_address: account::Id::new([0xA3; account::LENGTH]),
+            validator_index: 56789,
+            signature: Signature::Ed25519(ed25519::Signature::new([0; 64])),
+            #[cfg(feature = "vote-extensions")]
+            extension: vec![],
+            #[cfg(feature = "vote-extensions")]
+            extension_signature: None,
+        };
+        let chain_id = chain::Id::from("test_chain_id");
+
+        let signed_vote = SignedVote::new(
+            (&vote).into(),
+            chain_id.as_str(),
+            vote.validator_address,
+            vote.signature,
+        );
+
+        assert_eq!(
+            CanonicalVote::new(vote.clone(), chain_id).to_signable_bytes(),
+            signed_vote.sign_bytes()
+        );
+        assert_eq!(vote.to_signable_bytes(chain_id), signed_vote.sign_bytes());
+    }
+}
@@ -0,0 +1,76 @@
+use super::Vote;
+use crate::amino_types::{self, message::AminoMessage};
+use crate::{block, chain};
+
+/// CanonicalVoteExtension is the form of a [`Vote`]'s extension which
+/// validators sign, separately from the vote itself (Tendermint 0.38).
+///
+/// <https://github.com/tendermint/tendermint/blob/v0.38.0/proto/tendermint/types/canonical.proto#L56-L64>
+#[derive(Clone, Debug, PartialEq)]
+pub struct CanonicalVoteExtension {
+    /// Application-defined extension data
+    pub extension: Vec<u8>,
+
+    /// Block height
+    pub height: block::Height,
+
+    /// Round
+    pub round: u64,
+
+    /// Chain ID
+    pub chain_id: chain::Id,
+}
+
+impl CanonicalVoteExtension {
+    /// Canonicalize the given vote's extension for signing on the given chain
+    pub fn new(vote: Vote, chain_id: chain::Id) -> CanonicalVoteExtension {
+        CanonicalVoteExtension {
+            extension: vote.extension,
+            height: vote.height,
+            round: vote.round,
+            chain_id,
+        }
+    }
+
+    /// Serialize this extension as the length-prefixed bytes which are signed
+    pub fn to_signable_bytes(&self) -> Vec<u8> {
+        amino_types::vote::CanonicalVoteExtension::from(self).bytes_vec_length_delimited()
+    }
+}
+
+impl From<&CanonicalVoteExtension> for amino_types::vote::CanonicalVoteExtension {
+    fn from(extension: &CanonicalVoteExtension) -> Self {
+        amino_types::vote::CanonicalVoteExtension {
+            extension: extension.extension.clone(),
+            height: extension.height.value() as i64,
+            round: extension.round as i64,
+            chain_id: extension.chain_id.as_str().to_owned(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sign_bytes_encoding() {
+        let extension = CanonicalVoteExtension {
+            extension: b"ext".to_vec(),
+            height: block::Height::from(1u64),
+            round: 2,
+            chain_id: chain::Id::from("c"),
+        };
+
+        assert_eq!(
+            extension.to_signable_bytes(),
+            vec![
+                0x1a, // length prefix
+                0x0a, 0x03, b'e', b'x', b't', // extension
+                0x11, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // height
+                0x19, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // round
+                0x22, 0x01, b'c', // chain ID
+            ]
+        );
+    }
+}
@@ -0,0 +1,484 @@
+//! Vote sets: tallies of the votes cast in a single height, round and step
+
+use super::{Type, Vote};
+use crate::block::{self, Commit, CommitSig, CommitSigs};
+use crate::evidence::DuplicateVoteEvidence;
+use crate::trust_threshold::{TrustThreshold, TrustThresholdFraction};
+use crate::{account, chain, error::Kind, validator, Error};
+use anomaly::{fail, format_err};
+use std::collections::BTreeMap;
+
+/// Set of votes cast by a validator set for a single height, round and vote
+/// type (like Tendermint's `VoteSet`).
+///
+/// Votes are checked against the validator set (including their signatures)
+/// as they are added, and their voting power is tallied per block ID so that
+/// a +2/3 majority can be detected. Conflicting votes from the same validator
+/// are rejected and kept as [`DuplicateVoteEvidence`].
+#[derive(Clone, Debug)]
+pub struct VoteSet {
+    chain_id: chain::Id,
+    height: block::Height,
+    round: u64,
+    vote_type: Type,
+    validators: validator::Set,
+
+    /// Votes indexed by validator index
+    votes: Vec<Option<Vote>>,
+
+    /// Total voting power of all the votes in the set
+    sum: u64,
+
+    /// Voting power of the votes for each block ID (`None` being nil)
+    power_by_block: BTreeMap<Option<block::Id>, u64>,
+
+    /// First block ID (or nil) to reach a +2/3 majority
+    maj23: Option<Option<block::Id>>,
+
+    /// Conflicting votes which were rejected
+    evidence: Vec<DuplicateVoteEvidence>,
+}
+
+impl VoteSet {
+    /// Create an empty vote set for the given height, round and vote type
+    pub fn new(
+        chain_id: chain::Id,
+        height: block::Height,
+        round: u64,
+        vote_type: Type,
+        validators: validator::Set,
+    ) -> Self {
+        let votes = vec![None; validators.validators().len()];
+
+        Self {
+            chain_id,
+            height,
+            round,
+            vote_type,
+            validators,
+            votes,
+            sum: 0,
+            power_by_block: BTreeMap::new(),
+            maj23: None,
+            evidence: vec![],
+        }
+    }
+
+    /// Chain ID the votes are signed for
+    pub fn chain_id(&self) -> &chain::Id {
+        &self.chain_id
+    }
+
+    /// Height of the votes in this set
+    pub fn height(&self) -> block::Height {
+        self.height
+    }
+
+    /// Round of the votes in this set
+    pub fn round(&self) -> u64 {
+        self.round
+    }
+
+    /// Type of the votes in this set
+    pub fn vote_type(&self) -> Type {
+        self.vote_type
+    }
+
+    /// Validator set whose votes are collected
+    pub fn validators(&self) -> &validator::Set {
+        &self.validators
+    }
+
+    /// Add a vote to the set.
+    ///
+    /// Returns `Ok(true)` if the vote was added, or `Ok(false)` if the same
+    /// vote was already in the set. Votes for a different height, round or
+    /// type, from unknown validators or with invalid signatures are rejected,
+    /// as are votes which conflict with a vote already in the set (in which
+    /// case the pair is kept as evidence, see [`VoteSet::evidence`]).
+    pub fn add_vote(&mut self, vote: Vote) -> Result<bool, Error> {
+        if vote.height != self.height
+            || vote.round != self.round
+            || vote.vote_type != self.vote_type
+        {
+            fail!(
+                Kind::InvalidVote,
+                "expected {}/{}/{:?} vote, got {}/{}/{:?}",
+                self.height,
+                self.round,
+                self.vote_type,
+                vote.height,
+                vote.round,
+                vote.vote_type
+            );
+        }
+
+        let index = vote.validator_index as usize;
+
+        let validator =
+            *self.validators.validators().get(index).ok_or_else(|| {
+                format_err!(Kind::InvalidVote, "invalid validator index: {}", index)
+            })?;
+
+        if validator.address != vote.validator_address {
+            fail!(
+                Kind::InvalidVote,
+                "validator at index {} is {}, not {}",
+                index,
+                validator.address,
+                vote.validator_address
+            );
+        }
+
+        if let Some(existing) = &self.votes[index] {
+            if existing.block_id == vote.block_id && existing.signature == vote.signature {
+                return Ok(false);
+            }
+        }
+
+        vote.verify(self.chain_id, &validator.pub_key)?;
+
+        if let Some(existing) = &self.votes[index] {
+            if existing.block_id == vote.block_id {
+                fail!(
+                    Kind::InvalidVote,
+                    "non-deterministic signature from validator {}",
+                    vote.validator_address
+                );
+            }
+
+            self.evidence.push(DuplicateVoteEvidence::new(
+                validator.pub_key,
+                existing.clone(),
+                vote.clone(),
+            )?);
+
+            fail!(
+                Kind::ConflictingVote,
+                "conflicting votes from validator {}",
+                vote.validator_address
+            );
+        }
+
+        let total_power = self.validators.total_power();
+        let block_power = self
+            .power_by_block
+            .entry(vote.block_id.clone())
+            .or_insert(0);
+
+        *block_power += validator.power();
+        self.sum += validator.power();
+
+        if self.maj23.is_none()
+            && TrustThresholdFraction::TWO_THIRDS.is_enough_power(*block_power, total_power)
+        {
+            self.maj23 = Some(vote.block_id.clone());
+        }
+
+        self.votes[index] = Some(vote);
+        Ok(true)
+    }
+
+    /// Get the vote from the validator at the given index, if any
+    pub fn vote(&self, validator_index: usize) -> Option<&Vote> {
+        self.votes.get(validator_index)?.as_ref()
+    }
+
+    /// Get the vote from the validator with the given address, if any
+    pub fn vote_by_address(&self, address: account::Id) -> Option<&Vote> {
+        let index = self
+            .validators
+            .validators()
+            .iter()
+            .position(|validator| validator.address == address)?;
+
+        self.vote(index)
+    }
+
+    /// Iterate over the votes in the set, ordered by validator index
+    pub fn votes(&self) -> impl Iterator<Item = &Vote> {
+        self.votes.iter().filter_map(Option::as_ref)
+    }
+
+    /// Total voting power of the votes in the set
+    pub fn sum(&self) -> u64 {
+        self.sum
+    }
+
+    /// Voting power of the votes for the given block ID (`None` being nil)
+    pub fn power_for(&self, block_id: Option<&block::Id>) -> u64 {
+        self.power_by_block
+            .get(&block_id.cloned())
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Block ID which received a +2/3 majority of the voting power, if any.
+    ///
+    /// `Some(None)` means +2/3 voted for nil.
+    pub fn two_thirds_majority(&self) -> Option<Option<&block::Id>> {
+        self.maj23.as_ref().map(Option::as_ref)
+    }
+
+    /// Has a single block ID (or nil) received +2/3 of the voting power?
+    pub fn has_two_thirds_majority(&self) -> bool {
+        self.maj23.is_some()
+    }
+
+    /// Have the votes in the set, for any block ID, reached +2/3 of the
+    /// voting power?
+    pub fn has_two_thirds_any(&self) -> bool {
+        TrustThresholdFraction::TWO_THIRDS.is_enough_power(self.sum, self.validators.total_power())
+    }
+
+    /// Has every validator voted?
+    pub fn has_all(&self) -> bool {
+        self.votes.iter().all(Option::is_some)
+    }
+
+    /// Evidence of the conflicting votes which were rejected by this set
+    pub fn evidence(&self) -> &[DuplicateVoteEvidence] {
+        &self.evidence
+    }
+
+    /// Build the commit for the block which received +2/3 of the precommits
+    /// (like Tendermint's `VoteSet.MakeCommit`).
+    ///
+    /// Precommits for nil are included as such, while precommits for other
+    /// blocks are left out (marked as absent).
+    pub fn make_commit(&self) -> Result<Commit, Error> {
+        if self.vote_type != Type::Precommit {
+            fail!(
+                Kind::InvalidCommit,
+                "cannot make a commit from {:?} votes",
+                self.vote_type
+            );
+        }
+
+        let block_id = match &self.maj23 {
+            Some(Some(block_id)) => block_id.clone(),
+            _ => fail!(Kind::InvalidCommit, "no +2/3 majority for a block"),
+        };
+
+        let signatures = self
+            .votes
+            .iter()
+            .map(|vote| match vote {
+                Some(vote) if vote.block_id.as_ref() == Some(&block_id) => {
+                    CommitSig::BlockIDFlagCommit {
+                        validator_address: vote.validator_address,
+                        timestamp: vote.timestamp,
+                        signature: vote.signature,
+                        #[cfg(feature = "vote-extensions")]
+                        extension: vote.extension.clone(),
+                        #[cfg(feature = "vote-extensions")]
+                        extension_signature: vote.extension_signature,
+                    }
+                }
+                Some(vote) if vote.block_id.is_none() => CommitSig::BlockIDFlagNil {
+                    validator_address: vote.validator_address,
+                    timestamp: vote.timestamp,
+                    signature: vote.signature,
+                },
+                _ => CommitSig::BlockIDFlagAbsent,
+            })
+            .collect::<Vec<_>>();
+
+        Ok(Commit {
+            height: self.height,
+            round: self.round,
+            block_id,
+            signatures: CommitSigs::new(signatures),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{hash::Hash, vote::Power, Signature, Time};
+    use ed25519_dalek::{Keypair, SecretKey, Signer};
+    use std::str::FromStr;
+
+    const CHAIN_ID: &str = "test-chain";
+
+    fn keypair(seed: u8) -> Keypair {
+        let secret = SecretKey::from_bytes(&[seed; 32]).unwrap();
+        let public = (&secret).into();
+        Keypair { secret, public }
+    }
+
+    fn block_id(byte: u8) -> block::Id {
+        block::Id::new(Hash::Sha256([byte; 32]), None)
+    }
+
+    // validators with equal power, ordered as in the validator set
+    fn validators(count: u8) -> (validator::Set, Vec<Keypair>) {
+        let mut keypairs = (1..=count).map(keypair).collect::<Vec<_>>();
+        keypairs.sort_by_key(|keypair| account::Id::from(keypair.public));
+
+        let set = validator::Set::new(
+            keypairs
+                .iter()
+                .map(|keypair| validator::Info::new(keypair.public.into(), Power::new(10)))
+                .collect(),
+        );
+
+        (set, keypairs)
+    }
+
+    fn vote_set(validators: validator::Set) -> VoteSet {
+        VoteSet::new(
+            chain::Id::from(CHAIN_ID),
+            block::Height::from(7u64),
+            1,
+            Type::Precommit,
+            validators,
+        )
+    }
+
+    fn vote(keypairs: &[Keypair], index: usize, block_id: Option<block::Id>) -> Vote {
+        let mut vote = Vote {
+            vote_type: Type::Precommit,
+            height: block::Height::from(7u64),
+            round: 1,
+            block_id,
+            timestamp: Time::from_str("2020-09-01T00:00:00Z").unwrap(),
+            validator_address: account::Id::from(keypairs[index].public),
+            validator_index: index as u64,
+            signature: Signature::Ed25519(ed25519::Signature::new([0; 64])),
+            #[cfg(feature = "vote-extensions")]
+            extension: vec![],
+            #[cfg(feature = "vote-extensions")]
+            extension_signature: None,
+        };
+
+        let sign_bytes = vote.to_signable_bytes(chain::Id::from(CHAIN_ID));
+        vote.signature = Signature::Ed25519(keypairs[index].sign(&sign_bytes));
+        vote
+    }
+
+    #[test]
+    fn detects_two_thirds_majority() {
+        let (validators, keypairs) = validators(4);
+        let mut votes = vote_set(validators);
+
+        assert!(votes
+            .add_vote(vote(&keypairs, 0, Some(block_id(1))))
+            .unwrap());
+        assert!(votes.add_vote(vote(&keypairs, 1, None)).unwrap());
+        assert!(votes
+            .add_vote(vote(&keypairs, 2, Some(block_id(1))))
+            .unwrap());
+        assert!(!votes.has_two_thirds_majority());
+        assert!(votes.has_two_thirds_any());
+        assert!(votes.make_commit().is_err());
+
+        assert!(votes
+            .add_vote(vote(&keypairs, 3, Some(block_id(1))))
+            .unwrap());
+        assert_eq!(votes.two_thirds_majority(), Some(Some(&block_id(1))));
+        assert_eq!(votes.power_for(Some(&block_id(1))), 30);
+        assert_eq!(votes.power_for(None), 10);
+        assert_eq!(votes.sum(), 40);
+        assert!(votes.has_all());
+
+        // adding the same vote again is a no-op
+        assert!(!votes
+            .add_vote(vote(&keypairs, 0, Some(block_id(1))))
+            .unwrap());
+        assert_eq!(votes.sum(), 40);
+    }
+
+    #[test]
+    fn detects_nil_majority() {
+        let (validators, keypairs) = validators(3);
+        let mut votes = vote_set(validators);
+
+        for index in 0..3 {
+            votes.add_vote(vote(&keypairs, index, None)).unwrap();
+        }
+
+        assert_eq!(votes.two_thirds_majority(), Some(None));
+        assert!(votes.make_commit().is_err());
+    }
+
+    #[test]
+    fn makes_commit() {
+        let (validators, keypairs) = validators(4);
+        let mut votes = vote_set(validators.clone());
+
+        votes
+            .add_vote(vote(&keypairs, 0, Some(block_id(1))))
+            .unwrap();
+        votes.add_vote(vote(&keypairs, 1, None)).unwrap();
+        votes
+            .add_vote(vote(&keypairs, 2, Some(block_id(1))))
+            .unwrap();
+        votes
+            .add_vote(vote(&keypairs, 3, Some(block_id(1))))
+            .unwrap();
+
+        let commit = votes.make_commit().unwrap();
+        assert_eq!(commit.block_id, block_id(1));
+        assert_eq!(commit.signatures.len(), 4);
+        assert!(commit.signatures[0].is_commit());
+        assert!(commit.signatures[1].is_nil());
+        assert_eq!(commit.vote(2).as_ref(), votes.vote(2));
+
+        let mut rebuilt = vote_set(validators);
+        for index in 0..4 {
+            rebuilt.add_vote(commit.vote(index).unwrap()).unwrap();
+        }
+        assert_eq!(rebuilt.two_thirds_majority(), Some(Some(&block_id(1))));
+    }
+
+    #[test]
+    fn rejects_conflicting_votes() {
+        let (validators, keypairs) = validators(4);
+        let mut votes = vote_set(validators);
+
+        votes
+            .add_vote(vote(&keypairs, 0, Some(block_id(1))))
+            .unwrap();
+        assert!(votes
+            .add_vote(vote(&keypairs, 0, Some(block_id(2))))
+            .is_err());
+
+        assert_eq!(votes.sum(), 10);
+        assert_eq!(votes.power_for(Some(&block_id(2))), 0);
+        assert_eq!(votes.evidence().len(), 1);
+        assert!(votes.evidence()[0]
+            .verify(chain::Id::from(CHAIN_ID))
+            .is_ok());
+    }
+
+    #[test]
+    fn rejects_invalid_votes() {
+        let (validators, keypairs) = validators(4);
+        let mut votes = vote_set(validators);
+
+        // wrong validator index
+        let mut wrong_index = vote(&keypairs, 0, Some(block_id(1)));
+        wrong_index.validator_index = 1;
+        assert!(votes.add_vote(wrong_index).is_err());
+
+        // unknown validator index
+        let mut out_of_range = vote(&keypairs, 0, Some(block_id(1)));
+        out_of_range.validator_index = 4;
+        assert!(votes.add_vote(out_of_range).is_err());
+
+        // wrong round
+        let mut wrong_round = vote(&keypairs, 0, Some(block_id(1)));
+        wrong_round.round = 2;
+        assert!(votes.add_vote(wrong_round).is_err());
+
+        // bad signature
+        let mut bad_signature = vote(&keypairs, 0, Some(block_id(1)));
+        bad_signature.block_id = Some(block_id(2));
+        assert!(votes.add_vote(bad_signature).is_err());
+
+        assert_eq!(votes.sum(), 0);
+        assert!(votes.evidence().is_empty());
+    }
+}
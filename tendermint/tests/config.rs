@@ -4,7 +4,7 @@
 
 mod files {
     use std::{fs, path::PathBuf, time::Duration};
-    use tendermint::{config::*, net, node};
+    use tendermint::{config::*, net, node, private_key::PrivateKey, public_key::Algorithm};
 
     /// Read a fixture file from the `support/config` directory
     fn read_fixture(name: &str) -> String {
@@ -90,26 +90,26 @@ mod files {
         assert_eq!(
             p2p.seeds[0],
             "tcp://c2e1bde78877975b31e6f06e77da200a38048e2b@seed-1.example.com:26656"
-                .parse::<net::Address>()
+                .parse::<node::Address>()
                 .unwrap()
         );
         assert_eq!(
             p2p.seeds[1],
             "tcp://0eafed3e9e76f626a299e1b8a79454fffe9ca83c@seed-2.example.com:26656"
-                .parse::<net::Address>()
+                .parse::<node::Address>()
                 .unwrap()
         );
         assert_eq!(p2p.persistent_peers.len(), 2);
         assert_eq!(
             p2p.persistent_peers[0],
             "tcp://70d834561f91613153e4a873f01a2cbbf1b9678d@1.2.3.4:26656"
-                .parse::<net::Address>()
+                .parse::<node::Address>()
                 .unwrap()
         );
         assert_eq!(
             p2p.persistent_peers[1],
             "tcp://f68ed33a0baa0c734a939a9e60659566adc725cd@peer-2.example.com:26656"
-                .parse::<net::Address>()
+                .parse::<node::Address>()
                 .unwrap()
         );
         assert!(!p2p.upnp);
@@ -212,8 +212,43 @@ mod files {
         );
     }
 
+    /// Round trip a node key through a file, keeping its node ID
+    #[test]
+    fn node_key_from_private_key() {
+        let parsed = NodeKey::parse_json(&read_fixture("node_key.json")).unwrap();
+        let node_key = NodeKey::new(
+            PrivateKey::from_base64(Algorithm::Ed25519, &parsed.priv_key.to_base64()).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(node_key.node_id(), parsed.node_id());
+        assert_eq!(
+            node_key.ed25519_keypair().public,
+            parsed.ed25519_public_key()
+        );
+        assert_eq!(
+            node_key.node_id(),
+            node::Id::from(node_key.ed25519_public_key())
+        );
+
+        let path =
+            std::env::temp_dir().join(format!("tendermint-node-key-{}.json", std::process::id()));
+        node_key.save_json_file(&path).unwrap();
+        let loaded = NodeKey::load_json_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.node_id(), parsed.node_id());
+    }
+
+    /// Node keys must be Ed25519 keys
+    #[test]
+    #[cfg(feature = "secp256k1")]
+    fn node_key_rejects_secp256k1_keys() {
+        let priv_key = PrivateKey::from_raw(Algorithm::Secp256k1, &[0x42; 32]).unwrap();
+        assert!(NodeKey::new(priv_key).is_err());
+    }
+
     /// Parse an example `priv_validator_key.json` to a `PrivValidatorKey` struct
     #[test]
+    #[cfg(feature = "amino")]
     fn priv_validator_json_parser() {
         let raw_priv_validator_key = read_fixture("priv_validator_key.json");
         let priv_validator_key = PrivValidatorKey::parse_json(&raw_priv_validator_key).unwrap();
@@ -222,4 +257,30 @@ mod files {
             "1624DE6420F26BF4B2A2E84CEB7A53C3F1AE77408779B20064782FBADBDF0E365959EE4534"
         );
     }
+
+    /// Derive a `PrivValidatorKey` from its private key and round trip it
+    /// through a file
+    #[test]
+    fn priv_validator_key_from_private_key() {
+        let raw_priv_validator_key = read_fixture("priv_validator_key.json");
+        let parsed = PrivValidatorKey::parse_json(&raw_priv_validator_key).unwrap();
+
+        let priv_key = PrivateKey::from_base64(
+            Algorithm::Ed25519,
+            "skHDGUYe2pOhwfSrXZQ6KeKnmKgTOn+f++Vmj4OOqIHya/SyouhM63pTw/Gud0CHebIAZHgvutvfDjZZWe5FNA==",
+        )
+        .unwrap();
+        let derived = PrivValidatorKey::new(priv_key).unwrap();
+        assert_eq!(derived.address, parsed.address);
+        assert_eq!(derived.pub_key, parsed.pub_key);
+
+        let path = std::env::temp_dir().join(format!(
+            "tendermint-priv-validator-key-{}.json",
+            std::process::id()
+        ));
+        derived.save_json_file(&path).unwrap();
+        let loaded = PrivValidatorKey::load_json_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.address, parsed.address);
+    }
 }
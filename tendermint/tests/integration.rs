@@ -118,7 +118,10 @@ mod rpc {
     #[tokio::test]
     #[ignore]
     async fn genesis() {
-        let genesis = localhost_rpc_client().genesis().await.unwrap();
+        let genesis = localhost_rpc_client()
+            .genesis::<serde_json::Value>()
+            .await
+            .unwrap();
 
         assert_eq!(
             genesis.consensus_params.validator.pub_key_types[0].to_string(),
@@ -117,6 +117,10 @@ impl Generator<block::Commit> for Commit {
                 validator_address: vote.validator_address,
                 timestamp: vote.timestamp,
                 signature: vote.signature,
+                #[cfg(feature = "vote-extensions")]
+                extension: vote.extension,
+                #[cfg(feature = "vote-extensions")]
+                extension_signature: vote.extension_signature,
             })
         };
         let sigs = votes
@@ -178,6 +182,7 @@ mod tests {
                     validator_address: _,
                     timestamp: _,
                     signature,
+                    ..
                 } => {
                     let block_vote = votes[i].generate().unwrap();
                     let sign_bytes =
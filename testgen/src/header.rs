@@ -3,7 +3,7 @@ use gumdrop::Options;
 use serde::Deserialize;
 use simple_error::*;
 use std::str::FromStr;
-use tendermint::{block, chain, validator, Time};
+use tendermint::{app, block, chain, validator, Time};
 
 #[derive(Debug, Options, Deserialize, Clone)]
 pub struct Header {
@@ -94,7 +94,10 @@ impl Generator<block::Header> for Header {
             Err(_) => bail!("failed to construct header's chain_id"),
         };
         let header = block::Header {
-            version: block::header::Version { block: 0, app: 0 },
+            version: block::header::Version {
+                block: block::Version(0),
+                app: app::Version(0),
+            },
             chain_id,
             height: block::Height(self.height.unwrap_or(1)),
             time: self.time.unwrap_or_else(Time::now),
@@ -104,7 +107,7 @@ impl Generator<block::Header> for Header {
             validators_hash: valset.hash(),
             next_validators_hash: next_valset.hash(),
             consensus_hash: valset.hash(), // TODO: currently not clear how to produce a valid hash
-            app_hash: vec![],
+            app_hash: Default::default(),
             last_results_hash: None,
             evidence_hash: None,
             proposer_address: vals[self.proposer.unwrap_or(0)].address,
@@ -1,13 +1,10 @@
 //! Helper functions
 
+use ed25519_dalek::Verifier;
 use serde::de::DeserializeOwned;
 use simple_error::*;
 use std::io::{self, Read};
-use tendermint::{
-    amino_types, public_key,
-    signature::{Signature, Verifier},
-    vote,
-};
+use tendermint::{amino_types, public_key, signature::Signature, vote};
 
 /// A macro that generates a complete setter method from a one-liner with necessary information
 #[macro_export]
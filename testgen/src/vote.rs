@@ -107,6 +107,10 @@ impl Generator<vote::Vote> for Vote {
                 signature::Ed25519::try_from(&[0_u8; ED25519_SIGNATURE_SIZE][..]),
                 "failed to construct empty ed25519 signature"
             )),
+            #[cfg(feature = "vote-extensions")]
+            extension: vec![],
+            #[cfg(feature = "vote-extensions")]
+            extension_signature: None,
         };
         let sign_bytes = get_vote_sign_bytes(block_header.chain_id.as_str(), &vote);
         vote.signature = signer.sign(sign_bytes.as_slice()).into();